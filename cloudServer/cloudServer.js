@@ -1,6 +1,8 @@
 // cloudServer.js
 import express from "express";
 import Database from "better-sqlite3";
+import crypto from "crypto";
+import fs from "fs";
 
 const app = express();
 app.use(express.json());
@@ -25,52 +27,306 @@ db.prepare(`
   )
 `).run();
 
+// Columns added after the first release (older licenses.db files don't have them)
+const licenseColumns = db.prepare("PRAGMA table_info(licenses)").all().map((c) => c.name);
+if (!licenseColumns.includes("tier")) db.prepare("ALTER TABLE licenses ADD COLUMN tier TEXT DEFAULT 'basic'").run();
+if (!licenseColumns.includes("seats")) db.prepare("ALTER TABLE licenses ADD COLUMN seats INTEGER DEFAULT 1").run();
+
+// One row per machine registered on a license (seat usage)
+db.prepare(`
+  CREATE TABLE IF NOT EXISTS activations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    license_key TEXT NOT NULL,
+    machine_id TEXT NOT NULL,
+    hostname TEXT,
+    activated_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (license_key, machine_id)
+  )
+`).run();
+
+// Projects / clips / markers shared by every machine of a license (the app's sync.rs).
+// revision is one counter per license: a pull asks for everything after the last revision it saw.
+db.prepare(`
+  CREATE TABLE IF NOT EXISTS sync_records (
+    license_key TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    uuid TEXT NOT NULL,
+    parent TEXT,
+    revision INTEGER NOT NULL,
+    deleted INTEGER NOT NULL DEFAULT 0,
+    data TEXT,
+    updated_at TEXT,
+    machine_id TEXT,
+    PRIMARY KEY (license_key, kind, uuid)
+  )
+`).run();
+db.prepare("CREATE INDEX IF NOT EXISTS idx_sync_revision ON sync_records (license_key, revision)").run();
+
+
+//*____________ Response signing (Ed25519) ____________
+// The app only trusts license claims signed with this key, so a fake local server can't unlock it.
+// dev_signing_key.pem is a local development key made by `node genSigningKey.js` (gitignored, never committed):
+// debug builds of the app pick up its public half. Production sets LICENSE_SIGNING_KEY_FILE and builds the app
+// with the matching LICENSE_PUBLIC_KEY.
+const SIGNING_KEY_FILE = process.env.LICENSE_SIGNING_KEY_FILE || new URL("./dev_signing_key.pem", import.meta.url);
+if (!fs.existsSync(SIGNING_KEY_FILE)) {
+  console.error(`❌ No signing key at ${SIGNING_KEY_FILE}: run \`node genSigningKey.js\` or set LICENSE_SIGNING_KEY_FILE`);
+  process.exit(1);
+}
+const signingKey = crypto.createPrivateKey(fs.readFileSync(SIGNING_KEY_FILE));
+
+// Public keys of leaked pairs (same list as the app's build.rs): refuse to sign with them
+const REVOKED_PUBLIC_KEYS = ["0a17243521c55aef2be9a60740c645cb6cafaa1adecf190830ffd7f6675a8fb3"];
+const signingPublicKey = crypto.createPublicKey(signingKey).export({ format: "der", type: "spki" }).subarray(-32).toString("hex");
+if (REVOKED_PUBLIC_KEYS.includes(signingPublicKey)) {
+  console.error(`❌ ${SIGNING_KEY_FILE} is a revoked key: delete it and run \`node genSigningKey.js\``);
+  process.exit(1);
+}
+
+// Returns { payload, signature }: payload is the exact JSON string that was signed
+function signLicense(claims) {
+  const payload = JSON.stringify(claims);
+  const signature = crypto.sign(null, Buffer.from(payload), signingKey).toString("base64");
+  return { payload, signature };
+}
+
+// Claims bound to this key + machine, short-lived so old responses can't be replayed
+function licenseToken(details, key, machineId) {
+  return signLicense({
+    keyHash: crypto.createHash("sha256").update(key).digest("hex"),
+    machineId,
+    issuedAt: Math.floor(Date.now() / 1000),
+    ...details,
+  });
+}
+
+
+//*____________ Helpers ____________
+// Features unlocked by each tier (names must match the app's features.rs)
+const TIER_FEATURES = {
+  basic: [],
+  pro: ["batch_analyze", "face_search", "live_camera", "cloud_sync", "ws_priority"],
+};
+
+const countSeats = db.prepare("SELECT COUNT(*) AS n FROM activations WHERE license_key = ?");
+const findActivation = db.prepare("SELECT id FROM activations WHERE license_key = ? AND machine_id = ?");
+
+// Details the app shows next to the license state
+function licenseDetails(row, key) {
+  return {
+    tier: row.tier || "basic",
+    expiry: row.expiry || null,
+    seatsUsed: countSeats.get(key).n,
+    seatsTotal: row.seats || 1,
+    features: TIER_FEATURES[row.tier || "basic"] || [],
+  };
+}
+
+// Returns an error response body if the license row can't be used, null otherwise
+function checkLicenseRow(row) {
+  if (!row || row.valid !== 1) {
+    return { success: false, code: "invalid_key", message: "❌ License invalid or not found" };
+  }
+  const currentDate = new Date().toISOString().split('T')[0];
+  if (row.expiry && row.expiry < currentDate) {
+    return { success: false, code: "expired", message: "❌ License has expired" };
+  }
+  return null;
+}
+
 
 // Endpoint: validate license
 app.post("/validate", (req, res) => {
-  const { key } = req.body;
+  const { key, machineId } = req.body;
   
   // Input validation
   if (!key) {
     return res.status(200).json({ 
       success: false, 
+      code: "invalid_key",
       message: "❌ License key is required" 
     });
   }
 
   try {
-    const stmt = db.prepare("SELECT valid, expiry FROM licenses WHERE key = ?");
-    const row = stmt.get(key);
-
-    if (row && row.valid === 1) {
-      // Check if license has expired
-      const currentDate = new Date().toISOString().split('T')[0];
-      if (row.expiry && row.expiry < currentDate) {
-        return res.status(200).json({ 
-          success: false, 
-          message: "❌ License has expired" 
-        });
-      }
-      
-      res.status(200).json({ 
-        success: true, 
-        message: "✅ License valid" 
-      });
-    } else {
-      res.status(200).json({ 
+    const row = db.prepare("SELECT valid, expiry, tier, seats FROM licenses WHERE key = ?").get(key);
+
+    const error = checkLicenseRow(row);
+    if (error) return res.status(200).json(error);
+
+    // Clients that send a machine id must have activated it first
+    if (machineId && !findActivation.get(key, machineId)) {
+      return res.status(200).json({ 
         success: false, 
-        message: "❌ License invalid or not found" 
+        code: "not_activated",
+        message: "❌ This machine is not activated for this license" 
       });
     }
+
+    const details = licenseDetails(row, key);
+    res.status(200).json({ 
+      success: true, 
+      message: "✅ License valid",
+      ...details,
+      token: licenseToken(details, key, machineId || null)
+    });
   } catch (error) {
     console.error("Database error:", error);
     res.status(200).json({ 
       success: false, 
+      code: "server_error",
       message: "❌ Server error during validation" 
     });
   }
 });
 
+// Endpoint: activate a machine on a license (takes a seat)
+app.post("/activate", (req, res) => {
+  const { key, machineId, hostname } = req.body;
+
+  if (!key || !machineId) {
+    return res.status(200).json({ success: false, code: "invalid_key", message: "❌ License key and machine id are required" });
+  }
+
+  try {
+    const row = db.prepare("SELECT valid, expiry, tier, seats FROM licenses WHERE key = ?").get(key);
+
+    const error = checkLicenseRow(row);
+    if (error) return res.status(200).json(error);
+
+    // Re-activating an already registered machine is a no-op
+    if (!findActivation.get(key, machineId)) {
+      if (countSeats.get(key).n >= (row.seats || 1)) {
+        return res.status(200).json({ success: false, code: "seat_limit", message: "❌ All seats for this license are in use" });
+      }
+      db.prepare("INSERT INTO activations (license_key, machine_id, hostname) VALUES (?, ?, ?)")
+        .run(key, machineId, hostname || null);
+    }
+
+    const details = licenseDetails(row, key);
+    res.status(200).json({ success: true, message: "✅ Machine activated", ...details, token: licenseToken(details, key, machineId) });
+  } catch (error) {
+    console.error("Database error:", error);
+    res.status(200).json({ success: false, code: "server_error", message: "❌ Server error during activation" });
+  }
+});
+
+// Endpoint: release a machine's seat
+app.post("/deactivate", (req, res) => {
+  const { key, machineId } = req.body;
+
+  if (!key || !machineId) {
+    return res.status(200).json({ success: false, code: "invalid_key", message: "❌ License key and machine id are required" });
+  }
+
+  try {
+    db.prepare("DELETE FROM activations WHERE license_key = ? AND machine_id = ?").run(key, machineId);
+    res.status(200).json({ success: true, message: "✅ Machine deactivated" });
+  } catch (error) {
+    console.error("Database error:", error);
+    res.status(200).json({ success: false, code: "server_error", message: "❌ Server error during deactivation" });
+  }
+});
+
+
+//*____________ Cloud sync ____________
+const SYNC_KINDS = ["project", "clip", "marker"];
+const MAX_SYNC_PAGE = 500;
+
+const findSyncRecord = db.prepare("SELECT * FROM sync_records WHERE license_key = ? AND kind = ? AND uuid = ?");
+const nextRevision = db.prepare("SELECT COALESCE(MAX(revision), 0) + 1 AS n FROM sync_records WHERE license_key = ?");
+const saveSyncRecord = db.prepare(`
+  INSERT INTO sync_records (license_key, kind, uuid, parent, revision, deleted, data, updated_at, machine_id)
+  VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+  ON CONFLICT (license_key, kind, uuid) DO UPDATE SET parent = excluded.parent, revision = excluded.revision,
+    deleted = excluded.deleted, data = excluded.data, updated_at = excluded.updated_at, machine_id = excluded.machine_id
+`);
+
+// Row -> the record format of the app
+function syncRecord(row) {
+  return {
+    kind: row.kind,
+    uuid: row.uuid,
+    parent: row.parent,
+    revision: row.revision,
+    deleted: row.deleted === 1,
+    data: row.data ? JSON.parse(row.data) : null,
+    updatedAt: row.updated_at,
+  };
+}
+
+// Error response body if this machine can't sync, null otherwise
+function checkSyncAccess(key, machineId) {
+  if (!key || !machineId) {
+    return { success: false, code: "invalid_key", message: "❌ License key and machine id are required" };
+  }
+  const row = db.prepare("SELECT valid, expiry, tier FROM licenses WHERE key = ?").get(key);
+  const error = checkLicenseRow(row);
+  if (error) return error;
+  if (!findActivation.get(key, machineId)) {
+    return { success: false, code: "not_activated", message: "❌ This machine is not activated for this license" };
+  }
+  if (!(TIER_FEATURES[row.tier || "basic"] || []).includes("cloud_sync")) {
+    return { success: false, code: "feature_not_licensed", message: "❌ Cloud sync is not included in this license" };
+  }
+  return null;
+}
+
+// A record is stored only if it is based on the current revision; otherwise the current one is sent back
+const pushRecords = db.transaction((key, machineId, records) =>
+  records.map((record) => {
+    const current = findSyncRecord.get(key, record.kind, record.uuid);
+    if (current && current.revision !== record.revision) {
+      return { uuid: record.uuid, status: "conflict", current: syncRecord(current) };
+    }
+    const revision = nextRevision.get(key).n;
+    saveSyncRecord.run(
+      key, record.kind, record.uuid, record.parent || null, revision, record.deleted ? 1 : 0,
+      record.deleted ? null : JSON.stringify(record.data ?? null), record.updatedAt || null, machineId
+    );
+    return { uuid: record.uuid, status: "ok", revision };
+  })
+);
+
+// Endpoint: records changed after revision `since`, oldest first
+app.post("/sync/pull", (req, res) => {
+  const { key, machineId, since = 0, limit = MAX_SYNC_PAGE } = req.body;
+
+  const error = checkSyncAccess(key, machineId);
+  if (error) return res.status(200).json(error);
+
+  try {
+    const pageSize = Math.min(Math.max(Number(limit) || MAX_SYNC_PAGE, 1), MAX_SYNC_PAGE);
+    const rows = db
+      .prepare("SELECT * FROM sync_records WHERE license_key = ? AND revision > ? ORDER BY revision LIMIT ?")
+      .all(key, Number(since) || 0, pageSize + 1);
+    const records = rows.slice(0, pageSize).map(syncRecord);
+    const cursor = records.length ? records[records.length - 1].revision : Number(since) || 0;
+    res.status(200).json({ success: true, records, cursor, more: rows.length > pageSize });
+  } catch (error) {
+    console.error("Database error:", error);
+    res.status(200).json({ success: false, code: "server_error", message: "❌ Server error during sync" });
+  }
+});
+
+// Endpoint: store local changes; each one is answered "ok" (new revision) or "conflict" (current record)
+app.post("/sync/push", (req, res) => {
+  const { key, machineId, records } = req.body;
+
+  const error = checkSyncAccess(key, machineId);
+  if (error) return res.status(200).json(error);
+  if (!Array.isArray(records) || records.some((r) => !SYNC_KINDS.includes(r.kind) || !r.uuid)) {
+    return res.status(200).json({ success: false, code: "invalid_payload", message: "❌ records must be { kind, uuid, revision, ... }" });
+  }
+
+  try {
+    res.status(200).json({ success: true, results: pushRecords(key, machineId, records) });
+  } catch (error) {
+    console.error("Database error:", error);
+    res.status(200).json({ success: false, code: "server_error", message: "❌ Server error during sync" });
+  }
+});
+
 // Endpoint: ping (for health checks)
 app.get("/ping", (req, res) => {
   res.json({ message: "Server alive ✅" });
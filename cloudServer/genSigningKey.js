@@ -0,0 +1,30 @@
+// genSigningKey.js
+// Generate an Ed25519 key pair used to sign /validate and /activate responses.
+//
+// Usage: node genSigningKey.js [out.pem]
+//   - writes the PRIVATE key (PKCS#8 PEM) to out.pem (default: dev_signing_key.pem, the key cloudServer.js
+//     uses without LICENSE_SIGNING_KEY_FILE) — keep it local / on the server only, *.pem is gitignored
+//   - writes the raw PUBLIC key (hex) next to it as out.pub: debug builds of the app read dev_signing_key.pub
+//   - prints the public key — build a release with LICENSE_PUBLIC_KEY=<hex> to trust it
+//   - never overwrites an existing key: licenses signed with it would stop validating
+
+import crypto from "crypto";
+import fs from "fs";
+
+const out = process.argv[2] || new URL("./dev_signing_key.pem", import.meta.url).pathname;
+const pub = out.replace(/\.pem$/, "") + ".pub";
+
+if (fs.existsSync(out)) {
+  console.error(`❌ ${out} already exists: delete it first to generate a new key`);
+  process.exit(1);
+}
+
+const { privateKey, publicKey } = crypto.generateKeyPairSync("ed25519");
+fs.writeFileSync(out, privateKey.export({ format: "pem", type: "pkcs8" }), { mode: 0o600 });
+
+// SPKI DER for Ed25519 = 12 byte header + 32 byte raw key
+const rawPublic = publicKey.export({ format: "der", type: "spki" }).subarray(-32).toString("hex");
+fs.writeFileSync(pub, rawPublic + "\n");
+
+console.log(`🔑 Private key written to ${out}, public key to ${pub}`);
+console.log(`📋 LICENSE_PUBLIC_KEY=${rawPublic}`);
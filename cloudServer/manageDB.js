@@ -15,14 +15,19 @@ db.prepare(`
     )
 `).run();
 
+// Columns added after the first release
+const licenseColumns = db.prepare("PRAGMA table_info(licenses)").all().map((c) => c.name);
+if (!licenseColumns.includes("tier")) db.prepare("ALTER TABLE licenses ADD COLUMN tier TEXT DEFAULT 'basic'").run();
+if (!licenseColumns.includes("seats")) db.prepare("ALTER TABLE licenses ADD COLUMN seats INTEGER DEFAULT 1").run();
+
 // Add a license key
-function addLicense(key, email, firstName, lastName, expiry = null) {
+function addLicense(key, email, firstName, lastName, expiry = null, tier = "basic", seats = 1) {
     db.prepare(`
-        INSERT OR REPLACE INTO licenses (key, email, first_name, last_name, valid, expiry) 
-        VALUES (?, ?, ?, ?, ?, ?)
-    `).run(key, email, firstName, lastName, 1, expiry);
+        INSERT OR REPLACE INTO licenses (key, email, first_name, last_name, valid, expiry, tier, seats) 
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+    `).run(key, email, firstName, lastName, 1, expiry, tier, seats);
 
-    console.log(`✅ License ${key} added for ${firstName} ${lastName} (${email})`);
+    console.log(`✅ License ${key} (${tier}, ${seats} seats) added for ${firstName} ${lastName} (${email})`);
 }
 
 
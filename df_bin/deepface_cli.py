@@ -7,7 +7,7 @@ Refactored DeepFace CLI + WebSocket server for use as a child process
 WebSocket worker.
 
 Modes:
-    - CLI subcommands (default): analyze, verify, detect, find.
+    - CLI subcommands (default): analyze, verify, detect, find, represent, options.
     - Serve mode:
       * `serve` command starts a WebSocket server (default host=127.0.0.1)
       * Use `--port` to choose the port
@@ -16,9 +16,16 @@ Protocol (WebSocket):
     * Each message must be a JSON object. Example:
         {"requestId":1, "cmd":"analyze", "frame":"path1", "actions":"emotion", "detector":"opencv"}
 
+    * Binary messages carry an in-memory image instead of a "frame" path:
+        [u32 big-endian N][N bytes UTF-8 JSON header, same fields as above][encoded image bytes]
+      The image is decoded with OpenCV and passed to DeepFace as an array.
+
     * Responses are JSON messages with structure:
         { "requestId": <id>, "status": "ok|error", "command": "<cmd>", "data": <payload> }
 
+    * A request repeating a recently answered requestId (a retry after a connection problem) gets the
+      same successful answer again without being processed twice.
+
 Design:
     - Always processes one frame per request (no bulk).
     - stdout is NOT used by WebSocket mode. For CLI, stdout contains the final JSON.
@@ -33,6 +40,7 @@ import json
 import argparse
 import asyncio
 import traceback
+from collections import OrderedDict
 from typing import Any, Dict
 
 # third-party
@@ -49,6 +57,9 @@ except Exception:
     raise
 
 import numpy as np
+import cv2
+import struct
+import importlib.util
 
 # ----------------------------
 # PyInstaller support
@@ -107,6 +118,16 @@ def safe_call(func, kwargs: Dict[str, Any]):
 # ----------------------------
 # Command handlers (single-frame only)
 # ----------------------------
+def _frame_of(req: Dict[str, Any]):
+    """`frame` can be a path or a decoded image (binary WS message); `or` doesn't work on arrays."""
+    frame = req.get("frame")
+    if frame is None:
+        frame = (req.get("frames") or [None])[0]
+    return frame
+
+def _frame_label(frame) -> str:
+    return frame if isinstance(frame, str) else "<binary>"
+
 def cmd_analyze(args_or_req) -> Any:
     """
     Analyze a single frame.
@@ -127,7 +148,7 @@ def cmd_analyze(args_or_req) -> Any:
         enforce_detection = getattr(args_or_req, "enforce_detection", False)
         model = getattr(args_or_req, "model", None)
     elif isinstance(args_or_req, dict):
-        frame = args_or_req.get("frame") or (args_or_req.get("frames") or [None])[0]
+        frame = _frame_of(args_or_req)
         actions = args_or_req.get("actions")
         detector = args_or_req.get("detector")
         enforce_detection = args_or_req.get("enforce_detection", False)
@@ -135,7 +156,7 @@ def cmd_analyze(args_or_req) -> Any:
     else:
         raise ValueError("Unsupported input type for cmd_analyze")
 
-    if not frame:
+    if frame is None or (isinstance(frame, str) and not frame):
         raise ValueError("No frame provided")
 
     kwargs = {"img_path": frame, "enforce_detection": enforce_detection}
@@ -147,7 +168,7 @@ def cmd_analyze(args_or_req) -> Any:
         kwargs["model_name"] = model
         kwargs["model"] = model
 
-    return {"frame": frame, "result": safe_call(DeepFace.analyze, kwargs)}
+    return {"frame": _frame_label(frame), "result": safe_call(DeepFace.analyze, kwargs)}
 
 def cmd_verify(args_or_req) -> Any:
     """Verify: compare two images."""
@@ -185,16 +206,43 @@ def cmd_detect(args_or_req) -> Any:
         detector = getattr(args_or_req, "detector", None)
         enforce_detection = getattr(args_or_req, "enforce_detection", False)
     elif isinstance(args_or_req, dict):
-        frame = args_or_req.get("frame") or (args_or_req.get("frames") or [None])[0]
+        frame = _frame_of(args_or_req)
         detector = args_or_req.get("detector")
         enforce_detection = args_or_req.get("enforce_detection", False)
     else:
         raise ValueError("Unsupported input type for cmd_detect")
 
-    if not frame:
+    if frame is None or (isinstance(frame, str) and not frame):
+        raise ValueError("No frame provided")
+
+    return {"frame": _frame_label(frame), "faces": safe_call(DeepFace.extract_faces, {"img_path": frame, "detector_backend": detector, "enforce_detection": enforce_detection})}
+
+def cmd_represent(args_or_req) -> Any:
+    """Represent: one embedding per face found in a single frame."""
+    if isinstance(args_or_req, argparse.Namespace):
+        frame = getattr(args_or_req, "frame", None)
+        detector = getattr(args_or_req, "detector", None)
+        enforce_detection = getattr(args_or_req, "enforce_detection", False)
+        model = getattr(args_or_req, "model", None)
+    elif isinstance(args_or_req, dict):
+        frame = _frame_of(args_or_req)
+        detector = args_or_req.get("detector")
+        enforce_detection = args_or_req.get("enforce_detection", False)
+        model = args_or_req.get("model")
+    else:
+        raise ValueError("Unsupported input type for cmd_represent")
+
+    if frame is None or (isinstance(frame, str) and not frame):
         raise ValueError("No frame provided")
 
-    return {"frame": frame, "faces": safe_call(DeepFace.extract_faces, {"img_path": frame, "detector_backend": detector, "enforce_detection": enforce_detection})}
+    kwargs = {"img_path": frame, "enforce_detection": enforce_detection}
+    if detector:
+        kwargs["detector_backend"] = detector
+    if model:
+        kwargs["model_name"] = model
+
+    # [{ "embedding": [...], "facial_area": {x, y, w, h}, "face_confidence": f }, ...]
+    return {"frame": _frame_label(frame), "faces": safe_call(DeepFace.represent, kwargs)}
 
 def cmd_find(args_or_req) -> Any:
     """Find: search a database for similar faces from a single frame."""
@@ -229,13 +277,47 @@ def cmd_test(_args=None) -> Any:
     """Simple health-check command for the server; returns 'ok'."""
     return "ok"
 
+# name -> python module it needs besides deepface itself (None = always available)
+DETECTORS = {
+    "opencv": None, "ssd": None, "yunet": None, "centerface": None, "skip": None,
+    "retinaface": "retinaface", "mtcnn": "mtcnn", "fastmtcnn": "facenet_pytorch",
+    "dlib": "dlib", "mediapipe": "mediapipe", "yolov8": "ultralytics",
+}
+MODELS = {
+    "VGG-Face": None, "Facenet": None, "Facenet512": None, "OpenFace": None, "DeepFace": None,
+    "DeepID": None, "ArcFace": None, "SFace": None, "GhostFaceNet": None, "Dlib": "dlib",
+}
+
+def _available(names: Dict[str, Any]) -> list:
+    return [name for name, module in names.items() if module is None or importlib.util.find_spec(module) is not None]
+
+def cmd_options(_args=None) -> Any:
+    """Detector backends and recognition models this build can run (optional packages installed)."""
+    return {
+        "version": getattr(DeepFace, "__version__", None),
+        "detectors": _available(DETECTORS),
+        "models": _available(MODELS),
+    }
+
 # ----------------------------
 # WebSocket server
 # ----------------------------
+# requestId -> sent JSON of the last successful answers, for retried requests
+RECENT_ANSWERS: "OrderedDict[Any, str]" = OrderedDict()
+RECENT_ANSWERS_MAX = 256
+
 async def process_and_respond(ws, req: Dict[str, Any]):
     request_id = req.get("requestId")
     cmd      = req.get("cmd")
 
+    if request_id is not None and request_id in RECENT_ANSWERS:
+        eprint(f"[INFO] requestId={request_id} already answered, sending the answer again")
+        try:
+            await ws.send(RECENT_ANSWERS[request_id])
+        except Exception as send_err:
+            eprint(f"[WARN] failed to send response: {send_err}")
+        return
+
     try:
         # --- route command ---
         if cmd == "analyze":
@@ -246,8 +328,12 @@ async def process_and_respond(ws, req: Dict[str, Any]):
             res = cmd_detect(req)
         elif cmd == "find":
             res = cmd_find(req)
+        elif cmd == "represent":
+            res = cmd_represent(req)
         elif cmd == "test":
             res = cmd_test()
+        elif cmd == "options":
+            res = cmd_options()
         else:
             raise ValueError(f"Unsupported cmd '{cmd}'")
 
@@ -266,19 +352,50 @@ async def process_and_respond(ws, req: Dict[str, Any]):
                 "command": cmd,
                 "data": {"message": str(e), "traceback": tb}}
 
+    text = json.dumps(resp, ensure_ascii=False)
+    if request_id is not None and resp["status"] == "ok":
+        RECENT_ANSWERS[request_id] = text
+        while len(RECENT_ANSWERS) > RECENT_ANSWERS_MAX:
+            RECENT_ANSWERS.popitem(last=False)
+
     # always send something back
     try:
-        await ws.send(json.dumps(resp, ensure_ascii=False))
+        await ws.send(text)
     except Exception as send_err:
         # even the send might fail if client vanished – log and forget
         eprint(f"[WARN] failed to send response: {send_err}")
 
 
+def decode_binary_request(raw: bytes) -> Dict[str, Any]:
+    """[u32 header length][JSON header][image bytes] -> request dict with `frame` as a BGR array."""
+    if len(raw) < 4:
+        raise ValueError("shorter than 4 bytes")
+    (header_len,) = struct.unpack(">I", raw[:4])
+    if len(raw) < 4 + header_len:
+        raise ValueError("header length past the end")
+    try:
+        req = json.loads(raw[4:4 + header_len].decode("utf-8"))
+    except (UnicodeDecodeError, json.JSONDecodeError) as e:
+        raise ValueError(f"header is not JSON ({e})")
+    image = cv2.imdecode(np.frombuffer(raw[4 + header_len:], dtype=np.uint8), cv2.IMREAD_COLOR)
+    if image is None:
+        raise ValueError("image could not be decoded")
+    req["frame"] = image
+    return req
+
 async def ws_handler(websocket):          # <-- single param
     client = f"{websocket.remote_address[0]}:{websocket.remote_address[1]}"
     logging.info("[INFO] WS client connected: %s", client)
     try:
         async for raw in websocket:
+            if isinstance(raw, bytes):
+                try:
+                    req = decode_binary_request(raw)
+                except ValueError as e:
+                    await websocket.send(json.dumps({"status":"error","message":f"Invalid binary frame: {e}"}))
+                    continue
+                await process_and_respond(websocket, req)
+                continue
             try:
                 req = json.loads(raw)
             except json.JSONDecodeError as e:
@@ -336,10 +453,22 @@ def build_parser():
     f.add_argument("--model")
     f.set_defaults(func=cmd_find)
 
+    # REPRESENT
+    r = sub.add_parser("represent", help="Face embeddings of one frame")
+    r.add_argument("--frame", required=True)
+    r.add_argument("--detector")
+    r.add_argument("--enforce-detection", dest="enforce_detection", action="store_true")
+    r.add_argument("--model")
+    r.set_defaults(func=cmd_represent)
+
     # TEST
     t = sub.add_parser("test", help="health check")
     t.set_defaults(func=cmd_test)
 
+    # OPTIONS
+    o = sub.add_parser("options", help="available detectors and models")
+    o.set_defaults(func=cmd_options)
+
     return p
 
 def main():
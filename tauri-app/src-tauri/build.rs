@@ -1,3 +1,57 @@
+#[path = "build/bindings.rs"]
+mod bindings;
+
 fn main() {
+    license_public_key();
+
+    sidecar_checksum("deepface_cli", "DEEPFACE_SIDECAR_SHA256");
+    sidecar_checksum("whisper_cli", "WHISPER_SIDECAR_SHA256");
+    bindings::generate();
+
     tauri_build::build()
 }
+
+// Public key the app trusts for signed license responses (see src/license.rs). Release builds must be given
+// LICENSE_PUBLIC_KEY; debug builds fall back to the local key of cloudServer/genSigningKey.js (never committed)
+fn license_public_key() {
+    const DEV_PUBLIC_KEY_FILE: &str = "../../cloudServer/dev_signing_key.pub";
+    // Keys whose private half got out: a build never trusts them, wherever they come from
+    const REVOKED_PUBLIC_KEYS: &[&str] = &["0a17243521c55aef2be9a60740c645cb6cafaa1adecf190830ffd7f6675a8fb3"];
+    let refuse_revoked = |key: &str| {
+        if REVOKED_PUBLIC_KEYS.contains(&key.trim().to_lowercase().as_str()) {
+            panic!("LICENSE_PUBLIC_KEY is a revoked key: generate a new pair with cloudServer/genSigningKey.js");
+        }
+    };
+
+    println!("cargo:rerun-if-env-changed=LICENSE_PUBLIC_KEY");
+    if let Ok(key) = std::env::var("LICENSE_PUBLIC_KEY") {
+        refuse_revoked(&key);
+        return;
+    }
+    if std::env::var("PROFILE").as_deref() == Ok("release") {
+        panic!("LICENSE_PUBLIC_KEY must be set for a release build (hex Ed25519 key printed by cloudServer/genSigningKey.js)");
+    }
+    println!("cargo:rerun-if-changed={}", DEV_PUBLIC_KEY_FILE);
+    match std::fs::read_to_string(DEV_PUBLIC_KEY_FILE) {
+        Ok(key) => {
+            refuse_revoked(&key);
+            println!("cargo:rustc-env=LICENSE_PUBLIC_KEY={}", key.trim());
+        }
+        Err(_) => println!("cargo:warning=No LICENSE_PUBLIC_KEY: run `node genSigningKey.js` in cloudServer/, signed license responses will be rejected"),
+    }
+}
+
+// SHA-256 of a sidecar bundled for this target (externalBin binaries/<name>-<triple>), exported as `env`:
+// checked by deepFaceProcess.rs / whisper.rs before every spawn
+fn sidecar_checksum(name: &str, env: &str) {
+    use sha2::{Digest, Sha256};
+
+    let target = std::env::var("TARGET").unwrap_or_default();
+    let suffix = if target.contains("windows") { ".exe" } else { "" };
+    let path = format!("binaries/{}-{}{}", name, target, suffix);
+    println!("cargo:rerun-if-changed={}", path);
+    match std::fs::read(&path) {
+        Ok(bytes) => println!("cargo:rustc-env={}={}", env, hex::encode(Sha256::digest(&bytes))),
+        Err(_) => println!("cargo:warning={} not found: the {} sidecar won't be checksum-verified", path, name),
+    }
+}
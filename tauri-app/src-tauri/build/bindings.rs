@@ -0,0 +1,494 @@
+// build/bindings.rs
+//
+// Typed frontend bindings of the Tauri commands, generated by build.rs from the Rust sources.
+// - every #[tauri::command] fn of COMMAND_FILES becomes `commands.<camelName>(args…)` in ../src/bindings.js,
+//   with its TypeScript signature in ../src/bindings.d.ts (arguments sent camelCase, as Tauri expects them)
+// - the structs / enums the commands take or return, and the EVENT_PAYLOADS, are declared from their serde
+//   attributes (rename_all, rename, skip, skip_serializing_if, flatten, tag / content, untagged)
+// - a type the generator can't follow (generic, from another crate, custom Serialize) is `unknown`
+// - rejected promises carry an `AppError` ({ code, message, retryable, … }, see error.rs)
+// The files are rewritten only when they change, and aren't committed (.gitignore).
+// Usage in the webview: `import { commands, listen } from "./bindings.js"`
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use syn::{Attribute, Expr, Fields, FnArg, GenericArgument, Item, ItemFn, Lit, LitStr, Meta, Pat, PathArguments, ReturnType, Type};
+
+
+//____________Const___________
+const SOURCE_DIR: &str = "src";
+const COMMAND_FILES: &[&str] = &["src/commands.rs", "src/deepFaceProcess.rs"];
+const OUT_JS: &str = "../src/bindings.js";
+const OUT_DTS: &str = "../src/bindings.d.ts";
+// Arguments Tauri fills in itself
+const INJECTED: &[&str] = &["AppHandle", "State", "Window", "WebviewWindow", "Webview"];
+// Event name -> Rust type of its payload (events.rs and the modules emitting them)
+const EVENT_PAYLOADS: &[(&str, &str)] = &[
+    ("cep-connection", "ConnectionEvent"),
+    ("cep-status", "Localized"),
+    ("database-restored", "DatabaseStatus"),
+    ("db-changed", "DbChange"),
+    ("deepface-status", "SidecarStatus"),
+    ("job-progress", "Job"),
+    ("license-status", "LicenseStatus"),
+    ("marker-added", "Marker"),
+    ("markers-changed", "OperationChange"),
+    ("project-opened", "Project"),
+    ("settings-changed", "AppSettings"),
+    ("watched-files-changed", "WatchChange"),
+];
+const APP_ERROR: &str = "export interface AppError {
+  code: string;
+  message: string;
+  retryable: boolean;
+  license?: unknown;
+  fields?: { field: string; problem: string }[];
+}";
+
+
+//_____________Struct _________________________
+// The serde attributes the generated types follow
+#[derive(Default)]
+struct Serde {
+    rename: Option<String>,
+    rename_all: Option<String>,
+    tag: Option<String>,
+    content: Option<String>,
+    untagged: bool,
+    transparent: bool,
+    skip: bool,
+    optional: bool, // skip_serializing_if: the field may be missing
+    flatten: bool,
+}
+
+struct Command {
+    name: String,
+    docs: Vec<String>,
+    args: Vec<(String, Type)>, // camelCase name, type
+    output: Option<Type>,      // None = ()
+}
+
+struct Generator {
+    items: HashMap<String, Item>,         // struct / enum definitions of SOURCE_DIR, by name
+    declared: BTreeMap<String, String>,   // name -> TypeScript declaration
+    pending: Vec<String>,
+}
+
+
+//_____________fn ____________________________
+
+/// Write ../src/bindings.js and bindings.d.ts; a failure is a build warning, not an error.
+pub fn generate() {
+    println!("cargo:rerun-if-changed={}", SOURCE_DIR);
+    if let Err(e) = try_generate() {
+        println!("cargo:warning=Frontend bindings not generated: {}", e);
+    }
+}
+
+fn try_generate() -> Result<(), String> {
+    let mut generator = Generator { items: HashMap::new(), declared: BTreeMap::new(), pending: Vec::new() };
+    let mut sources: Vec<_> = std::fs::read_dir(SOURCE_DIR)
+        .map_err(|e| format!("{}: {}", SOURCE_DIR, e))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|x| x == "rs"))
+        .collect();
+    sources.sort();
+    for path in &sources {
+        for item in parse(path)?.items {
+            let name = match &item {
+                Item::Struct(s) => s.ident.to_string(),
+                Item::Enum(e) => e.ident.to_string(),
+                _ => continue,
+            };
+            generator.items.entry(name).or_insert(item); // first definition wins
+        }
+    }
+
+    let mut commands = Vec::new();
+    for file in COMMAND_FILES {
+        commands.extend(parse(Path::new(file))?.items.into_iter().filter_map(|item| match item {
+            Item::Fn(f) if is_command(&f) => Some(command(&f)),
+            _ => None,
+        }));
+    }
+
+    let signatures: Vec<String> = commands.iter().map(|c| generator.signature(c)).collect();
+    let events: Vec<String> = EVENT_PAYLOADS
+        .iter()
+        .map(|(event, payload)| format!("  \"{}\": {};", event, generator.named(payload)))
+        .collect();
+    generator.declare_pending();
+
+    write_if_changed(OUT_JS, &javascript(&commands))?;
+    write_if_changed(OUT_DTS, &typescript(&generator, &signatures, &events))
+}
+
+fn parse(path: &Path) -> Result<syn::File, String> {
+    let source = std::fs::read_to_string(path).map_err(|e| format!("{:?}: {}", path, e))?;
+    syn::parse_file(&source).map_err(|e| format!("{:?}: {}", path, e))
+}
+
+fn write_if_changed(path: &str, content: &str) -> Result<(), String> {
+    if std::fs::read_to_string(path).is_ok_and(|current| current == content) {
+        return Ok(());
+    }
+    std::fs::write(path, content).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn is_command(f: &ItemFn) -> bool {
+    f.attrs.iter().any(|a| {
+        let segments: Vec<String> = a.path().segments.iter().map(|s| s.ident.to_string()).collect();
+        segments == ["tauri", "command"]
+    })
+}
+
+fn command(f: &ItemFn) -> Command {
+    let args = f
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            FnArg::Typed(arg) => match &*arg.pat {
+                Pat::Ident(pat) if !INJECTED.contains(&last_ident(&arg.ty).as_str()) => {
+                    Some((camel_case(pat.ident.to_string().trim_start_matches("r#")), (*arg.ty).clone()))
+                }
+                _ => None,
+            },
+            FnArg::Receiver(_) => None,
+        })
+        .collect();
+    let output = match &f.sig.output {
+        ReturnType::Default => None,
+        ReturnType::Type(_, ty) => Some((**ty).clone()),
+    };
+    Command { name: f.sig.ident.to_string(), docs: docs(&f.attrs), args, output }
+}
+
+fn docs(attrs: &[Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter_map(|a| match &a.meta {
+            Meta::NameValue(nv) if nv.path.is_ident("doc") => match &nv.value {
+                Expr::Lit(lit) => match &lit.lit {
+                    Lit::Str(s) => Some(s.value().trim().replace("*/", "*\\/")),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+fn doc_comment(docs: &[String], indent: &str) -> String {
+    match docs {
+        [] => String::new(),
+        [line] => format!("{}/** {} */\n", indent, line),
+        lines => {
+            let body: String = lines.iter().map(|l| format!("{} * {}\n", indent, l).replace(" * \n", " *\n")).collect();
+            format!("{}/**\n{}{} */\n", indent, body, indent)
+        }
+    }
+}
+
+fn serde_attrs(attrs: &[Attribute]) -> Serde {
+    let mut serde = Serde::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            let key = meta.path.get_ident().map(|i| i.to_string()).unwrap_or_default();
+            if meta.input.peek(syn::token::Paren) {
+                // rename(serialize = "…", deserialize = "…"): only the serialized name matters here
+                return meta.parse_nested_meta(|inner| {
+                    let value: LitStr = inner.value()?.parse()?;
+                    if inner.path.is_ident("serialize") {
+                        match key.as_str() {
+                            "rename" => serde.rename = Some(value.value()),
+                            "rename_all" => serde.rename_all = Some(value.value()),
+                            _ => {}
+                        }
+                    }
+                    Ok(())
+                });
+            }
+            let value = if meta.input.peek(syn::Token![=]) { Some(meta.value()?.parse::<LitStr>()?.value()) } else { None };
+            match key.as_str() {
+                "rename" => serde.rename = value,
+                "rename_all" => serde.rename_all = value,
+                "tag" => serde.tag = value,
+                "content" => serde.content = value,
+                "untagged" => serde.untagged = true,
+                "transparent" => serde.transparent = true,
+                "skip" | "skip_serializing" => serde.skip = true,
+                "skip_serializing_if" => serde.optional = true,
+                "flatten" => serde.flatten = true,
+                _ => {}
+            }
+            Ok(())
+        });
+    }
+    serde
+}
+
+fn last_ident(ty: &Type) -> String {
+    match ty {
+        Type::Path(p) => p.path.segments.last().map(|s| s.ident.to_string()).unwrap_or_default(),
+        Type::Reference(r) => last_ident(&r.elem),
+        _ => String::new(),
+    }
+}
+
+fn type_args(ty: &Type) -> Vec<&Type> {
+    let Type::Path(p) = ty else { return Vec::new() };
+    match p.path.segments.last().map(|s| &s.arguments) {
+        Some(PathArguments::AngleBracketed(args)) => args
+            .args
+            .iter()
+            .filter_map(|a| match a {
+                GenericArgument::Type(t) => Some(t),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// snake_case words of a field / PascalCase words of a variant
+fn words(name: &str) -> Vec<String> {
+    if name.contains('_') || name.chars().all(|c| !c.is_uppercase()) {
+        return name.split('_').filter(|w| !w.is_empty()).map(str::to_lowercase).collect();
+    }
+    let mut words: Vec<String> = Vec::new();
+    for c in name.chars() {
+        if c.is_uppercase() || words.is_empty() {
+            words.push(String::new());
+        }
+        if let Some(word) = words.last_mut() {
+            word.extend(c.to_lowercase());
+        }
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+fn camel_case(name: &str) -> String {
+    let words = words(name);
+    words.iter().enumerate().map(|(i, w)| if i == 0 { w.clone() } else { capitalize(w) }).collect()
+}
+
+// Name of a field / variant on the wire under `rename_all`
+fn wire_name(ident: &str, rename_all: Option<&str>) -> String {
+    let ident = ident.trim_start_matches("r#");
+    let words = words(ident);
+    match rename_all {
+        Some("lowercase") => ident.to_lowercase(),
+        Some("UPPERCASE") => ident.to_uppercase(),
+        Some("camelCase") => camel_case(ident),
+        Some("PascalCase") => words.iter().map(|w| capitalize(w)).collect(),
+        Some("snake_case") => words.join("_"),
+        Some("SCREAMING_SNAKE_CASE") => words.join("_").to_uppercase(),
+        Some("kebab-case") => words.join("-"),
+        Some("SCREAMING-KEBAB-CASE") => words.join("-").to_uppercase(),
+        _ => ident.to_string(),
+    }
+}
+
+fn quoted(name: &str) -> String {
+    let plain = name.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+    if plain { name.to_string() } else { format!("\"{}\"", name) }
+}
+
+fn array_of(inner: String) -> String {
+    if inner.contains(' ') { format!("({})[]", inner) } else { format!("{}[]", inner) }
+}
+
+impl Generator {
+    // TypeScript of a Rust type, queuing the definitions it needs
+    fn ts(&mut self, ty: &Type) -> String {
+        match ty {
+            Type::Reference(r) => self.ts(&r.elem),
+            Type::Paren(p) => self.ts(&p.elem),
+            Type::Tuple(t) if t.elems.is_empty() => "null".into(),
+            Type::Tuple(t) => format!("[{}]", t.elems.iter().map(|e| self.ts(e)).collect::<Vec<_>>().join(", ")),
+            Type::Slice(s) => array_of(self.ts(&s.elem)),
+            Type::Array(a) => array_of(self.ts(&a.elem)),
+            Type::Path(_) => {
+                let args = type_args(ty);
+                let arg = |generator: &mut Self, i: usize| args.get(i).map(|t| generator.ts(t)).unwrap_or_else(|| "unknown".into());
+                match last_ident(ty).as_str() {
+                    "String" | "str" | "PathBuf" | "Path" | "OsString" | "char" | "DateTime" | "NaiveDate" | "NaiveDateTime" => "string".into(),
+                    "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "f32" | "f64" => "number".into(),
+                    "bool" => "boolean".into(),
+                    "Value" => "unknown".into(),
+                    "Map" => "Record<string, unknown>".into(),
+                    "Option" => format!("{} | null", arg(self, 0)),
+                    "Vec" | "VecDeque" | "HashSet" | "BTreeSet" => array_of(arg(self, 0)),
+                    "HashMap" | "BTreeMap" => format!("Record<string, {}>", arg(self, 1)),
+                    "Box" | "Arc" | "Rc" | "Cow" | "Result" => arg(self, 0),
+                    name => self.named(name),
+                }
+            }
+            _ => "unknown".into(),
+        }
+    }
+
+    // A type of SOURCE_DIR by name (declared later), or `unknown`
+    fn named(&mut self, name: &str) -> String {
+        if name == "AppError" {
+            return name.to_string(); // APP_ERROR, its Serialize is hand-written
+        }
+        let generic = match self.items.get(name) {
+            Some(Item::Struct(s)) => !s.generics.params.is_empty(),
+            Some(Item::Enum(e)) => !e.generics.params.is_empty(),
+            _ => return "unknown".into(),
+        };
+        if generic {
+            return "unknown".into();
+        }
+        if !self.declared.contains_key(name) && !self.pending.iter().any(|p| p == name) {
+            self.pending.push(name.to_string());
+        }
+        name.to_string()
+    }
+
+    fn declare_pending(&mut self) {
+        while let Some(name) = self.pending.pop() {
+            if self.declared.contains_key(&name) {
+                continue;
+            }
+            self.declared.insert(name.clone(), String::new()); // no loop on recursive types
+            let declaration = match self.items.get(&name).cloned() {
+                Some(Item::Struct(s)) => {
+                    let serde = serde_attrs(&s.attrs);
+                    let body = self.fields(&s.fields, serde.rename_all.as_deref(), serde.transparent);
+                    format!("{}export type {} = {};", doc_comment(&docs(&s.attrs), ""), name, body)
+                }
+                Some(Item::Enum(e)) => {
+                    let body = self.variants(&e);
+                    format!("{}export type {} = {};", doc_comment(&docs(&e.attrs), ""), name, body)
+                }
+                _ => format!("export type {} = unknown;", name),
+            };
+            self.declared.insert(name, declaration);
+        }
+    }
+
+    // `{ a: T; b?: U } & Flattened` of struct fields; a newtype / transparent struct is its field's type
+    fn fields(&mut self, fields: &Fields, rename_all: Option<&str>, transparent: bool) -> String {
+        match fields {
+            Fields::Unit => "null".into(),
+            Fields::Unnamed(f) if f.unnamed.len() == 1 => self.ts(&f.unnamed[0].ty),
+            Fields::Unnamed(f) => format!("[{}]", f.unnamed.iter().map(|f| self.ts(&f.ty)).collect::<Vec<_>>().join(", ")),
+            Fields::Named(f) => {
+                let visible: Vec<_> = f.named.iter().filter(|f| !serde_attrs(&f.attrs).skip).collect();
+                if transparent && visible.len() == 1 {
+                    return self.ts(&visible[0].ty);
+                }
+                let mut members = Vec::new();
+                let mut flattened = Vec::new();
+                for field in visible {
+                    let serde = serde_attrs(&field.attrs);
+                    let ty = self.ts(&field.ty);
+                    if serde.flatten {
+                        flattened.push(ty);
+                        continue;
+                    }
+                    let ident = field.ident.as_ref().map(|i| i.to_string()).unwrap_or_default();
+                    let name = serde.rename.unwrap_or_else(|| wire_name(&ident, rename_all));
+                    let optional = if serde.optional { "?" } else { "" };
+                    members.push(format!("{}  {}{}: {};\n", doc_comment(&docs(&field.attrs), "  "), quoted(&name), optional, ty));
+                }
+                let object = format!("{{\n{}}}", members.concat());
+                std::iter::once(object).chain(flattened).collect::<Vec<_>>().join(" & ")
+            }
+        }
+    }
+
+    fn variants(&mut self, e: &syn::ItemEnum) -> String {
+        let serde = serde_attrs(&e.attrs);
+        let mut out = Vec::new();
+        for variant in &e.variants {
+            let attrs = serde_attrs(&variant.attrs);
+            if attrs.skip {
+                continue;
+            }
+            let name = attrs.rename.unwrap_or_else(|| wire_name(&variant.ident.to_string(), serde.rename_all.as_deref()));
+            let unit = matches!(variant.fields, Fields::Unit);
+            let data = self.fields(&variant.fields, None, false);
+            out.push(match (&serde.tag, &serde.content) {
+                _ if serde.untagged => data,
+                (Some(tag), Some(_)) if unit => format!("{{ {}: \"{}\" }}", quoted(tag), name),
+                (Some(tag), Some(content)) => format!("{{ {}: \"{}\"; {}: {} }}", quoted(tag), name, quoted(content), data),
+                (Some(tag), None) if unit => format!("{{ {}: \"{}\" }}", quoted(tag), name),
+                (Some(tag), None) => format!("({{ {}: \"{}\" }} & {})", quoted(tag), name, data),
+                (None, _) if unit => format!("\"{}\"", name),
+                (None, _) => format!("{{ {}: {} }}", quoted(&name), data),
+            });
+        }
+        if out.is_empty() { "never".into() } else { out.join(" | ") }
+    }
+
+    // `name(a: T, b?: U | null): Promise<R>;` of a command; trailing Option arguments may be left out
+    fn signature(&mut self, command: &Command) -> String {
+        let mut optional = true;
+        let mut args: Vec<String> = command
+            .args
+            .iter()
+            .rev()
+            .map(|(name, ty)| {
+                optional &= last_ident(ty) == "Option";
+                format!("{}{}: {}", name, if optional { "?" } else { "" }, self.ts(ty))
+            })
+            .collect();
+        args.reverse();
+        let output = command.output.as_ref().map(|ty| self.ts(ty)).unwrap_or_else(|| "null".into());
+        format!("{}  {}({}): Promise<{}>;", doc_comment(&command.docs, "  "), camel_case(&command.name), args.join(", "), output)
+    }
+}
+
+fn javascript(commands: &[Command]) -> String {
+    let mut js = String::from(
+        "// Generated by src-tauri/build.rs (build/bindings.rs) from the Tauri commands: do not edit.\n\
+         // Types in bindings.d.ts; a rejected call gives an AppError { code, message, retryable }.\n\n\
+         const { invoke } = window.__TAURI__.core;\n\n\
+         /** @type {import(\"./bindings\").Commands} */\n\
+         export const commands = {\n",
+    );
+    for command in commands {
+        let names: Vec<&str> = command.args.iter().map(|(name, _)| name.as_str()).collect();
+        js += &format!(
+            "  {}: ({}) => invoke(\"{}\", {{ {} }}),\n",
+            camel_case(&command.name),
+            names.join(", "),
+            command.name,
+            names.join(", ")
+        )
+        .replace("{  }", "{}");
+    }
+    js += "};\n\n\
+           /** @type {import(\"./bindings\").listen} */\n\
+           export function listen(event, handler) {\n  \
+             return window.__TAURI__.event.listen(event, (e) => handler(e.payload));\n\
+           }\n";
+    js
+}
+
+fn typescript(generator: &Generator, signatures: &[String], events: &[String]) -> String {
+    let mut ts = String::from("// Generated by src-tauri/build.rs (build/bindings.rs) from the Rust sources: do not edit.\n\n");
+    ts += APP_ERROR;
+    ts += "\n\n";
+    for declaration in generator.declared.values() {
+        ts += declaration;
+        ts += "\n\n";
+    }
+    ts += &format!("/** Tauri commands; a rejected promise gives an AppError. */\nexport interface Commands {{\n{}\n}}\n\n", signatures.join("\n"));
+    ts += "export declare const commands: Commands;\n\n";
+    ts += &format!("/** Payload of each app event. */\nexport interface EventPayloads {{\n{}\n}}\n\n", events.join("\n"));
+    ts += "/** Listen to an app event; resolves to the function that stops listening. */\n\
+           export declare function listen<E extends keyof EventPayloads>(event: E, handler: (payload: EventPayloads[E]) => void): Promise<() => void>;\n";
+    ts
+}
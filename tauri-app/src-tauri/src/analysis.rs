@@ -0,0 +1,199 @@
+// src/analysis.rs
+//
+// Clip analysis: sample a clip with ffmpeg (media.rs), run deepface emotion analysis on each frame and store
+// one deepface marker per detected face.
+// - sampling "scenes" (default): the clip is cut into shots at scene changes (media::detect_shots) and sampled
+//   once per shot, SHOT_OFFSET_SECS after its cut, plus every everySecs (SHOT_GAP_SECS) inside long shots: a
+//   static interview takes a few frames instead of one every 2 s, and no cut is missed
+// - sampling "interval": one frame every everySecs (ANALYZE_EVERY_SECS), the default when only everySecs is given
+// - shared by the analyze_clip job (jobs.rs) and the headless batch mode (cli.rs); both pass a progress
+//   callback (fraction 0..1, message) whose error stops the run, e.g. when the job is cancelled
+// - the sidecar is started when it isn't running; the sampled frames are deleted afterwards
+// - with several sidecar instances (settings.deepface instances) that many frames are analyzed at once
+// - faces are tracked across the sampled frames (tracking.rs): each marker's metadata carries its `track` id,
+//   the same for every marker of one person
+// - each marker keeps the face's full deepface result (database analyses, `get_analysis`)
+// - the markers are stored in one transaction: a single undo removes the whole analysis
+
+use futures_util::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::str::FromStr;
+use tauri::AppHandle;
+use tracing::warn;
+
+use crate::database::{self, Clip, Marker, MarkerSource, NewAnalysis, NewMarker};
+use crate::deepFaceProcess::{self, MANAGER};
+use crate::error::AppError;
+use crate::media::{self, FrameFile, Shot};
+use crate::payloads::AnalyzeClipArgs;
+use crate::tracking::{self, FaceTracker, Region};
+
+
+//____________Const___________
+pub const ANALYZE_EVERY_SECS: f64 = 2.0; // interval sampling when everySecs is omitted
+pub const SHOT_GAP_SECS: f64 = 10.0;     // scenes sampling: longest time inside a shot without a frame
+const SHOT_OFFSET_SECS: f64 = 0.5;       // a shot's first frame is taken this long after the cut (past transitions)
+const ANALYZE_ACTIONS: &str = "emotion";
+
+
+//_____________Struct _________________________
+/// How frames are picked for analysis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Sampling {
+    Interval,
+    Scenes,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClipAnalysis {
+    pub clip: Clip,
+    pub frames: usize,        // frames analyzed
+    pub shots: Option<usize>, // scenes sampling: shots found
+    pub markers: Vec<Marker>, // stored, one per face
+    pub tracks: usize,        // people tracked across the frames
+}
+
+
+//_____________fn ____________________________
+
+impl FromStr for Sampling {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "interval" => Ok(Sampling::Interval),
+            "scenes" => Ok(Sampling::Scenes),
+            other => Err(AppError::InvalidInput(format!("Unknown sampling '{}' (expected interval or scenes)", other))),
+        }
+    }
+}
+
+/// Times to analyze in `shots`: SHOT_OFFSET_SECS into each (its middle when shorter), then every `gap` seconds.
+pub fn shot_sample_times(shots: &[Shot], gap: f64) -> Vec<f64> {
+    let mut times = Vec::new();
+    for shot in shots {
+        let mut time = shot.start + SHOT_OFFSET_SECS.min((shot.end - shot.start) / 2.0);
+        while time < shot.end {
+            times.push(time);
+            time += gap;
+        }
+    }
+    times
+}
+
+// Frames to analyze, as files in `dir`, and the number of shots when sampled per shot
+async fn sample<P>(progress: &P, clip: &Clip, args: &AnalyzeClipArgs, dir: &Path) -> Result<(Vec<FrameFile>, Option<usize>), AppError>
+where
+    P: Fn(f64, &str) -> Result<(), AppError>,
+{
+    let sampling = args.sampling.unwrap_or(if args.every_secs.is_some() { Sampling::Interval } else { Sampling::Scenes });
+    if sampling == Sampling::Interval {
+        progress(0.0, "Sampling frames")?;
+        let frames = media::sample_frames(&clip.path, args.every_secs.unwrap_or(ANALYZE_EVERY_SECS), dir, args.max_width).await?;
+        return Ok((frames, None));
+    }
+
+    let gap = args.every_secs.unwrap_or(SHOT_GAP_SECS);
+    if !gap.is_finite() || gap <= 0.0 {
+        return Err(AppError::InvalidInput("everySecs must be > 0".into()));
+    }
+    progress(0.0, "Detecting scene cuts")?;
+    let shots = media::detect_shots(&clip.path, args.scene_threshold).await?;
+    let times = shot_sample_times(&shots, gap);
+    progress(0.0, &format!("Sampling {} frames from {} shots", times.len(), shots.len()))?;
+    let frames = media::frames_at(&clip.path, &times, dir, args.max_width).await?;
+    Ok((frames, Some(shots.len())))
+}
+
+/// Start the deepface sidecar unless it is running: on `port` always (headless batch), without one as the
+/// settings.deepface startup policy allows.
+pub async fn ensure_deepface(port: Option<u16>) -> Result<(), AppError> {
+    match port {
+        Some(port) => MANAGER.start_if_needed(Some(port)).await,
+        None => MANAGER.ensure_started().await,
+    }
+}
+
+// Emotion analysis of every sampled frame, one marker per detected face (tagged with its track)
+async fn analyze_frames<P>(progress: &P, clip_id: i64, frames: Vec<FrameFile>, args: &AnalyzeClipArgs) -> Result<(Vec<NewMarker>, usize), AppError>
+where
+    P: Fn(f64, &str) -> Result<(), AppError>,
+{
+    let total = frames.len();
+    let mut markers = Vec::new();
+    let mut tracker = FaceTracker::new();
+
+    // As many frames in flight as sidecar instances; answers still come back in timeline order for the tracker
+    let answers = stream::iter(frames.into_iter().map(|frame| {
+        let (detector, model) = (args.detector.clone(), args.model.clone());
+        async move {
+            let jpeg = tokio::fs::read(&frame.path).await?;
+            let answer = deepFaceProcess::analyze_frame(&jpeg, Some(ANALYZE_ACTIONS.into()), detector, model).await?;
+            Ok::<_, AppError>((frame, answer))
+        }
+    }))
+    .buffered(MANAGER.instance_count());
+    futures_util::pin_mut!(answers);
+
+    let mut i = 0;
+    while let Some(analyzed) = answers.next().await {
+        let (frame, answer) = analyzed?;
+        i += 1;
+        progress(0.1 + 0.9 * i as f64 / total as f64, &format!("Analyzed frame {}/{} ({}s)", i, total, frame.timestamp))?;
+        if answer["status"] != "ok" {
+            warn!("⚠️ Frame at {}s not analyzed: {}", frame.timestamp, answer["data"]["message"]);
+            tracker.next_frame(&[]);
+            continue;
+        }
+        let faces = tracking::analyzed_faces(&answer);
+        let regions: Vec<Option<Region>> = faces.iter().map(|face| Region::of_face(face)).collect();
+        for (face, track) in faces.into_iter().zip(tracker.next_frame(&regions)) {
+            let mut metadata = face.clone();
+            metadata["track"] = track.into();
+            markers.push(NewMarker {
+                clip_id,
+                timestamp: frame.timestamp,
+                label: face["dominant_emotion"].as_str().map(String::from),
+                source: MarkerSource::Deepface,
+                metadata: Some(metadata),
+                analysis: Some(NewAnalysis {
+                    model: args.model.clone().or_else(|| MANAGER.settings().default_model),
+                    detector: args.detector.clone().or_else(|| MANAGER.settings().default_detector),
+                    result: face.clone(),
+                }),
+                ..NewMarker::default()
+            });
+        }
+    }
+    Ok((markers, tracker.track_count()))
+}
+
+/// Register the clip (when it isn't yet), analyze it and store the markers found.
+pub async fn analyze_clip<P>(app_handle: &AppHandle, args: &AnalyzeClipArgs, progress: P) -> Result<ClipAnalysis, AppError>
+where
+    P: Fn(f64, &str) -> Result<(), AppError>,
+{
+    // Markers are stored in seconds but exported as timecodes: the clip needs its real fps
+    let fps = match args.fps {
+        Some(fps) => Some(fps),
+        None => media::probe(&args.path).await.ok().and_then(|info| info.fps),
+    };
+    let clip = database::add_clip(&args.path, fps, args.project_id)?;
+    progress(0.0, "Starting deepface")?;
+    ensure_deepface(None).await?;
+
+    let dir = media::new_frames_dir(app_handle)?;
+    let analyzed = async {
+        let (frames, shots) = sample(&progress, &clip, args, &dir).await?;
+        let count = frames.len();
+        Ok::<_, AppError>((count, shots, analyze_frames(&progress, clip.id, frames, args).await?))
+    }
+    .await;
+    let _ = std::fs::remove_dir_all(&dir); // the frames were only needed for the analysis
+    let (frames, shots, (markers, tracks)) = analyzed?;
+
+    let markers = database::add_markers(&markers)?;
+    Ok(ClipAnalysis { clip, frames, shots, markers, tracks })
+}
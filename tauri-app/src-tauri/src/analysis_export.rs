@@ -0,0 +1,241 @@
+// src/analysis_export.rs
+//
+// Raw analysis data of a project (one row per analyzed face, from the analyses table) for post-processing
+// outside the app:
+// - jsonl   : one JSON object per line, scores under `emotions`, face box under `region`
+// - csv     : flat columns, one score column per emotion (emotion_stats::EMOTIONS)
+// - parquet : the same flat columns, typed (INT64 ids, DOUBLE scores / box, UTF8 text), one row group
+//
+// Usage: `invoke("export_analysis", { projectId: 1, format: "parquet", path: "C:/…/analysis.parquet" })`
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::info;
+
+use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+
+use crate::database::{self, AnalyzedFace};
+use crate::emotion_stats::EMOTIONS;
+use crate::error::AppError;
+use crate::tracking::Region;
+
+
+//_____________Enum _________________________
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnalysisFormat {
+    JsonLines,
+    Csv,
+    Parquet,
+}
+
+impl FromStr for AnalysisFormat {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "jsonl" | "ndjson" | "json" => Ok(AnalysisFormat::JsonLines),
+            "csv" => Ok(AnalysisFormat::Csv),
+            "parquet" => Ok(AnalysisFormat::Parquet),
+            other => Err(AppError::InvalidInput(format!(
+                "Unknown analysis export format '{}' (expected jsonl, csv or parquet)",
+                other
+            ))),
+        }
+    }
+}
+
+
+//_____________Struct _________________________
+/// One analyzed face.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisRow {
+    pub project_id: i64,
+    pub clip_id: i64,
+    pub clip_path: String,
+    pub marker_id: i64,
+    pub timestamp: f64, // seconds from the start of the clip
+    pub model: Option<String>,
+    pub detector: Option<String>,
+    pub dominant_emotion: Option<String>,
+    pub emotions: BTreeMap<String, f64>, // deepface score (0..100) per emotion
+    pub face_confidence: Option<f64>,
+    pub region: Option<Region>, // face box in frame pixels
+}
+
+// A parquet / csv column
+enum Column {
+    Int64(Vec<i64>),
+    Double(Vec<Option<f64>>),
+    Text(Vec<Option<String>>),
+}
+
+
+//_____________fn ____________________________
+
+/// The analyzed faces of a project, clip by clip in timeline order.
+pub fn project_rows(project_id: i64) -> Result<Vec<AnalysisRow>, AppError> {
+    let faces = database::project_analyses(project_id)?;
+    let paths: HashMap<i64, String> = database::list_clips(project_id)?.into_iter().map(|c| (c.id, c.path)).collect();
+    Ok(faces.into_iter().map(|face| row(project_id, &paths, face)).collect())
+}
+
+fn row(project_id: i64, paths: &HashMap<i64, String>, face: AnalyzedFace) -> AnalysisRow {
+    let result = &face.result;
+    AnalysisRow {
+        project_id,
+        clip_id: face.clip_id,
+        clip_path: paths.get(&face.clip_id).cloned().unwrap_or_default(),
+        marker_id: face.marker_id,
+        timestamp: face.timestamp,
+        dominant_emotion: result["dominant_emotion"].as_str().map(String::from),
+        emotions: EMOTIONS
+            .iter()
+            .filter_map(|emotion| Some((emotion.to_string(), result["emotion"][*emotion].as_f64()?)))
+            .collect(),
+        face_confidence: result["face_confidence"].as_f64(),
+        region: Region::of_face(result),
+        model: face.model,
+        detector: face.detector,
+    }
+}
+
+// Flat columns, in file order
+fn columns(rows: &[AnalysisRow]) -> Vec<(String, Column)> {
+    let text = |f: fn(&AnalysisRow) -> Option<String>| Column::Text(rows.iter().map(f).collect());
+    let mut columns = vec![
+        ("project_id".to_string(), Column::Int64(rows.iter().map(|r| r.project_id).collect())),
+        ("clip_id".to_string(), Column::Int64(rows.iter().map(|r| r.clip_id).collect())),
+        ("clip_path".to_string(), text(|r| Some(r.clip_path.clone()))),
+        ("marker_id".to_string(), Column::Int64(rows.iter().map(|r| r.marker_id).collect())),
+        ("timestamp".to_string(), Column::Double(rows.iter().map(|r| Some(r.timestamp)).collect())),
+        ("model".to_string(), text(|r| r.model.clone())),
+        ("detector".to_string(), text(|r| r.detector.clone())),
+        ("dominant_emotion".to_string(), text(|r| r.dominant_emotion.clone())),
+    ];
+    for emotion in EMOTIONS {
+        columns.push((emotion.to_string(), Column::Double(rows.iter().map(|r| r.emotions.get(*emotion).copied()).collect())));
+    }
+    columns.push(("face_confidence".to_string(), Column::Double(rows.iter().map(|r| r.face_confidence).collect())));
+    for (name, side) in [("box_x", 0), ("box_y", 1), ("box_w", 2), ("box_h", 3)] {
+        let values = rows.iter().map(|r| r.region.map(|b| [b.x, b.y, b.w, b.h][side])).collect();
+        columns.push((name.to_string(), Column::Double(values)));
+    }
+    columns
+}
+
+fn render_jsonl(rows: &[AnalysisRow]) -> Result<String, AppError> {
+    let mut out = String::new();
+    for row in rows {
+        out.push_str(&serde_json::to_string(row).map_err(|e| AppError::Io(format!("Failed to serialize a row: {}", e)))?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn render_csv(rows: &[AnalysisRow]) -> String {
+    let columns = columns(rows);
+    let mut out = columns.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(",");
+    out.push_str("\r\n");
+    for i in 0..rows.len() {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|(_, column)| match column {
+                Column::Int64(values) => values[i].to_string(),
+                Column::Double(values) => values[i].map(|v| v.to_string()).unwrap_or_default(),
+                Column::Text(values) => values[i].as_deref().map(csv_field).unwrap_or_default(),
+            })
+            .collect();
+        let _ = write!(out, "{}\r\n", cells.join(","));
+    }
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn parquet_error(e: ParquetError) -> AppError {
+    AppError::Io(format!("Failed to write parquet: {}", e))
+}
+
+// Values present, and the definition level of every row (1 = value, 0 = null)
+fn split<T: Clone>(values: &[Option<T>]) -> (Vec<T>, Vec<i16>) {
+    let present = values.iter().flatten().cloned().collect();
+    let levels = values.iter().map(|v| v.is_some() as i16).collect();
+    (present, levels)
+}
+
+fn write_parquet(rows: &[AnalysisRow], path: &str) -> Result<(), AppError> {
+    let columns = columns(rows);
+    let fields: Vec<String> = columns
+        .iter()
+        .map(|(name, column)| match column {
+            Column::Int64(_) => format!("REQUIRED INT64 {};", name),
+            Column::Double(_) => format!("OPTIONAL DOUBLE {};", name),
+            Column::Text(_) => format!("OPTIONAL BYTE_ARRAY {} (UTF8);", name),
+        })
+        .collect();
+    let schema = Arc::new(parse_message_type(&format!("message analysis {{ {} }}", fields.join(" "))).map_err(parquet_error)?);
+
+    let file = File::create(path).map_err(|e| AppError::Io(format!("Failed to create {}: {}", path, e)))?;
+    let mut writer = SerializedFileWriter::new(file, schema, Arc::new(WriterProperties::builder().build())).map_err(parquet_error)?;
+    let mut row_group = writer.next_row_group().map_err(parquet_error)?;
+    for (_, column) in &columns {
+        let Some(mut out) = row_group.next_column().map_err(parquet_error)? else { break };
+        match column {
+            Column::Int64(values) => {
+                out.typed::<Int64Type>().write_batch(values, None, None).map_err(parquet_error)?;
+            }
+            Column::Double(values) => {
+                let (present, levels) = split(values);
+                out.typed::<DoubleType>().write_batch(&present, Some(&levels), None).map_err(parquet_error)?;
+            }
+            Column::Text(values) => {
+                let bytes: Vec<Option<ByteArray>> = values.iter().map(|v| v.as_deref().map(ByteArray::from)).collect();
+                let (present, levels) = split(&bytes);
+                out.typed::<ByteArrayType>().write_batch(&present, Some(&levels), None).map_err(parquet_error)?;
+            }
+        }
+        out.close().map_err(parquet_error)?;
+    }
+    row_group.close().map_err(parquet_error)?;
+    writer.close().map_err(parquet_error)?;
+    Ok(())
+}
+
+/// Write the analysis rows of `project_id` to `path`. Returns the number of rows exported.
+pub fn export(project_id: i64, format: AnalysisFormat, path: &str) -> Result<usize, AppError> {
+    let rows = project_rows(project_id)?;
+    match format {
+        AnalysisFormat::Parquet => write_parquet(&rows, path)?,
+        AnalysisFormat::JsonLines | AnalysisFormat::Csv => {
+            let text = if format == AnalysisFormat::Csv { render_csv(&rows) } else { render_jsonl(&rows)? };
+            std::fs::write(path, text).map_err(|e| AppError::Io(format!("Failed to write {}: {}", path, e)))?;
+        }
+    }
+    info!("📤 Exported {} analysis rows of project {} to {} ({:?})", rows.len(), project_id, path, format);
+    Ok(rows.len())
+}
+
+
+//_____________Commands ________________________
+
+/// Write the raw analysis data of a project (jsonl, csv or parquet). Returns the number of rows exported.
+#[tauri::command]
+pub async fn export_analysis(project_id: i64, format: String, path: String) -> Result<usize, AppError> {
+    let format = AnalysisFormat::from_str(&format)?;
+    database::blocking(move || export(project_id, format, &path)).await
+}
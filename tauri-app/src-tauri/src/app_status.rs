@@ -0,0 +1,132 @@
+// src/app_status.rs
+//
+// One status for the whole app, instead of the frontend piecing it together from license / deepface / WS /
+// database / job events:
+// - `AppStatus`: license state and tier, deepface sidecar, WS server, database, active jobs
+// - rebuilt every STATUS_REFRESH seconds, and right away when a module calls `changed()` (license check,
+//   deepface start / stop, job started / finished)
+// - APP_STATUS_EVENT carries only the sections that changed since the last one, plus the full status:
+//   `{ "changes": { "deepface": { … } }, "status": { … } }`
+// Sections are compared as JSON, so live counters (WS last_seen_secs, …) are left out of them.
+
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::sync::Notify;
+use tracing::warn;
+
+use crate::crash;
+use crate::database::{self, DatabaseStatus};
+use crate::deepFaceProcess::{self, SidecarStatus};
+use crate::events;
+use crate::features;
+use crate::jobs;
+use crate::license::{self, LicenseState};
+use crate::websocket;
+
+
+//____________Const___________
+pub const APP_STATUS_EVENT: &str = "app-status-changed";
+const STATUS_REFRESH: u64 = 2; // seconds between two rebuilds without a `changed()`
+
+
+//_____________Struct _________________________
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LicenseSection {
+    pub state: Option<LicenseState>, // None until the first check finished
+    pub tier: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsSection {
+    pub listening: Option<String>,
+    pub tls: bool,
+    pub connections: usize,
+    pub max_connections: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobsSection {
+    pub active: Vec<i64>, // ids of the running jobs
+}
+
+/// Result of `get_app_status`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppStatus {
+    pub license: LicenseSection,
+    pub deepface: SidecarStatus,
+    pub ws: WsSection,
+    pub database: DatabaseStatus,
+    pub jobs: JobsSection,
+}
+
+
+//_____________Globals _______________________
+static CHANGED: Lazy<Notify> = Lazy::new(Notify::new);
+static LAST: Lazy<Mutex<Map<String, Value>>> = Lazy::new(|| Mutex::new(Map::new()));
+static STARTED: OnceCell<()> = OnceCell::new();
+
+
+//_____________fn ____________________________
+
+/// The status as it is now.
+pub async fn snapshot() -> AppStatus {
+    let ws = websocket::status();
+    AppStatus {
+        license: LicenseSection { state: license::current_state(), tier: features::current().tier },
+        deepface: deepFaceProcess::sidecar_status(None, None).await,
+        ws: WsSection { listening: ws.listening, tls: ws.tls, connections: ws.connections.len(), max_connections: ws.max_connections },
+        database: database::status(),
+        jobs: JobsSection { active: jobs::active() },
+    }
+}
+
+/// Something behind the status changed: rebuild it now instead of at the next refresh.
+pub fn changed() {
+    CHANGED.notify_one();
+}
+
+// Sections that differ from the last emitted status (all of them the first time)
+fn diff(status: &AppStatus) -> Option<Map<String, Value>> {
+    let Ok(Value::Object(current)) = serde_json::to_value(status) else { return None };
+    let mut last = LAST.lock().unwrap_or_else(|p| p.into_inner());
+    let changes: Map<String, Value> = current.iter().filter(|(k, v)| last.get(*k) != Some(*v)).map(|(k, v)| (k.clone(), v.clone())).collect();
+    *last = current;
+    (!changes.is_empty()).then_some(changes)
+}
+
+/// Start the status loop. Call once from setup.
+pub fn start(app_handle: AppHandle) {
+    if STARTED.set(()).is_err() {
+        warn!("❌ App status already started");
+        return;
+    }
+    crash::spawn("app-status", async move {
+        loop {
+            let status = snapshot().await;
+            if let Some(changes) = diff(&status) {
+                events::emit_all_surfaces(&app_handle, APP_STATUS_EVENT, json!({ "changes": changes, "status": status }));
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(STATUS_REFRESH)) => {}
+                _ = CHANGED.notified() => {}
+            }
+        }
+    });
+}
+
+
+//_____________Commands ________________________
+
+/// Example: `invoke("get_app_status")` -> `{ license: { state, tier }, deepface: { state, running, … }, ws, database, jobs: { active } }`.
+#[tauri::command]
+pub async fn get_app_status() -> AppStatus {
+    snapshot().await
+}
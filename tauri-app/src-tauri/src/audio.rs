@@ -0,0 +1,439 @@
+// src/audio.rs
+//
+// Audio side of clip analysis: what the faces alone miss (shouting, laughter, a sudden silence).
+// - the first audio stream is decoded by ffmpeg (media.rs) to 48 kHz float PCM, mono or stereo, and measured
+//   here as it streams in: nothing is written to disk and a long clip isn't held in memory
+// - loudness per EBU R128 / ITU-R BS.1770: K-weighting, momentary loudness (400 ms blocks every 100 ms),
+//   integrated loudness (absolute -70 LUFS and relative -10 LU gates), loudness range (LRA, short-term 3 s
+//   values between the 10th and 95th percentiles) and sample peak; the curve is kept per TIMELINE_STEP_SECS
+// - segments: "silence" under SILENCE_LUFS, "speech" otherwise (energy based: music and noise count as speech),
+//   shorter runs than MIN_SEGMENT_SECS merged into their neighbour
+// - cues: runs at least LOUD_LU above the integrated loudness, the shouting / laughter moments
+// - `analyze_audio` job: registers the clip, stores the result (database audio_analyses, `get_audio_analysis`) and,
+//   unless placeMarkers is false, adds one "audio" marker per cue in one undoable batch. Merged with the visual
+//   timeline: the marker takes the dominant emotion of the deepface markers around the cue ("loud" without any)
+//   and lists them, so an emotion marker lands where the voice peaks instead of on the next sampled frame
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::analysis;
+use crate::database::{self, Clip, Marker, MarkerSource, NewMarker};
+use crate::error::AppError;
+use crate::media::{self, MediaInfo};
+use crate::payloads::AnalyzeAudioArgs;
+
+
+//____________Const___________
+pub const SAMPLE_RATE: u32 = 48_000;  // the rate BS.1770 specifies its filters at
+const STEP_SECS: f64 = 0.1;           // momentary loudness every 100 ms
+const BLOCK_STEPS: usize = 4;         // 400 ms momentary block
+const SHORT_TERM_STEPS: usize = 30;   // 3 s short-term window (LRA)
+const ABSOLUTE_GATE: f64 = -70.0;     // LUFS; also the floor of the reported curve (silence is -inf)
+const RELATIVE_GATE: f64 = -10.0;     // LU below the absolutely gated loudness, integrated loudness
+const LRA_RELATIVE_GATE: f64 = -20.0; // same, loudness range
+pub const SILENCE_LUFS: f64 = -50.0;  // momentary loudness under this is silence
+const MIN_SEGMENT_SECS: f64 = 0.5;
+pub const LOUD_LU: f64 = 8.0;         // a cue is this far above the integrated loudness
+const MIN_CUE_SECS: f64 = 0.3;
+pub const TIMELINE_STEP_SECS: f64 = 1.0;
+const PROGRESS_EVERY_SECS: f64 = 10.0; // of decoded audio between progress reports (and cancellation checks)
+const NEUTRAL: &str = "neutral";
+const LOUD_LABEL: &str = "loud";      // label of a cue without an emotional face around
+
+
+//_____________Struct _________________________
+/// Loudness and segments of a clip's audio, as stored and returned by `get_audio_analysis`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioAnalysis {
+    pub clip_id: i64,
+    pub duration: f64,                // seconds of audio measured
+    pub channels: u32,                // 1 or 2 (more are downmixed to stereo)
+    pub integrated_lufs: Option<f64>, // None when the whole clip is under the absolute gate
+    pub loudness_range: Option<f64>,  // LU
+    pub sample_peak_db: f64,          // dBFS
+    pub timeline: Vec<LoudnessPoint>, // loudest momentary value per TIMELINE_STEP_SECS
+    pub segments: Vec<AudioSegment>,
+    pub cues: Vec<AudioCue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoudnessPoint {
+    pub time: f64,
+    pub lufs: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioSegment {
+    pub start: f64,
+    pub end: f64,
+    pub kind: String, // "silence" | "speech"
+}
+
+/// A loud moment (shouting, laughter, applause…).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioCue {
+    pub start: f64,
+    pub end: f64,
+    pub peak_lufs: f64, // loudest momentary value
+    pub above_lu: f64,  // how far above the integrated loudness
+}
+
+// One biquad section, transposed direct form II
+#[derive(Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2], // a1, a2 (a0 = 1)
+    z: [f64; 2],
+}
+
+// BS.1770 K-weighting of one channel: high shelf, then high pass
+#[derive(Clone, Copy)]
+struct KWeighting {
+    shelf: Biquad,
+    high_pass: Biquad,
+}
+
+// Mean square of the K-weighted signal per STEP_SECS, channels summed
+struct Meter {
+    filters: Vec<KWeighting>,
+    frames_per_step: usize,
+    in_step: usize,
+    step_sum: f64,
+    steps: Vec<f64>,
+    peak: f32,
+}
+
+
+//_____________fn ____________________________
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Biquad { b, a, z: [0.0; 2] }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+impl KWeighting {
+    // Coefficients for any sample rate (as libebur128 derives them)
+    fn new(rate: f64) -> Self {
+        let (f0, gain, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+        let k = (std::f64::consts::PI * f0 / rate).tan();
+        let vh = 10f64.powf(gain / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad::new(
+            [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        let (f0, q) = (38.13547087602444, 0.5003270373238773);
+        let k = (std::f64::consts::PI * f0 / rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Biquad::new([1.0, -2.0, 1.0], [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0]);
+        KWeighting { shelf, high_pass }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        self.high_pass.process(self.shelf.process(x))
+    }
+}
+
+impl Meter {
+    fn new(channels: usize) -> Self {
+        Meter {
+            filters: vec![KWeighting::new(f64::from(SAMPLE_RATE)); channels],
+            frames_per_step: (f64::from(SAMPLE_RATE) * STEP_SECS) as usize,
+            in_step: 0,
+            step_sum: 0.0,
+            steps: Vec::new(),
+            peak: 0.0,
+        }
+    }
+
+    // One frame: a sample per channel
+    fn push(&mut self, frame: &[f32]) {
+        for (filter, sample) in self.filters.iter_mut().zip(frame) {
+            self.peak = self.peak.max(sample.abs());
+            let weighted = filter.process(f64::from(*sample));
+            self.step_sum += weighted * weighted;
+        }
+        self.in_step += 1;
+        if self.in_step == self.frames_per_step {
+            self.steps.push(self.step_sum / self.frames_per_step as f64);
+            self.in_step = 0;
+            self.step_sum = 0.0;
+        }
+    }
+
+    fn seconds(&self) -> f64 {
+        self.steps.len() as f64 * STEP_SECS
+    }
+}
+
+fn lufs(mean_square: f64) -> f64 {
+    -0.691 + 10.0 * mean_square.log10()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len().max(1) as f64
+}
+
+// Mean squares of the windows of `len` steps ending at each step (shorter at the start)
+fn windows(steps: &[f64], len: usize) -> Vec<f64> {
+    (0..steps.len()).map(|i| mean(&steps[(i + 1).saturating_sub(len)..=i])).collect()
+}
+
+// BS.1770 gating: blocks over the absolute gate, then over `relative` LU below their loudness
+fn gated(blocks: &[f64], relative: f64) -> Vec<f64> {
+    let loud: Vec<f64> = blocks.iter().copied().filter(|ms| lufs(*ms) > ABSOLUTE_GATE).collect();
+    if loud.is_empty() {
+        return loud;
+    }
+    let threshold = lufs(mean(&loud)) + relative;
+    loud.into_iter().filter(|ms| lufs(*ms) > threshold).collect()
+}
+
+fn integrated(momentary: &[f64]) -> Option<f64> {
+    let blocks = gated(momentary, RELATIVE_GATE);
+    (!blocks.is_empty()).then(|| lufs(mean(&blocks)))
+}
+
+fn loudness_range(short_term: &[f64]) -> Option<f64> {
+    let mut values: Vec<f64> = gated(short_term, LRA_RELATIVE_GATE).into_iter().map(lufs).collect();
+    if values.len() < 2 {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let at = |p: f64| values[((values.len() - 1) as f64 * p).round() as usize];
+    Some(at(0.95) - at(0.10))
+}
+
+// Runs of consecutive steps for which `kind` gives the same value: (first step, end step, value)
+fn runs<T: PartialEq + Copy>(len: usize, kind: impl Fn(usize) -> T) -> Vec<(usize, usize, T)> {
+    let mut runs: Vec<(usize, usize, T)> = Vec::new();
+    for i in 0..len {
+        let k = kind(i);
+        match runs.last_mut() {
+            Some(run) if run.2 == k => run.1 = i + 1,
+            _ => runs.push((i, i + 1, k)),
+        }
+    }
+    runs
+}
+
+fn segments(momentary_lufs: &[f64]) -> Vec<AudioSegment> {
+    let min_steps = (MIN_SEGMENT_SECS / STEP_SECS).round() as usize;
+    let mut merged: Vec<(usize, usize, bool)> = Vec::new();
+    for (start, end, silent) in runs(momentary_lufs.len(), |i| momentary_lufs[i] < SILENCE_LUFS) {
+        match merged.last_mut() {
+            // too short, or same kind as the previous one once short runs are absorbed: extend it
+            Some(last) if end - start < min_steps || last.2 == silent => last.1 = end,
+            _ => merged.push((start, end, silent)),
+        }
+    }
+    merged
+        .into_iter()
+        .map(|(start, end, silent)| AudioSegment {
+            start: start as f64 * STEP_SECS,
+            end: end as f64 * STEP_SECS,
+            kind: if silent { "silence" } else { "speech" }.to_string(),
+        })
+        .collect()
+}
+
+fn cues(momentary_lufs: &[f64], integrated: Option<f64>) -> Vec<AudioCue> {
+    let Some(integrated) = integrated else { return Vec::new() };
+    let threshold = (integrated + LOUD_LU).max(SILENCE_LUFS);
+    let min_steps = (MIN_CUE_SECS / STEP_SECS).round() as usize;
+    runs(momentary_lufs.len(), |i| momentary_lufs[i] >= threshold)
+        .into_iter()
+        .filter(|(start, end, loud)| *loud && end - start >= min_steps)
+        .map(|(start, end, _)| {
+            let peak = momentary_lufs[start..end].iter().copied().fold(f64::MIN, f64::max);
+            AudioCue {
+                // the block ending at `start` began BLOCK_STEPS earlier
+                start: start.saturating_sub(BLOCK_STEPS - 1) as f64 * STEP_SECS,
+                end: end as f64 * STEP_SECS,
+                peak_lufs: peak,
+                above_lu: peak - integrated,
+            }
+        })
+        .collect()
+}
+
+fn timeline(momentary_lufs: &[f64]) -> Vec<LoudnessPoint> {
+    let per_point = (TIMELINE_STEP_SECS / STEP_SECS).round() as usize;
+    momentary_lufs
+        .chunks(per_point.max(1))
+        .enumerate()
+        .map(|(i, chunk)| LoudnessPoint {
+            time: i as f64 * TIMELINE_STEP_SECS,
+            lufs: chunk.iter().copied().fold(ABSOLUTE_GATE, f64::max),
+        })
+        .collect()
+}
+
+/// Measure the first audio stream of `clip` (`info`: its probe). `progress` gets the fraction decoded; its error stops the run.
+pub async fn measure<P>(clip: &Clip, info: &MediaInfo, progress: &P) -> Result<AudioAnalysis, AppError>
+where
+    P: Fn(f64, &str) -> Result<(), AppError>,
+{
+    let stream = info.audio.first().ok_or_else(|| AppError::Media(format!("No audio stream in {}", clip.path)))?;
+    let channels = stream.channels.unwrap_or(2).clamp(1, 2);
+
+    let ffmpeg = media::ffmpeg_path();
+    #[rustfmt::skip]
+    let args = [
+        "-i", clip.path.as_str(), "-map", "0:a:0", "-vn",
+        "-ac", &channels.to_string(), "-ar", &SAMPLE_RATE.to_string(), "-f", "f32le", "-",
+    ];
+    debug!("Running {:?} {:?}", ffmpeg, args);
+    let mut child = Command::new(&ffmpeg)
+        .args(["-hide_banner", "-loglevel", "error", "-nostdin"])
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| AppError::Media(format!("Failed to run {:?} (is ffmpeg installed?): {}", ffmpeg, e)))?;
+    let mut stdout = child.stdout.take().ok_or_else(|| AppError::Media("ffmpeg has no output".into()))?;
+
+    let mut meter = Meter::new(channels as usize);
+    let frame_bytes = 4 * channels as usize;
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut pending: Vec<u8> = Vec::new();
+    let mut reported = 0.0;
+    loop {
+        let read = stdout.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        pending.extend_from_slice(&buffer[..read]);
+        let whole = pending.len() - pending.len() % frame_bytes;
+        let mut frame = [0f32; 2];
+        for bytes in pending[..whole].chunks_exact(frame_bytes) {
+            for (sample, le) in frame.iter_mut().zip(bytes.chunks_exact(4)) {
+                *sample = f32::from_le_bytes([le[0], le[1], le[2], le[3]]);
+            }
+            meter.push(&frame[..channels as usize]);
+        }
+        pending.drain(..whole);
+
+        if meter.seconds() - reported >= PROGRESS_EVERY_SECS {
+            reported = meter.seconds();
+            let fraction = info.duration.filter(|d| *d > 0.0).map(|d| (reported / d).min(1.0)).unwrap_or(0.0);
+            progress(0.05 + 0.85 * fraction, &format!("Measured {:.0}s of audio", reported))?;
+        }
+    }
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::Media(format!("ffmpeg failed ({}): {}", output.status, stderr.lines().last().unwrap_or("no output"))));
+    }
+
+    let momentary = windows(&meter.steps, BLOCK_STEPS);
+    let momentary_lufs: Vec<f64> = momentary.iter().map(|ms| lufs(*ms).max(ABSOLUTE_GATE)).collect();
+    let integrated = integrated(&momentary[BLOCK_STEPS.min(momentary.len()).saturating_sub(1)..]);
+    Ok(AudioAnalysis {
+        clip_id: clip.id,
+        duration: meter.seconds(),
+        channels,
+        integrated_lufs: integrated,
+        loudness_range: loudness_range(&windows(&meter.steps, SHORT_TERM_STEPS)[SHORT_TERM_STEPS.min(meter.steps.len()).saturating_sub(1)..]),
+        sample_peak_db: (20.0 * f64::from(meter.peak).log10()).max(-144.0),
+        timeline: timeline(&momentary_lufs),
+        segments: segments(&momentary_lufs),
+        cues: cues(&momentary_lufs, integrated),
+    })
+}
+
+// One "audio" marker per cue, labeled with the dominant emotion of the deepface markers around it.
+// Cues that already have one (an earlier run) are skipped.
+fn cue_markers(analysis: &AudioAnalysis, markers: &[Marker]) -> Vec<NewMarker> {
+    let around = analysis::ANALYZE_EVERY_SECS; // deepface markers this close to a cue belong to it
+    analysis
+        .cues
+        .iter()
+        .filter(|cue| !markers.iter().any(|m| m.source == MarkerSource::Audio && (m.timestamp - cue.start).abs() < STEP_SECS))
+        .map(|cue| {
+            let faces: Vec<&Marker> = markers
+                .iter()
+                .filter(|m| m.source == MarkerSource::Deepface && m.timestamp >= cue.start - around && m.timestamp <= cue.end + around)
+                .collect();
+            let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+            for label in faces.iter().filter_map(|m| m.label.as_deref()).filter(|l| *l != NEUTRAL) {
+                *counts.entry(label).or_default() += 1;
+            }
+            let label = counts.into_iter().max_by_key(|(_, count)| *count).map(|(label, _)| label).unwrap_or(LOUD_LABEL);
+            NewMarker {
+                clip_id: analysis.clip_id,
+                timestamp: cue.start,
+                duration: cue.end - cue.start,
+                name: Some(format!("Audio cue (+{:.1} LU)", cue.above_lu)),
+                label: Some(label.to_string()),
+                source: MarkerSource::Audio,
+                metadata: Some(json!({
+                    "peakLufs": cue.peak_lufs,
+                    "aboveLu": cue.above_lu,
+                    "faceMarkers": faces.iter().map(|m| m.id).collect::<Vec<_>>(),
+                })),
+                ..NewMarker::default()
+            }
+        })
+        .collect()
+}
+
+/// Register the clip (when it isn't yet), measure its audio, store the result and place the cue markers.
+pub async fn analyze<P>(args: &AnalyzeAudioArgs, progress: P) -> Result<(AudioAnalysis, Vec<Marker>), AppError>
+where
+    P: Fn(f64, &str) -> Result<(), AppError>,
+{
+    let info = media::probe(&args.path).await?;
+    let clip = database::add_clip(&args.path, args.fps.or(info.fps), args.project_id)?;
+    progress(0.0, "Decoding audio")?;
+    let analysis = measure(&clip, &info, &progress).await?;
+
+    progress(0.9, "Storing the analysis")?;
+    let stored = serde_json::to_value(&analysis)?;
+    database::save_audio_analysis(clip.id, &stored)?;
+    let placed = if args.place_markers.unwrap_or(true) {
+        let new = cue_markers(&analysis, &database::list_markers(clip.id)?);
+        database::add_markers(&new)?
+    } else {
+        Vec::new()
+    };
+    Ok((analysis, placed))
+}
+
+/// The stored audio analysis of a clip, if it has one.
+pub fn get(clip_id: i64) -> Result<Option<AudioAnalysis>, AppError> {
+    database::get_audio_analysis(clip_id)?
+        .map(|stored: Value| serde_json::from_value(stored).map_err(|e| AppError::Db(format!("Unreadable audio analysis of clip {}: {}", clip_id, e))))
+        .transpose()
+}
+
+
+//_____________Commands ________________________
+
+/// Example: `invoke("get_audio_analysis", { clipId: 3 })` -> `{ integratedLufs: -23.1, loudnessRange: 7.4, cues: [...], … }` or null
+#[tauri::command]
+pub async fn get_audio_analysis(clip_id: i64) -> Result<Option<AudioAnalysis>, AppError> {
+    database::blocking(move || get(clip_id)).await
+}
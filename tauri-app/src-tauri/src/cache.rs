@@ -0,0 +1,318 @@
+// src/cache.rs
+//
+// Disk used by what the app keeps to go faster, per category, each with a size limit (settings.cache, MB, 0 = none):
+// - thumbnails: clip thumbnails (thumbnails.rs), default limit THUMBNAILS_LIMIT_MB
+// - frames:     extracted frames, one directory per extraction (media.rs, extract_frames), default FRAMES_LIMIT_MB
+// - models:     deepface weights (models.rs) and whisper ggml models (whisper.rs), no limit by default:
+//               an evicted model is downloaded again on its next use
+// - an entry is a file or directory right under a category's directories; over the limit, the least recently
+//   used entries are removed first. Last use = newest modified / accessed time inside it; cache hits `touch`
+//   their file, since many systems don't update access times
+// - entries used in the last EVICT_GRACE (a running analysis, a model being loaded) and `.part` downloads are
+//   never evicted, they still count in the usage
+// - limits are enforced at startup, every CHECK_INTERVAL and soon after something was written (`changed`)
+// - `get_cache_usage()` for the settings screen, `clear_cache(category)` ("all" for every category) removes
+//   everything of a category, in use or not
+
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+use tauri::AppHandle;
+use tokio::sync::Notify;
+use tracing::{debug, info, warn};
+
+use crate::crash;
+use crate::error::AppError;
+use crate::settings::{self, CacheSettings};
+use crate::{media, models, thumbnails, whisper};
+
+
+//____________Const___________
+pub const THUMBNAILS_LIMIT_MB: u64 = 512;  // default of settings.cache thumbnailsMb
+pub const FRAMES_LIMIT_MB: u64 = 2048;     // default of settings.cache framesMb
+pub const MODELS_LIMIT_MB: u64 = 0;        // default of settings.cache modelsMb (no limit)
+const ALL: &str = "all";                   // clear_cache every category
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const EVICT_GRACE: Duration = Duration::from_secs(5 * 60);
+const TOUCH_INTERVAL: Duration = Duration::from_secs(60 * 60); // a cache hit rewrites the mtime at most this often
+const PARTIAL_EXTENSION: &str = "part";
+const MB: u64 = 1024 * 1024;
+
+
+//_____________Struct _________________________
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheCategory {
+    Thumbnails,
+    Frames,
+    Models,
+}
+
+/// Disk use of one category, as returned by `get_cache_usage`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryUsage {
+    pub category: CacheCategory,
+    pub bytes: u64,
+    pub files: u64,
+    pub entries: usize,           // files / extraction directories eviction works on
+    pub limit_bytes: Option<u64>, // None = no limit
+    pub dirs: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheUsage {
+    pub bytes: u64,
+    pub categories: Vec<CategoryUsage>,
+}
+
+/// What `clear_cache` or an eviction removed from a category.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheCleared {
+    pub category: CacheCategory,
+    pub entries: usize,
+    pub bytes: u64,
+}
+
+// A file or directory right under a category directory
+struct Entry {
+    path: PathBuf,
+    bytes: u64,
+    files: u64,
+    last_used: SystemTime,
+    partial: bool,
+}
+
+
+//_____________Globals _______________________
+static STARTED: OnceCell<()> = OnceCell::new();
+static CHANGED: Lazy<Notify> = Lazy::new(Notify::new);
+
+
+//_____________fn ____________________________
+
+impl CacheCategory {
+    pub const ALL: &'static [CacheCategory] = &[CacheCategory::Thumbnails, CacheCategory::Frames, CacheCategory::Models];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CacheCategory::Thumbnails => "thumbnails",
+            CacheCategory::Frames => "frames",
+            CacheCategory::Models => "models",
+        }
+    }
+
+    fn limit_bytes(self, config: &CacheSettings) -> Option<u64> {
+        let mb = match self {
+            CacheCategory::Thumbnails => config.thumbnails_mb,
+            CacheCategory::Frames => config.frames_mb,
+            CacheCategory::Models => config.models_mb,
+        };
+        (mb > 0).then_some(mb * MB)
+    }
+
+    fn dirs(self, app_handle: &AppHandle) -> Result<Vec<PathBuf>, AppError> {
+        Ok(match self {
+            CacheCategory::Thumbnails => vec![thumbnails::thumbnails_dir(app_handle)?],
+            CacheCategory::Frames => vec![media::frames_dir(app_handle)?],
+            CacheCategory::Models => vec![models::weights_dir()?, whisper::model_dir(app_handle)?],
+        })
+    }
+}
+
+impl FromStr for CacheCategory {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CacheCategory::ALL.iter().copied().find(|c| c.as_str().eq_ignore_ascii_case(s.trim())).ok_or_else(|| {
+            AppError::InvalidInput(format!("Unknown cache category '{}' (expected thumbnails, frames, models or {})", s, ALL))
+        })
+    }
+}
+
+fn last_used(meta: &std::fs::Metadata) -> SystemTime {
+    let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+    meta.accessed().map_or(modified, |accessed| accessed.max(modified))
+}
+
+// Size, file count and newest use of `path`, a file or a whole directory
+fn measure(path: &Path, meta: &std::fs::Metadata) -> (u64, u64, SystemTime) {
+    if !meta.is_dir() {
+        return (meta.len(), 1, last_used(meta));
+    }
+    let mut total = (0, 0, last_used(meta));
+    for child in std::fs::read_dir(path).into_iter().flatten().flatten() {
+        if let Ok(child_meta) = child.metadata() {
+            let (bytes, files, used) = measure(&child.path(), &child_meta);
+            total = (total.0 + bytes, total.1 + files, total.2.max(used));
+        }
+    }
+    total
+}
+
+fn entries(dirs: &[PathBuf]) -> Vec<Entry> {
+    let mut found = Vec::new();
+    for dir in dirs {
+        let Ok(children) = std::fs::read_dir(dir) else { continue }; // not created yet: nothing cached
+        for child in children.flatten() {
+            let path = child.path();
+            let Ok(meta) = child.metadata() else { continue };
+            let (bytes, files, last_used) = measure(&path, &meta);
+            let partial = path.extension().is_some_and(|ext| ext == PARTIAL_EXTENSION);
+            found.push(Entry { path, bytes, files, last_used, partial });
+        }
+    }
+    found
+}
+
+fn remove(entry: &Entry) -> bool {
+    let removed = if entry.path.is_dir() { std::fs::remove_dir_all(&entry.path) } else { std::fs::remove_file(&entry.path) };
+    match removed {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("⚠️ Could not remove cached {:?}: {}", entry.path, e); // open by another process (Windows)
+            false
+        }
+    }
+}
+
+/// Disk use of every category.
+pub fn usage(app_handle: &AppHandle) -> Result<CacheUsage, AppError> {
+    let config = settings::get().cache;
+    let mut categories = Vec::new();
+    for category in CacheCategory::ALL {
+        let dirs = category.dirs(app_handle)?;
+        let found = entries(&dirs);
+        categories.push(CategoryUsage {
+            category: *category,
+            bytes: found.iter().map(|e| e.bytes).sum(),
+            files: found.iter().map(|e| e.files).sum(),
+            entries: found.len(),
+            limit_bytes: category.limit_bytes(&config),
+            dirs,
+        });
+    }
+    Ok(CacheUsage { bytes: categories.iter().map(|c| c.bytes).sum(), categories })
+}
+
+/// Remove the least recently used entries of the categories over their limit.
+pub fn enforce(app_handle: &AppHandle) -> Result<Vec<CacheCleared>, AppError> {
+    let config = settings::get().cache;
+    let recent = SystemTime::now() - EVICT_GRACE;
+    let mut cleared = Vec::new();
+    for category in CacheCategory::ALL {
+        let Some(limit) = category.limit_bytes(&config) else { continue };
+        let mut found = entries(&category.dirs(app_handle)?);
+        let mut total: u64 = found.iter().map(|e| e.bytes).sum();
+        if total <= limit {
+            continue;
+        }
+        found.sort_by_key(|e| e.last_used);
+        let mut evicted = CacheCleared { category: *category, entries: 0, bytes: 0 };
+        for entry in found.iter().filter(|e| !e.partial && e.last_used < recent) {
+            if total <= limit {
+                break;
+            }
+            if remove(entry) {
+                total -= entry.bytes;
+                evicted.entries += 1;
+                evicted.bytes += entry.bytes;
+            }
+        }
+        if evicted.entries > 0 {
+            info!("🧹 Cache {}: {} entries evicted ({} MB), {} MB left of {} MB", category.as_str(), evicted.entries, evicted.bytes / MB, total / MB, limit / MB);
+            cleared.push(evicted);
+        }
+    }
+    Ok(cleared)
+}
+
+/// Remove everything of `category` (a category name or "all").
+pub fn clear(app_handle: &AppHandle, category: &str) -> Result<Vec<CacheCleared>, AppError> {
+    let categories = match category.trim() {
+        c if c.eq_ignore_ascii_case(ALL) => CacheCategory::ALL.to_vec(),
+        c => vec![CacheCategory::from_str(c)?],
+    };
+    let mut cleared = Vec::new();
+    for category in categories {
+        let mut removed = CacheCleared { category, entries: 0, bytes: 0 };
+        for entry in entries(&category.dirs(app_handle)?) {
+            if remove(&entry) {
+                removed.entries += 1;
+                removed.bytes += entry.bytes;
+            }
+        }
+        info!("🧹 Cache {} cleared: {} entries ({} MB)", category.as_str(), removed.entries, removed.bytes / MB);
+        cleared.push(removed);
+    }
+    Ok(cleared)
+}
+
+/// Record a cache hit on `path` (LRU), without rewriting the file's time on every hit.
+pub fn touch(path: &Path) {
+    let stale = std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .is_ok_and(|modified| modified.elapsed().unwrap_or_default() > TOUCH_INTERVAL);
+    if stale {
+        let touched = std::fs::File::options().write(true).open(path).and_then(|file| file.set_modified(SystemTime::now()));
+        if let Err(e) = touched {
+            debug!("Could not touch cached {:?}: {}", path, e); // open elsewhere: it is in use anyway
+        }
+    }
+}
+
+/// Something was written to a cache (or the limits changed): check the limits now instead of at the next interval.
+pub fn changed() {
+    CHANGED.notify_one();
+}
+
+// Run `f` off the async runtime: it walks directories
+async fn blocking<T, F>(f: F) -> Result<T, AppError>
+where
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+    T: Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::Io(format!("Cache task failed: {}", e)))?
+}
+
+/// Enforce the limits now, every CHECK_INTERVAL and after `changed`. Call once from setup.
+pub fn start(app_handle: AppHandle) {
+    if STARTED.set(()).is_err() {
+        warn!("❌ Cache manager already started");
+        return;
+    }
+    crash::spawn("cache", async move {
+        loop {
+            let handle = app_handle.clone();
+            if let Err(e) = blocking(move || enforce(&handle)).await {
+                warn!("⚠️ Cache limits not enforced: {}", e);
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+                _ = CHANGED.notified() => {}
+            }
+        }
+    });
+}
+
+
+//_____________Commands ________________________
+
+/// Example: `invoke("get_cache_usage")` ->
+/// `{ bytes, categories: [{ category: "thumbnails", bytes: 10485760, files: 812, entries: 812, limitBytes: 536870912, dirs: ["…"] }, …] }`
+#[tauri::command]
+pub async fn get_cache_usage(app_handle: AppHandle) -> Result<CacheUsage, AppError> {
+    blocking(move || usage(&app_handle)).await
+}
+
+/// Example: `invoke("clear_cache", { category: "frames" })` -> `[{ category: "frames", entries: 12, bytes: 48234496 }]`
+#[tauri::command]
+pub async fn clear_cache(app_handle: AppHandle, category: String) -> Result<Vec<CacheCleared>, AppError> {
+    blocking(move || clear(&app_handle, &category)).await
+}
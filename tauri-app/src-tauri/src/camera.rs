@@ -0,0 +1,350 @@
+// src/camera.rs
+//
+// Live emotion preview from a webcam (live_camera feature), captured by the same ffmpeg binary as media.rs:
+// dshow on Windows, avfoundation on macOS, v4l2 on Linux.
+// - `list_cameras()` for the device picker, `start_camera(device?, fps?)` runs ffmpeg writing MJPEG frames
+//   to its stdout at settings.camera fps, `stop_camera()` kills it
+// - only the newest frame is analyzed (emotion, deepface sidecar): frames arriving while the sidecar is
+//   busy are dropped, so the preview never lags behind the camera
+// - each result is emitted as CAMERA_EMOTIONS_EVENT, start / stop / failure as CAMERA_STATE_EVENT;
+//   `fetch_deepFaceCameraEmotionList` returns the dominant emotions of the last analyzed frame
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tauri::async_runtime::JoinHandle;
+use tauri::AppHandle;
+use tokio::io::AsyncReadExt;
+use tokio::process::{Child, Command};
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+
+use crate::error::AppError;
+use crate::{analysis, crash, deepFaceProcess, events, features, media, settings};
+
+
+//____________Const___________
+pub const CAMERA_FPS: f64 = 2.0;
+pub const MAX_CAMERA_FPS: f64 = 10.0;
+pub const CAMERA_MAX_WIDTH: u32 = 640;
+pub const CAMERA_EMOTIONS_EVENT: &str = "camera-emotions"; // CameraEmotions of each analyzed frame
+pub const CAMERA_STATE_EVENT: &str = "camera-state";       // CameraStatus on start / stop / failure
+const READ_CHUNK: usize = 64 * 1024;
+const MAX_FRAME_BYTES: usize = 8 * 1024 * 1024; // buffered bytes without a complete JPEG are dropped past this
+
+
+//_____________Struct _________________________
+#[derive(Debug, Clone, Serialize)]
+pub struct CameraDevice {
+    pub id: String, // what start_camera / settings.camera device take
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CameraFace {
+    pub dominant_emotion: Option<String>,
+    pub emotion: Value, // score per emotion
+    pub region: Value,  // { x, y, w, h } in the analyzed frame
+    pub confidence: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CameraEmotions {
+    pub frame: u64, // analyzed frames since start
+    pub captured_at: String,
+    pub faces: Vec<CameraFace>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CameraStatus {
+    pub running: bool,
+    pub device: Option<String>,
+    pub fps: Option<f64>,
+    pub error: Option<String>, // why the last capture stopped on its own
+}
+
+struct Running {
+    id: u64,
+    device: String,
+    fps: f64,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+type FrameSender = watch::Sender<Option<Arc<Vec<u8>>>>;
+type FrameReceiver = watch::Receiver<Option<Arc<Vec<u8>>>>;
+
+
+//_____________Globals _______________________
+static CAMERA: Lazy<Mutex<Option<Running>>> = Lazy::new(|| Mutex::new(None));
+static LAST: Lazy<Mutex<Option<CameraEmotions>>> = Lazy::new(|| Mutex::new(None));
+static NEXT_RUN: AtomicU64 = AtomicU64::new(1);
+
+
+//_____________fn ____________________________
+fn camera() -> MutexGuard<'static, Option<Running>> {
+    CAMERA.lock().unwrap_or_else(|p| p.into_inner())
+}
+
+fn last() -> MutexGuard<'static, Option<CameraEmotions>> {
+    LAST.lock().unwrap_or_else(|p| p.into_inner())
+}
+
+// ffmpeg input of a device id on this platform
+fn input_args(device: &str) -> Vec<String> {
+    if cfg!(windows) {
+        vec!["-f".into(), "dshow".into(), "-i".into(), format!("video={}", device)]
+    } else if cfg!(target_os = "macos") {
+        // avfoundation refuses the default 29.97 on most cameras
+        vec!["-f".into(), "avfoundation".into(), "-framerate".into(), "30".into(), "-i".into(), device.to_string()]
+    } else {
+        vec!["-f".into(), "v4l2".into(), "-i".into(), device.to_string()]
+    }
+}
+
+// Device listings are written to stderr and ffmpeg exits with an error (there is no output)
+async fn ffmpeg_stderr(args: &[&str]) -> Result<String, AppError> {
+    let ffmpeg = media::ffmpeg_path();
+    let output = Command::new(&ffmpeg)
+        .args(["-hide_banner", "-nostdin"])
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| AppError::Media(format!("Failed to run {:?} (is ffmpeg installed?): {}", ffmpeg, e)))?;
+    Ok(String::from_utf8_lossy(&output.stderr).into_owned())
+}
+
+/// Cameras of this machine.
+pub async fn list() -> Result<Vec<CameraDevice>, AppError> {
+    if cfg!(windows) {
+        // [dshow @ 0000…] "Integrated Camera" (video)
+        let listing = ffmpeg_stderr(&["-list_devices", "true", "-f", "dshow", "-i", "dummy"]).await?;
+        Ok(listing
+            .lines()
+            .filter(|l| l.contains("(video)"))
+            .filter_map(|l| l.split('"').nth(1))
+            .map(|name| CameraDevice { id: name.to_string(), name: name.to_string() })
+            .collect())
+    } else if cfg!(target_os = "macos") {
+        // [AVFoundation indev @ 0x…] [0] FaceTime HD Camera, video devices listed before the audio ones
+        let listing = ffmpeg_stderr(&["-f", "avfoundation", "-list_devices", "true", "-i", ""]).await?;
+        let video = listing.split("audio devices").next().unwrap_or_default();
+        Ok(video
+            .lines()
+            .filter_map(|l| l.rsplit_once("] [")?.1.split_once("] "))
+            .filter(|(_, name)| !name.starts_with("Capture screen"))
+            .map(|(index, name)| CameraDevice { id: index.to_string(), name: name.trim().to_string() })
+            .collect())
+    } else {
+        let mut devices: Vec<CameraDevice> = std::fs::read_dir("/dev")?
+            .filter_map(|e| e.ok()?.file_name().into_string().ok())
+            .filter(|node| node.starts_with("video"))
+            .map(|node| {
+                let name = std::fs::read_to_string(format!("/sys/class/video4linux/{}/name", node))
+                    .map(|n| n.trim().to_string())
+                    .unwrap_or_else(|_| node.clone());
+                CameraDevice { id: format!("/dev/{}", node), name }
+            })
+            .collect();
+        devices.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(devices)
+    }
+}
+
+// The first complete JPEG (start to end-of-image marker) in `buffer`, removed from it.
+// ffmpeg's mjpeg frames have no embedded thumbnail, so the first FFD9 after FFD8 ends the frame.
+fn next_frame(buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
+    let start = buffer.windows(2).position(|w| w == [0xFF, 0xD8])?;
+    let end = start + 2 + buffer[start + 2..].windows(2).position(|w| w == [0xFF, 0xD9])? + 2;
+    let frame = buffer[start..end].to_vec();
+    buffer.drain(..end);
+    Some(frame)
+}
+
+// Read frames from ffmpeg until it exits (camera unplugged, device busy, …)
+async fn capture(mut child: Child, frames: FrameSender) -> AppError {
+    let Some(mut stdout) = child.stdout.take() else {
+        return AppError::Media("ffmpeg stdout not captured".into());
+    };
+    let mut buffer = Vec::new();
+    let mut chunk = vec![0u8; READ_CHUNK];
+    loop {
+        match stdout.read(&mut chunk).await {
+            Ok(0) => break,
+            Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+            Err(e) => return AppError::Media(format!("Camera stream unreadable: {}", e)),
+        }
+        while let Some(frame) = next_frame(&mut buffer) {
+            frames.send_replace(Some(Arc::new(frame)));
+        }
+        if buffer.len() > MAX_FRAME_BYTES {
+            buffer.clear();
+        }
+    }
+
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        let _ = pipe.read_to_string(&mut stderr).await;
+    }
+    let status = child.wait().await.map(|s| s.to_string()).unwrap_or_else(|e| e.to_string());
+    AppError::Media(format!("Camera capture stopped ({}): {}", status, stderr.lines().last().unwrap_or("no output")))
+}
+
+fn faces(result: &Value) -> Vec<CameraFace> {
+    result
+        .as_array()
+        .into_iter()
+        .flatten()
+        // Detection isn't enforced: a frame without a face comes back as one face with confidence 0
+        .filter(|face| face["face_confidence"].as_f64().unwrap_or(1.0) > 0.0)
+        .map(|face| CameraFace {
+            dominant_emotion: face["dominant_emotion"].as_str().map(String::from),
+            emotion: face["emotion"].clone(),
+            region: face["region"].clone(),
+            confidence: face["face_confidence"].as_f64(),
+        })
+        .collect()
+}
+
+// Analyze the newest frame each time the sidecar is free; ends with the capture
+async fn analyze(app_handle: AppHandle, mut frames: FrameReceiver) {
+    let mut count = 0;
+    while frames.changed().await.is_ok() {
+        let Some(jpeg) = frames.borrow_and_update().clone() else { continue };
+        match deepFaceProcess::analyze_frame(&jpeg, Some("emotion".into()), None, None).await {
+            Ok(answer) if answer["status"] == "ok" => {
+                count += 1;
+                let emotions = CameraEmotions {
+                    frame: count,
+                    captured_at: chrono::Utc::now().to_rfc3339(),
+                    faces: faces(&answer["data"]["result"]),
+                };
+                *last() = Some(emotions.clone());
+                events::emit_all_surfaces(&app_handle, CAMERA_EMOTIONS_EVENT, emotions);
+            }
+            Ok(answer) => debug!("Camera frame not analyzed: {}", answer["data"]["message"]),
+            Err(e) => warn!("⚠️ Camera frame not analyzed: {}", e),
+        }
+    }
+}
+
+pub fn status() -> CameraStatus {
+    match camera().as_ref() {
+        Some(run) => CameraStatus { running: true, device: Some(run.device.clone()), fps: Some(run.fps), error: None },
+        None => CameraStatus::default(),
+    }
+}
+
+/// Dominant emotion of each face in the last analyzed frame.
+pub fn last_emotions() -> Vec<String> {
+    last().as_ref().map(|e| e.faces.iter().filter_map(|f| f.dominant_emotion.clone()).collect()).unwrap_or_default()
+}
+
+/// Start the preview (restarting it when it runs); device and fps default to settings.camera.
+pub async fn start(app_handle: &AppHandle, device: Option<String>, fps: Option<f64>) -> Result<CameraStatus, AppError> {
+    if !features::is_enabled(features::LIVE_CAMERA) {
+        return Err(AppError::FeatureNotLicensed(features::LIVE_CAMERA.to_string()));
+    }
+    let config = settings::get().camera;
+    let fps = fps.unwrap_or(config.fps);
+    if fps <= 0.0 || fps > MAX_CAMERA_FPS {
+        return Err(AppError::InvalidInput(format!("fps must be above 0 and at most {}", MAX_CAMERA_FPS)));
+    }
+    let device = match device.or(config.device) {
+        Some(device) => device,
+        None => list().await?.into_iter().next().map(|d| d.id).ok_or_else(|| AppError::Media("No camera found".into()))?,
+    };
+    analysis::ensure_deepface(None).await?;
+    stop(app_handle);
+
+    let mut args = input_args(&device);
+    args.extend([
+        "-vf".to_string(),
+        format!("fps={},scale='min({},iw)':-2", fps, config.max_width),
+        "-q:v".to_string(),
+        settings::get().media.jpeg_quality.to_string(),
+    ]);
+    args.extend(["-f", "image2pipe", "-vcodec", "mjpeg", "pipe:1"].map(String::from));
+    let ffmpeg = media::ffmpeg_path();
+    debug!("Running {:?} {:?}", ffmpeg, args);
+    let child = Command::new(&ffmpeg)
+        .args(["-hide_banner", "-loglevel", "error", "-nostdin"])
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true) // aborting the capture task kills it
+        .spawn()
+        .map_err(|e| AppError::Media(format!("Failed to run {:?} (is ffmpeg installed?): {}", ffmpeg, e)))?;
+
+    let id = NEXT_RUN.fetch_add(1, Ordering::Relaxed);
+    let (frames, receiver) = watch::channel(None);
+    // Held while spawning: a capture that fails at once still finds its run registered
+    let mut current = camera();
+    let handle = app_handle.clone();
+    let capture_task = crash::spawn("camera-capture", async move {
+        let error = capture(child, frames).await;
+        // Still the current run: the camera stopped on its own
+        let mut current = camera();
+        if current.as_ref().is_some_and(|run| run.id == id) {
+            *current = None;
+            drop(current);
+            warn!("⚠️ {}", error);
+            events::emit_all_surfaces(&handle, CAMERA_STATE_EVENT, CameraStatus { error: Some(error.to_string()), ..CameraStatus::default() });
+        }
+    });
+    let analyze_task = crash::spawn("camera-analyze", analyze(app_handle.clone(), receiver));
+
+    *current = Some(Running { id, device: device.clone(), fps, tasks: vec![capture_task, analyze_task] });
+    drop(current);
+    *last() = None;
+    info!("📷 Camera preview started ({}, {} fps)", device, fps);
+    let status = status();
+    events::emit_all_surfaces(app_handle, CAMERA_STATE_EVENT, status.clone());
+    Ok(status)
+}
+
+/// Stop the preview; nothing happens when it isn't running.
+pub fn stop(app_handle: &AppHandle) -> CameraStatus {
+    if let Some(run) = camera().take() {
+        for task in run.tasks {
+            task.abort();
+        }
+        info!("📷 Camera preview stopped ({})", run.device);
+        events::emit_all_surfaces(app_handle, CAMERA_STATE_EVENT, CameraStatus::default());
+    }
+    CameraStatus::default()
+}
+
+
+//_____________Commands ________________________
+
+#[tauri::command]
+pub async fn list_cameras() -> Result<Vec<CameraDevice>, AppError> {
+    list().await
+}
+
+/// Example: `invoke("start_camera", { device: "Integrated Camera", fps: 2 })`, then listen to camera-emotions.
+#[tauri::command]
+pub async fn start_camera(app_handle: AppHandle, device: Option<String>, fps: Option<f64>) -> Result<CameraStatus, AppError> {
+    start(&app_handle, device, fps).await
+}
+
+#[tauri::command]
+pub fn stop_camera(app_handle: AppHandle) -> CameraStatus {
+    stop(&app_handle)
+}
+
+#[tauri::command]
+pub fn get_camera_status() -> CameraStatus {
+    status()
+}
@@ -0,0 +1,154 @@
+// src/cli.rs
+//
+// Headless batch mode for scripted (overnight) runs: `tauri-app --analyze <clip> --out markers.csv`
+// analyzes one clip and exports its markers without opening a window, then exits (0 = ok, 1 = failed, 2 = usage).
+// - the command line is read in main.rs: without --analyze the app starts as usual
+// - same settings, database and log files as the app, and the same pipeline as the analyze_clip job
+//   (analysis.rs); the clip goes into the current project unless --project is given
+// - needs the batch_analyze feature like the analyze_clip job: the license (or trial) is checked once before
+//   analyzing, offline within the grace period of the last signed validation
+// - can run while the app is open: it starts its own deepface sidecar on a free port, never binds the
+//   WS port and skips the single-instance check
+// - Windows release builds have no console: check the exit code and the log file
+
+use std::path::Path;
+use std::str::FromStr;
+use tauri::AppHandle;
+use tracing::{error, info, warn};
+
+use crate::analysis::{self, Sampling};
+use crate::database;
+use crate::deepFaceProcess::MANAGER;
+use crate::error::AppError;
+use crate::features;
+use crate::license;
+use crate::marker_export::{self, ExportFormat};
+use crate::payloads::AnalyzeClipArgs;
+
+
+//____________Const___________
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_FAILED: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+
+pub const USAGE: &str = "Usage: tauri-app --analyze <clip> --out <markers file> [options]
+  --format csv|edl|xml   export format (default: from the --out extension)
+  --sampling <mode>      scenes (one frame per shot, the default) or interval
+  --every <secs>         interval: seconds between frames (default 2); scenes: longest gap in a shot (default 10)
+  --project <id>         project of the clip (default: the current one)
+  --detector <name>      deepface detector backend
+  --model <name>         deepface model";
+
+
+//_____________Struct _________________________
+/// A parsed headless run.
+#[derive(Debug)]
+pub struct Batch {
+    analyze: AnalyzeClipArgs,
+    out: String,
+    format: ExportFormat,
+}
+
+
+//_____________fn ____________________________
+
+fn number<T: FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("{}: '{}' is not a valid number", flag, value))
+}
+
+/// The headless run asked for by `args` (without the executable), or None for a normal start.
+pub fn parse(args: &[String]) -> Option<Result<Batch, String>> {
+    if !args.iter().any(|a| a == "--analyze") {
+        return None;
+    }
+    Some(parse_batch(args))
+}
+
+fn parse_batch(args: &[String]) -> Result<Batch, String> {
+    let mut analyze = AnalyzeClipArgs {
+        path: String::new(),
+        sampling: None,
+        every_secs: None,
+        scene_threshold: None,
+        project_id: None,
+        fps: None,
+        max_width: None,
+        detector: None,
+        model: None,
+    };
+    let (mut out, mut format) = (None, None);
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("{} needs a value", flag))?;
+        match flag.as_str() {
+            "--analyze" => analyze.path = value.clone(),
+            "--out" => out = Some(value.clone()),
+            "--format" => format = Some(ExportFormat::from_str(value).map_err(|e| e.to_string())?),
+            "--sampling" => analyze.sampling = Some(Sampling::from_str(value).map_err(|e| e.to_string())?),
+            "--every" => analyze.every_secs = Some(number(flag, value)?),
+            "--project" => analyze.project_id = Some(number(flag, value)?),
+            "--detector" => analyze.detector = Some(value.clone()),
+            "--model" => analyze.model = Some(value.clone()),
+            other => return Err(format!("Unknown option '{}'", other)),
+        }
+    }
+
+    let out = out.ok_or("--out is required")?;
+    let format = match format {
+        Some(format) => format,
+        None => {
+            let extension = Path::new(&out).extension().and_then(|e| e.to_str()).unwrap_or_default();
+            ExportFormat::from_str(extension).map_err(|_| format!("Can't tell the format of '{}': pass --format", out))?
+        }
+    };
+    Ok(Batch { analyze, out, format })
+}
+
+// A port nobody listens on, for our own sidecar (the app's one may be running)
+fn free_port() -> Result<u16, AppError> {
+    Ok(std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?.port())
+}
+
+/// Analyze and export; returns the process exit code.
+pub async fn run(app_handle: AppHandle, batch: Batch) -> i32 {
+    info!("🎬 Headless analysis of {} -> {}", batch.analyze.path, batch.out);
+    let result = async {
+        if let Err(e) = license::check_once(&app_handle).await {
+            warn!("❌ License check: {}", e); // offline grace may still leave the feature on
+        }
+        if !features::is_enabled(features::BATCH_ANALYZE) {
+            return Err(AppError::FeatureNotLicensed(features::BATCH_ANALYZE.to_string()));
+        }
+        analysis::ensure_deepface(Some(free_port()?)).await?;
+        let analysis = analysis::analyze_clip(&app_handle, &batch.analyze, |fraction, message| {
+            info!("⏳ {:>3.0}% {}", fraction * 100.0, message);
+            Ok(())
+        })
+        .await?;
+        let (clip_id, format, out) = (analysis.clip.id, batch.format.extension().to_string(), batch.out.clone());
+        let exported = database::blocking(move || marker_export::export_markers(clip_id, format, out)).await?;
+        Ok::<_, AppError>((analysis, exported))
+    }
+    .await;
+    let _ = MANAGER.stop().await;
+
+    match result {
+        Ok((analysis, exported)) => {
+            info!(
+                "✅ {} markers ({} people) found in {} frames, {} markers of clip {} exported to {}",
+                analysis.markers.len(),
+                analysis.tracks,
+                analysis.frames,
+                exported,
+                analysis.clip.id,
+                batch.out
+            );
+            EXIT_OK
+        }
+        Err(e) => {
+            error!("❌ Headless analysis failed: {}", e);
+            EXIT_FAILED
+        }
+    }
+}
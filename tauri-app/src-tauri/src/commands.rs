@@ -1,6 +1,23 @@
 //! Make sure commands are public
 //TODO: pub might be too exposed, keep frontend commands here only
 
+use tracing::info;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::database::{self, Analysis, BackupInfo, Clip, DatabaseStatus, Marker, MarkerFilter, MarkerPage, MarkerSearch, MarkerSort, MarkerUpdate, NewMarker, OperationChange, Project};
+use crate::error::AppError;
+use crate::events;
+
+/// `open_project` result: the project and its clips.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenedProject {
+    pub project: Project,
+    pub clips: Vec<Clip>,
+}
+
 // ----------------- Commands -----------------
 
 // This is a Tauri command callable from JS (frontend).
@@ -13,7 +30,156 @@ pub fn greet(name: &str) -> String {
 
 //_________CEP____________
 
+/// Register a clip by path (idempotent) in `project_id`, the current project by default.
+/// `fps` defaults to database::DEFAULT_FPS.
+#[tauri::command]
+pub async fn add_clip(path: String, fps: Option<f64>, project_id: Option<i64>) -> Result<Clip, AppError> {
+    info!("🟢 add_clip called with path: {}", path);
+    database::blocking(move || database::add_clip(&path, fps, project_id)).await
+}
+
+/// Add a marker, returns it with its id.
+/// Example: `invoke("add_marker", { marker: { clipId: 1, timestamp: 12.5, name: "Laugh", color: "yellow" } })`
+/// A marker made from an analyze result can keep it: `marker: { …, analysis: { model, detector, result } }` (get_analysis).
+#[tauri::command]
+pub async fn add_marker(marker: NewMarker) -> Result<Marker, AppError> {
+    info!("🟢 add_marker called for clip {} at timestamp: {}", marker.clip_id, marker.timestamp);
+    let marker = database::blocking(move || database::add_marker(&marker)).await?;
+    events::publish(events::MARKER_ADDED, &marker);
+    Ok(marker)
+}
+
+/// Markers of a clip, a page at a time; `total` counts every marker matching the filter.
+/// Example: `invoke("list_markers", { clipId: 1, filter: { emotion: "angry", from: 60 }, sort: "duration", offset: 0, limit: 100 })`
+#[tauri::command]
+pub async fn list_markers(
+    clip_id: i64,
+    filter: Option<MarkerFilter>,
+    sort: Option<MarkerSort>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<MarkerPage, AppError> {
+    database::blocking(move || database::query_markers(clip_id, &filter.unwrap_or_default(), sort.unwrap_or_default(), offset, limit))
+        .await
+}
+
+/// Full-text search in the markers of a project (current one by default), ranked, one page at a time.
+/// Example: `invoke("search_markers", { query: "label:angry interview", offset: 0, limit: 50 })`
+#[tauri::command]
+pub async fn search_markers(
+    query: String,
+    project_id: Option<i64>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<MarkerSearch, AppError> {
+    database::blocking(move || database::search_markers(&query, project_id, offset, limit)).await
+}
+
+/// Change some fields of a marker, the others are kept.
+/// Example: `invoke("update_marker", { markerId: 3, changes: { name: "Big laugh", color: "green" } })`
+#[tauri::command]
+pub async fn update_marker(marker_id: i64, changes: MarkerUpdate) -> Result<Marker, AppError> {
+    info!("🟢 update_marker called for marker {}", marker_id);
+    database::blocking(move || database::update_marker(marker_id, &changes)).await
+}
+
+/// Returns false when no marker had this id.
+#[tauri::command]
+pub async fn delete_marker(marker_id: i64) -> Result<bool, AppError> {
+    database::blocking(move || database::delete_marker(marker_id)).await
+}
+
+/// Detailed deepface scores behind a marker, without running the analysis again; null for a marker without one.
+#[tauri::command]
+pub async fn get_analysis(marker_id: i64) -> Result<Option<Analysis>, AppError> {
+    database::blocking(move || database::get_analysis(marker_id)).await
+}
+
+// Both surfaces get a `markers-changed` event, so the webview and the CEP panel redraw the same thing
+fn history_changed(app_handle: &AppHandle, change: Option<OperationChange>) -> Option<OperationChange> {
+    if let Some(change) = &change {
+        events::emit_all_surfaces(app_handle, events::MARKERS_CHANGED, change.clone());
+    }
+    change
+}
+
+/// Revert the last clip / marker change of a project; null when there is nothing to undo.
+#[tauri::command]
+pub async fn undo_last(app_handle: AppHandle, project_id: i64) -> Result<Option<OperationChange>, AppError> {
+    info!("🟢 undo_last called for project {}", project_id);
+    let change = database::blocking(move || database::undo_last(project_id)).await?;
+    Ok(history_changed(&app_handle, change))
+}
+
+/// Apply again the last undone change; null when there is nothing to redo.
+#[tauri::command]
+pub async fn redo_last(app_handle: AppHandle, project_id: i64) -> Result<Option<OperationChange>, AppError> {
+    info!("🟢 redo_last called for project {}", project_id);
+    let change = database::blocking(move || database::redo_last(project_id)).await?;
+    Ok(history_changed(&app_handle, change))
+}
+
+
+//_________Database____________
+
+/// Ready or not, schema version, and why a migration failed (for the recovery dialog).
+#[tauri::command]
+pub async fn get_database_status() -> Result<DatabaseStatus, AppError> {
+    database::blocking(|| Ok(database::status())).await
+}
+
+/// After a failed migration: move the database file aside and start with an empty one.
+#[tauri::command]
+pub async fn reset_database(app_handle: AppHandle) -> Result<DatabaseStatus, AppError> {
+    info!("🟠 reset_database called");
+    database::blocking(move || database::reset(&app_handle)).await
+}
+
+/// Copy the database to `dest_path` while the app keeps running.
+/// Example: `invoke("backup_database", { destPath: "D:/backups/episode-3.db" })`
+#[tauri::command]
+pub async fn backup_database(app_handle: AppHandle, dest_path: String) -> Result<BackupInfo, AppError> {
+    info!("🟢 backup_database called with dest: {}", dest_path);
+    database::blocking(move || database::backup(&app_handle, std::path::Path::new(&dest_path))).await
+}
+
+/// Replace the database with a backup (the current one is backed up first).
+#[tauri::command]
+pub async fn restore_database(app_handle: AppHandle, src_path: String) -> Result<DatabaseStatus, AppError> {
+    info!("🟠 restore_database called with src: {}", src_path);
+    database::blocking(move || database::restore(&app_handle, std::path::Path::new(&src_path))).await
+}
+
+
+//_________Projects____________
+
+#[tauri::command]
+pub async fn create_project(name: String) -> Result<Project, AppError> {
+    info!("🟢 create_project called with name: {}", name);
+    database::blocking(move || database::create_project(&name)).await
+}
+
+/// Make a project current (new clips land in it) and return it with its clips.
+/// Both surfaces get a `project-opened` event.
+#[tauri::command]
+pub async fn open_project(app_handle: AppHandle, project_id: i64) -> Result<OpenedProject, AppError> {
+    let opened = database::blocking(move || {
+        Ok(OpenedProject { project: database::open_project(project_id)?, clips: database::list_clips(project_id)? })
+    })
+    .await?;
+    events::emit_all_surfaces(&app_handle, events::PROJECT_OPENED, opened.project.clone());
+    Ok(opened)
+}
+
+/// Most recently opened first; the first one is the current project.
+#[tauri::command]
+pub async fn list_projects() -> Result<Vec<Project>, AppError> {
+    database::blocking(database::list_projects).await
+}
+
+/// Deletes the project's clips and markers too. Returns false when no project had this id.
 #[tauri::command]
-pub fn add_marker(timestamp: f64) {
-    println!("🟢 add_marker called at timestamp: {}", timestamp);
+pub async fn delete_project(project_id: i64) -> Result<bool, AppError> {
+    info!("🟢 delete_project called for project {}", project_id);
+    database::blocking(move || database::delete_project(project_id)).await
 }
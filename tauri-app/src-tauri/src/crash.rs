@@ -0,0 +1,220 @@
+// src/crash.rs
+//
+// Crash reports: a panic anywhere (main thread or a spawned task) is written to
+// <app data dir>/crashes/crash-<id>.json with the message, location, backtrace, app version and the
+// last CRASH_LOG_LINES log records, then logged.
+// - long-lived tasks are started with `crash::spawn("name", fut)` so their reports say which task died
+// - `list_crash_reports` for the UI, `submit_crash_report(id)` uploads one to the cloud server
+//   (POST <serverUrl>/crash-report) only when the user asks; the machine id is not sent
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::backtrace::Backtrace;
+use std::future::Future;
+use std::panic::PanicHookInfo;
+use std::path::PathBuf;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+use tracing::{error, info};
+
+use crate::error::AppError;
+use crate::license;
+use crate::logging::{self, LogRecord};
+
+
+//____________Const___________
+pub const CRASH_LOG_LINES: usize = 200;
+const CRASH_DIR: &str = "crashes"; // under the app data dir
+const MAX_REPORTS: usize = 20;     // oldest reports are deleted past this
+
+
+//_____________Struct _________________________
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    pub id: String,               // also the file name
+    pub created_at: String,       // RFC 3339, UTC
+    pub message: String,
+    pub location: Option<String>, // file:line:column of the panic
+    pub thread: String,
+    pub task: Option<String>,     // name given to crash::spawn
+    pub backtrace: String,
+    pub app_version: String,
+    pub os: String,
+    pub logs: Vec<LogRecord>,
+    pub submitted: bool,
+}
+
+/// Listing entry, without the backtrace and logs.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashSummary {
+    pub id: String,
+    pub created_at: String,
+    pub message: String,
+    pub task: Option<String>,
+    pub submitted: bool,
+}
+
+
+//_____________Globals _______________________
+static CRASH_PATH: OnceCell<PathBuf> = OnceCell::new();
+static APP_VERSION: OnceCell<String> = OnceCell::new();
+
+tokio::task_local! {
+    static TASK_NAME: &'static str;
+}
+
+
+//_____________fn ____________________________
+
+/// Install the panic hook. Call in setup right after logging.
+pub fn init(app_handle: &AppHandle) {
+    match app_handle.path().app_data_dir() {
+        Ok(dir) => {
+            let _ = CRASH_PATH.set(dir.join(CRASH_DIR));
+        }
+        Err(e) => error!("❌ No app data dir, crash reports disabled: {}", e),
+    }
+    let _ = APP_VERSION.set(app_handle.package_info().version.to_string());
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        match write_report(info) {
+            Some(path) => error!("💥 Panic: {} (report {:?})", panic_message(info), path),
+            None => error!("💥 Panic: {}", panic_message(info)),
+        }
+        previous(info);
+    }));
+}
+
+/// Spawn a named task; a panic inside it is reported with that name.
+pub fn spawn<F>(name: &'static str, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tauri::async_runtime::spawn(TASK_NAME.scope(name, future))
+}
+
+fn panic_message(info: &PanicHookInfo) -> String {
+    let payload = info.payload();
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic payload".to_string())
+}
+
+// Runs inside the panic hook: never panic, give up quietly instead
+fn write_report(info: &PanicHookInfo) -> Option<PathBuf> {
+    let dir = CRASH_PATH.get()?;
+    std::fs::create_dir_all(dir).ok()?;
+
+    let now = chrono::Utc::now();
+    let id = format!("{}-{}", now.format("%Y%m%dT%H%M%S"), hex::encode(rand::random::<[u8; 3]>()));
+    let report = CrashReport {
+        id: id.clone(),
+        created_at: now.to_rfc3339(),
+        message: panic_message(info),
+        location: info.location().map(|l| l.to_string()),
+        thread: std::thread::current().name().unwrap_or("unnamed").to_string(),
+        task: TASK_NAME.try_with(|name| name.to_string()).ok(),
+        backtrace: Backtrace::force_capture().to_string(),
+        app_version: APP_VERSION.get().cloned().unwrap_or_default(),
+        os: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+        logs: logging::get_recent_logs(CRASH_LOG_LINES),
+        submitted: false,
+    };
+
+    let path = dir.join(format!("crash-{}.json", id));
+    std::fs::write(&path, serde_json::to_vec_pretty(&report).ok()?).ok()?;
+    prune(dir);
+    Some(path)
+}
+
+// Keep the MAX_REPORTS newest reports (ids sort by time)
+fn prune(dir: &std::path::Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    let mut files: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).filter(|p| is_report(p)).collect();
+    files.sort();
+    for old in files.iter().rev().skip(MAX_REPORTS) {
+        let _ = std::fs::remove_file(old);
+    }
+}
+
+fn is_report(path: &std::path::Path) -> bool {
+    path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with("crash-") && n.ends_with(".json"))
+}
+
+fn crash_dir() -> Result<&'static PathBuf, AppError> {
+    CRASH_PATH.get().ok_or_else(|| AppError::Io("Crash reports are not available (no app data dir)".into()))
+}
+
+fn report_path(id: &str) -> Result<PathBuf, AppError> {
+    // ids are generated by write_report: reject anything that could leave the crash dir
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(AppError::InvalidInput(format!("Invalid crash report id '{}'", id)));
+    }
+    Ok(crash_dir()?.join(format!("crash-{}.json", id)))
+}
+
+pub fn load(id: &str) -> Result<CrashReport, AppError> {
+    let path = report_path(id)?;
+    let bytes = std::fs::read(&path).map_err(|e| AppError::InvalidInput(format!("No crash report {}: {}", id, e)))?;
+    serde_json::from_slice(&bytes).map_err(|e| AppError::Io(format!("Unreadable crash report {:?}: {}", path, e)))
+}
+
+/// All reports on disk, newest first.
+pub fn list() -> Result<Vec<CrashSummary>, AppError> {
+    let dir = crash_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut ids: Vec<String> = std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| is_report(p))
+        .filter_map(|p| p.file_stem()?.to_str()?.strip_prefix("crash-").map(String::from))
+        .collect();
+    ids.sort_by(|a, b| b.cmp(a));
+
+    Ok(ids
+        .iter()
+        .filter_map(|id| load(id).ok())
+        .map(|r| CrashSummary { id: r.id, created_at: r.created_at, message: r.message, task: r.task, submitted: r.submitted })
+        .collect())
+}
+
+pub async fn submit(id: &str) -> Result<(), AppError> {
+    let mut report = load(id)?;
+    let url = format!("{}/crash-report", license::server_url());
+    let resp = license::http_client()
+        .post(&url)
+        .json(&report)
+        .send()
+        .await
+        .map_err(|e| AppError::Io(format!("Failed to send crash report: {}", e)))?;
+    if !resp.status().is_success() {
+        return Err(AppError::Io(format!("Crash report rejected by {}: HTTP {}", url, resp.status())));
+    }
+
+    report.submitted = true;
+    let bytes = serde_json::to_vec_pretty(&report).map_err(|e| AppError::Io(e.to_string()))?;
+    std::fs::write(report_path(id)?, bytes)?;
+    info!("📤 Crash report {} submitted", id);
+    Ok(())
+}
+
+
+//_____________Commands ________________________
+
+#[tauri::command]
+pub fn list_crash_reports() -> Result<Vec<CrashSummary>, AppError> {
+    list()
+}
+
+/// Upload one report to the cloud server (opt-in: only ever sent through this command).
+#[tauri::command]
+pub async fn submit_crash_report(id: String) -> Result<(), AppError> {
+    submit(&id).await
+}
@@ -1,20 +1,2118 @@
 // src/database.rs
-pub fn init_db() {
-    println!("🟢 init_db called");
+//
+// Local SQLite database (app data dir / tauri-app.db) holding projects, their clips and markers, and the job history.
+// - `init_db` opens the file and migrates it to the current schema once at startup (migrations.rs);
+//   when that fails the database stays closed and `reset_database` can move the file aside and start over
+// - `backup_database(dest)` / `restore_database(src)` use SQLite's online backup API (no need to close anything);
+//   automatic backups (before a migration or a restore) go to <app data>/backups, the MAX_AUTO_BACKUPS newest are kept
+// - every committed insert / update / delete of a project, clip, marker or job is announced as a `db-changed`
+//   event (DbChange) on both surfaces; rows removed by ON DELETE CASCADE are not listed
+// - `search_markers` queries the markers_fts full-text index (label / name / comment / clip path), best match first
+// - every other fn takes a connection from the pool through `conn()` (WAL, so readers don't wait for a writer);
+//   they block, so async callers (commands, WS handlers) go through `blocking(|| …)` to stay off the runtime threads
+// - clips belong to a project (e.g. one per episode); the current project is the last one opened,
+//   and a "Default" project is created when there is none
+// - clip and marker changes are written to the project's operation log (`operations`, at most MAX_OPERATIONS),
+//   `undo_last(project)` / `redo_last(project)` walk back and forth through it
+// - projects, clips and markers carry a uuid, a server revision and a dirty flag for the cloud sync (sync.rs):
+//   `sync_pending` lists what to push, `sync_apply` merges a record from the server (last writer wins)
+// - reference_faces holds the face embeddings identify_face compares against (faces.rs)
+// - a marker created from a deepface result can carry it (`analysis`): it goes to `analyses`, read back by `get_analysis`
+// - macros holds the named command sequences of run_macro (macros.rs), by unique name, steps as JSON
+// - audio_analyses holds the last audio analysis of a clip (audio.rs), as JSON
+// - transcript_segments holds the timestamped speech of a clip (whisper.rs), next to its markers
+// - SENSITIVE_COLUMNS are stored through `encryption::protect` and read through `encryption::open`
+//   (sealed only while settings.database encryptSensitive is on, see encryption.rs)
+
+use once_cell::sync::OnceCell;
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::backup::Progress;
+use rusqlite::{ffi, params, params_from_iter, Connection, DatabaseName, OpenFlags, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{debug, info, warn};
+
+use crate::error::AppError;
+use crate::{encryption, events};
+use crate::migrations::{self, MigrationFailure};
+
+
+//____________Const___________
+const DB_FILE: &str = "tauri-app.db";
+const BACKUP_DIR: &str = "backups"; // automatic backups, next to DB_FILE
+pub const MAX_AUTO_BACKUPS: usize = 10;
+const POOL_SIZE: u32 = 4;
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5); // a writer waits this long for another one
+pub const SEARCH_PAGE_SIZE: usize = 50;
+const MAX_SEARCH_PAGE_SIZE: usize = 500;
+// Columns of markers_fts a search word can be limited to (`label:angry`), "emotion" is the label
+const SEARCH_COLUMNS: &[(&str, &str)] = &[("label", "label"), ("emotion", "label"), ("name", "name"), ("comment", "comment"), ("clip", "clip")];
+// bm25 weight of each markers_fts column: an emotion label match ranks above a word in a comment
+const SEARCH_RANK: &str = "bm25(markers_fts, 2.0, 1.5, 1.0, 0.5)";
+const MOMENT_SEARCH_RANK: &str = "bm25(moments_fts, 2.0, 1.5, 1.0, 0.5)"; // moments_fts has the same columns
+pub const DEFAULT_FPS: f64 = 25.0;
+pub const DEFAULT_PROJECT: &str = "Default";
+pub const MAX_OPERATIONS: usize = 200; // undo history kept per project
+// (table, column) holding face analysis data, encrypted when settings.database encryptSensitive is on
+const SENSITIVE_COLUMNS: &[(&str, &str)] =
+    &[("markers", "metadata"), ("jobs", "result"), ("operations", "old_state"), ("operations", "new_state"), ("reference_faces", "embedding"), ("analyses", "result")];
+
+
+
+//_____________Struct _________________________
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    pub id: i64,
+    pub name: String,
+    pub created_at: String,
+    pub opened_at: String,
+    pub clip_count: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Clip {
+    pub id: i64,
+    pub project_id: i64,
+    pub path: String,
+    pub fps: f64,
+}
+
+/// Who created a marker.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarkerSource {
+    #[default]
+    Manual,
+    Deepface,
+    Import, // marker_import.rs
+    Audio,  // audio cue (audio.rs)
+}
+
+impl MarkerSource {
+    fn as_str(self) -> &'static str {
+        match self {
+            MarkerSource::Manual => "manual",
+            MarkerSource::Deepface => "deepface",
+            MarkerSource::Import => "import",
+            MarkerSource::Audio => "audio",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "deepface" => MarkerSource::Deepface,
+            "import" => MarkerSource::Import,
+            "audio" => MarkerSource::Audio,
+            _ => MarkerSource::Manual,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Marker {
+    pub id: i64,
+    pub clip_id: i64,
+    pub timestamp: f64,
+    pub duration: f64,
+    pub name: Option<String>,
+    pub comment: Option<String>,
+    pub label: Option<String>,    // emotion label
+    pub color: Option<String>,
+    pub source: MarkerSource,
+    pub metadata: Option<Value>,  // free-form JSON (emotion scores, face region, …)
+}
+
+/// Fields accepted when creating a marker; everything but clip and time is optional.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NewMarker {
+    pub clip_id: i64,
+    pub timestamp: f64,
+    pub duration: f64,
+    pub name: Option<String>,
+    pub comment: Option<String>,
+    pub label: Option<String>,
+    pub color: Option<String>,
+    pub source: MarkerSource,
+    pub metadata: Option<Value>,
+    pub analysis: Option<NewAnalysis>, // the deepface result the marker comes from
+}
+
+/// Deepface result stored with a new marker.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NewAnalysis {
+    pub model: Option<String>,
+    pub detector: Option<String>,
+    pub result: Value, // as deepface returned it
+}
+
+/// A marker's deepface result (`get_analysis`).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Analysis {
+    pub id: i64,
+    pub marker_id: i64,
+    pub clip_id: i64,
+    pub model: Option<String>,
+    pub detector: Option<String>,
+    pub result: Value,
+    pub created_at: String,
+}
+
+/// `list_markers` filters, all optional and combined.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MarkerFilter {
+    pub from: Option<f64>, // seconds, markers at or after
+    pub to: Option<f64>,   // seconds, markers at or before
+    pub source: Option<MarkerSource>,
+    pub emotion: Option<String>, // label
+    pub color: Option<String>,
+}
+
+/// `list_markers` order.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkerSort {
+    #[default]
+    Timestamp,     // timeline order
+    TimestampDesc,
+    Duration,      // longest first
+    Label,
+    Created,       // in the order they were added
+}
+
+impl MarkerSort {
+    fn order_by(self) -> &'static str {
+        match self {
+            MarkerSort::Timestamp => "timestamp, id",
+            MarkerSort::TimestampDesc => "timestamp DESC, id DESC",
+            MarkerSort::Duration => "duration DESC, timestamp, id",
+            MarkerSort::Label => "label IS NULL, label COLLATE NOCASE, timestamp, id",
+            MarkerSort::Created => "id",
+        }
+    }
+}
+
+/// One page of `list_markers`, with the number of markers matching the filter.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkerPage {
+    pub total: i64,
+    pub offset: usize,
+    pub limit: Option<usize>, // None = everything after offset
+    pub markers: Vec<Marker>,
+}
+
+/// Fields changed by `update_marker`; the ones left out keep their value.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MarkerUpdate {
+    pub timestamp: Option<f64>,
+    pub duration: Option<f64>,
+    pub name: Option<String>,
+    pub comment: Option<String>,
+    pub label: Option<String>,
+    pub color: Option<String>,
+}
+
+/// Kind of change in the operation log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    AddClip,      // new clip, or its fps changed
+    AddMarkers,   // add_marker, or a batch (import, analysis job)
+    UpdateMarker,
+    DeleteMarker,
+}
+
+impl OperationKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            OperationKind::AddClip => "add_clip",
+            OperationKind::AddMarkers => "add_markers",
+            OperationKind::UpdateMarker => "update_marker",
+            OperationKind::DeleteMarker => "delete_marker",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "add_clip" => OperationKind::AddClip,
+            "update_marker" => OperationKind::UpdateMarker,
+            "delete_marker" => OperationKind::DeleteMarker,
+            _ => OperationKind::AddMarkers,
+        }
+    }
+}
+
+// Rows touched by an operation, as they were before or after it
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct OperationState {
+    clips: Vec<Clip>,
+    markers: Vec<Marker>,
+}
+
+/// What `undo_last` / `redo_last` changed: the rows as they are now, and the ids that are gone.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationChange {
+    pub project_id: i64,
+    pub operation_id: i64,
+    pub kind: OperationKind,
+    pub undone: bool, // false for a redo
+    pub clips: Vec<Clip>,
+    pub markers: Vec<Marker>,
+    pub removed_clips: Vec<i64>,
+    pub removed_markers: Vec<i64>,
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+    Interrupted, // was running when the app quit
+}
+
+impl JobStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+            JobStatus::Interrupted => "interrupted",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "running" => JobStatus::Running,
+            "completed" => JobStatus::Completed,
+            "failed" => JobStatus::Failed,
+            "cancelled" => JobStatus::Cancelled,
+            "interrupted" => JobStatus::Interrupted,
+            _ => JobStatus::Queued,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    pub id: i64,
+    pub kind: String,
+    pub params: Value,
+    pub status: JobStatus,
+    pub progress: f64,
+    pub message: Option<String>,
+    pub result: Option<Value>,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A saved command sequence (macros.rs); `steps` as stored, checked by macros.rs before saving.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Macro {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub steps: Value,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A stretch of speech of a clip (whisper.rs), in seconds like markers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptSegment {
+    pub id: i64,
+    pub clip_id: i64,
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    pub language: Option<String>, // as whisper detected or was told
+}
+
+/// A stretch of a clip where speech meets an emotion peak (moments.rs).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Moment {
+    pub id: i64,
+    pub clip_id: i64,
+    pub start: f64,
+    pub end: f64,
+    pub emotion: String,
+    pub score: f64,              // deepface score of the peak, 0-100
+    pub title: String,           // "angry rant about budget"
+    pub text: Option<String>,    // what is said during the moment, None without a transcript
+    pub marker_id: Option<i64>,  // face marker of the peak
+}
+
+/// A moment before it is stored.
+#[derive(Debug, Clone)]
+pub struct NewMoment {
+    pub start: f64,
+    pub end: f64,
+    pub emotion: String,
+    pub score: f64,
+    pub title: String,
+    pub text: Option<String>,
+    pub marker_id: Option<i64>,
+}
+
+/// A known face; the embedding stays in the database (reference_embeddings).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceFace {
+    pub id: i64,
+    pub name: String,
+    pub model: String, // deepface model the embedding was computed with
+    pub created_at: String,
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DbOp {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// `db-changed` event payload.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DbChange {
+    pub table: &'static str, // projects | clips | markers | jobs | reference_faces | macros | transcripts | moments
+    pub op: DbOp,
+    pub id: i64,
+    pub data: Option<Value>, // the row as stored, the removed row for a delete (when known)
+}
+
+// Row changes of one call, emitted once they are committed
+#[derive(Default)]
+struct Changes(Vec<DbChange>);
+
+impl Changes {
+    fn push(&mut self, table: &'static str, op: DbOp, id: i64, row: &impl Serialize) {
+        self.0.push(DbChange { table, op, id, data: serde_json::to_value(row).ok() });
+    }
+
+    fn deleted(&mut self, table: &'static str, id: i64) {
+        self.0.push(DbChange { table, op: DbOp::Delete, id, data: None });
+    }
+
+    fn emit(self) {
+        for change in self.0 {
+            events::emit_global(events::DB_CHANGED, change);
+        }
+    }
+}
+
+// Single row change outside a transaction
+fn notify(table: &'static str, op: DbOp, id: i64, row: &impl Serialize) {
+    let mut changes = Changes::default();
+    changes.push(table, op, id, row);
+    changes.emit();
+}
+
+/// One `search_markers` result.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkerHit {
+    pub marker: Marker,
+    pub clip_path: String,
+    pub rank: f64, // bm25, lower = better match
+}
+
+/// A page of `search_markers` results, with the total number of matches.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkerSearch {
+    pub total: i64,
+    pub offset: usize,
+    pub limit: usize,
+    pub hits: Vec<MarkerHit>,
+}
+
+/// One `search_moments` result.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MomentHit {
+    pub moment: Moment,
+    pub clip_path: String,
+    pub rank: f64, // bm25, lower = better match
+}
+
+/// A page of `search_moments` results, with the total number of matches.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MomentSearch {
+    pub total: i64,
+    pub offset: usize,
+    pub limit: usize,
+    pub hits: Vec<MomentHit>,
+}
+
+/// `backup_database` result.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub path: String,
+    pub bytes: u64,
+}
+
+/// `get_database_status` result, for the recovery dialog.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseStatus {
+    pub ready: bool,
+    pub schema_version: Option<u32>,
+    pub latest_version: u32,
+    pub failure: Option<MigrationFailure>, // why it is not ready
+}
+
+
+/// Kind of a record exchanged with the cloud (sync.rs).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncKind {
+    Project,
+    Clip,
+    Marker,
+}
+
+impl SyncKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            SyncKind::Project => "project",
+            SyncKind::Clip => "clip",
+            SyncKind::Marker => "marker",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "project" => Some(SyncKind::Project),
+            "clip" => Some(SyncKind::Clip),
+            "marker" => Some(SyncKind::Marker),
+            _ => None,
+        }
+    }
+
+    fn table(self) -> &'static str {
+        match self {
+            SyncKind::Project => "projects",
+            SyncKind::Clip => "clips",
+            SyncKind::Marker => "markers",
+        }
+    }
+}
+
+/// A project, clip or marker as pushed to / pulled from the cloud, identified by its uuid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncRecord {
+    pub kind: SyncKind,
+    pub uuid: String,
+    pub parent: Option<String>, // uuid of the project of a clip, of the clip of a marker
+    pub revision: i64,          // server revision; for a local change the one it is based on (0 = never pushed)
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default)]
+    pub data: Value,            // project { name }, clip { path, fps }, marker { timestamp, duration, name, … }
+    pub updated_at: String,     // UTC "YYYY-MM-DD HH:MM:SS.SSS", the newest change wins a conflict
+}
+
+#[derive(Deserialize)]
+struct ProjectData {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct ClipData {
+    path: String,
+    fps: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncSide {
+    Local,
+    Remote,
+}
+
+/// Both sides changed a record since it was last synced (`sync-conflict` event payload).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConflict {
+    pub kind: SyncKind,
+    pub uuid: String,
+    pub winner: SyncSide, // the most recent updatedAt; the other change is lost
+    pub local: SyncRecord,
+    pub remote: SyncRecord,
+}
+
+/// Pull cursor and pending local changes.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncState {
+    pub cursor: i64, // last server revision pulled
+    pub last_sync: Option<String>,
+    pub pending: i64,
+}
+
+
+//_____________Globals _______________________
+static DB: OnceCell<Pool<SqliteConnectionManager>> = OnceCell::new();
+static MIGRATION_FAILURE: Mutex<Option<MigrationFailure>> = Mutex::new(None);
+
+
+//_____________fn ____________________________
+
+fn db_path(app_handle: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::Io(format!("No app data dir: {}", e)))?;
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join(DB_FILE))
+}
+
+fn migration_failure() -> MutexGuard<'static, Option<MigrationFailure>> {
+    MIGRATION_FAILURE.lock().unwrap_or_else(|p| p.into_inner())
+}
+
+/// Open (or create) the database and migrate it. Call once from setup.
+/// A failed migration leaves it closed and is emitted as migrations::MIGRATION_FAILED_EVENT.
+pub fn init_db(app_handle: &AppHandle) -> Result<(), AppError> {
+    let path = db_path(app_handle)?;
+    let mut conn = Connection::open(&path)?;
+    if let Err(failure) = migrations::run(&mut conn, &path) {
+        let error = AppError::Db(format!("Migrating {:?} failed: {}", path, failure.error));
+        *migration_failure() = Some(failure.clone());
+        events::emit_all_surfaces(app_handle, migrations::MIGRATION_FAILED_EVENT, failure);
+        return Err(error);
+    }
+    // Stored in the file: one writer and any number of readers at the same time
+    let journal_mode: String = conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get(0))?;
+    drop(conn);
+
+    // foreign_keys and busy_timeout are per connection, off by default
+    let manager = SqliteConnectionManager::file(&path).with_init(|conn| {
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.execute_batch("PRAGMA foreign_keys = ON;")
+    });
+    let pool = Pool::builder()
+        .max_size(POOL_SIZE)
+        .build(manager)
+        .map_err(|e| AppError::Db(format!("Failed to open {:?}: {}", path, e)))?;
+    if DB.set(pool).is_err() {
+        return Err(AppError::Db("Database already initialized".into()));
+    }
+    debug!("Database journal mode: {}", journal_mode);
+    *migration_failure() = None;
+    info!("🟢 Database ready at {:?}", path);
+    Ok(())
+}
+
+pub fn status() -> DatabaseStatus {
+    let schema_version = conn().ok().and_then(|conn| migrations::current_version(&conn).ok());
+    DatabaseStatus {
+        ready: DB.get().is_some(),
+        schema_version,
+        latest_version: migrations::latest_version(),
+        failure: migration_failure().clone(),
+    }
+}
+
+/// Recovery after a failed migration: keep the file as tauri-app.db.broken-<time> and start with an empty database.
+pub fn reset(app_handle: &AppHandle) -> Result<DatabaseStatus, AppError> {
+    if DB.get().is_some() {
+        return Err(AppError::InvalidInput("The database is open, there is nothing to reset".into()));
+    }
+    let path = db_path(app_handle)?;
+    if path.exists() {
+        let aside = path.with_extension(format!("db.broken-{}", chrono::Utc::now().format("%Y%m%dT%H%M%S")));
+        std::fs::rename(&path, &aside)?;
+        info!("🟠 Database {:?} moved to {:?}", path, aside);
+    }
+    init_db(app_handle)?;
+    Ok(status())
+}
+
+/// Timestamped copy in <app data>/backups before a risky change; only the MAX_AUTO_BACKUPS newest are kept.
+pub fn auto_backup(conn: &Connection, db_path: &Path, reason: &str) -> Result<PathBuf, AppError> {
+    let dir = db_path.parent().unwrap_or(Path::new(".")).join(BACKUP_DIR);
+    std::fs::create_dir_all(&dir)?;
+    let dest = dir.join(format!("tauri-app-{}-{}.db", chrono::Utc::now().format("%Y%m%dT%H%M%S"), reason));
+    conn.backup(DatabaseName::Main, &dest, None)?;
+    prune_backups(&dir);
+    info!("💾 Database backed up to {:?}", dest);
+    Ok(dest)
+}
+
+// Names start with the time, so they sort oldest first
+fn prune_backups(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with("tauri-app-") && n.ends_with(".db")))
+        .collect();
+    files.sort();
+    for old in files.iter().rev().skip(MAX_AUTO_BACKUPS) {
+        let _ = std::fs::remove_file(old);
+    }
+}
+
+fn is_live_database(path: &Path, live: &Path) -> bool {
+    matches!((path.canonicalize(), live.canonicalize()), (Ok(a), Ok(b)) if a == b)
+}
+
+/// Copy the open database to `dest`, while it stays in use.
+pub fn backup(app_handle: &AppHandle, dest: &Path) -> Result<BackupInfo, AppError> {
+    if dest.as_os_str().is_empty() {
+        return Err(AppError::InvalidInput("Backup path can't be empty".into()));
+    }
+    if is_live_database(dest, &db_path(app_handle)?) {
+        return Err(AppError::InvalidInput(format!("{:?} is the database itself", dest)));
+    }
+    if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    conn()?.backup(DatabaseName::Main, dest, None)?;
+    let bytes = std::fs::metadata(dest)?.len();
+    info!("💾 Database backed up to {:?} ({} bytes)", dest, bytes);
+    Ok(BackupInfo { path: dest.to_string_lossy().into_owned(), bytes })
+}
+
+// A readable database of ours, not newer than this build, and not the live file itself
+fn check_backup_file(src: &Path, live: &Path) -> Result<(), AppError> {
+    if !src.is_file() {
+        return Err(AppError::InvalidInput(format!("No backup file at {:?}", src)));
+    }
+    if is_live_database(src, live) {
+        return Err(AppError::InvalidInput(format!("{:?} is the database itself", src)));
+    }
+    let not_ours = |e: rusqlite::Error| AppError::InvalidInput(format!("{:?} is not a readable database: {}", src, e));
+    let file = Connection::open_with_flags(src, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(not_ours)?;
+    let check: String = file.query_row("PRAGMA quick_check", [], |row| row.get(0)).map_err(not_ours)?;
+    if check != "ok" {
+        return Err(AppError::InvalidInput(format!("{:?} is corrupted: {}", src, check)));
+    }
+    let has_markers: bool = file
+        .query_row("SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'markers'", [], |row| row.get(0))
+        .map_err(not_ours)?;
+    if !has_markers {
+        return Err(AppError::InvalidInput(format!("{:?} is not a tauri-app database", src)));
+    }
+    let version = migrations::current_version(&file)?;
+    if version > migrations::latest_version() {
+        return Err(AppError::InvalidInput(format!(
+            "{:?} has schema v{}, newer than this app (v{})",
+            src,
+            version,
+            migrations::latest_version()
+        )));
+    }
+    Ok(())
+}
+
+/// Replace the database with the backup at `src`, after an automatic backup of the current one.
+/// Older backups are migrated; both surfaces get a `database-restored` event.
+pub fn restore(app_handle: &AppHandle, src: &Path) -> Result<DatabaseStatus, AppError> {
+    let path = db_path(app_handle)?;
+    check_backup_file(src, &path)?;
+
+    match DB.get() {
+        Some(_) => {
+            let mut conn = conn()?;
+            let previous = auto_backup(&conn, &path, "pre-restore")?;
+            conn.restore(DatabaseName::Main, src, None::<fn(Progress)>)?;
+            if let Err(failure) = migrations::run(&mut conn, &path) {
+                // Back to what was there rather than run on a half-migrated database
+                conn.restore(DatabaseName::Main, &previous, None::<fn(Progress)>)?;
+                conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+                return Err(AppError::Db(format!("Backup {:?} can't be migrated: {}", src, failure.error)));
+            }
+            conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        }
+        None => {
+            // Not open (failed migration): overwrite the file, then open it like at startup
+            let mut conn = Connection::open(&path)?;
+            if let Err(e) = auto_backup(&conn, &path, "pre-restore") {
+                warn!("⚠️ No backup of {:?} before restoring: {}", path, e);
+            }
+            conn.restore(DatabaseName::Main, src, None::<fn(Progress)>)?;
+            drop(conn);
+            init_db(app_handle)?;
+        }
+    }
+
+    info!("💾 Database restored from {:?}", src);
+    let status = status();
+    events::emit_all_surfaces(app_handle, events::DATABASE_RESTORED, status.clone());
+    Ok(status)
+}
+
+fn conn() -> Result<PooledConnection<SqliteConnectionManager>, AppError> {
+    let db = DB.get().ok_or_else(|| AppError::Db("Database not initialized".into()))?;
+    db.get().map_err(|e| AppError::Db(format!("No database connection available: {}", e)))
+}
+
+/// Run database calls on the blocking thread pool, e.g. `database::blocking(move || database::list_projects()).await`.
+pub async fn blocking<T, F>(f: F) -> Result<T, AppError>
+where
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+    T: Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::Db(format!("Database task failed: {}", e)))?
+}
+
+const PROJECT_COLUMNS: &str =
+    "p.id, p.name, p.created_at, p.opened_at, (SELECT COUNT(*) FROM clips c WHERE c.project_id = p.id)";
+
+fn project_from_row(row: &Row) -> rusqlite::Result<Project> {
+    Ok(Project { id: row.get(0)?, name: row.get(1)?, created_at: row.get(2)?, opened_at: row.get(3)?, clip_count: row.get(4)? })
+}
+
+fn is_unique_violation(err: &rusqlite::Error) -> bool {
+    matches!(err, rusqlite::Error::SqliteFailure(e, _) if e.extended_code == ffi::SQLITE_CONSTRAINT_UNIQUE)
+}
+
+pub fn get_project(project_id: i64) -> Result<Project, AppError> {
+    conn()?
+        .query_row(&format!("SELECT {} FROM projects p WHERE p.id = ?1", PROJECT_COLUMNS), params![project_id], project_from_row)
+        .optional()?
+        .ok_or_else(|| AppError::InvalidInput(format!("Unknown project {}", project_id)))
+}
+
+pub fn create_project(name: &str) -> Result<Project, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::InvalidInput("Project name can't be empty".into()));
+    }
+    let id = {
+        let conn = conn()?;
+        conn.execute("INSERT INTO projects (name) VALUES (?1)", params![name]).map_err(|e| {
+            if is_unique_violation(&e) {
+                AppError::InvalidInput(format!("Project '{}' already exists", name))
+            } else {
+                AppError::from(e)
+            }
+        })?;
+        conn.last_insert_rowid()
+    };
+    let project = get_project(id)?;
+    notify("projects", DbOp::Insert, id, &project);
+    Ok(project)
+}
+
+/// Make `project_id` the current project.
+pub fn open_project(project_id: i64) -> Result<Project, AppError> {
+    let changed = conn()?.execute(
+        "UPDATE projects SET opened_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = ?1",
+        params![project_id],
+    )?;
+    if changed == 0 {
+        return Err(AppError::InvalidInput(format!("Unknown project {}", project_id)));
+    }
+    let project = get_project(project_id)?;
+    notify("projects", DbOp::Update, project_id, &project);
+    Ok(project)
+}
+
+/// Most recently opened first.
+pub fn list_projects() -> Result<Vec<Project>, AppError> {
+    let conn = conn()?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM projects p ORDER BY p.opened_at DESC, p.id DESC", PROJECT_COLUMNS))?;
+    let projects = stmt.query_map([], project_from_row)?.collect::<Result<Vec<_>, _>>()?;
+    Ok(projects)
+}
+
+/// Delete a project with its clips and their markers. Returns false when no project had this id.
+pub fn delete_project(project_id: i64) -> Result<bool, AppError> {
+    let deleted = conn()?.execute("DELETE FROM projects WHERE id = ?1", params![project_id])?;
+    if deleted > 0 {
+        let mut changes = Changes::default();
+        changes.deleted("projects", project_id);
+        changes.emit();
+    }
+    Ok(deleted > 0)
+}
+
+/// The last opened project, creating "Default" when there is none.
+pub fn current_project_id() -> Result<i64, AppError> {
+    let conn = conn()?;
+    let current = conn
+        .query_row("SELECT id FROM projects ORDER BY opened_at DESC, id DESC LIMIT 1", [], |row| row.get(0))
+        .optional()?;
+    match current {
+        Some(id) => Ok(id),
+        None => {
+            let inserted = conn.execute("INSERT OR IGNORE INTO projects (name) VALUES (?1)", params![DEFAULT_PROJECT])?;
+            let id = conn.query_row("SELECT id FROM projects WHERE name = ?1", params![DEFAULT_PROJECT], |row| row.get(0))?;
+            if inserted > 0 {
+                drop(conn);
+                notify("projects", DbOp::Insert, id, &get_project(id)?);
+            }
+            Ok(id)
+        }
+    }
+}
+
+const CLIP_COLUMNS: &str = "id, project_id, path, fps";
+
+fn clip_from_row(row: &Row) -> rusqlite::Result<Clip> {
+    Ok(Clip { id: row.get(0)?, project_id: row.get(1)?, path: row.get(2)?, fps: row.get(3)? })
+}
+
+/// Register a clip in `project_id` (the current project by default), or return the existing one with the same path.
+pub fn add_clip(path: &str, fps: Option<f64>, project_id: Option<i64>) -> Result<Clip, AppError> {
+    let project_id = match project_id {
+        Some(id) => get_project(id)?.id,
+        None => current_project_id()?,
+    };
+    let mut conn = conn()?;
+    let tx = conn.transaction()?;
+    let find_clip = |tx: &Connection| {
+        tx.query_row(
+            &format!("SELECT {} FROM clips WHERE project_id = ?1 AND path = ?2", CLIP_COLUMNS),
+            params![project_id, path],
+            clip_from_row,
+        )
+        .optional()
+    };
+    let old = find_clip(&tx)?;
+    tx.execute(
+        "INSERT INTO clips (project_id, path, fps) VALUES (?1, ?2, ?3)
+         ON CONFLICT(project_id, path) DO UPDATE SET fps = COALESCE(?4, fps)",
+        params![project_id, path, fps.unwrap_or(DEFAULT_FPS), fps],
+    )?;
+    let clip = find_clip(&tx)?.ok_or_else(|| AppError::Db(format!("Clip {} not stored", path)))?;
+    if old.as_ref() != Some(&clip) {
+        let before = OperationState { clips: old.iter().cloned().collect(), markers: Vec::new() };
+        let after = OperationState { clips: vec![clip.clone()], markers: Vec::new() };
+        record_operation(&tx, project_id, OperationKind::AddClip, &before, &after)?;
+    }
+    tx.commit()?;
+    match old {
+        None => notify("clips", DbOp::Insert, clip.id, &clip),
+        Some(old) if old != clip => notify("clips", DbOp::Update, clip.id, &clip),
+        Some(_) => {}
+    }
+    Ok(clip)
+}
+
+pub fn get_clip(clip_id: i64) -> Result<Clip, AppError> {
+    conn()?
+        .query_row(&format!("SELECT {} FROM clips WHERE id = ?1", CLIP_COLUMNS), params![clip_id], clip_from_row)
+        .optional()?
+        .ok_or_else(|| AppError::InvalidInput(format!("Unknown clip {}", clip_id)))
+}
+
+/// Clips of one project, in the order they were added.
+pub fn list_clips(project_id: i64) -> Result<Vec<Clip>, AppError> {
+    let conn = conn()?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM clips WHERE project_id = ?1 ORDER BY id", CLIP_COLUMNS))?;
+    let clips = stmt.query_map(params![project_id], clip_from_row)?.collect::<Result<Vec<_>, _>>()?;
+    Ok(clips)
+}
+
+const MARKER_COLUMNS: &str = "id, clip_id, timestamp, duration, name, comment, label, color, source, metadata";
+
+fn marker_from_row(row: &Row) -> rusqlite::Result<Marker> {
+    let source: String = row.get(8)?;
+    let metadata: Option<String> = row.get(9)?;
+    Ok(Marker {
+        id: row.get(0)?,
+        clip_id: row.get(1)?,
+        timestamp: row.get(2)?,
+        duration: row.get(3)?,
+        name: row.get(4)?,
+        comment: row.get(5)?,
+        label: row.get(6)?,
+        color: row.get(7)?,
+        source: MarkerSource::parse(&source),
+        metadata: metadata.and_then(|m| encryption::open(&m).ok()).and_then(|m| serde_json::from_str(&m).ok()),
+    })
+}
+
+fn check_times(timestamp: f64, duration: f64) -> Result<(), AppError> {
+    if !(timestamp >= 0.0 && duration >= 0.0) {
+        return Err(AppError::InvalidInput("timestamp and duration must be >= 0".into()));
+    }
+    Ok(())
+}
+
+fn get_marker(conn: &Connection, marker_id: i64) -> Result<Option<Marker>, AppError> {
+    let marker = conn
+        .query_row(&format!("SELECT {} FROM markers WHERE id = ?1", MARKER_COLUMNS), params![marker_id], marker_from_row)
+        .optional()?;
+    Ok(marker)
+}
+
+fn clip_project(conn: &Connection, clip_id: i64) -> Result<i64, AppError> {
+    conn.query_row("SELECT project_id FROM clips WHERE id = ?1", params![clip_id], |row| row.get(0))
+        .optional()?
+        .ok_or_else(|| AppError::InvalidInput(format!("Unknown clip {}", clip_id)))
+}
+
+fn insert_marker(conn: &Connection, marker: &NewMarker) -> Result<Marker, AppError> {
+    check_times(marker.timestamp, marker.duration)?;
+    let metadata = marker.metadata.as_ref().map(|m| encryption::protect(m.to_string())).transpose()?;
+
+    conn.execute(
+        "INSERT INTO markers (clip_id, timestamp, duration, name, comment, label, color, source, metadata)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            marker.clip_id,
+            marker.timestamp,
+            marker.duration,
+            marker.name,
+            marker.comment,
+            marker.label,
+            marker.color,
+            marker.source.as_str(),
+            metadata,
+        ],
+    )?;
+    let id = conn.last_insert_rowid();
+    if let Some(analysis) = &marker.analysis {
+        conn.execute(
+            "INSERT INTO analyses (marker_id, clip_id, model, detector, result) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, marker.clip_id, analysis.model, analysis.detector, encryption::protect(analysis.result.to_string())?],
+        )?;
+    }
+    conn.query_row(&format!("SELECT {} FROM markers WHERE id = ?1", MARKER_COLUMNS), params![id], marker_from_row)
+        .map_err(AppError::from)
+}
+
+// Write a marker back with its id (undo / redo, update), replacing the stored one
+fn write_marker(conn: &Connection, marker: &Marker) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT INTO markers (id, clip_id, timestamp, duration, name, comment, label, color, source, metadata)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+         ON CONFLICT(id) DO UPDATE SET clip_id = excluded.clip_id, timestamp = excluded.timestamp,
+             duration = excluded.duration, name = excluded.name, comment = excluded.comment, label = excluded.label,
+             color = excluded.color, source = excluded.source, metadata = excluded.metadata",
+        params![
+            marker.id,
+            marker.clip_id,
+            marker.timestamp,
+            marker.duration,
+            marker.name,
+            marker.comment,
+            marker.label,
+            marker.color,
+            marker.source.as_str(),
+            marker.metadata.as_ref().map(|m| encryption::protect(m.to_string())).transpose()?,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Insert a marker and return it as stored (with its id).
+pub fn add_marker(marker: &NewMarker) -> Result<Marker, AppError> {
+    add_markers(std::slice::from_ref(marker))?
+        .pop()
+        .ok_or_else(|| AppError::Db("Marker not stored".into()))
+}
+
+/// Insert several markers in one transaction: all of them or none. One undo step per project.
+pub fn add_markers(markers: &[NewMarker]) -> Result<Vec<Marker>, AppError> {
+    let mut conn = conn()?;
+    let tx = conn.transaction()?;
+    let stored = markers.iter().map(|m| insert_marker(&tx, m)).collect::<Result<Vec<_>, _>>()?;
+
+    let mut by_project: BTreeMap<i64, Vec<Marker>> = BTreeMap::new();
+    for marker in &stored {
+        by_project.entry(clip_project(&tx, marker.clip_id)?).or_default().push(marker.clone());
+    }
+    for (project_id, markers) in by_project {
+        let new = OperationState { clips: Vec::new(), markers };
+        record_operation(&tx, project_id, OperationKind::AddMarkers, &OperationState::default(), &new)?;
+    }
+    tx.commit()?;
+
+    let mut changes = Changes::default();
+    for marker in &stored {
+        changes.push("markers", DbOp::Insert, marker.id, marker);
+    }
+    changes.emit();
+    Ok(stored)
+}
+
+/// Change some fields of a marker, returns it as stored.
+pub fn update_marker(marker_id: i64, update: &MarkerUpdate) -> Result<Marker, AppError> {
+    let mut conn = conn()?;
+    let tx = conn.transaction()?;
+    let old = get_marker(&tx, marker_id)?.ok_or_else(|| AppError::InvalidInput(format!("Unknown marker {}", marker_id)))?;
+
+    let mut marker = old.clone();
+    marker.timestamp = update.timestamp.unwrap_or(old.timestamp);
+    marker.duration = update.duration.unwrap_or(old.duration);
+    check_times(marker.timestamp, marker.duration)?;
+    for (field, value) in [
+        (&mut marker.name, &update.name),
+        (&mut marker.comment, &update.comment),
+        (&mut marker.label, &update.label),
+        (&mut marker.color, &update.color),
+    ] {
+        if value.is_some() {
+            field.clone_from(value);
+        }
+    }
+    write_marker(&tx, &marker)?;
+
+    let project_id = clip_project(&tx, marker.clip_id)?;
+    let old = OperationState { clips: Vec::new(), markers: vec![old] };
+    let new = OperationState { clips: Vec::new(), markers: vec![marker.clone()] };
+    record_operation(&tx, project_id, OperationKind::UpdateMarker, &old, &new)?;
+    tx.commit()?;
+    notify("markers", DbOp::Update, marker.id, &marker);
+    Ok(marker)
+}
+
+/// Markers of one clip, in timeline order.
+pub fn list_markers(clip_id: i64) -> Result<Vec<Marker>, AppError> {
+    let conn = conn()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM markers WHERE clip_id = ?1 ORDER BY timestamp, id",
+        MARKER_COLUMNS
+    ))?;
+    let markers = stmt
+        .query_map(params![clip_id], marker_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(markers)
+}
+
+// User text -> FTS5 query: every word must match (as a prefix), `label:angry` limits a word to one column.
+// Words are quoted so FTS5 operators typed by the user are searched as text.
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .filter_map(|word| {
+            let (column, word) = match word.split_once(':') {
+                Some((column, rest)) => match SEARCH_COLUMNS.iter().find(|(name, _)| name.eq_ignore_ascii_case(column)) {
+                    Some((_, column)) => (Some(*column), rest),
+                    None => (None, word),
+                },
+                None => (None, word),
+            };
+            let word = word.replace('"', "");
+            if word.is_empty() {
+                return None;
+            }
+            Some(match column {
+                Some(column) => format!("{} : \"{}\"*", column, word),
+                None => format!("\"{}\"*", word),
+            })
+        })
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Full-text search over the markers of a project (the current one by default), best match first.
+/// Example query: `label:angry interview` = angry markers with "interview" in their name, comment or clip path.
+pub fn search_markers(
+    query: &str,
+    project_id: Option<i64>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<MarkerSearch, AppError> {
+    let match_query = fts_query(query).ok_or_else(|| AppError::InvalidInput("Search query can't be empty".into()))?;
+    let project_id = match project_id {
+        Some(id) => get_project(id)?.id,
+        None => current_project_id()?,
+    };
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(SEARCH_PAGE_SIZE).clamp(1, MAX_SEARCH_PAGE_SIZE);
+
+    let conn = conn()?;
+    let from = "FROM markers_fts JOIN markers m ON m.id = markers_fts.rowid JOIN clips c ON c.id = m.clip_id
+                WHERE markers_fts MATCH ?1 AND c.project_id = ?2";
+    let total: i64 = conn.query_row(&format!("SELECT COUNT(*) {}", from), params![match_query, project_id], |row| row.get(0))?;
+
+    // markers_fts has label / name / comment columns too: qualify the marker ones
+    let columns = MARKER_COLUMNS.split(", ").map(|c| format!("m.{}", c)).collect::<Vec<_>>().join(", ");
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, c.path, {} AS rank {} ORDER BY rank, m.id LIMIT ?3 OFFSET ?4",
+        columns, SEARCH_RANK, from
+    ))?;
+    let hits = stmt
+        .query_map(params![match_query, project_id, limit as i64, offset as i64], |row| {
+            Ok(MarkerHit { marker: marker_from_row(row)?, clip_path: row.get(10)?, rank: row.get(11)? })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(MarkerSearch { total, offset, limit, hits })
+}
+
+/// Markers of one clip matching `filter`, sorted, `limit` of them from `offset`.
+pub fn query_markers(
+    clip_id: i64,
+    filter: &MarkerFilter,
+    sort: MarkerSort,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<MarkerPage, AppError> {
+    use rusqlite::types::Value as SqlValue;
+
+    let mut conditions = vec!["clip_id = ?"];
+    let mut values = vec![SqlValue::Integer(clip_id)];
+    if let Some(from) = filter.from {
+        conditions.push("timestamp >= ?");
+        values.push(SqlValue::Real(from));
+    }
+    if let Some(to) = filter.to {
+        conditions.push("timestamp <= ?");
+        values.push(SqlValue::Real(to));
+    }
+    if let Some(source) = filter.source {
+        conditions.push("source = ?");
+        values.push(SqlValue::Text(source.as_str().to_string()));
+    }
+    if let Some(emotion) = &filter.emotion {
+        conditions.push("label = ? COLLATE NOCASE");
+        values.push(SqlValue::Text(emotion.clone()));
+    }
+    if let Some(color) = &filter.color {
+        conditions.push("color = ? COLLATE NOCASE");
+        values.push(SqlValue::Text(color.clone()));
+    }
+    let filter_sql = conditions.join(" AND ");
+
+    let conn = conn()?;
+    let total: i64 = conn.query_row(
+        &format!("SELECT COUNT(*) FROM markers WHERE {}", filter_sql),
+        params_from_iter(values.iter()),
+        |row| row.get(0),
+    )?;
+
+    let offset = offset.unwrap_or(0);
+    values.push(SqlValue::Integer(limit.map_or(-1, |l| l as i64))); // -1 = no limit
+    values.push(SqlValue::Integer(offset as i64));
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM markers WHERE {} ORDER BY {} LIMIT ? OFFSET ?",
+        MARKER_COLUMNS,
+        filter_sql,
+        sort.order_by()
+    ))?;
+    let markers = stmt.query_map(params_from_iter(values.iter()), marker_from_row)?.collect::<Result<Vec<_>, _>>()?;
+    Ok(MarkerPage { total, offset, limit, markers })
+}
+
+pub fn delete_marker(marker_id: i64) -> Result<bool, AppError> {
+    let mut conn = conn()?;
+    let tx = conn.transaction()?;
+    let Some(marker) = get_marker(&tx, marker_id)? else {
+        return Ok(false);
+    };
+    tx.execute("DELETE FROM markers WHERE id = ?1", params![marker_id])?;
+
+    let project_id = clip_project(&tx, marker.clip_id)?;
+    let old = OperationState { clips: Vec::new(), markers: vec![marker.clone()] };
+    record_operation(&tx, project_id, OperationKind::DeleteMarker, &old, &OperationState::default())?;
+    tx.commit()?;
+    notify("markers", DbOp::Delete, marker_id, &marker);
+    Ok(true)
+}
+
+
+/// The deepface result stored with a marker; None when it was created without one.
+pub fn get_analysis(marker_id: i64) -> Result<Option<Analysis>, AppError> {
+    let conn = conn()?;
+    if get_marker(&conn, marker_id)?.is_none() {
+        return Err(AppError::InvalidInput(format!("Unknown marker {}", marker_id)));
+    }
+    let row = conn
+        .query_row(
+            "SELECT id, marker_id, clip_id, model, detector, result, created_at FROM analyses WHERE marker_id = ?1",
+            params![marker_id],
+            |row| {
+                let analysis = Analysis {
+                    id: row.get(0)?,
+                    marker_id: row.get(1)?,
+                    clip_id: row.get(2)?,
+                    model: row.get(3)?,
+                    detector: row.get(4)?,
+                    result: Value::Null,
+                    created_at: row.get(6)?,
+                };
+                Ok((analysis, row.get::<_, String>(5)?))
+            },
+        )
+        .optional()?;
+    let Some((mut analysis, stored)) = row else { return Ok(None) };
+    analysis.result = serde_json::from_str(&encryption::open(&stored)?)
+        .map_err(|e| AppError::Db(format!("Unreadable analysis of marker {}: {}", marker_id, e)))?;
+    Ok(Some(analysis))
+}
+
+
+/// A face with a stored analysis: its marker, where it is on the timeline, the deepface result.
+#[derive(Debug, Clone)]
+pub struct AnalyzedFace {
+    pub marker_id: i64,
+    pub clip_id: i64,
+    pub timestamp: f64,
+    pub model: Option<String>,
+    pub detector: Option<String>,
+    pub result: Value,
+}
+
+/// Analyzed faces of a clip, in timeline order (deleted markers left out).
+pub fn clip_analyses(clip_id: i64) -> Result<Vec<AnalyzedFace>, AppError> {
+    get_clip(clip_id)?;
+    analyzed_faces("m.clip_id = ?1", clip_id)
+}
+
+/// Analyzed faces of every clip of a project, clip by clip in timeline order.
+pub fn project_analyses(project_id: i64) -> Result<Vec<AnalyzedFace>, AppError> {
+    get_project(project_id)?;
+    analyzed_faces("m.clip_id IN (SELECT id FROM clips WHERE project_id = ?1)", project_id)
+}
+
+fn analyzed_faces(filter: &str, id: i64) -> Result<Vec<AnalyzedFace>, AppError> {
+    let conn = conn()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT m.id, m.clip_id, m.timestamp, a.model, a.detector, a.result FROM analyses a JOIN markers m ON m.id = a.marker_id
+         WHERE {} ORDER BY m.clip_id, m.timestamp, m.id",
+        filter
+    ))?;
+    let rows = stmt
+        .query_map(params![id], |row| {
+            let face = AnalyzedFace {
+                marker_id: row.get(0)?,
+                clip_id: row.get(1)?,
+                timestamp: row.get(2)?,
+                model: row.get(3)?,
+                detector: row.get(4)?,
+                result: Value::Null,
+            };
+            Ok((face, row.get::<_, String>(5)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    rows.into_iter()
+        .map(|(mut face, stored)| {
+            face.result = serde_json::from_str(&encryption::open(&stored)?)
+                .map_err(|e| AppError::Db(format!("Unreadable analysis of marker {}: {}", face.marker_id, e)))?;
+            Ok(face)
+        })
+        .collect()
+}
+
+
+// A new change drops what could still be redone, and the history is capped at MAX_OPERATIONS
+fn record_operation(
+    conn: &Connection,
+    project_id: i64,
+    kind: OperationKind,
+    old: &OperationState,
+    new: &OperationState,
+) -> Result<(), AppError> {
+    let json = |state: &OperationState| {
+        encryption::protect(serde_json::to_string(state).map_err(|e| AppError::Db(e.to_string()))?)
+    };
+    conn.execute("DELETE FROM operations WHERE project_id = ?1 AND undone = 1", params![project_id])?;
+    conn.execute(
+        "INSERT INTO operations (project_id, kind, old_state, new_state) VALUES (?1, ?2, ?3, ?4)",
+        params![project_id, kind.as_str(), json(old)?, json(new)?],
+    )?;
+    conn.execute(
+        "DELETE FROM operations WHERE project_id = ?1 AND id NOT IN
+             (SELECT id FROM operations WHERE project_id = ?1 ORDER BY id DESC LIMIT ?2)",
+        params![project_id, MAX_OPERATIONS as i64],
+    )?;
+    Ok(())
+}
+
+// Go from `from` to `to`: rows only in `from` are deleted, the rows of `to` are written back with their ids
+fn apply_state(
+    conn: &Connection,
+    from: &OperationState,
+    to: &OperationState,
+    changes: &mut Changes,
+) -> Result<(Vec<i64>, Vec<i64>), AppError> {
+    let removed_markers: Vec<i64> =
+        from.markers.iter().map(|m| m.id).filter(|id| !to.markers.iter().any(|m| m.id == *id)).collect();
+    let removed_clips: Vec<i64> = from.clips.iter().map(|c| c.id).filter(|id| !to.clips.iter().any(|c| c.id == *id)).collect();
+
+    for marker in from.markers.iter().filter(|m| removed_markers.contains(&m.id)) {
+        conn.execute("DELETE FROM markers WHERE id = ?1", params![marker.id])?;
+        changes.push("markers", DbOp::Delete, marker.id, marker);
+    }
+    for clip in from.clips.iter().filter(|c| removed_clips.contains(&c.id)) {
+        conn.execute("DELETE FROM clips WHERE id = ?1", params![clip.id])?;
+        changes.push("clips", DbOp::Delete, clip.id, clip);
+    }
+    // Rows in both states are updated, the others come back
+    for clip in &to.clips {
+        conn.execute(
+            "INSERT INTO clips (id, project_id, path, fps) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(id) DO UPDATE SET path = excluded.path, fps = excluded.fps",
+            params![clip.id, clip.project_id, clip.path, clip.fps],
+        )?;
+        let op = if from.clips.iter().any(|c| c.id == clip.id) { DbOp::Update } else { DbOp::Insert };
+        changes.push("clips", op, clip.id, clip);
+    }
+    for marker in &to.markers {
+        write_marker(conn, marker)?;
+        let op = if from.markers.iter().any(|m| m.id == marker.id) { DbOp::Update } else { DbOp::Insert };
+        changes.push("markers", op, marker.id, marker);
+    }
+    Ok((removed_clips, removed_markers))
+}
+
+// Undo the last change of the project, or redo the last undone one
+fn step_history(project_id: i64, undo: bool) -> Result<Option<OperationChange>, AppError> {
+    let mut conn = conn()?;
+    let tx = conn.transaction()?;
+    let query = if undo {
+        "SELECT id, kind, old_state, new_state FROM operations WHERE project_id = ?1 AND undone = 0 ORDER BY id DESC LIMIT 1"
+    } else {
+        "SELECT id, kind, old_state, new_state FROM operations WHERE project_id = ?1 AND undone = 1 ORDER BY id LIMIT 1"
+    };
+    let operation = tx
+        .query_row(query, params![project_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?))
+        })
+        .optional()?;
+    let Some((operation_id, kind, old, new)) = operation else {
+        return Ok(None);
+    };
+
+    let state = |stored: &str| -> Result<OperationState, AppError> {
+        serde_json::from_str(&encryption::open(stored)?).map_err(|e| AppError::Db(format!("Unreadable operation {}: {}", operation_id, e)))
+    };
+    let (from, to) = if undo { (state(&new)?, state(&old)?) } else { (state(&old)?, state(&new)?) };
+    let mut changes = Changes::default();
+    let (removed_clips, removed_markers) = apply_state(&tx, &from, &to, &mut changes)?;
+    tx.execute("UPDATE operations SET undone = ?2 WHERE id = ?1", params![operation_id, undo])?;
+    tx.commit()?;
+    changes.emit();
+
+    Ok(Some(OperationChange {
+        project_id,
+        operation_id,
+        kind: OperationKind::parse(&kind),
+        undone: undo,
+        clips: to.clips,
+        markers: to.markers,
+        removed_clips,
+        removed_markers,
+    }))
+}
+
+/// Revert the last change of a project. None when there is nothing to undo.
+pub fn undo_last(project_id: i64) -> Result<Option<OperationChange>, AppError> {
+    step_history(project_id, true)
+}
+
+/// Apply again the last undone change. None when there is nothing to redo.
+pub fn redo_last(project_id: i64) -> Result<Option<OperationChange>, AppError> {
+    step_history(project_id, false)
+}
+
+
+const JOB_COLUMNS: &str = "id, kind, params, status, progress, message, result, error, created_at, updated_at";
+
+fn job_from_row(row: &Row) -> rusqlite::Result<Job> {
+    let params: String = row.get(2)?;
+    let status: String = row.get(3)?;
+    let result: Option<String> = row.get(6)?;
+    Ok(Job {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        params: serde_json::from_str(&params).unwrap_or(Value::Null),
+        status: JobStatus::parse(&status),
+        progress: row.get(4)?,
+        message: row.get(5)?,
+        result: result.and_then(|r| encryption::open(&r).ok()).and_then(|r| serde_json::from_str(&r).ok()),
+        error: row.get(7)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+pub fn get_job(job_id: i64) -> Result<Job, AppError> {
+    conn()?
+        .query_row(&format!("SELECT {} FROM jobs WHERE id = ?1", JOB_COLUMNS), params![job_id], job_from_row)
+        .optional()?
+        .ok_or_else(|| AppError::InvalidInput(format!("Unknown job {}", job_id)))
+}
+
+// The job as stored now, announced as a db-changed event
+fn job_changed(op: DbOp, job_id: i64) -> Result<Job, AppError> {
+    let job = get_job(job_id)?;
+    notify("jobs", op, job_id, &job);
+    Ok(job)
+}
+
+pub fn insert_job(kind: &str, job_params: &Value) -> Result<Job, AppError> {
+    let id = {
+        let conn = conn()?;
+        conn.execute("INSERT INTO jobs (kind, params) VALUES (?1, ?2)", params![kind, job_params.to_string()])?;
+        conn.last_insert_rowid()
+    };
+    job_changed(DbOp::Insert, id)
 }
 
-pub fn add_clip(path: &str) {
-    println!("🟢 add_clip called with path: {}", path);
+pub fn update_job_progress(job_id: i64, progress: f64, message: Option<&str>) -> Result<Job, AppError> {
+    conn()?.execute(
+        "UPDATE jobs SET progress = ?2, message = COALESCE(?3, message), updated_at = datetime('now') WHERE id = ?1",
+        params![job_id, progress, message],
+    )?;
+    job_changed(DbOp::Update, job_id)
 }
 
-pub fn add_marker(clip_id: i32, timestamp: f64) {
-    println!("🟢 add_marker to clip {} at {}", clip_id, timestamp);
+/// Move a job to `status`; `result` / `error` replace the previous ones.
+pub fn set_job_status(job_id: i64, status: JobStatus, result: Option<&Value>, error: Option<&str>) -> Result<Job, AppError> {
+    let result = result.map(|r| encryption::protect(r.to_string())).transpose()?;
+    conn()?.execute(
+        "UPDATE jobs SET status = ?2, result = ?3, error = ?4, updated_at = datetime('now') WHERE id = ?1",
+        params![job_id, status.as_str(), result, error],
+    )?;
+    job_changed(DbOp::Update, job_id)
 }
 
-pub fn list_markers(clip_id: i32) {
-    println!("🟢 list_markers called for clip {}", clip_id);
+/// Back to queued with no progress, for a job that runs again.
+pub fn reset_job(job_id: i64) -> Result<Job, AppError> {
+    conn()?.execute(
+        "UPDATE jobs SET status = 'queued', progress = 0, message = NULL, result = NULL, error = NULL,
+         updated_at = datetime('now') WHERE id = ?1",
+        params![job_id],
+    )?;
+    job_changed(DbOp::Update, job_id)
 }
 
-pub fn delete_marker(marker_id: i32) {
-    println!("🟢 delete_marker called for marker {}", marker_id);
+/// Most recent jobs first.
+pub fn list_jobs(limit: usize) -> Result<Vec<Job>, AppError> {
+    let conn = conn()?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM jobs ORDER BY id DESC LIMIT ?1", JOB_COLUMNS))?;
+    let jobs = stmt
+        .query_map(params![limit as i64], job_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(jobs)
+}
+
+/// Jobs still queued / running from a previous run of the app. Returns how many were marked interrupted.
+pub fn interrupt_unfinished_jobs() -> Result<usize, AppError> {
+    let ids = {
+        let conn = conn()?;
+        let mut stmt = conn.prepare(
+            "UPDATE jobs SET status = 'interrupted', updated_at = datetime('now') WHERE status IN ('queued', 'running')
+             RETURNING id",
+        )?;
+        let ids = stmt.query_map([], |row| row.get::<_, i64>(0))?.collect::<Result<Vec<_>, _>>()?;
+        ids
+    };
+    for id in &ids {
+        job_changed(DbOp::Update, *id)?;
+    }
+    Ok(ids.len())
+}
+
+
+const REFERENCE_COLUMNS: &str = "id, name, model, created_at";
+
+fn reference_from_row(row: &Row) -> rusqlite::Result<ReferenceFace> {
+    Ok(ReferenceFace { id: row.get(0)?, name: row.get(1)?, model: row.get(2)?, created_at: row.get(3)? })
+}
+
+pub fn add_reference_face(name: &str, model: &str, embedding: &[f64]) -> Result<ReferenceFace, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::InvalidInput("Reference face name can't be empty".into()));
+    }
+    let stored = encryption::protect(serde_json::to_string(embedding).map_err(|e| AppError::Db(e.to_string()))?)?;
+    let conn = conn()?;
+    conn.execute("INSERT INTO reference_faces (name, model, embedding) VALUES (?1, ?2, ?3)", params![name, model, stored])?;
+    let id = conn.last_insert_rowid();
+    let face = conn.query_row(
+        &format!("SELECT {} FROM reference_faces WHERE id = ?1", REFERENCE_COLUMNS),
+        params![id],
+        reference_from_row,
+    )?;
+    notify("reference_faces", DbOp::Insert, id, &face);
+    Ok(face)
+}
+
+/// By name, then oldest first.
+pub fn list_reference_faces() -> Result<Vec<ReferenceFace>, AppError> {
+    let conn = conn()?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM reference_faces ORDER BY name, id", REFERENCE_COLUMNS))?;
+    let faces = stmt.query_map([], reference_from_row)?.collect::<Result<Vec<_>, _>>()?;
+    Ok(faces)
+}
+
+/// Returns false when no reference face had this id.
+pub fn delete_reference_face(reference_id: i64) -> Result<bool, AppError> {
+    let deleted = conn()?.execute("DELETE FROM reference_faces WHERE id = ?1", params![reference_id])?;
+    if deleted > 0 {
+        let mut changes = Changes::default();
+        changes.deleted("reference_faces", reference_id);
+        changes.emit();
+    }
+    Ok(deleted > 0)
+}
+
+/// The reference faces computed with `model`, with their embedding.
+pub fn reference_embeddings(model: &str) -> Result<Vec<(ReferenceFace, Vec<f64>)>, AppError> {
+    let conn = conn()?;
+    let mut stmt = conn.prepare(&format!("SELECT {}, embedding FROM reference_faces WHERE model = ?1 ORDER BY id", REFERENCE_COLUMNS))?;
+    let rows = stmt
+        .query_map(params![model], |row| Ok((reference_from_row(row)?, row.get::<_, String>(4)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    rows.into_iter()
+        .map(|(face, stored)| {
+            let embedding = serde_json::from_str(&encryption::open(&stored)?)
+                .map_err(|e| AppError::Db(format!("Unreadable embedding of reference face {}: {}", face.id, e)))?;
+            Ok((face, embedding))
+        })
+        .collect()
+}
+
+
+const MACRO_COLUMNS: &str = "id, name, description, steps, created_at, updated_at";
+
+fn macro_from_row(row: &Row) -> rusqlite::Result<Macro> {
+    let steps: String = row.get(3)?;
+    Ok(Macro {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        steps: serde_json::from_str(&steps).unwrap_or(Value::Null),
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+/// Create the macro `name`, or replace its steps and description.
+pub fn save_macro(name: &str, description: Option<&str>, steps: &Value) -> Result<Macro, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::InvalidInput("Macro name can't be empty".into()));
+    }
+    let conn = conn()?;
+    let existed = conn.query_row("SELECT 1 FROM macros WHERE name = ?1", params![name], |_| Ok(())).optional()?.is_some();
+    conn.execute(
+        "INSERT INTO macros (name, description, steps) VALUES (?1, ?2, ?3)
+         ON CONFLICT(name) DO UPDATE SET description = excluded.description, steps = excluded.steps, updated_at = datetime('now')",
+        params![name, description, steps.to_string()],
+    )?;
+    let saved = conn.query_row(&format!("SELECT {} FROM macros WHERE name = ?1", MACRO_COLUMNS), params![name], macro_from_row)?;
+    notify("macros", if existed { DbOp::Update } else { DbOp::Insert }, saved.id, &saved);
+    Ok(saved)
+}
+
+pub fn get_macro(name: &str) -> Result<Option<Macro>, AppError> {
+    let found = conn()?
+        .query_row(&format!("SELECT {} FROM macros WHERE name = ?1", MACRO_COLUMNS), params![name.trim()], macro_from_row)
+        .optional()?;
+    Ok(found)
+}
+
+/// By name.
+pub fn list_macros() -> Result<Vec<Macro>, AppError> {
+    let conn = conn()?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM macros ORDER BY name", MACRO_COLUMNS))?;
+    let macros = stmt.query_map([], macro_from_row)?.collect::<Result<Vec<_>, _>>()?;
+    Ok(macros)
+}
+
+/// Returns false when no macro had this name.
+pub fn delete_macro(name: &str) -> Result<bool, AppError> {
+    let conn = conn()?;
+    let Some(id) = conn.query_row("SELECT id FROM macros WHERE name = ?1", params![name.trim()], |row| row.get::<_, i64>(0)).optional()? else {
+        return Ok(false);
+    };
+    conn.execute("DELETE FROM macros WHERE id = ?1", params![id])?;
+    let mut changes = Changes::default();
+    changes.deleted("macros", id);
+    changes.emit();
+    Ok(true)
+}
+
+const SEGMENT_COLUMNS: &str = "id, clip_id, start, end, text, language";
+
+fn segment_from_row(row: &Row) -> rusqlite::Result<TranscriptSegment> {
+    Ok(TranscriptSegment {
+        id: row.get(0)?,
+        clip_id: row.get(1)?,
+        start: row.get(2)?,
+        end: row.get(3)?,
+        text: row.get(4)?,
+        language: row.get(5)?,
+    })
+}
+
+/// Replace the transcript of a clip with `segments` (start, end, text) in one transaction. Returns them as stored.
+pub fn replace_transcript(clip_id: i64, language: Option<&str>, segments: &[(f64, f64, String)]) -> Result<Vec<TranscriptSegment>, AppError> {
+    let mut conn = conn()?;
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM transcript_segments WHERE clip_id = ?1", params![clip_id])?;
+    {
+        let mut insert = tx.prepare("INSERT INTO transcript_segments (clip_id, start, end, text, language) VALUES (?1, ?2, ?3, ?4, ?5)")?;
+        for (start, end, text) in segments {
+            insert.execute(params![clip_id, start, end, text, language])?;
+        }
+    }
+    tx.commit()?;
+    let stored = list_transcript(clip_id)?;
+    // One change for the whole transcript (a clip has hundreds of segments)
+    notify("transcripts", DbOp::Update, clip_id, &json!({ "clipId": clip_id, "segments": stored.len(), "language": language }));
+    Ok(stored)
+}
+
+/// Segments of a clip's transcript, in timeline order.
+pub fn list_transcript(clip_id: i64) -> Result<Vec<TranscriptSegment>, AppError> {
+    let conn = conn()?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM transcript_segments WHERE clip_id = ?1 ORDER BY start, id", SEGMENT_COLUMNS))?;
+    let segments = stmt.query_map(params![clip_id], segment_from_row)?.collect::<Result<Vec<_>, _>>()?;
+    Ok(segments)
+}
+
+const MOMENT_COLUMNS: &str = "id, clip_id, start, end, emotion, score, title, text, marker_id";
+
+fn moment_from_row(row: &Row) -> rusqlite::Result<Moment> {
+    Ok(Moment {
+        id: row.get(0)?,
+        clip_id: row.get(1)?,
+        start: row.get(2)?,
+        end: row.get(3)?,
+        emotion: row.get(4)?,
+        score: row.get(5)?,
+        title: row.get(6)?,
+        text: row.get(7)?,
+        marker_id: row.get(8)?,
+    })
+}
+
+/// Replace the moments of a clip with `moments` in one transaction. Returns them as stored.
+pub fn replace_moments(clip_id: i64, moments: &[NewMoment]) -> Result<Vec<Moment>, AppError> {
+    let mut conn = conn()?;
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM moments WHERE clip_id = ?1", params![clip_id])?;
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO moments (clip_id, start, end, emotion, score, title, text, marker_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+        for m in moments {
+            insert.execute(params![clip_id, m.start, m.end, m.emotion, m.score, m.title, m.text, m.marker_id])?;
+        }
+    }
+    tx.commit()?;
+    let stored = list_moments(clip_id)?;
+    notify("moments", DbOp::Update, clip_id, &json!({ "clipId": clip_id, "moments": stored.len() }));
+    Ok(stored)
+}
+
+/// Moments of a clip, in timeline order.
+pub fn list_moments(clip_id: i64) -> Result<Vec<Moment>, AppError> {
+    get_clip(clip_id)?;
+    let conn = conn()?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM moments WHERE clip_id = ?1 ORDER BY start, id", MOMENT_COLUMNS))?;
+    let moments = stmt.query_map(params![clip_id], moment_from_row)?.collect::<Result<Vec<_>, _>>()?;
+    Ok(moments)
+}
+
+/// Full-text search over the moments of a project (the current one by default), best match first.
+/// Same queries as search_markers: `emotion:angry budget` = angry moments saying "budget" (or titled so).
+pub fn search_moments(
+    query: &str,
+    project_id: Option<i64>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<MomentSearch, AppError> {
+    let match_query = fts_query(query).ok_or_else(|| AppError::InvalidInput("Search query can't be empty".into()))?;
+    let project_id = match project_id {
+        Some(id) => get_project(id)?.id,
+        None => current_project_id()?,
+    };
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(SEARCH_PAGE_SIZE).clamp(1, MAX_SEARCH_PAGE_SIZE);
+
+    let conn = conn()?;
+    let from = "FROM moments_fts JOIN moments m ON m.id = moments_fts.rowid JOIN clips c ON c.id = m.clip_id
+                WHERE moments_fts MATCH ?1 AND c.project_id = ?2";
+    let total: i64 = conn.query_row(&format!("SELECT COUNT(*) {}", from), params![match_query, project_id], |row| row.get(0))?;
+
+    let columns = MOMENT_COLUMNS.split(", ").map(|c| format!("m.{}", c)).collect::<Vec<_>>().join(", ");
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, c.path, {} AS rank {} ORDER BY rank, m.id LIMIT ?3 OFFSET ?4",
+        columns, MOMENT_SEARCH_RANK, from
+    ))?;
+    let hits = stmt
+        .query_map(params![match_query, project_id, limit as i64, offset as i64], |row| {
+            Ok(MomentHit { moment: moment_from_row(row)?, clip_path: row.get(9)?, rank: row.get(10)? })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(MomentSearch { total, offset, limit, hits })
+}
+
+/// Store the audio analysis of a clip, replacing the previous one.
+pub fn save_audio_analysis(clip_id: i64, result: &Value) -> Result<(), AppError> {
+    conn()?.execute(
+        "INSERT INTO audio_analyses (clip_id, result) VALUES (?1, ?2)
+         ON CONFLICT(clip_id) DO UPDATE SET result = excluded.result, created_at = datetime('now')",
+        params![clip_id, result.to_string()],
+    )?;
+    Ok(())
+}
+
+pub fn get_audio_analysis(clip_id: i64) -> Result<Option<Value>, AppError> {
+    let result = conn()?
+        .query_row("SELECT result FROM audio_analyses WHERE clip_id = ?1", params![clip_id], |row| row.get::<_, String>(0))
+        .optional()?;
+    Ok(result.and_then(|text| serde_json::from_str(&text).ok()))
+}
+
+
+/// Encrypt (or decrypt) the stored SENSITIVE_COLUMNS in one transaction. Returns how many values changed.
+pub fn convert_sensitive(encrypt: bool) -> Result<usize, AppError> {
+    let mut conn = conn()?;
+    let tx = conn.transaction()?;
+    let mut converted = 0;
+    for (table, column) in SENSITIVE_COLUMNS {
+        let rows = {
+            let mut stmt = tx.prepare(&format!("SELECT id, {column} FROM {table} WHERE {column} IS NOT NULL"))?;
+            let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+        for (id, stored) in rows {
+            if encryption::is_sealed(&stored) == encrypt {
+                continue;
+            }
+            let value = if encrypt { encryption::seal(&stored)? } else { encryption::open(&stored)? };
+            tx.execute(&format!("UPDATE {table} SET {column} = ?2 WHERE id = ?1"), params![id, value])?;
+            converted += 1;
+        }
+    }
+    tx.commit()?;
+    Ok(converted)
+}
+
+
+// The synced columns of a kind: sync_id, sync_revision, updated_at, parent uuid, then the data
+fn sync_select(kind: SyncKind) -> &'static str {
+    match kind {
+        SyncKind::Project => "SELECT sync_id, sync_revision, updated_at, NULL, name FROM projects",
+        SyncKind::Clip => {
+            "SELECT sync_id, sync_revision, updated_at, (SELECT sync_id FROM projects WHERE id = clips.project_id), path, fps
+             FROM clips"
+        }
+        SyncKind::Marker => {
+            "SELECT sync_id, sync_revision, updated_at, (SELECT sync_id FROM clips WHERE id = markers.clip_id),
+                 timestamp, duration, name, comment, label, color, source, metadata
+             FROM markers"
+        }
+    }
+}
+
+fn sync_record_from_row(kind: SyncKind, row: &Row) -> rusqlite::Result<SyncRecord> {
+    let data = match kind {
+        SyncKind::Project => json!({ "name": row.get::<_, String>(4)? }),
+        SyncKind::Clip => json!({ "path": row.get::<_, String>(4)?, "fps": row.get::<_, f64>(5)? }),
+        SyncKind::Marker => {
+            let metadata: Option<String> = row.get(11)?;
+            json!({
+                "timestamp": row.get::<_, f64>(4)?,
+                "duration": row.get::<_, f64>(5)?,
+                "name": row.get::<_, Option<String>>(6)?,
+                "comment": row.get::<_, Option<String>>(7)?,
+                "label": row.get::<_, Option<String>>(8)?,
+                "color": row.get::<_, Option<String>>(9)?,
+                "source": row.get::<_, String>(10)?,
+                "metadata": metadata
+                    .and_then(|m| encryption::open(&m).ok())
+                    .and_then(|m| serde_json::from_str::<Value>(&m).ok()),
+            })
+        }
+    };
+    Ok(SyncRecord {
+        kind,
+        uuid: row.get(0)?,
+        parent: row.get(3)?,
+        revision: row.get(1)?,
+        deleted: false,
+        data,
+        updated_at: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+    })
+}
+
+fn sync_record(conn: &Connection, kind: SyncKind, id: i64) -> Result<SyncRecord, AppError> {
+    let query = format!("{} WHERE id = ?1", sync_select(kind));
+    Ok(conn.query_row(&query, params![id], |row| sync_record_from_row(kind, row))?)
+}
+
+pub fn sync_state() -> Result<SyncState, AppError> {
+    let conn = conn()?;
+    let (cursor, last_sync) = conn.query_row("SELECT cursor, last_sync FROM sync_state WHERE id = 1", [], |row| {
+        Ok((row.get(0)?, row.get(1)?))
+    })?;
+    let pending = conn.query_row(
+        "SELECT (SELECT COUNT(*) FROM projects WHERE sync_dirty = 1) + (SELECT COUNT(*) FROM clips WHERE sync_dirty = 1)
+              + (SELECT COUNT(*) FROM markers WHERE sync_dirty = 1) + (SELECT COUNT(*) FROM sync_tombstones)",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(SyncState { cursor, last_sync, pending })
+}
+
+pub fn set_sync_cursor(cursor: i64) -> Result<(), AppError> {
+    conn()?.execute("UPDATE sync_state SET cursor = ?1 WHERE id = 1", params![cursor])?;
+    Ok(())
+}
+
+pub fn sync_finished() -> Result<(), AppError> {
+    conn()?.execute("UPDATE sync_state SET last_sync = datetime('now') WHERE id = 1", [])?;
+    Ok(())
+}
+
+/// Local changes to push: changed rows (parents first), then deletions (children first).
+pub fn sync_pending() -> Result<Vec<SyncRecord>, AppError> {
+    let conn = conn()?;
+    let mut records = Vec::new();
+    for kind in [SyncKind::Project, SyncKind::Clip, SyncKind::Marker] {
+        let mut stmt = conn.prepare(&format!("{} WHERE sync_dirty = 1 ORDER BY id", sync_select(kind)))?;
+        let rows = stmt.query_map([], |row| sync_record_from_row(kind, row))?;
+        records.extend(rows.collect::<Result<Vec<_>, _>>()?);
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT kind, sync_id, revision, deleted_at FROM sync_tombstones
+         ORDER BY CASE kind WHEN 'marker' THEN 0 WHEN 'clip' THEN 1 ELSE 2 END, deleted_at",
+    )?;
+    let deletions = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?, row.get::<_, String>(3)?))
+    })?;
+    for deletion in deletions {
+        let (kind, uuid, revision, deleted_at) = deletion?;
+        if let Some(kind) = SyncKind::parse(&kind) {
+            records.push(SyncRecord { kind, uuid, parent: None, revision, deleted: true, data: Value::Null, updated_at: deleted_at });
+        }
+    }
+    Ok(records)
+}
+
+/// The server stored `record` (as read by sync_pending) at `revision`.
+/// A row changed again since then stays pending, now based on that revision.
+pub fn sync_pushed(record: &SyncRecord, revision: i64) -> Result<(), AppError> {
+    let conn = conn()?;
+    if record.deleted {
+        conn.execute(
+            "DELETE FROM sync_tombstones WHERE kind = ?1 AND sync_id = ?2 AND deleted_at = ?3",
+            params![record.kind.as_str(), record.uuid, record.updated_at],
+        )?;
+        return Ok(());
+    }
+    let updated = conn.execute(
+        &format!("UPDATE {} SET sync_revision = ?2, sync_dirty = (updated_at IS NOT ?3) WHERE sync_id = ?1", record.kind.table()),
+        params![record.uuid, revision, record.updated_at],
+    )?;
+    // Deleted while it was being pushed (no tombstone then, it had never been synced)
+    if updated == 0 {
+        conn.execute(
+            "INSERT OR REPLACE INTO sync_tombstones (kind, sync_id, revision, deleted_at)
+             VALUES (?1, ?2, ?3, strftime('%Y-%m-%d %H:%M:%f', 'now'))",
+            params![record.kind.as_str(), record.uuid, revision],
+        )?;
+    }
+    Ok(())
+}
+
+/// Merge a record from the server. When the local row changed too since its last sync, the newest change
+/// wins: a local win stays pending, rebased on the server revision so the next push goes through.
+pub fn sync_apply(remote: &SyncRecord) -> Result<Option<SyncConflict>, AppError> {
+    let kind = remote.kind;
+    let mut conn = conn()?;
+    let tx = conn.transaction()?;
+    let row: Option<(i64, i64, bool)> = tx
+        .query_row(
+            &format!("SELECT id, sync_revision, sync_dirty FROM {} WHERE sync_id = ?1", kind.table()),
+            params![remote.uuid],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+    let tombstone: Option<(i64, String)> = tx
+        .query_row(
+            "SELECT revision, deleted_at FROM sync_tombstones WHERE kind = ?1 AND sync_id = ?2",
+            params![kind.as_str(), remote.uuid],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    // Already seen (e.g. our own push coming back)
+    let known = row.map(|r| r.1).or(tombstone.as_ref().map(|t| t.0)).unwrap_or(0);
+    if known >= remote.revision {
+        return Ok(None);
+    }
+
+    let local = match (row, tombstone) {
+        (Some((id, _, true)), _) => Some(sync_record(&tx, kind, id)?),
+        (None, Some((revision, deleted_at))) => Some(SyncRecord {
+            kind,
+            uuid: remote.uuid.clone(),
+            parent: None,
+            revision,
+            deleted: true,
+            data: Value::Null,
+            updated_at: deleted_at,
+        }),
+        _ => None,
+    };
+    if let Some(local) = local.as_ref().filter(|l| l.updated_at > remote.updated_at) {
+        if local.deleted {
+            tx.execute(
+                "UPDATE sync_tombstones SET revision = ?3 WHERE kind = ?1 AND sync_id = ?2",
+                params![kind.as_str(), remote.uuid, remote.revision],
+            )?;
+        } else {
+            tx.execute(
+                &format!("UPDATE {} SET sync_revision = ?2 WHERE sync_id = ?1", kind.table()),
+                params![remote.uuid, remote.revision],
+            )?;
+        }
+        tx.commit()?;
+        return Ok(Some(SyncConflict { kind, uuid: remote.uuid.clone(), winner: SyncSide::Local, local: local.clone(), remote: remote.clone() }));
+    }
+
+    let mut changes = Changes::default();
+    write_remote(&tx, remote, row.map(|r| r.0), &mut changes)?;
+    tx.execute("DELETE FROM sync_tombstones WHERE kind = ?1 AND sync_id = ?2", params![kind.as_str(), remote.uuid])?;
+    tx.commit()?;
+    changes.emit();
+    Ok(local.map(|local| SyncConflict { kind, uuid: remote.uuid.clone(), winner: SyncSide::Remote, local, remote: remote.clone() }))
+}
+
+// Store the server's version of a record over the local row `local_id` (None: not here yet)
+fn write_remote(tx: &Connection, remote: &SyncRecord, local_id: Option<i64>, changes: &mut Changes) -> Result<(), AppError> {
+    let table = remote.kind.table();
+    if remote.deleted {
+        if let Some(id) = local_id {
+            // Rows deleted with it (cascade) are gone remotely too: no tombstones for them
+            let mark: i64 = tx.query_row("SELECT COALESCE(MAX(rowid), 0) FROM sync_tombstones", [], |row| row.get(0))?;
+            tx.execute(&format!("DELETE FROM {} WHERE id = ?1", table), params![id])?;
+            tx.execute("DELETE FROM sync_tombstones WHERE rowid > ?1", params![mark])?;
+            changes.deleted(table, id);
+        }
+        return Ok(());
+    }
+
+    let unreadable = |e: serde_json::Error| AppError::Db(format!("Unreadable {} {} from the cloud: {}", remote.kind.as_str(), remote.uuid, e));
+    let parent = |parent_table: &str| -> Result<i64, AppError> {
+        let uuid = remote.parent.as_deref().unwrap_or_default();
+        tx.query_row(&format!("SELECT id FROM {} WHERE sync_id = ?1", parent_table), params![uuid], |row| row.get(0))
+            .optional()?
+            .ok_or_else(|| AppError::Db(format!("Parent {} of {} {} is not synced", uuid, remote.kind.as_str(), remote.uuid)))
+    };
+    // A local row with the same natural key that was never pushed (e.g. both machines' "Default") becomes this record
+    let adopt = |query: &str, values: &[&dyn rusqlite::ToSql]| -> Result<Option<i64>, AppError> {
+        Ok(tx.query_row(query, values, |row| row.get(0)).optional()?)
+    };
+
+    let (id, existed) = match remote.kind {
+        SyncKind::Project => {
+            let data: ProjectData = serde_json::from_value(remote.data.clone()).map_err(unreadable)?;
+            match local_id.or(adopt("SELECT id FROM projects WHERE name = ?1 AND sync_revision = 0", &[&data.name])?) {
+                Some(id) => {
+                    tx.execute("UPDATE projects SET name = ?2 WHERE id = ?1", params![id, data.name])?;
+                    (id, true)
+                }
+                None => {
+                    tx.execute("INSERT INTO projects (name) VALUES (?1)", params![data.name])?;
+                    (tx.last_insert_rowid(), false)
+                }
+            }
+        }
+        SyncKind::Clip => {
+            let data: ClipData = serde_json::from_value(remote.data.clone()).map_err(unreadable)?;
+            let project_id = parent("projects")?;
+            let existing = local_id.or(adopt(
+                "SELECT id FROM clips WHERE project_id = ?1 AND path = ?2 AND sync_revision = 0",
+                &[&project_id, &data.path],
+            )?);
+            match existing {
+                Some(id) => {
+                    tx.execute(
+                        "UPDATE clips SET project_id = ?2, path = ?3, fps = ?4 WHERE id = ?1",
+                        params![id, project_id, data.path, data.fps],
+                    )?;
+                    (id, true)
+                }
+                None => {
+                    tx.execute("INSERT INTO clips (project_id, path, fps) VALUES (?1, ?2, ?3)", params![project_id, data.path, data.fps])?;
+                    (tx.last_insert_rowid(), false)
+                }
+            }
+        }
+        SyncKind::Marker => {
+            let mut data: NewMarker = serde_json::from_value(remote.data.clone()).map_err(unreadable)?;
+            data.clip_id = parent("clips")?;
+            match local_id {
+                Some(id) => {
+                    check_times(data.timestamp, data.duration)?;
+                    let marker = Marker {
+                        id,
+                        clip_id: data.clip_id,
+                        timestamp: data.timestamp,
+                        duration: data.duration,
+                        name: data.name,
+                        comment: data.comment,
+                        label: data.label,
+                        color: data.color,
+                        source: data.source,
+                        metadata: data.metadata,
+                    };
+                    write_marker(tx, &marker)?;
+                    (id, true)
+                }
+                None => (insert_marker(tx, &data)?.id, false),
+            }
+        }
+    };
+    tx.execute(
+        &format!("UPDATE {} SET sync_id = ?2, sync_revision = ?3, sync_dirty = 0, updated_at = ?4 WHERE id = ?1", table),
+        params![id, remote.uuid, remote.revision, remote.updated_at],
+    )?;
+
+    let op = if existed { DbOp::Update } else { DbOp::Insert };
+    match remote.kind {
+        SyncKind::Project => {
+            let project = tx.query_row(&format!("SELECT {} FROM projects p WHERE p.id = ?1", PROJECT_COLUMNS), params![id], project_from_row)?;
+            changes.push(table, op, id, &project);
+        }
+        SyncKind::Clip => {
+            let clip = tx.query_row(&format!("SELECT {} FROM clips WHERE id = ?1", CLIP_COLUMNS), params![id], clip_from_row)?;
+            changes.push(table, op, id, &clip);
+        }
+        SyncKind::Marker => {
+            if let Some(marker) = get_marker(tx, id)? {
+                changes.push(table, op, id, &marker);
+            }
+        }
+    }
+    Ok(())
 }
@@ -1,18 +1,20 @@
 //deepFaceProcess.rs
 
-use once_cell::sync::OnceCell;
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
 
-use std::sync::Mutex;
-use std::path::PathBuf;
-use std::time::Duration;
-use std::process::{Child, Stdio}; // std::process Command direct conflict with tokio::processCommand
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::io::Cursor;
+use std::sync::{Arc, RwLock};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use std::process::Stdio; // std::process Command direct conflict with tokio::processCommand
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU64, AtomicUsize, Ordering};
 
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::net::TcpStream;
-use tokio::process::Command;
+use tokio::process::{Child, Command};
 use tokio::sync::Mutex as AsyncMutex;
 use tokio::sync::oneshot;
 
@@ -23,38 +25,482 @@ use tokio_tungstenite::{
     WebSocketStream
     };
 
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use futures_util::{SinkExt, StreamExt};
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+use tauri_plugin_shell::ShellExt;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use tracing::{debug, info, warn};
+
+use crate::analysis;
+use crate::app_status;
+use crate::deepface_results::{self, AnalyzeResult, DetectResult, VerifyResult};
+use crate::error::AppError;
+use crate::events;
+use crate::metrics;
+use crate::models;
+use crate::settings::{self, DeepFaceSettings};
+use crate::websocket;
 
 
 // ---------------------------------------
 // Globals
-static DEEPFACE_PROCESS: OnceCell<Mutex<Option<tokio::process::Child>>> = OnceCell::new();
-static WS_CLIENT: OnceCell<AsyncMutex<WebSocketStream<MaybeTlsStream<TcpStream>>>> = OnceCell::new();
+pub const DEBUG_DEEPFACE: bool = true; // default of settings.debug deepface (see logging.rs)
+pub const DEFAULT_PORT: u16 = 8765;      // default, see settings.deepface.port
+pub const STARTUP_TIMEOUT: u64 = 60;     // seconds to wait for "WebSocket server started successfully"
+pub const IDLE_SHUTDOWN_MINS: u64 = 15;  // default of settings.deepface idleShutdownMins
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+pub const MAX_INSTANCES: usize = 8;       // upper bound of settings.deepface instances
+const INSTANCE_MEMORY_MB: u64 = 1536;    // free RAM each extra instance needs (models loaded)
+pub const RETRY_ATTEMPTS: u32 = 2;       // default of settings.deepface retryAttempts
+pub const RETRY_BACKOFF_MS: u64 = 200;   // default of settings.deepface retryBackoffMs (first delay, then doubled)
+const RETRYABLE: &[&str] = &["analyze", "detect", "represent", "verify", "options"]; // same answer when sent twice
+const SIDECAR: &str = "deepface_cli";     // bundle.externalBin binaries/deepface_cli
+const SIDECAR_SHA256: Option<&str> = option_env!("DEEPFACE_SIDECAR_SHA256"); // set by build.rs
+pub const MAX_FRAME_DIMENSION: u32 = 960; // default of settings.deepface maxFrameDimension (longest side, px)
+pub const FRAME_JPEG_QUALITY: u8 = 85;   // default of settings.deepface frameJpegQuality (1..100)
+const CROP_JPEG_QUALITY: u8 = 90;        // re-encoding of a frame cropped to its roi with preprocessing off
 
-pub const DEBUG_DEEPFACE: bool = true;
 static REQUEST_COUNTER: AtomicU64 = AtomicU64::new(1);
+static QUEUED: AtomicUsize = AtomicUsize::new(0); // requests waiting for the sidecar or in flight
+static APP: OnceCell<AppHandle> = OnceCell::new();
 
-//------------------
-//    Functions
-// -----------------
+type WsClient = WebSocketStream<MaybeTlsStream<TcpStream>>;
 
-#[tauri::command]
-pub async fn start_deepface_server(port: u16) -> Result<(), String> {
+/// What the sidecar build can run (`options` cmd): detectors / models whose packages are installed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeepFaceOptions {
+    pub version: Option<String>, // deepface package version
+    pub detectors: Vec<String>,
+    pub models: Vec<String>,
+}
+
+/// When the sidecar is started (settings.deepface startup).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StartupPolicy {
+    Eager,  // at app setup
+    #[default]
+    Lazy,   // by the first request that needs it
+    Manual, // only by start_deepface_server; requests fail until then
+}
 
-    // Check if deepface instance already running
-    if DEEPFACE_PROCESS.get().is_some() {return Err("DeepFace server already started".into());}
+/// Sidecar state for WS clients (`deepface_status`, DEEPFACE_STATUS events).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarStatus {
+    pub state: &'static str, // "starting" | "running" | "stopping" | "stopped" | "idle" | "failed"
+    pub running: bool,
+    pub port: u16,
+    pub startup: StartupPolicy,
+    pub instances: usize, // processes taking requests (main + workers)
+    pub queue_depth: usize,
+    pub error: Option<String>,
+}
 
-    if DEBUG_DEEPFACE {println!("[Rust] Starting DeepFace server...");}
+/// Region of interest in frame pixels: only this part of the frame is sent to the sidecar.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Roi {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
 
-    // Resolve exe path & Include "_internal" dependencies floder.
-    let mut exe_path = std::env::current_exe()
-        .map_err(|e| format!("Failed to get current exe path: {}", e))?;
-    exe_path.pop(); // remove app exe name
-    exe_path.push("binaries");
-    exe_path.push("deepface_cli");
-    exe_path.push("deepface_cli.exe");
+/// A frame re-encoded for the sidecar, and where its pixels are in the original frame.
+pub struct PreparedFrame {
+    pub bytes: Vec<u8>,      // JPEG
+    pub scale: f64,          // original pixels per sent pixel
+    pub offset: (u32, u32),  // top-left corner of the roi
+}
 
-    let exe_dir: PathBuf = exe_path.parent().unwrap().to_path_buf();
+impl PreparedFrame {
+    /// Move the face boxes of a sidecar answer back into the original frame's pixels.
+    fn map_regions(&self, answer: &mut Value) {
+        let (scale, (dx, dy)) = (self.scale, self.offset);
+        let data = &mut answer["data"];
+        let faces = if data.get("faces").is_some() {
+            &mut data["faces"] // detect / represent: { frame, faces }
+        } else {
+            &mut data["result"] // analyze: { frame, result: [face, …] }
+        };
+        let Some(faces) = faces.as_array_mut() else { return };
+        for face in faces {
+            for key in ["region", "facial_area"] {
+                let Some(area) = face.get_mut(key).and_then(Value::as_object_mut) else { continue };
+                for (field, offset) in [("x", dx), ("y", dy), ("w", 0), ("h", 0)] {
+                    if let Some(v) = area.get(field).and_then(Value::as_f64) {
+                        area.insert(field.to_string(), json!((v * scale).round() + offset as f64));
+                    }
+                }
+                // eye positions are [x, y]
+                for eye in ["left_eye", "right_eye"] {
+                    let at = area.get(eye).and_then(Value::as_array).and_then(|p| Some((p.first()?.as_f64()?, p.get(1)?.as_f64()?)));
+                    if let Some((x, y)) = at {
+                        area.insert(eye.to_string(), json!([(x * scale).round() + dx as f64, (y * scale).round() + dy as f64]));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// An extra deepface_cli process for batch throughput, on its own port.
+struct Worker {
+    port: u16,
+    process: AsyncMutex<Option<Child>>,
+    client: AsyncMutex<Option<WsClient>>,
+    busy: AtomicUsize, // requests sent to it without an answer yet
+}
+
+/// Owns the deepface_cli child process and the WS connection to it (plus the extra worker instances).
+pub static MANAGER: Lazy<DeepFaceManager> = Lazy::new(DeepFaceManager::new);
+
+
+// ---------------------------------------
+// DeepFaceManager
+pub struct DeepFaceManager {
+    process: AsyncMutex<Option<Child>>,
+    client: AsyncMutex<Option<WsClient>>,
+    busy: AtomicUsize,                    // requests on `client`
+    port: AtomicU16,                      // port the main process listens on (reconnects)
+    workers: std::sync::Mutex<Vec<Arc<Worker>>>, // extra instances (settings.deepface instances)
+    settings: RwLock<DeepFaceSettings>,
+    last_used: std::sync::Mutex<Instant>, // last request (or start), for the idle shutdown
+    idle_stopped: AtomicBool,             // stopped by the idle watcher: the next request restarts it, whatever the policy
+    idle_watch: AtomicBool,               // idle watcher task spawned
+}
+
+impl DeepFaceManager {
+    fn new() -> Self {
+        DeepFaceManager {
+            process: AsyncMutex::new(None),
+            client: AsyncMutex::new(None),
+            busy: AtomicUsize::new(0),
+            port: AtomicU16::new(0),
+            workers: std::sync::Mutex::new(Vec::new()),
+            settings: RwLock::new(settings::get().deepface),
+            last_used: std::sync::Mutex::new(Instant::now()),
+            idle_stopped: AtomicBool::new(false),
+            idle_watch: AtomicBool::new(false),
+        }
+    }
+
+    fn touch(&self) {
+        if let Ok(mut last_used) = self.last_used.lock() {
+            *last_used = Instant::now();
+        }
+    }
+
+    fn idle_for(&self) -> Duration {
+        self.last_used.lock().map(|last_used| last_used.elapsed()).unwrap_or_default()
+    }
+
+    pub fn settings(&self) -> DeepFaceSettings {
+        self.settings.read().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    /// Hot-apply new settings. Defaults (detector/model) apply to the next request,
+    /// a new port / instance count to the next start.
+    pub fn apply_settings(&self, new: &DeepFaceSettings) {
+        if let Ok(mut current) = self.settings.write() {
+            if current.port != new.port {
+                info!("[Rust] DeepFace port changed to {} (applies on next start)", new.port);
+            }
+            *current = new.clone();
+        }
+    }
+
+    /// Spawn deepface_cli in serve mode, wait until it is ready and connect to it.
+    pub async fn start(&self, port: Option<u16>) -> Result<(), AppError> {
+        let config = self.settings();
+        let port = port.unwrap_or(config.port);
+
+        // Held for the whole startup so two callers can't spawn two processes
+        let mut process = self.process.lock().await;
+
+        // Check if deepface instance already running
+        if let Some(child) = process.as_mut() {
+            if matches!(child.try_wait(), Ok(None)) {
+                return Err(AppError::DeepFace("DeepFace server already started".into()));
+            }
+        }
+
+        info!("[Rust] Starting DeepFace server...");
+        let (child, ready_rx) = spawn_sidecar(port).await?;
+
+        // Store process handle
+        *process = Some(child);
+
+        // Wait for "DeepFace serve mode started", then connect WS
+        match connect_sidecar(port, ready_rx, config.startup_timeout_secs).await {
+            Ok(ws_stream) => {
+                *self.client.lock().await = Some(ws_stream);
+                self.port.store(port, Ordering::SeqCst);
+                self.touch();
+                self.idle_stopped.store(false, Ordering::SeqCst);
+                if !self.idle_watch.swap(true, Ordering::SeqCst) {
+                    tauri::async_runtime::spawn(idle_watch());
+                }
+                debug!("[Rust] deepface_cli.exe started and WS connected on port {}", port);
+                if instance_target(&config) > 1 {
+                    tauri::async_runtime::spawn(start_workers());
+                }
+                Ok(())
+            }
+            Err(e) => {
+                // Don't leave a half-started child behind
+                if let Some(mut child) = process.take() {
+                    let _ = child.kill().await;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Start the sidecar (on `port`, default settings.deepface port) unless it is running.
+    pub async fn start_if_needed(&self, port: Option<u16>) -> Result<(), AppError> {
+        if self.is_running().await {
+            return Ok(());
+        }
+        // Another caller may have started it in the meantime
+        match self.start(port).await {
+            Err(e) if !self.is_running().await => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Make sure the sidecar runs before a request, as the startup policy allows: eager / lazy start it
+    /// when it isn't running (eager after a crash), manual reports it instead (unless it was stopped for idleness).
+    pub async fn ensure_started(&self) -> Result<(), AppError> {
+        if self.idle_stopped.load(Ordering::SeqCst) {
+            return self.start_if_needed(None).await;
+        }
+        match self.settings().startup {
+            StartupPolicy::Manual if !self.is_running().await => {
+                Err(AppError::DeepFace("DeepFace server not started (startup policy is manual: start it first)".into()))
+            }
+            StartupPolicy::Manual => Ok(()),
+            StartupPolicy::Eager | StartupPolicy::Lazy => self.start_if_needed(None).await,
+        }
+    }
+
+    /// Sidecar processes able to take requests: the main one and its connected workers.
+    pub fn instance_count(&self) -> usize {
+        1 + self.workers.lock().map(|w| w.len()).unwrap_or(0)
+    }
+
+    /// Process id of the running sidecar.
+    pub async fn pid(&self) -> Option<u32> {
+        self.process.lock().await.as_ref().and_then(Child::id)
+    }
+
+    /// Whether the sidecar process is alive (it may still be starting).
+    pub async fn is_running(&self) -> bool {
+        match self.process.lock().await.as_mut() {
+            Some(child) => matches!(child.try_wait(), Ok(None)),
+            None => false,
+        }
+    }
+
+    // Started (with a "warming_up" progress event) when it isn't running
+    async fn ready_for(&self, req: &Value) -> Result<(), AppError> {
+        self.touch();
+        if !self.is_running().await {
+            progress(req, "warming_up");
+        }
+        self.ensure_started().await
+    }
+
+    /// Close the WS connection and kill the child process.
+    pub async fn stop(&self) -> Result<(), AppError> {
+        self.idle_stopped.store(false, Ordering::SeqCst);
+        let workers = std::mem::take(&mut *self.workers.lock().unwrap_or_else(|p| p.into_inner()));
+        for worker in workers {
+            worker.stop().await;
+        }
+        if let Some(mut ws) = self.client.lock().await.take() {
+            let _ = ws.close(None).await;
+        }
+        if let Some(mut child) = self.process.lock().await.take() {
+            child.kill().await.map_err(|e| AppError::Io(format!("Failed to kill deepface_cli: {}", e)))?;
+            info!("[Rust] deepface_cli.exe stopped.");
+        }
+        Ok(())
+    }
+
+    /// Send one request to the sidecar (once the model weights it needs are downloaded) and wait for its reply.
+    pub async fn send_request(&self, req: Value) -> Result<Value, AppError> {
+        self.ready_for(&req).await?;
+        models::ensure_for(&req).await?;
+        let text = req.to_string();
+        debug!("[Rust → WS] {}", text);
+        progress(&req, "sent");
+        let started = Instant::now();
+        let result = self.exchange_with_retry(&req, Message::Text(text)).await;
+        self.touch();
+        record(&req, started, &result);
+        result
+    }
+
+    /// Same as `send_request` with the image as raw bytes (binary frame, see websocket::encode_binary_frame)
+    /// instead of a `frame` path.
+    pub async fn send_binary(&self, req: Value, image: &[u8]) -> Result<Value, AppError> {
+        self.ready_for(&req).await?;
+        models::ensure_for(&req).await?;
+        debug!("[Rust → WS] {} + {} bytes", req, image.len());
+        progress(&req, "sent");
+        let started = Instant::now();
+        let result = self.exchange_with_retry(&req, Message::Binary(websocket::encode_binary_frame(&req, image))).await;
+        self.touch();
+        record(&req, started, &result);
+        result
+    }
+
+    // Least busy instance: a worker when one has fewer requests than the main process
+    fn pick_worker(&self) -> Option<Arc<Worker>> {
+        let workers = self.workers.lock().map(|w| w.clone()).unwrap_or_default();
+        let main_busy = self.busy.load(Ordering::SeqCst);
+        workers
+            .into_iter()
+            .min_by_key(|w| w.busy.load(Ordering::SeqCst))
+            .filter(|w| w.busy.load(Ordering::SeqCst) < main_busy)
+    }
+
+    // Idempotent commands are sent again (same requestId, the sidecar answers a repeat from its cache) after
+    // a transport failure, up to settings.deepface retryAttempts times with doubling delays
+    async fn exchange_with_retry(&self, req: &Value, msg: Message) -> Result<Value, AppError> {
+        let config = self.settings();
+        let retries = if RETRYABLE.contains(&req["cmd"].as_str().unwrap_or_default()) { config.retry_attempts } else { 0 };
+        let mut attempt = 0;
+        loop {
+            match self.exchange(&req["requestId"], msg.clone()).await {
+                Err(e) if attempt < retries => {
+                    attempt += 1;
+                    let delay = Duration::from_millis(config.retry_backoff_ms << (attempt - 1).min(6));
+                    warn!("⚠️ DeepFace {} failed ({}), retry {}/{} in {:?}", req["cmd"], e, attempt, retries, delay);
+                    metrics::incr("deepface.retries");
+                    tokio::time::sleep(delay).await;
+                    self.reconnect().await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    // New WS connection to the running sidecar after the previous one broke; a dead sidecar is restarted
+    // (as the startup policy allows)
+    async fn reconnect(&self) {
+        if !self.is_running().await {
+            if let Err(e) = self.ensure_started().await {
+                warn!("⚠️ DeepFace restart failed: {}", e);
+            }
+            return;
+        }
+        let mut client = self.client.lock().await;
+        if client.is_some() {
+            return;
+        }
+        let port = self.port.load(Ordering::SeqCst);
+        match connect_async(format!("ws://127.0.0.1:{}", port)).await {
+            Ok((ws_stream, _)) => {
+                info!("[Rust] DeepFace WS reconnected on port {}", port);
+                *client = Some(ws_stream);
+            }
+            Err(e) => warn!("⚠️ DeepFace WS reconnect failed: {}", e),
+        }
+    }
+
+    async fn exchange(&self, request_id: &Value, msg: Message) -> Result<Value, AppError> {
+        let _queued = Queued::enter();
+        let worker = self.pick_worker();
+        let (client, busy) = match &worker {
+            Some(worker) => (&worker.client, &worker.busy),
+            None => (&self.client, &self.busy),
+        };
+        busy.fetch_add(1, Ordering::SeqCst);
+        let result = roundtrip(client, request_id, msg).await;
+        busy.fetch_sub(1, Ordering::SeqCst);
+        if let (Some(worker), Err(e)) = (&worker, &result) {
+            // A broken worker leaves the pool; the next start brings the count back
+            warn!("⚠️ DeepFace worker on port {} dropped: {}", worker.port, e);
+            self.workers.lock().unwrap_or_else(|p| p.into_inner()).retain(|w| !Arc::ptr_eq(w, worker));
+            worker.stop().await;
+        }
+        result
+    }
+}
+
+impl Worker {
+    async fn stop(&self) {
+        if let Some(mut ws) = self.client.lock().await.take() {
+            let _ = ws.close(None).await;
+        }
+        if let Some(mut child) = self.process.lock().await.take() {
+            let _ = child.kill().await;
+        }
+    }
+}
+
+// Send one message on `client` and read its answer; a connection that failed is dropped (see reconnect)
+async fn roundtrip(client: &AsyncMutex<Option<WsClient>>, request_id: &Value, msg: Message) -> Result<Value, AppError> {
+    let mut guard = client.lock().await;
+    let ws = guard.as_mut().ok_or_else(|| AppError::DeepFace("DeepFace WS not started".into()))?;
+    let result = send_and_wait(ws, request_id, msg).await;
+    if result.is_err() {
+        *guard = None;
+    }
+    result
+}
+
+async fn send_and_wait(client: &mut WsClient, request_id: &Value, msg: Message) -> Result<Value, AppError> {
+    client
+        .send(msg)
+        .await
+        .map_err(|e| AppError::DeepFace(format!("WS send failed: {}", e)))?;
+
+    loop {
+        match client.next().await {
+            Some(Ok(Message::Text(resp))) => {
+                debug!("[WS → Rust] {}", resp);
+                let val: Value = serde_json::from_str(&resp)
+                    .map_err(|e| AppError::DeepFace(format!("Invalid JSON from DeepFace: {}", e)))?;
+                // Late answer to an attempt given up on: not ours (answers without requestId are errors about the frame itself)
+                if !val["requestId"].is_null() && val["requestId"] != *request_id {
+                    debug!("[Rust] Skipping stale DeepFace answer {}", val["requestId"]);
+                    continue;
+                }
+                return Ok(val);
+            }
+            Some(Ok(Message::Ping(_) | Message::Pong(_))) => continue,
+            Some(Ok(other)) => return Err(AppError::DeepFace(format!("Unexpected WS message: {:?}", other))),
+            Some(Err(e)) => return Err(AppError::DeepFace(format!("WS error: {}", e))),
+            None => return Err(AppError::DeepFace("No response from DeepFace".into())),
+        }
+    }
+}
+
+
+//------------------
+//    Functions
+// -----------------
+
+/// Keep the app handle the sidecar is resolved with (call once at setup).
+pub fn init(app_handle: &AppHandle) {
+    let _ = APP.set(app_handle.clone());
+}
+
+// Spawn deepface_cli in serve mode on `port`; the receiver fires once it is ready for connections
+async fn spawn_sidecar(port: u16) -> Result<(Child, oneshot::Receiver<()>), AppError> {
+    // Bundled sidecar (externalBin), checked against the checksum of the build before it runs
+    let sidecar = sidecar_command()?;
+    let exe_path = PathBuf::from(sidecar.get_program());
+    verify_sidecar(&exe_path, SIDECAR_SHA256, AppError::DeepFace).await?;
+    // "_internal" dependencies folder sits next to the exe
+    let exe_dir: PathBuf = exe_path.parent().map(PathBuf::from).unwrap_or_default();
 
     // Build args
     let args = vec![
@@ -65,19 +511,19 @@ pub async fn start_deepface_server(port: u16) -> Result<(), String> {
         port.to_string(),
     ];
 
-    if DEBUG_DEEPFACE {
-        println!("Running DeepFace exe at: {:?}", exe_path);
-        println!("With args: {:?}", args);
-    }
+    debug!("Running DeepFace exe at: {:?}", exe_path);
+    debug!("With args: {:?}", args);
 
     // Spawn process (tokio::process)
-    let mut child = Command::new(&exe_path)
+    let mut child = Command::from(sidecar)
         .args(&args)
         .current_dir(&exe_dir)
+        .env("DEEPFACE_HOME", models::deepface_home()?)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
+        .kill_on_drop(true)
         .spawn()
-        .map_err(|e| format!("Failed to start deepface_cli: {}", e))?;
+        .map_err(|e| AppError::Io(format!("Failed to start deepface_cli: {}", e)))?;
 
     // Read stdIO
     let stdout = child.stdout.take().unwrap();
@@ -90,122 +536,425 @@ pub async fn start_deepface_server(port: u16) -> Result<(), String> {
     tokio::spawn(async move {
         let mut reader = BufReader::new(stdout).lines();
         while let Ok(Some(line)) = reader.next_line().await {
-            println!("[deepface_cli stdout] {}", line);
+            debug!("[deepface_cli stdout] {}", line);
         }
     });
 
     // ---------- stderr reader ----------
     tokio::spawn(async move {
         let mut reader = BufReader::new(stderr).lines();
+        let mut ready_tx = Some(ready_tx);
         while let Ok(Some(line)) = reader.next_line().await {
-            eprintln!("[deepface_cli stderr] {}", line);
-            // LOOK FOR THE SUCCESS STRING HERE
+            debug!("[deepface_cli stderr] {}", line);
+            // LOOK FOR THE SUCCESS STRING HERE, keep draining afterwards so the pipe never fills up
             if line.contains("WebSocket server started successfully") {
-                let _ = ready_tx.send(());   // <- signal parent
-                break;                       // optional: stop scanning once signaled
+                if let Some(tx) = ready_tx.take() {
+                    let _ = tx.send(());   // <- signal parent
+                }
             }
         }
     });
 
-    // Store process handle
-    DEEPFACE_PROCESS.set(Mutex::new(Some(child))).ok();
+    Ok((child, ready_rx))
+}
 
-    // Wait for "DeepFace serve mode started"
-    tokio::time::timeout(Duration::from_secs(60), ready_rx)
+// Wait for the ready signal of a spawned sidecar, then open the WS connection to it
+async fn connect_sidecar(port: u16, ready_rx: oneshot::Receiver<()>, timeout_secs: u64) -> Result<WsClient, AppError> {
+    tokio::time::timeout(Duration::from_secs(timeout_secs), ready_rx)
         .await
-        .map_err(|_| "Timeout waiting for DeepFace to start".to_string())?
-        .map_err(|_| "DeepFace startup signal failed".to_string())?;
-
-    // Now connect WS
-    let url = format!("ws://127.0.0.1:{}", port);
-    let (ws_stream, _) = connect_async(&url)
+        .map_err(|_| AppError::DeepFace("Timeout waiting for DeepFace to start".into()))?
+        .map_err(|_| AppError::DeepFace("DeepFace startup signal failed".into()))?;
+    let (ws_stream, _) = connect_async(format!("ws://127.0.0.1:{}", port))
         .await
-        .map_err(|e| format!("Failed to connect WS: {}", e))?;
+        .map_err(|e| AppError::DeepFace(format!("Failed to connect WS: {}", e)))?;
+    Ok(ws_stream)
+}
+
+// Instances to run: settings.deepface instances, fewer when the free RAM can't hold them all
+fn instance_target(config: &DeepFaceSettings) -> usize {
+    if config.instances <= 1 {
+        return 1;
+    }
+    let mut system = sysinfo::System::new();
+    system.refresh_memory();
+    let fits = (system.available_memory() / (INSTANCE_MEMORY_MB * 1024 * 1024)) as usize;
+    config.instances.min(fits.max(1))
+}
+
+// Bring the worker pool up to `instance_target` (the main process counts as one), each on a free port
+async fn start_workers() {
+    let config = MANAGER.settings();
+    let missing = instance_target(&config).saturating_sub(MANAGER.instance_count());
+    if missing == 0 {
+        return;
+    }
+    info!("[Rust] Starting {} extra DeepFace instance(s)", missing);
+    for _ in 0..missing {
+        let started: Result<Worker, AppError> = async {
+            let port = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+            let (child, ready_rx) = spawn_sidecar(port).await?;
+            let worker = Worker { port, process: AsyncMutex::new(Some(child)), client: AsyncMutex::new(None), busy: AtomicUsize::new(0) };
+            match connect_sidecar(port, ready_rx, config.startup_timeout_secs).await {
+                Ok(ws_stream) => {
+                    *worker.client.lock().await = Some(ws_stream);
+                    Ok(worker)
+                }
+                Err(e) => {
+                    worker.stop().await;
+                    Err(e)
+                }
+            }
+        }
+        .await;
+        match started {
+            // The main process may have been stopped meanwhile: its workers go with it
+            Ok(worker) if MANAGER.is_running().await => {
+                debug!("[Rust] DeepFace worker ready on port {}", worker.port);
+                MANAGER.workers.lock().unwrap_or_else(|p| p.into_inner()).push(Arc::new(worker));
+            }
+            Ok(worker) => worker.stop().await,
+            Err(e) => {
+                warn!("⚠️ Extra DeepFace instance not started: {}", e);
+                break;
+            }
+        }
+    }
+}
 
-    WS_CLIENT.set(AsyncMutex::new(ws_stream)).ok();
+/// Start the sidecar in the background when the startup policy is eager (call at setup, after `init`).
+pub fn start_on_setup() {
+    if MANAGER.settings().startup != StartupPolicy::Eager {
+        return;
+    }
+    tauri::async_runtime::spawn(async {
+        if let Err(e) = MANAGER.start_if_needed(None).await {
+            warn!("⚠️ DeepFace eager start failed: {}", e);
+        }
+    });
+}
 
-    if DEBUG_DEEPFACE {println!("[Rust] deepface_cli.exe started and WS connected on port {}", port);}
+/// Where the bundled sidecar binary is expected (it may be missing from a dev build).
+pub fn sidecar_path() -> Result<PathBuf, AppError> {
+    Ok(PathBuf::from(sidecar_command()?.get_program()))
+}
 
+// The bundled deepface_cli as Tauri resolves it for this platform (next to the app exe)
+fn sidecar_command() -> Result<std::process::Command, AppError> {
+    bundled_sidecar(SIDECAR, AppError::DeepFace)
+}
+
+/// A bundled externalBin (`name`) as Tauri resolves it for this platform; `fail` builds the error.
+pub fn bundled_sidecar(name: &str, fail: fn(String) -> AppError) -> Result<std::process::Command, AppError> {
+    let app_handle = APP.get().ok_or_else(|| fail(format!("{} sidecar not initialized", name)))?;
+    let sidecar = app_handle.shell().sidecar(name).map_err(|e| fail(format!("{} sidecar not found: {}", name, e)))?;
+    Ok(sidecar.into())
+}
+
+/// SHA-256 of a sidecar against the one computed at build time (build.rs); a mismatch means a corrupted
+/// or replaced binary, which is never run. Builds made without the binary skip the check.
+pub async fn verify_sidecar(path: &Path, expected: Option<&str>, fail: fn(String) -> AppError) -> Result<(), AppError> {
+    let Some(expected) = expected else {
+        warn!("⚠️ No checksum built in for {:?}: running it unverified", path);
+        return Ok(());
+    };
+    let bytes = tokio::fs::read(path).await.map_err(|e| fail(format!("Can't read the sidecar at {:?}: {}", path, e)))?;
+    let actual = hex::encode(Sha256::digest(&bytes));
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(fail(format!(
+            "Sidecar at {:?} failed checksum verification (expected {}, got {}): reinstall the app",
+            path, expected, actual
+        )));
+    }
+    debug!("[Rust] {:?} checksum verified ({})", path, actual);
     Ok(())
 }
 
+#[tauri::command]
+pub async fn start_deepface_server(port: Option<u16>) -> Result<(), AppError> {
+    MANAGER.start(port).await
+}
+
 
 
 
 #[tauri::command]
-pub async fn stop_deepface_server() -> Result<(), String> {
-    if let Some(proc_mutex) = DEEPFACE_PROCESS.get() {
-        let mut lock = proc_mutex.lock().unwrap();
-        if let Some(child) = lock.as_mut() {
-            child.kill().await.map_err(|e| format!("Failed to kill deepface_cli: {}", e))?;
-            if DEBUG_DEEPFACE {
-                println!("[Rust] deepface_cli.exe stopped.");
+pub async fn stop_deepface_server() -> Result<(), AppError> {
+    MANAGER.stop().await
+}
+
+
+// Stop the sidecar after settings.deepface idleShutdownMins without requests (0 = never); it comes back on the
+// next request (DeepFaceManager::ensure_started)
+async fn idle_watch() {
+    loop {
+        tokio::time::sleep(IDLE_CHECK_INTERVAL).await;
+        let idle_mins = MANAGER.settings().idle_shutdown_mins;
+        if idle_mins == 0 || queue_depth() > 0 || MANAGER.idle_for() < Duration::from_secs(idle_mins * 60) {
+            continue;
+        }
+        if !MANAGER.is_running().await {
+            continue;
+        }
+        info!("[Rust] DeepFace idle for {} min, stopping it to free memory", idle_mins);
+        match MANAGER.stop().await {
+            Ok(()) => {
+                MANAGER.idle_stopped.store(true, Ordering::SeqCst);
+                events::publish(events::DEEPFACE_STATUS, &sidecar_status(Some("idle"), None).await);
             }
-            *lock = None;
+            Err(e) => warn!("⚠️ DeepFace idle shutdown failed: {}", e),
         }
     }
-    Ok(())
 }
 
-
 // Helpers
-fn next_request_id() -> u64 {
-    REQUEST_COUNTER.fetch_add(1, Ordering::SeqCst)
+// "deepface-progress" event for WS subscribers
+fn progress(req: &Value, stage: &str) {
+    events::publish(events::DEEPFACE_PROGRESS, &json!({ "requestId": req["requestId"], "cmd": req["cmd"], "stage": stage }));
+}
+
+// "done" / "failed" progress event + latency metrics of one request
+fn record(req: &Value, started: Instant, result: &Result<Value, AppError>) {
+    progress(req, if result.is_ok() { "done" } else { "failed" });
+    metrics::observe("deepface.latency_ms", started.elapsed());
+    if let Some(cmd) = req["cmd"].as_str() {
+        metrics::observe(&format!("deepface.latency_ms.{}", cmd), started.elapsed());
+    }
+    if result.is_err() {
+        metrics::incr("deepface.errors");
+    }
 }
 
-async fn send_request(req: Value) -> Result<Value, String> {
-    let client_mutex = WS_CLIENT.get().ok_or("DeepFace WS not started")?;
-    let mut client = client_mutex.lock().await;
+// Counts one request in QUEUED until dropped (answered, failed or cancelled)
+struct Queued;
 
-    let text = req.to_string();
-    if DEBUG_DEEPFACE {
-        println!("[Rust → WS] {}", text);
+impl Queued {
+    fn enter() -> Self {
+        QUEUED.fetch_add(1, Ordering::SeqCst);
+        Queued
     }
+}
 
-    client
-        .send(Message::Text(text))
-        .await
-        .map_err(|e| e.to_string())?;
+impl Drop for Queued {
+    fn drop(&mut self) {
+        QUEUED.fetch_sub(1, Ordering::SeqCst);
+    }
+}
 
-    if let Some(msg) = client.next().await {
-        match msg {
-            Ok(Message::Text(resp)) => {
-                if DEBUG_DEEPFACE {
-                    println!("[WS → Rust] {}", resp);
-                }
-                let val: Value = serde_json::from_str(&resp).map_err(|e| e.to_string())?;
-                Ok(val)
-            }
-            Ok(other) => Err(format!("Unexpected WS message: {:?}", other)),
-            Err(e) => Err(format!("WS error: {}", e)),
-        }
-    } else {
-        Err("No response from DeepFace".into())
+/// Current state of the sidecar; `state` overrides the running / stopped one (a transition in progress).
+pub async fn sidecar_status(state: Option<&'static str>, error: Option<String>) -> SidecarStatus {
+    let running = MANAGER.is_running().await;
+    let config = MANAGER.settings();
+    SidecarStatus {
+        state: state.unwrap_or(if running { "running" } else { "stopped" }),
+        running,
+        port: config.port,
+        startup: config.startup,
+        instances: if running { MANAGER.instance_count() } else { 0 },
+        queue_depth: queue_depth(),
+        error,
     }
 }
 
+/// Start (`start = true`) or stop the sidecar for a WS client: DEEPFACE_STATUS events before and after the
+/// change go to its subscribers and to `connection`. Starting a running sidecar is not an error.
+pub async fn control(connection: Option<u64>, start: bool, port: Option<u16>) -> Result<SidecarStatus, AppError> {
+    let transition = if start { "starting" } else { "stopping" };
+    events::publish_to(connection, events::DEEPFACE_STATUS, &sidecar_status(Some(transition), None).await);
+    let result = if start { MANAGER.start_if_needed(port).await } else { MANAGER.stop().await };
+    let status = match &result {
+        Ok(()) => sidecar_status(None, None).await,
+        Err(e) => sidecar_status(Some("failed"), Some(e.to_string())).await,
+    };
+    events::publish_to(connection, events::DEEPFACE_STATUS, &status);
+    app_status::changed();
+    result.map(|_| status)
+}
+
+/// Requests sent to the sidecar that have no answer yet (one runs at a time, the others wait).
+pub fn queue_depth() -> usize {
+    QUEUED.load(Ordering::SeqCst)
+}
+
+fn next_request_id() -> u64 {
+    REQUEST_COUNTER.fetch_add(1, Ordering::SeqCst)
+}
+
 //------------------
 //    Commands
 // -----------------
 
+/// Analyze an in-memory image (JPEG/PNG bytes), e.g. a frame the CEP panel sent as a binary WS message.
+pub async fn analyze_frame(image: &[u8], actions: Option<String>, detector: Option<String>, model: Option<String>) -> Result<Value, AppError> {
+    analyze_frame_roi(image, None, actions, detector, model).await
+}
+
+/// Detect faces in an in-memory image.
+pub async fn detect_frame(image: &[u8], detector: Option<String>) -> Result<Value, AppError> {
+    detect_frame_roi(image, None, detector).await
+}
+
+/// Face embeddings of an in-memory image (faces.rs); with `enforce_detection` a frame without a face is an error.
+pub async fn represent_frame(image: &[u8], model: &str, detector: Option<String>, enforce_detection: bool) -> Result<Value, AppError> {
+    let req = json!({
+        "requestId": next_request_id(),
+        "cmd": "represent",
+        "model": model,
+        "detector": detector.or(MANAGER.settings().default_detector),
+        "enforce_detection": enforce_detection
+    });
+    send_frame(req, image, None).await
+}
+
+/// `analyze_frame` of the `roi` part of the image (whole image without one); face boxes are in frame pixels.
+pub async fn analyze_frame_roi(
+    image: &[u8],
+    roi: Option<Roi>,
+    actions: Option<String>,
+    detector: Option<String>,
+    model: Option<String>,
+) -> Result<Value, AppError> {
+    let defaults = MANAGER.settings();
+    let req = json!({
+        "requestId": next_request_id(),
+        "cmd": "analyze",
+        "actions": actions,
+        "detector": detector.or(defaults.default_detector),
+        "model": model.or(defaults.default_model)
+    });
+    send_frame(req, image, roi).await
+}
+
+/// `detect_frame` of the `roi` part of the image (whole image without one); face boxes are in frame pixels.
+pub async fn detect_frame_roi(image: &[u8], roi: Option<Roi>, detector: Option<String>) -> Result<Value, AppError> {
+    let req = json!({
+        "requestId": next_request_id(),
+        "cmd": "detect",
+        "detector": detector.or(MANAGER.settings().default_detector)
+    });
+    send_frame(req, image, roi).await
+}
+
+// Send a frame through preprocess_frame; the face boxes of the answer are mapped back to the original frame
+async fn send_frame(req: Value, image: &[u8], roi: Option<Roi>) -> Result<Value, AppError> {
+    match preprocess_frame(image, roi, &MANAGER.settings())? {
+        None => MANAGER.send_binary(req, image).await,
+        Some(frame) => {
+            debug!("[Rust] Frame preprocessed: {} -> {} bytes", image.len(), frame.bytes.len());
+            let mut answer = MANAGER.send_binary(req, &frame.bytes).await?;
+            frame.map_regions(&mut answer);
+            Ok(answer)
+        }
+    }
+}
+
+/// Decode -> crop to `roi` -> resize to settings maxFrameDimension -> JPEG at settings frameJpegQuality.
+/// None when the image can go as it is (no roi and a JPEG already small enough, or preprocessing off).
+pub fn preprocess_frame(image: &[u8], roi: Option<Roi>, settings: &DeepFaceSettings) -> Result<Option<PreparedFrame>, AppError> {
+    let unreadable = |e: image::ImageError| AppError::InvalidInput(format!("Unreadable image: {}", e));
+    if roi.is_none() {
+        if !settings.preprocess_frames {
+            return Ok(None);
+        }
+        // Camera and clip frames are JPEGs from ffmpeg, usually small enough already
+        let reader = image::ImageReader::new(Cursor::new(image)).with_guessed_format()?;
+        if reader.format() == Some(image::ImageFormat::Jpeg) {
+            let (width, height) = reader.into_dimensions().map_err(unreadable)?;
+            if width.max(height) <= settings.max_frame_dimension {
+                return Ok(None);
+            }
+        }
+    }
+
+    let mut decoded = image::load_from_memory(image).map_err(unreadable)?;
+    let mut offset = (0, 0);
+    if let Some(roi) = roi {
+        let (width, height) = (decoded.width(), decoded.height());
+        if roi.w == 0 || roi.h == 0 || roi.x >= width || roi.y >= height {
+            return Err(AppError::InvalidInput(format!("roi {:?} is outside the {}x{} frame", roi, width, height)));
+        }
+        decoded = decoded.crop_imm(roi.x, roi.y, roi.w.min(width - roi.x), roi.h.min(height - roi.y));
+        offset = (roi.x, roi.y);
+    }
+    let mut scale = 1.0;
+    let max = settings.max_frame_dimension;
+    if settings.preprocess_frames && decoded.width().max(decoded.height()) > max {
+        let resized = decoded.resize(max, max, FilterType::Triangle);
+        scale = decoded.width() as f64 / resized.width() as f64;
+        decoded = resized;
+    }
+
+    let quality = if settings.preprocess_frames { settings.frame_jpeg_quality } else { CROP_JPEG_QUALITY };
+    let mut bytes = Vec::new();
+    JpegEncoder::new_with_quality(&mut bytes, quality)
+        .encode_image(&decoded.to_rgb8())
+        .map_err(|e| AppError::Io(format!("Failed to encode the frame: {}", e)))?;
+    Ok(Some(PreparedFrame { bytes, scale, offset }))
+}
+
+/// With `roi: { x, y, w, h }` only that part of the frame is cropped (in Rust) and analyzed.
 #[tauri::command]
 pub async fn analyze_deepface(
     frame: String,
     actions: String,
     detector: Option<String>,
     model: Option<String>,
-) -> Result<Value, String> {
+    roi: Option<Roi>,
+) -> Result<AnalyzeResult, AppError> {
+    if roi.is_some() {
+        return deepface_results::parse(analyze_frame_roi(&std::fs::read(&frame)?, roi, Some(actions), detector, model).await?);
+    }
+    let defaults = MANAGER.settings();
     let req = json!({
         "requestId": next_request_id(),
         "cmd": "analyze",
         "frame": frame,
         "actions": actions,
-        "detector": detector,
-        "model": model
+        "detector": detector.or(defaults.default_detector),
+        "model": model.or(defaults.default_model)
     });
 
     // if DEBUG_DEEPFACE {println("")}
-    send_request(req).await
+    deepface_results::parse(MANAGER.send_request(req).await?)
+}
+
+/// Whether two in-memory images show the same person. The sidecar's verify reads files, so both images
+/// go through temporary files, removed once it answered.
+pub async fn verify_frames(img1: &[u8], img2: &[u8], detector: Option<String>, model: Option<String>) -> Result<Value, AppError> {
+    let request_id = next_request_id();
+    let dir = std::env::temp_dir();
+    let paths = [dir.join(format!("deepface-verify-{}-1.img", request_id)), dir.join(format!("deepface-verify-{}-2.img", request_id))];
+    let written = async {
+        tokio::fs::write(&paths[0], img1).await?;
+        tokio::fs::write(&paths[1], img2).await
+    }
+    .await;
+    let answer = match written {
+        Ok(()) => {
+            let defaults = MANAGER.settings();
+            let req = json!({
+                "requestId": request_id,
+                "cmd": "verify",
+                "img1": paths[0],
+                "img2": paths[1],
+                "detector": detector.or(defaults.default_detector),
+                "model": model.or(defaults.default_model)
+            });
+            MANAGER.send_request(req).await
+        }
+        Err(e) => Err(AppError::Io(format!("Failed to write the images to verify: {}", e))),
+    };
+    for path in &paths {
+        let _ = tokio::fs::remove_file(path).await;
+    }
+    answer
+}
+
+/// Image bytes of a base64 string, with or without a `data:image/…;base64,` prefix.
+pub fn decode_image(text: &str) -> Result<Vec<u8>, AppError> {
+    let data = text.split_once(";base64,").map_or(text, |(_, data)| data);
+    BASE64
+        .decode(data.trim())
+        .map_err(|e| AppError::InvalidInput(format!("Invalid base64 image: {}", e)))
 }
 
 #[tauri::command]
@@ -214,27 +963,55 @@ pub async fn verify_deepface(
     img2: String,
     detector: Option<String>,
     model: Option<String>,
-) -> Result<Value, String> {
+) -> Result<VerifyResult, AppError> {
+    let defaults = MANAGER.settings();
     let req = json!({
         "requestId": next_request_id(),
         "cmd": "verify",
         "img1": img1,
         "img2": img2,
-        "detector": detector,
-        "model": model
+        "detector": detector.or(defaults.default_detector),
+        "model": model.or(defaults.default_model)
     });
-    send_request(req).await
+    deepface_results::parse(MANAGER.send_request(req).await?)
 }
 
+/// With `roi: { x, y, w, h }` only that part of the frame is cropped (in Rust) and searched.
 #[tauri::command]
-pub async fn detect_deepface(frame: String, detector: Option<String>) -> Result<Value, String> {
+pub async fn detect_deepface(frame: String, detector: Option<String>, roi: Option<Roi>) -> Result<DetectResult, AppError> {
+    if roi.is_some() {
+        return deepface_results::parse(detect_frame_roi(&std::fs::read(&frame)?, roi, detector).await?);
+    }
     let req = json!({
         "requestId": next_request_id(),
         "cmd": "detect",
         "frame": frame,
-        "detector": detector
+        "detector": detector.or(MANAGER.settings().default_detector)
     });
-    send_request(req).await
+    deepface_results::parse(MANAGER.send_request(req).await?)
+}
+
+/// Ask the sidecar (started if needed) which detectors and models it can run.
+pub async fn options() -> Result<DeepFaceOptions, AppError> {
+    analysis::ensure_deepface(None).await?;
+    let answer = MANAGER.send_request(json!({ "requestId": next_request_id(), "cmd": "options" })).await?;
+    if answer["status"] != "ok" {
+        // e.g. "Unsupported cmd 'options'" from a sidecar older than this app
+        return Err(AppError::DeepFace(format!("options failed: {}", answer["data"]["message"])));
+    }
+    serde_json::from_value(answer["data"].clone()).map_err(|e| AppError::DeepFace(format!("Invalid options from DeepFace: {}", e)))
+}
+
+/// Detector backends for the settings dropdown (settings.deepface defaultDetector).
+#[tauri::command]
+pub async fn list_deepface_detectors() -> Result<Vec<String>, AppError> {
+    Ok(options().await?.detectors)
+}
+
+/// Recognition models for the settings dropdown (settings.deepface defaultModel).
+#[tauri::command]
+pub async fn list_deepface_models() -> Result<Vec<String>, AppError> {
+    Ok(options().await?.models)
 }
 
 
@@ -298,7 +1075,7 @@ pub async fn detect_deepface(frame: String, detector: Option<String>) -> Result<
 //     let mut output_str = String::new();
 //     for line in reader.lines() {
 //         let l = line.unwrap_or_default();
-//         if DEBUG_DEEPFACE {println!("DeepFace stdout: {}", l);}
+//         debug!("DeepFace stdout: {}", l);
 //         output_str.push_str(&l);
 //     }
 
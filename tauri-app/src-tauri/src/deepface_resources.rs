@@ -0,0 +1,132 @@
+// src/deepface_resources.rs
+//
+// CPU / memory of the deepface_cli sidecar (sysinfo), for the settings screen and the monitor window.
+// - `get_deepface_resource_usage()`: CPU % (of one core), resident memory and uptime of the child process
+// - a watcher samples it every CHECK_INTERVAL: above settings.deepface memoryWarningMb it logs a warning and
+//   sends DEEPFACE_RESOURCES_EVENT (once per crossing); above memoryLimitMb (0 = off) the sidecar is restarted
+//   as soon as no request is waiting for it, since a process that big is leaking
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use sysinfo::{Pid, ProcessRefreshKind, System};
+use tracing::{info, warn};
+
+use crate::crash;
+use crate::deepFaceProcess::{self, MANAGER};
+use crate::error::AppError;
+use crate::events;
+
+
+//____________Const___________
+pub const DEEPFACE_RESOURCES_EVENT: &str = "deepface-resources"; // ResourceUsage + level: "warning" | "restart"
+pub const MEMORY_WARNING_MB: u64 = 3072; // default of settings.deepface memoryWarningMb
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+const MB: u64 = 1024 * 1024;
+
+
+//_____________Struct _________________________
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUsage {
+    pub pid: u32,
+    pub cpu_percent: f32, // 100 = one core busy
+    pub rss_bytes: u64,
+    pub uptime_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResourceAlert {
+    level: &'static str,
+    usage: ResourceUsage,
+    threshold_mb: u64,
+}
+
+
+//_____________Globals _______________________
+// Kept between samples: CPU usage is measured since the previous refresh
+static SYSTEM: Lazy<Mutex<System>> = Lazy::new(|| Mutex::new(System::new()));
+
+
+//_____________fn ____________________________
+
+fn refresh(system: &mut System, pid: Pid) -> bool {
+    system.refresh_process_specifics(pid, ProcessRefreshKind::new().with_cpu().with_memory())
+}
+
+/// Usage of the sidecar process, None when it isn't running.
+pub async fn usage() -> Option<ResourceUsage> {
+    let pid = Pid::from_u32(MANAGER.pid().await?);
+    let first_sample = {
+        let mut system = SYSTEM.lock().unwrap_or_else(|p| p.into_inner());
+        let first_sample = system.process(pid).is_none();
+        if !refresh(&mut system, pid) {
+            return None;
+        }
+        first_sample
+    };
+    if first_sample {
+        // CPU usage needs two samples of the same process
+        tokio::time::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL).await;
+        refresh(&mut SYSTEM.lock().unwrap_or_else(|p| p.into_inner()), pid);
+    }
+    let system = SYSTEM.lock().unwrap_or_else(|p| p.into_inner());
+    let process = system.process(pid)?;
+    Some(ResourceUsage {
+        pid: pid.as_u32(),
+        cpu_percent: process.cpu_usage(),
+        rss_bytes: process.memory(),
+        uptime_secs: process.run_time(),
+    })
+}
+
+/// Sample the sidecar every CHECK_INTERVAL: memory warning, restart past the hard cap (call once at setup).
+pub fn start_watcher() {
+    crash::spawn("deepface-resources", async {
+        let mut warned = false;
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            let Some(usage) = usage().await else {
+                warned = false;
+                continue;
+            };
+            let config = MANAGER.settings();
+            let rss_mb = usage.rss_bytes / MB;
+
+            if config.memory_limit_mb > 0 && rss_mb >= config.memory_limit_mb && deepFaceProcess::queue_depth() == 0 {
+                warn!("⚠️ DeepFace uses {} MB (limit {} MB), restarting it", rss_mb, config.memory_limit_mb);
+                let alert = ResourceAlert { level: "restart", usage, threshold_mb: config.memory_limit_mb };
+                events::emit_global(DEEPFACE_RESOURCES_EVENT, alert);
+                let restarted = match MANAGER.stop().await {
+                    Ok(()) => MANAGER.start_if_needed(None).await,
+                    Err(e) => Err(e),
+                };
+                match restarted {
+                    Ok(()) => info!("🔁 DeepFace restarted after reaching its memory limit"),
+                    Err(e) => warn!("❌ DeepFace restart failed: {}", e),
+                }
+                warned = false;
+            } else if rss_mb >= config.memory_warning_mb {
+                if !warned {
+                    warn!("⚠️ DeepFace uses {} MB (warning at {} MB)", rss_mb, config.memory_warning_mb);
+                    let alert = ResourceAlert { level: "warning", usage, threshold_mb: config.memory_warning_mb };
+                    events::emit_global(DEEPFACE_RESOURCES_EVENT, alert);
+                    warned = true;
+                }
+            } else {
+                warned = false;
+            }
+        }
+    });
+}
+
+
+//_____________Commands ________________________
+
+/// Example: `invoke("get_deepface_resource_usage")` -> `{ pid, cpuPercent, rssBytes, uptimeSecs }`, null when stopped.
+#[tauri::command]
+pub async fn get_deepface_resource_usage() -> Result<Option<ResourceUsage>, AppError> {
+    Ok(usage().await)
+}
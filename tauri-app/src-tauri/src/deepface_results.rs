@@ -0,0 +1,186 @@
+// src/deepface_results.rs
+//
+// Typed answers of the deepface_cli commands, so the frontend gets a fixed schema instead of raw JSON and a
+// change in the Python side shows up here as a clear error rather than as missing fields in the UI.
+// - `AnalyzeResult` (analyze: emotion / age / gender / race per face), `VerifyResult` (same person, distance),
+//   `DetectResult` (face boxes); face boxes are `FaceBox` in frame pixels
+// - `parse::<T>(answer)` turns a sidecar answer into T: an "error" status becomes AppError::DeepFace with the
+//   sidecar's message, an answer that doesn't fit T or fails `validate` names the command and the field
+// Fields deepface only fills for some actions / versions are optional; unknown fields are ignored.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::error::AppError;
+
+
+//_____________Struct _________________________
+/// A face box in frame pixels (deepface `region` / `facial_area`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FaceBox {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+    #[serde(default, alias = "left_eye")]
+    pub left_eye: Option<[f64; 2]>,
+    #[serde(default, alias = "right_eye")]
+    pub right_eye: Option<[f64; 2]>,
+}
+
+/// One analyzed face; score maps are deepface's 0..100 per class.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FaceAnalysis {
+    pub region: FaceBox,
+    #[serde(default, alias = "face_confidence")]
+    pub face_confidence: Option<f64>,
+    #[serde(default, alias = "dominant_emotion")]
+    pub dominant_emotion: Option<String>,
+    #[serde(default)]
+    pub emotion: Option<BTreeMap<String, f64>>,
+    #[serde(default)]
+    pub age: Option<f64>,
+    #[serde(default, alias = "dominant_gender")]
+    pub dominant_gender: Option<String>,
+    #[serde(default)]
+    pub gender: Option<BTreeMap<String, f64>>,
+    #[serde(default, alias = "dominant_race")]
+    pub dominant_race: Option<String>,
+    #[serde(default)]
+    pub race: Option<BTreeMap<String, f64>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyzeResult {
+    pub frame: String, // path, "<binary>" for in-memory frames
+    #[serde(rename(deserialize = "result"))]
+    pub faces: Vec<FaceAnalysis>,
+}
+
+/// Face boxes of both images of a verify.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyAreas {
+    pub img1: FaceBox,
+    pub img2: FaceBox,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyResult {
+    pub verified: bool,
+    pub distance: f64,
+    pub threshold: f64, // verified = distance <= threshold
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default, alias = "detector_backend")]
+    pub detector_backend: Option<String>,
+    #[serde(default, alias = "similarity_metric")]
+    pub similarity_metric: Option<String>,
+    #[serde(default, alias = "facial_areas")]
+    pub facial_areas: Option<VerifyAreas>,
+    #[serde(default)]
+    pub time: Option<f64>, // seconds spent by deepface
+}
+
+/// One detected face (the cropped face pixels deepface also returns are dropped).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedFace {
+    #[serde(alias = "facial_area")]
+    pub facial_area: FaceBox,
+    #[serde(default)]
+    pub confidence: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectResult {
+    pub frame: String,
+    pub faces: Vec<DetectedFace>,
+}
+
+
+//_____________Trait _________________________
+/// A typed sidecar answer: the command it answers and the checks serde can't express.
+pub trait DeepFaceResult: DeserializeOwned {
+    const CMD: &'static str;
+
+    fn validate(&self) -> Result<(), String>;
+}
+
+fn check_box(field: &str, area: &FaceBox) -> Result<(), String> {
+    let values = [area.x, area.y, area.w, area.h];
+    if values.iter().any(|v| !v.is_finite()) || area.w < 0.0 || area.h < 0.0 {
+        return Err(format!("{}: invalid face box {:?}", field, values));
+    }
+    Ok(())
+}
+
+fn check_scores(field: &str, scores: &Option<BTreeMap<String, f64>>) -> Result<(), String> {
+    match scores.iter().flatten().find(|(_, score)| !score.is_finite() || **score < 0.0) {
+        Some((class, score)) => Err(format!("{}.{}: invalid score {}", field, class, score)),
+        None => Ok(()),
+    }
+}
+
+impl DeepFaceResult for AnalyzeResult {
+    const CMD: &'static str = "analyze";
+
+    fn validate(&self) -> Result<(), String> {
+        for (i, face) in self.faces.iter().enumerate() {
+            check_box(&format!("result[{}].region", i), &face.region)?;
+            check_scores(&format!("result[{}].emotion", i), &face.emotion)?;
+            check_scores(&format!("result[{}].gender", i), &face.gender)?;
+            check_scores(&format!("result[{}].race", i), &face.race)?;
+            if face.age.is_some_and(|age| !age.is_finite() || age < 0.0) {
+                return Err(format!("result[{}].age: invalid age {:?}", i, face.age));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DeepFaceResult for VerifyResult {
+    const CMD: &'static str = "verify";
+
+    fn validate(&self) -> Result<(), String> {
+        if !self.distance.is_finite() || !self.threshold.is_finite() {
+            return Err(format!("invalid distance {} / threshold {}", self.distance, self.threshold));
+        }
+        if let Some(areas) = &self.facial_areas {
+            check_box("facial_areas.img1", &areas.img1)?;
+            check_box("facial_areas.img2", &areas.img2)?;
+        }
+        Ok(())
+    }
+}
+
+impl DeepFaceResult for DetectResult {
+    const CMD: &'static str = "detect";
+
+    fn validate(&self) -> Result<(), String> {
+        for (i, face) in self.faces.iter().enumerate() {
+            check_box(&format!("faces[{}].facial_area", i), &face.facial_area)?;
+        }
+        Ok(())
+    }
+}
+
+
+//_____________fn ____________________________
+
+/// The typed `data` of a sidecar answer.
+pub fn parse<T: DeepFaceResult>(answer: Value) -> Result<T, AppError> {
+    if answer["status"] != "ok" {
+        return Err(AppError::DeepFace(format!("{} failed: {}", T::CMD, answer["data"]["message"].as_str().unwrap_or("unknown error"))));
+    }
+    let mismatch = |reason: String| AppError::DeepFace(format!("Unexpected {} answer from deepface_cli: {}", T::CMD, reason));
+    let result: T = serde_json::from_value(answer["data"].clone()).map_err(|e| mismatch(e.to_string()))?;
+    result.validate().map_err(mismatch)?;
+    Ok(result)
+}
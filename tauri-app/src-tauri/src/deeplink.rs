@@ -0,0 +1,141 @@
+// src/deeplink.rs
+//
+// faceapp:// links, so a Premiere script (ExtendScript `File.execute`, a batch file, …) can launch or focus
+// the app and run a command in it, e.g. `faceapp://analyze_clip?path=C:/…/clip.mp4&everySecs=2`.
+// - the host is the registry command, the query its payload: values are converted to the field types the
+//   command declares (payloads.rs), so `everySecs=2` arrives as a number and `clipIds=1,2` as an array
+// - runs through registry::dispatch like a WS request (same validation and license gating), but only the
+//   commands in DEEP_LINK_COMMANDS: any web page can open a faceapp:// link
+// - the outcome is emitted as DEEP_LINK_EVENT { url, command, result | error }
+// - a second launch with a link (Windows / Linux) is forwarded to the running instance by the
+//   single-instance plugin, which hands the url to on_open_url
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+use tracing::{info, warn};
+
+use crate::error::AppError;
+use crate::{events, instance};
+use crate::payloads::Kind;
+use crate::registry::{self, CommandContext};
+
+
+//____________Const___________
+pub const SCHEME: &str = "faceapp"; // also in tauri.conf.json plugins.deep-link
+pub const DEEP_LINK_EVENT: &str = "deep-link";
+const DEEP_LINK_COMMANDS: &[&str] = &["analyze_clip", "add_clip", "open_project"];
+
+
+//_____________Struct _________________________
+/// DEEP_LINK_EVENT payload.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepLinkOutcome {
+    pub url: String,
+    pub command: Option<String>,
+    pub result: Option<Value>,
+    pub error: Option<String>,
+}
+
+
+//_____________fn ____________________________
+
+// A query value as the JSON type the command expects; left as a string when it doesn't parse,
+// so the payload check reports it like any other mistyped field
+fn convert(value: &str, kind: Option<Kind>) -> Value {
+    let parsed = match kind {
+        Some(Kind::String) => None,
+        Some(Kind::Integer) => value.parse::<i64>().ok().map(Value::from),
+        Some(Kind::Number) => value.parse::<f64>().ok().filter(|n| n.is_finite()).map(Value::from),
+        Some(Kind::Boolean) => match value {
+            "true" | "1" => Some(Value::Bool(true)),
+            "false" | "0" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        Some(Kind::Array) if !value.starts_with('[') => {
+            Some(Value::Array(value.split(',').filter(|v| !v.is_empty()).map(|v| convert(v, None)).collect()))
+        }
+        Some(Kind::Array) | Some(Kind::Object) | None => serde_json::from_str(value).ok(),
+    };
+    parsed.unwrap_or_else(|| Value::String(value.to_string()))
+}
+
+/// `faceapp://<command>?a=1&b=x` -> (command, payload).
+pub fn parse(url: &Url) -> Result<(String, Value), AppError> {
+    if url.scheme() != SCHEME {
+        return Err(AppError::InvalidInput(format!("Not a {}:// link: {}", SCHEME, url)));
+    }
+    // Windows may add a slash (faceapp://analyze_clip/?…), some launchers drop the host (faceapp:///analyze_clip)
+    let command = url
+        .host_str()
+        .filter(|h| !h.is_empty())
+        .map(str::to_string)
+        .or_else(|| url.path_segments()?.find(|s| !s.is_empty()).map(str::to_string))
+        .ok_or_else(|| AppError::InvalidInput(format!("No command in {}", url)))?;
+    if !DEEP_LINK_COMMANDS.contains(&command.as_str()) {
+        return Err(AppError::InvalidInput(format!(
+            "'{}' can't be run from a link (expected {})",
+            command,
+            DEEP_LINK_COMMANDS.join(", ")
+        )));
+    }
+
+    let fields = registry::list().into_iter().find(|c| c.name == command).and_then(|c| c.params).unwrap_or_default();
+    let mut payload = Map::new();
+    for (key, value) in url.query_pairs() {
+        let kind = fields.iter().find(|f| f.name == key).map(|f| f.kind);
+        payload.insert(key.into_owned(), convert(&value, kind));
+    }
+    Ok((command, Value::Object(payload)))
+}
+
+/// Parse and run one link; the outcome is logged and emitted.
+pub async fn handle(app_handle: AppHandle, url: Url) {
+    instance::focus_main(&app_handle);
+    let mut outcome = DeepLinkOutcome { url: url.to_string(), command: None, result: None, error: None };
+    let ran = match parse(&url) {
+        Ok((command, payload)) => {
+            info!("🔗 Deep link: {} {}", command, payload);
+            outcome.command = Some(command.clone());
+            registry::dispatch(&command, payload, CommandContext::new(app_handle.clone())).await
+        }
+        Err(e) => Err(e),
+    };
+    match ran {
+        Ok(result) => outcome.result = Some(result),
+        Err(e) => {
+            warn!("⚠️ Deep link {} failed: {}", url, e);
+            outcome.error = Some(e.to_string());
+        }
+    }
+    events::emit_all_surfaces(&app_handle, DEEP_LINK_EVENT, &outcome);
+}
+
+/// Handle links from now on, and the one the app was launched with. Call at the end of setup.
+pub fn init(app_handle: &AppHandle) {
+    let deep_link = app_handle.deep_link();
+
+    // Installed builds register the scheme in the installer; dev builds do it at startup
+    #[cfg(all(debug_assertions, any(windows, target_os = "linux")))]
+    if let Err(e) = deep_link.register_all() {
+        warn!("⚠️ {}:// not registered: {}", SCHEME, e);
+    }
+
+    let app = app_handle.clone();
+    deep_link.on_open_url(move |event| {
+        for url in event.urls() {
+            tauri::async_runtime::spawn(handle(app.clone(), url));
+        }
+    });
+
+    match deep_link.get_current() {
+        Ok(urls) => {
+            for url in urls.unwrap_or_default() {
+                tauri::async_runtime::spawn(handle(app_handle.clone(), url));
+            }
+        }
+        Err(e) => warn!("⚠️ Launch link not read: {}", e),
+    }
+}
@@ -0,0 +1,130 @@
+// src/discovery.rs
+//
+// How external tools find the running app, now that settings.ws port can be 0 (any free port).
+// - mDNS / DNS-SD: the WS listener is advertised as `_faceapp._tcp` with its bound port, and TXT records
+//   version (app), protocol (PROTOCOL_VERSION), tls, path (settings.ws path) and http (httpPort, when on)
+// - re-advertised on every (re)start of the WS server, withdrawn when it stops; settings.ws mdns turns it off
+// - the address advertised is settings.ws host (127.0.0.1 by default: discoverable, reachable from this machine
+//   only); a wildcard host (0.0.0.0 / ::) advertises every interface
+// - `get_server_endpoints` lists the open listeners (ws / wss, local socket, http) with their URLs
+// Browse with `dns-sd -B _faceapp._tcp` (macOS / Windows Bonjour) or `avahi-browse -r _faceapp._tcp` (Linux).
+
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+use crate::http_api;
+use crate::settings::{self, WsSettings};
+use crate::websocket;
+
+
+//____________Const___________
+pub const SERVICE_TYPE: &str = "_faceapp._tcp.local.";
+pub const MDNS: bool = true; // default of settings.ws mdns
+
+
+//_____________Struct _________________________
+/// An open listener, as returned by `get_server_endpoints`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerEndpoint {
+    pub kind: &'static str,  // "ws" | "wss" | "local" | "http"
+    pub address: String,     // "127.0.0.1:8080", "unix:/tmp/faceapp.sock", …
+    pub url: Option<String>, // what a client connects to (None for the local socket: no URL scheme)
+}
+
+struct Advertised {
+    daemon: ServiceDaemon,
+    fullname: String,
+}
+
+
+//_____________Globals _______________________
+static ADVERTISED: Lazy<Mutex<Option<Advertised>>> = Lazy::new(|| Mutex::new(None));
+
+
+//_____________fn ____________________________
+
+/// Advertise the WS listener bound to `config.host`:`config.port` (the bound port, not 0), replacing
+/// the previous advertisement. Called by websocket.rs once it listens.
+pub fn advertise(app_handle: &AppHandle, config: &WsSettings) {
+    withdraw();
+    if !config.mdns {
+        return;
+    }
+    match register(app_handle, config) {
+        Ok(advertised) => {
+            info!("📣 Advertised {} on port {} (mDNS)", advertised.fullname, config.port);
+            *ADVERTISED.lock().unwrap_or_else(|p| p.into_inner()) = Some(advertised);
+        }
+        Err(e) => warn!("❌ mDNS advertisement failed, the server is only found at its configured port: {}", e),
+    }
+}
+
+fn register(app_handle: &AppHandle, config: &WsSettings) -> Result<Advertised, mdns_sd::Error> {
+    let package = app_handle.package_info();
+    let host = sysinfo::System::host_name().unwrap_or_else(|| "faceapp".to_string());
+    let host = host.split('.').next().unwrap_or("faceapp"); // "studio-mac.lan" -> "studio-mac"
+    let instance = format!("{} ({})", package.name, host);
+    let host_name = format!("{}.local.", host);
+
+    let mut properties = HashMap::new();
+    properties.insert("version".to_string(), package.version.to_string());
+    properties.insert("protocol".to_string(), websocket::PROTOCOL_VERSION.to_string());
+    properties.insert("tls".to_string(), config.tls.to_string());
+    properties.insert("path".to_string(), config.path.clone());
+    if config.http_port != 0 {
+        properties.insert("http".to_string(), config.http_port.to_string());
+    }
+
+    // A name (localhost…) resolves here only: advertise loopback
+    let service = match config.host.parse::<IpAddr>() {
+        Ok(ip) if ip.is_unspecified() => ServiceInfo::new(SERVICE_TYPE, &instance, &host_name, "", config.port, properties)?.enable_addr_auto(),
+        Ok(ip) => ServiceInfo::new(SERVICE_TYPE, &instance, &host_name, ip, config.port, properties)?,
+        Err(_) => ServiceInfo::new(SERVICE_TYPE, &instance, &host_name, IpAddr::V4(Ipv4Addr::LOCALHOST), config.port, properties)?,
+    };
+    let fullname = service.get_fullname().to_string();
+    let daemon = ServiceDaemon::new()?;
+    daemon.register(service)?;
+    Ok(Advertised { daemon, fullname })
+}
+
+/// Stop advertising (the WS server stopped or is restarting).
+pub fn withdraw() {
+    if let Some(advertised) = ADVERTISED.lock().unwrap_or_else(|p| p.into_inner()).take() {
+        let _ = advertised.daemon.unregister(&advertised.fullname); // sends the goodbye packets
+        let _ = advertised.daemon.shutdown();
+    }
+}
+
+/// Open listeners of the WS server and the HTTP API.
+pub fn endpoints() -> Vec<ServerEndpoint> {
+    let status = websocket::status();
+    let path = settings::get().ws.path;
+    let mut endpoints = Vec::new();
+    if let Some(address) = status.listening {
+        let kind = if status.tls { "wss" } else { "ws" };
+        endpoints.push(ServerEndpoint { kind, url: Some(format!("{}://{}{}", kind, address, path)), address });
+    }
+    if let Some(address) = status.local {
+        endpoints.push(ServerEndpoint { kind: "local", address, url: None });
+    }
+    if let Some(address) = http_api::listening() {
+        endpoints.push(ServerEndpoint { kind: "http", url: Some(format!("http://{}{}", address, http_api::COMMAND_PATH)), address });
+    }
+    endpoints
+}
+
+
+//_____________Commands ________________________
+
+/// Example: `invoke("get_server_endpoints")` -> `[{ kind: "ws", address: "127.0.0.1:52814", url: "ws://127.0.0.1:52814" }]`
+#[tauri::command]
+pub fn get_server_endpoints() -> Vec<ServerEndpoint> {
+    endpoints()
+}
@@ -0,0 +1,213 @@
+// src/emotion_stats.rs
+//
+// Emotion statistics of a clip or a project, computed from the stored analyses (database analyses table),
+// shaped for charts:
+// - `distribution`: how many faces had each dominant emotion, most frequent first (pie / bar chart)
+// - `arc`: average score of every emotion over consecutive windows of the clip (line chart), the window
+//   defaults to ARC_WINDOW_SECS
+// - `peaks`: the PEAKS_PER_EMOTION strongest faces of each emotion, with their marker to jump to
+// A project summary has the project-wide distribution and peaks, and a short summary per clip (no arc:
+// timelines of different clips don't line up).
+// Scores are deepface's, 0..100.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::database::{self, AnalyzedFace};
+use crate::error::AppError;
+
+
+//____________Const___________
+pub const EMOTIONS: &[&str] = &["angry", "disgust", "fear", "happy", "sad", "surprise", "neutral"];
+pub const ARC_WINDOW_SECS: f64 = 10.0;
+const MIN_ARC_WINDOW_SECS: f64 = 0.5;
+const PEAKS_PER_EMOTION: usize = 3;
+
+
+//_____________Struct _________________________
+/// Faces whose dominant emotion was `emotion`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmotionShare {
+    pub emotion: String,
+    pub count: usize,
+    pub share: f64, // 0..1 of the analyzed faces
+}
+
+/// Average scores of one window of the timeline.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArcPoint {
+    pub start: f64, // seconds
+    pub end: f64,
+    pub faces: usize,
+    pub scores: BTreeMap<String, f64>, // emotion -> average score
+    pub dominant: Option<String>,
+}
+
+/// One of the strongest faces of an emotion.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmotionPeak {
+    pub emotion: String,
+    pub score: f64,
+    pub clip_id: i64,
+    pub marker_id: i64,
+    pub timestamp: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipEmotionSummary {
+    pub clip_id: i64,
+    pub faces: usize, // analyzed faces
+    pub dominant: Option<String>,
+    pub distribution: Vec<EmotionShare>,
+    pub window_secs: f64,
+    pub arc: Vec<ArcPoint>,
+    pub peaks: Vec<EmotionPeak>,
+}
+
+/// A clip in a project summary.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipEmotions {
+    pub clip_id: i64,
+    pub path: String,
+    pub faces: usize,
+    pub dominant: Option<String>,
+    pub distribution: Vec<EmotionShare>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectEmotionSummary {
+    pub project_id: i64,
+    pub faces: usize,
+    pub dominant: Option<String>,
+    pub distribution: Vec<EmotionShare>,
+    pub peaks: Vec<EmotionPeak>,
+    pub clips: Vec<ClipEmotions>,
+}
+
+
+//_____________fn ____________________________
+
+fn dominant(face: &AnalyzedFace) -> Option<&str> {
+    face.result["dominant_emotion"].as_str()
+}
+
+fn score(face: &AnalyzedFace, emotion: &str) -> Option<f64> {
+    face.result["emotion"][emotion].as_f64()
+}
+
+fn distribution(faces: &[AnalyzedFace]) -> Vec<EmotionShare> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for emotion in faces.iter().filter_map(dominant) {
+        *counts.entry(emotion).or_default() += 1;
+    }
+    let mut shares: Vec<EmotionShare> = counts
+        .into_iter()
+        .map(|(emotion, count)| EmotionShare { emotion: emotion.to_string(), count, share: count as f64 / faces.len() as f64 })
+        .collect();
+    shares.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.emotion.cmp(&b.emotion)));
+    shares
+}
+
+// Windows from 0 to the last face; empty windows are kept (faces 0) so the line has no gaps in time
+fn arc(faces: &[AnalyzedFace], window_secs: f64) -> Vec<ArcPoint> {
+    let Some(last) = faces.iter().map(|f| f.timestamp).max_by(f64::total_cmp) else { return Vec::new() };
+    let windows = (last / window_secs).floor() as usize + 1;
+    (0..windows)
+        .map(|i| {
+            let (start, end) = (i as f64 * window_secs, (i + 1) as f64 * window_secs);
+            let in_window: Vec<&AnalyzedFace> = faces.iter().filter(|f| f.timestamp >= start && f.timestamp < end).collect();
+            let scores: BTreeMap<String, f64> = EMOTIONS
+                .iter()
+                .filter_map(|emotion| {
+                    let values: Vec<f64> = in_window.iter().filter_map(|f| score(f, emotion)).collect();
+                    (!values.is_empty()).then(|| (emotion.to_string(), values.iter().sum::<f64>() / values.len() as f64))
+                })
+                .collect();
+            let dominant = scores.iter().max_by(|a, b| a.1.total_cmp(b.1)).map(|(emotion, _)| emotion.clone());
+            ArcPoint { start, end, faces: in_window.len(), scores, dominant }
+        })
+        .collect()
+}
+
+fn peaks(faces: &[AnalyzedFace]) -> Vec<EmotionPeak> {
+    let mut peaks = Vec::new();
+    for emotion in EMOTIONS {
+        let mut scored: Vec<(&AnalyzedFace, f64)> = faces.iter().filter_map(|f| Some((f, score(f, emotion)?))).collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        peaks.extend(scored.into_iter().take(PEAKS_PER_EMOTION).map(|(face, score)| EmotionPeak {
+            emotion: emotion.to_string(),
+            score,
+            clip_id: face.clip_id,
+            marker_id: face.marker_id,
+            timestamp: face.timestamp,
+        }));
+    }
+    peaks
+}
+
+/// Distribution, arc and peaks of a clip's analyzed faces.
+pub fn clip_summary(clip_id: i64, window_secs: Option<f64>) -> Result<ClipEmotionSummary, AppError> {
+    let window_secs = window_secs.unwrap_or(ARC_WINDOW_SECS);
+    if window_secs.is_nan() || window_secs < MIN_ARC_WINDOW_SECS {
+        return Err(AppError::InvalidInput(format!("windowSecs must be at least {}", MIN_ARC_WINDOW_SECS)));
+    }
+    let faces = database::clip_analyses(clip_id)?;
+    let distribution = distribution(&faces);
+    Ok(ClipEmotionSummary {
+        clip_id,
+        faces: faces.len(),
+        dominant: distribution.first().map(|d| d.emotion.clone()),
+        distribution,
+        window_secs,
+        arc: arc(&faces, window_secs),
+        peaks: peaks(&faces),
+    })
+}
+
+/// Distribution and peaks of a project's analyzed faces, with a distribution per clip.
+pub fn project_summary(project_id: i64) -> Result<ProjectEmotionSummary, AppError> {
+    let faces = database::project_analyses(project_id)?;
+    let clips = database::list_clips(project_id)?
+        .into_iter()
+        .map(|clip| {
+            let clip_faces: Vec<AnalyzedFace> = faces.iter().filter(|f| f.clip_id == clip.id).cloned().collect();
+            let distribution = distribution(&clip_faces);
+            ClipEmotions {
+                clip_id: clip.id,
+                path: clip.path,
+                faces: clip_faces.len(),
+                dominant: distribution.first().map(|d| d.emotion.clone()),
+                distribution,
+            }
+        })
+        .collect();
+    let distribution = distribution(&faces);
+    Ok(ProjectEmotionSummary {
+        project_id,
+        faces: faces.len(),
+        dominant: distribution.first().map(|d| d.emotion.clone()),
+        distribution,
+        peaks: peaks(&faces),
+        clips,
+    })
+}
+
+
+//_____________Commands ________________________
+
+/// Example: `invoke("get_clip_emotion_summary", { clipId: 1, windowSecs: 5 })`.
+#[tauri::command]
+pub async fn get_clip_emotion_summary(clip_id: i64, window_secs: Option<f64>) -> Result<ClipEmotionSummary, AppError> {
+    database::blocking(move || clip_summary(clip_id, window_secs)).await
+}
+
+#[tauri::command]
+pub async fn get_project_emotion_summary(project_id: i64) -> Result<ProjectEmotionSummary, AppError> {
+    database::blocking(move || project_summary(project_id)).await
+}
@@ -0,0 +1,103 @@
+// src/encryption.rs
+//
+// Optional encryption of the sensitive database fields (settings.database encryptSensitive, off by default):
+// marker metadata (deepface emotion scores, face regions), job results, and the undo log snapshots holding them.
+// - AES-256-GCM with a random nonce per value, stored as "enc:v1:<base64(nonce + ciphertext)>"
+// - the key is created on first use and kept as a secret (secrets.rs: OS keychain, encrypted file fallback),
+//   never in the database or its backups
+// - both forms are read, and `follow_settings` converts the existing rows when the setting changes,
+//   so an unencrypted database keeps working and gets encrypted in place
+
+use aes_gcm::aead::KeyInit;
+use aes_gcm::Aes256Gcm;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use once_cell::sync::OnceCell;
+use tracing::{error, info};
+
+use crate::error::AppError;
+use crate::{crash, database, secrets, settings};
+
+
+//____________Const___________
+const SEALED_PREFIX: &str = "enc:v1:";
+
+
+//_____________Globals _______________________
+static CIPHER: OnceCell<Aes256Gcm> = OnceCell::new();
+
+
+//_____________fn ____________________________
+fn key_error(e: AppError) -> AppError {
+    AppError::Db(format!("Database key unavailable: {}", e))
+}
+
+fn load_cipher() -> Result<Aes256Gcm, AppError> {
+    let key = match secrets::get_secret(secrets::DATABASE_KEY).map_err(key_error)? {
+        Some(encoded) => STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| AppError::Db(format!("Stored database key is unreadable: {}", e)))?,
+        None => {
+            let key = rand::random::<[u8; 32]>().to_vec();
+            secrets::set_secret(secrets::DATABASE_KEY, &STANDARD.encode(&key)).map_err(key_error)?;
+            info!("🔑 Database encryption key created");
+            key
+        }
+    };
+    Aes256Gcm::new_from_slice(&key).map_err(|_| AppError::Db("Stored database key has the wrong length".into()))
+}
+
+fn cipher() -> Result<&'static Aes256Gcm, AppError> {
+    CIPHER.get_or_try_init(load_cipher)
+}
+
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(SEALED_PREFIX)
+}
+
+pub fn seal(plain: &str) -> Result<String, AppError> {
+    let sealed = secrets::seal_with(cipher()?, plain.as_bytes()).ok_or_else(|| AppError::Db("Failed to encrypt a database field".into()))?;
+    Ok(format!("{}{}", SEALED_PREFIX, sealed))
+}
+
+/// The plain value of a stored field, sealed or not.
+pub fn open(stored: &str) -> Result<String, AppError> {
+    let Some(encoded) = stored.strip_prefix(SEALED_PREFIX) else {
+        return Ok(stored.to_string());
+    };
+    secrets::open_with(cipher()?, encoded)
+        .and_then(|plain| String::from_utf8(plain).ok())
+        .ok_or_else(|| AppError::Db("Can't decrypt a database field (key missing or changed?)".into()))
+}
+
+/// What to store for a sensitive value: sealed while settings.database encryptSensitive is on.
+pub fn protect(plain: String) -> Result<String, AppError> {
+    if settings::get().database.encrypt_sensitive {
+        seal(&plain)
+    } else {
+        Ok(plain)
+    }
+}
+
+/// Encrypt / decrypt the stored fields to match the setting, at startup and on every change.
+/// Call once the database is open.
+pub fn follow_settings() {
+    let mut rx = settings::watch();
+    crash::spawn("db-encryption", async move {
+        let mut applied: Option<bool> = None;
+        loop {
+            let wanted = rx.borrow_and_update().database.encrypt_sensitive;
+            if applied != Some(wanted) {
+                match database::blocking(move || database::convert_sensitive(wanted)).await {
+                    Ok(0) => {}
+                    Ok(n) => info!("🔐 {} database fields {}", n, if wanted { "encrypted" } else { "decrypted" }),
+                    Err(e) => error!("❌ Database fields not converted: {}", e),
+                }
+                applied = Some(wanted);
+            }
+            if rx.changed().await.is_err() {
+                return;
+            }
+        }
+    });
+}
@@ -0,0 +1,171 @@
+// src/error.rs
+//
+// One error type for every command (Tauri invoke and WS).
+// Serialized as:
+//   { "code": "deepface", "message": "DeepFace WS not started", "retryable": true }
+//   { "code": "license", "message": "...", "retryable": false, "license": { "kind": "expired", "message": "..." } }
+//   { "code": "invalid_payload", "message": "...", "retryable": false, "fields": [{ "field": "clipId", "problem": "missing (integer)" }] }
+// so the frontend / CEP panel can branch on `code` (retry vs re-auth vs bug report).
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde::Serialize as DeriveSerialize;
+use std::fmt;
+
+use crate::license::LicenseError;
+
+
+//_____________Struct _________________________
+/// One problem found in a command payload (`field` is empty for the payload as a whole).
+#[derive(Debug, Clone, DeriveSerialize)]
+pub struct FieldError {
+    pub field: String,
+    pub problem: String,
+}
+
+
+//_____________Enum _________________________
+#[derive(Debug, Clone)]
+pub enum AppError {
+    Ws(String),                 // local WebSocket server / protocol
+    DeepFace(String),           // sidecar not running, crashed, bad answer
+    License(LicenseError),      // license server said no, or couldn't be reached
+    Db(String),                 // local SQLite database
+    Media(String),              // ffmpeg missing or failed
+    Update(String),             // update check / download / install (updater.rs)
+    Sync(String),               // cloud sync: server unreachable or refused (sync.rs)
+    Plugin(String),             // a plugin command failed or broke the plugin ABI (plugins.rs)
+    Transcription(String),      // whisper_cli sidecar missing, failed or gave no transcript (whisper.rs)
+    Io(String),                 // files, paths, spawning processes
+    Settings(String),           // rejected settings patch
+    InvalidInput(String),       // bad arguments / payload
+    InvalidPayload(String, Vec<FieldError>), // (command, problems) from payloads.rs
+    UnknownCommand(String),
+    FeatureNotLicensed(String), // gated by features.rs
+    UnsupportedProtocol(String), // WS client asked for a protocol version we can't speak
+    RateLimited(String),        // WS client over its request budget
+    PayloadTooLarge(String),    // WS message over settings.ws maxMessageBytes
+    Cancelled(String),          // job stopped by cancel_job
+    Unauthorized(String),       // WS client without a valid access token (ws_auth.rs)
+    PermissionDenied(String),   // WS client whose role can't run the command (ws_auth.rs)
+}
+
+impl AppError {
+    /// Stable machine-readable category, also used as `code` in WsResponse.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Ws(_) => "ws",
+            AppError::DeepFace(_) => "deepface",
+            AppError::License(_) => "license",
+            AppError::Db(_) => "db",
+            AppError::Media(_) => "media",
+            AppError::Update(_) => "update",
+            AppError::Sync(_) => "sync",
+            AppError::Plugin(_) => "plugin",
+            AppError::Transcription(_) => "transcription",
+            AppError::Io(_) => "io",
+            AppError::Settings(_) => "settings",
+            AppError::InvalidInput(_) => "invalid_input",
+            AppError::InvalidPayload(..) => "invalid_payload",
+            AppError::UnknownCommand(_) => "unknown_command",
+            AppError::FeatureNotLicensed(_) => "feature_not_licensed",
+            AppError::UnsupportedProtocol(_) => "unsupported_protocol",
+            AppError::RateLimited(_) => "rate_limited",
+            AppError::PayloadTooLarge(_) => "payload_too_large",
+            AppError::Cancelled(_) => "cancelled",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::PermissionDenied(_) => "permission_denied",
+        }
+    }
+
+    /// Whether trying again later can succeed without the user changing anything.
+    pub fn retryable(&self) -> bool {
+        match self {
+            AppError::Ws(_) | AppError::DeepFace(_) | AppError::Db(_) | AppError::Update(_) | AppError::Sync(_) | AppError::RateLimited(_) => true,
+            AppError::License(err) => err.is_offline(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::License(err) => write!(f, "{}", err),
+            AppError::UnknownCommand(cmd) => write!(f, "Unknown command: {}", cmd),
+            AppError::FeatureNotLicensed(feature) => write!(f, "Feature not licensed: {}", feature),
+            AppError::InvalidPayload(command, problems) => {
+                let problems: Vec<String> = problems
+                    .iter()
+                    .map(|p| if p.field.is_empty() { p.problem.clone() } else { format!("{}: {}", p.field, p.problem) })
+                    .collect();
+                write!(f, "Invalid payload for {}: {}", command, problems.join("; "))
+            }
+            AppError::Ws(msg)
+            | AppError::DeepFace(msg)
+            | AppError::Db(msg)
+            | AppError::Media(msg)
+            | AppError::Update(msg)
+            | AppError::Sync(msg)
+            | AppError::Plugin(msg)
+            | AppError::Transcription(msg)
+            | AppError::Io(msg)
+            | AppError::Settings(msg)
+            | AppError::InvalidInput(msg)
+            | AppError::UnsupportedProtocol(msg)
+            | AppError::RateLimited(msg)
+            | AppError::PayloadTooLarge(msg)
+            | AppError::Cancelled(msg)
+            | AppError::Unauthorized(msg)
+            | AppError::PermissionDenied(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 4)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("retryable", &self.retryable())?;
+        match self {
+            AppError::License(err) => state.serialize_field("license", err)?,
+            AppError::InvalidPayload(_, problems) => state.serialize_field("fields", problems)?,
+            _ => state.skip_field("details")?,
+        }
+        state.end()
+    }
+}
+
+
+//_____________Conversions ___________________
+impl From<LicenseError> for AppError {
+    fn from(err: LicenseError) -> Self {
+        AppError::License(err)
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(err: std::io::Error) -> Self {
+        AppError::Io(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        AppError::InvalidInput(err.to_string())
+    }
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for AppError {
+    fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+        AppError::Ws(err.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(err: rusqlite::Error) -> Self {
+        AppError::Db(err.to_string())
+    }
+}
@@ -0,0 +1,213 @@
+// src/events.rs
+//
+// Event streams pushed to WebSocket clients that asked for them.
+// - A CEP client opts in with `{ "command": "subscribe", "payload": { "events": ["marker-added"] } }`
+//   and out with `unsubscribe` (no `events` = all of them)
+// - `publish(event, data)` fans out only to the connections subscribed to `event`, as
+//   `{ "status": "event", "event": "marker-added", "data": { … } }` (no requestId: nobody asked for this frame)
+// - Each connection registers a queue when it opens; websocket.rs writes the queued frames between requests
+//   (sessions.rs also uses it to hand over replies of a resumed session)
+// - `emit_all_surfaces(app, event, payload)` = `app.emit` to the webview + `publish` to WS subscribers,
+//   so app events (cep-status, settings-changed, …) reach the CEP panel without extra code;
+//   `emit_global` does the same for code without an app handle (database.rs), once `init` kept it
+
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tracing::{debug, warn};
+
+use crate::error::AppError;
+use crate::protocol::EventFrame;
+use crate::{app_status, camera, deepface_resources, deeplink, features, instance, license, migrations, models, preflight, settings, sync, updater, watcher};
+
+
+//____________Const___________
+pub const DEEPFACE_PROGRESS: &str = "deepface-progress"; // { requestId, cmd, stage: "warming_up" | "sent" | "done" | "failed" }
+pub const DEEPFACE_STATUS: &str = "deepface-status";     // deepFaceProcess::SidecarStatus after deepface_start / _stop, idle shutdown
+pub const LICENSE_STATUS: &str = "license-status";       // same payload as license::LICENSE_EVENT
+pub const MARKER_ADDED: &str = "marker-added";           // the stored Marker
+pub const CEP_STATUS: &str = "cep-status";               // i18n::Localized status line shown in the webview
+pub const CEP_CONNECTION: &str = "cep-connection";       // websocket::ConnectionEvent: a client connected / disconnected / was rejected
+pub const JOB_PROGRESS: &str = "job-progress";           // the Job (jobs.rs) after each change
+pub const PROJECT_OPENED: &str = "project-opened";       // the Project that became current
+pub const MARKERS_CHANGED: &str = "markers-changed";     // database::OperationChange after an undo / redo
+pub const DATABASE_RESTORED: &str = "database-restored"; // database::DatabaseStatus after restore_database
+pub const DB_CHANGED: &str = "db-changed";               // database::DbChange, one per committed row change
+
+/// Events a WS client can subscribe to; the last ones are webview events bridged by `emit_all_surfaces`.
+pub const EVENTS: &[&str] = &[
+    DEEPFACE_PROGRESS,
+    DEEPFACE_STATUS,
+    LICENSE_STATUS,
+    MARKER_ADDED,
+    JOB_PROGRESS,
+    PROJECT_OPENED,
+    MARKERS_CHANGED,
+    DATABASE_RESTORED,
+    DB_CHANGED,
+    CEP_STATUS,
+    CEP_CONNECTION,
+    license::LICENSE_STATE_EVENT,
+    license::LICENSE_EXPIRING_EVENT,
+    settings::SETTINGS_EVENT,
+    features::FEATURES_EVENT,
+    updater::UPDATE_EVENT,
+    migrations::MIGRATION_FAILED_EVENT,
+    sync::SYNC_EVENT,
+    sync::SYNC_CONFLICT_EVENT,
+    deeplink::DEEP_LINK_EVENT,
+    instance::SECOND_INSTANCE_EVENT,
+    camera::CAMERA_EMOTIONS_EVENT,
+    camera::CAMERA_STATE_EVENT,
+    models::MODEL_DOWNLOAD_EVENT,
+    preflight::PREFLIGHT_EVENT,
+    deepface_resources::DEEPFACE_RESOURCES_EVENT,
+    app_status::APP_STATUS_EVENT,
+    watcher::WATCH_EVENT,
+];
+
+
+//_____________Struct _________________________
+struct Subscriber {
+    queue: UnboundedSender<String>,
+    events: BTreeSet<&'static str>,
+}
+
+
+//_____________Globals _______________________
+static SUBSCRIBERS: Lazy<Mutex<HashMap<u64, Subscriber>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static APP: OnceCell<AppHandle> = OnceCell::new();
+
+
+//_____________fn ____________________________
+fn subscribers() -> MutexGuard<'static, HashMap<u64, Subscriber>> {
+    SUBSCRIBERS.lock().unwrap_or_else(|p| p.into_inner())
+}
+
+/// Open the event queue of a new connection (subscribed to nothing yet).
+pub fn register(connection: u64) -> UnboundedReceiver<String> {
+    let (queue, rx) = mpsc::unbounded_channel();
+    subscribers().insert(connection, Subscriber { queue, events: BTreeSet::new() });
+    rx
+}
+
+pub fn unregister(connection: u64) {
+    subscribers().remove(&connection);
+}
+
+/// What `connection` is subscribed to (saved by sessions.rs when it closes).
+pub fn subscriptions(connection: u64) -> Vec<String> {
+    subscribers()
+        .get(&connection)
+        .map(|s| s.events.iter().map(|e| e.to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Queue an already serialized frame on `connection`; gives it back when that connection is gone.
+pub fn push(connection: u64, frame: String) -> Result<(), String> {
+    match subscribers().get(&connection) {
+        Some(s) => s.queue.send(frame).map_err(|e| e.0),
+        None => Err(frame),
+    }
+}
+
+// Names as given by the client -> our &'static names; unknown ones are rejected all together
+fn known_events(names: &[String]) -> Result<Vec<&'static str>, AppError> {
+    let unknown: Vec<&str> = names.iter().map(String::as_str).filter(|n| !EVENTS.contains(n)).collect();
+    if !unknown.is_empty() {
+        return Err(AppError::InvalidInput(format!(
+            "Unknown event(s): {} (expected {})",
+            unknown.join(", "),
+            EVENTS.join(", ")
+        )));
+    }
+    Ok(EVENTS.iter().copied().filter(|e| names.iter().any(|n| n == e)).collect())
+}
+
+/// Add `events` to the connection's subscriptions; returns everything it is now subscribed to.
+pub fn subscribe(connection: u64, events: &[String]) -> Result<Vec<&'static str>, AppError> {
+    let events = known_events(events)?;
+    let mut subscribers = subscribers();
+    let subscriber = subscribers
+        .get_mut(&connection)
+        .ok_or_else(|| AppError::Ws(format!("Connection {} is closed", connection)))?;
+    subscriber.events.extend(events);
+    Ok(subscriber.events.iter().copied().collect())
+}
+
+/// Remove `events` (all of them when `None`); returns what is left.
+pub fn unsubscribe(connection: u64, events: Option<&[String]>) -> Result<Vec<&'static str>, AppError> {
+    let events = events.map(known_events).transpose()?;
+    let mut subscribers = subscribers();
+    let subscriber = subscribers
+        .get_mut(&connection)
+        .ok_or_else(|| AppError::Ws(format!("Connection {} is closed", connection)))?;
+    match events {
+        Some(events) => {
+            for event in events {
+                subscriber.events.remove(event);
+            }
+        }
+        None => subscriber.events.clear(),
+    }
+    Ok(subscriber.events.iter().copied().collect())
+}
+
+/// `publish`, plus the event pushed to `connection` even when it isn't subscribed to it (a client that asked
+/// for a state change hears about it).
+pub fn publish_to<T: Serialize>(connection: Option<u64>, event: &str, data: &T) {
+    publish(event, data);
+    let Some(connection) = connection else { return };
+    if subscriptions(connection).iter().any(|e| e == event) {
+        return;
+    }
+    if let Some(frame) = event_frame(event, data) {
+        let _ = push(connection, frame);
+    }
+}
+
+/// Keep the app handle for `emit_global`. Call from setup, before the database opens.
+pub fn init(app_handle: &AppHandle) {
+    let _ = APP.set(app_handle.clone());
+}
+
+/// `emit_all_surfaces` for callers without an app handle; WS subscribers only until `init` ran.
+pub fn emit_global<T: Serialize + Clone>(event: &str, payload: T) {
+    match APP.get() {
+        Some(app_handle) => emit_all_surfaces(app_handle, event, payload),
+        None => publish(event, &payload),
+    }
+}
+
+/// Emit `payload` to the webview and push it to the WS clients subscribed to `event`.
+pub fn emit_all_surfaces<T: Serialize + Clone>(app_handle: &AppHandle, event: &str, payload: T) {
+    publish(event, &payload);
+    if let Err(e) = app_handle.emit(event, payload) {
+        warn!("Failed to emit {} event: {}", event, e);
+    }
+}
+
+// `{ "status": "event", … }` frame of `data`
+fn event_frame<T: Serialize>(event: &str, data: &T) -> Option<String> {
+    match serde_json::to_value(data) {
+        Ok(data) => serde_json::to_string(&EventFrame::new(event, data)).ok(),
+        Err(e) => {
+            debug!("Event {} not serializable: {}", event, e);
+            None
+        }
+    }
+}
+
+/// Push `data` to every connection subscribed to `event`.
+pub fn publish<T: Serialize>(event: &str, data: &T) {
+    let mut subscribers = subscribers();
+    if !subscribers.values().any(|s| s.events.contains(event)) {
+        return;
+    }
+    let Some(frame) = event_frame(event, data) else { return };
+    // A closed queue means the connection is going away; drop it here rather than wait for unregister
+    subscribers.retain(|_, s| !s.events.contains(event) || s.queue.send(frame.clone()).is_ok());
+}
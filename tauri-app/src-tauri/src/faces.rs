@@ -0,0 +1,177 @@
+// src/faces.rs
+//
+// Face verification against a library of known people: `add_reference_face(name, image)` stores the deepface
+// embedding of the one face in the image, `identify_face(frame)` tells who the faces of a frame are
+// (face_search feature).
+// - embeddings come from the sidecar's `represent` command and are stored in reference_faces (database.rs),
+//   encrypted with the other sensitive columns
+// - embeddings of different models can't be compared: a frame is only matched against the references
+//   computed with the same model (argument, else settings.deepface defaultModel, else DEFAULT_MODEL)
+// - a face matches its closest reference when the cosine distance is under the model's threshold (deepface's
+//   own values); confidence goes from 1 (same embedding) to 0 (at the threshold)
+// - several images of one person can be added under the same name, the closest one wins
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::database::{self, ReferenceFace};
+use crate::deepFaceProcess::{self, MANAGER};
+use crate::error::AppError;
+use crate::{analysis, features};
+
+
+//____________Const___________
+pub const DEFAULT_MODEL: &str = "VGG-Face"; // deepface's default
+// Cosine distance under which two faces are the same person, per model (deepface verification thresholds)
+const THRESHOLDS: &[(&str, f64)] = &[
+    ("VGG-Face", 0.68),
+    ("Facenet", 0.40),
+    ("Facenet512", 0.30),
+    ("ArcFace", 0.68),
+    ("Dlib", 0.07),
+    ("SFace", 0.593),
+    ("OpenFace", 0.10),
+    ("DeepFace", 0.23),
+    ("DeepID", 0.015),
+    ("GhostFaceNet", 0.65),
+];
+const FALLBACK_THRESHOLD: f64 = 0.40;
+
+
+//_____________Struct _________________________
+/// The closest reference of one face found in a frame.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FaceMatch {
+    pub name: Option<String>, // None when even the closest reference is over the threshold
+    pub reference_id: i64,    // closest reference face
+    pub distance: f64,        // cosine distance to it
+    pub threshold: f64,
+    pub confidence: f64, // 0..1
+    pub facial_area: Value, // { x, y, w, h } in the frame
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Identification {
+    pub model: String,
+    pub faces: Vec<FaceMatch>, // one per face in the frame, best match first
+}
+
+
+//_____________fn ____________________________
+
+fn model_or_default(model: Option<String>) -> String {
+    model.or(MANAGER.settings().default_model).unwrap_or_else(|| DEFAULT_MODEL.to_string())
+}
+
+fn threshold(model: &str) -> f64 {
+    THRESHOLDS.iter().find(|(name, _)| *name == model).map(|(_, t)| *t).unwrap_or(FALLBACK_THRESHOLD)
+}
+
+fn cosine_distance(a: &[f64], b: &[f64]) -> Option<f64> {
+    if a.len() != b.len() || a.is_empty() {
+        return None;
+    }
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norms = a.iter().map(|x| x * x).sum::<f64>().sqrt() * b.iter().map(|y| y * y).sum::<f64>().sqrt();
+    (norms > 0.0).then(|| 1.0 - dot / norms)
+}
+
+// The faces deepface found in `image`: (embedding, facial area)
+async fn represent(image: &[u8], model: &str, enforce_detection: bool) -> Result<Vec<(Vec<f64>, Value)>, AppError> {
+    analysis::ensure_deepface(None).await?;
+    let answer = deepFaceProcess::represent_frame(image, model, None, enforce_detection).await?;
+    if answer["status"] != "ok" {
+        return Err(AppError::DeepFace(format!("represent failed: {}", answer["data"]["message"])));
+    }
+    let faces = answer["data"]["faces"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        // Detection isn't enforced for identify: a frame without a face comes back as one face with confidence 0
+        .filter(|face| enforce_detection || face["face_confidence"].as_f64().unwrap_or(1.0) > 0.0)
+        .filter_map(|face| {
+            let embedding = serde_json::from_value(face["embedding"].clone()).ok()?;
+            Some((embedding, face["facial_area"].clone()))
+        })
+        .collect();
+    Ok(faces)
+}
+
+/// Store the face of `image` (a single person) as a reference for `name`.
+pub async fn add_reference(name: &str, image: &[u8], model: Option<String>) -> Result<ReferenceFace, AppError> {
+    if !features::is_enabled(features::FACE_SEARCH) {
+        return Err(AppError::FeatureNotLicensed(features::FACE_SEARCH.to_string()));
+    }
+    let model = model_or_default(model);
+    let mut faces = represent(image, &model, true).await?;
+    if faces.len() != 1 {
+        return Err(AppError::InvalidInput(format!("A reference image needs exactly one face, found {}", faces.len())));
+    }
+    let (embedding, _) = faces.remove(0);
+    let (name, model) = (name.to_string(), model.clone());
+    database::blocking(move || database::add_reference_face(&name, &model, &embedding)).await
+}
+
+/// Match every face of `image` against the reference faces.
+pub async fn identify(image: &[u8], model: Option<String>) -> Result<Identification, AppError> {
+    if !features::is_enabled(features::FACE_SEARCH) {
+        return Err(AppError::FeatureNotLicensed(features::FACE_SEARCH.to_string()));
+    }
+    let model = model_or_default(model);
+    let references = {
+        let model = model.clone();
+        database::blocking(move || database::reference_embeddings(&model)).await?
+    };
+    if references.is_empty() {
+        return Err(AppError::InvalidInput(format!("No reference face for model {}: add one first", model)));
+    }
+
+    let threshold = threshold(&model);
+    let mut faces: Vec<FaceMatch> = represent(image, &model, false)
+        .await?
+        .into_iter()
+        .filter_map(|(embedding, facial_area)| {
+            let (reference, distance) = references
+                .iter()
+                .filter_map(|(reference, stored)| Some((reference, cosine_distance(&embedding, stored)?)))
+                .min_by(|a, b| a.1.total_cmp(&b.1))?;
+            Some(FaceMatch {
+                name: (distance <= threshold).then(|| reference.name.clone()),
+                reference_id: reference.id,
+                distance,
+                threshold,
+                confidence: (1.0 - distance / threshold).clamp(0.0, 1.0),
+                facial_area,
+            })
+        })
+        .collect();
+    faces.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+    Ok(Identification { model, faces })
+}
+
+
+//_____________Commands ________________________
+
+/// Example: `invoke("add_reference_face", { name: "Alice", image: "C:/faces/alice.jpg" })`.
+#[tauri::command]
+pub async fn add_reference_face(name: String, image: String, model: Option<String>) -> Result<ReferenceFace, AppError> {
+    add_reference(&name, &std::fs::read(&image)?, model).await
+}
+
+/// `frame` is an image path, e.g. a frame sampled by media.rs.
+#[tauri::command]
+pub async fn identify_face(frame: String, model: Option<String>) -> Result<Identification, AppError> {
+    identify(&std::fs::read(&frame)?, model).await
+}
+
+#[tauri::command]
+pub async fn list_reference_faces() -> Result<Vec<ReferenceFace>, AppError> {
+    database::blocking(database::list_reference_faces).await
+}
+
+#[tauri::command]
+pub async fn delete_reference_face(reference_id: i64) -> Result<bool, AppError> {
+    database::blocking(move || database::delete_reference_face(reference_id)).await
+}
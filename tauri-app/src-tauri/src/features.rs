@@ -0,0 +1,88 @@
+// src/features.rs
+//
+// Feature flags driven by the license tier.
+// - The license server returns the list of features unlocked by the key (`features: [...]`)
+// - license.rs feeds every validation result into `apply_license` (without a key: the trial's, see trial.rs)
+// - Commands (webview) and the WS dispatcher ask `is_enabled(name)` before running gated code
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::sync::RwLock;
+use tauri::AppHandle;
+
+use crate::events;
+use crate::license::LicenseDetails;
+
+
+//____________Const___________
+pub const FEATURES_EVENT: &str = "feature-flags";
+
+// Known feature names (must match the cloud server's TIER_FEATURES table)
+pub const LIVE_CAMERA: &str = "live_camera";
+pub const CLOUD_SYNC: &str = "cloud_sync";
+pub const BATCH_ANALYZE: &str = "batch_analyze"; // analyze_clip jobs, however they are started (jobs.rs)
+pub const FACE_SEARCH: &str = "face_search";     // reference faces and identify_face (faces.rs)
+pub const WS_PRIORITY: &str = "ws_priority"; // more WS connections, queued instead of refused (websocket.rs)
+
+
+//_____________Struct _________________________
+/// Features unlocked by the current license.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureFlags {
+    pub tier: Option<String>,
+    pub enabled: BTreeSet<String>,
+}
+
+
+//_____________Globals _______________________
+static FLAGS: Lazy<RwLock<FeatureFlags>> = Lazy::new(|| RwLock::new(FeatureFlags::default()));
+
+
+//_____________fn ____________________________
+
+/// Replace the flags with what the latest license check allows.
+/// `None` means the app is locked (invalid / expired / offline past grace): everything is disabled.
+pub fn apply_license(app_handle: &AppHandle, details: Option<&LicenseDetails>) {
+    let flags = match details {
+        Some(d) => FeatureFlags {
+            tier: d.tier.clone(),
+            enabled: d.features.iter().cloned().collect(),
+        },
+        None => FeatureFlags::default(),
+    };
+
+    let changed = match FLAGS.write() {
+        Ok(mut current) if *current != flags => {
+            *current = flags.clone();
+            true
+        }
+        _ => false,
+    };
+
+    if changed {
+        events::emit_all_surfaces(app_handle, FEATURES_EVENT, &flags);
+    }
+}
+
+pub fn is_enabled(name: &str) -> bool {
+    FLAGS.read().map(|f| f.enabled.contains(name)).unwrap_or(false)
+}
+
+pub fn current() -> FeatureFlags {
+    FLAGS.read().map(|f| f.clone()).unwrap_or_default()
+}
+
+
+//_____________Commands ________________________
+
+#[tauri::command]
+pub fn is_feature_enabled(name: String) -> bool {
+    is_enabled(&name)
+}
+
+#[tauri::command]
+pub fn get_feature_flags() -> FeatureFlags {
+    current()
+}
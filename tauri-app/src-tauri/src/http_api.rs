@@ -0,0 +1,161 @@
+// src/http_api.rs
+//
+// REST fallback of the WS server, for scripts and tools that can't speak WebSocket (curl in a build pipeline…).
+// - `POST /command` on settings.ws host:httpPort (0 = off, the default), body = a WS request frame:
+//     curl -H "Authorization: Bearer <token>" -d '{"command":"list_markers","payload":{}}' http://127.0.0.1:8081/command
+// - replies with the WS envelope (protocol.rs WsResponse); the HTTP status follows its `code`
+//   (401 unauthorized, 403 permission_denied, 404 unknown_command, 400 invalid input / payload, …)
+// - same registry, same access tokens and roles (ws_auth.rs, as `Authorization: Bearer`), same Origin / Host
+//   checks (settings.ws allowedOrigins / allowedHosts) and the same message size limit as the WS server
+// - the registry's middleware (middleware.rs) logs and counts them as http.* metrics; no rate limit (no connection)
+// - one request, one reply: streamed chunks are collected into the final reply (as for v1 WS clients), no events
+// Plain HTTP only (settings.ws tls doesn't apply): keep it on localhost. Requests show in the WS audit as connection 0.
+
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use once_cell::sync::Lazy;
+use std::sync::{Arc, Mutex};
+use tauri::AppHandle;
+use tokio::net::TcpListener;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+use crate::crash;
+use crate::error::AppError;
+use crate::protocol::{WsRequest, WsResponse};
+use crate::registry::{self, CommandContext};
+use crate::settings::{self, WsSettings};
+use crate::websocket::{self, HeadlessServer};
+use crate::ws_audit;
+use crate::ws_auth;
+
+
+//____________Const___________
+pub const HTTP_PORT: u16 = 0; // default of settings.ws httpPort: off
+pub const COMMAND_PATH: &str = "/command";
+const HTTP_CONNECTION: u64 = 0; // connection id of HTTP requests in the WS audit (WS connections start at 1)
+
+
+//_____________Struct _________________________
+#[derive(Clone)]
+struct HttpState {
+    app_handle: Option<AppHandle>,
+    config: Arc<WsSettings>,
+}
+
+
+//_____________Globals _______________________
+static SERVER_TASK: Lazy<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>> = Lazy::new(|| Mutex::new(None));
+static LISTENING: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None)); // "127.0.0.1:8081" while bound
+
+
+//_____________fn ____________________________
+
+/// Serve `POST /command` when settings.ws httpPort is set. Call from setup, after ws_auth::init.
+pub fn start(app_handle: AppHandle) {
+    let config = settings::get().ws;
+    if config.http_port == 0 {
+        return;
+    }
+    let task = crash::spawn("http-api", async move {
+        let listener = match TcpListener::bind((config.host.as_str(), config.http_port)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("❌ Failed to bind the HTTP API on {}:{}: {}", config.host, config.http_port, e);
+                return;
+            }
+        };
+        info!("🚀 HTTP API listening on http://{}:{}{}", config.host, config.http_port, COMMAND_PATH);
+        *LISTENING.lock().unwrap_or_else(|p| p.into_inner()) = Some(format!("{}:{}", config.host, config.http_port));
+        serve(listener, Some(app_handle), config).await;
+    });
+    *SERVER_TASK.lock().unwrap_or_else(|p| p.into_inner()) = Some(task);
+}
+
+/// Stop the HTTP API and start it again with the current settings (settings.ws changed).
+pub fn restart(app_handle: AppHandle) {
+    if let Some(task) = SERVER_TASK.lock().unwrap_or_else(|p| p.into_inner()).take() {
+        task.abort();
+        *LISTENING.lock().unwrap_or_else(|p| p.into_inner()) = None;
+    }
+    start(app_handle);
+}
+
+/// Address of the HTTP API while it listens (get_server_endpoints).
+pub fn listening() -> Option<String> {
+    LISTENING.lock().unwrap_or_else(|p| p.into_inner()).clone()
+}
+
+/// The HTTP API on `config.host`:`config.http_port` (0 = any free port) without the Tauri app.
+/// Used by the integration tests (tests/http_api.rs).
+pub async fn serve_headless(config: WsSettings) -> Result<HeadlessServer, AppError> {
+    let listener = TcpListener::bind((config.host.as_str(), config.http_port))
+        .await
+        .map_err(|e| AppError::Ws(format!("Failed to bind {}:{}: {}", config.host, config.http_port, e)))?;
+    let addr = listener.local_addr()?;
+    let task = crash::spawn("http-api", serve(listener, None, config));
+    Ok(HeadlessServer { addr, task })
+}
+
+async fn serve(listener: TcpListener, app_handle: Option<AppHandle>, config: WsSettings) {
+    let router = Router::new()
+        .route(COMMAND_PATH, post(run_command))
+        .layer(DefaultBodyLimit::max(config.max_message_bytes))
+        .with_state(HttpState { app_handle, config: Arc::new(config) });
+    if let Err(e) = axum::serve(listener, router).await {
+        error!("❌ HTTP API stopped: {}", e);
+    }
+}
+
+async fn run_command(State(state): State<HttpState>, headers: HeaderMap, body: Bytes) -> (StatusCode, Json<WsResponse>) {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    // Browser pages of other sites (and DNS rebinding) are refused, as at the WS handshake
+    if let Err(reason) = websocket::check_origin_and_host(header("origin"), header("host"), &state.config) {
+        warn!("⛔ Refusing HTTP request: {}", reason);
+        return reply(WsResponse::error(None, String::new(), AppError::PermissionDenied(reason)));
+    }
+    let token = header("authorization").and_then(|v| v.strip_prefix("Bearer ")).map(str::trim);
+    let role = match ws_auth::authenticate(token) {
+        Ok(role) => role,
+        Err(err) => return reply(WsResponse::error(None, String::new(), err)),
+    };
+    let WsRequest { request_id, command, payload } = match serde_json::from_slice::<WsRequest>(&body) {
+        Ok(req) => req,
+        Err(e) => return reply(WsResponse::error(None, String::new(), AppError::InvalidInput(format!("Invalid JSON: {}", e)))),
+    };
+
+    let ctx = match &state.app_handle {
+        Some(app_handle) => CommandContext::new(app_handle.clone()),
+        None => CommandContext::detached(),
+    }
+    .with_role(role)
+    .with_source("http");
+    let audited_payload = payload.clone();
+    let started = Instant::now();
+    let result = registry::dispatch(&command, payload, ctx).await;
+    ws_audit::record(HTTP_CONNECTION, &command, request_id, audited_payload, None, started.elapsed(), &result);
+
+    reply(match result {
+        Ok(data) => WsResponse::ok(request_id, command, data),
+        Err(err) => WsResponse::error(request_id, command, err),
+    })
+}
+
+// The envelope, with the HTTP status matching its error code
+fn reply(response: WsResponse) -> (StatusCode, Json<WsResponse>) {
+    let status = match response.code.as_deref() {
+        None => StatusCode::OK,
+        Some("unauthorized") => StatusCode::UNAUTHORIZED,
+        Some("permission_denied" | "feature_not_licensed") => StatusCode::FORBIDDEN,
+        Some("unknown_command") => StatusCode::NOT_FOUND,
+        Some("invalid_input" | "invalid_payload") => StatusCode::BAD_REQUEST,
+        Some("payload_too_large") => StatusCode::PAYLOAD_TOO_LARGE,
+        Some("rate_limited") => StatusCode::TOO_MANY_REQUESTS,
+        Some(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, Json(response))
+}
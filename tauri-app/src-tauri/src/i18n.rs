@@ -0,0 +1,114 @@
+// src/i18n.rs
+//
+// Localized backend messages.
+// - user-facing strings have a code ("cep.connected", "license.trial") with params, looked up in the message
+//   catalogs bundled from src-tauri/locales/<lang>.json; `{name}` in a message is replaced by the param `name`
+// - events and replies carry `{ code, params, message }` (`Localized`), so the frontend can render its own
+//   translation from the code, while surfaces that can't (notifications, tray, CEP status line) use `message`
+// - the locale is settings.locale (`set_locale`), empty = DEFAULT_LOCALE; a code missing from a catalog falls
+//   back to DEFAULT_LOCALE, then to the code itself
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+use crate::error::AppError;
+use crate::settings;
+
+
+//____________Const___________
+pub const DEFAULT_LOCALE: &str = "en";
+const CATALOG_SOURCES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.json")),
+    ("fr", include_str!("../locales/fr.json")),
+];
+
+
+//_____________Struct _________________________
+/// A message as sent to the frontend / CEP panel.
+/// Example: `{ "code": "ws.queued", "params": { "secs": 10 }, "message": "Server busy: waiting up to 10s for a free connection slot" }`
+#[derive(Debug, Clone, Serialize)]
+pub struct Localized {
+    pub code: &'static str,
+    pub params: Map<String, Value>,
+    pub message: String, // in the current locale
+}
+
+
+//_____________Globals _______________________
+static CATALOGS: Lazy<HashMap<&'static str, HashMap<String, String>>> = Lazy::new(|| {
+    CATALOG_SOURCES
+        .iter()
+        .map(|(lang, source)| (*lang, serde_json::from_str(source).unwrap_or_else(|e| panic!("Invalid locales/{}.json: {}", lang, e))))
+        .collect()
+});
+
+
+//_____________fn ____________________________
+
+/// Locales with a bundled catalog.
+pub fn available() -> Vec<&'static str> {
+    CATALOG_SOURCES.iter().map(|(lang, _)| *lang).collect()
+}
+
+pub fn locale() -> String {
+    let locale = settings::get().locale;
+    if locale.is_empty() { DEFAULT_LOCALE.to_string() } else { locale }
+}
+
+fn render(template: &str, params: &Map<String, Value>) -> String {
+    params.iter().fold(template.to_string(), |text, (name, value)| {
+        let value = match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        text.replace(&format!("{{{}}}", name), &value)
+    })
+}
+
+/// `code` in the current locale, with `params` (a JSON object) filled in.
+pub fn t(code: &str, params: &Value) -> String {
+    let locale = locale();
+    let template = [locale.as_str(), DEFAULT_LOCALE]
+        .iter()
+        .find_map(|lang| CATALOGS.get(lang)?.get(code))
+        .map(String::as_str)
+        .unwrap_or(code);
+    match params {
+        Value::Object(params) => render(template, params),
+        _ => template.to_string(),
+    }
+}
+
+/// `code` with its params and its text in the current locale.
+pub fn localized(code: &'static str, params: Value) -> Localized {
+    let message = t(code, &params);
+    let params = match params {
+        Value::Object(params) => params,
+        _ => Map::new(),
+    };
+    Localized { code, params, message }
+}
+
+/// A message without params.
+pub fn text(code: &'static str) -> String {
+    t(code, &json!({}))
+}
+
+
+//_____________Commands ________________________
+
+/// Switch the language of backend messages (saved in settings.locale).
+/// Example: `invoke("set_locale", { lang: "fr" })`
+#[tauri::command]
+pub fn set_locale(app_handle: AppHandle, lang: String) -> Result<String, AppError> {
+    let lang = lang.trim().to_ascii_lowercase();
+    let lang = lang.split(['-', '_']).next().unwrap_or_default().to_string(); // "fr-CA" -> "fr"
+    if !available().contains(&lang.as_str()) {
+        return Err(AppError::InvalidInput(format!("Unsupported locale '{}' (expected {})", lang, available().join(", "))));
+    }
+    settings::update_settings(app_handle, json!({ "locale": lang }))?;
+    Ok(lang)
+}
@@ -0,0 +1,163 @@
+// src/ingest.rs
+//
+// Bringing clips into a project, from files dropped on the window or picked in the UI.
+// - `ingest_clips(paths)` starts an "ingest_clips" job (jobs.rs): per file, ffprobe (media::probe), register the
+//   clip with its fps (database::add_clip), extract its thumbnail (thumbnails.rs), and with `analyze` start an
+//   "analyze_clip" job for it; progress comes as job-progress events like any job
+// - files dropped on the main window go through the same job (`on_drop`), analyzed when settings.media
+//   analyzeOnDrop is on; folders are expanded to the media files they contain
+// A file that fails (not a video, ffprobe missing, …) is listed in `failed`, the others are still imported.
+
+use serde::Serialize;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+use crate::database::{self, Job};
+use crate::error::AppError;
+use crate::jobs;
+use crate::media::{self, MediaInfo};
+use crate::payloads::IngestClipsArgs;
+use crate::settings;
+use crate::thumbnails;
+use crate::watcher;
+
+
+//____________Const___________
+const THUMBNAIL_AT: f64 = 1.0; // seconds into the clip (halfway for shorter clips)
+
+
+//_____________Struct _________________________
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestedClip {
+    pub clip_id: i64,
+    pub path: String,
+    pub media: MediaInfo,
+    pub thumbnail: Option<PathBuf>,  // None when ffmpeg couldn't extract one
+    pub analysis_job: Option<i64>,   // the "analyze_clip" job started for it
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestFailure {
+    pub path: String,
+    pub error: String,
+}
+
+/// Result of an "ingest_clips" job.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestReport {
+    pub clips: Vec<IngestedClip>,
+    pub failed: Vec<IngestFailure>,
+}
+
+
+//_____________fn ____________________________
+
+// Files as given, folders replaced by the media files under them
+fn expand(paths: &[String]) -> Vec<String> {
+    fn walk(dir: &Path, out: &mut Vec<String>) {
+        let Ok(entries) = std::fs::read_dir(dir) else { return };
+        let mut entries: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+        entries.sort();
+        for path in entries {
+            if path.is_dir() {
+                walk(&path, out);
+            } else if watcher::is_media(&path) {
+                out.push(path.to_string_lossy().into_owned());
+            }
+        }
+    }
+    let mut out = Vec::new();
+    for path in paths {
+        if Path::new(path).is_dir() {
+            walk(Path::new(path), &mut out);
+        } else {
+            out.push(path.clone());
+        }
+    }
+    out
+}
+
+async fn ingest_one(app_handle: &AppHandle, path: &str, args: &IngestClipsArgs) -> Result<IngestedClip, AppError> {
+    let info = media::probe(path).await?;
+    if info.codec.is_none() {
+        return Err(AppError::Media("No video stream".into()));
+    }
+    let (owned, fps, project_id) = (path.to_string(), info.fps, args.project_id);
+    let clip = database::blocking(move || database::add_clip(&owned, fps, project_id)).await?;
+
+    let at = info.duration.map(|d| THUMBNAIL_AT.min(d / 2.0)).unwrap_or(0.0);
+    let thumbnail = match thumbnails::clip_thumbnail(app_handle, path, at, None, false).await {
+        Ok(thumbnail) => Some(thumbnail.path),
+        Err(e) => {
+            warn!("⚠️ No thumbnail for {}: {}", path, e);
+            None
+        }
+    };
+    let analysis_job = if args.analyze {
+        let params = json!({ "path": path, "projectId": clip.project_id, "fps": clip.fps });
+        Some(jobs::start(app_handle, "analyze_clip", params)?.id)
+    } else {
+        None
+    };
+    Ok(IngestedClip { clip_id: clip.id, path: clip.path, media: info, thumbnail, analysis_job })
+}
+
+/// Import `args.paths`, reporting `progress(fraction, message)` after each file.
+pub async fn ingest<P>(app_handle: &AppHandle, args: &IngestClipsArgs, progress: P) -> Result<IngestReport, AppError>
+where
+    P: Fn(f64, &str) -> Result<(), AppError>,
+{
+    let paths = expand(&args.paths);
+    let total = paths.len();
+    let mut report = IngestReport::default();
+    for (i, path) in paths.iter().enumerate() {
+        let name = Path::new(path).file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| path.clone());
+        progress(i as f64 / total as f64, &format!("Importing {} ({}/{})", name, i + 1, total))?;
+        match ingest_one(app_handle, path, args).await {
+            Ok(clip) => report.clips.push(clip),
+            Err(e) => {
+                warn!("❌ Failed to import {}: {}", path, e);
+                report.failed.push(IngestFailure { path: path.clone(), error: e.to_string() });
+            }
+        }
+    }
+    info!("📥 Imported {} clips ({} failed)", report.clips.len(), report.failed.len());
+    Ok(report)
+}
+
+fn start(app_handle: &AppHandle, paths: Vec<String>, project_id: Option<i64>, analyze: bool) -> Result<Job, AppError> {
+    if paths.is_empty() {
+        return Err(AppError::InvalidInput("No files to import".into()));
+    }
+    jobs::start(app_handle, "ingest_clips", json!({ "paths": paths, "projectId": project_id, "analyze": analyze }))
+}
+
+/// Files dropped on the main window (lib.rs setup).
+pub fn on_drop(app_handle: &AppHandle, paths: &[PathBuf]) {
+    let paths: Vec<String> = paths
+        .iter()
+        .filter(|p| p.is_dir() || watcher::is_media(p))
+        .map(|p| p.to_string_lossy().into_owned())
+        .collect();
+    if paths.is_empty() {
+        return;
+    }
+    if let Err(e) = start(app_handle, paths, None, settings::get().media.analyze_on_drop) {
+        warn!("❌ Failed to import dropped files: {}", e);
+    }
+}
+
+
+//_____________Commands ________________________
+
+/// Import clips (files or folders) into a project as a background job; returns the job.
+/// Example: `invoke("ingest_clips", { paths: ["C:/…/a.mp4", "D:/Footage/day1"], projectId: 2, analyze: true })`
+#[tauri::command]
+pub fn ingest_clips(app_handle: AppHandle, paths: Vec<String>, project_id: Option<i64>, analyze: Option<bool>) -> Result<Job, AppError> {
+    start(&app_handle, paths, project_id, analyze.unwrap_or(false))
+}
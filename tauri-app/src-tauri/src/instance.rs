@@ -0,0 +1,47 @@
+// src/instance.rs
+//
+// One running instance: a second launch (tauri-plugin-single-instance, registered first in lib.rs) exits
+// before its setup, so it never binds the WS port again nor spawns a second deepface child.
+// - its command line is forwarded here: the main window is focused and SECOND_INSTANCE_EVENT
+//   { args, cwd } is emitted to the webview and WS subscribers
+// - faceapp:// arguments are also run by deeplink.rs (the plugin's deep-link feature hands them over first)
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use tracing::info;
+
+use crate::events;
+
+
+//____________Const___________
+pub const MAIN_WINDOW: &str = "main";
+pub const SECOND_INSTANCE_EVENT: &str = "second-instance";
+
+
+//_____________Struct _________________________
+/// SECOND_INSTANCE_EVENT payload.
+#[derive(Debug, Clone, Serialize)]
+pub struct SecondInstance {
+    pub args: Vec<String>, // without the executable
+    pub cwd: String,       // working directory of the second launch, for relative paths
+}
+
+
+//_____________fn ____________________________
+
+/// Bring the main window to the front (also when it was hidden to the tray).
+pub fn focus_main(app_handle: &AppHandle) {
+    if let Some(window) = app_handle.get_webview_window(MAIN_WINDOW) {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// single-instance callback: runs in the first instance with the second one's command line.
+pub fn on_second_instance(app_handle: &AppHandle, argv: Vec<String>, cwd: String) {
+    let args: Vec<String> = argv.into_iter().skip(1).collect();
+    info!("🪟 Second launch forwarded: {:?} (cwd {})", args, cwd);
+    focus_main(app_handle);
+    events::emit_all_surfaces(app_handle, SECOND_INSTANCE_EVENT, SecondInstance { args, cwd });
+}
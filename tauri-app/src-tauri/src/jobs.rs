@@ -0,0 +1,365 @@
+// src/jobs.rs
+//
+// Background jobs: long operations (batch exports, frame extraction, clip analysis, later model
+// downloads) that run detached from the request that started them.
+// - `start_job(kind, params)` stores the job in SQLite (database.rs `jobs`), spawns it and returns it at once
+// - progress is saved and emitted as `job-progress` (webview + subscribed WS clients) with the whole Job
+// - `cancel_job(id)` stops it; `list_jobs` is the history, kept across restarts
+// - jobs that were running when the app quit are marked `interrupted` at startup and can be
+//   started again with `resume_job(id)` (same params)
+//
+// A new kind = an entry in KINDS: its params check (payloads.rs struct), a runner taking a JobContext
+// and the summary of its result shown in the completion notification (notify.rs).
+
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+use crate::database::{self, Job, JobStatus};
+use crate::error::AppError;
+use crate::payloads::{self, AnalyzeAudioArgs, AnalyzeClipArgs, BuildMomentsArgs, ExportMarkersJobArgs, FramesJobArgs, IngestClipsArgs, TranscribeClipArgs};
+use crate::{analysis, app_status, audio, cache, crash, events, features, ingest, marker_export, media, metrics, moments, notify, whisper};
+
+
+//____________Const___________
+pub const DEFAULT_LIST_LIMIT: usize = 50;
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<Value, AppError>> + Send>>;
+type JobRunner = fn(JobContext, Value) -> JobFuture;
+type ParamsCheck = fn(&Value) -> Result<(), AppError>;
+type ResultSummary = fn(&Value) -> String;
+
+struct JobKind {
+    name: &'static str,
+    check: ParamsCheck, // run before the job is stored
+    run: JobRunner,
+    summary: ResultSummary,
+}
+
+// Job kinds this build can run
+const KINDS: &[JobKind] = &[
+    JobKind {
+        name: "export_markers",
+        check: |params| payloads::parse::<ExportMarkersJobArgs>("export_markers", params.clone()).map(drop),
+        run: |ctx, params| Box::pin(export_markers_job(ctx, params)),
+        summary: |result| {
+            let files = result["files"].as_array().map(Vec::len).unwrap_or(0);
+            let markers: u64 = result["files"].as_array().into_iter().flatten().filter_map(|f| f["markers"].as_u64()).sum();
+            format!("Marker export complete ({} markers in {} files)", markers, files)
+        },
+    },
+    JobKind {
+        name: "extract_frames",
+        check: |params| payloads::parse::<FramesJobArgs>("extract_frames", params.clone()).map(drop),
+        run: |ctx, params| Box::pin(extract_frames_job(ctx, params)),
+        summary: |result| format!("Frame extraction complete ({} frames)", result["frames"].as_array().map(Vec::len).unwrap_or(0)),
+    },
+    JobKind {
+        name: "analyze_clip",
+        check: |params| {
+            if !features::is_enabled(features::BATCH_ANALYZE) {
+                return Err(AppError::FeatureNotLicensed(features::BATCH_ANALYZE.to_string()));
+            }
+            payloads::parse::<AnalyzeClipArgs>("analyze_clip", params.clone()).map(drop)
+        },
+        run: |ctx, params| Box::pin(analyze_clip_job(ctx, params)),
+        summary: |result| format!("Clip analysis complete ({} markers from {} frames)", result["markers"], result["frames"]),
+    },
+    JobKind {
+        name: "analyze_audio",
+        check: |params| payloads::parse::<AnalyzeAudioArgs>("analyze_audio", params.clone()).map(drop),
+        run: |ctx, params| Box::pin(analyze_audio_job(ctx, params)),
+        summary: |result| format!("Audio analysis complete ({} LUFS, {} cue markers)", result["integratedLufs"], result["markers"]),
+    },
+    JobKind {
+        name: "transcribe_clip",
+        check: |params| payloads::parse::<TranscribeClipArgs>("transcribe_clip", params.clone()).map(drop),
+        run: |ctx, params| Box::pin(transcribe_clip_job(ctx, params)),
+        summary: |result| format!("Transcription complete ({} segments)", result["segments"]),
+    },
+    JobKind {
+        name: "build_moments",
+        check: |params| payloads::parse::<BuildMomentsArgs>("build_moments", params.clone()).map(drop),
+        run: |ctx, params| Box::pin(build_moments_job(ctx, params)),
+        summary: |result| format!("Moments built ({} moments)", result["moments"]),
+    },
+    JobKind {
+        name: "ingest_clips",
+        check: |params| payloads::parse::<IngestClipsArgs>("ingest_clips", params.clone()).map(drop),
+        run: |ctx, params| Box::pin(ingest_clips_job(ctx, params)),
+        summary: |result| {
+            let count = |key: &str| result[key].as_array().map(Vec::len).unwrap_or(0);
+            format!("Clip import complete ({} clips, {} failed)", count("clips"), count("failed"))
+        },
+    },
+];
+
+
+//_____________Struct _________________________
+/// What a running job gets to report progress and notice cancellation.
+#[derive(Clone)]
+pub struct JobContext {
+    pub app: AppHandle,
+    pub id: i64,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl JobContext {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Save and emit progress (`fraction` 0..1). Fails once the job is cancelled, so `?` stops the runner.
+    pub fn progress(&self, fraction: f64, message: &str) -> Result<(), AppError> {
+        if self.is_cancelled() {
+            return Err(AppError::Cancelled(format!("Job {} cancelled", self.id)));
+        }
+        let job = database::update_job_progress(self.id, fraction.clamp(0.0, 1.0), Some(message))?;
+        events::emit_all_surfaces(&self.app, events::JOB_PROGRESS, job);
+        Ok(())
+    }
+}
+
+struct RunningJob {
+    cancelled: Arc<AtomicBool>,
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+
+//_____________Globals _______________________
+static RUNNING: Lazy<Mutex<HashMap<i64, RunningJob>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+
+//_____________fn ____________________________
+fn running() -> MutexGuard<'static, HashMap<i64, RunningJob>> {
+    RUNNING.lock().unwrap_or_else(|p| p.into_inner())
+}
+
+/// Mark jobs left over from the previous run as interrupted. Call once from setup, after the database.
+pub fn init() {
+    match database::interrupt_unfinished_jobs() {
+        Ok(0) => {}
+        Ok(n) => warn!("⚠️ {} job(s) were interrupted by the last shutdown (resume_job to run them again)", n),
+        Err(e) => warn!("❌ Failed to check interrupted jobs: {}", e),
+    }
+}
+
+fn find_kind(name: &str) -> Result<&'static JobKind, AppError> {
+    KINDS.iter().find(|k| k.name == name).ok_or_else(|| {
+        let kinds: Vec<&str> = KINDS.iter().map(|k| k.name).collect();
+        AppError::InvalidInput(format!("Unknown job kind '{}' (expected {})", name, kinds.join(", ")))
+    })
+}
+
+/// Ids of the jobs running now.
+pub fn active() -> Vec<i64> {
+    let mut ids: Vec<i64> = running().keys().copied().collect();
+    ids.sort_unstable();
+    ids
+}
+
+// Emit the job as it is now, also when saving it failed
+fn emit(app_handle: &AppHandle, job: Result<Job, AppError>) {
+    app_status::changed();
+    match job {
+        Ok(job) => events::emit_all_surfaces(app_handle, events::JOB_PROGRESS, job),
+        Err(e) => warn!("❌ Failed to update job: {}", e),
+    }
+}
+
+// Run a stored job in the background
+fn spawn(app_handle: AppHandle, job: Job) -> Result<Job, AppError> {
+    let kind = find_kind(&job.kind)?;
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let ctx = JobContext { app: app_handle.clone(), id: job.id, cancelled: cancelled.clone() };
+    let job = database::set_job_status(job.id, JobStatus::Running, None, None)?;
+    let (id, params) = (job.id, job.params.clone());
+
+    // Held while spawning: the task can't finish (and remove itself) before it is registered
+    let mut jobs = running();
+    let task = crash::spawn("job", async move {
+        let started = Instant::now();
+        let result = (kind.run)(ctx, params).await;
+        metrics::observe(&format!("job.duration_ms.{}", kind.name), started.elapsed());
+        // Whoever takes the job out of RUNNING (this task or cancel) writes the final status
+        if running().remove(&id).is_none() {
+            return;
+        }
+        let saved = match &result {
+            Ok(value) => database::set_job_status(id, JobStatus::Completed, Some(value), None),
+            Err(AppError::Cancelled(_)) => database::set_job_status(id, JobStatus::Cancelled, None, None),
+            Err(err) => database::set_job_status(id, JobStatus::Failed, None, Some(&err.to_string())),
+        };
+        let outcome = match &result {
+            Ok(_) => "completed",
+            Err(AppError::Cancelled(_)) => "cancelled",
+            Err(_) => "failed",
+        };
+        metrics::incr(&format!("job.{}.{}", outcome, kind.name));
+        info!("🏁 Job {} finished: {}", id, outcome);
+        emit(&app_handle, saved);
+        match &result {
+            Ok(value) => notify::job_completed(&app_handle, &(kind.summary)(value)),
+            Err(AppError::Cancelled(_)) => {}
+            Err(err) => notify::job_failed(&app_handle, &format!("Job {} ({})", id, kind.name), &err.to_string()),
+        }
+    });
+    jobs.insert(id, RunningJob { cancelled, task });
+    Ok(job)
+}
+
+pub fn start(app_handle: &AppHandle, kind: &str, params: Value) -> Result<Job, AppError> {
+    (find_kind(kind)?.check)(&params)?;
+    let job = database::insert_job(kind, &params)?;
+    info!("▶️ Job {} started ({})", job.id, kind);
+    spawn(app_handle.clone(), job)
+}
+
+pub fn cancel(app_handle: &AppHandle, job_id: i64) -> Result<Job, AppError> {
+    let Some(job) = running().remove(&job_id) else {
+        return Err(AppError::InvalidInput(format!("Job {} is not running", job_id)));
+    };
+    // The flag stops runners between steps; abort stops them at their current await
+    job.cancelled.store(true, Ordering::Relaxed);
+    job.task.abort();
+    let cancelled = database::set_job_status(job_id, JobStatus::Cancelled, None, None)?;
+    info!("⏹️ Job {} cancelled", job_id);
+    emit(app_handle, Ok(cancelled.clone()));
+    Ok(cancelled)
+}
+
+/// Run an interrupted (or failed / cancelled) job again with its saved params.
+pub fn resume(app_handle: &AppHandle, job_id: i64) -> Result<Job, AppError> {
+    let job = database::get_job(job_id)?;
+    if !matches!(job.status, JobStatus::Interrupted | JobStatus::Failed | JobStatus::Cancelled) {
+        return Err(AppError::InvalidInput(format!("Job {} is {:?}, nothing to resume", job_id, job.status)));
+    }
+    // Checked again, as in `start`: the license may no longer include the job's feature
+    (find_kind(&job.kind)?.check)(&job.params)?;
+    let job = database::reset_job(job_id)?;
+    info!("🔁 Job {} resumed ({})", job_id, job.kind);
+    spawn(app_handle.clone(), job)
+}
+
+
+//_____________Job kinds ________________________
+
+// Export the markers of several clips into `dir`, one file per clip
+async fn export_markers_job(ctx: JobContext, params: Value) -> Result<Value, AppError> {
+    let args: ExportMarkersJobArgs = payloads::parse("export_markers", params)?;
+    let extension = args.format.parse::<marker_export::ExportFormat>()?.extension();
+    let total = args.clip_ids.len();
+    let mut files = Vec::with_capacity(total);
+
+    for (i, clip_id) in args.clip_ids.into_iter().enumerate() {
+        ctx.progress(i as f64 / total as f64, &format!("Exporting clip {} ({}/{})", clip_id, i + 1, total))?;
+        let clip = database::get_clip(clip_id)?;
+        let stem = Path::new(&clip.path).file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_else(|| format!("clip_{}", clip_id));
+        let path = Path::new(&args.dir).join(format!("{}.{}", stem, extension)).to_string_lossy().into_owned();
+        let count = marker_export::export_markers(clip_id, args.format.clone(), path.clone())?;
+        files.push(json!({ "clipId": clip_id, "path": path, "markers": count }));
+    }
+    ctx.progress(1.0, "Done")?;
+    Ok(json!({ "files": files }))
+}
+
+// Frames at `timestamps` into a new cache directory, one ffmpeg run per frame
+async fn extract_frames_job(ctx: JobContext, params: Value) -> Result<Value, AppError> {
+    let args: FramesJobArgs = payloads::parse("extract_frames", params)?;
+    let dir = media::new_frames_dir(&ctx.app)?;
+    let total = args.timestamps.len();
+    let mut frames = Vec::with_capacity(total);
+
+    for (i, timestamp) in args.timestamps.into_iter().enumerate() {
+        ctx.progress(i as f64 / total as f64, &format!("Frame {}/{}", i + 1, total))?;
+        let jpeg = media::extract_frame(&args.path, timestamp, args.max_width).await?;
+        let path = dir.join(format!("frame_{:06}.jpg", i + 1));
+        std::fs::write(&path, jpeg)?;
+        frames.push(media::FrameFile { timestamp, path });
+    }
+    cache::changed();
+    ctx.progress(1.0, "Done")?;
+    Ok(json!({ "frames": frames }))
+}
+
+// Emotion markers for a clip (analysis.rs)
+async fn analyze_clip_job(ctx: JobContext, params: Value) -> Result<Value, AppError> {
+    let args: AnalyzeClipArgs = payloads::parse("analyze_clip", params)?;
+    let analysis = analysis::analyze_clip(&ctx.app, &args, |fraction, message| ctx.progress(fraction, message)).await?;
+    ctx.progress(1.0, "Done")?;
+    Ok(json!({ "clipId": analysis.clip.id, "frames": analysis.frames, "shots": analysis.shots, "markers": analysis.markers.len(), "tracks": analysis.tracks }))
+}
+
+// Loudness, segments and cue markers of a clip's audio (audio.rs)
+async fn analyze_audio_job(ctx: JobContext, params: Value) -> Result<Value, AppError> {
+    let args: AnalyzeAudioArgs = payloads::parse("analyze_audio", params)?;
+    let (analysis, markers) = audio::analyze(&args, |fraction, message| ctx.progress(fraction, message)).await?;
+    ctx.progress(1.0, "Done")?;
+    Ok(json!({
+        "clipId": analysis.clip_id,
+        "integratedLufs": analysis.integrated_lufs.map(|lufs| (lufs * 10.0).round() / 10.0),
+        "loudnessRange": analysis.loudness_range,
+        "segments": analysis.segments.len(),
+        "cues": analysis.cues.len(),
+        "markers": markers.len(),
+    }))
+}
+
+// Timestamped transcript of a clip with the whisper sidecar (whisper.rs)
+async fn transcribe_clip_job(ctx: JobContext, params: Value) -> Result<Value, AppError> {
+    let args: TranscribeClipArgs = payloads::parse("transcribe_clip", params)?;
+    let transcript = whisper::transcribe(&ctx.app, &args, |fraction, message| ctx.progress(fraction, message)).await?;
+    ctx.progress(1.0, "Done")?;
+    Ok(json!({ "clipId": transcript.clip.id, "language": transcript.language, "segments": transcript.segments.len() }))
+}
+
+// Transcript segments joined with emotion peaks (moments.rs)
+async fn build_moments_job(ctx: JobContext, params: Value) -> Result<Value, AppError> {
+    let args: BuildMomentsArgs = payloads::parse("build_moments", params)?;
+    let clip_id = args.clip_id;
+    let job = ctx.clone();
+    let moments = database::blocking(move || moments::build(&args, |fraction, message| job.progress(fraction, message))).await?;
+    ctx.progress(1.0, "Done")?;
+    Ok(json!({ "clipId": clip_id, "moments": moments.len() }))
+}
+
+// Probe, register and thumbnail dropped / picked clips (ingest.rs)
+async fn ingest_clips_job(ctx: JobContext, params: Value) -> Result<Value, AppError> {
+    let args: IngestClipsArgs = payloads::parse("ingest_clips", params)?;
+    let report = ingest::ingest(&ctx.app, &args, |fraction, message| ctx.progress(fraction, message)).await?;
+    ctx.progress(1.0, "Done")?;
+    Ok(json!(report))
+}
+
+
+//_____________Commands ________________________
+
+/// Example: `invoke("start_job", { kind: "export_markers", params: { clipIds: [1, 2], format: "edl", dir: "C:/…" } })`
+/// or `{ kind: "analyze_clip", params: { path: "C:/…/clip.mp4", everySecs: 2 } }`
+#[tauri::command]
+pub fn start_job(app_handle: AppHandle, kind: String, params: Option<Value>) -> Result<Job, AppError> {
+    start(&app_handle, &kind, params.unwrap_or_else(|| json!({})))
+}
+
+#[tauri::command]
+pub fn cancel_job(app_handle: AppHandle, job_id: i64) -> Result<Job, AppError> {
+    cancel(&app_handle, job_id)
+}
+
+#[tauri::command]
+pub fn resume_job(app_handle: AppHandle, job_id: i64) -> Result<Job, AppError> {
+    resume(&app_handle, job_id)
+}
+
+#[tauri::command]
+pub fn list_jobs(limit: Option<usize>) -> Result<Vec<Job>, AppError> {
+    database::list_jobs(limit.unwrap_or(DEFAULT_LIST_LIMIT))
+}
@@ -5,14 +5,127 @@ use tauri::{App, Manager};
 mod commands;
 mod license;
 mod database;
+mod migrations;
+mod encryption;
 mod websocket;
 mod deepFaceProcess;
+mod features;
+mod settings;
+mod logging;
+mod error;
+mod marker_export;
+mod marker_import;
+mod registry;
+mod payloads;
+mod events;
+mod sessions;
+mod tls;
+mod media;
+mod thumbnails;
+mod jobs;
+mod updater;
+mod tray;
+mod notify;
+mod crash;
+mod metrics;
+mod sync;
+mod monitor;
+mod deeplink;
+mod instance;
+mod analysis;
+pub mod cli;
+mod camera;
+mod faces;
+mod tracking;
+mod emotion_stats;
+mod analysis_export;
+mod models;
+mod preflight;
+mod deepface_resources;
+mod deepface_results;
+mod trial;
+mod app_status;
+mod secrets;
+mod onboarding;
+mod i18n;
+mod watcher;
+mod ingest;
+mod timecode;
+mod ws_audit;
+mod ws_auth;
+mod ws_transport;
+mod ws_queue;
+mod http_api;
+mod discovery;
+mod middleware;
+mod macros;
+mod plugins;
+mod audio;
+mod whisper;
+mod moments;
+mod cache;
+pub mod protocol;
+#[doc(hidden)]
+pub mod testing;
 
 use crate::license::start_license_checker;
+use crate::license::force_license_check;
+use crate::license::set_license_key;
+use crate::license::activate_license;
+use crate::license::deactivate_license;
+use crate::license::test_license_server;
+use crate::license::pause_license_checker;
+use crate::license::resume_license_checker;
+use crate::license::set_license_check_interval;
+use crate::features::is_feature_enabled;
+use crate::features::get_feature_flags;
+use crate::settings::get_settings;
+use crate::settings::update_settings;
+use crate::logging::set_log_level;
+use crate::logging::get_recent_logs;
 use crate::deepFaceProcess::start_deepface_server;
+use crate::deepFaceProcess::stop_deepface_server;
 use crate::deepFaceProcess::analyze_deepface;
 use crate::deepFaceProcess::verify_deepface;
 use crate::deepFaceProcess::detect_deepface;
+use crate::deepFaceProcess::list_deepface_detectors;
+use crate::deepFaceProcess::list_deepface_models;
+use crate::marker_export::export_markers;
+use crate::marker_import::import_markers;
+use crate::analysis_export::export_analysis;
+use crate::registry::run_command;
+use crate::registry::stream_command;
+use crate::registry::list_commands;
+use crate::websocket::ws_status;
+use crate::tls::get_tls_info;
+use crate::media::extract_frames;
+use crate::media::probe_media;
+use crate::media::detect_scenes;
+use crate::thumbnails::get_clip_thumbnail;
+use crate::jobs::start_job;
+use crate::jobs::cancel_job;
+use crate::jobs::resume_job;
+use crate::jobs::list_jobs;
+use crate::updater::check_for_update;
+use crate::updater::download_update;
+use crate::updater::install_update;
+use crate::crash::list_crash_reports;
+use crate::crash::submit_crash_report;
+use crate::metrics::get_metrics;
+use crate::sync::sync_now;
+use crate::sync::get_sync_status;
+use crate::monitor::open_monitor_window;
+use crate::camera::list_cameras;
+use crate::camera::start_camera;
+use crate::camera::stop_camera;
+use crate::camera::get_camera_status;
+use crate::faces::add_reference_face;
+use crate::faces::identify_face;
+use crate::faces::list_reference_faces;
+use crate::faces::delete_reference_face;
+use crate::tracking::track_faces;
+use crate::emotion_stats::get_clip_emotion_summary;
+use crate::emotion_stats::get_project_emotion_summary;
 
 // ----------------- App Entry -----------------
 
@@ -21,33 +134,266 @@ use crate::deepFaceProcess::detect_deepface;
 pub fn run() {
     tauri::Builder::default()
 
-        // PLUGINS
+        // PLUGINS (single-instance first: a second launch forwards its args / faceapp:// link here and exits)
+        .plugin(tauri_plugin_single_instance::init(instance::on_second_instance))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_shell::init())
         
 
         // FRONTEND Commands
         .invoke_handler(tauri::generate_handler![
             commands::greet,
+            commands::add_clip,
             commands::add_marker,
+            commands::list_markers,
+            commands::search_markers,
+            commands::update_marker,
+            commands::delete_marker,
+            commands::get_analysis,
+            commands::undo_last,
+            commands::redo_last,
+            commands::create_project,
+            commands::open_project,
+            commands::list_projects,
+            commands::delete_project,
+            commands::get_database_status,
+            commands::reset_database,
+            commands::backup_database,
+            commands::restore_database,
+            export_markers,
+            import_markers,
+            export_analysis,
+            models::list_models,
+            models::download_models,
+            cache::get_cache_usage,
+            cache::clear_cache,
+            preflight::run_preflight,
+            preflight::last_preflight,
+            onboarding::onboarding_state,
+            onboarding::complete_onboarding_step,
+            deepface_resources::get_deepface_resource_usage,
             start_deepface_server,        //? NOT a command, no prefix
+            stop_deepface_server,
             analyze_deepface,
             verify_deepface,
-            detect_deepface
+            detect_deepface,
+            list_deepface_detectors,
+            list_deepface_models,
+            force_license_check,
+            set_license_key,
+            activate_license,
+            deactivate_license,
+            test_license_server,
+            trial::get_trial_status,
+            pause_license_checker,
+            resume_license_checker,
+            set_license_check_interval,
+            is_feature_enabled,
+            get_feature_flags,
+            get_settings,
+            i18n::set_locale,
+            watcher::watch_path,
+            watcher::unwatch_path,
+            watcher::list_watched_paths,
+            ingest::ingest_clips,
+            timecode::format_timecode,
+            timecode::parse_timecode,
+            ws_audit::get_ws_audit,
+            ws_audit::replay_ws_request,
+            ws_auth::create_ws_token,
+            ws_auth::revoke_ws_token,
+            ws_auth::list_ws_tokens,
+            update_settings,
+            set_log_level,
+            get_recent_logs,
+            run_command,
+            stream_command,
+            list_commands,
+            ws_status,
+            websocket::list_ws_clients,
+            discovery::get_server_endpoints,
+            macros::run_macro,
+            macros::save_macro,
+            macros::list_macros,
+            macros::delete_macro,
+            plugins::list_plugins,
+            audio::get_audio_analysis,
+            whisper::transcribe_clip,
+            whisper::get_transcript,
+            moments::build_moments,
+            moments::list_moments,
+            moments::search_moments,
+            moments::export_moments,
+            get_tls_info,
+            extract_frames,
+            probe_media,
+            detect_scenes,
+            get_clip_thumbnail,
+            start_job,
+            cancel_job,
+            resume_job,
+            list_jobs,
+            check_for_update,
+            download_update,
+            install_update,
+            list_crash_reports,
+            submit_crash_report,
+            sync_now,
+            get_sync_status,
+            open_monitor_window,
+            list_cameras,
+            start_camera,
+            stop_camera,
+            get_camera_status,
+            add_reference_face,
+            identify_face,
+            list_reference_faces,
+            delete_reference_face,
+            track_faces,
+            get_clip_emotion_summary,
+            get_project_emotion_summary,
+            app_status::get_app_status,
+            get_metrics
         ])
 
         // Code Running at startup
         .setup(|app| {
-            
+
+            // LOGGING (first, so every module's startup is captured)
+            logging::init(app.handle());
+
+            // CRASH REPORTS (panic hook, right after logging)
+            crash::init(app.handle());
+
+            // SETTINGS (must load before anything reads them)
+            settings::init(app.handle());
+            logging::follow_settings();
+
+            // SECRETS (before anything reading one: database key, install keys, license key, WS access tokens;
+            // OS keychain, encrypted file fallback)
+            secrets::init(app.handle());
+
+            // DATABASE (events first: the database announces its changes)
+            events::init(app.handle());
+            if let Err(e) = database::init_db(app.handle()) {
+                tracing::error!("❌ Database unavailable: {}", e);
+            }
+            encryption::follow_settings();
+            jobs::init();
+
+            // DEEPFACE (bundled sidecar, resolved through the shell plugin; weights under the app data dir)
+            deepFaceProcess::init(app.handle());
+            models::init(app.handle());
+            deepFaceProcess::start_on_setup(); // settings.deepface startup: eager
+            deepface_resources::start_watcher();
+
+            // DISK CACHES (thumbnails, frames, models kept under settings.cache limits)
+            cache::start(app.handle().clone());
+
+            // PREFLIGHT (disk space, sidecar, ports, app data dir; report sent to the onboarding UI)
+            preflight::run_on_startup(app.handle().clone());
+
+            // WS AUDIT (request log for get_ws_audit / replay_ws_request)
+            ws_audit::init(app.handle());
+
+            // WS ACCESS TOKENS (read-only / control roles of WS clients)
+            ws_auth::init();
+
+            // PLUGINS (third-party commands join the registry before the servers take requests)
+            plugins::load_all(app.handle());
+
             // WEBSOCKET
             websocket::start_websocket_server(app.handle().clone());
 
+            // HTTP API (REST fallback of the WS commands, settings.ws httpPort)
+            http_api::start(app.handle().clone());
+
+            // TRAY (before the license checker, which reports to it)
+            if let Err(e) = tray::init(app.handle()) {
+                tracing::error!("❌ {}", e);
+            }
+
             // Start background license checker when app launches
             start_license_checker(app.handle().clone()); 
 
+            // APP STATUS (one aggregate of license / deepface / WS / database / jobs, app-status-changed events)
+            app_status::start(app.handle().clone());
+
+            // TELEMETRY (opt-in upload, see settings.telemetry)
+            metrics::start_uploader(app.handle().clone());
+
+            // CLOUD SYNC (opt-in, see settings.sync)
+            sync::start(app.handle().clone());
+
+            // WATCHED FOLDERS / MARKER FILES (settings.watch)
+            watcher::start(app.handle().clone());
+
+            // DRAG AND DROP (clips dropped on the window -> ingest_clips job)
+            if let Some(window) = app.get_webview_window(instance::MAIN_WINDOW) {
+                let handle = app.handle().clone();
+                window.on_window_event(move |event| {
+                    if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+                        ingest::on_drop(&handle, paths);
+                    }
+                });
+            }
+
+            // UPDATES
+            updater::check_on_startup(app.handle().clone());
+
+            // DEEP LINKS (last: a launch link may run a command right away)
+            deeplink::init(app.handle());
+
             Ok(())
         })
 
         // Build & run app
-        .run(tauri::generate_context!()) 
+        .run(context()) 
         .expect("error while running tauri application");
 }
+
+// One embedded copy of the config and frontend for both modes
+fn context() -> tauri::Context {
+    tauri::generate_context!()
+}
+
+// ----------------- Headless batch mode -----------------
+
+/// `tauri-app --analyze …` (cli.rs): no window, tray, WS server or single-instance check; cli.rs checks the
+/// license once. Returns the exit code.
+pub fn run_headless(batch: cli::Batch) -> i32 {
+    let mut context = context();
+    context.config_mut().app.windows.clear();
+
+    let app = tauri::Builder::default()
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_shell::init())
+        .setup(move |app| {
+            logging::init(app.handle());
+            crash::init(app.handle());
+            settings::init(app.handle());
+            secrets::init(app.handle());
+            events::init(app.handle());
+            if let Err(e) = database::init_db(app.handle()) {
+                tracing::error!("❌ Database unavailable: {}", e);
+            }
+            encryption::follow_settings();
+            deepFaceProcess::init(app.handle());
+            models::init(app.handle());
+            deepface_resources::start_watcher();
+
+            let handle = app.handle().clone();
+            crash::spawn("headless-batch", async move {
+                let code = cli::run(handle.clone(), batch).await;
+                handle.exit(code);
+            });
+            Ok(())
+        })
+        .build(context)
+        .expect("error while building the headless app");
+    app.run_return(|_, _| {})
+}
@@ -1,114 +1,873 @@
 // Import traits and libraries
-use tauri::{Emitter, Manager}; // Tauri tools: Manager lets us access app state, Emitter lets us send events to frontend
-use reqwest::blocking::Client; // Reqwest = HTTP client (blocking means synchronous calls)
-use serde::Deserialize;      // parse JSON responses into Rust structs
-use std::time::Duration;       // For sleep
+use tauri::{Emitter, Manager}; // Manager gives access to app paths, Emitter lets us send events to frontend
+use reqwest::Client;           // Reqwest = async HTTP client (runs on the Tauri/tokio runtime)
+use serde::{Deserialize, Serialize}; // parse JSON responses into Rust structs / send typed events
+use once_cell::sync::{Lazy, OnceCell};
+use rand::Rng;
+use tokio::sync::{mpsc, oneshot};
+use hmac::{Hmac, Mac};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH}; // For sleep / timeouts / cache timestamps
+use tracing::{debug, error, warn};
+
+use crate::app_status;
+use crate::crash;
+use crate::error::AppError;
+use crate::events;
+use crate::features;
+use crate::i18n;
+use crate::settings::{self, LicenseSettings};
+use crate::notify;
+use crate::secrets;
+use crate::tray;
+use crate::trial::{self, TrialState};
 
 
 //____________Const___________
-pub const CLOUD_ADDRESS: &str = "http://localhost:3000";
-pub const DEBUG_LICENSE: bool = false;
-pub const SLEEP_INTERVAL: u64 = 20; /// Sleep interval between license checks (seconds)
+pub const CLOUD_ADDRESS: &str = "http://localhost:3000"; // default, see settings.license.serverUrl
+pub const DEBUG_LICENSE: bool = false; // default of settings.debug license (see logging.rs)
+pub const SLEEP_INTERVAL: u64 = 20; // default interval between license checks (seconds), see settings.license.checkIntervalSecs
+pub const REQUEST_TIMEOUT: u64 = 10; // Max time for a single validation request (seconds)
+
+// Delays used while the server is unreachable: interval (20s) → 1m → 5m → 30m (capped)
+pub const BACKOFF_STEPS: [u64; 3] = [60, 5 * 60, 30 * 60];
+pub const BACKOFF_JITTER: f64 = 0.2; // ±20% so many clients don't retry in lockstep
+
+pub const LICENSE_EVENT: &str = "status-tauri-cloud";
+pub const LICENSE_STATE_EVENT: &str = "license-state";
+pub const LICENSE_EXPIRING_EVENT: &str = "license-expiring";
+pub const EXPIRY_NOTICE_DAYS: [i64; 3] = [14, 7, 1]; // "expiring soon" notices, each sent once per expiry date
+
+// Offline grace: how long a cached successful validation keeps the app unlocked without the server
+pub const GRACE_PERIOD_HOURS: u64 = 72; // default, see settings.license.gracePeriodHours
+const CACHE_FILE: &str = "license_cache.json";
+
+type HmacSha256 = Hmac<Sha256>;
+
+// Ed25519 public key (hex) of the license server, set at compile time (build.rs: required for release builds,
+// debug builds read the local key of cloudServer/genSigningKey.js). None = no signed response is accepted
+const LICENSE_PUBLIC_KEY: Option<&str> = option_env!("LICENSE_PUBLIC_KEY");
+// Signed claims older/newer than this are rejected (replay of captured responses, skewed clocks)
+pub const MAX_CLAIM_AGE: u64 = 5 * 60;
+// Key used by debug builds when none was ever entered (cloudServer's dev database has it)
+const DEV_LICENSE_KEY: &str = "TEST-123";
 
 
 //_____________Struct _________________________
-// Example server response: { "success": true, "message": "✅ License valid" }
+// Example server response: { "success": true, "message": "✅ License valid", "tier": "pro", "expiry": "2026-12-31", "seatsUsed": 1, "seatsTotal": 3 }
+//                          { "success": false, "code": "expired", "message": "❌ License has expired" }
 #[derive(Deserialize, Debug)]
 struct ValidateResponse {
     success: bool,
+    #[serde(default)]
+    code: Option<String>,   // machine readable reason when success == false
+    message: String,
+    #[serde(flatten)]
+    details: LicenseDetails,
+    #[serde(default)]
+    token: Option<SignedToken>, // signed copy of the claims; the unsigned fields above are never trusted
+}
+
+// { "payload": "<exact JSON that was signed>", "signature": "<base64 Ed25519 signature>" }
+#[derive(Deserialize, Debug)]
+struct SignedToken {
+    payload: String,
+    signature: String,
+}
+
+// What the server signs: who the license is for, when it was issued, what it unlocks
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct LicenseClaims {
+    key_hash: String,
+    machine_id: Option<String>,
+    issued_at: u64,
+    #[serde(flatten)]
+    details: LicenseDetails,
+}
+
+/// What the server told us about the license (all optional: older servers only send success/message).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LicenseDetails {
+    #[serde(default)]
+    pub tier: Option<String>,
+    #[serde(default)]
+    pub expiry: Option<String>,     // ISO date (YYYY-MM-DD)
+    #[serde(default)]
+    pub seats_used: Option<u32>,
+    #[serde(default)]
+    pub seats_total: Option<u32>,
+    #[serde(default)]
+    pub features: Vec<String>,      // features unlocked by the tier, see features.rs
+}
+
+/// Why a license check failed.
+/// Serialized as `{ "kind": "expired", "message": "..." }` so the frontend can branch on `kind`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "camelCase")]
+pub enum LicenseError {
+    Network(String),     // server unreachable, timeout, DNS…
+    InvalidKey(String),  // key unknown, revoked or missing
+    Expired(String),     // key known but past its expiry date
+    NotActivated(String),// key valid but this machine isn't registered for it
+    SeatLimit(String),   // activation refused: every seat is taken
+    BadSignature(String),// response not signed by the license server (spoofed or tampered)
+    ServerError(String), // non-200 status, unparsable body, DB error on the server
+}
+
+impl fmt::Display for LicenseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LicenseError::Network(msg) => write!(f, "Network error: {}", msg),
+            LicenseError::InvalidKey(msg) => write!(f, "Invalid license: {}", msg),
+            LicenseError::Expired(msg) => write!(f, "License expired: {}", msg),
+            LicenseError::NotActivated(msg) => write!(f, "Machine not activated: {}", msg),
+            LicenseError::SeatLimit(msg) => write!(f, "Seat limit reached: {}", msg),
+            LicenseError::BadSignature(msg) => write!(f, "Untrusted license response: {}", msg),
+            LicenseError::ServerError(msg) => write!(f, "Server error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for LicenseError {}
+
+impl LicenseError {
+    /// Errors that mean "we couldn't ask" rather than "the server said no".
+    pub fn is_offline(&self) -> bool {
+        matches!(self, LicenseError::Network(_) | LicenseError::ServerError(_))
+    }
+}
+
+impl From<reqwest::Error> for LicenseError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_decode() {
+            LicenseError::ServerError(format!("Parse error: {}", err))
+        } else {
+            LicenseError::Network(err.to_string())
+        }
+    }
+}
+
+/// Overall license state as seen by the app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LicenseState {
+    Valid,          // server confirmed the key
+    OfflineGrace,   // server unreachable, last good validation still within grace window
+    OfflineExpired, // server unreachable and no (valid) cached validation left
+    Invalid,        // server rejected the key
+    Unlicensed,     // no key on this machine (never activated, or deactivated) and no trial left
+    Trial,          // no key, trial running (trial.rs)
+}
+
+/// Payload of the `status-tauri-cloud` event.
+/// Example: `{ "state": "valid", "valid": true, "tier": "pro", "expiresAt": "2026-12-31", "daysLeft": 12, "code": "license.valid", "params": {}, "message": "✅ License valid", "error": null, "graceRemainingSecs": null }`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LicenseStatus {
+    pub state: LicenseState,
+    pub valid: bool,                       // true for Valid, OfflineGrace and Trial (app stays unlocked)
+    pub tier: Option<String>,
+    pub expires_at: Option<String>,        // ISO date (YYYY-MM-DD), None for licenses that don't expire
+    pub days_left: Option<i64>,            // whole days until expires_at, 0 on the last day
+    pub code: &'static str,                // i18n code of `message` ("license.trial", …)
+    pub params: serde_json::Map<String, serde_json::Value>,
+    pub message: String,                   // in the current locale (i18n.rs)
+    pub error: Option<LicenseError>,
+    pub grace_remaining_secs: Option<u64>, // only set while offline
+}
+
+/// Payload of the `license-expiring` event, sent once per EXPIRY_NOTICE_DAYS step.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LicenseExpiring {
+    pub notice_days: i64, // the EXPIRY_NOTICE_DAYS step reached
+    pub days_left: i64,
+    pub expires_at: String,
+    pub tier: Option<String>,
+}
+
+/// Payload of the `license-state` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LicenseStateEvent {
+    pub state: LicenseState,
+    pub machine_id: String,
+    #[serde(flatten)]
+    pub details: LicenseDetails,
+}
+
+/// Last successful validation, stored in the app data dir.
+#[derive(Debug, Serialize, Deserialize)]
+struct LicenseCache {
+    key_hash: String,   // sha256(key) so the cache only applies to the key that produced it
+    validated_at: u64,  // unix seconds
     message: String,
+    #[serde(default)]
+    details: LicenseDetails,
+    signature: String,  // hex HMAC over the fields above
+}
+
+
+/// Result of `test_license_server`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerReport {
+    pub url: String,
+    pub https: bool,
+    pub proxy: Option<String>,
+    pub reachable: bool,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Messages accepted by the checker loop.
+enum CheckerMsg {
+    // Check right away; optionally send the result back
+    CheckNow(Option<oneshot::Sender<Result<String, LicenseError>>>),
+    Pause,  // no scheduled checks until Resume (CheckNow still works)
+    Resume, // check right away, then back to the schedule
 }
 
 
+//_____________Globals _______________________
+static LICENSE_KEY: Lazy<RwLock<String>> = Lazy::new(|| RwLock::new(String::new())); // loaded from secrets.rs at start
+static CHECKER_TX: OnceCell<mpsc::UnboundedSender<CheckerMsg>> = OnceCell::new();
+static CHECKER_PAUSED: AtomicBool = AtomicBool::new(false);
+static LAST_STATE: RwLock<Option<LicenseState>> = RwLock::new(None); // state of the last check (app_status.rs)
+// Last "expiring soon" notice sent: (expiry date, EXPIRY_NOTICE_DAYS step)
+static EXPIRY_NOTICE: Lazy<std::sync::Mutex<Option<(String, i64)>>> = Lazy::new(|| std::sync::Mutex::new(None));
+
+// Stable per-device id sent with every license request (see machine_fingerprint)
+static MACHINE_ID: Lazy<String> = Lazy::new(machine_fingerprint);
+
+static CONFIG: Lazy<RwLock<LicenseSettings>> = Lazy::new(|| RwLock::new(LicenseSettings::default()));
+
+// One HTTP client for the whole app: reuses connections between checks. Rebuilt by `apply_config`.
+static HTTP_CLIENT: Lazy<RwLock<Client>> = Lazy::new(|| {
+    RwLock::new(build_client(&LicenseSettings::default()).expect("Failed to build license HTTP client"))
+});
+
+
 //_____________fn ____________________________
 
-// Function to send license key to the server and get result
-fn validate_license(key: &str, app_handle: &tauri::AppHandle) -> Result<String, String> {
-    // Create an HTTP client
-    let client = Client::new();
+//_____________Config _________________________
+
+fn build_client(config: &LicenseSettings) -> Result<Client, String> {
+    let mut builder = Client::builder().timeout(Duration::from_secs(REQUEST_TIMEOUT));
+
+    if let Some(proxy) = &config.proxy {
+        let proxy = reqwest::Proxy::all(proxy).map_err(|e| format!("Invalid proxy '{}': {}", proxy, e))?;
+        builder = builder.proxy(proxy);
+    }
+    if let Some(path) = &config.ca_cert_path {
+        let pem = std::fs::read(path).map_err(|e| format!("Failed to read CA certificate {:?}: {}", path, e))?;
+        let cert = reqwest::Certificate::from_pem(&pem).map_err(|e| format!("Invalid CA certificate {:?}: {}", path, e))?;
+        builder = builder.add_root_certificate(cert);
+    }
+
+    builder.build().map_err(|e| format!("Failed to build HTTP client: {}", e))
+}
+
+// Use `config` for every following request. Keeps the previous client if the new config is broken.
+fn apply_config(config: LicenseSettings) -> Result<(), String> {
+    let client = build_client(&config)?;
+    if config.server_url.starts_with("http://") && !config.server_url.contains("localhost") && !config.server_url.contains("127.0.0.1") {
+        warn!("⚠️ License server {} is not using HTTPS", config.server_url);
+    }
+    if let Ok(mut current) = HTTP_CLIENT.write() {
+        *current = client;
+    }
+    if let Ok(mut current) = CONFIG.write() {
+        *current = config;
+    }
+    Ok(())
+}
+
+/// Hot-apply new license settings: rebuild the client, then re-check right away if the server changed
+/// (a new interval is picked up by the checker loop through settings::watch).
+pub fn apply_settings(new: &LicenseSettings) {
+    let old = CONFIG.read().map(|c| c.clone()).unwrap_or_default();
+    if let Err(e) = apply_config(new.clone()) {
+        error!("❌ License settings rejected: {}", e);
+        return;
+    }
+
+    let server_changed = old.server_url != new.server_url || old.proxy != new.proxy || old.ca_cert_path != new.ca_cert_path;
+    if let (true, Some(tx)) = (server_changed, CHECKER_TX.get()) {
+        let _ = tx.send(CheckerMsg::CheckNow(None));
+    }
+}
+
+fn grace_period_secs() -> u64 {
+    CONFIG.read().map(|c| c.grace_period_hours).unwrap_or(GRACE_PERIOD_HOURS) * 3600
+}
+
+pub fn server_url() -> String {
+    CONFIG.read().map(|c| c.server_url.trim_end_matches('/').to_string()).unwrap_or_else(|_| CLOUD_ADDRESS.to_string())
+}
+
+pub fn http_client() -> Client {
+    // Client is an Arc inside, cloning is cheap
+    HTTP_CLIENT.read().map(|c| c.clone()).unwrap_or_else(|poisoned| poisoned.into_inner().clone())
+}
+
+
+// POST a license request to the cloud server and map the answer to a typed result
+async fn post_license(endpoint: &str, body: serde_json::Value) -> Result<ValidateResponse, LicenseError> {
+    debug!("Sending {} request to the cloud server...", endpoint);
+
+    let resp = http_client()
+        .post(format!("{}/{}", server_url(), endpoint))
+        .json(&body)
+        .send()
+        .await?;
+
+    // Non-200 response (like 403, 500…)
+    if !resp.status().is_success() {
+        return Err(LicenseError::ServerError(format!("HTTP error: {}", resp.status())));
+    }
+
+    // Try parsing JSON into our ValidateResponse struct
+    let parsed: ValidateResponse = resp.json().await?;
+
+    // debug: show parsed response
+    debug!("Server response: {:?}", parsed);
+
+    if parsed.success {
+        return Ok(parsed);
+    }
+
+    match parsed.code.as_deref() {
+        Some("expired") => Err(LicenseError::Expired(parsed.message)),
+        Some("not_activated") => Err(LicenseError::NotActivated(parsed.message)),
+        Some("seat_limit") => Err(LicenseError::SeatLimit(parsed.message)),
+        Some("server_error") => Err(LicenseError::ServerError(parsed.message)),
+        _ => Err(LicenseError::InvalidKey(parsed.message)),
+    }
+}
+
+// Send license key + machine id to the server: { "key": key, "machineId": id }
+async fn request_validation(key: &str) -> Result<ValidateResponse, LicenseError> {
+    let resp = post_license("validate", serde_json::json!({ "key": key, "machineId": MACHINE_ID.as_str() })).await?;
+    verify_response(resp, key)
+}
+
+// Check the signed claims of a successful response and replace the unsigned details with them.
+// Only "success" answers need this: a forged failure can lock the app but never unlock it.
+fn verify_response(mut resp: ValidateResponse, key: &str) -> Result<ValidateResponse, LicenseError> {
+    let token = resp.token.as_ref().ok_or_else(|| LicenseError::BadSignature("missing signature".into()))?;
+    let claims = verify_token(token)?;
+
+    if claims.key_hash != hash_key(key) {
+        return Err(LicenseError::BadSignature("claims issued for another key".into()));
+    }
+    if claims.machine_id.as_deref() != Some(MACHINE_ID.as_str()) {
+        return Err(LicenseError::BadSignature("claims issued for another machine".into()));
+    }
+    if now_secs().abs_diff(claims.issued_at) > MAX_CLAIM_AGE {
+        return Err(LicenseError::BadSignature("claims are stale (check the system clock)".into()));
+    }
+    if let Some(expiry) = &claims.details.expiry {
+        let expiry = chrono::NaiveDate::parse_from_str(expiry, "%Y-%m-%d")
+            .map_err(|_| LicenseError::BadSignature(format!("invalid expiry date '{}'", expiry)))?;
+        if expiry < chrono::Utc::now().date_naive() {
+            return Err(LicenseError::Expired("❌ License has expired".into()));
+        }
+    }
 
-    if DEBUG_LICENSE {println!("Sending license key to the cloud server...");}
+    resp.details = claims.details;
+    Ok(resp)
+}
+
+fn verify_token(token: &SignedToken) -> Result<LicenseClaims, LicenseError> {
+    let bad = |what: &str| LicenseError::BadSignature(what.to_string());
+
+    let key = LICENSE_PUBLIC_KEY.ok_or_else(|| bad("no license public key in this build (LICENSE_PUBLIC_KEY)"))?;
+    let key_bytes: [u8; 32] = hex::decode(key)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| bad("invalid embedded public key"))?;
+    let public_key = VerifyingKey::from_bytes(&key_bytes).map_err(|_| bad("invalid embedded public key"))?;
+
+    let sig_bytes = base64::engine::general_purpose::STANDARD
+        .decode(&token.signature)
+        .map_err(|_| bad("signature is not base64"))?;
+    let signature = Signature::from_slice(&sig_bytes).map_err(|_| bad("malformed signature"))?;
 
-    // Send POST request to cloud server with { "key": key }
-    let res = client
-        .post(&format!("{}/validate", CLOUD_ADDRESS))
-        .json(&serde_json::json!({ "key": key }))
-        .send();
+    public_key
+        .verify(token.payload.as_bytes(), &signature)
+        .map_err(|_| bad("signature does not match"))?;
 
-    // Handle server response
-    let result = match res {
+    serde_json::from_str(&token.payload).map_err(|e| bad(&format!("unreadable claims: {}", e)))
+}
+
+// Validate the key, fall back to the cached validation when offline, and emit the result to the frontend
+pub async fn validate_license(key: &str, app_handle: &tauri::AppHandle) -> Result<String, LicenseError> {
+    if key.is_empty() {
+        let trial = trial::check(app_handle);
+        if let Some(details) = trial::details(&trial) {
+            let text = i18n::localized("license.trial", serde_json::json!({ "days": trial.days_left.unwrap_or(0) }));
+            let message = text.message.clone();
+            features::apply_license(app_handle, Some(&details));
+            emit_license_state(app_handle, LicenseState::Trial, details.clone());
+            emit_status(app_handle, license_status(LicenseState::Trial, &details, text, None, None));
+            return Ok(message);
+        }
+        let err = match trial.state {
+            TrialState::Expired | TrialState::Tampered => LicenseError::InvalidKey("Trial ended, activate a license key".into()),
+            _ => LicenseError::InvalidKey("No license key on this machine".into()),
+        };
+        features::apply_license(app_handle, None);
+        emit_license_state(app_handle, LicenseState::Unlicensed, LicenseDetails::default());
+        let text = i18n::localized("license.unlicensed", serde_json::json!({ "reason": err.to_string() }));
+        let status = license_status(LicenseState::Unlicensed, &LicenseDetails::default(), text, Some(err.clone()), None);
+        emit_status(app_handle, status);
+        return Err(err);
+    }
+
+    let result = request_validation(key).await;
+
+    let (status, details) = match &result {
         Ok(resp) => {
-            // If HTTP status is success (200 OK)
-            if resp.status().is_success() {
-                // Try parsing JSON into our ValidateResponse struct
-                let parsed: ValidateResponse = resp.json().unwrap_or(ValidateResponse {
-                    success: false,
-                    message: "Parse error".to_string(),
-                });
-
-                // debug: show parsed response
-                if DEBUG_LICENSE {println!("Server response: {:?}", parsed);}
-
-                // Return Ok if license is valid, else Err
-                if parsed.success {Ok(parsed.message)} 
-                else {Err(parsed.message)}
-
-            } else {
-                // Non-200 response (like 403, 500…)
-                Err(format!("HTTP error: {}", resp.status()))
-            }
+            store_cache(app_handle, key, &resp.message, &resp.details);
+            let status = license_status(LicenseState::Valid, &resp.details, i18n::localized("license.valid", serde_json::json!({})), None, None);
+            (status, resp.details.clone())
+        }
+        Err(err) if err.is_offline() => {
+            warn!("❌ License server unreachable: {}", err);
+            let details = load_cache(app_handle, key).map(|c| c.details).unwrap_or_default();
+            (offline_status(app_handle, key, err, &details), details)
         }
-        // Network failure (server down, no internet…)
         Err(err) => {
-            eprintln!("❌ Network error while validating license: {}", err);
-            Err(format!("Network error: {}", err))
+            // The server answered and said no: a cached "valid" must not keep the app unlocked
+            clear_cache(app_handle);
+            let text = i18n::localized("license.invalid", serde_json::json!({ "reason": err.to_string() }));
+            let status = license_status(LicenseState::Invalid, &LicenseDetails::default(), text, Some(err.clone()), None);
+            (status, LicenseDetails::default())
         }
     };
 
+    // Offline grace keeps the cached features; any locked state disables them
+    features::apply_license(app_handle, status.valid.then_some(&details));
+    emit_license_state(app_handle, status.state, details);
+
     // Emit the result regardless of success/failure
-    match &result {
-        Ok(msg) => {
-            let _ = app_handle.emit("status-tauri-cloud", msg);
+    emit_status(app_handle, status);
+
+    result.map(|resp| resp.message)
+}
+
+fn license_status(state: LicenseState, details: &LicenseDetails, text: i18n::Localized, error: Option<LicenseError>, grace_remaining_secs: Option<u64>) -> LicenseStatus {
+    let i18n::Localized { code, params, message } = text;
+    LicenseStatus {
+        state,
+        valid: matches!(state, LicenseState::Valid | LicenseState::OfflineGrace | LicenseState::Trial),
+        tier: details.tier.clone(),
+        expires_at: details.expiry.clone(),
+        days_left: days_until(details.expiry.as_deref()),
+        code,
+        params,
+        message,
+        error,
+        grace_remaining_secs,
+    }
+}
+
+fn days_until(expiry: Option<&str>) -> Option<i64> {
+    let expiry = chrono::NaiveDate::parse_from_str(expiry?, "%Y-%m-%d").ok()?;
+    Some((expiry - chrono::Utc::now().date_naive()).num_days())
+}
+
+fn emit_status(app_handle: &tauri::AppHandle, status: LicenseStatus) {
+    expiry_notice(app_handle, &status);
+    events::publish(events::LICENSE_STATUS, &status);
+    let _ = app_handle.emit(LICENSE_EVENT, status);
+}
+
+// Runs after every check: the first check past a EXPIRY_NOTICE_DAYS step sends LICENSE_EXPIRING_EVENT and a
+// notification, once per step (a renewed license with a new expiry date starts over)
+fn expiry_notice(app_handle: &tauri::AppHandle, status: &LicenseStatus) {
+    let (true, Some(expires_at), Some(days_left)) = (status.valid, &status.expires_at, status.days_left) else { return };
+    let Some(notice_days) = EXPIRY_NOTICE_DAYS.iter().copied().filter(|days| (0..=*days).contains(&days_left)).min() else { return };
+
+    let mut last = EXPIRY_NOTICE.lock().unwrap_or_else(|p| p.into_inner());
+    if last.as_ref().is_some_and(|(expiry, days)| expiry == expires_at && *days <= notice_days) {
+        return;
+    }
+    *last = Some((expires_at.clone(), notice_days));
+    drop(last);
+
+    let payload = LicenseExpiring { notice_days, days_left, expires_at: expires_at.clone(), tier: status.tier.clone() };
+    notify::license_expiring(app_handle, days_left);
+    events::emit_all_surfaces(app_handle, LICENSE_EXPIRING_EVENT, payload);
+}
+
+fn emit_license_state(app_handle: &tauri::AppHandle, state: LicenseState, details: LicenseDetails) {
+    tray::set_license_state(state);
+    if let Ok(mut last) = LAST_STATE.write() {
+        *last = Some(state);
+    }
+    app_status::changed();
+    notify::license_checked(app_handle, state);
+    let payload = LicenseStateEvent { state, machine_id: MACHINE_ID.clone(), details };
+    events::emit_all_surfaces(app_handle, LICENSE_STATE_EVENT, payload);
+}
+
+// Build the status for a failed check while offline, based on the cached validation (if any)
+fn offline_status(app_handle: &tauri::AppHandle, key: &str, err: &LicenseError, details: &LicenseDetails) -> LicenseStatus {
+    let grace_secs = grace_period_secs();
+    let remaining = load_cache(app_handle, key)
+        .map(|cache| (cache.validated_at + grace_secs).saturating_sub(now_secs()))
+        .unwrap_or(0);
+
+    if remaining > 0 {
+        let text = i18n::localized("license.offline_grace", serde_json::json!({ "hours": remaining / 3600 }));
+        license_status(LicenseState::OfflineGrace, details, text, Some(err.clone()), Some(remaining))
+    } else {
+        let text = i18n::localized("license.offline_expired", serde_json::json!({ "reason": err.to_string() }));
+        license_status(LicenseState::OfflineExpired, details, text, Some(err.clone()), Some(0))
+    }
+}
+
+
+
+//_____________Cache __________________________
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+// Signed with the random key of this install (secrets.rs), so the cache can't be edited by hand or forged
+// to extend the grace window. None = no key store available: no offline grace
+fn sign_cache(key_hash: &str, validated_at: u64, message: &str, details: &LicenseDetails) -> Option<HmacSha256> {
+    let signing_key = secrets::install_key(secrets::LICENSE_CACHE_KEY)
+        .map_err(|e| error!("❌ No license cache key, offline grace unavailable: {}", e))
+        .ok()?;
+    let details = serde_json::to_string(details).unwrap_or_default();
+    let mut mac = HmacSha256::new_from_slice(&signing_key).expect("HMAC accepts any key length");
+    mac.update(format!("{}|{}|{}|{}", key_hash, validated_at, message, details).as_bytes());
+    Some(mac)
+}
+
+fn cache_path(app_handle: &tauri::AppHandle) -> Option<PathBuf> {
+    app_handle.path().app_data_dir().ok().map(|dir| dir.join(CACHE_FILE))
+}
+
+fn store_cache(app_handle: &tauri::AppHandle, key: &str, message: &str, details: &LicenseDetails) {
+    let Some(path) = cache_path(app_handle) else { return };
+
+    let key_hash = hash_key(key);
+    let validated_at = now_secs();
+    let Some(mac) = sign_cache(&key_hash, validated_at, message, details) else { return };
+    let signature = hex::encode(mac.finalize().into_bytes());
+    let cache = LicenseCache { key_hash, validated_at, message: message.to_string(), details: details.clone(), signature };
+
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    match serde_json::to_vec(&cache) {
+        Ok(bytes) => {
+            if let Err(e) = std::fs::write(&path, bytes) {
+                error!("❌ Failed to write license cache {:?}: {}", path, e);
+            }
         }
-        Err(err) => {
-            let _ = app_handle.emit("status-tauri-cloud", err);
+        Err(e) => error!("❌ Failed to serialize license cache: {}", e),
+    }
+}
+
+// Returns the cache only if it belongs to `key` and its signature checks out
+fn load_cache(app_handle: &tauri::AppHandle, key: &str) -> Option<LicenseCache> {
+    let bytes = std::fs::read(cache_path(app_handle)?).ok()?;
+    let cache: LicenseCache = serde_json::from_slice(&bytes).ok()?;
+
+    if cache.key_hash != hash_key(key) {
+        return None;
+    }
+    let signature = hex::decode(&cache.signature).ok()?;
+    if sign_cache(&cache.key_hash, cache.validated_at, &cache.message, &cache.details)?.verify_slice(&signature).is_err() {
+        debug!("License cache signature mismatch, ignoring it");
+        return None;
+    }
+    // A timestamp in the future means the clock or the file was tampered with
+    if cache.validated_at > now_secs() {
+        return None;
+    }
+    Some(cache)
+}
+
+fn clear_cache(app_handle: &tauri::AppHandle) {
+    if let Some(path) = cache_path(app_handle) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+
+
+//_____________Machine fingerprint _____________
+
+// Hash of the OS machine id (registry MachineGuid / IOPlatformUUID / /etc/machine-id),
+// so the raw hardware id never leaves the device. Falls back to the host name.
+fn machine_fingerprint() -> String {
+    let raw = machine_uid::get().unwrap_or_else(|e| {
+        warn!("❌ Failed to read machine id, falling back to host name: {}", e);
+        host_name()
+    });
+    let seed = format!("tauri-app|{}|{}|{}", raw.trim(), std::env::consts::OS, std::env::consts::ARCH);
+    hex::encode(Sha256::digest(seed.as_bytes()))
+}
+
+pub fn machine_id() -> String {
+    MACHINE_ID.clone()
+}
+
+fn host_name() -> String {
+    std::env::var("COMPUTERNAME").or_else(|_| std::env::var("HOSTNAME")).unwrap_or_default()
+}
+
+
+/// State of the last license check, None before the first one finished.
+pub fn current_state() -> Option<LicenseState> {
+    LAST_STATE.read().ok().and_then(|state| *state)
+}
+
+pub fn current_key() -> String {
+    LICENSE_KEY.read().map(|k| k.clone()).unwrap_or_default()
+}
+
+// Use `key` from now on and keep it for the next launches (empty = forget it)
+fn store_key(key: &str) {
+    let saved = if key.is_empty() { secrets::delete_secret(secrets::LICENSE_KEY) } else { secrets::set_secret(secrets::LICENSE_KEY, key) };
+    if let Err(e) = saved {
+        error!("❌ License key not saved, it will be asked again next launch: {}", e);
+    }
+    if let Ok(mut current) = LICENSE_KEY.write() {
+        *current = key.to_string();
+    }
+}
+
+fn load_key() {
+    let key = match secrets::get_secret(secrets::LICENSE_KEY) {
+        Ok(Some(key)) => key,
+        Ok(None) if cfg!(debug_assertions) => DEV_LICENSE_KEY.to_string(),
+        Ok(None) => return,
+        Err(e) => {
+            error!("❌ Failed to read the saved license key: {}", e);
+            return;
         }
+    };
+    if let Ok(mut current) = LICENSE_KEY.write() {
+        *current = key;
     }
-    result
 }
 
+/// One check without the background checker (headless batch mode, cli.rs): the saved key, else the trial,
+/// falling back to the signed cache within the offline grace. Feature flags follow the result, as in the app.
+pub async fn check_once(app_handle: &tauri::AppHandle) -> Result<String, LicenseError> {
+    if let Err(e) = apply_config(settings::get().license) {
+        error!("❌ License settings rejected, using defaults: {}", e);
+    }
+    load_key();
+    validate_license(&current_key(), app_handle).await
+}
 
+// Delay before the next check, given how many checks in a row failed to reach the server
+fn backoff_delay(failures: usize) -> Duration {
+    let interval = CONFIG.read().map(|c| c.check_interval_secs).unwrap_or(SLEEP_INTERVAL);
+    let base = match failures {
+        0 => interval,
+        n => BACKOFF_STEPS[(n - 1).min(BACKOFF_STEPS.len() - 1)].max(interval),
+    } as f64;
+    let jitter = rand::thread_rng().gen_range(1.0 - BACKOFF_JITTER..=1.0 + BACKOFF_JITTER);
+    Duration::from_secs_f64(base * jitter)
+}
 
-// This function runs in a separate thread and checks license every 5s
-pub fn start_license_checker(app_handle: tauri::AppHandle) {
-    let key = "TEST-123"; // ⚠️ TODO: replace later with config or user input
 
-    
 
-    // Spawn a background thread so it doesn’t block the main app
-    std::thread::spawn(move || {
+// This task runs on the async runtime and re-checks the license periodically,
+// backing off while the server is unreachable and waking up early on CheckerMsg.
+pub fn start_license_checker(app_handle: tauri::AppHandle) {
+    if let Err(e) = apply_config(settings::get().license) {
+        error!("❌ License settings rejected, using defaults: {}", e);
+    }
+    load_key();
 
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    if CHECKER_TX.set(tx).is_err() {
+        warn!("❌ License checker already started");
+        return;
+    }
 
-        std::thread::sleep(Duration::from_secs(2)); // let UI time to register
-        let _ = validate_license(key, &app_handle); // Initial Check (startup)
+    // Spawn a background task so it doesn’t block the main app
+    crash::spawn("license-checker", async move {
 
+        tokio::time::sleep(Duration::from_secs(2)).await; // let UI time to register
 
+        let mut settings_rx = settings::watch();
+        let mut failures = 0;
+        let mut reply: Option<oneshot::Sender<_>> = None; // Initial Check (startup) has nobody waiting for it
 
         loop {
-            
-            std::thread::sleep(Duration::from_secs(SLEEP_INTERVAL)); // Sleep 5 seconds before checking again
-            let _ = validate_license(key, &app_handle); // Call license validator
+            let result = validate_license(&current_key(), &app_handle).await;
+
+            failures = match &result {
+                Err(err) if err.is_offline() => failures + 1,
+                _ => 0,
+            };
+            if let Some(tx) = reply.take() {
+                let _ = tx.send(result);
+            }
+
+            let checked_at = tokio::time::Instant::now();
+            let mut delay = backoff_delay(failures);
+            debug!("Next license check in {:?} ({} failures)", delay, failures);
+
+            // Sleep until the next scheduled check (none while paused), or until someone asks for one
+            loop {
+                let paused = CHECKER_PAUSED.load(Ordering::SeqCst);
+                tokio::select! {
+                    _ = tokio::time::sleep_until(checked_at + delay), if !paused => break,
+                    msg = rx.recv() => match msg {
+                        Some(CheckerMsg::CheckNow(tx)) => { reply = tx; break; }
+                        Some(CheckerMsg::Pause) => CHECKER_PAUSED.store(true, Ordering::SeqCst),
+                        Some(CheckerMsg::Resume) if paused => { CHECKER_PAUSED.store(false, Ordering::SeqCst); break; }
+                        Some(CheckerMsg::Resume) => {}
+                        None => return,
+                    },
+                    // e.g. a new checkIntervalSecs: recompute the current delay without checking
+                    Ok(()) = settings_rx.changed() => delay = backoff_delay(failures),
+                }
+            }
         }
     });
 }
 
+// Wake the checker loop and wait for the result of its check
+async fn request_check() -> Result<String, LicenseError> {
+    let tx = CHECKER_TX.get().ok_or_else(|| LicenseError::ServerError("License checker not started".into()))?;
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(CheckerMsg::CheckNow(Some(reply_tx)))
+        .map_err(|_| LicenseError::ServerError("License checker stopped".into()))?;
+    reply_rx.await.map_err(|_| LicenseError::ServerError("License checker stopped".into()))?
+}
 
+fn send_to_checker(msg: CheckerMsg) -> Result<(), AppError> {
+    let tx = CHECKER_TX.get().ok_or_else(|| LicenseError::ServerError("License checker not started".into()))?;
+    tx.send(msg).map_err(|_| LicenseError::ServerError("License checker stopped".into()))?;
+    Ok(())
+}
 
 
 
+//_____________Commands ________________________
 
+/// Ping the license server with the current config and report reachability and latency.
+#[tauri::command]
+pub async fn test_license_server() -> ServerReport {
+    let config = CONFIG.read().map(|c| c.clone()).unwrap_or_default();
+    let url = server_url();
 
+    let started = std::time::Instant::now();
+    let result = http_client().get(format!("{}/ping", url)).send().await;
+    let latency_ms = started.elapsed().as_millis() as u64;
 
+    let (reachable, latency_ms, error) = match result {
+        Ok(resp) if resp.status().is_success() => (true, Some(latency_ms), None),
+        Ok(resp) => (false, Some(latency_ms), Some(format!("HTTP error: {}", resp.status()))),
+        Err(e) => (false, None, Some(e.to_string())),
+    };
 
+    ServerReport {
+        https: url.starts_with("https://"),
+        url,
+        proxy: config.proxy.or_else(|| std::env::var("HTTPS_PROXY").or_else(|_| std::env::var("https_proxy")).ok()),
+        reachable,
+        latency_ms,
+        error,
+    }
+}
 
+/// Re-check the license now (manual retry), resetting the backoff on success.
+#[tauri::command]
+pub async fn force_license_check() -> Result<String, AppError> {
+    Ok(request_check().await?)
+}
+
+/// Stop the periodic checks (e.g. while presenting offline); the current state is kept and
+/// `force_license_check` still works.
+#[tauri::command]
+pub fn pause_license_checker() -> Result<(), AppError> {
+    send_to_checker(CheckerMsg::Pause)
+}
+
+/// Check right away and go back to the periodic checks.
+#[tauri::command]
+pub fn resume_license_checker() -> Result<(), AppError> {
+    send_to_checker(CheckerMsg::Resume)
+}
+
+/// Seconds between two checks (settings.license checkIntervalSecs, saved); the pending check is rescheduled.
+/// Example: `invoke("set_license_check_interval", { secs: 300 })`
+#[tauri::command]
+pub fn set_license_check_interval(app_handle: tauri::AppHandle, secs: u64) -> Result<u64, AppError> {
+    let saved = settings::update_settings(app_handle, serde_json::json!({ "license": { "checkIntervalSecs": secs } }))?;
+    Ok(saved.license.check_interval_secs)
+}
 
+/// Register this machine for `key` (takes a seat), then validate it immediately.
+#[tauri::command]
+pub async fn activate_license(key: String) -> Result<LicenseDetails, AppError> {
+    let key = key.trim().to_string();
+    if key.is_empty() {
+        return Err(LicenseError::InvalidKey("License key is required".into()).into());
+    }
+
+    let resp = post_license("activate", serde_json::json!({
+        "key": key,
+        "machineId": MACHINE_ID.as_str(),
+        "hostname": host_name(),
+    })).await?;
+    let resp = verify_response(resp, &key)?;
+
+    store_key(&key);
+    request_check().await?;
+    Ok(resp.details)
+}
+
+/// Release this machine's seat and forget the key locally.
+#[tauri::command]
+pub async fn deactivate_license(app_handle: tauri::AppHandle) -> Result<(), AppError> {
+    let key = current_key();
+    if key.is_empty() {
+        return Err(LicenseError::InvalidKey("No license key on this machine".into()).into());
+    }
+
+    post_license("deactivate", serde_json::json!({ "key": key, "machineId": MACHINE_ID.as_str() })).await?;
+
+    store_key("");
+    clear_cache(&app_handle);
+    let _ = request_check().await; // emits Unlicensed
+    Ok(())
+}
+
+/// Replace the license key and validate it immediately.
+#[tauri::command]
+pub async fn set_license_key(key: String) -> Result<String, AppError> {
+    let key = key.trim().to_string();
+    if key.is_empty() {
+        return Err(LicenseError::InvalidKey("License key is required".into()).into());
+    }
+    store_key(&key);
+    Ok(request_check().await?)
+}
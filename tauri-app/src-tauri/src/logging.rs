@@ -0,0 +1,247 @@
+// src/logging.rs
+//
+// Structured logging with `tracing`.
+// - stdout (dev) + daily rolling file in the app log dir (release builds too)
+// - per-module levels changeable at runtime: `set_log_level("websocket", "debug")`
+// - last MAX_RECENT records kept in memory for the in-app log viewer: `get_recent_logs(200)`
+//
+// settings.debug { websocket, license, deepface } picks the level of those modules (debug when true,
+// info otherwise) and is followed live; the DEBUG_* consts are only its defaults.
+
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write as _;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, Registry};
+
+use crate::error::AppError;
+use crate::settings::DebugSettings;
+use crate::{crash, deepFaceProcess, license, settings, websocket};
+
+
+//____________Const___________
+const CRATE: &str = env!("CARGO_CRATE_NAME");
+const LOG_FILE_PREFIX: &str = "tauri-app";
+const MAX_LOG_FILES: usize = 7;      // days of rolling files kept
+pub const MAX_RECENT: usize = 1000;  // records kept in memory for get_recent_logs
+
+
+//_____________Struct _________________________
+/// One log line as returned to the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogRecord {
+    pub timestamp: String, // RFC 3339, UTC
+    pub level: String,
+    pub target: String,    // module path, e.g. "_tauri_local::websocket"
+    pub message: String,   // message followed by any extra `key=value` fields
+}
+
+// Collects the `message` field and any structured fields of an event
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+}
+
+// Layer feeding the in-memory ring buffer
+struct RecentLogsLayer;
+
+impl<S: Subscriber> Layer<S> for RecentLogsLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let meta = event.metadata();
+        let record = LogRecord {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            level: meta.level().to_string(),
+            target: meta.target().to_string(),
+            message: visitor.message + &visitor.fields,
+        };
+
+        let mut recent = RECENT.lock().unwrap_or_else(|p| p.into_inner());
+        if recent.len() == MAX_RECENT {
+            recent.pop_front();
+        }
+        recent.push_back(record);
+        LOGGED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+
+//_____________Globals _______________________
+static RECENT: Lazy<Mutex<VecDeque<LogRecord>>> = Lazy::new(|| Mutex::new(VecDeque::with_capacity(MAX_RECENT)));
+static LOGGED: AtomicU64 = AtomicU64::new(0); // records since startup, to pick the new ones out of RECENT
+static FILTER_HANDLE: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
+static FILE_GUARD: OnceCell<WorkerGuard> = OnceCell::new(); // flushes the file writer on exit
+
+// Current level per module ("" = everything else)
+static LEVELS: Lazy<Mutex<BTreeMap<String, LevelFilter>>> = Lazy::new(|| Mutex::new(default_levels()));
+
+
+//_____________fn ____________________________
+
+fn debug_level(debug: bool) -> LevelFilter {
+    if debug { LevelFilter::DEBUG } else { LevelFilter::INFO }
+}
+
+fn default_levels() -> BTreeMap<String, LevelFilter> {
+    BTreeMap::from([
+        (String::new(), LevelFilter::INFO),
+        ("websocket".to_string(), debug_level(websocket::DEBUG_WS)),
+        ("license".to_string(), debug_level(license::DEBUG_LICENSE)),
+        ("deepFaceProcess".to_string(), debug_level(deepFaceProcess::DEBUG_DEEPFACE)),
+    ])
+}
+
+fn apply_debug_settings(debug: &DebugSettings) -> Result<(), AppError> {
+    let mut levels = LEVELS.lock().map_err(|_| AppError::Io("Log levels lock poisoned".into()))?;
+    levels.insert("websocket".to_string(), debug_level(debug.websocket));
+    levels.insert("license".to_string(), debug_level(debug.license));
+    levels.insert("deepFaceProcess".to_string(), debug_level(debug.deepface));
+    reload(&levels)
+}
+
+/// Keep the module levels in line with settings.debug. Call from setup after settings::init;
+/// `set_log_level` overrides stay until settings.debug changes again.
+pub fn follow_settings() {
+    let mut rx = settings::watch();
+    crash::spawn("log-levels", async move {
+        let mut applied: Option<DebugSettings> = None;
+        loop {
+            let wanted = rx.borrow_and_update().debug.clone();
+            if applied.as_ref() != Some(&wanted) {
+                match apply_debug_settings(&wanted) {
+                    Ok(()) => tracing::debug!("Log levels follow settings.debug {:?}", wanted),
+                    Err(e) => eprintln!("❌ {}", e),
+                }
+                applied = Some(wanted);
+            }
+            if rx.changed().await.is_err() {
+                return;
+            }
+        }
+    });
+}
+
+// {"": info, "websocket": debug} -> "info,_tauri_local::websocket=debug"
+fn directives(levels: &BTreeMap<String, LevelFilter>) -> String {
+    levels
+        .iter()
+        .map(|(module, level)| match module.as_str() {
+            "" => level.to_string().to_lowercase(),
+            m => format!("{}::{}={}", CRATE, m, level.to_string().to_lowercase()),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Install the global subscriber. Call first thing in setup.
+pub fn init(app_handle: &AppHandle) {
+    let levels = LEVELS.lock().map(|l| directives(&l)).unwrap_or_default();
+    let (filter, handle) = reload::Layer::new(EnvFilter::new(levels));
+
+    // File output is optional: without a log dir we still log to stdout + memory
+    let file_layer = app_handle.path().app_log_dir().ok().and_then(|dir| {
+        let appender = RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(LOG_FILE_PREFIX)
+            .filename_suffix("log")
+            .max_log_files(MAX_LOG_FILES)
+            .build(&dir)
+            .map_err(|e| eprintln!("❌ Failed to open log dir {:?}: {}", dir, e)) // no logger yet
+            .ok()?;
+        let (writer, guard) = tracing_appender::non_blocking(appender);
+        let _ = FILE_GUARD.set(guard);
+        Some(tracing_subscriber::fmt::layer().with_writer(writer).with_ansi(false))
+    });
+
+    let result = tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer())
+        .with(file_layer)
+        .with(RecentLogsLayer)
+        .try_init();
+
+    match result {
+        Ok(()) => {
+            let _ = FILTER_HANDLE.set(handle);
+        }
+        Err(e) => eprintln!("❌ Logger already initialized: {}", e),
+    }
+}
+
+/// Change the level of one module (`websocket`, `license`, `deepFaceProcess`, …) or of
+/// everything else with `module = ""`/`"*"`.
+pub fn set_level(module: &str, level: &str) -> Result<(), AppError> {
+    let level = LevelFilter::from_str(level)
+        .map_err(|_| AppError::InvalidInput(format!("Unknown log level '{}'", level)))?;
+    let module = match module.trim() {
+        "*" | "all" => "",
+        m => m.trim_start_matches(&format!("{}::", CRATE)),
+    };
+
+    let mut levels = LEVELS.lock().map_err(|_| AppError::Io("Log levels lock poisoned".into()))?;
+    levels.insert(module.to_string(), level);
+    reload(&levels)
+}
+
+// Push `levels` into the running filter
+fn reload(levels: &BTreeMap<String, LevelFilter>) -> Result<(), AppError> {
+    let handle = FILTER_HANDLE.get().ok_or_else(|| AppError::Io("Logger not initialized".into()))?;
+    handle
+        .reload(EnvFilter::new(directives(levels)))
+        .map_err(|e| AppError::Io(format!("Failed to apply log level: {}", e)))
+}
+
+
+//_____________Commands ________________________
+
+#[tauri::command]
+pub fn set_log_level(module: String, level: String) -> Result<(), AppError> {
+    set_level(&module, &level)?;
+    tracing::info!("Log level of '{}' set to {}", module, level);
+    Ok(())
+}
+
+/// How many records were logged since startup (`get_recent_logs(now - before)` = the ones in between).
+pub fn logged_count() -> u64 {
+    LOGGED.load(Ordering::SeqCst)
+}
+
+/// Last `n` log records, oldest first.
+#[tauri::command]
+pub fn get_recent_logs(n: usize) -> Vec<LogRecord> {
+    let recent = RECENT.lock().unwrap_or_else(|p| p.into_inner());
+    let skip = recent.len().saturating_sub(n);
+    recent.iter().skip(skip).cloned().collect()
+}
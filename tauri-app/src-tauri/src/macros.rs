@@ -0,0 +1,242 @@
+// src/macros.rs
+//
+// Command sequences run as one: `run_macro` executes registered commands in order, e.g.
+// "analyze the selected clip -> create markers -> export EDL" from one button of the panel.
+// - a step is `{ "command": "analyze_clip", "payload": { "clipId": "${clip}" }, "as": "analysis", "onError": "stop" }`
+// - `${name.path}` in a payload is replaced by a variable: the `vars` given to run_macro, `prev` (result of the
+//   previous successful step) or a step result saved with `as`; the path walks object keys / array indexes
+//   (`${analysis.markers.0.id}`). A string that is only a placeholder takes the variable's JSON type,
+//   placeholders inside text are replaced by the value as text
+// - a failing step stops the macro (onError "stop", the default) or is recorded and skipped ("continue")
+// - every step goes through registry::dispatch with the caller's role: a read-only client can't do more
+//   through a macro than by itself. Macros can't run macros
+// - step outcomes are streamed as partial results while it runs; the final reply lists them all
+// - named macros are saved in the database (`save_macro` / `list_macros` / `delete_macro`) and run with
+//   `run_macro { name, vars }`; their steps are checked when saved (known commands, MAX_STEPS)
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::AppHandle;
+use tokio::time::Instant;
+use tracing::info;
+
+use crate::database::{self, Macro};
+use crate::error::AppError;
+use crate::registry::{self, CommandContext};
+
+
+//____________Const___________
+pub const MAX_STEPS: usize = 50;
+const PREVIOUS: &str = "prev"; // variable holding the result of the last successful step
+const MACRO_COMMANDS: &[&str] = &["run_macro", "save_macro", "delete_macro"]; // not allowed as steps
+
+
+//_____________Struct _________________________
+/// What a failing step does to the rest of the macro.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OnError {
+    #[default]
+    Stop,
+    Continue,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroStep {
+    pub command: String,
+    #[serde(default)]
+    pub payload: Value,
+    #[serde(default, rename = "as")]
+    pub save_as: Option<String>, // variable name for this step's result
+    #[serde(default)]
+    pub on_error: OnError,
+}
+
+/// One step of a run, streamed as it completes.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepOutcome {
+    pub index: usize,
+    pub command: String,
+    pub status: &'static str, // "ok" | "error"
+    pub data: Option<Value>,
+    pub code: Option<&'static str>,
+    pub message: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// What `run_macro` returns.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroRun {
+    pub name: Option<String>,
+    pub completed: bool,            // every step ran (failed "continue" steps included)
+    pub stopped_at: Option<usize>,  // index of the step that stopped it
+    pub steps: Vec<StepOutcome>,
+    pub result: Value,              // result of the last successful step
+}
+
+
+//_____________fn ____________________________
+
+/// Check a JSON step list: a non-empty array of at most MAX_STEPS registered commands.
+pub fn parse_steps(steps: Value) -> Result<Vec<MacroStep>, AppError> {
+    let steps: Vec<MacroStep> = serde_json::from_value(steps).map_err(|e| AppError::InvalidInput(format!("Invalid macro steps: {}", e)))?;
+    if steps.is_empty() || steps.len() > MAX_STEPS {
+        return Err(AppError::InvalidInput(format!("A macro has 1 to {} steps ({} given)", MAX_STEPS, steps.len())));
+    }
+    let known = registry::list();
+    for (index, step) in steps.iter().enumerate() {
+        if MACRO_COMMANDS.contains(&step.command.as_str()) {
+            return Err(AppError::InvalidInput(format!("Step {}: {} can't run inside a macro", index, step.command)));
+        }
+        if !known.iter().any(|c| c.name == step.command) {
+            return Err(AppError::UnknownCommand(format!("{} (step {})", step.command, index)));
+        }
+        if step.save_as.as_deref().is_some_and(|name| name.is_empty() || name == PREVIOUS || name.contains('.')) {
+            return Err(AppError::InvalidInput(format!("Step {}: `as` must be a name without dots, other than {}", index, PREVIOUS)));
+        }
+    }
+    Ok(steps)
+}
+
+/// Run `steps` in order with `vars`, as the caller of `ctx`. Fails only on invalid input; failed steps are in the result.
+pub async fn run(ctx: &CommandContext, name: Option<String>, steps: Vec<MacroStep>, vars: Map<String, Value>) -> Result<MacroRun, AppError> {
+    info!("▶️ Running macro {} ({} steps)", name.as_deref().unwrap_or("(inline)"), steps.len());
+    let mut vars = vars;
+    let mut run = MacroRun { name, completed: true, stopped_at: None, steps: Vec::with_capacity(steps.len()), result: Value::Null };
+
+    for (index, step) in steps.into_iter().enumerate() {
+        let started = Instant::now();
+        let result = match substitute(&step.payload, &vars) {
+            Ok(payload) => registry::dispatch(&step.command, payload, ctx.for_step()).await,
+            Err(err) => Err(err),
+        };
+        let duration_ms = started.elapsed().as_millis() as u64;
+
+        let outcome = match result {
+            Ok(data) => {
+                if let Some(save_as) = &step.save_as {
+                    vars.insert(save_as.clone(), data.clone());
+                }
+                vars.insert(PREVIOUS.to_string(), data.clone());
+                run.result = data.clone();
+                StepOutcome { index, command: step.command, status: "ok", data: Some(data), code: None, message: None, duration_ms }
+            }
+            Err(err) => {
+                StepOutcome { index, command: step.command, status: "error", data: None, code: Some(err.code()), message: Some(err.to_string()), duration_ms }
+            }
+        };
+        let failed = outcome.status == "error";
+        ctx.send_chunk(&outcome)?;
+        run.steps.push(outcome);
+        if failed && step.on_error == OnError::Stop {
+            run.completed = false;
+            run.stopped_at = Some(index);
+            break;
+        }
+    }
+    Ok(run)
+}
+
+/// Run the saved macro `name`, or the inline `steps`.
+pub async fn run_named_or_inline(ctx: &CommandContext, name: Option<String>, steps: Option<Value>, vars: Option<Value>) -> Result<MacroRun, AppError> {
+    let vars = match vars {
+        None | Some(Value::Null) => Map::new(),
+        Some(Value::Object(vars)) => vars,
+        Some(_) => return Err(AppError::InvalidInput("`vars` must be an object".into())),
+    };
+    let steps = match (&name, steps) {
+        (_, Some(steps)) => steps,
+        (Some(name), None) => {
+            let lookup = name.clone();
+            let saved = database::blocking(move || database::get_macro(&lookup)).await?;
+            saved.ok_or_else(|| AppError::InvalidInput(format!("No macro named {}", name)))?.steps
+        }
+        (None, None) => return Err(AppError::InvalidInput("Give the `name` of a saved macro or its `steps`".into())),
+    };
+    run(ctx, name, parse_steps(steps)?, vars).await
+}
+
+/// Check `steps`, then save them as the macro `name` (replacing it).
+pub async fn save(name: String, description: Option<String>, steps: Value) -> Result<Macro, AppError> {
+    let steps = serde_json::to_value(parse_steps(steps)?)?;
+    database::blocking(move || database::save_macro(&name, description.as_deref(), &steps)).await
+}
+
+// `value` with its `${…}` placeholders replaced
+fn substitute(value: &Value, vars: &Map<String, Value>) -> Result<Value, AppError> {
+    match value {
+        Value::String(text) => substitute_text(text, vars),
+        Value::Array(items) => items.iter().map(|item| substitute(item, vars)).collect::<Result<Vec<_>, _>>().map(Value::Array),
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(key, item)| Ok((key.clone(), substitute(item, vars)?)))
+            .collect::<Result<Map<_, _>, AppError>>()
+            .map(Value::Object),
+        other => Ok(other.clone()),
+    }
+}
+
+fn substitute_text(text: &str, vars: &Map<String, Value>) -> Result<Value, AppError> {
+    // Only a placeholder: the variable as is (number, object…)
+    if let Some(path) = text.strip_prefix("${").and_then(|rest| rest.strip_suffix('}')).filter(|path| !path.contains("${")) {
+        return lookup(path, vars).cloned();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        let end = rest[start..].find('}').ok_or_else(|| AppError::InvalidInput(format!("Unclosed placeholder in \"{}\"", text)))?;
+        out.push_str(&rest[..start]);
+        match lookup(&rest[start + 2..start + end], vars)? {
+            Value::String(s) => out.push_str(s),
+            other => out.push_str(&other.to_string()),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(Value::String(out))
+}
+
+// `analysis.markers.0.id` in `vars`
+fn lookup<'a>(path: &str, vars: &'a Map<String, Value>) -> Result<&'a Value, AppError> {
+    let mut parts = path.split('.');
+    let name = parts.next().unwrap_or_default();
+    let mut value = vars.get(name).ok_or_else(|| AppError::InvalidInput(format!("Unknown macro variable ${{{}}}", name)))?;
+    for part in parts {
+        value = match value {
+            Value::Object(fields) => fields.get(part),
+            Value::Array(items) => part.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        }
+        .ok_or_else(|| AppError::InvalidInput(format!("${{{}}}: no `{}` in the variable", path, part)))?;
+    }
+    Ok(value)
+}
+
+
+//_____________Commands ________________________
+
+/// Example: `invoke("run_macro", { name: "analyze-and-export", vars: { clip: 3 } })`,
+/// or inline: `invoke("run_macro", { steps: [{ command: "list_markers", payload: { clipId: "${clip}" } }], vars: { clip: 3 } })`
+#[tauri::command]
+pub async fn run_macro(app_handle: AppHandle, name: Option<String>, steps: Option<Value>, vars: Option<Value>) -> Result<MacroRun, AppError> {
+    run_named_or_inline(&CommandContext::new(app_handle), name, steps, vars).await
+}
+
+/// Example: `invoke("save_macro", { name: "analyze-and-export", description: "…", steps: [...] })`
+#[tauri::command]
+pub async fn save_macro(name: String, description: Option<String>, steps: Value) -> Result<Macro, AppError> {
+    save(name, description, steps).await
+}
+
+#[tauri::command]
+pub async fn list_macros() -> Result<Vec<Macro>, AppError> {
+    database::blocking(database::list_macros).await
+}
+
+#[tauri::command]
+pub async fn delete_macro(name: String) -> Result<bool, AppError> {
+    database::blocking(move || database::delete_macro(&name)).await
+}
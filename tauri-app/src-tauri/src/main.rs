@@ -4,11 +4,77 @@
 //  │   ├── main.rs       # <- entrypoint
 //  │   ├── lib.rs        # <- app wiring (setup, plugins, invoke_handler)
 //  │   ├── commands.rs   # <- frontend-callable functions
-//  │   └── license.rs    # <- license validation logic + background thread
+//  │   └── license.rs    # <- license validation logic + background checker task (pause / resume / interval)
 //  │   └── websocket.rs   # <- manage communication with CEP
-//  │   └── database.rs 
+//  │   └── database.rs   # <- SQLite (clips, markers)
+//  │   └── migrations.rs # <- versioned database schema, applied at startup
+//  │   └── encryption.rs # <- optional AES-GCM of sensitive DB fields, key in the OS keychain
+//  │   └── marker_export.rs  # <- markers to CSV / EDL / FCP XML for Premiere
+//  │   └── marker_import.rs  # <- markers back from Premiere CSV / FCP XML / FCPXML
+//  │   └── features.rs   # <- license-tier feature flags (gates commands)
+//  │   └── settings.rs   # <- persisted AppSettings, hot-applied to the other modules
+//  │   └── logging.rs    # <- tracing setup: rolling log files, runtime levels, recent logs buffer
+//  │   └── registry.rs   # <- command registry shared by WS requests and `run_command`
+//  │   └── middleware.rs # <- chain around every registry command: logging, metrics, rate limit, auth, feature gating
+//  │   └── macros.rs     # <- run_macro: command sequences with ${variables} and stop / continue on error, saved in the DB
+//  │   └── plugins.rs    # <- third-party command libraries (C ABI, JSON in / out) loaded from the plugins dir at startup
+//  │   └── payloads.rs   # <- typed + validated payloads of the registry commands
+//  │   └── events.rs     # <- event streams pushed to subscribed WS clients
+//  │   └── sessions.rs   # <- resumable WS sessions (buffered replies, saved subscriptions)
+//  │   └── tls.rs        # <- self-signed certificate + acceptor for wss://
+//  │   └── media.rs      # <- ffmpeg frame extraction from clips, scene cuts (detect_scenes), ffprobe metadata (probe_media)
+//  │   └── thumbnails.rs # <- cached clip thumbnails for the marker list
+//  │   └── jobs.rs       # <- background jobs: progress, cancel, history in SQLite
+//  │   └── updater.rs    # <- self-update from signed releases, stable / beta channel
+//  │   └── tray.rs       # <- tray icon: license / deepface status, quick actions
+//  │   └── notify.rs     # <- native notifications: job done / failed, license problems
+//  │   └── crash.rs      # <- panic hook writing crash reports, opt-in upload
+//  │   └── metrics.rs    # <- counters + latency histograms, opt-in anonymous upload
+//  │   └── sync.rs       # <- cloud sync of projects / clips / markers, last writer wins
+//  │   └── monitor.rs    # <- detached monitor window: logs, WS / deepface / job status
+//  │   └── deeplink.rs   # <- faceapp:// links running registry commands (analyze_clip, …)
+//  │   └── instance.rs   # <- single instance: second launches forward their args and exit
+//  │   └── analysis.rs   # <- clip analysis: frames sampled per shot (or interval) -> deepface -> emotion markers
+//  │   └── cli.rs        # <- headless batch mode: --analyze <clip> --out <file>, no window
+//  │   └── camera.rs     # <- webcam capture through ffmpeg, live emotion preview events
+//  │   └── faces.rs      # <- reference-face library: add_reference_face, identify_face
+//  │   └── tracking.rs   # <- face tracks across consecutive frames (per-person emotion tracks)
+//  │   └── emotion_stats.rs # <- emotion distribution / arc / peaks of a clip or project, from stored analyses
+//  │   └── audio.rs      # <- clip audio via ffmpeg: EBU R128 loudness, silence / speech segments, loud cues merged into emotion markers
+//  │   └── whisper.rs    # <- speech-to-text with the whisper.cpp sidecar: transcribe_clip job, transcript segments in the DB
+//  │   └── moments.rs    # <- transcript segments joined with emotion peaks into labeled moments: build / search / export
+//  │   └── analysis_export.rs # <- raw analysis rows of a project as jsonl / csv / parquet
+//  │   └── models.rs     # <- deepface weights downloads: resume, checksums, model-download events
+//  │   └── cache.rs      # <- disk caches (thumbnails, frames, models): usage per category, LRU eviction past settings.cache limits
+//  │   └── preflight.rs  # <- environment checks (disk space, sidecar, ports, app data) for onboarding
+//  │   └── deepface_resources.rs # <- sidecar CPU / memory / uptime, memory warning and restart past a cap
+//  │   └── deepface_results.rs # <- typed analyze / verify / detect answers, validated against the sidecar protocol
+//  │   └── trial.rs      # <- 14-day trial without a key: signed local record, usage metering
+//  │   └── app_status.rs # <- AppStatus aggregate (license, deepface, WS, database, jobs), app-status-changed diffs
+//  │   └── secrets.rs    # <- license key / credentials in the OS keychain, encrypted file fallback
+//  │   └── onboarding.rs # <- first-run wizard steps (preflight, license, model preload) kept in settings
+//  │   └── i18n.rs       # <- message codes + bundled locales/*.json catalogs, set_locale
+//  │   └── watcher.rs    # <- watched footage folders / marker files (notify), auto-import of new clips
+//  │   └── ingest.rs     # <- ingest_clips job: dropped / picked clips probed, registered, thumbnailed
+//  │   └── timecode.rs   # <- SMPTE timecode <-> seconds (drop frame at 29.97 / 59.94), format_timecode
+//  │   └── ws_audit.rs   # <- WS request audit (memory + rotating ws_audit.jsonl), replay_ws_request
+//  │   └── ws_auth.rs    # <- WS access tokens (keychain, hashed) bound to a read-only / control role
+//  │   └── ws_transport.rs # <- where WS connections come from: TCP, Unix socket / named pipe (settings.ws localSocket)
+//  │   └── ws_queue.rs   # <- bounded outbound queue + writer task per WS connection (event overflow policy)
+//  │   └── http_api.rs   # <- POST /command on settings.ws httpPort: the WS commands for curl & co, same tokens / envelope
+//  │   └── discovery.rs  # <- mDNS advertisement (_faceapp._tcp) of the WS port, get_server_endpoints
+//  │   └── protocol.rs   # <- WS frames (WsRequest / WsResponse / EventFrame), JSON Schema in protocol.schema.json
+//  │   └── testing.rs    # <- what tests/ reach of the crate: serve_headless (WS server without Tauri)
+//  │   └── error.rs      # <- AppError: one serializable error type for commands and WS replies
 
 //  │   └── deepFaceProcess.rs    # <- spawn deepface AI, send commands to it
+//  ├── tests/
+//  │   └── ws_server.rs  # <- WS integration tests against serve_headless, with a tokio-tungstenite client
+//  │   └── http_api.rs   # <- POST /command integration tests against serve_http_headless, with reqwest
+//  │   └── protocol.rs   # <- WS frame round trips, protocol.schema.json kept up to date
+//  ├── protocol.schema.json # <- JSON Schema of the WS frames, for the CEP panel
+//  ├── build/
+//  │   └── bindings.rs   # <- (build.rs) ../src/bindings.js + .d.ts: typed wrappers of the commands.rs / deepFaceProcess.rs commands
 
 
 
@@ -16,6 +82,17 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use _tauri_local::cli;
+
 fn main() {
-    _tauri_local::run()
+    // `--analyze <clip> --out <file>`: batch run without a window (cli.rs)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match cli::parse(&args) {
+        None => _tauri_local::run(),
+        Some(Ok(batch)) => std::process::exit(_tauri_local::run_headless(batch)),
+        Some(Err(e)) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(cli::EXIT_USAGE)
+        }
+    }
 }
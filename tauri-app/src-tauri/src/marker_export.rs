@@ -0,0 +1,274 @@
+// src/marker_export.rs
+//
+// Write a clip's markers to files Premiere Pro (and most NLEs) can import:
+// - csv : Premiere "Markers" panel layout (name, description, in/out timecodes, duration, color)
+// - edl : CMX3600 with marker comments (`|C:color |M:name |D:duration`), also read by Resolve
+// Timecodes (timecode.rs) are drop frame for 29.97 / 59.94 clips, like Premiere shows them.
+// - xml : Final Cut Pro 7 XML (xmeml v4), Premiere's File > Import reads markers and colors from it
+//
+// Usage: `invoke("export_markers", { clipId: 1, format: "edl", path: "C:/…/markers.edl" })`
+// marker_import.rs reads csv and xml back.
+
+use std::fmt::Write as _;
+use std::path::Path;
+use std::str::FromStr;
+use tracing::info;
+
+use crate::database::{self, Clip, Marker};
+use crate::error::AppError;
+use crate::timecode;
+
+
+//____________Const___________
+
+// Premiere's marker colors: (name, ARGB used by `pproColor`, closest Resolve EDL color)
+pub const MARKER_COLORS: &[(&str, u32, &str)] = &[
+    ("green",  0xFF35B85A, "Green"),
+    ("red",    0xFFE03C31, "Red"),
+    ("purple", 0xFF9B59D0, "Purple"),
+    ("orange", 0xFFF08A24, "Sand"),
+    ("yellow", 0xFFF5D327, "Yellow"),
+    ("white",  0xFFFFFFFF, "Cream"),
+    ("blue",   0xFF3D7CE0, "Blue"),
+    ("cyan",   0xFF22C7D6, "Cyan"),
+];
+const DEFAULT_COLOR: &str = "green"; // Premiere's default marker color
+
+// Color given to a marker that has an emotion label but no explicit color
+pub const EMOTION_COLORS: &[(&str, &str)] = &[
+    ("angry", "red"),
+    ("disgust", "green"),
+    ("fear", "purple"),
+    ("happy", "yellow"),
+    ("sad", "blue"),
+    ("surprise", "orange"),
+    ("neutral", "white"),
+];
+
+
+//_____________Enum _________________________
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Edl,
+    FcpXml,
+}
+
+impl FromStr for ExportFormat {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "edl" | "cmx3600" => Ok(ExportFormat::Edl),
+            "xml" | "fcpxml" | "fcp7" => Ok(ExportFormat::FcpXml),
+            other => Err(AppError::InvalidInput(format!(
+                "Unknown export format '{}' (expected csv, edl or xml)",
+                other
+            ))),
+        }
+    }
+}
+
+
+impl ExportFormat {
+    /// File extension of an export in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Edl => "edl",
+            ExportFormat::FcpXml => "xml",
+        }
+    }
+}
+
+
+//_____________fn ____________________________
+
+/// Render `markers` of `clip` in `format`.
+pub fn render(clip: &Clip, markers: &[Marker], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Csv => render_csv(clip, markers),
+        ExportFormat::Edl => render_edl(clip, markers),
+        ExportFormat::FcpXml => render_fcp_xml(clip, markers),
+    }
+}
+
+fn render_csv(clip: &Clip, markers: &[Marker]) -> String {
+    let mut out = String::from("Marker Name,Description,In,Out,Duration,Marker Type,Color\r\n");
+    for (i, marker) in markers.iter().enumerate() {
+        let _ = write!(
+            out,
+            "{},{},{},{},{},Comment,{}\r\n",
+            csv_field(&marker_name(marker, i)),
+            csv_field(&marker_comment(marker)),
+            timecode(marker.timestamp, clip.fps),
+            timecode(marker.timestamp + marker.duration, clip.fps),
+            timecode(marker.duration, clip.fps),
+            marker_color(marker),
+        );
+    }
+    out
+}
+
+fn render_edl(clip: &Clip, markers: &[Marker]) -> String {
+    let fcm = if timecode::is_drop_frame_rate(clip.fps) { "DROP FRAME" } else { "NON-DROP FRAME" };
+    let mut out = format!("TITLE: {}\r\nFCM: {}\r\n\r\n", clip_name(clip), fcm);
+    for (i, marker) in markers.iter().enumerate() {
+        let frames = duration_frames(marker.duration, clip.fps).max(1); // EDL events can't be empty
+        let start = timecode::to_frames(marker.timestamp, clip.fps);
+        let tc_in = frames_timecode(start, clip.fps);
+        let tc_out = frames_timecode(start + frames, clip.fps);
+        let _ = write!(
+            out,
+            "{:03}  001      V     C        {} {} {} {}  \r\n |C:ResolveColor{} |M:{} |D:{}\r\n",
+            i + 1,
+            tc_in,
+            tc_out,
+            tc_in,
+            tc_out,
+            edl_color(marker_color(marker)),
+            marker_name(marker, i).replace(['\r', '\n', '|'], " "),
+            frames,
+        );
+        if let Some(comment) = marker.comment.as_deref().filter(|c| !c.is_empty()) {
+            let _ = write!(out, "* COMMENT: {}\r\n", comment.replace(['\r', '\n'], " "));
+        }
+        out.push_str("\r\n");
+    }
+    out
+}
+
+fn render_fcp_xml(clip: &Clip, markers: &[Marker]) -> String {
+    let (timebase, ntsc) = xml_rate(clip.fps);
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE xmeml>\n<xmeml version=\"4\">\n");
+    let _ = write!(
+        out,
+        "\t<clip id=\"clip-{}\">\n\t\t<name>{}</name>\n\t\t<rate>\n\t\t\t<timebase>{}</timebase>\n\t\t\t<ntsc>{}</ntsc>\n\t\t</rate>\n",
+        clip.id,
+        xml_escape(&clip_name(clip)),
+        timebase,
+        if ntsc { "TRUE" } else { "FALSE" },
+    );
+    for (i, marker) in markers.iter().enumerate() {
+        let start = timecode::to_frames(marker.timestamp, clip.fps) as i64;
+        let out_frame = match duration_frames(marker.duration, clip.fps) {
+            0 => -1, // point marker
+            frames => start + frames as i64,
+        };
+        let _ = write!(
+            out,
+            "\t\t<marker>\n\t\t\t<name>{}</name>\n\t\t\t<comment>{}</comment>\n\t\t\t<in>{}</in>\n\t\t\t<out>{}</out>\n\t\t\t<pproColor>{}</pproColor>\n\t\t</marker>\n",
+            xml_escape(&marker_name(marker, i)),
+            xml_escape(&marker_comment(marker)),
+            start,
+            out_frame,
+            ppro_color(marker_color(marker)),
+        );
+    }
+    out.push_str("\t</clip>\n</xmeml>\n");
+    out
+}
+
+// Seconds -> "HH:MM:SS:FF" at the clip's frame rate, drop frame when the rate has one
+fn timecode(seconds: f64, fps: f64) -> String {
+    timecode::to_timecode(seconds, fps, timecode::is_drop_frame_rate(fps))
+}
+
+fn frames_timecode(frames: u64, fps: f64) -> String {
+    timecode::frames_to_timecode(frames, fps, timecode::is_drop_frame_rate(fps))
+}
+
+// 29.97 -> (30, true), 25 -> (25, false)
+fn xml_rate(fps: f64) -> (u64, bool) {
+    let timebase = fps.round().max(1.0);
+    (timebase as u64, (timebase - fps).abs() > 0.001)
+}
+
+fn duration_frames(seconds: f64, fps: f64) -> u64 {
+    timecode::to_frames(seconds, fps)
+}
+
+// Name, else the emotion label, else "Marker N"
+fn marker_name(marker: &Marker, index: usize) -> String {
+    marker
+        .name
+        .clone()
+        .filter(|n| !n.is_empty())
+        .or_else(|| marker.label.clone())
+        .unwrap_or_else(|| format!("Marker {}", index + 1))
+}
+
+// Comment, plus the emotion label when the name doesn't already show it
+fn marker_comment(marker: &Marker) -> String {
+    let comment = marker.comment.clone().unwrap_or_default();
+    match (&marker.name, &marker.label) {
+        (Some(name), Some(label)) if !name.is_empty() && name != label => {
+            if comment.is_empty() { label.clone() } else { format!("{} ({})", comment, label) }
+        }
+        _ => comment,
+    }
+}
+
+// Explicit color, else the emotion's color, else Premiere's default
+fn marker_color(marker: &Marker) -> &'static str {
+    let known = |name: &str| MARKER_COLORS.iter().find(|(c, _, _)| c.eq_ignore_ascii_case(name)).map(|(c, _, _)| *c);
+    marker
+        .color
+        .as_deref()
+        .and_then(known)
+        .or_else(|| {
+            let label = marker.label.as_deref()?;
+            EMOTION_COLORS.iter().find(|(e, _)| e.eq_ignore_ascii_case(label)).map(|(_, c)| *c)
+        })
+        .unwrap_or(DEFAULT_COLOR)
+}
+
+fn ppro_color(color: &str) -> u32 {
+    MARKER_COLORS.iter().find(|(c, _, _)| *c == color).map(|(_, argb, _)| *argb).unwrap_or(MARKER_COLORS[0].1)
+}
+
+fn edl_color(color: &str) -> &'static str {
+    MARKER_COLORS.iter().find(|(c, _, _)| *c == color).map(|(_, _, edl)| *edl).unwrap_or(MARKER_COLORS[0].2)
+}
+
+fn clip_name(clip: &Clip) -> String {
+    Path::new(&clip.path)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| clip.path.clone())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+
+//_____________Commands ________________________
+
+/// Write the markers of `clip_id` to `path`. Returns the number of markers exported.
+#[tauri::command]
+pub fn export_markers(clip_id: i64, format: String, path: String) -> Result<usize, AppError> {
+    let format = ExportFormat::from_str(&format)?;
+    let clip = database::get_clip(clip_id)?;
+    let markers = database::list_markers(clip_id)?;
+
+    std::fs::write(&path, render(&clip, &markers, format))
+        .map_err(|e| AppError::Io(format!("Failed to write {}: {}", path, e)))?;
+
+    info!("📤 Exported {} markers of clip {} to {} ({:?})", markers.len(), clip_id, path, format);
+    Ok(markers.len())
+}
@@ -0,0 +1,480 @@
+// src/marker_import.rs
+//
+// Read markers back from files Premiere Pro writes (the counterpart of marker_export.rs):
+// - csv : Premiere "Markers" panel export (tab or comma separated, UTF-8 or UTF-16), timecodes at the clip's fps
+// - xml : Final Cut Pro 7 XML (xmeml, `<marker>` with in/out frames and pproColor)
+//         or FCPXML (Final Cut Pro X, `<marker start="…s" value="…" note="…"/>`), detected from the root element
+//
+// Colors are mapped to MARKER_COLORS (Premiere's newer color names to the closest one), a name or comment
+// that is an emotion becomes the marker's label. Markers already on the clip (same time within half a frame,
+// same name) are skipped, so importing the same file twice adds nothing.
+//
+// Usage: `invoke("import_markers", { clipId: 1, path: "C:/…/markers.csv", format: "csv" })`
+
+use serde::Serialize;
+use std::str::FromStr;
+use tracing::info;
+
+use crate::database::{self, Clip, Marker, MarkerSource, NewMarker};
+use crate::error::AppError;
+use crate::events;
+use crate::marker_export::{ExportFormat, EMOTION_COLORS, MARKER_COLORS};
+use crate::timecode;
+
+
+//____________Const___________
+
+// Premiere 2024+ color names -> closest color of MARKER_COLORS
+const COLOR_ALIASES: &[(&str, &str)] = &[
+    ("violet", "purple"),
+    ("lavender", "purple"),
+    ("iris", "blue"),
+    ("cerulean", "blue"),
+    ("caribbean", "cyan"),
+    ("forest", "green"),
+    ("rose", "red"),
+    ("mango", "orange"),
+];
+
+
+//_____________Struct _________________________
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub skipped: usize,        // already on the clip (or twice in the file)
+    pub invalid: usize,        // rows / elements that couldn't be read
+    pub markers: Vec<Marker>,  // the imported ones, as stored
+}
+
+// A marker read from the file, before deduplication
+struct Parsed {
+    timestamp: f64,
+    duration: f64,
+    name: Option<String>,
+    comment: Option<String>,
+    color: Option<String>,
+}
+
+
+//_____________fn ____________________________
+
+/// Parse `content` as `format` markers for `clip`. Returns the markers and the number of unreadable entries.
+fn parse(clip: &Clip, content: &str, format: ExportFormat) -> Result<(Vec<Parsed>, usize), AppError> {
+    match format {
+        ExportFormat::Csv => Ok(parse_csv(clip, content)),
+        ExportFormat::FcpXml => parse_xml(clip, content),
+        ExportFormat::Edl => Err(AppError::InvalidInput("EDL import is not supported (use csv or xml)".into())),
+    }
+}
+
+// Premiere writes UTF-16 LE with a BOM; our own exports are UTF-8
+fn decode(bytes: &[u8]) -> String {
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => {
+            let units: Vec<u16> = rest.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+            String::from_utf16_lossy(&units)
+        }
+        [0xFE, 0xFF, rest @ ..] => {
+            let units: Vec<u16> = rest.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+            String::from_utf16_lossy(&units)
+        }
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+// One CSV record per line; quoted fields may hold the delimiter, "" is a quote
+fn split_record(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+// Records, joining lines while a quote is open (multi-line descriptions)
+fn csv_records(content: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut pending = String::new();
+    for line in content.lines() {
+        if !pending.is_empty() {
+            pending.push('\n');
+        }
+        pending.push_str(line);
+        if pending.matches('"').count().is_multiple_of(2) {
+            records.push(split_record(&pending, delimiter));
+            pending.clear();
+        }
+    }
+    if !pending.is_empty() {
+        records.push(split_record(&pending, delimiter));
+    }
+    records
+}
+
+fn parse_csv(clip: &Clip, content: &str) -> (Vec<Parsed>, usize) {
+    let header_line = content.lines().next().unwrap_or_default();
+    let delimiter = if header_line.contains('\t') { '\t' } else { ',' };
+    let mut records = csv_records(content, delimiter).into_iter();
+    let header: Vec<String> = records.next().unwrap_or_default().iter().map(|h| h.trim().to_ascii_lowercase()).collect();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let (name_col, desc_col, in_col, out_col, dur_col, color_col) =
+        (column("marker name"), column("description"), column("in"), column("out"), column("duration"), column("color"));
+
+    let mut markers = Vec::new();
+    let mut invalid = 0;
+    for record in records {
+        if record.iter().all(|f| f.trim().is_empty()) {
+            continue;
+        }
+        let field = |col: Option<usize>| col.and_then(|c| record.get(c)).map(|f| f.trim()).filter(|f| !f.is_empty());
+        let Some(timestamp) = field(in_col).and_then(|tc| parse_timecode(tc, clip.fps)) else {
+            invalid += 1;
+            continue;
+        };
+        let duration = field(dur_col)
+            .and_then(|tc| parse_timecode(tc, clip.fps))
+            .or_else(|| field(out_col).and_then(|tc| parse_timecode(tc, clip.fps)).map(|out| out - timestamp))
+            .unwrap_or(0.0)
+            .max(0.0);
+        markers.push(Parsed {
+            timestamp,
+            duration,
+            name: field(name_col).map(String::from),
+            comment: field(desc_col).map(String::from),
+            color: field(color_col).and_then(color_name),
+        });
+    }
+    (markers, invalid)
+}
+
+// "HH:MM:SS:FF" (";" for drop frame) -> seconds
+fn parse_timecode(tc: &str, fps: f64) -> Option<f64> {
+    timecode::parse(tc, fps)
+}
+
+fn parse_xml(clip: &Clip, content: &str) -> Result<(Vec<Parsed>, usize), AppError> {
+    let doc = roxmltree::Document::parse(content).map_err(|e| AppError::InvalidInput(format!("Invalid XML: {}", e)))?;
+    match doc.root_element().tag_name().name() {
+        "xmeml" => Ok(parse_xmeml(clip, &doc)),
+        "fcpxml" => Ok(parse_fcpxml(&doc)),
+        other => Err(AppError::InvalidInput(format!("Unknown XML document <{}> (expected xmeml or fcpxml)", other))),
+    }
+}
+
+fn child_text<'a>(node: roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children().find(|c| c.has_tag_name(name)).and_then(|c| c.text()).map(str::trim).filter(|t| !t.is_empty())
+}
+
+// FCP7: frames at the nearest <rate> up the tree (the clip's fps when there is none)
+fn parse_xmeml(clip: &Clip, doc: &roxmltree::Document) -> (Vec<Parsed>, usize) {
+    let mut markers = Vec::new();
+    let mut invalid = 0;
+    for node in doc.descendants().filter(|n| n.has_tag_name("marker")) {
+        let fps = node
+            .ancestors()
+            .find_map(|a| a.children().find(|c| c.has_tag_name("rate")))
+            .and_then(|rate| {
+                let timebase: f64 = child_text(rate, "timebase")?.parse().ok()?;
+                let ntsc = child_text(rate, "ntsc").is_some_and(|n| n.eq_ignore_ascii_case("true"));
+                Some(if ntsc { timebase * 1000.0 / 1001.0 } else { timebase })
+            })
+            .unwrap_or(clip.fps);
+        let Some(start) = child_text(node, "in").and_then(|f| f.parse::<i64>().ok()).filter(|f| *f >= 0) else {
+            invalid += 1;
+            continue;
+        };
+        let end = child_text(node, "out").and_then(|f| f.parse::<i64>().ok()).filter(|f| *f > start);
+        markers.push(Parsed {
+            timestamp: start as f64 / fps,
+            duration: end.map(|end| (end - start) as f64 / fps).unwrap_or(0.0),
+            name: child_text(node, "name").map(String::from),
+            comment: child_text(node, "comment").map(String::from),
+            color: child_text(node, "pproColor").and_then(|c| c.parse::<u32>().ok()).map(nearest_color),
+        });
+    }
+    (markers, invalid)
+}
+
+// FCPX rational time: "3600/24s", "12.5s", "0s"
+fn parse_rational_time(value: &str) -> Option<f64> {
+    let value = value.trim().strip_suffix('s')?;
+    match value.split_once('/') {
+        Some((num, den)) => {
+            let den: f64 = den.parse().ok()?;
+            (den != 0.0).then_some(num.parse::<f64>().ok()? / den)
+        }
+        None => value.parse().ok(),
+    }
+}
+
+// Markers at their `start` in the clip (offsets of the parent clip aren't applied: Premiere exports clip markers)
+fn parse_fcpxml(doc: &roxmltree::Document) -> (Vec<Parsed>, usize) {
+    let mut markers = Vec::new();
+    let mut invalid = 0;
+    for node in doc.descendants().filter(|n| n.has_tag_name("marker") || n.has_tag_name("chapter-marker")) {
+        let Some(timestamp) = node.attribute("start").and_then(parse_rational_time).filter(|t| *t >= 0.0) else {
+            invalid += 1;
+            continue;
+        };
+        let text = |attr: &str| node.attribute(attr).map(str::trim).filter(|v| !v.is_empty()).map(String::from);
+        markers.push(Parsed {
+            timestamp,
+            duration: node.attribute("duration").and_then(parse_rational_time).unwrap_or(0.0).max(0.0),
+            name: text("value"),
+            comment: text("note"),
+            color: None,
+        });
+    }
+    (markers, invalid)
+}
+
+// A color name of MARKER_COLORS, through COLOR_ALIASES for Premiere's newer names
+fn color_name(value: &str) -> Option<String> {
+    let value = value.trim().to_ascii_lowercase();
+    let value = COLOR_ALIASES.iter().find(|(alias, _)| *alias == value).map(|(_, c)| c.to_string()).unwrap_or(value);
+    MARKER_COLORS.iter().find(|(c, _, _)| *c == value).map(|(c, _, _)| c.to_string())
+}
+
+// pproColor ARGB -> closest MARKER_COLORS entry by RGB distance
+fn nearest_color(argb: u32) -> String {
+    let rgb = |c: u32| [(c >> 16) & 0xFF, (c >> 8) & 0xFF, c & 0xFF].map(|v| v as i64);
+    let target = rgb(argb);
+    let distance = |c: u32| rgb(c).iter().zip(target).map(|(a, b)| (a - b).pow(2)).sum::<i64>();
+    let (name, _, _) = MARKER_COLORS.iter().min_by_key(|(_, c, _)| distance(*c)).unwrap_or(&MARKER_COLORS[0]);
+    name.to_string()
+}
+
+fn emotion(value: &str) -> Option<String> {
+    EMOTION_COLORS.iter().find(|(e, _)| e.eq_ignore_ascii_case(value.trim())).map(|(e, _)| e.to_string())
+}
+
+// Undo marker_export's naming: a name that is an emotion, or a "comment (emotion)" suffix, is the label
+fn into_new_marker(clip_id: i64, parsed: Parsed) -> NewMarker {
+    let Parsed { timestamp, duration, mut name, mut comment, color } = parsed;
+    let mut label = name.as_deref().and_then(emotion);
+    if label.is_some() {
+        name = None;
+    } else if let Some(text) = comment.clone() {
+        if let Some(found) = emotion(&text) {
+            label = Some(found);
+            comment = None;
+        } else if let Some((rest, suffix)) = text.strip_suffix(')').and_then(|t| t.rsplit_once(" (")) {
+            if let Some(found) = emotion(suffix) {
+                label = Some(found);
+                comment = Some(rest.to_string());
+            }
+        }
+    }
+    let name = name.or_else(|| label.clone());
+    NewMarker { clip_id, timestamp, duration, name, comment, label, color, source: MarkerSource::Import, metadata: None, analysis: None }
+}
+
+// Same place on the timeline (half a frame) and same name
+fn is_duplicate(existing: &[(f64, Option<String>)], marker: &NewMarker, fps: f64) -> bool {
+    let tolerance = 0.5 / fps.max(1.0);
+    existing.iter().any(|(t, n)| (t - marker.timestamp).abs() <= tolerance && *n == marker.name)
+}
+
+
+//_____________Commands ________________________
+
+/// Import the markers of the Premiere export at `path` into `clip_id`.
+#[tauri::command]
+pub fn import_markers(clip_id: i64, path: String, format: String) -> Result<ImportSummary, AppError> {
+    let format = ExportFormat::from_str(&format)?;
+    let clip = database::get_clip(clip_id)?;
+    let bytes = std::fs::read(&path).map_err(|e| AppError::Io(format!("Failed to read {}: {}", path, e)))?;
+    let (parsed, invalid) = parse(&clip, &decode(&bytes), format)?;
+
+    let mut existing: Vec<(f64, Option<String>)> =
+        database::list_markers(clip_id)?.into_iter().map(|m| (m.timestamp, m.name)).collect();
+    let mut new_markers = Vec::new();
+    let mut skipped = 0;
+    for parsed in parsed {
+        let marker = into_new_marker(clip_id, parsed);
+        if is_duplicate(&existing, &marker, clip.fps) {
+            skipped += 1;
+            continue;
+        }
+        existing.push((marker.timestamp, marker.name.clone()));
+        new_markers.push(marker);
+    }
+
+    let markers = database::add_markers(&new_markers)?;
+    for marker in &markers {
+        events::publish(events::MARKER_ADDED, marker);
+    }
+    info!("📥 Imported {} markers into clip {} from {} ({} skipped, {} invalid)", markers.len(), clip_id, path, skipped, invalid);
+    Ok(ImportSummary { imported: markers.len(), skipped, invalid, markers })
+}
+
+
+//_____________Tests ________________________
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(fps: f64) -> Clip {
+        Clip { id: 1, project_id: 1, path: "clip.mp4".into(), fps }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn csv_reads_premiere_rows() {
+        let content = "Marker Name\tDescription\tIn\tOut\tDuration\tMarker Type\tColor\n\
+                       Intro\tFirst words\t00:00:01:00\t00:00:02:00\t00:00:01:12\tComment\tRose\n\
+                       \tNo name\t00:00:03:05\t00:00:04:05\t\tComment\t\n";
+        let (markers, invalid) = parse_csv(&clip(25.0), content);
+        assert_eq!((markers.len(), invalid), (2, 0));
+        assert!(close(markers[0].timestamp, 1.0) && close(markers[0].duration, 1.48));
+        assert_eq!(markers[0].name.as_deref(), Some("Intro"));
+        assert_eq!(markers[0].comment.as_deref(), Some("First words"));
+        assert_eq!(markers[0].color.as_deref(), Some("red"));
+        // No Duration: Out - In
+        assert!(close(markers[1].timestamp, 3.2) && close(markers[1].duration, 1.0));
+        assert_eq!((markers[1].name.as_deref(), markers[1].color.as_deref()), (None, None));
+    }
+
+    #[test]
+    fn malformed_csv_rows_are_counted_invalid() {
+        let content = "Marker Name,In,Out\n\
+                       bad digits,00:00:xx:00,\n\
+                       frame past the rate,00:00:00:25,\n\
+                       missing in\n\
+                       ,,\n\
+                       out before in,00:00:02:00,00:00:01:00\n";
+        let (markers, invalid) = parse_csv(&clip(25.0), content);
+        assert_eq!((markers.len(), invalid), (1, 3)); // the blank row is skipped, not invalid
+        assert_eq!(markers[0].name.as_deref(), Some("out before in"));
+        assert!(close(markers[0].duration, 0.0));
+    }
+
+    #[test]
+    fn csv_quotes_hold_delimiters_quotes_and_newlines() {
+        let content = "Marker Name,Description,In\n\
+                       \"Hello, world\",\"She said \"\"no\"\"\nthen left\",00:00:01:00\n";
+        let (markers, invalid) = parse_csv(&clip(25.0), content);
+        assert_eq!((markers.len(), invalid), (1, 0));
+        assert_eq!(markers[0].name.as_deref(), Some("Hello, world"));
+        assert_eq!(markers[0].comment.as_deref(), Some("She said \"no\"\nthen left"));
+        // An unterminated quote runs to the end of the record
+        assert_eq!(split_record("a,\"b,c", ','), vec!["a", "b,c"]);
+    }
+
+    #[test]
+    fn drop_frame_csv_timecodes() {
+        let content = "Marker Name\tIn\nA\t00:01:00;02\nB\t00:01:00;00\n";
+        let (markers, invalid) = parse_csv(&clip(29.97), content);
+        assert_eq!((markers.len(), invalid), (1, 1));
+        assert!(close(markers[0].timestamp, 1800.0 * 1001.0 / 30000.0));
+    }
+
+    #[test]
+    fn utf16_exports_are_decoded() {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend("In\n".encode_utf16().flat_map(u16::to_le_bytes));
+        assert_eq!(decode(&bytes), "In\n");
+        assert_eq!(decode(&[0xEF, 0xBB, 0xBF, b'I', b'n']), "In");
+    }
+
+    #[test]
+    fn rational_times() {
+        assert_eq!(parse_rational_time("3600/24s"), Some(150.0));
+        assert_eq!(parse_rational_time(" 12.5s "), Some(12.5));
+        assert_eq!(parse_rational_time("0s"), Some(0.0));
+        assert_eq!(parse_rational_time("1/0s"), None);
+        assert_eq!(parse_rational_time("12"), None);
+        assert_eq!(parse_rational_time("abc/24s"), None);
+    }
+
+    #[test]
+    fn xmeml_markers_use_the_sequence_rate() {
+        let content = r#"<xmeml version="4"><clip><rate><timebase>30</timebase><ntsc>TRUE</ntsc></rate>
+            <marker><name>A</name><comment>c</comment><in>30</in><out>60</out><pproColor>4292885553</pproColor></marker>
+            <marker><name>no out</name><in>90</in><out>-1</out></marker>
+            <marker><name>negative</name><in>-1</in><out>-1</out></marker>
+            <marker><name>no in</name></marker>
+        </clip></xmeml>"#;
+        let (markers, invalid) = parse_xml(&clip(25.0), content).expect("xmeml");
+        assert_eq!((markers.len(), invalid), (2, 2));
+        assert!(close(markers[0].timestamp, 1.001) && close(markers[0].duration, 1.001));
+        assert_eq!(markers[0].color.as_deref(), Some("red"));
+        assert!(close(markers[1].duration, 0.0));
+    }
+
+    #[test]
+    fn fcpxml_markers() {
+        let content = r#"<fcpxml version="1.9"><library><event><project><sequence><spine><asset-clip>
+            <marker start="3600/24s" duration="1/24s" value="Laugh" note="big one"/>
+            <chapter-marker start="12.5s" value="Chapter"/>
+            <marker start="abc" value="unreadable"/>
+            <marker value="no start"/>
+            <marker start="-2s" value="negative"/>
+        </asset-clip></spine></sequence></project></event></library></fcpxml>"#;
+        let (markers, invalid) = parse_xml(&clip(25.0), content).expect("fcpxml");
+        assert_eq!((markers.len(), invalid), (2, 3));
+        assert!(close(markers[0].timestamp, 150.0) && close(markers[0].duration, 1.0 / 24.0));
+        assert_eq!(markers[0].comment.as_deref(), Some("big one"));
+        assert_eq!(markers[1].name.as_deref(), Some("Chapter"));
+    }
+
+    #[test]
+    fn unknown_or_broken_xml_is_an_error() {
+        assert!(parse_xml(&clip(25.0), "<markers/>").is_err());
+        assert!(parse_xml(&clip(25.0), "<xmeml><marker>").is_err());
+        assert!(parse(&clip(25.0), "", ExportFormat::Edl).is_err());
+    }
+
+    #[test]
+    fn colors_map_to_marker_colors() {
+        assert_eq!(color_name(" Violet ").as_deref(), Some("purple"));
+        assert_eq!(color_name("green").as_deref(), Some("green"));
+        assert_eq!(color_name("magenta"), None);
+        assert_eq!(nearest_color(0xFF3D7CE0), "blue");
+        assert_eq!(nearest_color(0xFFFF0000), "red");
+    }
+
+    #[test]
+    fn emotion_names_become_labels() {
+        let parsed = |name: Option<&str>, comment: Option<&str>| Parsed {
+            timestamp: 1.0,
+            duration: 0.0,
+            name: name.map(String::from),
+            comment: comment.map(String::from),
+            color: None,
+        };
+        let marker = into_new_marker(1, parsed(Some("Angry"), None));
+        assert_eq!((marker.name.as_deref(), marker.label.as_deref()), (Some("angry"), Some("angry")));
+        let marker = into_new_marker(1, parsed(Some("Budget"), Some("raised voice (angry)")));
+        assert_eq!((marker.comment.as_deref(), marker.label.as_deref()), (Some("raised voice"), Some("angry")));
+        let marker = into_new_marker(1, parsed(Some("Budget"), Some("costs (Q3)")));
+        assert_eq!((marker.comment.as_deref(), marker.label), (Some("costs (Q3)"), None));
+    }
+
+    #[test]
+    fn duplicates_are_within_half_a_frame() {
+        let existing = vec![(1.0, Some("A".to_string()))];
+        let marker = |timestamp: f64, name: &str| into_new_marker(1, Parsed { timestamp, duration: 0.0, name: Some(name.into()), comment: None, color: None });
+        assert!(is_duplicate(&existing, &marker(1.019, "A"), 25.0));
+        assert!(!is_duplicate(&existing, &marker(1.021, "A"), 25.0));
+        assert!(!is_duplicate(&existing, &marker(1.0, "B"), 25.0));
+    }
+}
@@ -0,0 +1,420 @@
+// src/media.rs
+//
+// Frame extraction from clips with an ffmpeg binary (no decoding in the panel anymore).
+// - ffmpeg is settings.media ffmpegPath, else binaries/ffmpeg/ffmpeg(.exe) next to the app, else `ffmpeg` on PATH
+// - `extract_frame` returns the JPEG bytes of one frame (deepface pipeline, thumbnails)
+// - `sample_frames` writes one JPEG every N seconds into a directory in a single ffmpeg run, `frames_at` one per
+//   given timestamp
+// - `extract_wav`: the audio track as the 16 kHz mono WAV whisper.cpp transcribes (whisper.rs)
+// - `detect_shots` / `detect_scenes` command: the clip's shots between scene cuts, from ffmpeg's scene score
+//   (select filter, 0..1 difference with the previous frame); analysis.rs samples per shot with them
+// - `probe` / `probe_media` command: duration, fps, resolution, codecs and audio streams of a clip, read with
+//   ffprobe (next to ffmpeg, else on PATH); analysis sampling, exporters (timecodes at the clip's fps) and the UI use it
+// - `extract_frames` command: files in the app cache dir, e.g.
+//   `invoke("extract_frames", { path: "C:/…/clip.mp4", timestamps: [1.5, 12] })` or `{ path, everySecs: 2 }`
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::cache;
+use crate::error::AppError;
+use crate::settings;
+
+
+//____________Const___________
+pub const JPEG_QUALITY: u8 = 3;    // ffmpeg -q:v, 2 (best) .. 31 (worst)
+const FRAME_TIMEOUT: u64 = 30;     // seconds for one single-frame ffmpeg run
+const PROBE_TIMEOUT: u64 = 20;     // seconds for one ffprobe run
+const FRAMES_DIR: &str = "frames"; // under the app cache dir
+pub const SCENE_THRESHOLD: f64 = 0.3; // scene score above which a frame starts a new shot
+const SCENE_WIDTH: u32 = 320;      // frames are scaled down to this before scoring: faster, same cuts
+
+
+//_____________Struct _________________________
+/// A frame written to disk.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameFile {
+    pub timestamp: f64, // seconds from the start of the clip
+    pub path: PathBuf,
+}
+
+/// A shot: from a scene cut (or the start) to the next one (or the end), in seconds.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Shot {
+    pub start: f64,
+    pub end: f64,
+}
+
+/// What ffprobe says about a clip.
+/// Example: `{ duration: 12.48, fps: 29.97, width: 1920, height: 1080, codec: "h264", container: "mov,mp4,…",
+/// bitRate: 8000000, audio: [{ index: 1, codec: "aac", channels: 2, channelLayout: "stereo", sampleRate: 48000 }] }`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaInfo {
+    pub duration: Option<f64>, // seconds
+    pub fps: Option<f64>,      // average frame rate of the video stream
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub codec: Option<String>, // of the first video stream, e.g. "h264"; None = no video
+    pub pixel_format: Option<String>,
+    pub container: Option<String>,
+    pub bit_rate: Option<u64>, // bits per second, whole file
+    pub audio: Vec<AudioStream>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioStream {
+    pub index: u32, // stream index in the file
+    pub codec: Option<String>,
+    pub channels: Option<u32>,
+    pub channel_layout: Option<String>,
+    pub sample_rate: Option<u32>, // Hz
+    pub language: Option<String>, // ISO 639 tag, when the file has one
+}
+
+// `ffprobe -print_format json -show_format -show_streams` (numbers come as strings)
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ProbeOutput {
+    format: ProbeFormat,
+    streams: Vec<ProbeStream>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ProbeFormat {
+    format_name: Option<String>,
+    duration: Option<String>,
+    bit_rate: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ProbeStream {
+    index: u32,
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    pix_fmt: Option<String>,
+    avg_frame_rate: Option<String>,
+    r_frame_rate: Option<String>,
+    duration: Option<String>,
+    channels: Option<u32>,
+    channel_layout: Option<String>,
+    sample_rate: Option<String>,
+    tags: ProbeTags,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ProbeTags {
+    language: Option<String>,
+}
+
+
+//_____________fn ____________________________
+
+/// The ffmpeg executable to run.
+pub fn ffmpeg_path() -> PathBuf {
+    if let Some(path) = settings::get().media.ffmpeg_path {
+        return path;
+    }
+    let bundled = std::env::current_exe().ok().and_then(|exe| {
+        let path = exe.parent()?.join("binaries").join("ffmpeg").join(format!("ffmpeg{}", std::env::consts::EXE_SUFFIX));
+        path.exists().then_some(path)
+    });
+    bundled.unwrap_or_else(|| PathBuf::from("ffmpeg"))
+}
+
+/// The ffprobe executable: next to the ffmpeg in use, else `ffprobe` on PATH.
+pub fn ffprobe_path() -> PathBuf {
+    let name = format!("ffprobe{}", std::env::consts::EXE_SUFFIX);
+    match ffmpeg_path().parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.join(name),
+        _ => PathBuf::from(name),
+    }
+}
+
+fn check_clip(path: &str) -> Result<(), AppError> {
+    if !Path::new(path).is_file() {
+        return Err(AppError::InvalidInput(format!("Clip not found: {}", path)));
+    }
+    Ok(())
+}
+
+// "-vf scale=W:-2" keeps the aspect ratio with an even height (what mjpeg wants)
+fn scale_filter(max_width: Option<u32>) -> Option<String> {
+    max_width.map(|w| format!("scale='min({},iw)':-2", w))
+}
+
+// Run ffmpeg with `args`, return its stdout
+async fn run_ffmpeg(args: &[String]) -> Result<Vec<u8>, AppError> {
+    let ffmpeg = ffmpeg_path();
+    debug!("Running {:?} {:?}", ffmpeg, args);
+    let output = Command::new(&ffmpeg)
+        .args(["-hide_banner", "-loglevel", "error", "-nostdin"])
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| AppError::Media(format!("Failed to run {:?} (is ffmpeg installed?): {}", ffmpeg, e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().last().unwrap_or("no output");
+        return Err(AppError::Media(format!("ffmpeg failed ({}): {}", output.status, reason)));
+    }
+    Ok(output.stdout)
+}
+
+// "30000/1001" -> 29.97; "0/0" (unknown) -> None
+fn parse_rate(rate: &str) -> Option<f64> {
+    let (num, den) = rate.split_once('/').unwrap_or((rate, "1"));
+    let (num, den): (f64, f64) = (num.parse().ok()?, den.parse().ok()?);
+    (num > 0.0 && den > 0.0).then(|| num / den)
+}
+
+/// Duration, codec, resolution and fps of the clip at `path`.
+pub async fn probe(path: &str) -> Result<MediaInfo, AppError> {
+    check_clip(path)?;
+    let ffprobe = ffprobe_path();
+    debug!("Probing {} with {:?}", path, ffprobe);
+    let run = Command::new(&ffprobe)
+        .args(["-v", "error", "-print_format", "json", "-show_format", "-show_streams", path])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(Duration::from_secs(PROBE_TIMEOUT), run)
+        .await
+        .map_err(|_| AppError::Media(format!("ffprobe took more than {}s", PROBE_TIMEOUT)))?
+        .map_err(|e| AppError::Media(format!("Failed to run {:?} (is ffprobe installed?): {}", ffprobe, e)))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::Media(format!("ffprobe failed ({}): {}", output.status, stderr.lines().last().unwrap_or("no output"))));
+    }
+    let report: ProbeOutput =
+        serde_json::from_slice(&output.stdout).map_err(|e| AppError::Media(format!("Unreadable ffprobe output: {}", e)))?;
+    Ok(media_info(report))
+}
+
+fn media_info(report: ProbeOutput) -> MediaInfo {
+    fn number<T: std::str::FromStr>(value: &Option<String>) -> Option<T> {
+        value.as_deref().and_then(|s| s.parse().ok())
+    }
+    let kind = |stream: &&ProbeStream, kind: &str| stream.codec_type.as_deref() == Some(kind);
+    let video = report.streams.iter().find(|s| kind(s, "video"));
+    let audio = report
+        .streams
+        .iter()
+        .filter(|s| kind(s, "audio"))
+        .map(|s| AudioStream {
+            index: s.index,
+            codec: s.codec_name.clone(),
+            channels: s.channels,
+            channel_layout: s.channel_layout.clone(),
+            sample_rate: number(&s.sample_rate),
+            language: s.tags.language.clone(),
+        })
+        .collect();
+    // avg_frame_rate is 0/0 for some streams (VFR, still images); r_frame_rate is the container's base rate
+    let fps = video.and_then(|v| v.avg_frame_rate.as_deref().and_then(parse_rate).or_else(|| v.r_frame_rate.as_deref().and_then(parse_rate)));
+    MediaInfo {
+        duration: number(&report.format.duration).or_else(|| video.and_then(|v| number(&v.duration))),
+        fps,
+        width: video.and_then(|v| v.width),
+        height: video.and_then(|v| v.height),
+        codec: video.and_then(|v| v.codec_name.clone()),
+        pixel_format: video.and_then(|v| v.pix_fmt.clone()),
+        container: report.format.format_name.clone(),
+        bit_rate: number(&report.format.bit_rate),
+        audio,
+    }
+}
+
+/// JPEG bytes of the frame at `timestamp` seconds, at most `max_width` pixels wide.
+pub async fn extract_frame(path: &str, timestamp: f64, max_width: Option<u32>) -> Result<Vec<u8>, AppError> {
+    check_clip(path)?;
+    if !timestamp.is_finite() || timestamp < 0.0 {
+        return Err(AppError::InvalidInput("timestamp must be >= 0".into()));
+    }
+
+    // -ss before -i seeks on keyframes first, then decodes up to the exact time: fast and accurate
+    let mut args = vec!["-ss".to_string(), format!("{:.3}", timestamp), "-i".to_string(), path.to_string()];
+    if let Some(filter) = scale_filter(max_width) {
+        args.extend(["-vf".to_string(), filter]);
+    }
+    args.extend(
+        ["-frames:v", "1", "-q:v", &settings::get().media.jpeg_quality.to_string(), "-f", "image2pipe", "-vcodec", "mjpeg", "pipe:1"]
+            .map(String::from),
+    );
+
+    let jpeg = tokio::time::timeout(Duration::from_secs(FRAME_TIMEOUT), run_ffmpeg(&args))
+        .await
+        .map_err(|_| AppError::Media(format!("ffmpeg took more than {}s for one frame", FRAME_TIMEOUT)))??;
+    if jpeg.is_empty() {
+        return Err(AppError::Media(format!("No frame at {}s (past the end of the clip?)", timestamp)));
+    }
+    Ok(jpeg)
+}
+
+/// One frame every `every_secs` seconds, written as frame_000001.jpg, … into `dir`.
+pub async fn sample_frames(path: &str, every_secs: f64, dir: &Path, max_width: Option<u32>) -> Result<Vec<FrameFile>, AppError> {
+    check_clip(path)?;
+    if !every_secs.is_finite() || every_secs <= 0.0 {
+        return Err(AppError::InvalidInput("everySecs must be > 0".into()));
+    }
+    std::fs::create_dir_all(dir)?;
+
+    let mut filter = format!("fps=1/{}", every_secs);
+    if let Some(scale) = scale_filter(max_width) {
+        filter = format!("{},{}", filter, scale);
+    }
+    let pattern = dir.join("frame_%06d.jpg");
+    let args = vec![
+        "-i".to_string(),
+        path.to_string(),
+        "-vf".to_string(),
+        filter,
+        "-q:v".to_string(),
+        settings::get().media.jpeg_quality.to_string(),
+        pattern.to_string_lossy().into_owned(),
+    ];
+    run_ffmpeg(&args).await?;
+
+    // The fps filter emits frame N (1-based) at (N - 1) * every_secs
+    let mut frames: Vec<FrameFile> = std::fs::read_dir(dir)?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let n: u64 = path.file_stem()?.to_str()?.strip_prefix("frame_")?.parse().ok()?;
+            Some(FrameFile { timestamp: (n.saturating_sub(1)) as f64 * every_secs, path })
+        })
+        .collect();
+    frames.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+    Ok(frames)
+}
+
+/// One frame per timestamp, written as frame_000001.jpg, … into `dir`.
+pub async fn frames_at(path: &str, timestamps: &[f64], dir: &Path, max_width: Option<u32>) -> Result<Vec<FrameFile>, AppError> {
+    std::fs::create_dir_all(dir)?;
+    let mut frames = Vec::with_capacity(timestamps.len());
+    for (i, timestamp) in timestamps.iter().copied().enumerate() {
+        let jpeg = extract_frame(path, timestamp, max_width).await?;
+        let file = dir.join(format!("frame_{:06}.jpg", i + 1));
+        std::fs::write(&file, jpeg)?;
+        frames.push(FrameFile { timestamp, path: file });
+    }
+    Ok(frames)
+}
+
+/// The first audio stream of a clip as a 16 kHz mono 16-bit WAV file at `out` (what whisper.cpp reads).
+pub async fn extract_wav(path: &str, out: &Path) -> Result<(), AppError> {
+    check_clip(path)?;
+    let out = out.to_string_lossy().into_owned();
+    let args = ["-y", "-i", path, "-map", "0:a:0", "-vn", "-ac", "1", "-ar", "16000", "-c:a", "pcm_s16le", &out].map(String::from);
+    run_ffmpeg(&args).await?;
+    Ok(())
+}
+
+/// Times (seconds) of the frames whose scene score is above `threshold` (0..1), in order.
+pub async fn scene_cuts(path: &str, threshold: f64) -> Result<Vec<f64>, AppError> {
+    check_clip(path)?;
+    if !(threshold > 0.0 && threshold < 1.0) {
+        return Err(AppError::InvalidInput("sceneThreshold must be between 0 and 1".into()));
+    }
+    // metadata=print:file=- writes "frame:N pts:… pts_time:T" per selected frame to stdout
+    let filter = format!("scale='min({},iw)':-2,select='gt(scene,{:.3})',metadata=print:file=-", SCENE_WIDTH, threshold);
+    let args = ["-i", path, "-an", "-sn", "-dn", "-vf", &filter, "-f", "null", "-"].map(String::from);
+    let output = run_ffmpeg(&args).await?;
+
+    let mut cuts: Vec<f64> = String::from_utf8_lossy(&output)
+        .lines()
+        .filter_map(|line| line.split_whitespace().find_map(|field| field.strip_prefix("pts_time:")))
+        .filter_map(|time| time.parse::<f64>().ok())
+        .filter(|time| time.is_finite() && *time > 0.0)
+        .collect();
+    cuts.sort_by(f64::total_cmp);
+    cuts.dedup();
+    Ok(cuts)
+}
+
+/// The shots of a clip, cut where the scene score is above `threshold` (default SCENE_THRESHOLD).
+pub async fn detect_shots(path: &str, threshold: Option<f64>) -> Result<Vec<Shot>, AppError> {
+    let duration = probe(path).await?.duration.ok_or_else(|| AppError::Media(format!("Unknown duration of {}", path)))?;
+    let cuts = scene_cuts(path, threshold.unwrap_or(SCENE_THRESHOLD)).await?;
+    let bounds: Vec<f64> = std::iter::once(0.0).chain(cuts.into_iter().filter(|cut| *cut < duration)).chain(std::iter::once(duration)).collect();
+    Ok(bounds.windows(2).filter(|w| w[1] > w[0]).map(|w| Shot { start: w[0], end: w[1] }).collect())
+}
+
+/// Where extracted frames are cached, one directory per extraction (cache.rs keeps it under its limit).
+pub fn frames_dir(app_handle: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(app_handle
+        .path()
+        .app_cache_dir()
+        .map_err(|e| AppError::Io(format!("No app cache dir: {}", e)))?
+        .join(FRAMES_DIR))
+}
+
+/// A fresh directory under the app cache dir for one extraction.
+pub fn new_frames_dir(app_handle: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = frames_dir(app_handle)?.join(hex::encode(rand::random::<[u8; 8]>()));
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Frames at `timestamps`, or every `every_secs` seconds, as files in a new cache directory.
+pub async fn extract_frames_to_cache(
+    app_handle: &AppHandle,
+    path: &str,
+    timestamps: Option<Vec<f64>>,
+    every_secs: Option<f64>,
+    max_width: Option<u32>,
+) -> Result<Vec<FrameFile>, AppError> {
+    let dir = new_frames_dir(app_handle)?;
+    let frames = match (timestamps, every_secs) {
+        (Some(timestamps), None) => frames_at(path, &timestamps, &dir, max_width).await,
+        (None, Some(every_secs)) => sample_frames(path, every_secs, &dir, max_width).await,
+        _ => Err(AppError::InvalidInput("Pass either timestamps or everySecs".into())),
+    };
+    cache::changed();
+    frames
+}
+
+
+//_____________Commands ________________________
+
+/// Example: `invoke("probe_media", { path: "C:/…/clip.mp4" })` -> `{ duration, fps, width, height, codec, audio: [ … ] }`
+#[tauri::command]
+pub async fn probe_media(path: String) -> Result<MediaInfo, AppError> {
+    probe(&path).await
+}
+
+/// Example: `invoke("detect_scenes", { path: "C:/…/clip.mp4", threshold: 0.3 })` -> `[{ start: 0, end: 4.92 }, { start: 4.92, end: 12.48 }]`
+#[tauri::command]
+pub async fn detect_scenes(path: String, threshold: Option<f64>) -> Result<Vec<Shot>, AppError> {
+    detect_shots(&path, threshold).await
+}
+
+#[tauri::command]
+pub async fn extract_frames(
+    app_handle: AppHandle,
+    path: String,
+    timestamps: Option<Vec<f64>>,
+    every_secs: Option<f64>,
+    max_width: Option<u32>,
+) -> Result<Vec<FrameFile>, AppError> {
+    extract_frames_to_cache(&app_handle, &path, timestamps, every_secs, max_width).await
+}
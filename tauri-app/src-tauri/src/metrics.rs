@@ -0,0 +1,148 @@
+// src/metrics.rs
+//
+// In-process metrics for the debug dashboard: `get_metrics()`.
+// - counters: `metrics::incr("ws.requests")`, e.g. ws.requests / ws.errors (+ per command), job.completed.<kind>
+// - latency histograms in ms: `metrics::observe("deepface.latency_ms", elapsed)`, fixed BUCKETS_MS
+// - settings.telemetry enabled (off by default): the snapshot is POSTed to <serverUrl>/metrics every
+//   uploadIntervalSecs. Anonymous: no machine id or license key, only a random id for this run.
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tracing::{debug, warn};
+
+use crate::{crash, license, settings};
+
+
+//____________Const___________
+pub const UPLOAD_INTERVAL: u64 = 3600; // seconds between telemetry uploads
+const BUCKETS_MS: &[u64] = &[10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000, 30000];
+
+
+//_____________Struct _________________________
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Histogram {
+    pub count: u64,
+    pub sum_ms: f64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    pub buckets: Vec<(u64, u64)>, // (upper bound ms, observations <= bound); the rest are above the last bound
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Histogram { count: 0, sum_ms: 0.0, min_ms: f64::MAX, max_ms: 0.0, buckets: BUCKETS_MS.iter().map(|b| (*b, 0)).collect() }
+    }
+
+    fn observe(&mut self, ms: f64) {
+        self.count += 1;
+        self.sum_ms += ms;
+        self.min_ms = self.min_ms.min(ms);
+        self.max_ms = self.max_ms.max(ms);
+        for (bound, count) in self.buckets.iter_mut() {
+            if ms <= *bound as f64 {
+                *count += 1;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsSnapshot {
+    pub run_id: String,
+    pub app_version: String,
+    pub os: String,
+    pub uptime_secs: u64,
+    pub counters: BTreeMap<String, u64>,
+    pub histograms: BTreeMap<String, Histogram>,
+}
+
+struct Registry {
+    started: Instant,
+    run_id: String,
+    counters: BTreeMap<String, u64>,
+    histograms: BTreeMap<String, Histogram>,
+}
+
+
+//_____________Globals _______________________
+static METRICS: Lazy<Mutex<Registry>> = Lazy::new(|| {
+    Mutex::new(Registry {
+        started: Instant::now(),
+        run_id: hex::encode(rand::random::<[u8; 8]>()),
+        counters: BTreeMap::new(),
+        histograms: BTreeMap::new(),
+    })
+});
+
+
+//_____________fn ____________________________
+fn metrics() -> MutexGuard<'static, Registry> {
+    METRICS.lock().unwrap_or_else(|p| p.into_inner())
+}
+
+pub fn incr(name: &str) {
+    add(name, 1);
+}
+
+/// Add `n` to a counter (byte counts…).
+pub fn add(name: &str, n: u64) {
+    *metrics().counters.entry(name.to_string()).or_insert(0) += n;
+}
+
+pub fn observe(name: &str, elapsed: Duration) {
+    let ms = elapsed.as_secs_f64() * 1000.0;
+    metrics().histograms.entry(name.to_string()).or_insert_with(Histogram::new).observe(ms);
+}
+
+pub fn snapshot(app_handle: &AppHandle) -> MetricsSnapshot {
+    let metrics = metrics();
+    MetricsSnapshot {
+        run_id: metrics.run_id.clone(),
+        app_version: app_handle.package_info().version.to_string(),
+        os: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+        uptime_secs: metrics.started.elapsed().as_secs(),
+        counters: metrics.counters.clone(),
+        histograms: metrics.histograms.clone(),
+    }
+}
+
+async fn upload(app_handle: &AppHandle) -> Result<(), String> {
+    let url = format!("{}/metrics", license::server_url());
+    let resp = license::http_client().post(&url).json(&snapshot(app_handle)).send().await.map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status()));
+    }
+    debug!("Metrics uploaded to {}", url);
+    Ok(())
+}
+
+/// Start the telemetry upload loop; it does nothing while settings.telemetry enabled is off.
+pub fn start_uploader(app_handle: AppHandle) {
+    crash::spawn("metrics-upload", async move {
+        loop {
+            // Re-read every round so settings changes apply without a restart
+            let config = settings::get().telemetry;
+            tokio::time::sleep(Duration::from_secs(config.upload_interval_secs)).await;
+            if !settings::get().telemetry.enabled {
+                continue;
+            }
+            if let Err(e) = upload(&app_handle).await {
+                warn!("⚠️ Metrics upload failed: {}", e);
+            }
+        }
+    });
+}
+
+
+//_____________Commands ________________________
+
+#[tauri::command]
+pub fn get_metrics(app_handle: AppHandle) -> MetricsSnapshot {
+    snapshot(&app_handle)
+}
@@ -0,0 +1,170 @@
+// src/middleware.rs
+//
+// What wraps every command run through registry::dispatch, whoever the caller is (WS, HTTP API, webview,
+// deep links, audit replays), so new commands get it without touching the dispatcher or the transports.
+// - a middleware gets the `Call` (command, its registry info, payload, context) and the `Next` step: it can
+//   refuse the call, change it, or run `next.run(call)` and look at the result
+// - CHAIN, outermost first:
+//   - log_call:       debug line per command with its caller, duration and error code
+//   - record_metrics: <source>.requests (+ per command), <source>.errors, <source>.request_ms (source: ws / http / webview)
+//   - rate_limit:     the caller's token bucket, when it has one (a WS connection: settings.ws rateLimitPerSec / Burst)
+//   - auth:           the caller's role (ws_auth.rs): read-only clients only run read-only commands
+//   - feature_gate:   commands of an unlicensed feature (license tier) are refused
+// - unknown commands go through the chain too (logged, counted, rate limited) and fail with unknown_command
+// Audit (ws_audit.rs) stays with the transports: it records request ids and attachment sizes the registry doesn't see.
+
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::debug;
+
+use crate::error::AppError;
+use crate::features;
+use crate::metrics;
+use crate::registry::{CommandContext, CommandInfo, Handler, HandlerFuture};
+use crate::ws_auth;
+
+
+//_____________Struct _________________________
+/// One command going through the chain.
+pub struct Call {
+    pub command: String,
+    pub info: Option<CommandInfo>, // None = not registered: the handler fails with unknown_command
+    pub payload: Value,
+    pub ctx: CommandContext,
+}
+
+pub type Middleware = Arc<dyn Fn(Call, Next) -> HandlerFuture + Send + Sync>;
+
+/// The rest of the chain, then the command's handler.
+pub struct Next {
+    rest: &'static [Middleware],
+    handler: Handler,
+}
+
+/// Token bucket: `burst` requests at once, refilled at `per_sec`.
+pub struct RateLimiter {
+    per_sec: f64,
+    burst: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+
+//_____________Globals _______________________
+static CHAIN: Lazy<Vec<Middleware>> = Lazy::new(|| {
+    vec![
+        Arc::new(log_call) as Middleware,
+        Arc::new(record_metrics) as Middleware,
+        Arc::new(rate_limit) as Middleware,
+        Arc::new(auth) as Middleware,
+        Arc::new(feature_gate) as Middleware,
+    ]
+});
+
+
+//_____________fn ____________________________
+
+/// Run `call` through CHAIN, ending with `handler`.
+pub fn run(call: Call, handler: Handler) -> HandlerFuture {
+    Next { rest: CHAIN.as_slice(), handler }.run(call)
+}
+
+impl Next {
+    pub fn run(self, call: Call) -> HandlerFuture {
+        match self.rest.split_first() {
+            Some((middleware, rest)) => middleware(call, Next { rest, handler: self.handler }),
+            None => (self.handler)(call.ctx, call.payload),
+        }
+    }
+}
+
+impl RateLimiter {
+    pub fn new(per_sec: u32, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        RateLimiter { per_sec: f64::from(per_sec), burst, tokens: burst, last: Instant::now() }
+    }
+
+    /// Ok, or Err(time until the next token).
+    fn try_take(&mut self) -> Result<(), Duration> {
+        if self.per_sec == 0.0 {
+            return Ok(()); // unlimited
+        }
+        let now = Instant::now();
+        self.tokens = (self.tokens + now.duration_since(self.last).as_secs_f64() * self.per_sec).min(self.burst);
+        self.last = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.per_sec))
+        }
+    }
+}
+
+fn log_call(call: Call, next: Next) -> HandlerFuture {
+    Box::pin(async move {
+        let (command, source) = (call.command.clone(), call.ctx.source());
+        debug!("Running command {} ({})", command, source);
+        let started = Instant::now();
+        let result = next.run(call).await;
+        match &result {
+            Ok(_) => debug!("Command {} ({}) done in {:?}", command, source, started.elapsed()),
+            Err(err) => debug!("Command {} ({}) failed in {:?}: {} {}", command, source, started.elapsed(), err.code(), err),
+        }
+        result
+    })
+}
+
+fn record_metrics(call: Call, next: Next) -> HandlerFuture {
+    Box::pin(async move {
+        let source = call.ctx.source();
+        // Per command counters for registered names only: clients don't get to create metrics
+        let per_command = call.info.as_ref().map(|info| format!("{}.requests.{}", source, info.name));
+        let started = Instant::now();
+        let result = next.run(call).await;
+
+        metrics::incr(&format!("{}.requests", source));
+        if let Some(name) = per_command {
+            metrics::incr(&name);
+        }
+        metrics::observe(&format!("{}.request_ms", source), started.elapsed());
+        if result.is_err() {
+            metrics::incr(&format!("{}.errors", source));
+        }
+        result
+    })
+}
+
+fn rate_limit(call: Call, next: Next) -> HandlerFuture {
+    let refused = call.ctx.rate_limiter().and_then(|limiter| {
+        let mut limiter = limiter.lock().unwrap_or_else(|p| p.into_inner());
+        limiter.try_take().err().map(|wait| {
+            AppError::RateLimited(format!("Too many requests (limit {}/s), retry in {} ms", limiter.per_sec, wait.as_millis().max(1)))
+        })
+    });
+    match refused {
+        Some(err) => Box::pin(async move { Err(err) }),
+        None => next.run(call),
+    }
+}
+
+fn auth(call: Call, next: Next) -> HandlerFuture {
+    if call.info.is_some() {
+        if let Err(err) = ws_auth::check(call.ctx.role(), &call.command) {
+            return Box::pin(async move { Err(err) });
+        }
+    }
+    next.run(call)
+}
+
+fn feature_gate(call: Call, next: Next) -> HandlerFuture {
+    if let Some(feature) = call.info.as_ref().and_then(|info| info.feature.clone()) {
+        if !features::is_enabled(&feature) {
+            return Box::pin(async move { Err(AppError::FeatureNotLicensed(feature)) });
+        }
+    }
+    next.run(call)
+}
@@ -0,0 +1,492 @@
+// src/migrations.rs
+//
+// Versioned schema of the local database, applied by database::init_db before anything else touches it.
+// - MIGRATIONS run in order, each in its own transaction; the applied ones are recorded in `schema_version`
+// - migration 1 also upgrades databases from before versioning (columns / tables added in place)
+// - a timestamped backup (database::auto_backup) is taken before migrating an existing database
+// - any failure (or a database written by a newer app) is returned as a MigrationFailure: init_db emits it as
+//   MIGRATION_FAILED_EVENT and nothing else opens the database, so the UI can offer `reset_database`
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::database::{self, DEFAULT_PROJECT};
+use crate::error::AppError;
+
+
+//____________Const___________
+pub const MIGRATION_FAILED_EVENT: &str = "database-migration-failed"; // MigrationFailure
+
+const VERSION_TABLE: &str = "
+    CREATE TABLE IF NOT EXISTS schema_version (
+        version    INTEGER PRIMARY KEY,
+        name       TEXT NOT NULL,
+        applied_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+";
+
+const INITIAL_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS projects (
+        id         INTEGER PRIMARY KEY AUTOINCREMENT,
+        name       TEXT NOT NULL UNIQUE,
+        created_at TEXT NOT NULL DEFAULT (datetime('now')),
+        opened_at  TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')) -- latest = current project
+    );
+
+    CREATE TABLE IF NOT EXISTS clips (
+        id         INTEGER PRIMARY KEY AUTOINCREMENT,
+        project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+        path       TEXT NOT NULL,
+        fps        REAL NOT NULL DEFAULT 25,
+        created_at TEXT NOT NULL DEFAULT (datetime('now')),
+        UNIQUE (project_id, path)
+    );
+
+    CREATE TABLE IF NOT EXISTS markers (
+        id        INTEGER PRIMARY KEY AUTOINCREMENT,
+        clip_id   INTEGER NOT NULL REFERENCES clips(id) ON DELETE CASCADE,
+        timestamp REAL NOT NULL,          -- seconds from the start of the clip
+        label     TEXT,                   -- emotion label, e.g. \"happy\"
+        color     TEXT,                   -- Premiere marker color name, e.g. \"yellow\"
+        name      TEXT,
+        comment   TEXT,
+        duration  REAL NOT NULL DEFAULT 0, -- seconds, 0 = point marker
+        source    TEXT NOT NULL DEFAULT 'manual',
+        metadata  TEXT                    -- JSON, e.g. deepface emotion scores
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_markers_clip ON markers(clip_id, timestamp);
+
+    CREATE TABLE IF NOT EXISTS jobs (
+        id         INTEGER PRIMARY KEY AUTOINCREMENT,
+        kind       TEXT NOT NULL,          -- see jobs.rs KINDS
+        params     TEXT NOT NULL,          -- JSON, enough to run the job again
+        status     TEXT NOT NULL DEFAULT 'queued',
+        progress   REAL NOT NULL DEFAULT 0, -- 0..1
+        message    TEXT,
+        result     TEXT,                   -- JSON
+        error      TEXT,
+        created_at TEXT NOT NULL DEFAULT (datetime('now')),
+        updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+";
+
+const OPERATION_LOG: &str = "
+    CREATE TABLE IF NOT EXISTS operations (
+        id         INTEGER PRIMARY KEY AUTOINCREMENT,
+        project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+        kind       TEXT NOT NULL,          -- add_clip | add_markers | update_marker | delete_marker
+        old_state  TEXT NOT NULL,          -- JSON { clips, markers } before the change
+        new_state  TEXT NOT NULL,          -- JSON { clips, markers } after it
+        undone     INTEGER NOT NULL DEFAULT 0,
+        created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+";
+
+// Full-text index of the markers (database::search_markers), kept up to date by triggers; rowid = marker id
+const MARKER_SEARCH: &str = "
+    CREATE VIRTUAL TABLE IF NOT EXISTS markers_fts USING fts5(
+        label, name, comment, clip,
+        tokenize = 'unicode61 remove_diacritics 2'
+    );
+
+    CREATE TRIGGER IF NOT EXISTS markers_fts_insert AFTER INSERT ON markers BEGIN
+        INSERT INTO markers_fts (rowid, label, name, comment, clip)
+            VALUES (new.id, new.label, new.name, new.comment, (SELECT path FROM clips WHERE id = new.clip_id));
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS markers_fts_update AFTER UPDATE ON markers BEGIN
+        DELETE FROM markers_fts WHERE rowid = old.id;
+        INSERT INTO markers_fts (rowid, label, name, comment, clip)
+            VALUES (new.id, new.label, new.name, new.comment, (SELECT path FROM clips WHERE id = new.clip_id));
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS markers_fts_delete AFTER DELETE ON markers BEGIN
+        DELETE FROM markers_fts WHERE rowid = old.id;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS clips_fts_path AFTER UPDATE OF path ON clips BEGIN
+        UPDATE markers_fts SET clip = new.path WHERE rowid IN (SELECT id FROM markers WHERE clip_id = new.id);
+    END;
+
+    INSERT INTO markers_fts (rowid, label, name, comment, clip)
+        SELECT m.id, m.label, m.name, m.comment, c.path FROM markers m JOIN clips c ON c.id = m.clip_id;
+";
+
+// Cloud sync bookkeeping (sync.rs): a uuid, the server revision and a dirty flag per synced row,
+// deletions of synced rows as tombstones until pushed, and the pull cursor
+const SYNC_STATE: &str = "
+    CREATE TABLE IF NOT EXISTS sync_tombstones (
+        kind       TEXT NOT NULL,          -- project | clip | marker
+        sync_id    TEXT NOT NULL,
+        revision   INTEGER NOT NULL,       -- server revision of the deleted row
+        deleted_at TEXT NOT NULL,
+        PRIMARY KEY (kind, sync_id)
+    );
+
+    CREATE TABLE IF NOT EXISTS sync_state (
+        id        INTEGER PRIMARY KEY CHECK (id = 1),
+        cursor    INTEGER NOT NULL DEFAULT 0, -- last server revision pulled
+        last_sync TEXT
+    );
+    INSERT OR IGNORE INTO sync_state (id) VALUES (1);
+
+    -- Only the indexed columns re-index a marker: the sync triggers update markers right after an insert
+    DROP TRIGGER IF EXISTS markers_fts_update;
+    CREATE TRIGGER markers_fts_update AFTER UPDATE OF clip_id, label, name, comment ON markers BEGIN
+        DELETE FROM markers_fts WHERE rowid = old.id;
+        INSERT INTO markers_fts (rowid, label, name, comment, clip)
+            VALUES (new.id, new.label, new.name, new.comment, (SELECT path FROM clips WHERE id = new.clip_id));
+    END;
+";
+// Known people for identify_face (faces.rs); the embedding is a JSON array, sealed like marker metadata
+const REFERENCE_FACES: &str = "
+    CREATE TABLE IF NOT EXISTS reference_faces (
+        id         INTEGER PRIMARY KEY AUTOINCREMENT,
+        name       TEXT NOT NULL,          -- several rows per name = several images of one person
+        model      TEXT NOT NULL,          -- embeddings of different models can't be compared
+        embedding  TEXT NOT NULL,
+        created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+    CREATE INDEX IF NOT EXISTS idx_reference_faces_model ON reference_faces(model);
+";
+// Full deepface result behind a marker (database::get_analysis). No foreign key on marker_id: a deleted marker
+// can come back through undo, with the same id; the rows go with their clip
+const ANALYSES: &str = "
+    CREATE TABLE IF NOT EXISTS analyses (
+        id         INTEGER PRIMARY KEY AUTOINCREMENT,
+        marker_id  INTEGER NOT NULL UNIQUE,
+        clip_id    INTEGER NOT NULL REFERENCES clips(id) ON DELETE CASCADE,
+        model      TEXT,
+        detector   TEXT,
+        result     TEXT NOT NULL,          -- JSON as deepface returned it, sealed like marker metadata
+        created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+    CREATE INDEX IF NOT EXISTS idx_analyses_clip ON analyses(clip_id);
+";
+// Named command sequences for run_macro (macros.rs)
+const MACROS: &str = "
+    CREATE TABLE IF NOT EXISTS macros (
+        id          INTEGER PRIMARY KEY AUTOINCREMENT,
+        name        TEXT NOT NULL UNIQUE,
+        description TEXT,
+        steps       TEXT NOT NULL,         -- JSON array of { command, payload, as?, onError? }
+        created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+        updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+";
+// Loudness / segments of a clip's audio (audio.rs), one per clip, replaced by a new analysis
+const AUDIO_ANALYSES: &str = "
+    CREATE TABLE IF NOT EXISTS audio_analyses (
+        id         INTEGER PRIMARY KEY AUTOINCREMENT,
+        clip_id    INTEGER NOT NULL UNIQUE REFERENCES clips(id) ON DELETE CASCADE,
+        result     TEXT NOT NULL,          -- JSON audio::AudioAnalysis
+        created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+";
+// Timestamped speech of a clip (whisper.rs), replaced as a whole by a new transcription
+const TRANSCRIPTS: &str = "
+    CREATE TABLE IF NOT EXISTS transcript_segments (
+        id         INTEGER PRIMARY KEY AUTOINCREMENT,
+        clip_id    INTEGER NOT NULL REFERENCES clips(id) ON DELETE CASCADE,
+        start      REAL NOT NULL,          -- seconds, like marker timestamps
+        end        REAL NOT NULL,
+        text       TEXT NOT NULL,
+        language   TEXT,
+        created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+    CREATE INDEX IF NOT EXISTS idx_transcript_segments_clip ON transcript_segments(clip_id, start);
+";
+// Labeled stretches of a clip where speech meets an emotion peak (moments.rs), replaced per clip by build_moments.
+// moments_fts has the columns of markers_fts (the emotion is the label, the title the name, the speech the comment)
+// so database::search_moments takes the same queries as search_markers
+const MOMENTS: &str = "
+    CREATE TABLE IF NOT EXISTS moments (
+        id         INTEGER PRIMARY KEY AUTOINCREMENT,
+        clip_id    INTEGER NOT NULL REFERENCES clips(id) ON DELETE CASCADE,
+        start      REAL NOT NULL,          -- seconds, like marker timestamps
+        end        REAL NOT NULL,
+        emotion    TEXT NOT NULL,
+        score      REAL NOT NULL,          -- deepface score of the peak, 0-100
+        title      TEXT NOT NULL,          -- 'angry rant about budget'
+        text       TEXT,                   -- transcript segments of the moment
+        marker_id  INTEGER,                -- the face marker of the peak
+        created_at TEXT NOT NULL DEFAULT (datetime('now'))
+    );
+    CREATE INDEX IF NOT EXISTS idx_moments_clip ON moments(clip_id, start);
+
+    CREATE VIRTUAL TABLE IF NOT EXISTS moments_fts USING fts5(
+        label, name, comment, clip,
+        tokenize = 'unicode61 remove_diacritics 2'
+    );
+
+    CREATE TRIGGER IF NOT EXISTS moments_fts_insert AFTER INSERT ON moments BEGIN
+        INSERT INTO moments_fts (rowid, label, name, comment, clip)
+            VALUES (new.id, new.emotion, new.title, new.text, (SELECT path FROM clips WHERE id = new.clip_id));
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS moments_fts_delete AFTER DELETE ON moments BEGIN
+        DELETE FROM moments_fts WHERE rowid = old.id;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS clips_moments_fts_path AFTER UPDATE OF path ON clips BEGIN
+        UPDATE moments_fts SET clip = new.path WHERE rowid IN (SELECT id FROM moments WHERE clip_id = new.id);
+    END;
+";
+
+// (table, kind, columns whose change has to be synced)
+const SYNCED_TABLES: &[(&str, &str, &str)] = &[
+    ("projects", "project", "name"),
+    ("clips", "clip", "project_id, path, fps"),
+    ("markers", "marker", "clip_id, timestamp, duration, name, comment, label, color, source, metadata"),
+];
+const SYNC_NOW: &str = "strftime('%Y-%m-%d %H:%M:%f', 'now')";
+
+const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, name: "initial schema", up: initial_schema },
+    Migration { version: 2, name: "operation log", up: operation_log },
+    Migration { version: 3, name: "marker search", up: marker_search },
+    Migration { version: 4, name: "cloud sync", up: cloud_sync },
+    Migration { version: 5, name: "reference faces", up: reference_faces },
+    Migration { version: 6, name: "analyses", up: analyses },
+    Migration { version: 7, name: "macros", up: macros },
+    Migration { version: 8, name: "audio analyses", up: audio_analyses },
+    Migration { version: 9, name: "transcripts", up: transcripts },
+    Migration { version: 10, name: "moments", up: moments },
+];
+
+
+//_____________Struct _________________________
+struct Migration {
+    version: u32,
+    name: &'static str,
+    up: fn(&Connection) -> Result<(), AppError>,
+}
+
+/// Why the database couldn't be brought to the current schema.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationFailure {
+    pub path: String,
+    pub found_version: u32,          // schema of the file before migrating
+    pub latest_version: u32,         // schema this build expects
+    pub failed_version: Option<u32>, // migration that failed, None when the file is newer than this build
+    pub error: String,
+    pub backup: Option<String>,      // copy taken before migrating
+}
+
+
+//_____________fn ____________________________
+
+/// Latest schema version this build knows.
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+/// Schema version of an open database (0 = never migrated).
+pub fn current_version(conn: &Connection) -> Result<u32, AppError> {
+    let versioned: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !versioned {
+        return Ok(0);
+    }
+    Ok(conn.query_row("SELECT COALESCE(MAX(version), 0) FROM schema_version", [], |row| row.get(0))?)
+}
+
+/// Bring the database at `path` to latest_version. Returns the version it was at.
+pub fn run(conn: &mut Connection, path: &Path) -> Result<u32, MigrationFailure> {
+    let latest = latest_version();
+    let failure = |found_version: u32, failed_version: Option<u32>, error: String, backup: Option<String>| MigrationFailure {
+        path: path.to_string_lossy().into_owned(),
+        found_version,
+        latest_version: latest,
+        failed_version,
+        error,
+        backup,
+    };
+
+    let found = current_version(conn).map_err(|e| failure(0, None, e.to_string(), None))?;
+    if found > latest {
+        let error = format!("Database schema v{} is newer than this app (v{}), update the app", found, latest);
+        return Err(failure(found, None, error, None));
+    }
+    if found == latest {
+        return Ok(found);
+    }
+
+    // Anything in the file (a versioned schema, or tables from before versioning) is worth a backup
+    let has_tables = conn
+        .query_row("SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table'", [], |row| row.get::<_, bool>(0))
+        .unwrap_or(true);
+    let backup = if has_tables {
+        match database::auto_backup(conn, path, &format!("v{}", found)) {
+            Ok(backup) => Some(backup.to_string_lossy().into_owned()),
+            Err(e) => {
+                warn!("⚠️ No backup before migrating {:?}: {}", path, e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    conn.execute_batch(VERSION_TABLE).map_err(|e| failure(found, None, e.to_string(), backup.clone()))?;
+    // foreign_keys can only change outside a transaction; checked by hand before each commit
+    conn.execute_batch("PRAGMA foreign_keys = OFF;").map_err(|e| failure(found, None, e.to_string(), backup.clone()))?;
+    for migration in MIGRATIONS.iter().filter(|m| m.version > found) {
+        if let Err(e) = apply(conn, migration) {
+            let _ = conn.execute_batch("PRAGMA foreign_keys = ON;");
+            return Err(failure(found, Some(migration.version), e.to_string(), backup));
+        }
+        info!("🟢 Database migrated to v{} ({})", migration.version, migration.name);
+    }
+    conn.execute_batch("PRAGMA foreign_keys = ON;").map_err(|e| failure(found, None, e.to_string(), backup))?;
+    Ok(found)
+}
+
+fn apply(conn: &mut Connection, migration: &Migration) -> Result<(), AppError> {
+    let tx = conn.transaction()?;
+    (migration.up)(&tx)?;
+    let violation: Option<String> = tx.query_row("PRAGMA foreign_key_check", [], |row| row.get(0)).optional()?;
+    if let Some(table) = violation {
+        return Err(AppError::Db(format!("Foreign key violation in {} after migration", table)));
+    }
+    tx.execute("INSERT INTO schema_version (version, name) VALUES (?1, ?2)", params![migration.version, migration.name])?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn initial_schema(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(INITIAL_SCHEMA)?;
+    upgrade_markers(conn)?;
+    upgrade_clips(conn)
+}
+
+fn operation_log(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(OPERATION_LOG)?;
+    Ok(())
+}
+
+fn marker_search(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(MARKER_SEARCH)?;
+    Ok(())
+}
+
+// Existing rows get a uuid and are pending (dirty); triggers keep the columns up to date from then on
+fn cloud_sync(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(SYNC_STATE)?;
+    for (table, kind, columns) in SYNCED_TABLES {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {table} ADD COLUMN sync_id TEXT;
+             ALTER TABLE {table} ADD COLUMN sync_revision INTEGER NOT NULL DEFAULT 0;
+             ALTER TABLE {table} ADD COLUMN sync_dirty INTEGER NOT NULL DEFAULT 1;
+             ALTER TABLE {table} ADD COLUMN updated_at TEXT;
+             UPDATE {table} SET sync_id = lower(hex(randomblob(16))), updated_at = {now};
+             CREATE UNIQUE INDEX IF NOT EXISTS idx_{table}_sync ON {table}(sync_id);
+
+             CREATE TRIGGER IF NOT EXISTS {table}_sync_insert AFTER INSERT ON {table} WHEN new.sync_id IS NULL BEGIN
+                 UPDATE {table} SET sync_id = lower(hex(randomblob(16))), updated_at = {now} WHERE id = new.id;
+             END;
+
+             CREATE TRIGGER IF NOT EXISTS {table}_sync_update AFTER UPDATE OF {columns} ON {table} BEGIN
+                 UPDATE {table} SET sync_dirty = 1, updated_at = {now} WHERE id = new.id;
+             END;
+
+             CREATE TRIGGER IF NOT EXISTS {table}_sync_delete AFTER DELETE ON {table} WHEN old.sync_revision > 0 BEGIN
+                 INSERT OR REPLACE INTO sync_tombstones (kind, sync_id, revision, deleted_at)
+                     VALUES ('{kind}', old.sync_id, old.sync_revision, {now});
+             END;",
+            now = SYNC_NOW
+        ))?;
+    }
+    Ok(())
+}
+
+fn reference_faces(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(REFERENCE_FACES)?;
+    Ok(())
+}
+
+fn analyses(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(ANALYSES)?;
+    Ok(())
+}
+
+fn macros(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(MACROS)?;
+    Ok(())
+}
+
+fn audio_analyses(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(AUDIO_ANALYSES)?;
+    Ok(())
+}
+
+fn transcripts(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(TRANSCRIPTS)?;
+    Ok(())
+}
+
+fn moments(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch(MOMENTS)?;
+    Ok(())
+}
+
+// Databases created before markers had name/comment/duration/source/metadata
+fn upgrade_markers(conn: &Connection) -> Result<(), AppError> {
+    let columns = conn
+        .prepare("PRAGMA table_info(markers)")?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?;
+    let added = [
+        ("name", "TEXT"),
+        ("comment", "TEXT"),
+        ("duration", "REAL NOT NULL DEFAULT 0"),
+        ("source", "TEXT NOT NULL DEFAULT 'manual'"),
+        ("metadata", "TEXT"),
+    ];
+    for (column, definition) in added {
+        if !columns.iter().any(|c| c == column) {
+            conn.execute_batch(&format!("ALTER TABLE markers ADD COLUMN {} {};", column, definition))?;
+        }
+    }
+    Ok(())
+}
+
+// Databases created before projects: clips had a global UNIQUE(path) and no project_id.
+// SQLite can't change a constraint in place, so the table is rebuilt with every clip in "Default".
+fn upgrade_clips(conn: &Connection) -> Result<(), AppError> {
+    let has_project: bool = conn
+        .prepare("PRAGMA table_info(clips)")?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?
+        .iter()
+        .any(|c| c == "project_id");
+    if has_project {
+        return Ok(());
+    }
+
+    // foreign_keys is off while migrating: dropping the old table must not cascade to the markers
+    conn.execute_batch(&format!(
+        "INSERT OR IGNORE INTO projects (name) VALUES ('{default}');
+         CREATE TABLE clips_new (
+             id         INTEGER PRIMARY KEY AUTOINCREMENT,
+             project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+             path       TEXT NOT NULL,
+             fps        REAL NOT NULL DEFAULT 25,
+             created_at TEXT NOT NULL DEFAULT (datetime('now')),
+             UNIQUE (project_id, path)
+         );
+         INSERT INTO clips_new (id, project_id, path, fps, created_at)
+             SELECT id, (SELECT id FROM projects WHERE name = '{default}'), path, fps, created_at FROM clips;
+         DROP TABLE clips;
+         ALTER TABLE clips_new RENAME TO clips;",
+        default = DEFAULT_PROJECT
+    ))?;
+    info!("🟢 Existing clips moved into project '{}'", DEFAULT_PROJECT);
+    Ok(())
+}
@@ -0,0 +1,300 @@
+// src/models.rs
+//
+// Deepface model weights, downloaded by the app instead of by the Python process on first use.
+// - the sidecar runs with DEEPFACE_HOME = settings.models dir (default <app data>/deepface) and finds the
+//   weights in <DEEPFACE_HOME>/.deepface/weights, where deepface itself would have put them
+// - before a request reaches the sidecar, `ensure_for(req)` downloads what it needs (recognition model,
+//   analysis actions, detector) when settings.models autoDownload is on; models not in CATALOG are left to deepface
+// - downloads go to `<file>.part` and resume from there (HTTP Range) after a failure or a restart,
+//   up to MAX_ATTEMPTS tries per call, with MODEL_DOWNLOAD_EVENT progress on both surfaces
+// - a file pinned in settings.models checksums { "<file>": "<sha256>" } is verified before it is moved in place
+// - `list_models` / `download_models(names)` commands for an onboarding screen
+
+use once_cell::sync::{Lazy, OnceCell};
+use reqwest::header::RANGE;
+use reqwest::{Client, StatusCode};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex as AsyncMutex;
+use tracing::{info, warn};
+
+use crate::cache;
+use crate::error::AppError;
+use crate::events;
+use crate::settings::{self, ModelsSettings};
+
+
+//____________Const___________
+pub const MODEL_DOWNLOAD_EVENT: &str = "model-download"; // ModelProgress
+pub const MODELS_URL: &str = "https://github.com/serengil/deepface_models/releases/download/v1.0"; // default baseUrl
+const HOME_DIR: &str = "deepface";              // default DEEPFACE_HOME, under the app data dir
+const WEIGHTS_DIR: &str = ".deepface/weights";  // under DEEPFACE_HOME, as deepface expects
+const MAX_ATTEMPTS: usize = 3;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+const DEFAULT_MODEL: &str = "VGG-Face";  // deepface's default recognition model
+const DEFAULT_DETECTOR: &str = "opencv"; // deepface's default detector
+const ALL_ACTIONS: &[&str] = &["emotion", "age", "gender", "race"]; // analyze without actions
+
+// (name as deepface knows it, weights file served from baseUrl)
+pub const CATALOG: &[(&str, &str)] = &[
+    ("VGG-Face", "vgg_face_weights.h5"),
+    ("Facenet", "facenet_weights.h5"),
+    ("Facenet512", "facenet512_weights.h5"),
+    ("OpenFace", "openface_weights.h5"),
+    ("DeepID", "deepid_keras_weights.h5"),
+    ("ArcFace", "arcface_weights.h5"),
+    ("GhostFaceNet", "ghostfacenet_v1.h5"),
+    ("emotion", "facial_expression_model_weights.h5"),
+    ("age", "age_model_weights.h5"),
+    ("gender", "gender_model_weights.h5"),
+    ("race", "race_model_single_batch.h5"),
+    ("retinaface", "retinaface.h5"),
+];
+
+
+//_____________Struct _________________________
+/// A catalog model and whether its weights are in place.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelStatus {
+    pub name: String,
+    pub file: String,
+    pub downloaded: bool,
+    pub size: Option<u64>, // bytes on disk
+    pub path: PathBuf,
+}
+
+/// MODEL_DOWNLOAD_EVENT payload.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelProgress {
+    pub name: String,
+    pub file: String,
+    pub stage: &'static str, // "downloading" | "verifying" | "done" | "failed"
+    pub downloaded: u64,     // bytes
+    pub total: Option<u64>,  // unknown when the server doesn't say
+    pub error: Option<String>,
+}
+
+
+//_____________Globals _______________________
+static DATA_DIR: OnceCell<PathBuf> = OnceCell::new();
+static DOWNLOADS: Lazy<AsyncMutex<()>> = Lazy::new(|| AsyncMutex::new(())); // one download at a time
+static CLIENT: Lazy<Client> = Lazy::new(|| Client::builder().connect_timeout(CONNECT_TIMEOUT).build().unwrap_or_default());
+
+
+//_____________fn ____________________________
+
+/// Keep the app data dir the default DEEPFACE_HOME lives in (call once at setup).
+pub fn init(app_handle: &AppHandle) {
+    match app_handle.path().app_data_dir() {
+        Ok(dir) => {
+            let _ = DATA_DIR.set(dir);
+        }
+        Err(e) => warn!("⚠️ No app data dir for the deepface models: {}", e),
+    }
+}
+
+/// DEEPFACE_HOME given to the sidecar.
+pub fn deepface_home() -> Result<PathBuf, AppError> {
+    if let Some(dir) = settings::get().models.dir {
+        return Ok(dir);
+    }
+    DATA_DIR
+        .get()
+        .map(|dir| dir.join(HOME_DIR))
+        .ok_or_else(|| AppError::Io("Models directory not initialized".into()))
+}
+
+/// Where the catalog weights are (cache.rs counts them as the "models" cache).
+pub fn weights_dir() -> Result<PathBuf, AppError> {
+    Ok(deepface_home()?.join(WEIGHTS_DIR))
+}
+
+fn catalog_file(name: &str) -> Option<&'static str> {
+    CATALOG.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, file)| *file)
+}
+
+fn status(name: &str, file: &str) -> Result<ModelStatus, AppError> {
+    let path = weights_dir()?.join(file);
+    let size = std::fs::metadata(&path).ok().map(|m| m.len());
+    Ok(ModelStatus { name: name.to_string(), file: file.to_string(), downloaded: size.is_some(), size, path })
+}
+
+/// Every catalog model with its download state.
+pub fn list() -> Result<Vec<ModelStatus>, AppError> {
+    CATALOG.iter().map(|(name, file)| status(name, file)).collect()
+}
+
+// Catalog models a sidecar request will load
+fn required(req: &Value) -> Vec<String> {
+    let text = |key: &str| req[key].as_str().filter(|s| !s.is_empty()).map(String::from);
+    let detector = text("detector").unwrap_or_else(|| DEFAULT_DETECTOR.to_string());
+    let mut names = match req["cmd"].as_str() {
+        Some("analyze") => match text("actions") {
+            Some(actions) => actions.split(',').map(|a| a.trim().to_string()).collect(),
+            None => ALL_ACTIONS.iter().map(|a| a.to_string()).collect(),
+        },
+        Some("verify") | Some("represent") | Some("find") => vec![text("model").unwrap_or_else(|| DEFAULT_MODEL.to_string())],
+        _ => Vec::new(),
+    };
+    if matches!(req["cmd"].as_str(), Some("analyze" | "verify" | "represent" | "find" | "detect")) {
+        names.push(detector);
+    }
+    names.retain(|name| catalog_file(name).is_some());
+    names
+}
+
+/// Download the weights a sidecar request needs (no-op when autoDownload is off or they are in place).
+pub async fn ensure_for(req: &Value) -> Result<(), AppError> {
+    if !settings::get().models.auto_download {
+        return Ok(());
+    }
+    for name in required(req) {
+        download(&name).await?;
+    }
+    Ok(())
+}
+
+/// Download `names` (default: every catalog model) unless they are in place.
+pub async fn download_all(names: Option<Vec<String>>) -> Result<Vec<ModelStatus>, AppError> {
+    let names = names.unwrap_or_else(|| CATALOG.iter().map(|(name, _)| name.to_string()).collect());
+    let mut downloaded = Vec::new();
+    for name in names {
+        downloaded.push(download(&name).await?);
+    }
+    Ok(downloaded)
+}
+
+/// The weights of catalog model `name`, downloaded (and verified) when missing.
+pub async fn download(name: &str) -> Result<ModelStatus, AppError> {
+    let file = catalog_file(name).ok_or_else(|| AppError::InvalidInput(format!("Unknown model '{}'", name)))?;
+    download_file(name, file, &settings::get().models.base_url, &weights_dir()?).await?;
+    status(name, file)
+}
+
+/// `file` of model `name` from `base_url` into `dir` unless it is there, as the catalog weights are downloaded
+/// (resumed, checked against settings.models checksums, MODEL_DOWNLOAD_EVENT progress). Also used by whisper.rs.
+pub async fn download_file(name: &str, file: &str, base_url: &str, dir: &Path) -> Result<PathBuf, AppError> {
+    let path = dir.join(file);
+    if path.exists() {
+        cache::touch(&path);
+        return Ok(path);
+    }
+
+    let _one_at_a_time = DOWNLOADS.lock().await;
+    if path.exists() {
+        return Ok(path); // another caller just downloaded it
+    }
+    tokio::fs::create_dir_all(dir).await?;
+    let config: ModelsSettings = settings::get().models;
+    let url = format!("{}/{}", base_url.trim_end_matches('/'), file);
+    let part = dir.join(format!("{}.part", file));
+    let mut progress = ModelProgress { name: name.to_string(), file: file.to_string(), stage: "downloading", downloaded: 0, total: None, error: None };
+
+    info!("⬇️ Downloading model {} from {}", name, url);
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match fetch(&url, &part, &mut progress).await {
+            Ok(()) => break,
+            Err(e) if attempt < MAX_ATTEMPTS => warn!("⚠️ Download of {} interrupted ({}), resuming", file, e),
+            Err(e) => {
+                progress.stage = "failed";
+                progress.error = Some(e.to_string());
+                events::emit_global(MODEL_DOWNLOAD_EVENT, progress);
+                return Err(e);
+            }
+        }
+    }
+
+    if let Some(expected) = config.checksums.get(file) {
+        progress.stage = "verifying";
+        events::emit_global(MODEL_DOWNLOAD_EVENT, progress.clone());
+        let actual = sha256_file(&part).await?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            let _ = tokio::fs::remove_file(&part).await;
+            let e = AppError::Io(format!("Checksum mismatch for {} (expected {}, got {}): download discarded", file, expected, actual));
+            progress.stage = "failed";
+            progress.error = Some(e.to_string());
+            events::emit_global(MODEL_DOWNLOAD_EVENT, progress);
+            return Err(e);
+        }
+    }
+    tokio::fs::rename(&part, &path).await?;
+    cache::changed();
+
+    progress.stage = "done";
+    events::emit_global(MODEL_DOWNLOAD_EVENT, progress);
+    info!("✅ Model {} ready at {:?}", name, path);
+    Ok(path)
+}
+
+// Download `url` into `part`, resuming after the bytes already there
+async fn fetch(url: &str, part: &Path, progress: &mut ModelProgress) -> Result<(), AppError> {
+    let mut offset = tokio::fs::metadata(part).await.map(|m| m.len()).unwrap_or(0);
+    let mut request = CLIENT.get(url);
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={}-", offset));
+    }
+    let mut response = request.send().await.map_err(|e| AppError::Io(format!("Download of {} failed: {}", url, e)))?;
+    match response.status() {
+        StatusCode::PARTIAL_CONTENT => {}
+        StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => return Ok(()), // the part file is already complete
+        status if status.is_success() => offset = 0,                      // range ignored: start over
+        status => return Err(AppError::Io(format!("Download of {} failed: HTTP {}", url, status))),
+    }
+    progress.total = response.content_length().map(|len| len + offset);
+    progress.downloaded = offset;
+
+    let mut out = if offset > 0 {
+        tokio::fs::OpenOptions::new().append(true).open(part).await?
+    } else {
+        tokio::fs::File::create(part).await?
+    };
+    let mut last_event = Instant::now();
+    while let Some(chunk) = response.chunk().await.map_err(|e| AppError::Io(format!("Download of {} failed: {}", url, e)))? {
+        out.write_all(&chunk).await?;
+        progress.downloaded += chunk.len() as u64;
+        if last_event.elapsed() >= PROGRESS_INTERVAL {
+            events::emit_global(MODEL_DOWNLOAD_EVENT, progress.clone());
+            last_event = Instant::now();
+        }
+    }
+    out.flush().await?;
+    Ok(())
+}
+
+async fn sha256_file(path: &Path) -> Result<String, AppError> {
+    let path = path.to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || -> Result<String, AppError> {
+        let mut file = std::fs::File::open(&path)?;
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut file, &mut hasher)?;
+        Ok(hex::encode(hasher.finalize()))
+    })
+    .await
+    .map_err(|e| AppError::Io(format!("Checksum task failed: {}", e)))?
+}
+
+
+//_____________Commands ________________________
+
+/// Catalog models and whether their weights are downloaded.
+#[tauri::command]
+pub fn list_models() -> Result<Vec<ModelStatus>, AppError> {
+    list()
+}
+
+/// Example: `invoke("download_models", { names: ["VGG-Face", "emotion", "retinaface"] })` (all without names).
+#[tauri::command]
+pub async fn download_models(names: Option<Vec<String>>) -> Result<Vec<ModelStatus>, AppError> {
+    download_all(names).await
+}
@@ -0,0 +1,237 @@
+// src/moments.rs
+//
+// Moments: what was said while a face showed a strong emotion, e.g. "angry rant about budget, 00:12:31-00:12:50".
+// Built from what the other analyses stored for a clip, so run analyze_clip and transcribe_clip first:
+// - emotion runs: analyzed faces (database analyses) whose dominant emotion scores at least minScore
+//   (MIN_PEAK_SCORE), neutral left out; faces of one emotion less than MERGE_GAP_SECS apart make one run
+// - each run takes the transcript segments (whisper.rs) it overlaps, within PAD_SECS: the moment spans both,
+//   its text is what is said. A run without speech is still a moment ("surprised reaction")
+// - the title is the emotion's adjective and noun (EMOTION_WORDS), plus the most repeated word of the text
+// - `build_moments(clipId)` starts a "build_moments" job (jobs.rs) that replaces the clip's moments
+// - stored in the moments table, searched like markers (`search_moments`, `emotion:angry budget`) and exported
+//   with the marker formats (`export_moments`, csv / edl / xml): title as name, text as comment
+
+use serde_json::json;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tauri::AppHandle;
+use tracing::info;
+
+use crate::database::{self, AnalyzedFace, Job, Marker, MarkerSource, Moment, MomentSearch, NewMoment, TranscriptSegment};
+use crate::error::AppError;
+use crate::jobs;
+use crate::marker_export::{self, ExportFormat};
+use crate::payloads::BuildMomentsArgs;
+
+
+//____________Const___________
+pub const MIN_PEAK_SCORE: f64 = 60.0; // default minScore: deepface score (0-100) of the dominant emotion
+const MERGE_GAP_SECS: f64 = 5.0;      // faces of one emotion closer than this are one run
+const PAD_SECS: f64 = 1.0;            // speech this close to a run belongs to its moment
+const MIN_MOMENT_SECS: f64 = 1.0;     // a run of one face without speech still lasts this long
+const MIN_KEYWORD_LEN: usize = 4;
+
+// (emotion, adjective, what is said) -> "angry rant about budget"
+const EMOTION_WORDS: &[(&str, &str, &str)] = &[
+    ("angry", "angry", "rant"),
+    ("disgust", "disgusted", "complaint"),
+    ("fear", "fearful", "confession"),
+    ("happy", "happy", "story"),
+    ("sad", "sad", "story"),
+    ("surprise", "surprised", "remark"),
+];
+
+// Not topics (English; other languages just get a less precise title)
+const STOPWORDS: &[&str] = &[
+    "about", "after", "again", "also", "because", "been", "before", "being", "could", "didn't", "does", "doesn't", "don't",
+    "from", "have", "having", "here", "just", "know", "like", "mean", "more", "much", "only", "other", "really", "right",
+    "said", "some", "that", "that's", "them", "then", "there", "these", "they", "thing", "things", "think", "this", "those",
+    "very", "want", "well", "were", "what", "when", "where", "which", "while", "will", "with", "would", "yeah", "your",
+];
+
+
+//_____________Struct _________________________
+// Faces of one emotion close in time
+struct Run<'a> {
+    emotion: &'static str,
+    start: f64,
+    end: f64,
+    peak: &'a AnalyzedFace,
+    score: f64,
+}
+
+
+//_____________fn ____________________________
+
+// Dominant emotion of a face and its score, when it is a non-neutral one at least `min_score`
+fn strong_emotion(face: &AnalyzedFace, min_score: f64) -> Option<(&'static str, f64)> {
+    let dominant = face.result["dominant_emotion"].as_str()?;
+    let (emotion, _, _) = EMOTION_WORDS.iter().find(|(e, _, _)| *e == dominant)?;
+    let score = face.result["emotion"][dominant].as_f64()?;
+    (score >= min_score).then_some((*emotion, score))
+}
+
+// Runs of every emotion, in timeline order (`faces` are)
+fn runs(faces: &[AnalyzedFace], min_score: f64) -> Vec<Run<'_>> {
+    let mut open: HashMap<&str, Run> = HashMap::new();
+    let mut done = Vec::new();
+    for face in faces {
+        let Some((emotion, score)) = strong_emotion(face, min_score) else { continue };
+        match open.get_mut(emotion).filter(|run| face.timestamp - run.end <= MERGE_GAP_SECS) {
+            Some(run) => {
+                run.end = face.timestamp;
+                if score > run.score {
+                    run.peak = face;
+                    run.score = score;
+                }
+            }
+            None => {
+                let run = Run { emotion, start: face.timestamp, end: face.timestamp, peak: face, score };
+                done.extend(open.insert(emotion, run));
+            }
+        }
+    }
+    done.extend(open.into_values());
+    done.sort_by(|a, b| a.start.total_cmp(&b.start));
+    done
+}
+
+// The most repeated word of `text` that could be a topic; the first one on a tie
+fn keyword(text: &str) -> Option<String> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for word in text.split(|c: char| !(c.is_alphanumeric() || c == '\'')) {
+        let word = word.trim_matches('\'').to_lowercase();
+        if word.chars().count() < MIN_KEYWORD_LEN || word.chars().all(|c| c.is_numeric()) || STOPWORDS.contains(&word.as_str()) {
+            continue;
+        }
+        match counts.iter_mut().find(|(w, _)| *w == word) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((word, 1)),
+        }
+    }
+    let best = counts.iter().map(|(_, count)| *count).max()?;
+    counts.into_iter().find(|(_, count)| *count == best).map(|(word, _)| word)
+}
+
+/// "angry rant about budget", "angry rant" (nothing to name), "angry reaction" (nothing said).
+pub fn title(emotion: &str, text: Option<&str>) -> String {
+    let (adjective, noun) = EMOTION_WORDS.iter().find(|(e, _, _)| *e == emotion).map(|(_, a, n)| (*a, *n)).unwrap_or((emotion, "moment"));
+    match text {
+        None => format!("{} reaction", adjective),
+        Some(text) => match keyword(text) {
+            Some(topic) => format!("{} {} about {}", adjective, noun, topic),
+            None => format!("{} {}", adjective, noun),
+        },
+    }
+}
+
+// One run joined with the speech around it
+fn moment(run: &Run, transcript: &[TranscriptSegment]) -> NewMoment {
+    let spoken: Vec<&TranscriptSegment> =
+        transcript.iter().filter(|s| s.end >= run.start - PAD_SECS && s.start <= run.end + PAD_SECS).collect();
+    let start = spoken.iter().map(|s| s.start).fold(run.start, f64::min);
+    let end = spoken.iter().map(|s| s.end).fold(run.end, f64::max).max(start + MIN_MOMENT_SECS);
+    let text = (!spoken.is_empty()).then(|| spoken.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" "));
+    NewMoment {
+        start,
+        end,
+        emotion: run.emotion.to_string(),
+        score: run.score,
+        title: title(run.emotion, text.as_deref()),
+        text,
+        marker_id: Some(run.peak.marker_id),
+    }
+}
+
+/// Join the clip's emotion peaks with its transcript and store the moments, replacing the previous ones.
+pub fn build<P>(args: &BuildMomentsArgs, progress: P) -> Result<Vec<Moment>, AppError>
+where
+    P: Fn(f64, &str) -> Result<(), AppError>,
+{
+    let min_score = args.min_score.unwrap_or(MIN_PEAK_SCORE);
+    if !(0.0..=100.0).contains(&min_score) {
+        return Err(AppError::InvalidInput(format!("minScore must be 0 to 100 ({} given)", min_score)));
+    }
+    progress(0.0, "Reading analyses and transcript")?;
+    let faces = database::clip_analyses(args.clip_id)?;
+    let transcript = database::list_transcript(args.clip_id)?;
+
+    progress(0.5, "Building moments")?;
+    let moments: Vec<NewMoment> = runs(&faces, min_score).iter().map(|run| moment(run, &transcript)).collect();
+    let moments = database::replace_moments(args.clip_id, &moments)?;
+    info!(
+        "✨ Clip {}: {} moments from {} analyzed faces and {} transcript segments",
+        args.clip_id,
+        moments.len(),
+        faces.len(),
+        transcript.len()
+    );
+    Ok(moments)
+}
+
+/// A moment as a marker, for marker_export (title as name, text as comment, emotion as label).
+pub fn as_marker(moment: &Moment) -> Marker {
+    Marker {
+        id: moment.id,
+        clip_id: moment.clip_id,
+        timestamp: moment.start,
+        duration: moment.end - moment.start,
+        name: Some(moment.title.clone()),
+        comment: moment.text.clone(),
+        label: Some(moment.emotion.clone()),
+        color: None, // the emotion's
+        source: MarkerSource::Deepface,
+        metadata: Some(json!({ "score": moment.score, "markerId": moment.marker_id })),
+    }
+}
+
+/// Write the moments of `clip_id` to `path` in a marker format. Returns the number of moments exported.
+pub fn export(clip_id: i64, format: &str, path: &str) -> Result<usize, AppError> {
+    let format = ExportFormat::from_str(format)?;
+    let clip = database::get_clip(clip_id)?;
+    let markers: Vec<Marker> = database::list_moments(clip_id)?.iter().map(as_marker).collect();
+
+    std::fs::write(path, marker_export::render(&clip, &markers, format))
+        .map_err(|e| AppError::Io(format!("Failed to write {}: {}", path, e)))?;
+
+    info!("📤 Exported {} moments of clip {} to {} ({:?})", markers.len(), clip_id, path, format);
+    Ok(markers.len())
+}
+
+/// Start a "build_moments" job.
+pub fn start(app_handle: &AppHandle, clip_id: i64, min_score: Option<f64>) -> Result<Job, AppError> {
+    jobs::start(app_handle, "build_moments", json!({ "clipId": clip_id, "minScore": min_score }))
+}
+
+
+//_____________Commands ________________________
+
+/// Example: `invoke("build_moments", { clipId: 3 })` -> the queued Job (minScore omitted = MIN_PEAK_SCORE)
+#[tauri::command]
+pub fn build_moments(app_handle: AppHandle, clip_id: i64, min_score: Option<f64>) -> Result<Job, AppError> {
+    start(&app_handle, clip_id, min_score)
+}
+
+/// Example: `invoke("list_moments", { clipId: 3 })` ->
+/// `[{ id, clipId: 3, start: 751.2, end: 770.4, emotion: "angry", score: 91.3, title: "angry rant about budget", text: "…", markerId: 42 }]`
+#[tauri::command]
+pub async fn list_moments(clip_id: i64) -> Result<Vec<Moment>, AppError> {
+    database::blocking(move || database::list_moments(clip_id)).await
+}
+
+/// Example: `invoke("search_moments", { query: "emotion:angry budget", offset: 0, limit: 50 })`
+#[tauri::command]
+pub async fn search_moments(
+    query: String,
+    project_id: Option<i64>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<MomentSearch, AppError> {
+    database::blocking(move || database::search_moments(&query, project_id, offset, limit)).await
+}
+
+/// Example: `invoke("export_moments", { clipId: 3, format: "edl", path: "C:/…/moments.edl" })`
+#[tauri::command]
+pub async fn export_moments(clip_id: i64, format: String, path: String) -> Result<usize, AppError> {
+    database::blocking(move || export(clip_id, &format, &path)).await
+}
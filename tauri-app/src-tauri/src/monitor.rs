@@ -0,0 +1,109 @@
+// src/monitor.rs
+//
+// Detached monitor window for debugging the CEP integration: `open_monitor_window()` opens (or focuses)
+// a second webview (MONITOR_LABEL, src/monitor.html) next to the main one.
+// - while it is open, a MONITOR_EVENT snapshot is sent to that window only, every MONITOR_REFRESH_MS:
+//   WS server status and connections, deepface sidecar running / queue depth, queued and running jobs,
+//   and the log records written since the previous snapshot
+// - the window also listens to job-progress itself; closing it stops the snapshots
+
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+use tracing::{info, warn};
+
+use crate::crash;
+use crate::database::{self, Job, JobStatus};
+use crate::deepFaceProcess::{self, MANAGER};
+use crate::error::AppError;
+use crate::logging::{self, LogRecord, MAX_RECENT};
+use crate::websocket::{self, WsStatus};
+
+
+//____________Const___________
+pub const MONITOR_LABEL: &str = "monitor";
+pub const MONITOR_EVENT: &str = "monitor-snapshot"; // MonitorSnapshot, to the monitor window only
+const MONITOR_URL: &str = "monitor.html";
+const MONITOR_REFRESH_MS: u64 = 1000;
+const RECENT_JOBS: usize = 50; // jobs looked at for the queued / running ones
+
+
+//_____________Struct _________________________
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepFaceStatus {
+    pub running: bool,
+    pub queue_depth: usize, // requests without an answer yet
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorSnapshot {
+    pub ws: WsStatus,
+    pub deepface: DeepFaceStatus,
+    pub jobs: Vec<Job>,       // queued and running
+    pub logs: Vec<LogRecord>, // new since the previous snapshot
+}
+
+
+//_____________fn ____________________________
+async fn snapshot(logged_before: u64) -> Result<(MonitorSnapshot, u64), AppError> {
+    let jobs = database::blocking(|| database::list_jobs(RECENT_JOBS)).await?;
+    let logged = logging::logged_count();
+    let new_logs = (logged.saturating_sub(logged_before) as usize).min(MAX_RECENT);
+    let snapshot = MonitorSnapshot {
+        ws: websocket::status(),
+        deepface: DeepFaceStatus { running: MANAGER.is_running().await, queue_depth: deepFaceProcess::queue_depth() },
+        jobs: jobs.into_iter().filter(|j| matches!(j.status, JobStatus::Queued | JobStatus::Running)).collect(),
+        logs: logging::get_recent_logs(new_logs),
+    };
+    Ok((snapshot, logged))
+}
+
+// Feed the window until it is closed
+fn start_feed(app_handle: AppHandle) {
+    crash::spawn("monitor-feed", async move {
+        let mut logged = logging::logged_count();
+        while app_handle.get_webview_window(MONITOR_LABEL).is_some() {
+            match snapshot(logged).await {
+                Ok((snapshot, now)) => {
+                    logged = now;
+                    if let Err(e) = app_handle.emit_to(MONITOR_LABEL, MONITOR_EVENT, &snapshot) {
+                        warn!("⚠️ Monitor snapshot not sent: {}", e);
+                    }
+                }
+                Err(e) => warn!("⚠️ Monitor snapshot failed: {}", e),
+            }
+            tokio::time::sleep(Duration::from_millis(MONITOR_REFRESH_MS)).await;
+        }
+        info!("🖥️ Monitor window closed");
+    });
+}
+
+/// Open the monitor window, or bring it to the front when it is already open.
+pub fn open(app_handle: &AppHandle) -> Result<(), AppError> {
+    if let Some(window) = app_handle.get_webview_window(MONITOR_LABEL) {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+        return Ok(());
+    }
+
+    WebviewWindowBuilder::new(app_handle, MONITOR_LABEL, WebviewUrl::App(MONITOR_URL.into()))
+        .title("tauri-app monitor")
+        .inner_size(960.0, 640.0)
+        .build()
+        .map_err(|e| AppError::Io(format!("Failed to open the monitor window: {}", e)))?;
+    info!("🖥️ Monitor window opened");
+    start_feed(app_handle.clone());
+    Ok(())
+}
+
+
+//_____________Commands ________________________
+
+// async: creating a window from a sync command deadlocks on Windows
+#[tauri::command]
+pub async fn open_monitor_window(app_handle: AppHandle) -> Result<(), AppError> {
+    open(&app_handle)
+}
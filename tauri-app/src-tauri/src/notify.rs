@@ -0,0 +1,92 @@
+// src/notify.rs
+//
+// Native OS notifications (tauri-plugin-notification), shown even when the window is hidden.
+// - jobs.rs: a job completed ("Clip analysis complete (42 markers added)") or failed
+// - license.rs: the license stopped being valid, went offline, or reached one of the "expiring soon" steps
+//   (license::EXPIRY_NOTICE_DAYS) within settings.notifications expiryWarningDays
+// - settings.notifications enabled = false silences all of them
+// Texts are in settings.locale (i18n.rs).
+
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+use tracing::{debug, warn};
+
+use crate::i18n;
+use crate::license::LicenseState;
+use crate::settings;
+
+
+//____________Const___________
+pub const EXPIRY_WARNING_DAYS: u32 = 14;
+const APP_NAME: &str = "tauri-app";
+
+
+//_____________Struct _________________________
+#[derive(Default)]
+struct LicenseSeen {
+    state: Option<LicenseState>,
+}
+
+
+//_____________Globals _______________________
+static LICENSE_SEEN: Lazy<Mutex<LicenseSeen>> = Lazy::new(|| Mutex::new(LicenseSeen::default()));
+
+
+//_____________fn ____________________________
+
+/// Show a notification, unless they are turned off in the settings.
+pub fn send(app_handle: &AppHandle, title: &str, body: &str) {
+    if !settings::get().notifications.enabled {
+        return;
+    }
+    debug!("🔔 {}: {}", title, body);
+    if let Err(e) = app_handle.notification().builder().title(title).body(body).show() {
+        warn!("❌ Failed to show notification: {}", e);
+    }
+}
+
+pub fn job_completed(app_handle: &AppHandle, summary: &str) {
+    send(app_handle, APP_NAME, summary);
+}
+
+pub fn job_failed(app_handle: &AppHandle, what: &str, error: &str) {
+    send(app_handle, APP_NAME, &i18n::t("notify.job_failed", &serde_json::json!({ "what": what, "error": error })));
+}
+
+// i18n code of the notification for `state`
+fn license_message(state: LicenseState) -> Option<&'static str> {
+    match state {
+        LicenseState::Valid | LicenseState::Unlicensed | LicenseState::Trial => None,
+        LicenseState::OfflineGrace => Some("notify.license_offline_grace"),
+        LicenseState::OfflineExpired => Some("notify.license_offline_expired"),
+        LicenseState::Invalid => Some("notify.license_invalid"),
+    }
+}
+
+/// Called by license.rs after every check; only changes notify.
+pub fn license_checked(app_handle: &AppHandle, state: LicenseState) {
+    let mut seen = LICENSE_SEEN.lock().unwrap_or_else(|p| p.into_inner());
+
+    // The first check of a run only reports problems, not "still valid"
+    if seen.state != Some(state) {
+        if let Some(code) = license_message(state) {
+            send(app_handle, APP_NAME, &i18n::text(code));
+        }
+        seen.state = Some(state);
+    }
+}
+
+/// Called by license.rs when an "expiring soon" step is reached.
+pub fn license_expiring(app_handle: &AppHandle, days_left: i64) {
+    if days_left > settings::get().notifications.expiry_warning_days as i64 {
+        return;
+    }
+    let body = match days_left {
+        0 => i18n::text("notify.license_expires_today"),
+        1 => i18n::text("notify.license_expires_tomorrow"),
+        n => i18n::t("notify.license_expires_in", &serde_json::json!({ "days": n })),
+    };
+    send(app_handle, APP_NAME, &body);
+}
@@ -0,0 +1,143 @@
+// src/onboarding.rs
+//
+// Backend of the first-run wizard: which steps are done is kept in settings.onboarding, so the frontend
+// only drives the screens.
+// - steps, in order: "preflight" (preflight.rs checks, no error left), "license" (a key activated, or the
+//   trial running), "models" (optional: starts downloading every catalog model, MODEL_DOWNLOAD_EVENT progress)
+// - `complete_onboarding_step(step)` checks what the step needs before recording it; `skip: true` records an
+//   optional step as skipped
+// - `onboarding_state()` gives the steps, the next one and what the screens show (last preflight report,
+//   license state, models on disk)
+
+use serde::Serialize;
+use serde_json::json;
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+use crate::crash;
+use crate::error::AppError;
+use crate::license::{self, LicenseState};
+use crate::models::{self, ModelStatus};
+use crate::preflight::{self, CheckStatus, PreflightReport};
+use crate::settings::{self, OnboardingSettings};
+
+
+//____________Const___________
+pub const STEP_PREFLIGHT: &str = "preflight";
+pub const STEP_LICENSE: &str = "license";
+pub const STEP_MODELS: &str = "models";
+const STEPS: &[(&str, bool)] = &[(STEP_PREFLIGHT, false), (STEP_LICENSE, false), (STEP_MODELS, true)]; // (name, optional)
+
+
+//_____________Struct _________________________
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnboardingStep {
+    pub name: &'static str,
+    pub optional: bool,
+    pub done: bool,
+    pub skipped: bool,
+}
+
+/// Result of `onboarding_state` and `complete_onboarding_step`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnboardingState {
+    pub steps: Vec<OnboardingStep>,
+    pub next: Option<&'static str>, // first step neither done nor skipped, None once finished
+    pub finished: bool,
+    pub preflight: Option<PreflightReport>,
+    pub license: Option<LicenseState>,
+    pub models: Vec<ModelStatus>,
+}
+
+
+//_____________fn ____________________________
+
+pub fn state() -> OnboardingState {
+    let saved = settings::get().onboarding;
+    let steps: Vec<OnboardingStep> = STEPS
+        .iter()
+        .map(|&(name, optional)| OnboardingStep {
+            name,
+            optional,
+            done: saved.completed_steps.iter().any(|s| s == name),
+            skipped: saved.skipped_steps.iter().any(|s| s == name),
+        })
+        .collect();
+    let next = steps.iter().find(|s| !s.done && !s.skipped).map(|s| s.name);
+    OnboardingState {
+        finished: next.is_none(),
+        next,
+        steps,
+        preflight: preflight::last_preflight(),
+        license: license::current_state(),
+        models: models::list().unwrap_or_default(),
+    }
+}
+
+// What `step` needs before it can be recorded as done
+async fn check_step(app_handle: &AppHandle, step: &str) -> Result<(), AppError> {
+    match step {
+        STEP_PREFLIGHT => {
+            let report = preflight::run(app_handle).await;
+            let failed: Vec<&str> = report.checks.iter().filter(|c| c.status == CheckStatus::Error).map(|c| c.name).collect();
+            if !failed.is_empty() {
+                return Err(AppError::InvalidInput(format!("Preflight checks failed: {}", failed.join(", "))));
+            }
+        }
+        STEP_LICENSE => {
+            if !matches!(license::current_state(), Some(LicenseState::Valid | LicenseState::OfflineGrace | LicenseState::Trial)) {
+                return Err(AppError::InvalidInput("Activate a license key first (or start the trial)".into()));
+            }
+        }
+        STEP_MODELS => {
+            crash::spawn("onboarding-models", async {
+                match models::download_all(None).await {
+                    Ok(_) => info!("✅ Onboarding model preload done"),
+                    Err(e) => warn!("❌ Onboarding model preload failed: {}", e),
+                }
+            });
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Record `step` as done (after its check) or, for optional steps, as skipped.
+pub async fn complete_step(app_handle: AppHandle, step: &str, skip: bool) -> Result<OnboardingState, AppError> {
+    let Some((name, optional)) = STEPS.iter().find(|(name, _)| *name == step) else {
+        let names: Vec<&str> = STEPS.iter().map(|(name, _)| *name).collect();
+        return Err(AppError::InvalidInput(format!("Unknown onboarding step '{}' (expected {})", step, names.join(", "))));
+    };
+    if skip && !optional {
+        return Err(AppError::InvalidInput(format!("Onboarding step '{}' can't be skipped", name)));
+    }
+    if !skip {
+        check_step(&app_handle, name).await?;
+    }
+
+    let OnboardingSettings { mut completed_steps, mut skipped_steps } = settings::get().onboarding;
+    completed_steps.retain(|s| s != name);
+    skipped_steps.retain(|s| s != name);
+    let recorded = if skip { &mut skipped_steps } else { &mut completed_steps };
+    recorded.push(name.to_string());
+    settings::update_settings(app_handle, json!({ "onboarding": { "completedSteps": completed_steps, "skippedSteps": skipped_steps } }))?;
+    info!("🧭 Onboarding step {} {}", name, if skip { "skipped" } else { "done" });
+    Ok(state())
+}
+
+
+//_____________Commands ________________________
+
+/// Example: `invoke("onboarding_state")` -> `{ steps: [{ name, optional, done, skipped }], next: "license", finished, preflight, license, models }`.
+#[tauri::command]
+pub fn onboarding_state() -> OnboardingState {
+    state()
+}
+
+/// Example: `invoke("complete_onboarding_step", { step: "models", skip: true })`.
+#[tauri::command]
+pub async fn complete_onboarding_step(app_handle: AppHandle, step: String, skip: Option<bool>) -> Result<OnboardingState, AppError> {
+    complete_step(app_handle, &step, skip.unwrap_or(false)).await
+}
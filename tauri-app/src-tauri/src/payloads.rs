@@ -0,0 +1,799 @@
+// src/payloads.rs
+//
+// Typed payloads of the registry commands, checked at the protocol boundary.
+// - Each struct lists its fields in `Payload::FIELDS` (also shown by `list_commands`)
+// - `parse` reports every missing / mistyped / unknown field at once before serde runs,
+//   so a CEP developer sees e.g. `clipId: missing (integer); chunkSize: expected integer, got string`
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::analysis::Sampling;
+use crate::database::{MarkerFilter, MarkerSort, MarkerUpdate, NewMarker};
+use crate::deepFaceProcess::Roi;
+use crate::error::{AppError, FieldError};
+
+
+//_____________Struct _________________________
+
+/// JSON type expected for a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Object,
+    Array,
+}
+
+/// One payload field (JSON name, camelCase).
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Field {
+    pub name: &'static str,
+    pub kind: Kind,
+    pub required: bool,
+}
+
+const fn required(name: &'static str, kind: Kind) -> Field {
+    Field { name, kind, required: true }
+}
+
+const fn optional(name: &'static str, kind: Kind) -> Field {
+    Field { name, kind, required: false }
+}
+
+/// A command payload: serde struct + the field list used for validation.
+pub trait Payload: DeserializeOwned + Send + 'static {
+    const FIELDS: &'static [Field];
+}
+
+/// For commands that take nothing.
+#[derive(Debug, Default, Deserialize)]
+pub struct NoArgs {}
+
+impl Payload for NoArgs {
+    const FIELDS: &'static [Field] = &[];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnalyzeFrameArgs {
+    pub actions: Option<String>, // "emotion,age"
+    pub detector: Option<String>,
+    pub model: Option<String>,
+    pub roi: Option<Roi>, // { x, y, w, h }: crop before analysis
+}
+
+impl Payload for AnalyzeFrameArgs {
+    const FIELDS: &'static [Field] = &[
+        optional("actions", Kind::String),
+        optional("detector", Kind::String),
+        optional("model", Kind::String),
+        optional("roi", Kind::Object),
+    ];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DetectFrameArgs {
+    pub detector: Option<String>,
+    pub roi: Option<Roi>,
+}
+
+impl Payload for DetectFrameArgs {
+    const FIELDS: &'static [Field] = &[optional("detector", Kind::String), optional("roi", Kind::Object)];
+}
+
+/// deepface_* WS commands: the image is `image` (base64 / data URL) or the bytes of a binary message.
+#[derive(Debug, Deserialize)]
+pub struct DeepFaceAnalyzeArgs {
+    pub image: Option<String>,
+    pub actions: Option<String>,
+    pub detector: Option<String>,
+    pub model: Option<String>,
+    pub roi: Option<Roi>,
+}
+
+impl Payload for DeepFaceAnalyzeArgs {
+    const FIELDS: &'static [Field] = &[
+        optional("image", Kind::String),
+        optional("actions", Kind::String),
+        optional("detector", Kind::String),
+        optional("model", Kind::String),
+        optional("roi", Kind::Object),
+    ];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeepFaceDetectArgs {
+    pub image: Option<String>,
+    pub detector: Option<String>,
+    pub roi: Option<Roi>,
+}
+
+impl Payload for DeepFaceDetectArgs {
+    const FIELDS: &'static [Field] = &[
+        optional("image", Kind::String),
+        optional("detector", Kind::String),
+        optional("roi", Kind::Object),
+    ];
+}
+
+/// Both images as base64, or both in one binary message: img1 is its first `split` bytes, img2 the rest.
+#[derive(Debug, Deserialize)]
+pub struct DeepFaceVerifyArgs {
+    pub img1: Option<String>,
+    pub img2: Option<String>,
+    pub split: Option<usize>,
+    pub detector: Option<String>,
+    pub model: Option<String>,
+}
+
+impl Payload for DeepFaceVerifyArgs {
+    const FIELDS: &'static [Field] = &[
+        optional("img1", Kind::String),
+        optional("img2", Kind::String),
+        optional("split", Kind::Integer),
+        optional("detector", Kind::String),
+        optional("model", Kind::String),
+    ];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddReferenceFaceArgs {
+    pub name: String,
+    pub model: Option<String>,
+}
+
+impl Payload for AddReferenceFaceArgs {
+    const FIELDS: &'static [Field] = &[required("name", Kind::String), optional("model", Kind::String)];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IdentifyFaceArgs {
+    pub model: Option<String>,
+}
+
+impl Payload for IdentifyFaceArgs {
+    const FIELDS: &'static [Field] = &[optional("model", Kind::String)];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceIdArgs {
+    pub reference_id: i64,
+}
+
+impl Payload for ReferenceIdArgs {
+    const FIELDS: &'static [Field] = &[required("referenceId", Kind::Integer)];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubscribeArgs {
+    pub events: Vec<String>,
+}
+
+impl Payload for SubscribeArgs {
+    const FIELDS: &'static [Field] = &[required("events", Kind::Array)];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnsubscribeArgs {
+    pub events: Option<Vec<String>>, // None = all
+}
+
+impl Payload for UnsubscribeArgs {
+    const FIELDS: &'static [Field] = &[optional("events", Kind::Array)];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GreetArgs {
+    pub name: String,
+}
+
+impl Payload for GreetArgs {
+    const FIELDS: &'static [Field] = &[required("name", Kind::String)];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddClipArgs {
+    pub path: String,
+    pub fps: Option<f64>,
+    pub project_id: Option<i64>,
+}
+
+impl Payload for AddClipArgs {
+    const FIELDS: &'static [Field] =
+        &[required("path", Kind::String), optional("fps", Kind::Number), optional("projectId", Kind::Integer)];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateProjectArgs {
+    pub name: String,
+}
+
+impl Payload for CreateProjectArgs {
+    const FIELDS: &'static [Field] = &[required("name", Kind::String)];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectIdArgs {
+    pub project_id: i64,
+}
+
+impl Payload for ProjectIdArgs {
+    const FIELDS: &'static [Field] = &[required("projectId", Kind::Integer)];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupDatabaseArgs {
+    pub dest_path: String,
+}
+
+impl Payload for BackupDatabaseArgs {
+    const FIELDS: &'static [Field] = &[required("destPath", Kind::String)];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreDatabaseArgs {
+    pub src_path: String,
+}
+
+impl Payload for RestoreDatabaseArgs {
+    const FIELDS: &'static [Field] = &[required("srcPath", Kind::String)];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddMarkerArgs {
+    pub marker: NewMarker,
+}
+
+impl Payload for AddMarkerArgs {
+    const FIELDS: &'static [Field] = &[required("marker", Kind::Object)];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListMarkersArgs {
+    pub clip_id: i64,
+    pub filter: Option<MarkerFilter>,
+    pub sort: Option<MarkerSort>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+    pub chunk_size: Option<usize>,
+}
+
+impl Payload for ListMarkersArgs {
+    const FIELDS: &'static [Field] = &[
+        required("clipId", Kind::Integer),
+        optional("filter", Kind::Object),
+        optional("sort", Kind::String),
+        optional("offset", Kind::Integer),
+        optional("limit", Kind::Integer),
+        optional("chunkSize", Kind::Integer),
+    ];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkerIdArgs {
+    pub marker_id: i64,
+}
+
+impl Payload for MarkerIdArgs {
+    const FIELDS: &'static [Field] = &[required("markerId", Kind::Integer)];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMarkersArgs {
+    pub query: String,
+    pub project_id: Option<i64>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+impl Payload for SearchMarkersArgs {
+    const FIELDS: &'static [Field] = &[
+        required("query", Kind::String),
+        optional("projectId", Kind::Integer),
+        optional("offset", Kind::Integer),
+        optional("limit", Kind::Integer),
+    ];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateMarkerArgs {
+    pub marker_id: i64,
+    pub changes: MarkerUpdate,
+}
+
+impl Payload for UpdateMarkerArgs {
+    const FIELDS: &'static [Field] = &[required("markerId", Kind::Integer), required("changes", Kind::Object)];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportMarkersArgs {
+    pub clip_id: i64,
+    pub format: String,
+    pub path: String,
+}
+
+impl Payload for ExportMarkersArgs {
+    const FIELDS: &'static [Field] = &[
+        required("clipId", Kind::Integer),
+        required("format", Kind::String),
+        required("path", Kind::String),
+    ];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportAnalysisArgs {
+    pub project_id: i64,
+    pub format: String, // jsonl | csv | parquet
+    pub path: String,
+}
+
+impl Payload for ExportAnalysisArgs {
+    const FIELDS: &'static [Field] = &[
+        required("projectId", Kind::Integer),
+        required("format", Kind::String),
+        required("path", Kind::String),
+    ];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportMarkersArgs {
+    pub clip_id: i64,
+    pub path: String,
+    pub format: String,
+}
+
+impl Payload for ImportMarkersArgs {
+    const FIELDS: &'static [Field] = &[
+        required("clipId", Kind::Integer),
+        required("path", Kind::String),
+        required("format", Kind::String),
+    ];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractFramesArgs {
+    pub path: String,
+    pub timestamps: Option<Vec<f64>>,
+    pub every_secs: Option<f64>,
+    pub max_width: Option<u32>,
+}
+
+impl Payload for ExtractFramesArgs {
+    const FIELDS: &'static [Field] = &[
+        required("path", Kind::String),
+        optional("timestamps", Kind::Array),
+        optional("everySecs", Kind::Number),
+        optional("maxWidth", Kind::Integer),
+    ];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProbeMediaArgs {
+    pub path: String,
+}
+
+impl Payload for ProbeMediaArgs {
+    const FIELDS: &'static [Field] = &[required("path", Kind::String)];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DetectScenesArgs {
+    pub path: String,
+    pub threshold: Option<f64>, // default media::SCENE_THRESHOLD
+}
+
+impl Payload for DetectScenesArgs {
+    const FIELDS: &'static [Field] = &[required("path", Kind::String), optional("threshold", Kind::Number)];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClipThumbnailArgs {
+    pub path: String,
+    pub timestamp: f64,
+    pub size: Option<u32>,
+    pub base64: Option<bool>,
+}
+
+impl Payload for ClipThumbnailArgs {
+    const FIELDS: &'static [Field] = &[
+        required("path", Kind::String),
+        required("timestamp", Kind::Number),
+        optional("size", Kind::Integer),
+        optional("base64", Kind::Boolean),
+    ];
+}
+
+/// Params of an "export_markers" job (jobs.rs).
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportMarkersJobArgs {
+    pub clip_ids: Vec<i64>,
+    pub format: String,
+    pub dir: String,
+}
+
+impl Payload for ExportMarkersJobArgs {
+    const FIELDS: &'static [Field] = &[
+        required("clipIds", Kind::Array),
+        required("format", Kind::String),
+        required("dir", Kind::String),
+    ];
+}
+
+/// Params of an "extract_frames" job (jobs.rs).
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FramesJobArgs {
+    pub path: String,
+    pub timestamps: Vec<f64>,
+    pub max_width: Option<u32>,
+}
+
+impl Payload for FramesJobArgs {
+    const FIELDS: &'static [Field] = &[
+        required("path", Kind::String),
+        required("timestamps", Kind::Array),
+        optional("maxWidth", Kind::Integer),
+    ];
+}
+
+/// Params of an "analyze_clip" job (jobs.rs); the clip is registered in the project when it isn't yet.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyzeClipArgs {
+    pub path: String,
+    pub sampling: Option<Sampling>,   // default scenes, or interval when everySecs is given
+    pub every_secs: Option<f64>,      // interval: default analysis::ANALYZE_EVERY_SECS; scenes: longest gap in a shot (SHOT_GAP_SECS)
+    pub scene_threshold: Option<f64>, // default media::SCENE_THRESHOLD
+    pub project_id: Option<i64>,      // default the current project
+    pub fps: Option<f64>,
+    pub max_width: Option<u32>,
+    pub detector: Option<String>,
+    pub model: Option<String>,
+}
+
+impl Payload for AnalyzeClipArgs {
+    const FIELDS: &'static [Field] = &[
+        required("path", Kind::String),
+        optional("sampling", Kind::String),
+        optional("everySecs", Kind::Number),
+        optional("sceneThreshold", Kind::Number),
+        optional("projectId", Kind::Integer),
+        optional("fps", Kind::Number),
+        optional("maxWidth", Kind::Integer),
+        optional("detector", Kind::String),
+        optional("model", Kind::String),
+    ];
+}
+
+/// Params of an "analyze_audio" job (jobs.rs, audio.rs); the clip is registered in the project when it isn't yet.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyzeAudioArgs {
+    pub path: String,
+    pub project_id: Option<i64>,     // default the current project
+    pub fps: Option<f64>,
+    pub place_markers: Option<bool>, // default true: one "audio" marker per loud cue
+}
+
+impl Payload for AnalyzeAudioArgs {
+    const FIELDS: &'static [Field] = &[
+        required("path", Kind::String),
+        optional("projectId", Kind::Integer),
+        optional("fps", Kind::Number),
+        optional("placeMarkers", Kind::Boolean),
+    ];
+}
+
+/// Params of a "transcribe_clip" job (jobs.rs, whisper.rs); the clip is registered in the project when it isn't yet.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscribeClipArgs {
+    pub path: String,
+    pub language: Option<String>, // default settings.whisper language ("auto" = detected)
+    pub project_id: Option<i64>,  // default the current project
+    pub fps: Option<f64>,
+}
+
+impl Payload for TranscribeClipArgs {
+    const FIELDS: &'static [Field] = &[
+        required("path", Kind::String),
+        optional("language", Kind::String),
+        optional("projectId", Kind::Integer),
+        optional("fps", Kind::Number),
+    ];
+}
+
+/// Params of a "build_moments" job (jobs.rs, moments.rs).
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildMomentsArgs {
+    pub clip_id: i64,
+    pub min_score: Option<f64>, // default moments::MIN_PEAK_SCORE
+}
+
+impl Payload for BuildMomentsArgs {
+    const FIELDS: &'static [Field] = &[required("clipId", Kind::Integer), optional("minScore", Kind::Number)];
+}
+
+/// Params of an "ingest_clips" job (jobs.rs, ingest.rs).
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestClipsArgs {
+    pub paths: Vec<String>,
+    pub project_id: Option<i64>, // default the current project
+    #[serde(default)]
+    pub analyze: bool,           // queue an "analyze_clip" job per imported clip
+}
+
+impl Payload for IngestClipsArgs {
+    const FIELDS: &'static [Field] = &[
+        required("paths", Kind::Array),
+        optional("projectId", Kind::Integer),
+        optional("analyze", Kind::Boolean),
+    ];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrackFacesArgs {
+    pub frames: Vec<String>,            // image paths, in timeline order
+    pub timestamps: Option<Vec<f64>>,   // default the frame index
+    pub detector: Option<String>,
+    pub model: Option<String>,
+}
+
+impl Payload for TrackFacesArgs {
+    const FIELDS: &'static [Field] = &[
+        required("frames", Kind::Array),
+        optional("timestamps", Kind::Array),
+        optional("detector", Kind::String),
+        optional("model", Kind::String),
+    ];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepFaceStartArgs {
+    pub port: Option<u16>, // default settings.deepface port
+}
+
+impl Payload for DeepFaceStartArgs {
+    const FIELDS: &'static [Field] = &[optional("port", Kind::Integer)];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadModelsArgs {
+    pub names: Option<Vec<String>>, // models::CATALOG names, default all of them
+}
+
+impl Payload for DownloadModelsArgs {
+    const FIELDS: &'static [Field] = &[optional("names", Kind::Array)];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClearCacheArgs {
+    pub category: String, // cache::CacheCategory name or "all"
+}
+
+impl Payload for ClearCacheArgs {
+    const FIELDS: &'static [Field] = &[required("category", Kind::String)];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipEmotionSummaryArgs {
+    pub clip_id: i64,
+    pub window_secs: Option<f64>, // default emotion_stats::ARC_WINDOW_SECS
+}
+
+impl Payload for ClipEmotionSummaryArgs {
+    const FIELDS: &'static [Field] = &[required("clipId", Kind::Integer), optional("windowSecs", Kind::Number)];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipIdArgs {
+    pub clip_id: i64,
+}
+
+impl Payload for ClipIdArgs {
+    const FIELDS: &'static [Field] = &[required("clipId", Kind::Integer)];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StartCameraArgs {
+    pub device: Option<String>,
+    pub fps: Option<f64>,
+}
+
+impl Payload for StartCameraArgs {
+    const FIELDS: &'static [Field] = &[optional("device", Kind::String), optional("fps", Kind::Number)];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartJobArgs {
+    pub kind: String,
+    pub params: Option<Value>,
+}
+
+impl Payload for StartJobArgs {
+    const FIELDS: &'static [Field] = &[required("kind", Kind::String), optional("params", Kind::Object)];
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobIdArgs {
+    pub job_id: i64,
+}
+
+impl Payload for JobIdArgs {
+    const FIELDS: &'static [Field] = &[required("jobId", Kind::Integer)];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListJobsArgs {
+    pub limit: Option<usize>,
+}
+
+impl Payload for ListJobsArgs {
+    const FIELDS: &'static [Field] = &[optional("limit", Kind::Integer)];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CrashReportArgs {
+    pub id: String,
+}
+
+impl Payload for CrashReportArgs {
+    const FIELDS: &'static [Field] = &[required("id", Kind::String)];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunMacroArgs {
+    pub name: Option<String>,
+    pub steps: Option<Value>,
+    pub vars: Option<Value>,
+}
+
+impl Payload for RunMacroArgs {
+    const FIELDS: &'static [Field] = &[optional("name", Kind::String), optional("steps", Kind::Array), optional("vars", Kind::Object)];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SaveMacroArgs {
+    pub name: String,
+    pub description: Option<String>,
+    pub steps: Value,
+}
+
+impl Payload for SaveMacroArgs {
+    const FIELDS: &'static [Field] = &[required("name", Kind::String), optional("description", Kind::String), required("steps", Kind::Array)];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MacroNameArgs {
+    pub name: String,
+}
+
+impl Payload for MacroNameArgs {
+    const FIELDS: &'static [Field] = &[required("name", Kind::String)];
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecentLogsArgs {
+    pub n: usize,
+}
+
+impl Payload for RecentLogsArgs {
+    const FIELDS: &'static [Field] = &[required("n", Kind::Integer)];
+}
+
+
+//_____________fn ____________________________
+
+/// Validate `payload` against `T::FIELDS`, then deserialize it. `null` counts as `{}`.
+pub fn parse<T: Payload>(command: &str, payload: Value) -> Result<T, AppError> {
+    let payload = match payload {
+        Value::Null => Value::Object(Map::new()),
+        other => other,
+    };
+    let problems = check(&payload, T::FIELDS);
+    if !problems.is_empty() {
+        return Err(AppError::InvalidPayload(command.to_string(), problems));
+    }
+    // Shape is right; what's left are value-level errors (negative usize, bad nested object, …)
+    serde_json::from_value(payload).map_err(|e| {
+        AppError::InvalidPayload(command.to_string(), vec![FieldError { field: String::new(), problem: e.to_string() }])
+    })
+}
+
+fn check(payload: &Value, fields: &[Field]) -> Vec<FieldError> {
+    let Value::Object(object) = payload else {
+        return vec![FieldError { field: String::new(), problem: format!("payload must be an object, got {}", kind_of(payload)) }];
+    };
+
+    let mut problems = Vec::new();
+    for field in fields {
+        match object.get(field.name) {
+            None | Some(Value::Null) if field.required => problems.push(FieldError {
+                field: field.name.to_string(),
+                problem: format!("missing ({})", kind_name(field.kind)),
+            }),
+            None | Some(Value::Null) => {}
+            Some(value) if !matches_kind(value, field.kind) => problems.push(FieldError {
+                field: field.name.to_string(),
+                problem: format!("expected {}, got {}", kind_name(field.kind), kind_of(value)),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    for key in object.keys() {
+        if !fields.iter().any(|f| f.name == key) {
+            let expected: Vec<&str> = fields.iter().map(|f| f.name).collect();
+            problems.push(FieldError {
+                field: key.clone(),
+                problem: if expected.is_empty() {
+                    "unknown field (this command takes no arguments)".to_string()
+                } else {
+                    format!("unknown field (expected {})", expected.join(", "))
+                },
+            });
+        }
+    }
+    problems
+}
+
+fn matches_kind(value: &Value, kind: Kind) -> bool {
+    match kind {
+        Kind::String => value.is_string(),
+        Kind::Integer => value.is_i64() || value.is_u64(),
+        Kind::Number => value.is_number(),
+        Kind::Boolean => value.is_boolean(),
+        Kind::Object => value.is_object(),
+        Kind::Array => value.is_array(),
+    }
+}
+
+fn kind_name(kind: Kind) -> &'static str {
+    match kind {
+        Kind::String => "string",
+        Kind::Integer => "integer",
+        Kind::Number => "number",
+        Kind::Boolean => "boolean",
+        Kind::Object => "object",
+        Kind::Array => "array",
+    }
+}
+
+fn kind_of(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
@@ -0,0 +1,236 @@
+// src/plugins.rs
+//
+// Third-party commands without forking the app: dynamic libraries in the plugins directory (settings.plugins dir,
+// default <app data>/plugins) are loaded at startup and their commands join the registry, so the CEP panel,
+// the HTTP API and the webview reach them like the built-in ones (same middleware: auth, rate limit, metrics).
+// - stable C ABI (PLUGIN_ABI), every value crossing it is a NUL-terminated UTF-8 JSON string:
+//     uint32_t    faceapp_plugin_abi(void);       // must return PLUGIN_ABI
+//     const char* faceapp_plugin_manifest(void);  // { "name", "version", "commands": [{ "name", "description" }] }, static
+//     char*       faceapp_plugin_call(const char* command, const char* payload);  // { "ok": <value> } | { "error": "<message>" }
+//     void        faceapp_plugin_free(char* reply);  // frees what faceapp_plugin_call returned
+// - a plugin command is registered as `<plugin>.<command>` (e.g. `acme.export_xml`): never replaces a built-in,
+//   and only control clients run it (ws_auth.rs never counts a dotted name as read-only)
+// - calls run on the blocking pool, one at a time per plugin (plugins don't have to be thread-safe)
+// - a library that fails to load (wrong ABI, bad manifest, name taken) is skipped and reported by `list_plugins`;
+//   settings.plugins disabled lists plugins not to load. Changes apply at the next launch (libraries stay loaded)
+// Plugins run in-process with the app's rights: only install libraries you trust. WASM modules aren't supported.
+
+use libloading::{Library, Symbol};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::ffi::{c_char, CStr, CString};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+use crate::error::AppError;
+use crate::registry::{self, Handler};
+use crate::settings;
+
+
+//____________Const___________
+pub const PLUGIN_ABI: u32 = 1;
+pub const PLUGINS: bool = true; // default of settings.plugins enabled
+const PLUGIN_DIR: &str = "plugins"; // under the app data dir
+#[cfg(target_os = "windows")]
+const LIBRARY_EXTENSION: &str = "dll";
+#[cfg(target_os = "macos")]
+const LIBRARY_EXTENSION: &str = "dylib";
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const LIBRARY_EXTENSION: &str = "so";
+
+
+//_____________Struct _________________________
+type AbiFn = unsafe extern "C" fn() -> u32;
+type ManifestFn = unsafe extern "C" fn() -> *const c_char;
+type CallFn = unsafe extern "C" fn(*const c_char, *const c_char) -> *mut c_char;
+type FreeFn = unsafe extern "C" fn(*mut c_char);
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    name: String,
+    version: String,
+    commands: Vec<ManifestCommand>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestCommand {
+    name: String,
+    #[serde(default)]
+    description: String,
+}
+
+// What a command sends back over the ABI
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Reply {
+    Ok(Value),
+    Error(String),
+}
+
+/// A library of the plugins directory, as returned by `list_plugins`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginInfo {
+    pub file: String,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub commands: Vec<String>, // registered names, `<plugin>.<command>`
+    pub error: Option<String>, // why it wasn't loaded
+}
+
+// A loaded library and its call / free entry points, resolved once; kept for the life of the app (handlers hold it)
+struct Plugin {
+    call: CallFn,
+    free: FreeFn,
+    call_lock: Mutex<()>,
+    _library: Library, // the entry points are valid while it stays loaded
+}
+
+
+//_____________Globals _______________________
+static PLUGINS_FOUND: Lazy<Mutex<Vec<PluginInfo>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+
+//_____________fn ____________________________
+
+/// Load the libraries of the plugins directory and register their commands. Call once from setup.
+pub fn load_all(app_handle: &AppHandle) {
+    let config = settings::get().plugins;
+    if !config.enabled {
+        return;
+    }
+    let dir = match config.dir.or_else(|| app_handle.path().app_data_dir().ok().map(|dir| dir.join(PLUGIN_DIR))) {
+        Some(dir) => dir,
+        None => return,
+    };
+    let Ok(entries) = std::fs::read_dir(&dir) else { return }; // no plugins directory: nothing to load
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == LIBRARY_EXTENSION))
+        .collect();
+    files.sort();
+
+    let mut found = Vec::new();
+    for path in files {
+        let file = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+        let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        if config.disabled.iter().any(|d| *d == stem || *d == file) {
+            continue;
+        }
+        let info = match load(&path) {
+            Ok((manifest, commands)) => {
+                info!("🧩 Plugin {} {} loaded from {}: {}", manifest.name, manifest.version, file, commands.join(", "));
+                PluginInfo { file, name: Some(manifest.name), version: Some(manifest.version), commands, error: None }
+            }
+            Err(e) => {
+                warn!("❌ Plugin {} not loaded: {}", file, e);
+                PluginInfo { file, name: None, version: None, commands: Vec::new(), error: Some(e.to_string()) }
+            }
+        };
+        found.push(info);
+    }
+    *PLUGINS_FOUND.lock().unwrap_or_else(|p| p.into_inner()) = found;
+}
+
+// Open one library, check its ABI and manifest, register its commands
+fn load(path: &Path) -> Result<(Manifest, Vec<String>), AppError> {
+    let failed = |e: libloading::Error| AppError::Plugin(e.to_string());
+    // SAFETY: loading runs the library's initializers; plugins are trusted code the user installed
+    let library = unsafe { Library::new(path) }.map_err(failed)?;
+
+    // SAFETY: the symbol types are the ones PLUGIN_ABI documents
+    let abi = unsafe { library.get::<AbiFn>(b"faceapp_plugin_abi\0").map_err(failed)?() };
+    if abi != PLUGIN_ABI {
+        return Err(AppError::Plugin(format!("Plugin ABI {} (this app supports {})", abi, PLUGIN_ABI)));
+    }
+    let manifest = unsafe {
+        let manifest: Symbol<ManifestFn> = library.get(b"faceapp_plugin_manifest\0").map_err(failed)?;
+        let text = manifest();
+        if text.is_null() {
+            return Err(AppError::Plugin("Empty plugin manifest".into()));
+        }
+        CStr::from_ptr(text).to_string_lossy().to_string()
+    };
+    let manifest: Manifest = serde_json::from_str(&manifest).map_err(|e| AppError::Plugin(format!("Invalid plugin manifest: {}", e)))?;
+    if manifest.name.is_empty() || manifest.name.contains('.') {
+        return Err(AppError::Plugin("The plugin name must be non-empty, without dots".into()));
+    }
+    // Resolved once: the pointers stay valid as long as the Plugin holding them keeps the library loaded
+    let call: CallFn = unsafe { *library.get::<CallFn>(b"faceapp_plugin_call\0").map_err(failed)? };
+    let free: FreeFn = unsafe { *library.get::<FreeFn>(b"faceapp_plugin_free\0").map_err(failed)? };
+
+    // All of its commands or none
+    let names: Vec<String> = manifest.commands.iter().map(|c| format!("{}.{}", manifest.name, c.name)).collect();
+    if let Some((_, duplicate)) = names.iter().enumerate().find(|(i, name)| names[..*i].contains(name)) {
+        return Err(AppError::Plugin(format!("Command {} is listed twice in the manifest", duplicate)));
+    }
+    let known = registry::list();
+    if let Some(taken) = names.iter().find(|name| known.iter().any(|c| c.name == **name)) {
+        return Err(AppError::Plugin(format!("Command {} is already registered", taken)));
+    }
+
+    let plugin = Arc::new(Plugin { call, free, call_lock: Mutex::new(()), _library: library });
+    for (i, (name, command)) in names.iter().zip(&manifest.commands).enumerate() {
+        let description = format!("{} (plugin {})", command.description, manifest.name);
+        if let Err(e) = registry::register(name, &description, handler(plugin.clone(), command.name.clone())) {
+            names[..i].iter().for_each(|registered| registry::unregister(registered)); // taken meanwhile: undo the others
+            return Err(e);
+        }
+    }
+    Ok((manifest, names))
+}
+
+// Registry handler of one plugin command
+fn handler(plugin: Arc<Plugin>, command: String) -> Handler {
+    Arc::new(move |_, payload| {
+        let (plugin, command) = (plugin.clone(), command.clone());
+        Box::pin(async move {
+            tauri::async_runtime::spawn_blocking(move || plugin.call(&command, &payload))
+                .await
+                .map_err(|e| AppError::Plugin(format!("Plugin task failed: {}", e)))?
+        })
+    })
+}
+
+impl Plugin {
+    fn call(&self, command: &str, payload: &Value) -> Result<Value, AppError> {
+        let command = CString::new(command).map_err(|e| AppError::InvalidInput(e.to_string()))?;
+        let payload = CString::new(payload.to_string()).map_err(|e| AppError::InvalidInput(e.to_string()))?;
+        let _one_at_a_time = self.call_lock.lock().unwrap_or_else(|p| p.into_inner());
+
+        // SAFETY: both entry points come from the library this Plugin keeps loaded; the reply is freed by the
+        // plugin that allocated it
+        let text = unsafe {
+            let reply = (self.call)(command.as_ptr(), payload.as_ptr());
+            if reply.is_null() {
+                return Err(AppError::Plugin("The plugin returned no reply".into()));
+            }
+            let text = CStr::from_ptr(reply).to_string_lossy().to_string();
+            (self.free)(reply);
+            text
+        };
+        match serde_json::from_str::<Reply>(&text) {
+            Ok(Reply::Ok(value)) => Ok(value),
+            Ok(Reply::Error(message)) => Err(AppError::Plugin(message)),
+            Err(e) => Err(AppError::Plugin(format!("Invalid plugin reply: {}", e))),
+        }
+    }
+}
+
+/// The libraries found at startup, loaded or not.
+pub fn list() -> Vec<PluginInfo> {
+    PLUGINS_FOUND.lock().unwrap_or_else(|p| p.into_inner()).clone()
+}
+
+
+//_____________Commands ________________________
+
+/// Example: `invoke("list_plugins")` -> `[{ file: "acme.dll", name: "acme", version: "1.0.0", commands: ["acme.export_xml"], error: null }]`
+#[tauri::command]
+pub fn list_plugins() -> Vec<PluginInfo> {
+    list()
+}
@@ -0,0 +1,192 @@
+// src/preflight.rs
+//
+// Environment checks for the onboarding screen, run once at setup and on demand (`run_preflight`):
+// - free disk space where the model weights (models.rs) and the caches (thumbnails, frames) go
+// - the bundled deepface_cli sidecar is present (and whisper_cli: a warning only, transcription is optional)
+// - the WS port and the deepface port are free (or already used by this app)
+// - the app data dir (database, settings, logs) is writable
+// Every check gives a status: "ok", "warning" (works, but the user should know) or "error" (a feature won't
+// work). The startup report is logged and sent as PREFLIGHT_EVENT; `last_preflight` returns it later.
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+use crate::deepFaceProcess;
+use crate::error::AppError;
+use crate::events;
+use crate::models;
+use crate::settings;
+use crate::websocket;
+use crate::whisper;
+
+
+//____________Const___________
+pub const PREFLIGHT_EVENT: &str = "preflight-report"; // PreflightReport of the startup run
+const MODELS_MIN_FREE: u64 = 2 * 1024 * 1024 * 1024; // every catalog model is ~1.5 GB
+const CACHE_MIN_FREE: u64 = 512 * 1024 * 1024;
+const WRITE_PROBE: &str = ".preflight";
+
+
+//_____________Struct _________________________
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightCheck {
+    pub name: &'static str, // modelsDiskSpace | cacheDiskSpace | sidecar | wsPort | deepfacePort | appDataWritable
+    pub status: CheckStatus,
+    pub message: String,
+    pub details: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightReport {
+    pub ok: bool, // no check in error
+    pub checks: Vec<PreflightCheck>,
+    pub checked_at: String,
+}
+
+
+//_____________Globals _______________________
+static LAST: Lazy<RwLock<Option<PreflightReport>>> = Lazy::new(|| RwLock::new(None));
+
+
+//_____________fn ____________________________
+
+fn check(name: &'static str, status: CheckStatus, message: impl Into<String>, details: Value) -> PreflightCheck {
+    PreflightCheck { name, status, message: message.into(), details }
+}
+
+// Free bytes on the volume of `path`, measured on its closest existing ancestor
+fn available_space(path: &Path) -> Result<u64, AppError> {
+    let existing = path.ancestors().find(|p| p.exists()).unwrap_or(path);
+    fs2::available_space(existing).map_err(|e| AppError::Io(format!("Can't read free space of {:?}: {}", existing, e)))
+}
+
+fn disk_space(name: &'static str, dir: Result<PathBuf, AppError>, min_free: u64) -> PreflightCheck {
+    let dir = match dir {
+        Ok(dir) => dir,
+        Err(e) => return check(name, CheckStatus::Error, e.to_string(), Value::Null),
+    };
+    match available_space(&dir) {
+        Ok(free) if free >= min_free => check(name, CheckStatus::Ok, format!("{} MB free", free / (1024 * 1024)), json!({ "path": dir, "freeBytes": free, "minFreeBytes": min_free })),
+        Ok(free) => check(
+            name,
+            CheckStatus::Warning,
+            format!("Only {} MB free on the disk of {:?} ({} MB recommended)", free / (1024 * 1024), dir, min_free / (1024 * 1024)),
+            json!({ "path": dir, "freeBytes": free, "minFreeBytes": min_free }),
+        ),
+        Err(e) => check(name, CheckStatus::Warning, e.to_string(), json!({ "path": dir })),
+    }
+}
+
+fn sidecar() -> PreflightCheck {
+    match deepFaceProcess::sidecar_path() {
+        Ok(path) if path.is_file() => check("sidecar", CheckStatus::Ok, "deepface_cli found", json!({ "path": path })),
+        Ok(path) => check("sidecar", CheckStatus::Error, format!("deepface_cli missing at {:?}: reinstall the app", path), json!({ "path": path })),
+        Err(e) => check("sidecar", CheckStatus::Error, e.to_string(), Value::Null),
+    }
+}
+
+fn whisper_sidecar() -> PreflightCheck {
+    match whisper::sidecar_path() {
+        Ok(path) if path.is_file() => check("whisperSidecar", CheckStatus::Ok, "whisper_cli found", json!({ "path": path })),
+        Ok(path) => check("whisperSidecar", CheckStatus::Warning, format!("whisper_cli missing at {:?}: transcription won't work", path), json!({ "path": path })),
+        Err(e) => check("whisperSidecar", CheckStatus::Warning, e.to_string(), Value::Null),
+    }
+}
+
+// Free, or already bound by this app (`ours`)
+fn port(name: &'static str, host: &str, port: u16, ours: bool) -> PreflightCheck {
+    let details = json!({ "host": host, "port": port });
+    if ours {
+        return check(name, CheckStatus::Ok, format!("Port {} in use by this app", port), details);
+    }
+    match TcpListener::bind((host, port)) {
+        Ok(_) => check(name, CheckStatus::Ok, format!("Port {} free", port), details),
+        Err(e) => check(name, CheckStatus::Error, format!("Port {} unavailable ({}): close the program using it or change it in settings", port, e), details),
+    }
+}
+
+fn app_data_writable(app_handle: &AppHandle) -> PreflightCheck {
+    let dir = match app_handle.path().app_data_dir() {
+        Ok(dir) => dir,
+        Err(e) => return check("appDataWritable", CheckStatus::Error, format!("No app data dir: {}", e), Value::Null),
+    };
+    let probe = dir.join(WRITE_PROBE);
+    let written = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&probe, b"ok"));
+    let _ = std::fs::remove_file(&probe);
+    match written {
+        Ok(()) => check("appDataWritable", CheckStatus::Ok, "App data dir writable", json!({ "path": dir })),
+        Err(e) => check("appDataWritable", CheckStatus::Error, format!("Can't write to {:?}: {}", dir, e), json!({ "path": dir })),
+    }
+}
+
+/// Run every check.
+pub async fn run(app_handle: &AppHandle) -> PreflightReport {
+    let config = settings::get();
+    let ws_ours = websocket::status().listening.is_some_and(|addr| addr.ends_with(&format!(":{}", config.ws.port)));
+    let deepface_ours = deepFaceProcess::MANAGER.is_running().await;
+    let cache_dir = app_handle.path().app_cache_dir().map_err(|e| AppError::Io(format!("No app cache dir: {}", e)));
+
+    let checks = vec![
+        disk_space("modelsDiskSpace", models::deepface_home(), MODELS_MIN_FREE),
+        disk_space("cacheDiskSpace", cache_dir, CACHE_MIN_FREE),
+        sidecar(),
+        whisper_sidecar(),
+        port("wsPort", &config.ws.host, config.ws.port, ws_ours),
+        port("deepfacePort", "127.0.0.1", config.deepface.port, deepface_ours),
+        app_data_writable(app_handle),
+    ];
+    let report = PreflightReport {
+        ok: checks.iter().all(|c| c.status != CheckStatus::Error),
+        checks,
+        checked_at: chrono::Utc::now().to_rfc3339(),
+    };
+    if let Ok(mut last) = LAST.write() {
+        *last = Some(report.clone());
+    }
+    report
+}
+
+/// Startup run: problems are logged and the report is sent to the onboarding UI.
+pub fn run_on_startup(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let report = run(&app_handle).await;
+        for problem in report.checks.iter().filter(|c| c.status != CheckStatus::Ok) {
+            warn!("⚠️ Preflight {}: {}", problem.name, problem.message);
+        }
+        if report.ok {
+            info!("✅ Preflight checks passed");
+        }
+        events::emit_all_surfaces(&app_handle, PREFLIGHT_EVENT, report);
+    });
+}
+
+
+//_____________Commands ________________________
+
+/// Example: `invoke("run_preflight")` -> `{ ok, checks: [{ name, status, message, details }], checkedAt }`.
+#[tauri::command]
+pub async fn run_preflight(app: AppHandle) -> PreflightReport {
+    run(&app).await
+}
+
+/// Report of the last run (the startup one until `run_preflight` is called), None before it finished.
+#[tauri::command]
+pub fn last_preflight() -> Option<PreflightReport> {
+    LAST.read().ok().and_then(|last| last.clone())
+}
@@ -0,0 +1,144 @@
+// src/protocol.rs
+//
+// Frames of the WS protocol, shared by the server (websocket.rs, events.rs) and anything that talks to it.
+// - `WsRequest`: what a client sends, `{ requestId?, command, payload }` (also the JSON header of binary frames)
+// - `WsResponse`: the reply, `{ requestId, status: "ok" | "error" | "partial", seq?, code?, command, data }`
+// - `EventFrame`: a pushed event, `{ status: "event", event, data }` (no requestId)
+// - field names are camelCase on the wire; `schema()` is the JSON Schema of these frames, kept in
+//   protocol.schema.json for the CEP panel (tests/protocol.rs fails when the file is out of date)
+// - `Encoding`: JSON text frames, or MessagePack binary frames (the same fields, as maps) for v7 clients that
+//   connect with `?encoding=msgpack`
+// Also served to WS clients by the `protocol_schema` command.
+
+use schemars::gen::SchemaSettings;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::error::AppError;
+
+
+//____________Const___________
+pub const SCHEMA_FILE: &str = "protocol.schema.json"; // next to Cargo.toml
+
+
+//_____________Struct _________________________
+/// Request from a client (CEP panel).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WsRequest {
+    pub request_id: Option<u64>, // echoed back in the reply so the client can match responses
+    pub command: String,
+    #[serde(default)]
+    pub payload: Value,          // arguments of the command, see registry.rs
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseStatus {
+    Ok,
+    Error,
+    Partial, // streamed chunk, more frames follow
+}
+
+/// Reply to a request: one final "ok" / "error" frame, after any "partial" ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WsResponse {
+    pub request_id: Option<u64>,
+    pub status: ResponseStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,     // chunk index of "partial" frames, starting at 0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>, // AppError::code() when status is "error"
+    pub command: String,
+    pub data: Value,          // the command result; on error the serialized AppError
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EventStatus {
+    Event,
+}
+
+/// Event pushed to a subscribed connection (events.rs).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EventFrame {
+    pub status: EventStatus,
+    pub event: String, // one of events::EVENTS
+    pub data: Value,
+}
+
+
+/// Encoding of a connection's frames, chosen by the client in its URL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    #[default]
+    Json,
+    MsgPack,
+}
+
+
+//_____________fn ____________________________
+
+impl Encoding {
+    /// `?encoding=` value; unknown values keep JSON.
+    pub fn from_query(value: Option<&str>) -> Self {
+        match value {
+            Some("msgpack") => Encoding::MsgPack,
+            _ => Encoding::Json,
+        }
+    }
+}
+
+/// A frame as MessagePack, fields by name (a map, like the JSON object).
+pub fn to_msgpack<T: Serialize>(frame: &T) -> Result<Vec<u8>, AppError> {
+    rmp_serde::to_vec_named(frame).map_err(|e| AppError::InvalidInput(format!("MessagePack encoding failed: {}", e)))
+}
+
+pub fn from_msgpack<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, AppError> {
+    rmp_serde::from_slice(bytes).map_err(|e| AppError::InvalidInput(format!("Invalid MessagePack: {}", e)))
+}
+
+impl WsResponse {
+    pub fn ok(request_id: Option<u64>, command: String, data: Value) -> Self {
+        WsResponse { request_id, status: ResponseStatus::Ok, seq: None, code: None, command, data }
+    }
+
+    pub fn partial(request_id: Option<u64>, command: String, seq: u64, data: Value) -> Self {
+        WsResponse { request_id, status: ResponseStatus::Partial, seq: Some(seq), code: None, command, data }
+    }
+
+    pub fn error(request_id: Option<u64>, command: String, err: AppError) -> Self {
+        WsResponse {
+            request_id,
+            status: ResponseStatus::Error,
+            seq: None,
+            code: Some(err.code().to_string()),
+            command,
+            data: serde_json::to_value(&err).unwrap_or_else(|_| json!({ "message": err.to_string() })),
+        }
+    }
+}
+
+impl EventFrame {
+    pub fn new(event: &str, data: Value) -> Self {
+        EventFrame { status: EventStatus::Event, event: event.to_string(), data }
+    }
+}
+
+/// JSON Schema (draft-07) of the frames, each under `definitions`.
+pub fn schema() -> Value {
+    let mut generator = SchemaSettings::draft07().into_generator();
+    generator.subschema_for::<WsRequest>();
+    generator.subschema_for::<WsResponse>();
+    generator.subschema_for::<EventFrame>();
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "WS protocol",
+        "definitions": generator.definitions(),
+    })
+}
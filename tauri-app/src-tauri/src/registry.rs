@@ -0,0 +1,678 @@
+// src/registry.rs
+//
+// Command registry shared by the WebSocket dispatcher and the webview.
+// - Every command is registered once by name with an async handler `(CommandContext, payload) -> Result<Value, AppError>`
+// - The payload carries the same arguments as `invoke`, e.g. `{ "clipId": 1 }` for list_markers,
+//   and is validated against the command's payload struct (payloads.rs) before the handler runs
+// - WS:      `{ "command": "list_markers", "payload": { "clipId": 1 } }`
+// - Webview: `invoke("run_command", { name: "list_markers", payload: { clipId: 1 } })`
+// - `list_commands` lets the CEP panel discover what this build supports.
+// - Handlers can stream: `ctx.send_chunk(..)` becomes a `status: "partial"` WS frame (or a message on the
+//   webview's `stream_command` Channel) before the final reply. Callers that can't stream get the chunks as an array.
+//
+// Auth, rate limiting, feature gating (license tier), logging and metrics wrap every command here, as the
+// middleware chain of middleware.rs, so they apply to every caller.
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, RwLock};
+use tauri::ipc::Channel;
+use tauri::AppHandle;
+use crate::database;
+use crate::error::AppError;
+use crate::middleware::{self, Call, RateLimiter};
+use crate::payloads::{
+    self, AddClipArgs, AddMarkerArgs, AddReferenceFaceArgs, AnalyzeAudioArgs, AnalyzeClipArgs, AnalyzeFrameArgs, BackupDatabaseArgs, BuildMomentsArgs, ClearCacheArgs, ClipEmotionSummaryArgs, ClipIdArgs, ClipThumbnailArgs, CrashReportArgs, CreateProjectArgs, DeepFaceAnalyzeArgs, DeepFaceDetectArgs, DeepFaceStartArgs, DeepFaceVerifyArgs, DetectFrameArgs, DetectScenesArgs, DownloadModelsArgs, ExportAnalysisArgs, ExportMarkersArgs, ExtractFramesArgs, Field, GreetArgs, IdentifyFaceArgs, ImportMarkersArgs, TranscribeClipArgs,
+    JobIdArgs, ListJobsArgs, ListMarkersArgs, MacroNameArgs, MarkerIdArgs, NoArgs, Payload, ProbeMediaArgs, ProjectIdArgs, RecentLogsArgs, ReferenceIdArgs, RestoreDatabaseArgs, RunMacroArgs, SaveMacroArgs, SearchMarkersArgs, StartCameraArgs, StartJobArgs, SubscribeArgs, TrackFacesArgs, UnsubscribeArgs, UpdateMarkerArgs,
+};
+use crate::{analysis_export, audio, cache, camera, commands, crash, deepFaceProcess, deepface_resources, discovery, emotion_stats, events, faces, features, jobs, logging, macros, marker_export, marker_import, media, metrics, models, moments, monitor, plugins, preflight, protocol, settings, sync, thumbnails, tls, tracking, updater, websocket};
+use crate::ws_auth::{self, Role};
+
+
+//_____________Struct _________________________
+pub type HandlerFuture = Pin<Box<dyn Future<Output = Result<Value, AppError>> + Send>>;
+pub type Handler = Arc<dyn Fn(CommandContext, Value) -> HandlerFuture + Send + Sync>;
+pub type ChunkSink = Arc<dyn Fn(Value) + Send + Sync>;
+
+/// What a handler gets besides its payload.
+#[derive(Clone)]
+pub struct CommandContext {
+    app: Option<AppHandle>,               // None on a WS server started without the app (serve_headless)
+    sink: Option<ChunkSink>,              // where partial results go while the command runs
+    collected: Arc<Mutex<Vec<Value>>>,    // chunks kept for callers that can't stream
+    attachment: Option<Arc<Vec<u8>>>,     // raw bytes of a binary WS request (e.g. an image)
+    connection: Option<u64>,              // WS connection the request came from
+    role: Role,                           // what the caller may run (ws_auth.rs); control but for WS clients
+    source: &'static str,                 // "webview" | "ws" | "http": prefix of the request metrics
+    rate_limiter: Option<Arc<Mutex<RateLimiter>>>, // the caller's request budget (a WS connection's)
+}
+
+impl CommandContext {
+    pub fn new(app: AppHandle) -> Self {
+        CommandContext { app: Some(app), ..CommandContext::detached() }
+    }
+
+    /// A context without the app: commands that need it fail, the others run as usual.
+    pub fn detached() -> Self {
+        CommandContext {
+            app: None,
+            sink: None,
+            collected: Arc::default(),
+            attachment: None,
+            connection: None,
+            role: Role::Control,
+            source: "webview",
+            rate_limiter: None,
+        }
+    }
+
+    pub fn with_sink(mut self, sink: ChunkSink) -> Self {
+        self.sink = Some(sink);
+        self
+    }
+
+    pub fn app(&self) -> Result<&AppHandle, AppError> {
+        self.app.as_ref().ok_or_else(|| AppError::Ws("This command needs the running app (server started without it)".into()))
+    }
+
+    pub fn with_attachment(mut self, bytes: Vec<u8>) -> Self {
+        self.attachment = Some(Arc::new(bytes));
+        self
+    }
+
+    /// The binary part of the request; commands that need one fail with a clear error without it.
+    pub fn attachment(&self) -> Result<&[u8], AppError> {
+        self.attachment
+            .as_deref()
+            .map(Vec::as_slice)
+            .ok_or_else(|| AppError::InvalidInput("This command expects a binary WS message (header + image bytes)".into()))
+    }
+
+    pub fn with_connection(mut self, connection: u64) -> Self {
+        self.connection = Some(connection);
+        self
+    }
+
+    /// Id of the calling WS connection (protocol v4+); webview callers have none.
+    pub fn connection(&self) -> Result<u64, AppError> {
+        self.connection.ok_or_else(|| {
+            AppError::InvalidInput("This command needs a WebSocket connection on protocol v4+ (webview: use listen())".into())
+        })
+    }
+
+    pub fn with_role(mut self, role: Role) -> Self {
+        self.role = role;
+        self
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    pub fn with_source(mut self, source: &'static str) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn source(&self) -> &'static str {
+        self.source
+    }
+
+    /// Requests through this context share `limiter`'s budget (one per WS connection).
+    pub fn with_rate_limiter(mut self, limiter: Arc<Mutex<RateLimiter>>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    pub fn rate_limiter(&self) -> Option<&Arc<Mutex<RateLimiter>>> {
+        self.rate_limiter.as_ref()
+    }
+
+    /// Context of a command run by this one (macros.rs): same app, connection, source and role, its own
+    /// chunks, no attachment; the caller's request already spent its rate limit.
+    pub fn for_step(&self) -> CommandContext {
+        CommandContext {
+            app: self.app.clone(),
+            connection: self.connection,
+            role: self.role,
+            source: self.source,
+            ..CommandContext::detached()
+        }
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Send one partial result ahead of the final reply.
+    pub fn send_chunk<T: Serialize>(&self, chunk: T) -> Result<(), AppError> {
+        let chunk = to_value(chunk)?;
+        match &self.sink {
+            Some(sink) => sink(chunk),
+            None => self.collected.lock().unwrap_or_else(|p| p.into_inner()).push(chunk),
+        }
+        Ok(())
+    }
+
+    /// Stream `items` in chunks of `chunk_size`; the final reply is then `{ total, chunks }`.
+    /// Without a streaming caller this is just the whole array.
+    pub fn send_items<T: Serialize>(&self, items: Vec<T>, chunk_size: usize) -> Result<Value, AppError> {
+        if !self.is_streaming() {
+            return to_value(items);
+        }
+        let total = items.len();
+        let mut chunks = 0;
+        for chunk in items.chunks(chunk_size.max(1)) {
+            self.send_chunk(chunk)?;
+            chunks += 1;
+        }
+        Ok(json!({ "total": total, "chunks": chunks }))
+    }
+
+    // Final value for non-streaming callers: chunks sent without a sink replace an empty result
+    fn finish(&self, result: Value) -> Value {
+        let collected = std::mem::take(&mut *self.collected.lock().unwrap_or_else(|p| p.into_inner()));
+        if collected.is_empty() || !result.is_null() {
+            result
+        } else {
+            Value::Array(collected)
+        }
+    }
+}
+
+/// What `list_commands` returns for each command.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandInfo {
+    pub name: String,
+    pub description: String,
+    pub feature: Option<String>, // license feature required to run it
+    pub params: Option<Vec<Field>>, // payload fields; None = any JSON
+    pub read_only: bool,            // runnable by read-only WS clients (ws_auth.rs)
+}
+
+struct Command {
+    info: CommandInfo,
+    handler: Handler,
+}
+
+
+//_____________Globals _______________________
+static REGISTRY: Lazy<RwLock<HashMap<String, Command>>> = Lazy::new(|| {
+    let mut commands = HashMap::new();
+    builtin_commands(&mut commands);
+    RwLock::new(commands)
+});
+
+
+//_____________fn ____________________________
+
+// Add (or replace) a command taking a raw JSON payload
+fn insert<F, Fut>(
+    commands: &mut HashMap<String, Command>,
+    name: &str,
+    description: &str,
+    feature: Option<&str>,
+    params: Option<Vec<Field>>,
+    handler: F,
+) where
+    F: Fn(CommandContext, Value) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Value, AppError>> + Send + 'static,
+{
+    let info = CommandInfo {
+        name: name.to_string(),
+        description: description.to_string(),
+        feature: feature.map(str::to_string),
+        params,
+        read_only: ws_auth::is_read_only(name),
+    };
+    let handler: Handler = Arc::new(move |ctx, payload| Box::pin(handler(ctx, payload)));
+    commands.insert(name.to_string(), Command { info, handler });
+}
+
+// Add a command whose payload is parsed (and validated) into `A` first
+fn insert_typed<A, F, Fut>(commands: &mut HashMap<String, Command>, name: &str, description: &str, feature: Option<&str>, handler: F)
+where
+    A: Payload,
+    F: Fn(CommandContext, A) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Value, AppError>> + Send + 'static,
+{
+    let handler = Arc::new(handler);
+    let command = name.to_string();
+    insert(commands, name, description, feature, Some(A::FIELDS.to_vec()), move |ctx, payload| {
+        let parsed = payloads::parse::<A>(&command, payload);
+        let handler = handler.clone();
+        async move { handler(ctx, parsed?).await }
+    });
+}
+
+/// Add a command from outside the built-ins (plugins.rs), taking a raw JSON payload. Never replaces one.
+pub fn register(name: &str, description: &str, handler: Handler) -> Result<(), AppError> {
+    let mut registry = REGISTRY.write().unwrap_or_else(|p| p.into_inner());
+    if registry.contains_key(name) {
+        return Err(AppError::InvalidInput(format!("Command {} is already registered", name)));
+    }
+    let info = CommandInfo {
+        name: name.to_string(),
+        description: description.to_string(),
+        feature: None,
+        params: None,
+        read_only: ws_auth::is_read_only(name),
+    };
+    registry.insert(name.to_string(), Command { info, handler });
+    Ok(())
+}
+
+/// Remove a command added with `register` (a plugin whose other commands failed to register).
+pub fn unregister(name: &str) {
+    REGISTRY.write().unwrap_or_else(|p| p.into_inner()).remove(name);
+}
+
+/// Registered commands, sorted by name.
+pub fn list() -> Vec<CommandInfo> {
+    let registry = REGISTRY.read().unwrap_or_else(|p| p.into_inner());
+    let mut infos: Vec<CommandInfo> = registry.values().map(|c| c.info.clone()).collect();
+    infos.sort_by(|a, b| a.name.cmp(&b.name));
+    infos
+}
+
+/// Run `name` with `payload` through the middleware chain (middleware.rs), which refuses it when the caller is
+/// over its rate limit, its role can't run it or its feature isn't licensed.
+pub async fn dispatch(name: &str, payload: Value, ctx: CommandContext) -> Result<Value, AppError> {
+    let (handler, info) = {
+        let registry = REGISTRY.read().unwrap_or_else(|p| p.into_inner());
+        match registry.get(name) {
+            Some(command) => (command.handler.clone(), Some(command.info.clone())),
+            None => (unknown_command(name), None),
+        }
+    };
+    let call = Call { command: name.to_string(), info, payload, ctx: ctx.clone() };
+    let result = middleware::run(call, handler).await?;
+    Ok(ctx.finish(result))
+}
+
+// Handler of a name that isn't registered
+fn unknown_command(name: &str) -> Handler {
+    let name = name.to_string();
+    Arc::new(move |_, _| {
+        let name = name.clone();
+        Box::pin(async move { Err(AppError::UnknownCommand(name)) })
+    })
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, AppError> {
+    serde_json::to_value(value).map_err(|e| AppError::Io(format!("Failed to serialize result: {}", e)))
+}
+
+// Image of a deepface_* command: base64 in the payload, else the bytes of the binary message
+fn image_of(ctx: &CommandContext, base64: Option<&str>) -> Result<Vec<u8>, AppError> {
+    match base64 {
+        Some(text) => deepFaceProcess::decode_image(text),
+        None => ctx
+            .attachment()
+            .map(<[u8]>::to_vec)
+            .map_err(|_| AppError::InvalidInput("Send the image as base64 `image` or as a binary message".into())),
+    }
+}
+
+// Both images of deepface_verify
+fn image_pair(ctx: &CommandContext, args: &DeepFaceVerifyArgs) -> Result<(Vec<u8>, Vec<u8>), AppError> {
+    if let (Some(img1), Some(img2)) = (&args.img1, &args.img2) {
+        return Ok((deepFaceProcess::decode_image(img1)?, deepFaceProcess::decode_image(img2)?));
+    }
+    let bytes = ctx.attachment().map_err(|_| AppError::InvalidInput("Send img1 and img2 as base64, or both in a binary message with `split`".into()))?;
+    match args.split {
+        Some(split) if split > 0 && split < bytes.len() => Ok((bytes[..split].to_vec(), bytes[split..].to_vec())),
+        _ => Err(AppError::InvalidInput("`split` must be the byte length of img1, inside the binary message".into())),
+    }
+}
+
+fn builtin_commands(commands: &mut HashMap<String, Command>) {
+    insert_typed(commands, "list_commands", "Commands supported by this build", None, |_, _: NoArgs| async {
+        to_value(list())
+    });
+
+    // CEP panel
+    insert_typed(commands, "test_server_connection", "Check that the server answers", None, |ctx, _: NoArgs| async move {
+        if let Ok(app) = ctx.app() {
+            websocket::emit_cep_status(app, "cep.connection_tested");
+        }
+        Ok(json!("Server is alive!"))
+    });
+    insert_typed(commands, "subscribe", "Receive pushed events on this connection", None, |ctx, args: SubscribeArgs| async move {
+        to_value(events::subscribe(ctx.connection()?, &args.events)?)
+    });
+    insert_typed(commands, "unsubscribe", "Stop pushed events (all when events is omitted)", None, |ctx, args: UnsubscribeArgs| async move {
+        to_value(events::unsubscribe(ctx.connection()?, args.events.as_deref())?)
+    });
+    insert_typed(commands, "list_events", "Events available to subscribe", None, |_, _: NoArgs| async { to_value(events::EVENTS) });
+    insert_typed(commands, "protocol_schema", "JSON Schema of the WS frames (requests, replies, events)", None, |_, _: NoArgs| async { Ok(protocol::schema()) });
+    insert(commands, "fetch_JSON", "Echo the payload back", None, None, |_, payload| async move { Ok(payload) });
+    insert_typed(
+        commands,
+        "fetch_deepFaceCameraEmotionList",
+        "Dominant emotions in the last analyzed camera frame",
+        Some(features::LIVE_CAMERA),
+        |_, _: NoArgs| async { to_value(camera::last_emotions()) },
+    );
+    insert_typed(commands, "deepface_start", "Start the deepface sidecar { port? } (deepface-status events)", None, |ctx, args: DeepFaceStartArgs| async move {
+        to_value(deepFaceProcess::control(ctx.connection().ok(), true, args.port).await?)
+    });
+    insert_typed(commands, "deepface_stop", "Stop the deepface sidecar (deepface-status events)", None, |ctx, _: NoArgs| async move {
+        to_value(deepFaceProcess::control(ctx.connection().ok(), false, None).await?)
+    });
+    insert_typed(commands, "deepface_status", "Deepface sidecar running / port / startup policy / queue depth", None, |_, _: NoArgs| async {
+        to_value(deepFaceProcess::sidecar_status(None, None).await)
+    });
+    insert_typed(commands, "get_deepface_resource_usage", "CPU %, resident memory and uptime of the deepface sidecar (null when stopped)", None, |_, _: NoArgs| async {
+        to_value(deepface_resources::usage().await)
+    });
+    insert_typed(commands, "list_deepface_detectors", "Detector backends the deepface sidecar can run", None, |_, _: NoArgs| async {
+        to_value(deepFaceProcess::options().await?.detectors)
+    });
+    insert_typed(commands, "list_deepface_models", "Recognition models the deepface sidecar can run", None, |_, _: NoArgs| async {
+        to_value(deepFaceProcess::options().await?.models)
+    });
+    insert_typed(commands, "list_models", "Deepface model weights and whether they are downloaded", None, |_, _: NoArgs| async {
+        to_value(models::list()?)
+    });
+    insert_typed(commands, "download_models", "Download model weights { names? } (model-download events)", None, |_, args: DownloadModelsArgs| async move {
+        to_value(models::download_all(args.names).await?)
+    });
+    insert_typed(commands, "get_cache_usage", "Disk used by thumbnails / frames / models, with their limits", None, |ctx, _: NoArgs| async move {
+        to_value(cache::get_cache_usage(ctx.app()?.clone()).await?)
+    });
+    insert_typed(commands, "clear_cache", "Remove a cache category { category: thumbnails|frames|models|all }", None, |ctx, args: ClearCacheArgs| async move {
+        to_value(cache::clear_cache(ctx.app()?.clone(), args.category).await?)
+    });
+    insert_typed(commands, "run_preflight", "Disk space, sidecar, ports and app data dir checks", None, |ctx, _: NoArgs| async move {
+        to_value(preflight::run(ctx.app()?).await)
+    });
+    insert_typed(commands, "list_cameras", "Webcams of this machine (ids for start_camera)", None, |_, _: NoArgs| async {
+        to_value(camera::list().await?)
+    });
+    insert_typed(commands, "start_camera", "Live emotion preview { device?, fps? } (camera-emotions events)", Some(features::LIVE_CAMERA), |ctx, args: StartCameraArgs| async move {
+        to_value(camera::start(ctx.app()?, args.device, args.fps).await?)
+    });
+    insert_typed(commands, "stop_camera", "Stop the live emotion preview", None, |ctx, _: NoArgs| async move {
+        to_value(camera::stop(ctx.app()?))
+    });
+    insert_typed(commands, "get_camera_status", "Live preview running / device / fps", None, |_, _: NoArgs| async {
+        to_value(camera::status())
+    });
+    insert_typed(commands, "analyze_frame", "Analyze the image sent as a binary message (only its roi when given)", None, |ctx, args: AnalyzeFrameArgs| async move {
+        deepFaceProcess::analyze_frame_roi(ctx.attachment()?, args.roi, args.actions, args.detector, args.model).await
+    });
+    insert_typed(commands, "detect_frame", "Detect faces in the image sent as a binary message (only its roi when given)", None, |ctx, args: DetectFrameArgs| async move {
+        deepFaceProcess::detect_frame_roi(ctx.attachment()?, args.roi, args.detector).await
+    });
+    insert_typed(commands, "deepface_analyze", "Analyze an image { image? (base64), actions?, detector?, model?, roi? } or a binary message", None, |ctx, args: DeepFaceAnalyzeArgs| async move {
+        let image = image_of(&ctx, args.image.as_deref())?;
+        deepFaceProcess::analyze_frame_roi(&image, args.roi, args.actions, args.detector, args.model).await
+    });
+    insert_typed(commands, "deepface_detect", "Detect faces in an image { image? (base64), detector?, roi? } or a binary message", None, |ctx, args: DeepFaceDetectArgs| async move {
+        let image = image_of(&ctx, args.image.as_deref())?;
+        deepFaceProcess::detect_frame_roi(&image, args.roi, args.detector).await
+    });
+    insert_typed(commands, "deepface_verify", "Same person? { img1, img2 } base64, or one binary message split at `split`", None, |ctx, args: DeepFaceVerifyArgs| async move {
+        let (img1, img2) = image_pair(&ctx, &args)?;
+        deepFaceProcess::verify_frames(&img1, &img2, args.detector, args.model).await
+    });
+    insert_typed(commands, "track_faces", "Per-person emotion tracks over image files { frames, timestamps?, detector?, model? }", None, |_, args: TrackFacesArgs| async move {
+        to_value(tracking::track_frames(&args.frames, args.timestamps, args.detector, args.model).await?)
+    });
+    insert_typed(commands, "add_reference_face", "Store the face of the image sent as a binary message as { name, model? }", Some(features::FACE_SEARCH), |ctx, args: AddReferenceFaceArgs| async move {
+        to_value(faces::add_reference(&args.name, ctx.attachment()?, args.model).await?)
+    });
+    insert_typed(commands, "identify_face", "Match the faces of the image sent as a binary message against the reference faces", Some(features::FACE_SEARCH), |ctx, args: IdentifyFaceArgs| async move {
+        to_value(faces::identify(ctx.attachment()?, args.model).await?)
+    });
+    insert_typed(commands, "list_reference_faces", "Reference faces known to identify_face", None, |_, _: NoArgs| async {
+        to_value(database::blocking(database::list_reference_faces).await?)
+    });
+    insert_typed(commands, "delete_reference_face", "Forget a reference face", None, |_, args: ReferenceIdArgs| async move {
+        to_value(database::blocking(move || database::delete_reference_face(args.reference_id)).await?)
+    });
+    insert_typed(commands, "greet", "Say hello", None, |_, args: GreetArgs| async move {
+        Ok(json!(commands::greet(&args.name)))
+    });
+
+    // Clips & markers
+    insert_typed(commands, "add_clip", "Register a clip (in the current project by default)", None, |_, args: AddClipArgs| async move {
+        to_value(database::blocking(move || database::add_clip(&args.path, args.fps, args.project_id)).await?)
+    });
+    insert_typed(commands, "add_marker", "Add a marker { marker: { clipId, timestamp, … } }", None, |_, args: AddMarkerArgs| async move {
+        to_value(commands::add_marker(args.marker).await?)
+    });
+    insert_typed(commands, "list_markers", "A page of a clip's markers { filter, sort, offset, limit } (streamed when chunkSize is set)", None, |ctx, args: ListMarkersArgs| async move {
+        let filter = args.filter.unwrap_or_default();
+        let (clip_id, sort, offset, limit) = (args.clip_id, args.sort.unwrap_or_default(), args.offset, args.limit);
+        let page = database::blocking(move || database::query_markers(clip_id, &filter, sort, offset, limit)).await?;
+        match args.chunk_size {
+            // The page's markers as chunks, its counts in the final reply
+            Some(size) if ctx.is_streaming() => {
+                let streamed = ctx.send_items(page.markers, size)?;
+                Ok(json!({ "total": page.total, "offset": page.offset, "limit": page.limit, "chunks": streamed["chunks"] }))
+            }
+            _ => to_value(page),
+        }
+    });
+    insert_typed(commands, "search_markers", "Full-text search in a project's markers { query, projectId?, offset?, limit? }", None, |_, args: SearchMarkersArgs| async move {
+        to_value(database::blocking(move || database::search_markers(&args.query, args.project_id, args.offset, args.limit)).await?)
+    });
+    insert_typed(commands, "update_marker", "Change fields of a marker { markerId, changes: { name, … } }", None, |_, args: UpdateMarkerArgs| async move {
+        to_value(commands::update_marker(args.marker_id, args.changes).await?)
+    });
+    insert_typed(commands, "delete_marker", "Delete a marker", None, |_, args: MarkerIdArgs| async move {
+        to_value(database::blocking(move || database::delete_marker(args.marker_id)).await?)
+    });
+    insert_typed(commands, "get_analysis", "The deepface result stored with a marker (null without one)", None, |_, args: MarkerIdArgs| async move {
+        to_value(database::blocking(move || database::get_analysis(args.marker_id)).await?)
+    });
+    insert_typed(commands, "get_clip_emotion_summary", "Emotion distribution, arc and peaks of a clip { clipId, windowSecs? }", None, |_, args: ClipEmotionSummaryArgs| async move {
+        to_value(database::blocking(move || emotion_stats::clip_summary(args.clip_id, args.window_secs)).await?)
+    });
+    insert_typed(commands, "get_project_emotion_summary", "Emotion distribution and peaks of a project, per clip too", None, |_, args: ProjectIdArgs| async move {
+        to_value(database::blocking(move || emotion_stats::project_summary(args.project_id)).await?)
+    });
+    insert_typed(commands, "undo_last", "Undo the last clip / marker change of a project", None, |ctx, args: ProjectIdArgs| async move {
+        to_value(commands::undo_last(ctx.app()?.clone(), args.project_id).await?)
+    });
+    insert_typed(commands, "redo_last", "Redo the last undone change of a project", None, |ctx, args: ProjectIdArgs| async move {
+        to_value(commands::redo_last(ctx.app()?.clone(), args.project_id).await?)
+    });
+    insert_typed(commands, "export_markers", "Export markers (format: csv|edl|xml)", None, |_, args: ExportMarkersArgs| async move {
+        to_value(database::blocking(move || marker_export::export_markers(args.clip_id, args.format, args.path)).await?)
+    });
+    insert_typed(commands, "export_analysis", "Export a project's raw analysis rows (format: jsonl|csv|parquet)", None, |_, args: ExportAnalysisArgs| async move {
+        let format = args.format.parse()?;
+        to_value(database::blocking(move || analysis_export::export(args.project_id, format, &args.path)).await?)
+    });
+    insert_typed(commands, "import_markers", "Import markers from a Premiere csv / xml export", None, |_, args: ImportMarkersArgs| async move {
+        to_value(database::blocking(move || marker_import::import_markers(args.clip_id, args.path, args.format)).await?)
+    });
+
+    // Projects
+    insert_typed(commands, "create_project", "Create a named project", None, |_, args: CreateProjectArgs| async move {
+        to_value(commands::create_project(args.name).await?)
+    });
+    insert_typed(commands, "open_project", "Make a project current, returns it with its clips", None, |ctx, args: ProjectIdArgs| async move {
+        to_value(commands::open_project(ctx.app()?.clone(), args.project_id).await?)
+    });
+    insert_typed(commands, "list_projects", "Projects, current (last opened) first", None, |_, _: NoArgs| async {
+        to_value(database::blocking(database::list_projects).await?)
+    });
+    insert_typed(commands, "delete_project", "Delete a project with its clips and markers", None, |_, args: ProjectIdArgs| async move {
+        to_value(database::blocking(move || database::delete_project(args.project_id)).await?)
+    });
+
+    // Media
+    insert_typed(commands, "extract_frames", "Frames of a clip as JPEG files (timestamps or everySecs)", None, |ctx, args: ExtractFramesArgs| async move {
+        to_value(media::extract_frames_to_cache(ctx.app()?, &args.path, args.timestamps, args.every_secs, args.max_width).await?)
+    });
+    insert_typed(commands, "probe_media", "Duration, fps, resolution, codecs and audio streams of a clip (ffprobe)", None, |_, args: ProbeMediaArgs| async move {
+        to_value(media::probe(&args.path).await?)
+    });
+    insert_typed(commands, "detect_scenes", "Shots of a clip between scene cuts { path, threshold? }", None, |_, args: DetectScenesArgs| async move {
+        to_value(media::detect_shots(&args.path, args.threshold).await?)
+    });
+    insert_typed(commands, "get_clip_thumbnail", "Cached thumbnail of a clip (path, or base64 data URL)", None, |ctx, args: ClipThumbnailArgs| async move {
+        let thumbnail = thumbnails::clip_thumbnail(ctx.app()?, &args.path, args.timestamp, args.size, args.base64.unwrap_or(false));
+        to_value(thumbnail.await?)
+    });
+
+    // Jobs
+    insert_typed(commands, "start_job", "Start a background job { kind, params }", None, |ctx, args: StartJobArgs| async move {
+        to_value(jobs::start(ctx.app()?, &args.kind, args.params.unwrap_or_else(|| json!({})))?)
+    });
+    insert_typed(commands, "cancel_job", "Cancel a running job", None, |ctx, args: JobIdArgs| async move {
+        to_value(jobs::cancel(ctx.app()?, args.job_id)?)
+    });
+    insert_typed(commands, "resume_job", "Run an interrupted / failed job again", None, |ctx, args: JobIdArgs| async move {
+        to_value(jobs::resume(ctx.app()?, args.job_id)?)
+    });
+    insert_typed(commands, "list_jobs", "Job history, most recent first", None, |_, args: ListJobsArgs| async move {
+        to_value(database::blocking(move || database::list_jobs(args.limit.unwrap_or(jobs::DEFAULT_LIST_LIMIT))).await?)
+    });
+    // Same params as the job: checked by jobs::start, which keeps them as given
+    insert(commands, "analyze_clip", "Emotion markers for a clip, as an analyze_clip job { path, sampling?, everySecs? }", Some(features::BATCH_ANALYZE), Some(AnalyzeClipArgs::FIELDS.to_vec()), |ctx, payload| async move {
+        to_value(jobs::start(ctx.app()?, "analyze_clip", payload)?)
+    });
+    insert(commands, "analyze_audio", "Loudness, silence / speech segments and audio cue markers of a clip, as an analyze_audio job { path, placeMarkers? }", None, Some(AnalyzeAudioArgs::FIELDS.to_vec()), |ctx, payload| async move {
+        to_value(jobs::start(ctx.app()?, "analyze_audio", payload)?)
+    });
+    insert_typed(commands, "get_audio_analysis", "The stored audio analysis of a clip (null without one)", None, |_, args: ClipIdArgs| async move {
+        to_value(database::blocking(move || audio::get(args.clip_id)).await?)
+    });
+    insert(commands, "transcribe_clip", "Timestamped transcript of a clip (whisper), as a transcribe_clip job { path, language? }", None, Some(TranscribeClipArgs::FIELDS.to_vec()), |ctx, payload| async move {
+        to_value(jobs::start(ctx.app()?, "transcribe_clip", payload)?)
+    });
+    insert_typed(commands, "get_transcript", "Transcript segments of a clip, in timeline order", None, |_, args: ClipIdArgs| async move {
+        to_value(database::blocking(move || database::list_transcript(args.clip_id)).await?)
+    });
+    insert(commands, "build_moments", "Transcript segments joined with emotion peaks into labeled moments, as a build_moments job { clipId, minScore? }", None, Some(BuildMomentsArgs::FIELDS.to_vec()), |ctx, payload| async move {
+        to_value(jobs::start(ctx.app()?, "build_moments", payload)?)
+    });
+    insert_typed(commands, "list_moments", "Moments of a clip, in timeline order", None, |_, args: ClipIdArgs| async move {
+        to_value(database::blocking(move || database::list_moments(args.clip_id)).await?)
+    });
+    insert_typed(commands, "search_moments", "Full-text search in a project's moments { query, projectId?, offset?, limit? }", None, |_, args: SearchMarkersArgs| async move {
+        to_value(database::blocking(move || database::search_moments(&args.query, args.project_id, args.offset, args.limit)).await?)
+    });
+    insert_typed(commands, "export_moments", "Export a clip's moments as markers (format: csv|edl|xml)", None, |_, args: ExportMarkersArgs| async move {
+        to_value(database::blocking(move || moments::export(args.clip_id, &args.format, &args.path)).await?)
+    });
+
+    // Updates
+    insert_typed(commands, "check_for_update", "Newer version on the update channel, or null", None, |ctx, _: NoArgs| async move {
+        to_value(updater::check(ctx.app()?).await?)
+    });
+    insert_typed(commands, "download_update", "Download the update found by check_for_update", None, |ctx, _: NoArgs| async move {
+        to_value(updater::download(ctx.app()?).await?)
+    });
+    insert_typed(commands, "install_update", "Install the downloaded update and restart", None, |ctx, _: NoArgs| async move {
+        to_value(updater::install(ctx.app()?)?)
+    });
+
+    // App state
+    insert_typed(commands, "get_settings", "Current settings", None, |_, _: NoArgs| async { to_value(settings::get()) });
+    insert_typed(commands, "get_database_status", "Database ready / schema version / failed migration", None, |_, _: NoArgs| async {
+        to_value(database::blocking(|| Ok(database::status())).await?)
+    });
+    insert_typed(commands, "reset_database", "Move a database that failed to migrate aside, start empty", None, |ctx, _: NoArgs| async move {
+        to_value(database::blocking(move || database::reset(ctx.app()?)).await?)
+    });
+    insert_typed(commands, "backup_database", "Copy the database to destPath", None, |ctx, args: BackupDatabaseArgs| async move {
+        to_value(database::blocking(move || database::backup(ctx.app()?, std::path::Path::new(&args.dest_path))).await?)
+    });
+    insert_typed(commands, "restore_database", "Replace the database with the backup at srcPath", None, |ctx, args: RestoreDatabaseArgs| async move {
+        to_value(database::blocking(move || database::restore(ctx.app()?, std::path::Path::new(&args.src_path))).await?)
+    });
+    insert_typed(commands, "get_feature_flags", "Features unlocked by the license", None, |_, _: NoArgs| async {
+        to_value(features::current())
+    });
+    insert_typed(commands, "ws_status", "WebSocket listener and open connections", None, |_, _: NoArgs| async {
+        to_value(websocket::status())
+    });
+    insert_typed(commands, "list_ws_clients", "Open WS connections (peer, role, session, traffic)", None, |_, _: NoArgs| async {
+        to_value(websocket::status().connections)
+    });
+    insert_typed(commands, "get_server_endpoints", "Open listeners (ws / wss, local socket, http) with their URLs", None, |_, _: NoArgs| async {
+        to_value(discovery::endpoints())
+    });
+    insert_typed(commands, "get_tls_info", "wss:// certificate fingerprint, to pin it", None, |ctx, _: NoArgs| async move {
+        to_value(tls::info(ctx.app()?)?)
+    });
+    insert_typed(commands, "get_recent_logs", "Last n log records", None, |_, args: RecentLogsArgs| async move {
+        to_value(logging::get_recent_logs(args.n))
+    });
+    insert_typed(commands, "get_metrics", "Request counters and latency histograms", None, |ctx, _: NoArgs| async move {
+        to_value(metrics::snapshot(ctx.app()?))
+    });
+    insert_typed(commands, "list_crash_reports", "Crash reports on disk, newest first", None, |_, _: NoArgs| async { to_value(crash::list()?) });
+    insert_typed(commands, "submit_crash_report", "Upload a crash report to the cloud server", None, |_, args: CrashReportArgs| async move {
+        to_value(crash::submit(&args.id).await?)
+    });
+    insert_typed(commands, "open_monitor_window", "Open the desktop monitor window (logs, WS, deepface, jobs)", None, |ctx, _: NoArgs| async move {
+        to_value(monitor::open(ctx.app()?)?)
+    });
+    insert_typed(commands, "sync_now", "Pull and push projects / clips / markers now", Some(features::CLOUD_SYNC), |ctx, _: NoArgs| async move {
+        to_value(sync::run(ctx.app()?).await?)
+    });
+    insert_typed(commands, "get_sync_status", "Cloud sync cursor, pending changes, last run", None, |_, _: NoArgs| async {
+        to_value(sync::status().await?)
+    });
+
+    // Macros
+    insert_typed(commands, "run_macro", "Run a saved macro { name, vars? } or inline { steps, vars? }, step outcomes streamed", None, |ctx, args: RunMacroArgs| async move {
+        to_value(macros::run_named_or_inline(&ctx, args.name, args.steps, args.vars).await?)
+    });
+    insert_typed(commands, "save_macro", "Save (or replace) the macro { name, description?, steps }", None, |_, args: SaveMacroArgs| async move {
+        to_value(macros::save(args.name, args.description, args.steps).await?)
+    });
+    insert_typed(commands, "list_macros", "Saved macros with their steps", None, |_, _: NoArgs| async {
+        to_value(database::blocking(database::list_macros).await?)
+    });
+    insert_typed(commands, "delete_macro", "Delete the macro { name }", None, |_, args: MacroNameArgs| async move {
+        to_value(database::blocking(move || database::delete_macro(&args.name)).await?)
+    });
+
+    // Plugins (their own commands are added by plugins::load_all)
+    insert_typed(commands, "list_plugins", "Plugin libraries found at startup, their commands or why they weren't loaded", None, |_, _: NoArgs| async {
+        to_value(plugins::list())
+    });
+}
+
+
+//_____________Commands ________________________
+
+/// Run a registered command from the webview (same names/payloads as over WS).
+/// Streamed chunks are returned as an array when the command's own result is empty.
+#[tauri::command]
+pub async fn run_command(app_handle: AppHandle, name: String, payload: Option<Value>) -> Result<Value, AppError> {
+    dispatch(&name, payload.unwrap_or_else(|| json!({})), CommandContext::new(app_handle)).await
+}
+
+/// Same as `run_command`, delivering partial results on `onChunk` as they are produced.
+/// Example: `invoke("stream_command", { name: "list_markers", payload: { clipId: 1, chunkSize: 100 }, onChunk: channel })`
+#[tauri::command]
+pub async fn stream_command(
+    app_handle: AppHandle,
+    name: String,
+    payload: Option<Value>,
+    on_chunk: Channel<Value>,
+) -> Result<Value, AppError> {
+    let sink: ChunkSink = Arc::new(move |chunk| {
+        let _ = on_chunk.send(chunk);
+    });
+    dispatch(&name, payload.unwrap_or_else(|| json!({})), CommandContext::new(app_handle).with_sink(sink)).await
+}
+
+#[tauri::command]
+pub fn list_commands() -> Vec<CommandInfo> {
+    list()
+}
@@ -0,0 +1,192 @@
+// src/secrets.rs
+//
+// Secrets of the app (license key, cloud credentials, database key) kept out of settings.json and the database.
+// - `set_secret` / `get_secret` / `delete_secret` by name, stored in the OS keychain (Keychain, Credential
+//   Manager, kernel keyring) under the KEYRING_SERVICE service
+// - platforms without a usable keychain fall back to secrets.json in the app data dir, every value sealed with
+//   AES-256-GCM under a key derived from the machine id (so the file is useless on another machine)
+// - a secret is looked up in the keychain first, then in the file
+// - `install_key(name)`: a random key made on first use and kept as a secret, for HMACs of local records
+//   (license cache, trial) that must not be forgeable with anything found in the source
+// - `seal_with` / `open_with`: the AES-256-GCM sealing of the fallback file, for other keys (encryption.rs)
+// Not exposed as commands: only Rust code reads secrets.
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use once_cell::sync::{Lazy, OnceCell};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tracing::{debug, warn};
+
+use crate::error::AppError;
+use crate::license;
+
+
+//____________Const___________
+pub const LICENSE_KEY: &str = "license-key";
+pub const WS_ACCESS_TOKENS: &str = "ws-access-tokens"; // ws_auth.rs: JSON list of token hashes and roles
+pub const LICENSE_CACHE_KEY: &str = "license-cache-key"; // license.rs: install_key signing the offline-grace cache
+pub const TRIAL_KEY: &str = "trial-key";                 // trial.rs: install_key signing trial.json
+pub const TRIAL_STARTED: &str = "trial-started";         // trial.rs: unix start of the trial, outlives trial.json
+pub const DATABASE_KEY: &str = "database-key";           // encryption.rs: AES key of the sensitive fields (base64)
+const KEYRING_SERVICE: &str = "tauri-app";
+const FALLBACK_FILE: &str = "secrets.json";
+const FALLBACK_KEY_CONTEXT: &str = "tauri-app/secrets/v1";
+const NONCE_LEN: usize = 12; // AES-GCM standard nonce
+const INSTALL_KEY_LEN: usize = 32;
+
+
+//_____________Globals _______________________
+static FALLBACK_PATH: OnceCell<PathBuf> = OnceCell::new();
+static FALLBACK_LOCK: Mutex<()> = Mutex::new(());
+static INSTALL_KEYS: Lazy<Mutex<BTreeMap<String, Vec<u8>>>> = Lazy::new(|| Mutex::new(BTreeMap::new())); // read once per run
+
+
+//_____________fn ____________________________
+
+/// Remember where the fallback file goes. Call once from setup, right after settings::init: before anything reads a secret.
+pub fn init(app_handle: &AppHandle) {
+    match app_handle.path().app_data_dir() {
+        Ok(dir) => {
+            let _ = FALLBACK_PATH.set(dir.join(FALLBACK_FILE));
+        }
+        Err(e) => warn!("❌ No app data dir, secrets need the OS keychain: {}", e),
+    }
+}
+
+fn entry(name: &str) -> Result<keyring::Entry, keyring::Error> {
+    keyring::Entry::new(KEYRING_SERVICE, name)
+}
+
+/// Store `value` under `name`, replacing the previous one.
+pub fn set_secret(name: &str, value: &str) -> Result<(), AppError> {
+    match entry(name).and_then(|e| e.set_password(value)) {
+        Ok(()) => {
+            // Drop a copy left in the file by an earlier keychain outage
+            let _ = update_fallback(|secrets| secrets.remove(name).map(|_| ()));
+            Ok(())
+        }
+        Err(e) => {
+            debug!("OS keychain unavailable for {} ({}), using {}", name, e, FALLBACK_FILE);
+            let sealed = seal(value)?;
+            update_fallback(|secrets| {
+                secrets.insert(name.to_string(), sealed);
+                Some(())
+            })
+        }
+    }
+}
+
+/// The secret stored under `name`, None if there is none.
+pub fn get_secret(name: &str) -> Result<Option<String>, AppError> {
+    match entry(name).and_then(|e| e.get_password()) {
+        Ok(value) => return Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => {}
+        Err(e) => debug!("OS keychain unavailable for {} ({}), reading {}", name, e, FALLBACK_FILE),
+    }
+    let _guard = FALLBACK_LOCK.lock().unwrap_or_else(|p| p.into_inner());
+    match read_fallback()?.get(name) {
+        Some(sealed) => open(sealed).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Forget the secret stored under `name` (no error if there was none).
+pub fn delete_secret(name: &str) -> Result<(), AppError> {
+    match entry(name).and_then(|e| e.delete_credential()) {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => debug!("OS keychain unavailable for {} ({})", name, e),
+    }
+    if FALLBACK_PATH.get().is_none() {
+        return Ok(());
+    }
+    update_fallback(|secrets| secrets.remove(name).map(|_| ()))
+}
+
+/// The random key of this install stored under `name`, created on first use.
+pub fn install_key(name: &str) -> Result<Vec<u8>, AppError> {
+    let mut keys = INSTALL_KEYS.lock().unwrap_or_else(|p| p.into_inner());
+    if let Some(key) = keys.get(name) {
+        return Ok(key.clone());
+    }
+    let stored = get_secret(name)?.and_then(|hex| hex::decode(hex.trim()).ok()).filter(|key| key.len() == INSTALL_KEY_LEN);
+    let key = match stored {
+        Some(key) => key,
+        None => {
+            let key = rand::random::<[u8; INSTALL_KEY_LEN]>().to_vec();
+            set_secret(name, &hex::encode(&key))?;
+            key
+        }
+    };
+    keys.insert(name.to_string(), key.clone());
+    Ok(key)
+}
+
+
+//_____________Fallback file ___________________
+
+fn fallback_path() -> Result<&'static PathBuf, AppError> {
+    FALLBACK_PATH.get().ok_or_else(|| AppError::Io("No OS keychain and no app data dir to keep secrets in".into()))
+}
+
+fn read_fallback() -> Result<BTreeMap<String, String>, AppError> {
+    let path = fallback_path()?;
+    match std::fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| AppError::Io(format!("Unreadable {:?}: {}", path, e))),
+        Err(_) => Ok(BTreeMap::new()),
+    }
+}
+
+// Apply `change` to the file's secrets; the file is only written when `change` returns Some
+fn update_fallback(change: impl FnOnce(&mut BTreeMap<String, String>) -> Option<()>) -> Result<(), AppError> {
+    let _guard = FALLBACK_LOCK.lock().unwrap_or_else(|p| p.into_inner());
+    let mut secrets = read_fallback()?;
+    if change(&mut secrets).is_none() {
+        return Ok(());
+    }
+    let path = fallback_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| AppError::Io(format!("Failed to create {:?}: {}", dir, e)))?;
+    }
+    let bytes = serde_json::to_vec_pretty(&secrets).map_err(|e| AppError::Io(format!("Failed to serialize secrets: {}", e)))?;
+    std::fs::write(path, bytes).map_err(|e| AppError::Io(format!("Failed to write {:?}: {}", path, e)))
+}
+
+fn cipher() -> Aes256Gcm {
+    let key = Sha256::digest(format!("{}|{}", FALLBACK_KEY_CONTEXT, license::machine_id()).as_bytes());
+    Aes256Gcm::new_from_slice(&key).expect("sha256 is a valid AES-256 key")
+}
+
+/// `plain` sealed with `cipher`: base64 of a random nonce followed by the ciphertext. None if encryption failed.
+pub fn seal_with(cipher: &Aes256Gcm, plain: &[u8]) -> Option<String> {
+    let nonce = rand::random::<[u8; NONCE_LEN]>();
+    let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce), plain).ok()?;
+    let mut bytes = nonce.to_vec();
+    bytes.extend(ciphertext);
+    Some(STANDARD.encode(bytes))
+}
+
+/// What `seal_with` sealed. None if `sealed` isn't such a value or another key sealed it.
+pub fn open_with(cipher: &Aes256Gcm, sealed: &str) -> Option<Vec<u8>> {
+    let bytes = STANDARD.decode(sealed).ok()?;
+    if bytes.len() < NONCE_LEN {
+        return None;
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    cipher.decrypt(Nonce::from_slice(nonce), ciphertext).ok()
+}
+
+fn seal(plain: &str) -> Result<String, AppError> {
+    seal_with(&cipher(), plain.as_bytes()).ok_or_else(|| AppError::Io("Failed to encrypt a secret".into()))
+}
+
+fn open(sealed: &str) -> Result<String, AppError> {
+    open_with(&cipher(), sealed)
+        .and_then(|plain| String::from_utf8(plain).ok())
+        .ok_or_else(|| AppError::Io(format!("Can't decrypt a secret of {} (copied from another machine?)", FALLBACK_FILE)))
+}
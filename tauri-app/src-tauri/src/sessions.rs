@@ -0,0 +1,115 @@
+// src/sessions.rs
+//
+// Resumable WebSocket sessions, so a CEP panel reloaded by Premiere picks up where it left off.
+// - The hello frame of a v5 connection carries `sessionToken`; reconnect with
+//   `ws://host:port/?protocol=5&session=<token>` to resume it (`resumed: true` in the hello)
+// - Replies of requests that complete while no connection holds the session are buffered
+//   (last MAX_BUFFERED) and sent right after the next hello
+// - Event subscriptions (events.rs) are saved on disconnect and restored on resume
+// - Sessions nobody resumes within settings.ws sessionResumeSecs are dropped
+// - Elsewhere a session shows as its `public_id` (a hash prefix), never as the token
+
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+use crate::{events, settings};
+
+
+//____________Const___________
+pub const MAX_BUFFERED: usize = 100; // replies kept per detached session, oldest dropped first
+
+
+//_____________Struct _________________________
+struct Session {
+    connection: Option<u64>, // None = detached, waiting to be resumed
+    detached_at: Instant,
+    subscriptions: Vec<String>,
+    buffered: VecDeque<String>, // serialized reply frames
+}
+
+/// Outcome of `open`: what the hello frame reports and what to send right after it.
+pub struct Opened {
+    pub token: String,
+    pub resumed: bool,
+    pub subscriptions: Vec<&'static str>,
+    pub buffered: Vec<String>,
+}
+
+
+//_____________Globals _______________________
+static SESSIONS: Lazy<Mutex<HashMap<String, Session>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+
+//_____________fn ____________________________
+fn sessions() -> MutexGuard<'static, HashMap<String, Session>> {
+    SESSIONS.lock().unwrap_or_else(|p| p.into_inner())
+}
+
+fn new_token() -> String {
+    hex::encode(rand::random::<[u8; 16]>())
+}
+
+/// Id of the session of `token` that can be shown (ws_status, cep-connection events): the token itself resumes it.
+pub fn public_id(token: &str) -> String {
+    hex::encode(&Sha256::digest(token.as_bytes())[..6])
+}
+
+/// Resume `requested` for `connection` when it is still known, else start a new session.
+/// A session still held by another connection is taken over (the old panel is usually dead without a Close).
+pub fn open(requested: Option<&str>, connection: u64) -> Opened {
+    let resume_for = Duration::from_secs(settings::get().ws.session_resume_secs);
+    let mut sessions = sessions();
+    sessions.retain(|_, s| s.connection.is_some() || s.detached_at.elapsed() < resume_for);
+
+    if let Some((token, session)) = requested.and_then(|t| sessions.get_mut(t).map(|s| (t.to_string(), s))) {
+        session.connection = Some(connection);
+        let buffered: Vec<String> = session.buffered.drain(..).collect();
+        // Unknown names can't be in here: they were accepted by events::subscribe before
+        let subscriptions = events::subscribe(connection, &session.subscriptions).unwrap_or_default();
+        info!("🔁 Connection {} resumed session ({} buffered replies)", connection, buffered.len());
+        return Opened { token, resumed: true, subscriptions, buffered };
+    }
+
+    let token = new_token();
+    sessions.insert(
+        token.clone(),
+        Session { connection: Some(connection), detached_at: Instant::now(), subscriptions: Vec::new(), buffered: VecDeque::new() },
+    );
+    Opened { token, resumed: false, subscriptions: Vec::new(), buffered: Vec::new() }
+}
+
+/// `connection` closed: keep its subscriptions until the session is resumed or expires.
+pub fn detach(token: &str, connection: u64, subscriptions: Vec<String>) {
+    if let Some(session) = sessions().get_mut(token) {
+        // Already taken over by a newer connection: that one owns the state now
+        if session.connection == Some(connection) {
+            session.connection = None;
+            session.detached_at = Instant::now();
+            session.subscriptions = subscriptions;
+        }
+    }
+}
+
+/// A reply `connection` couldn't deliver: hand it to the connection that resumed the session, or buffer it.
+pub fn buffer(token: &str, connection: u64, frame: String) {
+    let mut sessions = sessions();
+    let Some(session) = sessions.get_mut(token) else {
+        return;
+    };
+    let frame = match session.connection {
+        Some(other) if other != connection => match events::push(other, frame) {
+            Ok(()) => return,
+            Err(frame) => frame,
+        },
+        _ => frame,
+    };
+    if session.buffered.len() >= MAX_BUFFERED {
+        session.buffered.pop_front();
+    }
+    session.buffered.push_back(frame);
+    debug!("Buffered a reply for session of connection {} ({} waiting)", connection, session.buffered.len());
+}
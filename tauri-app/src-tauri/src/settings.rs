@@ -0,0 +1,623 @@
+// src/settings.rs
+//
+// Application settings, persisted as JSON in the app config dir (settings.json).
+// - `init` loads them once at startup (before the WS server / license checker start)
+// - `get` returns a copy for any module that needs a value
+// - `update_settings(patch)` merges a partial JSON object, saves, emits `settings-changed`
+//   and re-applies the changed sections to the running subsystems.
+// - `watch` gives a receiver that sees every new version, for loops that adapt on their own
+//   (log levels of settings.debug, the license check interval, database field encryption)
+
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tauri::{AppHandle, Manager};
+use tokio::sync::watch;
+use tracing::{error, warn};
+
+use crate::deepFaceProcess::StartupPolicy;
+use crate::error::AppError;
+use crate::ws_queue::{self, EventOverflow};
+use crate::{cache, camera, deepFaceProcess, deepface_resources, discovery, events, http_api, i18n, license, media, metrics, models, notify, plugins, sync, tray, updater, watcher, websocket, whisper, ws_audit};
+
+
+//____________Const___________
+const SETTINGS_FILE: &str = "settings.json";
+const LEGACY_LICENSE_FILE: &str = "license.json"; // pre-settings license config, imported once
+pub const SETTINGS_EVENT: &str = "settings-changed";
+
+
+//_____________Struct _________________________
+
+/// Everything the user (or support) can change without a rebuild.
+/// Missing fields in settings.json fall back to their defaults, so old files keep loading.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppSettings {
+    pub ws: WsSettings,
+    pub license: LicenseSettings,
+    pub deepface: DeepFaceSettings,
+    pub models: ModelsSettings,
+    pub media: MediaSettings,
+    pub camera: CameraSettings,
+    pub update: UpdateSettings,
+    pub notifications: NotificationSettings,
+    pub telemetry: TelemetrySettings,
+    pub database: DatabaseSettings,
+    pub sync: SyncSettings,
+    pub onboarding: OnboardingSettings,
+    pub watch: WatchSettings,
+    pub plugins: PluginSettings,
+    pub whisper: WhisperSettings,
+    pub cache: CacheSettings,
+    pub locale: String, // language of backend messages (i18n.rs), "" = i18n::DEFAULT_LOCALE
+    pub debug: DebugSettings,
+}
+
+/// Embedded WebSocket server used by the CEP panel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WsSettings {
+    pub host: String,
+    pub port: u16,
+    pub max_connections: usize,
+    pub pro_max_connections: usize, // limit with the ws_priority feature (pro tier)
+    pub admission_wait_secs: u64,   // how long a pro client over the limit waits for a slot
+    pub ping_interval_secs: u64,
+    pub idle_timeout_secs: u64,   // no frame (pong included) for this long -> connection closed
+    pub max_message_bytes: usize, // larger messages are refused before parsing
+    pub rate_limit_per_sec: u32,  // sustained requests per connection, 0 = unlimited
+    pub rate_limit_burst: u32,    // requests allowed in a burst above the sustained rate
+    pub session_resume_secs: u64, // how long a closed connection's session can be resumed
+    pub tls: bool,                // wss:// with the self-signed certificate of tls.rs
+    pub allowed_origins: Vec<String>, // Origin headers accepted at the handshake (clients without one always are)
+    pub allowed_hosts: Vec<String>,   // Host names accepted besides localhost and IP addresses
+    pub path: String,                 // e.g. "/cep": only this path is upgraded, "" = any
+    pub local_socket: String,         // Unix socket path / Windows pipe name served next to host:port, "" = off
+    pub http_port: u16,               // REST fallback on host:httpPort (http_api.rs), 0 = off
+    pub mdns: bool,                   // advertise the listener as _faceapp._tcp (discovery.rs)
+    pub send_queue_frames: usize,     // frames queued per connection before events are dropped / the client is closed
+    pub event_overflow: EventOverflow, // which pushed event goes when that queue is full (ws_queue.rs)
+    pub compression: bool,            // deflate large frames for v6 clients that ask (`?compression=deflate`)
+    pub compress_threshold_bytes: usize, // smaller frames are sent as they are
+}
+
+impl Default for WsSettings {
+    fn default() -> Self {
+        WsSettings {
+            host: websocket::WS_HOST.to_string(),
+            port: websocket::WS_PORT,
+            max_connections: websocket::MAX_CONNECTIONS,
+            pro_max_connections: websocket::PRO_MAX_CONNECTIONS,
+            admission_wait_secs: websocket::ADMISSION_WAIT,
+            ping_interval_secs: websocket::PING_INTERVAL,
+            idle_timeout_secs: websocket::IDLE_TIMEOUT,
+            max_message_bytes: websocket::MAX_MESSAGE_BYTES,
+            rate_limit_per_sec: websocket::RATE_LIMIT_PER_SEC,
+            rate_limit_burst: websocket::RATE_LIMIT_BURST,
+            session_resume_secs: websocket::SESSION_RESUME,
+            tls: false,
+            allowed_origins: websocket::ALLOWED_ORIGINS.iter().map(|o| o.to_string()).collect(),
+            allowed_hosts: Vec::new(),
+            path: String::new(),
+            local_socket: String::new(),
+            http_port: http_api::HTTP_PORT,
+            mdns: discovery::MDNS,
+            send_queue_frames: ws_queue::SEND_QUEUE_FRAMES,
+            event_overflow: EventOverflow::default(),
+            compression: true,
+            compress_threshold_bytes: ws_queue::COMPRESS_THRESHOLD,
+        }
+    }
+}
+
+/// License server connection and check schedule.
+/// When `proxy` is not set, the usual HTTPS_PROXY / HTTP_PROXY / NO_PROXY env vars apply.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LicenseSettings {
+    pub server_url: String,
+    pub proxy: Option<String>,
+    pub ca_cert_path: Option<PathBuf>, // extra root certificate (PEM), e.g. a corporate TLS-inspecting proxy
+    pub check_interval_secs: u64,
+    pub grace_period_hours: u64,
+}
+
+impl Default for LicenseSettings {
+    fn default() -> Self {
+        LicenseSettings {
+            server_url: license::CLOUD_ADDRESS.to_string(),
+            proxy: None,
+            ca_cert_path: None,
+            check_interval_secs: license::SLEEP_INTERVAL,
+            grace_period_hours: license::GRACE_PERIOD_HOURS,
+        }
+    }
+}
+
+/// deepface_cli sidecar.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DeepFaceSettings {
+    pub port: u16,
+    pub default_detector: Option<String>, // used when a command doesn't pass one
+    pub default_model: Option<String>,
+    pub startup: StartupPolicy,   // eager | lazy | manual
+    pub instances: usize,         // sidecar processes for batch throughput, capped by free RAM
+    pub startup_timeout_secs: u64,
+    pub idle_shutdown_mins: u64,  // stop the sidecar after this long without requests, 0 = never
+    pub retry_attempts: u32,      // resends of an idempotent request after a WS failure
+    pub retry_backoff_ms: u64,    // delay before the first resend, doubled for each next one
+    pub memory_warning_mb: u64,   // resident memory that logs a warning (deepface_resources.rs)
+    pub memory_limit_mb: u64,     // resident memory that restarts the sidecar, 0 = never
+    pub preprocess_frames: bool,  // resize / re-encode in-memory frames before sending them
+    pub max_frame_dimension: u32, // longest side after preprocessing, px
+    pub frame_jpeg_quality: u8,   // 1 (worst) .. 100
+}
+
+impl Default for DeepFaceSettings {
+    fn default() -> Self {
+        DeepFaceSettings {
+            port: deepFaceProcess::DEFAULT_PORT,
+            default_detector: None,
+            default_model: None,
+            startup: StartupPolicy::default(),
+            instances: 1,
+            startup_timeout_secs: deepFaceProcess::STARTUP_TIMEOUT,
+            idle_shutdown_mins: deepFaceProcess::IDLE_SHUTDOWN_MINS,
+            retry_attempts: deepFaceProcess::RETRY_ATTEMPTS,
+            retry_backoff_ms: deepFaceProcess::RETRY_BACKOFF_MS,
+            memory_warning_mb: deepface_resources::MEMORY_WARNING_MB,
+            memory_limit_mb: 0,
+            preprocess_frames: true,
+            max_frame_dimension: deepFaceProcess::MAX_FRAME_DIMENSION,
+            frame_jpeg_quality: deepFaceProcess::FRAME_JPEG_QUALITY,
+        }
+    }
+}
+
+/// Deepface model weights (models.rs), downloaded before the sidecar needs them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ModelsSettings {
+    pub auto_download: bool,                 // fetch missing weights before a request needs them
+    pub base_url: String,                    // where the weights files are served (mirror)
+    pub dir: Option<PathBuf>,                // DEEPFACE_HOME, None = <app data>/deepface (next sidecar start)
+    pub checksums: BTreeMap<String, String>, // weights file -> expected sha256 (hex)
+}
+
+impl Default for ModelsSettings {
+    fn default() -> Self {
+        ModelsSettings {
+            auto_download: true,
+            base_url: models::MODELS_URL.to_string(),
+            dir: None,
+            checksums: BTreeMap::new(),
+        }
+    }
+}
+
+/// ffmpeg frame extraction (media.rs).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MediaSettings {
+    pub ffmpeg_path: Option<PathBuf>, // None = bundled binary, then PATH
+    pub jpeg_quality: u8,             // ffmpeg -q:v, 2 (best) .. 31
+    pub analyze_on_drop: bool,        // clips dropped on the window also get an analyze_clip job (ingest.rs)
+}
+
+impl Default for MediaSettings {
+    fn default() -> Self {
+        MediaSettings { ffmpeg_path: None, jpeg_quality: media::JPEG_QUALITY, analyze_on_drop: false }
+    }
+}
+
+/// Live webcam emotion preview (camera.rs).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CameraSettings {
+    pub device: Option<String>, // id from list_cameras; None = the first camera found
+    pub fps: f64,               // frames analyzed per second
+    pub max_width: u32,         // frames are scaled down to this width before analysis
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        CameraSettings { device: None, fps: camera::CAMERA_FPS, max_width: camera::CAMERA_MAX_WIDTH }
+    }
+}
+
+/// Self-update (updater.rs).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UpdateSettings {
+    pub channel: updater::UpdateChannel, // "stable" | "beta"
+    pub endpoint: String,                // latest.json URL, `{channel}` is replaced
+    pub check_on_startup: bool,
+}
+
+impl Default for UpdateSettings {
+    fn default() -> Self {
+        UpdateSettings {
+            channel: updater::UpdateChannel::Stable,
+            endpoint: updater::UPDATE_ENDPOINT.to_string(),
+            check_on_startup: true,
+        }
+    }
+}
+
+/// First-run wizard progress (onboarding.rs).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OnboardingSettings {
+    pub completed_steps: Vec<String>,
+    pub skipped_steps: Vec<String>, // optional steps the user chose to skip
+}
+
+/// Folders and marker files watched for changes (watcher.rs).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WatchSettings {
+    pub paths: Vec<watcher::WatchedPath>,
+}
+
+/// Third-party command libraries (plugins.rs), loaded at startup: changes apply at the next launch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PluginSettings {
+    pub enabled: bool,
+    pub dir: Option<PathBuf>,  // None = <app data>/plugins
+    pub disabled: Vec<String>, // plugin files (with or without extension) not to load
+}
+
+impl Default for PluginSettings {
+    fn default() -> Self {
+        PluginSettings { enabled: plugins::PLUGINS, dir: None, disabled: Vec::new() }
+    }
+}
+
+/// Speech-to-text with the whisper.cpp sidecar (whisper.rs).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WhisperSettings {
+    pub model: String,              // ggml model name, e.g. "base", "small", "medium.en"
+    pub model_url: String,          // where ggml-<model>.bin files are served (mirror)
+    pub model_dir: Option<PathBuf>, // None = <app data>/whisper
+    pub language: String,           // when transcribe_clip gets none, "auto" = detected
+    pub threads: u32,               // 0 = whisper's default
+}
+
+impl Default for WhisperSettings {
+    fn default() -> Self {
+        WhisperSettings {
+            model: whisper::DEFAULT_MODEL.to_string(),
+            model_url: whisper::MODELS_URL.to_string(),
+            model_dir: None,
+            language: whisper::AUTO_LANGUAGE.to_string(),
+            threads: 0,
+        }
+    }
+}
+
+/// Size limits of the disk caches (cache.rs), in MB; 0 = no limit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CacheSettings {
+    pub thumbnails_mb: u64,
+    pub frames_mb: u64,
+    pub models_mb: u64, // evicted models are downloaded again when needed
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        CacheSettings { thumbnails_mb: cache::THUMBNAILS_LIMIT_MB, frames_mb: cache::FRAMES_LIMIT_MB, models_mb: cache::MODELS_LIMIT_MB }
+    }
+}
+
+/// Native notifications (notify.rs).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    pub expiry_warning_days: u32, // notify the license::EXPIRY_NOTICE_DAYS steps within this many days
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        NotificationSettings { enabled: true, expiry_warning_days: notify::EXPIRY_WARNING_DAYS }
+    }
+}
+
+/// Anonymous metrics upload (metrics.rs), opt-in.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TelemetrySettings {
+    pub enabled: bool,
+    pub upload_interval_secs: u64,
+}
+
+impl Default for TelemetrySettings {
+    fn default() -> Self {
+        TelemetrySettings { enabled: false, upload_interval_secs: metrics::UPLOAD_INTERVAL }
+    }
+}
+
+/// Optional encryption of sensitive fields (encryption.rs), applied to existing rows when changed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DatabaseSettings {
+    pub encrypt_sensitive: bool, // marker metadata (emotion scores), job results, undo snapshots
+}
+
+/// Cloud sync of projects / clips / markers (sync.rs), opt-in.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SyncSettings {
+    pub enabled: bool,
+    pub interval_secs: u64,
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        SyncSettings { enabled: false, interval_secs: sync::SYNC_INTERVAL }
+    }
+}
+
+/// Verbose (debug level) logging per module, applied live by logging.rs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DebugSettings {
+    pub websocket: bool,
+    pub license: bool,
+    pub deepface: bool,
+    pub ws_audit: bool, // record WS requests for get_ws_audit / replay_ws_request (ws_audit.rs)
+}
+
+impl Default for DebugSettings {
+    fn default() -> Self {
+        DebugSettings {
+            websocket: websocket::DEBUG_WS,
+            license: license::DEBUG_LICENSE,
+            deepface: deepFaceProcess::DEBUG_DEEPFACE,
+            ws_audit: ws_audit::WS_AUDIT,
+        }
+    }
+}
+
+
+//_____________Globals _______________________
+static SETTINGS: Lazy<RwLock<AppSettings>> = Lazy::new(|| RwLock::new(AppSettings::default()));
+static SETTINGS_PATH: OnceCell<PathBuf> = OnceCell::new();
+static WATCH: Lazy<watch::Sender<AppSettings>> = Lazy::new(|| watch::channel(AppSettings::default()).0);
+
+
+//_____________fn ____________________________
+
+/// Load settings.json (or defaults). Call once from setup, before starting the subsystems.
+pub fn init(app_handle: &AppHandle) {
+    let Ok(dir) = app_handle.path().app_config_dir() else {
+        error!("❌ No app config dir, settings will not be saved");
+        return;
+    };
+    let path = dir.join(SETTINGS_FILE);
+    let _ = SETTINGS_PATH.set(path.clone());
+
+    let settings = match std::fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            error!("❌ Invalid {:?}, using defaults: {}", path, e);
+            AppSettings::default()
+        }),
+        Err(_) => {
+            let settings = import_legacy(&dir);
+            if let Err(e) = save(&settings) {
+                error!("❌ {}", e);
+            }
+            settings
+        }
+    };
+
+    if let Ok(mut current) = SETTINGS.write() {
+        *current = settings.clone();
+    }
+    WATCH.send_replace(settings);
+}
+
+// First run with settings: keep the license server config users already put in license.json
+fn import_legacy(dir: &std::path::Path) -> AppSettings {
+    let mut settings = AppSettings::default();
+    if let Ok(bytes) = std::fs::read(dir.join(LEGACY_LICENSE_FILE)) {
+        match serde_json::from_slice::<LicenseSettings>(&bytes) {
+            Ok(license) => settings.license = license,
+            Err(e) => warn!("❌ Ignoring invalid {}: {}", LEGACY_LICENSE_FILE, e),
+        }
+    }
+    settings
+}
+
+/// Current settings (cheap copy).
+pub fn get() -> AppSettings {
+    SETTINGS.read().map(|s| s.clone()).unwrap_or_default()
+}
+
+/// Receiver notified of every settings change (saved and applied), starting from the current ones.
+pub fn watch() -> watch::Receiver<AppSettings> {
+    WATCH.subscribe()
+}
+
+fn save(settings: &AppSettings) -> Result<(), AppError> {
+    let Some(path) = SETTINGS_PATH.get() else { return Ok(()) };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| AppError::Io(format!("Failed to create {:?}: {}", dir, e)))?;
+    }
+    let bytes = serde_json::to_vec_pretty(settings).map_err(|e| AppError::Settings(format!("Failed to serialize settings: {}", e)))?;
+    std::fs::write(path, bytes).map_err(|e| AppError::Io(format!("Failed to write {:?}: {}", path, e)))
+}
+
+fn validate(settings: &AppSettings) -> Result<(), AppError> {
+    let invalid = |msg: &str| Err(AppError::Settings(msg.to_string()));
+    // ws port 0 = any free port, found with get_server_endpoints or mDNS (discovery.rs)
+    if settings.deepface.port == 0 {
+        return invalid("Ports must be between 1 and 65535");
+    }
+    if settings.ws.port == settings.deepface.port {
+        return invalid("WebSocket and DeepFace ports must differ");
+    }
+    if settings.ws.http_port != 0 && (settings.ws.http_port == settings.ws.port || settings.ws.http_port == settings.deepface.port) {
+        return invalid("httpPort must differ from the WebSocket and DeepFace ports");
+    }
+    if settings.ws.max_connections == 0 {
+        return invalid("maxConnections must be at least 1");
+    }
+    if settings.ws.pro_max_connections < settings.ws.max_connections {
+        return invalid("proMaxConnections must be at least maxConnections");
+    }
+    if settings.ws.admission_wait_secs > 300 {
+        return invalid("admissionWaitSecs must be at most 300");
+    }
+    if settings.ws.ping_interval_secs == 0 || settings.ws.idle_timeout_secs <= settings.ws.ping_interval_secs {
+        return invalid("idleTimeoutSecs must be greater than pingIntervalSecs (>= 1)");
+    }
+    if settings.ws.send_queue_frames < 8 {
+        return invalid("sendQueueFrames must be at least 8");
+    }
+    if settings.ws.max_message_bytes < 1024 {
+        return invalid("maxMessageBytes must be at least 1024");
+    }
+    if !settings.ws.path.is_empty() && !settings.ws.path.starts_with('/') {
+        return invalid("ws path must start with / (or be empty)");
+    }
+    if settings.ws.allowed_origins.iter().chain(&settings.ws.allowed_hosts).any(|v| v.trim().is_empty()) {
+        return invalid("allowedOrigins / allowedHosts can't contain empty entries");
+    }
+    if cfg!(windows) && !settings.ws.local_socket.is_empty() && !settings.ws.local_socket.starts_with(r"\\.\pipe\") {
+        return invalid(r"ws localSocket must be a pipe name (\\.\pipe\...) on Windows");
+    }
+    if settings.license.check_interval_secs < 5 {
+        return invalid("checkIntervalSecs must be at least 5");
+    }
+    if !settings.license.server_url.starts_with("http://") && !settings.license.server_url.starts_with("https://") {
+        return invalid("serverUrl must start with http:// or https://");
+    }
+    if settings.deepface.retry_attempts > 10 {
+        return invalid("retryAttempts must be at most 10");
+    }
+    if !(1..=deepFaceProcess::MAX_INSTANCES).contains(&settings.deepface.instances) {
+        return invalid("deepface instances must be between 1 and 8");
+    }
+    if settings.deepface.memory_limit_mb > 0 && settings.deepface.memory_limit_mb <= settings.deepface.memory_warning_mb {
+        return invalid("memoryLimitMb must be above memoryWarningMb (or 0)");
+    }
+    if settings.deepface.max_frame_dimension < 64 {
+        return invalid("maxFrameDimension must be at least 64");
+    }
+    if !(1..=100).contains(&settings.deepface.frame_jpeg_quality) {
+        return invalid("frameJpegQuality must be between 1 and 100");
+    }
+    if !settings.models.base_url.starts_with("https://") {
+        return invalid("models baseUrl must start with https://");
+    }
+    if settings.models.checksums.values().any(|sum| sum.len() != 64 || !sum.chars().all(|c| c.is_ascii_hexdigit())) {
+        return invalid("models checksums must be sha256 hex digests");
+    }
+    if !settings.whisper.model_url.starts_with("https://") {
+        return invalid("whisper modelUrl must start with https://");
+    }
+    if settings.whisper.model.is_empty() || !settings.whisper.model.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-') {
+        return invalid("whisper model must be a ggml model name, e.g. base or small.en");
+    }
+    if !(2..=31).contains(&settings.media.jpeg_quality) {
+        return invalid("jpegQuality must be between 2 and 31");
+    }
+    if settings.camera.fps <= 0.0 || settings.camera.fps > camera::MAX_CAMERA_FPS {
+        return invalid("camera fps must be above 0 and at most 10");
+    }
+    if settings.telemetry.upload_interval_secs < 60 {
+        return invalid("uploadIntervalSecs must be at least 60");
+    }
+    if settings.sync.interval_secs < 30 {
+        return invalid("sync intervalSecs must be at least 30");
+    }
+    if !settings.update.endpoint.starts_with("https://") {
+        return invalid("update endpoint must start with https://");
+    }
+    if !settings.locale.is_empty() && !i18n::available().contains(&settings.locale.as_str()) {
+        return invalid("locale must be one of the bundled catalogs (en, fr)");
+    }
+    Ok(())
+}
+
+// RFC 7386 JSON merge patch: objects merge recursively, `null` resets a field to its default
+fn merge_patch(target: &mut Value, patch: &Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                if value.is_null() {
+                    target.remove(key);
+                } else {
+                    merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+                }
+            }
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+// Push changed sections into the running subsystems
+fn apply(app_handle: &AppHandle, old: &AppSettings, new: &AppSettings) {
+    if old.ws != new.ws {
+        websocket::restart_websocket_server(app_handle.clone());
+        http_api::restart(app_handle.clone());
+    }
+    if old.license != new.license {
+        license::apply_settings(&new.license);
+    }
+    if old.deepface != new.deepface {
+        deepFaceProcess::MANAGER.apply_settings(&new.deepface);
+    }
+    if old.locale != new.locale {
+        tray::relabel();
+    }
+    if old.watch != new.watch {
+        watcher::apply_settings(&new.watch);
+    }
+    if old.cache != new.cache {
+        cache::changed();
+    }
+}
+
+
+//_____________Commands ________________________
+
+#[tauri::command]
+pub fn get_settings() -> AppSettings {
+    get()
+}
+
+/// Merge `patch` (partial settings object) into the current settings, save and apply them.
+/// Example: `invoke("update_settings", { patch: { ws: { port: 8081 } } })`
+#[tauri::command]
+pub fn update_settings(app_handle: AppHandle, patch: Value) -> Result<AppSettings, AppError> {
+    let old = get();
+
+    let mut merged = serde_json::to_value(&old).map_err(|e| AppError::Settings(e.to_string()))?;
+    merge_patch(&mut merged, &patch);
+    let new: AppSettings = serde_json::from_value(merged).map_err(|e| AppError::Settings(format!("Invalid settings: {}", e)))?;
+    validate(&new)?;
+
+    if new == old {
+        return Ok(new);
+    }
+
+    save(&new)?;
+    if let Ok(mut current) = SETTINGS.write() {
+        *current = new.clone();
+    }
+    apply(&app_handle, &old, &new);
+    WATCH.send_replace(new.clone());
+
+    events::emit_all_surfaces(&app_handle, SETTINGS_EVENT, &new);
+    Ok(new)
+}
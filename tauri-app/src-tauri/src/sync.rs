@@ -0,0 +1,300 @@
+// src/sync.rs
+//
+// Cloud sync of projects, clips and markers through the license server, so a team sharing one license
+// sees the same markers on every workstation (needs the cloud_sync feature).
+// - every synced row has a uuid, the server revision it was last synced at and a dirty flag kept by triggers
+//   (migration 4); deleted rows stay as tombstones until the deletion is pushed
+// - a run pulls first (POST <serverUrl>/sync/pull, records newer than the saved cursor, PULL_PAGE_SIZE at a time),
+//   then pushes the local changes (POST <serverUrl>/sync/push, each based on the revision it was read at)
+// - a record changed on both sides since the last sync is a conflict: last writer (updatedAt) wins, the server
+//   refuses a push based on an old revision, and every conflict is emitted as SYNC_CONFLICT_EVENT
+// - runs every settings.sync intervalSecs while enabled (off by default); `sync_now` runs one right away
+// - authenticates with the license key (license::current_key, kept by secrets.rs), never stored by sync itself
+
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::AppHandle;
+use tracing::{debug, info, warn};
+
+use crate::database::{self, SyncConflict, SyncRecord};
+use crate::error::AppError;
+use crate::{crash, events, features, license, settings};
+
+
+//____________Const___________
+pub const SYNC_INTERVAL: u64 = 300; // default seconds between runs, see settings.sync intervalSecs
+pub const SYNC_EVENT: &str = "sync-completed";         // SyncReport after every run
+pub const SYNC_CONFLICT_EVENT: &str = "sync-conflict"; // database::SyncConflict
+const PULL_PAGE_SIZE: usize = 500;
+const PUSH_BATCH_SIZE: usize = 200;
+const PUSH_ROUNDS: usize = 2; // a second push sends the local winners of conflicts found by the first
+
+
+//_____________Struct _________________________
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PullRequest {
+    key: String,
+    machine_id: String,
+    since: i64,
+    limit: usize,
+}
+
+#[derive(Deserialize)]
+struct PullResponse {
+    #[serde(default)]
+    records: Vec<SyncRecord>, // in revision order
+    cursor: i64,              // revision of the last record sent
+    #[serde(default)]
+    more: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PushRequest<'a> {
+    key: String,
+    machine_id: String,
+    records: &'a [SyncRecord],
+}
+
+#[derive(Deserialize)]
+struct PushResponse {
+    results: Vec<PushResult>, // one per pushed record, same order
+}
+
+#[derive(Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum PushStatus {
+    Ok,
+    Conflict,
+}
+
+#[derive(Deserialize)]
+struct PushResult {
+    status: PushStatus,
+    revision: Option<i64>,       // new revision when stored
+    current: Option<SyncRecord>, // the server's record on a conflict
+}
+
+// Every server answer: { success, code?, message?, ... }
+#[derive(Deserialize)]
+struct Envelope<T> {
+    success: bool,
+    #[serde(default)]
+    message: String,
+    #[serde(flatten)]
+    body: Option<T>,
+}
+
+/// What one run did (`sync_now` result, SYNC_EVENT payload).
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub pulled: usize,
+    pub pushed: usize,
+    pub conflicts: usize,
+    pub skipped: usize, // records that couldn't be stored (see the log)
+    pub cursor: i64,
+}
+
+/// `get_sync_status` result.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatus {
+    pub enabled: bool,
+    pub licensed: bool,
+    pub cursor: i64,
+    pub last_sync: Option<String>,
+    pub pending: i64, // local changes not pushed yet
+    pub last_error: Option<String>,
+    pub last_report: Option<SyncReport>,
+}
+
+#[derive(Default)]
+struct LastRun {
+    error: Option<String>,
+    report: Option<SyncReport>,
+}
+
+
+//_____________Globals _______________________
+static RUNNING: Lazy<tokio::sync::Mutex<()>> = Lazy::new(|| tokio::sync::Mutex::new(()));
+static LAST_RUN: Lazy<Mutex<LastRun>> = Lazy::new(|| Mutex::new(LastRun::default()));
+
+
+//_____________fn ____________________________
+async fn post<B: Serialize, R: DeserializeOwned>(endpoint: &str, body: &B) -> Result<R, AppError> {
+    let url = format!("{}/sync/{}", license::server_url(), endpoint);
+    let resp = license::http_client()
+        .post(&url)
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| AppError::Sync(format!("Cloud server unreachable: {}", e)))?;
+    if !resp.status().is_success() {
+        return Err(AppError::Sync(format!("{} answered HTTP {}", url, resp.status())));
+    }
+    let envelope: Envelope<R> = resp.json().await.map_err(|e| AppError::Sync(format!("Bad answer from {}: {}", url, e)))?;
+    match (envelope.success, envelope.body) {
+        (true, Some(body)) => Ok(body),
+        (true, None) => Err(AppError::Sync(format!("Bad answer from {}", url))),
+        (false, _) => Err(AppError::Sync(envelope.message)),
+    }
+}
+
+// Merge a server record; conflicts are announced, failures skip the record
+async fn apply(app_handle: &AppHandle, record: SyncRecord, report: &mut SyncReport) {
+    let (kind, uuid) = (record.kind, record.uuid.clone());
+    match database::blocking(move || database::sync_apply(&record)).await {
+        Ok(Some(conflict)) => {
+            report.conflicts += 1;
+            announce(app_handle, &conflict);
+        }
+        Ok(None) => {}
+        Err(e) => {
+            report.skipped += 1;
+            warn!("⚠️ Synced {:?} {} not stored: {}", kind, uuid, e);
+        }
+    }
+}
+
+fn announce(app_handle: &AppHandle, conflict: &SyncConflict) {
+    info!("🔀 Sync conflict on {:?} {}: {:?} change kept", conflict.kind, conflict.uuid, conflict.winner);
+    events::emit_all_surfaces(app_handle, SYNC_CONFLICT_EVENT, conflict);
+}
+
+async fn pull(app_handle: &AppHandle, key: &str, report: &mut SyncReport) -> Result<(), AppError> {
+    let mut cursor = database::blocking(database::sync_state).await?.cursor;
+    loop {
+        let request = PullRequest { key: key.to_string(), machine_id: license::machine_id(), since: cursor, limit: PULL_PAGE_SIZE };
+        let page: PullResponse = post("pull", &request).await?;
+        report.pulled += page.records.len();
+        for record in page.records {
+            apply(app_handle, record, report).await;
+        }
+        cursor = page.cursor.max(cursor);
+        database::blocking(move || database::set_sync_cursor(cursor)).await?;
+        if !page.more {
+            report.cursor = cursor;
+            return Ok(());
+        }
+    }
+}
+
+async fn push(app_handle: &AppHandle, key: &str, report: &mut SyncReport) -> Result<(), AppError> {
+    for _ in 0..PUSH_ROUNDS {
+        let pending = database::blocking(database::sync_pending).await?;
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let mut lost = 0;
+        for batch in pending.chunks(PUSH_BATCH_SIZE) {
+            let request = PushRequest { key: key.to_string(), machine_id: license::machine_id(), records: batch };
+            let answer: PushResponse = post("push", &request).await?;
+            for (record, result) in batch.iter().zip(answer.results) {
+                match (result.status, result.revision, result.current) {
+                    (PushStatus::Ok, Some(revision), _) => {
+                        let record = record.clone();
+                        database::blocking(move || database::sync_pushed(&record, revision)).await?;
+                        report.pushed += 1;
+                    }
+                    (PushStatus::Conflict, _, Some(current)) => {
+                        lost += 1;
+                        apply(app_handle, current, report).await;
+                    }
+                    _ => {
+                        report.skipped += 1;
+                        warn!("⚠️ Push of {:?} {} got an incomplete answer", record.kind, record.uuid);
+                    }
+                }
+            }
+        }
+        if lost == 0 {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+/// One full sync: pull, then push. Runs one at a time.
+pub async fn run(app_handle: &AppHandle) -> Result<SyncReport, AppError> {
+    if !features::is_enabled(features::CLOUD_SYNC) {
+        return Err(AppError::FeatureNotLicensed(features::CLOUD_SYNC.to_string()));
+    }
+    let key = license::current_key();
+    if key.is_empty() {
+        return Err(AppError::Sync("No license key: cloud sync is per license".into()));
+    }
+    let _running = RUNNING.lock().await;
+
+    let mut report = SyncReport::default();
+    let result = async {
+        pull(app_handle, &key, &mut report).await?;
+        push(app_handle, &key, &mut report).await?;
+        database::blocking(database::sync_finished).await
+    }
+    .await;
+
+    let mut last = LAST_RUN.lock().unwrap_or_else(|p| p.into_inner());
+    match result {
+        Ok(()) => {
+            debug!("Sync done: {:?}", report);
+            *last = LastRun { error: None, report: Some(report.clone()) };
+            drop(last);
+            events::emit_all_surfaces(app_handle, SYNC_EVENT, &report);
+            Ok(report)
+        }
+        Err(e) => {
+            last.error = Some(e.to_string());
+            Err(e)
+        }
+    }
+}
+
+pub async fn status() -> Result<SyncStatus, AppError> {
+    let state = database::blocking(database::sync_state).await?;
+    let last = LAST_RUN.lock().unwrap_or_else(|p| p.into_inner());
+    Ok(SyncStatus {
+        enabled: settings::get().sync.enabled,
+        licensed: features::is_enabled(features::CLOUD_SYNC),
+        cursor: state.cursor,
+        last_sync: state.last_sync,
+        pending: state.pending,
+        last_error: last.error.clone(),
+        last_report: last.report.clone(),
+    })
+}
+
+/// Start the periodic sync; it does nothing while settings.sync enabled is off or the license lacks cloud_sync.
+pub fn start(app_handle: AppHandle) {
+    crash::spawn("cloud-sync", async move {
+        loop {
+            // Re-read every round so settings changes apply without a restart
+            let config = settings::get().sync;
+            tokio::time::sleep(Duration::from_secs(config.interval_secs)).await;
+            if !settings::get().sync.enabled || !features::is_enabled(features::CLOUD_SYNC) {
+                continue;
+            }
+            if let Err(e) = run(&app_handle).await {
+                warn!("⚠️ Cloud sync failed: {}", e);
+            }
+        }
+    });
+}
+
+
+//_____________Commands ________________________
+
+/// Sync now, without waiting for the next scheduled run (works even when the schedule is off).
+#[tauri::command]
+pub async fn sync_now(app_handle: AppHandle) -> Result<SyncReport, AppError> {
+    run(&app_handle).await
+}
+
+#[tauri::command]
+pub async fn get_sync_status() -> Result<SyncStatus, AppError> {
+    status().await
+}
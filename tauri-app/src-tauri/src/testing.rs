@@ -0,0 +1,23 @@
+// src/testing.rs
+//
+// What the integration tests in tests/ reach of the crate (its modules are private to the app).
+// - `serve_headless` starts the WS server without Tauri on a free port; talk to it with any WS client
+// - `serve_http_headless` does the same for the HTTP API (POST /command)
+// - `publish_event` pushes an event to the subscribed WS connections, as the app does on a change
+// Not part of the app's API: only the tests use it.
+
+use serde_json::Value;
+
+use crate::events;
+
+pub use crate::http_api::{serve_headless as serve_http_headless, COMMAND_PATH};
+pub use crate::settings::WsSettings;
+pub use crate::websocket::{serve_headless, HeadlessServer, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION};
+
+
+//_____________fn ____________________________
+
+/// Push `data` to the WS connections subscribed to `event` (one of events::EVENTS).
+pub fn publish_event(event: &str, data: &Value) {
+    events::publish(event, data);
+}
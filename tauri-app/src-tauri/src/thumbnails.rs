@@ -0,0 +1,104 @@
+// src/thumbnails.rs
+//
+// Clip thumbnails for the marker list, extracted once with ffmpeg (media.rs) and cached.
+// - Cached as <app cache dir>/thumbnails/<key>.jpg, key = hash of path + mtime + timestamp + size,
+//   so a re-rendered / replaced clip gets new thumbnails and an unchanged one never hits ffmpeg again
+// - `get_clip_thumbnail` returns the file path, plus a `data:image/jpeg;base64,…` URL when asked
+//   (for webviews that can't load local files)
+
+use base64::Engine;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+use tauri::{AppHandle, Manager};
+use tracing::debug;
+
+use crate::cache;
+use crate::error::AppError;
+use crate::media;
+
+
+//____________Const___________
+pub const THUMBNAIL_WIDTH: u32 = 320; // pixels, height follows the clip's aspect ratio
+const THUMBNAILS_DIR: &str = "thumbnails"; // under the app cache dir
+
+
+//_____________Struct _________________________
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Thumbnail {
+    pub path: PathBuf,
+    pub data_url: Option<String>, // only when requested
+    pub cached: bool,             // false = just extracted
+}
+
+
+//_____________fn ____________________________
+pub fn thumbnails_dir(app_handle: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app_handle
+        .path()
+        .app_cache_dir()
+        .map_err(|e| AppError::Io(format!("No app cache dir: {}", e)))?
+        .join(THUMBNAILS_DIR);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+// Same clip file + time + size -> same key; touching the file invalidates it
+fn cache_key(path: &str, timestamp: f64, width: u32) -> Result<String, AppError> {
+    let modified = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map_err(|e| AppError::InvalidInput(format!("Clip not found: {} ({})", path, e)))?;
+    let mtime = modified.duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default();
+    let digest = Sha256::digest(format!("{}|{}|{:.3}|{}", path, mtime, timestamp, width).as_bytes());
+    Ok(hex::encode(&digest[..16]))
+}
+
+/// Thumbnail of `path` at `timestamp` seconds, `width` pixels wide (THUMBNAIL_WIDTH by default).
+pub async fn clip_thumbnail(
+    app_handle: &AppHandle,
+    path: &str,
+    timestamp: f64,
+    width: Option<u32>,
+    as_data_url: bool,
+) -> Result<Thumbnail, AppError> {
+    let width = width.unwrap_or(THUMBNAIL_WIDTH).clamp(16, 4096);
+    let file = thumbnails_dir(app_handle)?.join(format!("{}.jpg", cache_key(path, timestamp, width)?));
+
+    let cached = file.is_file();
+    let jpeg = if cached {
+        debug!("Thumbnail cache hit {:?}", file);
+        cache::touch(&file);
+        as_data_url.then(|| std::fs::read(&file)).transpose()?
+    } else {
+        let jpeg = media::extract_frame(path, timestamp, Some(width)).await?;
+        // Write then rename: a concurrent request never reads half a file
+        let partial = file.with_extension(format!("{}.part", hex::encode(rand::random::<[u8; 4]>())));
+        std::fs::write(&partial, &jpeg)?;
+        std::fs::rename(&partial, &file)?;
+        cache::changed();
+        Some(jpeg)
+    };
+
+    let data_url = match (as_data_url, jpeg) {
+        (true, Some(jpeg)) => Some(format!("data:image/jpeg;base64,{}", base64::engine::general_purpose::STANDARD.encode(jpeg))),
+        _ => None,
+    };
+    Ok(Thumbnail { path: file, data_url, cached })
+}
+
+
+//_____________Commands ________________________
+
+/// Example: `invoke("get_clip_thumbnail", { path: "C:/…/clip.mp4", timestamp: 12.5, size: 240, base64: true })`
+#[tauri::command]
+pub async fn get_clip_thumbnail(
+    app_handle: AppHandle,
+    path: String,
+    timestamp: f64,
+    size: Option<u32>,
+    base64: Option<bool>,
+) -> Result<Thumbnail, AppError> {
+    clip_thumbnail(&app_handle, &path, timestamp, size, base64.unwrap_or(false)).await
+}
@@ -0,0 +1,195 @@
+// src/timecode.rs
+//
+// SMPTE timecode <-> seconds at a clip's frame rate, for the marker exporters / importers and the panel.
+// - NTSC rates (23.976, 29.97, 59.94) are stored rounded in the database; `exact_rate` gives back 24000/1001, …
+// - drop frame (29.97 / 59.94 only): "HH:MM:SS;FF", frame numbers 0-1 (0-3 at 59.94) skipped every minute
+//   except each tenth, so the timecode keeps up with the wall clock
+// - non drop frame: "HH:MM:SS:FF", frames counted at the rounded rate (what Premiere shows for 23.976)
+// Usage: `invoke("format_timecode", { seconds: 3600, fps: 29.97, dropFrame: true })` -> "01:00:00;00"
+
+use crate::error::AppError;
+
+
+//____________Const___________
+const NTSC_RATES: &[f64] = &[24.0, 30.0, 60.0]; // x 1000/1001
+const RATE_TOLERANCE: f64 = 0.01;
+
+
+//_____________fn ____________________________
+
+fn valid_rate(fps: f64) -> Result<f64, AppError> {
+    if !fps.is_finite() || fps <= 0.0 {
+        return Err(AppError::InvalidInput(format!("Invalid frame rate {}", fps)));
+    }
+    Ok(exact_rate(fps))
+}
+
+/// 29.97 -> 30000/1001, 23.976 -> 24000/1001; other rates unchanged.
+pub fn exact_rate(fps: f64) -> f64 {
+    NTSC_RATES
+        .iter()
+        .map(|base| base * 1000.0 / 1001.0)
+        .find(|ntsc| (ntsc - fps).abs() < RATE_TOLERANCE)
+        .unwrap_or(fps)
+}
+
+/// Whole frames per second a timecode counts (30 for 29.97).
+pub fn timebase(fps: f64) -> u64 {
+    fps.round().max(1.0) as u64
+}
+
+/// 29.97 and 59.94 have a drop-frame timecode.
+pub fn is_drop_frame_rate(fps: f64) -> bool {
+    let fps = exact_rate(fps);
+    timebase(fps) % 30 == 0 && (timebase(fps) as f64 - fps).abs() > RATE_TOLERANCE
+}
+
+// Frame numbers skipped each minute: 2 at 29.97, 4 at 59.94
+fn dropped_per_minute(fps: f64) -> u64 {
+    timebase(fps) / 15
+}
+
+/// Frame index of `seconds` at `fps`.
+pub fn to_frames(seconds: f64, fps: f64) -> u64 {
+    (seconds.max(0.0) * exact_rate(fps)).round() as u64
+}
+
+/// "HH:MM:SS:FF" (";" before FF in drop frame) of frame `frames`.
+pub fn frames_to_timecode(frames: u64, fps: f64, drop_frame: bool) -> String {
+    let base = timebase(fps);
+    let drop_frame = drop_frame && is_drop_frame_rate(fps);
+    let mut frames = frames;
+    if drop_frame {
+        // Add back the frame numbers skipped so far, then count as non drop frame
+        let drop = dropped_per_minute(fps);
+        let per_ten_minutes = base * 600 - drop * 9;
+        let per_minute = base * 60 - drop;
+        let (tens, rest) = (frames / per_ten_minutes, frames % per_ten_minutes);
+        frames += drop * 9 * tens + if rest > drop { drop * ((rest - drop) / per_minute) } else { 0 };
+    }
+    let (ff, total_secs) = (frames % base, frames / base);
+    let separator = if drop_frame { ';' } else { ':' };
+    format!("{:02}:{:02}:{:02}{}{:02}", total_secs / 3600, total_secs / 60 % 60, total_secs % 60, separator, ff)
+}
+
+/// Timecode of `seconds` at `fps`.
+pub fn to_timecode(seconds: f64, fps: f64, drop_frame: bool) -> String {
+    frames_to_timecode(to_frames(seconds, fps), fps, drop_frame)
+}
+
+/// Frame index of "HH:MM:SS:FF" / "HH:MM:SS;FF" (";" or "." = drop frame) at `fps`.
+pub fn parse_frames(tc: &str, fps: f64) -> Option<u64> {
+    let drop_frame = tc.contains([';', '.']) && is_drop_frame_rate(fps);
+    let parts: Vec<u64> = tc.split([':', ';', '.']).map(|p| p.trim().parse().ok()).collect::<Option<_>>()?;
+    let [hh, mm, ss, ff] = parts[..] else { return None };
+    let base = timebase(fps);
+    if mm >= 60 || ss >= 60 || ff >= base {
+        return None;
+    }
+    let frames = (hh * 3600 + mm * 60 + ss) * base + ff;
+    if !drop_frame {
+        return Some(frames);
+    }
+    if ss == 0 && ff < dropped_per_minute(fps) && mm % 10 != 0 {
+        return None; // a skipped frame number (00:01:00;00 doesn't exist)
+    }
+    let minutes = hh * 60 + mm;
+    frames.checked_sub(dropped_per_minute(fps) * (minutes - minutes / 10))
+}
+
+/// Seconds of a timecode at `fps`.
+pub fn parse(tc: &str, fps: f64) -> Option<f64> {
+    Some(parse_frames(tc, fps)? as f64 / exact_rate(fps))
+}
+
+
+//_____________Commands ________________________
+
+/// `dropFrame` defaults to true at 29.97 / 59.94.
+#[tauri::command]
+pub fn format_timecode(seconds: f64, fps: f64, drop_frame: Option<bool>) -> Result<String, AppError> {
+    let fps = valid_rate(fps)?;
+    if !seconds.is_finite() || seconds < 0.0 {
+        return Err(AppError::InvalidInput("seconds must be >= 0".into()));
+    }
+    Ok(to_timecode(seconds, fps, drop_frame.unwrap_or_else(|| is_drop_frame_rate(fps))))
+}
+
+/// Example: `invoke("parse_timecode", { timecode: "00:01:00;02", fps: 29.97 })` -> 60.06
+#[tauri::command]
+pub fn parse_timecode(timecode: String, fps: f64) -> Result<f64, AppError> {
+    let fps = valid_rate(fps)?;
+    parse(&timecode, fps).ok_or_else(|| AppError::InvalidInput(format!("Invalid timecode '{}' at {} fps", timecode, fps)))
+}
+
+
+//_____________Tests ________________________
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ntsc_rates_are_exact() {
+        assert_eq!(exact_rate(29.97), 30000.0 / 1001.0);
+        assert_eq!(exact_rate(23.976), 24000.0 / 1001.0);
+        assert_eq!(exact_rate(25.0), 25.0);
+        assert!(is_drop_frame_rate(29.97) && is_drop_frame_rate(59.94));
+        assert!(!is_drop_frame_rate(23.976) && !is_drop_frame_rate(30.0) && !is_drop_frame_rate(25.0));
+    }
+
+    #[test]
+    fn drop_frame_skips_two_numbers_a_minute_at_29_97() {
+        assert_eq!(frames_to_timecode(1799, 29.97, true), "00:00:59;29");
+        assert_eq!(frames_to_timecode(1800, 29.97, true), "00:01:00;02");
+        assert_eq!(parse_frames("00:01:00;02", 29.97), Some(1800));
+        assert_eq!(parse_frames("00:00:59;29", 29.97), Some(1799));
+        // Except every tenth minute
+        assert_eq!(frames_to_timecode(17982, 29.97, true), "00:10:00;00");
+        assert_eq!(parse_frames("00:10:00;00", 29.97), Some(17982));
+        assert_eq!(to_timecode(3600.0, 29.97, true), "01:00:00;00");
+    }
+
+    #[test]
+    fn drop_frame_skips_four_numbers_a_minute_at_59_94() {
+        assert_eq!(frames_to_timecode(3599, 59.94, true), "00:00:59;59");
+        assert_eq!(frames_to_timecode(3600, 59.94, true), "00:01:00;04");
+        assert_eq!(parse_frames("00:01:00;04", 59.94), Some(3600));
+    }
+
+    #[test]
+    fn skipped_drop_frame_numbers_are_rejected() {
+        assert_eq!(parse_frames("00:01:00;00", 29.97), None);
+        assert_eq!(parse_frames("00:01:00;01", 29.97), None);
+        assert_eq!(parse_frames("00:02:00;03", 59.94), None);
+        // Same digits as non drop frame are a real frame
+        assert_eq!(parse_frames("00:01:00:00", 29.97), Some(1800));
+    }
+
+    #[test]
+    fn drop_frame_round_trips_every_frame() {
+        for fps in [29.97, 59.94] {
+            for frame in 0..40_000 {
+                let tc = frames_to_timecode(frame, fps, true);
+                assert_eq!(parse_frames(&tc, fps), Some(frame), "{} at {}", tc, fps);
+            }
+        }
+    }
+
+    #[test]
+    fn non_drop_frame_counts_at_the_rounded_rate() {
+        assert_eq!(to_timecode(1.0, 25.0, false), "00:00:01:00");
+        assert_eq!(frames_to_timecode(24, 23.976, false), "00:00:01:00");
+        // Drop frame asked at a rate without one
+        assert_eq!(to_timecode(1.0, 25.0, true), "00:00:01:00");
+        assert_eq!(parse("00:00:01:12", 25.0), Some(1.48));
+    }
+
+    #[test]
+    fn malformed_timecodes_are_rejected() {
+        for tc in ["", "00:00:01", "00:00:00:00:00", "00:60:00:00", "00:00:60:00", "00:00:00:25", "aa:00:00:00", "00:00:-1:00"] {
+            assert_eq!(parse_frames(tc, 25.0), None, "{}", tc);
+        }
+        assert_eq!(parse_frames("00:00:00:30", 29.97), None);
+    }
+}
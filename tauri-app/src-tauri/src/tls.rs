@@ -0,0 +1,108 @@
+// src/tls.rs
+//
+// Optional TLS for the embedded WebSocket server (wss://), for CEP/UXP hosts that refuse plain ws://.
+// - Enabled with settings.ws `tls: true`; the listener restarts and accepts wss:// only
+// - A self-signed certificate for localhost / 127.0.0.1 is generated once into the app data dir
+//   (ws-cert.pem / ws-key.pem) and reused, so its fingerprint stays stable across restarts
+// - `get_tls_info` returns the SHA-256 fingerprint so the panel can pin the certificate
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::TlsAcceptor;
+use tracing::info;
+
+use crate::error::AppError;
+use crate::settings;
+
+
+//____________Const___________
+const CERT_FILE: &str = "ws-cert.pem";
+const KEY_FILE: &str = "ws-key.pem";
+const CERT_NAMES: &[&str] = &["localhost", "127.0.0.1"];
+
+
+//_____________Struct _________________________
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsInfo {
+    pub enabled: bool,           // settings.ws tls
+    pub fingerprint: String,     // SHA-256 of the DER certificate, "AB:CD:…"
+    pub cert_path: PathBuf,
+}
+
+
+//_____________fn ____________________________
+fn cert_paths(app_handle: &AppHandle) -> Result<(PathBuf, PathBuf), AppError> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::Io(format!("No app data dir: {}", e)))?;
+    std::fs::create_dir_all(&dir)?;
+    Ok((dir.join(CERT_FILE), dir.join(KEY_FILE)))
+}
+
+// Read the certificate + key, generating them the first time
+fn load_or_generate(app_handle: &AppHandle) -> Result<(CertificateDer<'static>, PrivateKeyDer<'static>), AppError> {
+    let (cert_path, key_path) = cert_paths(app_handle)?;
+
+    if !cert_path.exists() || !key_path.exists() {
+        let names: Vec<String> = CERT_NAMES.iter().map(|n| n.to_string()).collect();
+        let generated = rcgen::generate_simple_self_signed(names)
+            .map_err(|e| AppError::Ws(format!("Failed to generate TLS certificate: {}", e)))?;
+        std::fs::write(&cert_path, generated.cert.pem())?;
+        std::fs::write(&key_path, generated.key_pair.serialize_pem())?;
+        info!("🔐 Generated self-signed WS certificate at {:?}", cert_path);
+    }
+
+    let bad = |path: &PathBuf, what: &str| AppError::Ws(format!("{:?}: {}", path, what));
+    let cert = rustls_pemfile::certs(&mut BufReader::new(std::fs::File::open(&cert_path)?))
+        .next()
+        .ok_or_else(|| bad(&cert_path, "no certificate"))?
+        .map_err(|e| bad(&cert_path, &e.to_string()))?;
+    let key = rustls_pemfile::private_key(&mut BufReader::new(std::fs::File::open(&key_path)?))
+        .map_err(|e| bad(&key_path, &e.to_string()))?
+        .ok_or_else(|| bad(&key_path, "no private key"))?;
+    Ok((cert, key))
+}
+
+/// TLS acceptor for the WS listener.
+pub fn acceptor(app_handle: &AppHandle) -> Result<TlsAcceptor, AppError> {
+    let (cert, key) = load_or_generate(app_handle)?;
+    let config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert], key)
+        .map_err(|e| AppError::Ws(format!("Invalid TLS certificate: {}", e)))?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+fn fingerprint(cert: &CertificateDer) -> String {
+    Sha256::digest(cert.as_ref())
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+pub fn info(app_handle: &AppHandle) -> Result<TlsInfo, AppError> {
+    let (cert, _) = load_or_generate(app_handle)?;
+    Ok(TlsInfo {
+        enabled: settings::get().ws.tls,
+        fingerprint: fingerprint(&cert),
+        cert_path: cert_paths(app_handle)?.0,
+    })
+}
+
+
+//_____________Commands ________________________
+
+/// Fingerprint of the WS certificate (generated if needed, even while TLS is off).
+#[tauri::command]
+pub fn get_tls_info(app_handle: AppHandle) -> Result<TlsInfo, AppError> {
+    info(&app_handle)
+}
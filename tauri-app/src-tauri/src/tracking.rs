@@ -0,0 +1,310 @@
+// src/tracking.rs
+//
+// Face tracking across consecutive frames: the same person keeps the same track id from frame to frame, so a
+// timeline analysis gives per-person emotion tracks instead of anonymous per-frame faces.
+// - association is by overlap: a face continues the open track whose last box overlaps it most (IoU over
+//   MIN_IOU), greedily from the best overlap down; every other face opens a new track
+// - a track stays open MAX_GAP frames without a face (a look away, a missed detection), then it is closed
+// - the analyze_clip pipeline (analysis.rs) stores the track id in each marker's metadata as `track`
+// - `track_faces(frames)` command: emotion analysis of image files given in timeline order, e.g.
+//   `invoke("track_faces", { frames: ["C:/…/f1.jpg", "C:/…/f2.jpg"], timestamps: [0, 2] })`
+
+use serde::Serialize;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::analysis;
+use crate::deepFaceProcess;
+use crate::error::AppError;
+
+
+//____________Const___________
+const MIN_IOU: f64 = 0.3;  // overlap under which two boxes are different faces
+const MAX_GAP: usize = 2;  // frames a track may miss before it is closed
+const TRACK_ACTIONS: &str = "emotion";
+
+
+//_____________Struct _________________________
+/// A face box in frame pixels (deepface `region` / `facial_area`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Region {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+}
+
+impl Region {
+    /// The box of a deepface face, None when it has no usable one.
+    pub fn of_face(face: &Value) -> Option<Region> {
+        let area = if face["region"].is_object() { &face["region"] } else { &face["facial_area"] };
+        let region = Region {
+            x: area["x"].as_f64()?,
+            y: area["y"].as_f64()?,
+            w: area["w"].as_f64()?,
+            h: area["h"].as_f64()?,
+        };
+        (region.w > 0.0 && region.h > 0.0).then_some(region)
+    }
+
+    fn iou(&self, other: &Region) -> f64 {
+        let w = (self.x + self.w).min(other.x + other.w) - self.x.max(other.x);
+        let h = (self.y + self.h).min(other.y + other.h) - self.y.max(other.y);
+        if w <= 0.0 || h <= 0.0 {
+            return 0.0;
+        }
+        let inter = w * h;
+        inter / (self.w * self.h + other.w * other.h - inter)
+    }
+}
+
+struct OpenTrack {
+    id: u32,
+    region: Region, // last box seen
+    last_frame: usize,
+}
+
+/// Track ids for the faces of consecutive frames (state kept between frames).
+#[derive(Default)]
+pub struct FaceTracker {
+    open: Vec<OpenTrack>,
+    next_id: u32,
+    frame: usize,
+    tracks: usize, // tracks opened so far
+}
+
+impl FaceTracker {
+    pub fn new() -> Self {
+        FaceTracker { next_id: 1, ..FaceTracker::default() }
+    }
+
+    /// Track ids of the next frame's faces, in the order given; a face without a box always opens a new track.
+    pub fn next_frame(&mut self, regions: &[Option<Region>]) -> Vec<u32> {
+        let frame = self.frame;
+        self.frame += 1;
+        self.open.retain(|track| frame - track.last_frame <= MAX_GAP + 1);
+
+        // Every (face, open track) pair that overlaps enough, best overlap first
+        let mut pairs: Vec<(usize, usize, f64)> = Vec::new();
+        for (face, region) in regions.iter().enumerate() {
+            let Some(region) = region else { continue };
+            for (track, open) in self.open.iter().enumerate() {
+                let iou = region.iou(&open.region);
+                if iou >= MIN_IOU {
+                    pairs.push((face, track, iou));
+                }
+            }
+        }
+        pairs.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+        let mut ids: Vec<Option<u32>> = vec![None; regions.len()];
+        let mut taken = vec![false; self.open.len()];
+        for (face, track, _) in pairs {
+            if ids[face].is_some() || taken[track] {
+                continue;
+            }
+            taken[track] = true;
+            ids[face] = Some(self.open[track].id);
+            if let Some(region) = regions[face] {
+                self.open[track].region = region;
+            }
+            self.open[track].last_frame = frame;
+        }
+
+        ids.into_iter()
+            .zip(regions)
+            .map(|(id, region)| {
+                id.unwrap_or_else(|| {
+                    let id = self.next_id;
+                    self.next_id += 1;
+                    self.tracks += 1;
+                    if let Some(region) = region {
+                        self.open.push(OpenTrack { id, region: *region, last_frame: frame });
+                    }
+                    id
+                })
+            })
+            .collect()
+    }
+
+    /// How many tracks were opened so far.
+    pub fn track_count(&self) -> usize {
+        self.tracks
+    }
+}
+
+/// One face of a track.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackPoint {
+    pub timestamp: f64,
+    pub emotion: Option<String>, // dominant emotion
+    pub emotions: Value,         // deepface scores per emotion
+    pub region: Option<Region>,
+}
+
+/// One person across the frames.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FaceTrack {
+    pub id: u32,
+    pub start: f64, // timestamp of the first face
+    pub end: f64,   // timestamp of the last face
+    pub points: Vec<TrackPoint>,
+}
+
+
+//_____________fn ____________________________
+
+/// The faces deepface found in an analyze answer (a frame without a face comes back as one face with confidence 0).
+pub fn analyzed_faces(answer: &Value) -> Vec<&Value> {
+    answer["data"]["result"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|face| face["face_confidence"].as_f64().unwrap_or(1.0) > 0.0)
+        .collect()
+}
+
+/// Emotion analysis of `frames` (image files in timeline order) grouped into per-person tracks.
+/// `timestamps` default to the frame index.
+pub async fn track_frames(
+    frames: &[String],
+    timestamps: Option<Vec<f64>>,
+    detector: Option<String>,
+    model: Option<String>,
+) -> Result<Vec<FaceTrack>, AppError> {
+    if let Some(timestamps) = &timestamps {
+        if timestamps.len() != frames.len() {
+            return Err(AppError::InvalidInput(format!("{} timestamps for {} frames", timestamps.len(), frames.len())));
+        }
+    }
+    analysis::ensure_deepface(None).await?;
+
+    let mut tracker = FaceTracker::new();
+    let mut tracks: Vec<FaceTrack> = Vec::new();
+    for (i, frame) in frames.iter().enumerate() {
+        let timestamp = timestamps.as_ref().map_or(i as f64, |t| t[i]);
+        let image = std::fs::read(frame)?;
+        let answer = deepFaceProcess::analyze_frame(&image, Some(TRACK_ACTIONS.into()), detector.clone(), model.clone()).await?;
+        if answer["status"] != "ok" {
+            warn!("⚠️ Frame {} not analyzed: {}", frame, answer["data"]["message"]);
+            tracker.next_frame(&[]);
+            continue;
+        }
+
+        let faces = analyzed_faces(&answer);
+        let regions: Vec<Option<Region>> = faces.iter().map(|face| Region::of_face(face)).collect();
+        for ((face, region), id) in faces.iter().zip(&regions).zip(tracker.next_frame(&regions)) {
+            let point = TrackPoint {
+                timestamp,
+                emotion: face["dominant_emotion"].as_str().map(String::from),
+                emotions: face["emotion"].clone(),
+                region: *region,
+            };
+            match tracks.iter_mut().find(|track| track.id == id) {
+                Some(track) => {
+                    track.end = timestamp;
+                    track.points.push(point);
+                }
+                None => tracks.push(FaceTrack { id, start: timestamp, end: timestamp, points: vec![point] }),
+            }
+        }
+    }
+    Ok(tracks)
+}
+
+
+//_____________Commands ________________________
+
+/// Per-person emotion tracks over `frames` (image paths in timeline order).
+#[tauri::command]
+pub async fn track_faces(
+    frames: Vec<String>,
+    timestamps: Option<Vec<f64>>,
+    detector: Option<String>,
+    model: Option<String>,
+) -> Result<Vec<FaceTrack>, AppError> {
+    track_frames(&frames, timestamps, detector, model).await
+}
+
+
+//_____________Tests ________________________
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn region(x: f64, y: f64, w: f64, h: f64) -> Region {
+        Region { x, y, w, h }
+    }
+
+    #[test]
+    fn iou_of_overlapping_boxes() {
+        let a = region(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(a.iou(&a), 1.0);
+        assert_eq!(a.iou(&region(5.0, 0.0, 10.0, 10.0)), 50.0 / 150.0);
+        assert_eq!(a.iou(&region(2.5, 2.5, 5.0, 5.0)), 0.25); // inside
+        assert_eq!(a.iou(&region(5.0, 0.0, 10.0, 10.0)), region(5.0, 0.0, 10.0, 10.0).iou(&a));
+    }
+
+    #[test]
+    fn iou_of_disjoint_or_touching_boxes_is_zero() {
+        let a = region(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(a.iou(&region(20.0, 20.0, 5.0, 5.0)), 0.0);
+        assert_eq!(a.iou(&region(10.0, 0.0, 10.0, 10.0)), 0.0); // shared edge
+        assert_eq!(a.iou(&region(0.0, 10.0, 10.0, 10.0)), 0.0);
+    }
+
+    #[test]
+    fn iou_of_zero_area_boxes_is_zero() {
+        let a = region(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(a.iou(&region(5.0, 5.0, 0.0, 0.0)), 0.0);
+        assert_eq!(a.iou(&region(5.0, 5.0, 0.0, 4.0)), 0.0);
+        let point = region(5.0, 5.0, 0.0, 0.0);
+        assert_eq!(point.iou(&point), 0.0);
+    }
+
+    #[test]
+    fn faces_without_a_usable_box_have_no_region() {
+        assert_eq!(Region::of_face(&json!({ "region": { "x": 1, "y": 2, "w": 3, "h": 4 } })), Some(region(1.0, 2.0, 3.0, 4.0)));
+        assert_eq!(Region::of_face(&json!({ "facial_area": { "x": 1, "y": 2, "w": 3, "h": 4 } })), Some(region(1.0, 2.0, 3.0, 4.0)));
+        assert_eq!(Region::of_face(&json!({ "region": { "x": 1, "y": 2, "w": 0, "h": 4 } })), None);
+        assert_eq!(Region::of_face(&json!({ "region": { "x": 1, "y": 2 } })), None);
+    }
+
+    #[test]
+    fn overlapping_faces_keep_their_track() {
+        let mut tracker = FaceTracker::new();
+        let (left, right) = (region(0.0, 0.0, 10.0, 10.0), region(100.0, 0.0, 10.0, 10.0));
+        assert_eq!(tracker.next_frame(&[Some(left), Some(right)]), vec![1, 2]);
+        // Moved a little, given in the other order
+        assert_eq!(tracker.next_frame(&[Some(region(102.0, 0.0, 10.0, 10.0)), Some(region(1.0, 1.0, 10.0, 10.0))]), vec![2, 1]);
+        // Too little overlap (IoU under MIN_IOU): a new person
+        assert_eq!(tracker.next_frame(&[Some(region(8.0, 8.0, 10.0, 10.0))]), vec![3]);
+        assert_eq!(tracker.track_count(), 3);
+    }
+
+    #[test]
+    fn tracks_close_after_max_gap_frames() {
+        let mut tracker = FaceTracker::new();
+        let face = region(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(tracker.next_frame(&[Some(face)]), vec![1]);
+        for _ in 0..MAX_GAP {
+            tracker.next_frame(&[]);
+        }
+        assert_eq!(tracker.next_frame(&[Some(face)]), vec![1]);
+        for _ in 0..=MAX_GAP {
+            tracker.next_frame(&[]);
+        }
+        assert_eq!(tracker.next_frame(&[Some(face)]), vec![2]);
+    }
+
+    #[test]
+    fn a_face_without_a_box_always_opens_a_track() {
+        let mut tracker = FaceTracker::new();
+        assert_eq!(tracker.next_frame(&[None, None]), vec![1, 2]);
+        assert_eq!(tracker.next_frame(&[None]), vec![3]);
+    }
+}
@@ -0,0 +1,220 @@
+// src/tray.rs
+//
+// System tray icon: app health at a glance while Premiere stays fullscreen.
+// - tooltip + two read-only lines: license state (set by license.rs) and deepface server running / stopped
+//   (polled every TRAY_REFRESH seconds, so a crashed sidecar shows up too)
+// - Restart DeepFace, Open logs (app log dir), Copy WS port, Show window, Quit
+// - left click shows the menu, like the right click
+// - texts are in settings.locale (i18n.rs tray.* codes), `relabel` redraws them when it changes
+
+use once_cell::sync::OnceCell;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::{TrayIcon, TrayIconBuilder};
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_opener::OpenerExt;
+use tracing::{info, warn};
+
+use crate::deepFaceProcess::MANAGER;
+use crate::error::AppError;
+use crate::i18n;
+use crate::license::LicenseState;
+use crate::{settings, websocket};
+
+
+//____________Const___________
+const TRAY_ID: &str = "main";
+const TRAY_REFRESH: u64 = 5; // seconds between deepface status polls
+
+const ITEM_RESTART_DEEPFACE: &str = "restart-deepface";
+const ITEM_OPEN_LOGS: &str = "open-logs";
+const ITEM_COPY_PORT: &str = "copy-ws-port";
+const ITEM_SHOW: &str = "show-window";
+const ITEM_QUIT: &str = "quit";
+
+
+//_____________Struct _________________________
+struct Tray {
+    icon: TrayIcon<Wry>,
+    license_item: MenuItem<Wry>,
+    deepface_item: MenuItem<Wry>,
+    actions: Vec<(MenuItem<Wry>, &'static str)>, // (item, i18n code of its text)
+    status: Mutex<TrayStatus>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct TrayStatus {
+    license: Option<LicenseState>, // None = first check not done yet
+    deepface_running: bool,
+}
+
+
+//_____________Globals _______________________
+static TRAY: OnceCell<Tray> = OnceCell::new();
+
+
+//_____________fn ____________________________
+fn license_label(state: Option<LicenseState>) -> String {
+    i18n::text(match state {
+        None => "tray.license_checking",
+        Some(LicenseState::Valid) => "tray.license_valid",
+        Some(LicenseState::OfflineGrace) => "tray.license_offline_grace",
+        Some(LicenseState::OfflineExpired) => "tray.license_offline_expired",
+        Some(LicenseState::Invalid) => "tray.license_invalid",
+        Some(LicenseState::Unlicensed) => "tray.license_unlicensed",
+        Some(LicenseState::Trial) => "tray.license_trial",
+    })
+}
+
+fn deepface_label(running: bool) -> String {
+    i18n::text(if running { "tray.deepface_running" } else { "tray.deepface_stopped" })
+}
+
+// Push `update` into the labels and tooltip, when it changes anything
+fn update_status(update: impl FnOnce(&mut TrayStatus)) {
+    let Some(tray) = TRAY.get() else { return };
+    let mut status = tray.status.lock().unwrap_or_else(|p| p.into_inner());
+    let old = *status;
+    update(&mut status);
+    if *status == old {
+        return;
+    }
+
+    draw(tray, &status);
+}
+
+fn draw(tray: &Tray, status: &TrayStatus) {
+    let _ = tray.license_item.set_text(license_label(status.license));
+    let _ = tray.deepface_item.set_text(deepface_label(status.deepface_running));
+    let _ = tray.icon.set_tooltip(Some(tooltip(status)));
+}
+
+/// Redraw every text in the current locale (settings.locale changed).
+pub fn relabel() {
+    let Some(tray) = TRAY.get() else { return };
+    for (item, code) in &tray.actions {
+        let _ = item.set_text(i18n::text(code));
+    }
+    let status = *tray.status.lock().unwrap_or_else(|p| p.into_inner());
+    draw(tray, &status);
+}
+
+fn tooltip(status: &TrayStatus) -> String {
+    format!("tauri-app\n{}\n{}", license_label(status.license), deepface_label(status.deepface_running))
+}
+
+/// Called by license.rs on every license state change.
+pub fn set_license_state(state: LicenseState) {
+    update_status(|status| status.license = Some(state));
+}
+
+fn ws_port() -> u16 {
+    websocket::status()
+        .listening
+        .and_then(|addr| addr.rsplit(':').next()?.parse().ok())
+        .unwrap_or_else(|| settings::get().ws.port)
+}
+
+fn open_logs(app_handle: &AppHandle) -> Result<(), AppError> {
+    let dir = app_handle.path().app_log_dir().map_err(|e| AppError::Io(format!("No app log dir: {}", e)))?;
+    std::fs::create_dir_all(&dir)?;
+    app_handle
+        .opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| AppError::Io(format!("Failed to open {:?}: {}", dir, e)))
+}
+
+fn show_window(app_handle: &AppHandle) {
+    if let Some(window) = app_handle.webview_windows().into_values().next() {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn restart_deepface() {
+    tauri::async_runtime::spawn(async {
+        info!("🔁 Restarting DeepFace server from the tray");
+        let result = match MANAGER.stop().await {
+            Ok(()) => MANAGER.start(None).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            warn!("❌ DeepFace restart failed: {}", e);
+        }
+        let running = MANAGER.is_running().await;
+        update_status(|status| status.deepface_running = running);
+    });
+}
+
+fn on_menu_event(app_handle: &AppHandle, event: MenuEvent) {
+    match event.id.as_ref() {
+        ITEM_RESTART_DEEPFACE => restart_deepface(),
+        ITEM_OPEN_LOGS => {
+            if let Err(e) = open_logs(app_handle) {
+                warn!("❌ {}", e);
+            }
+        }
+        ITEM_COPY_PORT => {
+            if let Err(e) = app_handle.clipboard().write_text(ws_port().to_string()) {
+                warn!("❌ Failed to copy the WS port: {}", e);
+            }
+        }
+        ITEM_SHOW => show_window(app_handle),
+        ITEM_QUIT => app_handle.exit(0),
+        _ => {}
+    }
+}
+
+/// Create the tray icon and start the status poll. Call from setup.
+pub fn init(app_handle: &AppHandle) -> Result<(), AppError> {
+    let tray_err = |e: tauri::Error| AppError::Io(format!("Failed to create the tray icon: {}", e));
+    let status = TrayStatus { license: None, deepface_running: false };
+
+    let license_item = MenuItem::new(app_handle, license_label(status.license), false, None::<&str>).map_err(tray_err)?;
+    let deepface_item = MenuItem::new(app_handle, deepface_label(status.deepface_running), false, None::<&str>).map_err(tray_err)?;
+    let item = |id: &str, code: &'static str| MenuItem::with_id(app_handle, id, i18n::text(code), true, None::<&str>).map(|item| (item, code));
+    let restart = item(ITEM_RESTART_DEEPFACE, "tray.restart_deepface").map_err(tray_err)?;
+    let open_logs = item(ITEM_OPEN_LOGS, "tray.open_logs").map_err(tray_err)?;
+    let copy_port = item(ITEM_COPY_PORT, "tray.copy_port").map_err(tray_err)?;
+    let show = item(ITEM_SHOW, "tray.show").map_err(tray_err)?;
+    let quit = item(ITEM_QUIT, "tray.quit").map_err(tray_err)?;
+    let menu = Menu::with_items(
+        app_handle,
+        &[
+            &license_item,
+            &deepface_item,
+            &PredefinedMenuItem::separator(app_handle).map_err(tray_err)?,
+            &restart.0,
+            &open_logs.0,
+            &copy_port.0,
+            &PredefinedMenuItem::separator(app_handle).map_err(tray_err)?,
+            &show.0,
+            &quit.0,
+        ],
+    )
+    .map_err(tray_err)?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(tooltip(&status))
+        .menu(&menu)
+        .show_menu_on_left_click(true)
+        .on_menu_event(on_menu_event);
+    if let Some(icon) = app_handle.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    let icon = builder.build(app_handle).map_err(tray_err)?;
+
+    let actions = vec![restart, open_logs, copy_port, show, quit];
+    let _ = TRAY.set(Tray { icon, license_item, deepface_item, actions, status: Mutex::new(status) });
+    tauri::async_runtime::spawn(async {
+        loop {
+            let running = MANAGER.is_running().await;
+            update_status(|status| status.deepface_running = running);
+            tokio::time::sleep(Duration::from_secs(TRAY_REFRESH)).await;
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,262 @@
+// src/trial.rs
+//
+// 14-day trial for machines without a license key.
+// - The first license check without a key (license.rs) starts the trial: its start is written to trial.json in
+//   the app data dir, signed with an HMAC over the machine id under a random per-install key kept in the keychain
+//   (secrets.rs), so it can't be edited, forged or copied to another machine
+// - The start is also kept as a secret (TRIAL_STARTED): a trial.json deleted to get a new trial reads as expired
+// - While it runs, features.rs gets the TRIAL_FEATURES under the "trial" tier; once it lapses the app is
+//   Unlicensed and the pro features are gated again
+// - Usage is metered locally (launches, last use); a clock set back before the last use ends the trial,
+//   as does a record whose signature doesn't match
+// Activating a key takes over; the record stays, so a machine that had its trial gets no new one after deactivating.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tracing::{error, info, warn};
+
+use crate::features;
+use crate::license::{self, LicenseDetails};
+use crate::secrets;
+
+
+//____________Const___________
+pub const TRIAL_DAYS: u64 = 14;
+pub const TRIAL_TIER: &str = "trial";
+pub const TRIAL_FEATURES: &[&str] = &[features::BATCH_ANALYZE, features::FACE_SEARCH, features::LIVE_CAMERA, features::CLOUD_SYNC];
+const TRIAL_FILE: &str = "trial.json";
+const CLOCK_SLACK_SECS: u64 = 3600; // tolerated step back of the clock (DST, NTP corrections)
+const LAST_SEEN_STEP_SECS: u64 = 3600; // last_seen is only rewritten after this long
+
+type HmacSha256 = Hmac<Sha256>;
+
+
+//_____________Struct _________________________
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TrialState {
+    NotStarted, // a key was used from the first launch
+    Active,
+    Expired,
+    Tampered,   // record edited, copied from another machine, or clock set back
+}
+
+/// Result of `get_trial_status`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrialStatus {
+    pub state: TrialState,
+    pub started_at: Option<String>, // RFC 3339
+    pub expires_at: Option<String>,
+    pub days_left: Option<u64>,     // whole days, 0 on the last day
+    pub launches: u64,              // app runs that used the trial
+    pub last_used_at: Option<String>,
+}
+
+// trial.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TrialRecord {
+    started_at: u64, // unix seconds
+    last_seen: u64,
+    launches: u64,
+    signature: String, // hex HMAC over machine id and the fields above
+}
+
+
+//_____________Globals _______________________
+static LAUNCH_COUNTED: AtomicBool = AtomicBool::new(false); // this run is in `launches`
+static RECORD_LOCK: Mutex<()> = Mutex::new(());
+
+
+//_____________fn ____________________________
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn rfc3339(secs: u64) -> Option<String> {
+    DateTime::<Utc>::from_timestamp(secs as i64, 0).map(|d| d.to_rfc3339())
+}
+
+fn trial_path(app_handle: &AppHandle) -> Option<PathBuf> {
+    app_handle.path().app_data_dir().ok().map(|dir| dir.join(TRIAL_FILE))
+}
+
+// None = no key store to keep the signing key in: no trial can be recorded or verified
+fn sign(started_at: u64, last_seen: u64, launches: u64) -> Option<HmacSha256> {
+    let signing_key = secrets::install_key(secrets::TRIAL_KEY).map_err(|e| error!("❌ No trial signing key: {}", e)).ok()?;
+    let mut mac = HmacSha256::new_from_slice(&signing_key).expect("HMAC accepts any key length");
+    mac.update(format!("{}|{}|{}|{}", license::machine_id(), started_at, last_seen, launches).as_bytes());
+    Some(mac)
+}
+
+fn new_record(started_at: u64, last_seen: u64, launches: u64) -> Option<TrialRecord> {
+    let signature = hex::encode(sign(started_at, last_seen, launches)?.finalize().into_bytes());
+    Some(TrialRecord { started_at, last_seen, launches, signature })
+}
+
+fn verified(record: &TrialRecord) -> bool {
+    match (hex::decode(&record.signature), sign(record.started_at, record.last_seen, record.launches)) {
+        (Ok(signature), Some(mac)) => mac.verify_slice(&signature).is_ok(),
+        _ => false,
+    }
+}
+
+// Start of a trial this install already had, whether trial.json is still there or not
+fn started_marker() -> Option<u64> {
+    match secrets::get_secret(secrets::TRIAL_STARTED) {
+        Ok(started) => started.and_then(|s| s.trim().parse().ok()),
+        Err(e) => {
+            warn!("⚠️ Trial start marker unreadable: {}", e);
+            None
+        }
+    }
+}
+
+fn set_started_marker(started_at: u64) {
+    if let Err(e) = secrets::set_secret(secrets::TRIAL_STARTED, &started_at.to_string()) {
+        error!("❌ Failed to keep the trial start: {}", e);
+    }
+}
+
+// trial.json is gone but a trial was started: it was deleted to get a new one
+fn deleted_record_status(started_at: u64) -> TrialStatus {
+    TrialStatus {
+        state: TrialState::Expired,
+        started_at: rfc3339(started_at),
+        expires_at: rfc3339(started_at + TRIAL_DAYS * 86_400),
+        days_left: None,
+        launches: 0,
+        last_used_at: None,
+    }
+}
+
+// Ok(None) = no trial yet; Err(()) = a file that isn't a valid record of this machine
+fn load(app_handle: &AppHandle) -> Result<Option<TrialRecord>, ()> {
+    let Some(path) = trial_path(app_handle) else { return Ok(None) };
+    let Ok(bytes) = std::fs::read(&path) else { return Ok(None) };
+    match serde_json::from_slice::<TrialRecord>(&bytes) {
+        Ok(record) if verified(&record) => Ok(Some(record)),
+        _ => {
+            warn!("⚠️ Trial record {:?} doesn't match this machine", path);
+            Err(())
+        }
+    }
+}
+
+fn store(app_handle: &AppHandle, record: &TrialRecord) {
+    let Some(path) = trial_path(app_handle) else { return };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    match serde_json::to_vec(record) {
+        Ok(bytes) => {
+            if let Err(e) = std::fs::write(&path, bytes) {
+                error!("❌ Failed to write trial record {:?}: {}", path, e);
+            }
+        }
+        Err(e) => error!("❌ Failed to serialize trial record: {}", e),
+    }
+}
+
+fn status_of(record: Option<&TrialRecord>, tampered: bool, now: u64) -> TrialStatus {
+    let Some(record) = record else {
+        let state = if tampered { TrialState::Tampered } else { TrialState::NotStarted };
+        return TrialStatus { state, started_at: None, expires_at: None, days_left: None, launches: 0, last_used_at: None };
+    };
+    let expires = record.started_at + TRIAL_DAYS * 86_400;
+    let state = if tampered {
+        TrialState::Tampered
+    } else if now >= expires {
+        TrialState::Expired
+    } else {
+        TrialState::Active
+    };
+    TrialStatus {
+        state,
+        started_at: rfc3339(record.started_at),
+        expires_at: rfc3339(expires),
+        days_left: (state == TrialState::Active).then(|| (expires - now) / 86_400),
+        launches: record.launches,
+        last_used_at: rfc3339(record.last_seen),
+    }
+}
+
+/// Trial status for a license check without a key: starts the trial on first use, meters the launch and
+/// the last use.
+pub fn check(app_handle: &AppHandle) -> TrialStatus {
+    let _guard = RECORD_LOCK.lock().unwrap_or_else(|p| p.into_inner());
+    let now = now_secs();
+    let record = match load(app_handle) {
+        Ok(Some(record)) => record,
+        Ok(None) => {
+            if let Some(started_at) = started_marker() {
+                warn!("⚠️ Trial record missing but a trial was started, trial ended");
+                return deleted_record_status(started_at);
+            }
+            let Some(record) = new_record(now, now, 1) else { return status_of(None, false, now) };
+            set_started_marker(now);
+            LAUNCH_COUNTED.store(true, Ordering::SeqCst);
+            store(app_handle, &record);
+            info!("🕒 Trial started ({} days)", TRIAL_DAYS);
+            return status_of(Some(&record), false, now);
+        }
+        Err(()) => return status_of(None, true, now),
+    };
+    if started_marker().is_none() {
+        set_started_marker(record.started_at); // keychain wiped, or the record predates the marker
+    }
+
+    if now + CLOCK_SLACK_SECS < record.last_seen || now < record.started_at {
+        warn!("⚠️ Clock is before the last trial use, trial ended");
+        return status_of(Some(&record), true, now);
+    }
+
+    let launches = record.launches + u64::from(!LAUNCH_COUNTED.load(Ordering::SeqCst));
+    if launches != record.launches || now >= record.last_seen + LAST_SEEN_STEP_SECS {
+        if let Some(updated) = new_record(record.started_at, now.max(record.last_seen), launches) {
+            store(app_handle, &updated);
+            LAUNCH_COUNTED.store(true, Ordering::SeqCst);
+            return status_of(Some(&updated), false, now);
+        }
+    }
+    status_of(Some(&record), false, now)
+}
+
+/// Trial status without starting or metering anything.
+pub fn status(app_handle: &AppHandle) -> TrialStatus {
+    match load(app_handle) {
+        Ok(None) => match started_marker() {
+            Some(started_at) => deleted_record_status(started_at),
+            None => status_of(None, false, now_secs()),
+        },
+        Ok(record) => status_of(record.as_ref(), false, now_secs()),
+        Err(()) => status_of(None, true, now_secs()),
+    }
+}
+
+/// License details granted by an active trial (features.rs), None once it lapsed.
+pub fn details(status: &TrialStatus) -> Option<LicenseDetails> {
+    (status.state == TrialState::Active).then(|| LicenseDetails {
+        tier: Some(TRIAL_TIER.to_string()),
+        expiry: status.expires_at.as_deref().map(|d| d[..10].to_string()),
+        features: TRIAL_FEATURES.iter().map(|f| f.to_string()).collect(),
+        ..LicenseDetails::default()
+    })
+}
+
+
+//_____________Commands ________________________
+
+/// Example: `invoke("get_trial_status")` -> `{ state: "active", startedAt, expiresAt, daysLeft: 9, launches: 4, lastUsedAt }`.
+#[tauri::command]
+pub fn get_trial_status(app_handle: AppHandle) -> TrialStatus {
+    status(&app_handle)
+}
@@ -0,0 +1,216 @@
+// src/updater.rs
+//
+// Self-update through tauri-plugin-updater, with signed release artifacts.
+// - `check_for_update` asks the endpoint of settings.update `channel` (stable / beta) for a newer version
+// - `download_update` downloads and verifies it, emitting `update-progress` { downloaded, total }
+// - `install_update` installs the downloaded package and restarts the app
+//
+// Signing: `npm run tauri signer generate -- -w ~/.tauri/tauri-app.key` once, put the public key in
+// tauri.conf.json plugins.updater.pubkey, and build releases with TAURI_SIGNING_PRIVATE_KEY set
+// (bundle.createUpdaterArtifacts writes the .sig files). Each channel's endpoint serves the
+// updater `latest.json` of its newest release.
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri_plugin_updater::{Update, UpdaterExt};
+use tracing::{info, warn};
+
+use crate::error::AppError;
+use crate::{events, settings};
+
+
+//____________Const___________
+// `{channel}` is replaced by settings.update channel
+pub const UPDATE_ENDPOINT: &str = "https://github.com/Muten-Roshi-Sama/tauri-app/releases/download/updater-{channel}/latest.json";
+pub const UPDATE_EVENT: &str = "update-progress";
+
+
+//_____________Enum _________________________
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    fn as_str(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "stable",
+            UpdateChannel::Beta => "beta",
+        }
+    }
+}
+
+
+//_____________Struct _________________________
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub channel: UpdateChannel,
+    pub date: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateProgress {
+    downloaded: u64,
+    total: Option<u64>,
+    done: bool,
+}
+
+// Found by check_for_update, filled by download_update, consumed by install_update
+struct Pending {
+    update: Update,
+    bytes: Option<Vec<u8>>,
+}
+
+
+//_____________Globals _______________________
+static PENDING: Lazy<Mutex<Option<Pending>>> = Lazy::new(|| Mutex::new(None));
+
+
+//_____________fn ____________________________
+fn update_err(err: impl std::fmt::Display) -> AppError {
+    AppError::Update(err.to_string())
+}
+
+fn endpoint(channel: UpdateChannel) -> Result<tauri::Url, AppError> {
+    let template = settings::get().update.endpoint;
+    template
+        .replace("{channel}", channel.as_str())
+        .parse()
+        .map_err(|e| AppError::Settings(format!("Invalid update endpoint {}: {}", template, e)))
+}
+
+// Without the release public key every download would fail its signature check
+fn check_pubkey(app_handle: &AppHandle) -> Result<(), AppError> {
+    let config = app_handle.config().plugins.0.get("updater");
+    let pubkey = config.and_then(|c| c.get("pubkey")).and_then(|k| k.as_str()).unwrap_or_default();
+    if pubkey.trim().is_empty() {
+        return Err(AppError::Update("Updates are not configured in this build (no plugins.updater.pubkey)".into()));
+    }
+    Ok(())
+}
+
+fn take_pending() -> Option<Pending> {
+    PENDING.lock().unwrap_or_else(|p| p.into_inner()).take()
+}
+
+fn set_pending(pending: Option<Pending>) {
+    *PENDING.lock().unwrap_or_else(|p| p.into_inner()) = pending;
+}
+
+/// Check once in the background at startup when settings.update checkOnStartup is on.
+pub fn check_on_startup(app_handle: AppHandle) {
+    if !settings::get().update.check_on_startup {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = check(&app_handle).await {
+            warn!("⚠️ Update check failed: {}", e);
+        }
+    });
+}
+
+pub async fn check(app_handle: &AppHandle) -> Result<Option<UpdateInfo>, AppError> {
+    check_pubkey(app_handle)?;
+    let channel = settings::get().update.channel;
+    let updater = app_handle
+        .updater_builder()
+        .endpoints(vec![endpoint(channel)?])
+        .map_err(update_err)?
+        .build()
+        .map_err(update_err)?;
+
+    let Some(update) = updater.check().await.map_err(update_err)? else {
+        set_pending(None);
+        return Ok(None);
+    };
+    let info = UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        channel,
+        date: update.date.map(|d| d.to_string()),
+        notes: update.body.clone(),
+    };
+    info!("⬆️ Update {} available on {:?} (current {})", info.version, channel, info.current_version);
+    set_pending(Some(Pending { update, bytes: None }));
+    Ok(Some(info))
+}
+
+pub async fn download(app_handle: &AppHandle) -> Result<(), AppError> {
+    let Some(mut pending) = take_pending() else {
+        return Err(AppError::InvalidInput("No update to download, call check_for_update first".into()));
+    };
+
+    let mut downloaded = 0u64;
+    let progress_app = app_handle.clone();
+    let done_app = app_handle.clone();
+    let result = pending
+        .update
+        .download(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                events::emit_all_surfaces(&progress_app, UPDATE_EVENT, UpdateProgress { downloaded, total, done: false });
+            },
+            || {
+                events::emit_all_surfaces(&done_app, UPDATE_EVENT, UpdateProgress { downloaded: 0, total: None, done: true });
+            },
+        )
+        .await;
+
+    match result {
+        Ok(bytes) => {
+            info!("⬇️ Update {} downloaded ({} bytes, signature verified)", pending.update.version, bytes.len());
+            pending.bytes = Some(bytes);
+            set_pending(Some(pending));
+            Ok(())
+        }
+        Err(e) => {
+            // Keep the update so the download can be retried
+            set_pending(Some(pending));
+            Err(update_err(e))
+        }
+    }
+}
+
+pub fn install(app_handle: &AppHandle) -> Result<(), AppError> {
+    let Some(pending) = take_pending() else {
+        return Err(AppError::InvalidInput("No update to install, call check_for_update first".into()));
+    };
+    let Some(bytes) = pending.bytes.as_deref() else {
+        let err = AppError::InvalidInput("Update not downloaded yet, call download_update first".into());
+        set_pending(Some(pending));
+        return Err(err);
+    };
+    info!("📦 Installing update {}", pending.update.version);
+    pending.update.install(bytes).map_err(update_err)?;
+    app_handle.restart()
+}
+
+
+//_____________Commands ________________________
+
+/// The newer version on the configured channel, or null when up to date.
+#[tauri::command]
+pub async fn check_for_update(app_handle: AppHandle) -> Result<Option<UpdateInfo>, AppError> {
+    check(&app_handle).await
+}
+
+#[tauri::command]
+pub async fn download_update(app_handle: AppHandle) -> Result<(), AppError> {
+    download(&app_handle).await
+}
+
+/// Installs the downloaded update; the app restarts on success.
+#[tauri::command]
+pub fn install_update(app_handle: AppHandle) -> Result<(), AppError> {
+    install(&app_handle)
+}
@@ -0,0 +1,241 @@
+// src/watcher.rs
+//
+// Watches folders editors drop footage into, and marker files exported from Premiere, with the notify crate.
+// - settings.watch.paths lists what to watch: a folder (recursive, media files only) or one marker file
+//   (its folder is watched, so exports that replace the file are seen too)
+// - every change is a WATCH_EVENT: `{ "kind": "added" | "renamed" | "removed" | "markersChanged", "path", "from", "clipId" }`
+// - a folder with autoImport registers new (and renamed-in) clips into the current project (database::add_clip);
+//   renames and removals are only reported, the clips already in the project keep their path
+// - `watch_path` / `unwatch_path` edit settings.watch; `apply_settings` re-creates the watcher after a change
+// Usage: `invoke("watch_path", { path: "D:/Footage/day1", autoImport: true })`
+
+use ::notify::event::{ModifyKind, RenameMode};
+use ::notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tracing::{debug, info, warn};
+
+use crate::crash;
+use crate::database;
+use crate::error::AppError;
+use crate::events;
+use crate::settings::{self, WatchSettings};
+
+
+//____________Const___________
+pub const WATCH_EVENT: &str = "watched-files-changed";
+pub const MEDIA_EXTENSIONS: &[&str] = &["mp4", "mov", "mxf", "avi", "mkv", "m4v", "mts", "webm"];
+const MARKER_EXTENSIONS: &[&str] = &["csv", "xml"];
+
+
+//_____________Struct _________________________
+/// One entry of settings.watch.paths.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchedPath {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub auto_import: bool, // folders only: register new clips into the current project
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WatchKind {
+    Added,
+    Renamed,
+    Removed,
+    MarkersChanged, // a watched marker file was written (re-run import_markers to pick it up)
+}
+
+/// Payload of WATCH_EVENT.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchChange {
+    pub kind: WatchKind,
+    pub path: PathBuf,
+    pub from: Option<PathBuf>,  // renamed: the previous path
+    pub clip_id: Option<i64>,   // the clip auto-imported for it
+}
+
+// What the notify callback hands to the async side
+type Raw = Result<Event, ::notify::Error>;
+
+
+//_____________Globals _______________________
+static WATCHER: Lazy<Mutex<Option<RecommendedWatcher>>> = Lazy::new(|| Mutex::new(None));
+static SENDER: OnceCell<UnboundedSender<Raw>> = OnceCell::new();
+
+
+//_____________fn ____________________________
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| extensions.iter().any(|x| x.eq_ignore_ascii_case(e)))
+}
+
+pub fn is_media(path: &Path) -> bool {
+    has_extension(path, MEDIA_EXTENSIONS)
+}
+
+// The watched entry `path` belongs to: a marker file itself, or a folder above a media file
+fn entry_of<'a>(paths: &'a [WatchedPath], path: &Path) -> Option<&'a WatchedPath> {
+    paths.iter().find(|w| if w.path.is_dir() { path.starts_with(&w.path) && is_media(path) } else { path == w.path })
+}
+
+/// Watch settings.watch.paths, dropping the previous watcher. Called from `start` and settings::apply.
+pub fn apply_settings(config: &WatchSettings) {
+    let mut current = WATCHER.lock().unwrap_or_else(|p| p.into_inner());
+    *current = None;
+    if config.paths.is_empty() {
+        return;
+    }
+    let Some(sender) = SENDER.get().cloned() else { return };
+    let mut watcher = match ::notify::recommended_watcher(move |raw: Raw| {
+        let _ = sender.send(raw);
+    }) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            warn!("❌ File watcher unavailable: {}", e);
+            return;
+        }
+    };
+    for entry in &config.paths {
+        let (target, mode) = match entry.path.parent() {
+            Some(parent) if !entry.path.is_dir() => (parent, RecursiveMode::NonRecursive),
+            _ => (entry.path.as_path(), RecursiveMode::Recursive),
+        };
+        match watcher.watch(target, mode) {
+            Ok(()) => info!("👀 Watching {:?}{}", entry.path, if entry.auto_import { " (auto-import)" } else { "" }),
+            Err(e) => warn!("❌ Can't watch {:?}: {}", entry.path, e),
+        }
+    }
+    *current = Some(watcher);
+}
+
+// Turn one notify event into the changes of watched paths
+fn changes(event: &Event, paths: &[WatchedPath]) -> Vec<WatchChange> {
+    let change = |kind, path: &PathBuf, from: Option<&PathBuf>| WatchChange { kind, path: path.clone(), from: from.cloned(), clip_id: None };
+    let watched = |path: &PathBuf| entry_of(paths, path).is_some();
+    let marker_file = |path: &PathBuf| entry_of(paths, path).is_some_and(|w| !w.path.is_dir());
+
+    let mut out = Vec::new();
+    for path in event.paths.iter().filter(|p| marker_file(p)) {
+        if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) && path.exists() {
+            out.push(change(WatchKind::MarkersChanged, path, None));
+        }
+    }
+    let clips: Vec<&PathBuf> = event.paths.iter().filter(|p| watched(p) && !marker_file(p)).collect();
+    match event.kind {
+        EventKind::Create(_) => out.extend(clips.iter().map(|p| change(WatchKind::Added, p, None))),
+        EventKind::Remove(_) => out.extend(clips.iter().map(|p| change(WatchKind::Removed, p, None))),
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+            let (from, to) = (&event.paths[0], &event.paths[1]);
+            match (watched(from), watched(to)) {
+                (_, true) => out.push(change(WatchKind::Renamed, to, Some(from))),
+                (true, false) => out.push(change(WatchKind::Removed, from, None)),
+                _ => {}
+            }
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => out.extend(clips.iter().map(|p| change(WatchKind::Removed, p, None))),
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => out.extend(clips.iter().map(|p| change(WatchKind::Added, p, None))),
+        // macOS doesn't say which side of a rename a path is
+        EventKind::Modify(ModifyKind::Name(_)) => out.extend(
+            clips.iter().map(|p| change(if p.exists() { WatchKind::Added } else { WatchKind::Removed }, p, None)),
+        ),
+        _ => {}
+    }
+    out
+}
+
+// Register the clip of an added / renamed path when its folder has autoImport
+async fn auto_import(change: &mut WatchChange, paths: &[WatchedPath]) {
+    if !matches!(change.kind, WatchKind::Added | WatchKind::Renamed) || !entry_of(paths, &change.path).is_some_and(|w| w.auto_import) {
+        return;
+    }
+    let path = change.path.to_string_lossy().into_owned();
+    match database::blocking(move || database::add_clip(&path, None, None)).await {
+        Ok(clip) => {
+            info!("📥 Auto-imported clip {} ({:?})", clip.id, change.path);
+            change.clip_id = Some(clip.id);
+        }
+        Err(e) => warn!("❌ Auto-import of {:?} failed: {}", change.path, e),
+    }
+}
+
+/// Start handling file changes and watch settings.watch.paths. Call once from setup, after the database.
+pub fn start(app_handle: AppHandle) {
+    let (sender, mut receiver) = unbounded_channel::<Raw>();
+    if SENDER.set(sender).is_err() {
+        warn!("❌ File watcher already started");
+        return;
+    }
+    crash::spawn("file-watcher", async move {
+        while let Some(raw) = receiver.recv().await {
+            let event = match raw {
+                Ok(event) => event,
+                Err(e) => {
+                    debug!("File watcher error: {}", e);
+                    continue;
+                }
+            };
+            let paths = settings::get().watch.paths;
+            for mut change in changes(&event, &paths) {
+                auto_import(&mut change, &paths).await;
+                debug!("Watched file {:?}: {:?}", change.kind, change.path);
+                events::emit_all_surfaces(&app_handle, WATCH_EVENT, change);
+            }
+        }
+    });
+    apply_settings(&settings::get().watch);
+}
+
+fn check_target(path: &Path) -> Result<(), AppError> {
+    if path.is_dir() {
+        return Ok(());
+    }
+    if !path.is_file() {
+        return Err(AppError::InvalidInput(format!("Nothing to watch at {:?}", path)));
+    }
+    if !has_extension(path, MARKER_EXTENSIONS) {
+        return Err(AppError::InvalidInput(format!("{:?} is neither a folder nor a marker export (.csv, .xml)", path)));
+    }
+    Ok(())
+}
+
+
+//_____________Commands ________________________
+
+/// Watch a folder (clips) or a marker file; watching it again only updates `autoImport`.
+/// Example: `invoke("watch_path", { path: "D:/Footage/day1", autoImport: true })` -> the watched paths.
+#[tauri::command]
+pub fn watch_path(app_handle: AppHandle, path: String, auto_import: Option<bool>) -> Result<Vec<WatchedPath>, AppError> {
+    let path = PathBuf::from(path.trim());
+    check_target(&path)?;
+    let auto_import = auto_import.unwrap_or(false) && path.is_dir();
+    let mut paths = settings::get().watch.paths;
+    paths.retain(|w| w.path != path);
+    paths.push(WatchedPath { path, auto_import });
+    settings::update_settings(app_handle, json!({ "watch": { "paths": paths } }))?;
+    Ok(paths)
+}
+
+/// Stop watching `path` (no error if it wasn't watched).
+#[tauri::command]
+pub fn unwatch_path(app_handle: AppHandle, path: String) -> Result<Vec<WatchedPath>, AppError> {
+    let path = PathBuf::from(path.trim());
+    let mut paths = settings::get().watch.paths;
+    paths.retain(|w| w.path != path);
+    settings::update_settings(app_handle, json!({ "watch": { "paths": paths } }))?;
+    Ok(paths)
+}
+
+#[tauri::command]
+pub fn list_watched_paths() -> Vec<WatchedPath> {
+    settings::get().watch.paths
+}
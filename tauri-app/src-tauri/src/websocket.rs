@@ -6,128 +6,466 @@
 // - Uses tokio + tokio-tungstenite
 // - Limits active connections with a Semaphore (MAX_CONNECTIONS)
 // - Sends a JSON "server busy" reply to excess clients and closes the connection
+// - The limits follow the license tier: with the ws_priority feature (pro) up to settings.ws proMaxConnections,
+//   and a client over the limit is queued (a "queued" frame, then up to admissionWaitSecs for a free slot)
+//   before it gets "server busy"
+// - Pings every client and closes connections that stop answering (settings.ws idleTimeoutSecs)
+// - Protocol versioning: clients connect to `ws://host:port/?protocol=N`; the hello frame
+//   answers with the negotiated version and the capabilities it enables
+// - Binary requests (protocol v3) carry an image next to a JSON header, routed to deepface without base64
+//   (analyze_frame, detect_frame, deepface_analyze / deepface_detect / deepface_verify, which also take base64)
+// - `subscribe` / `unsubscribe` opt a connection into pushed event frames (events.rs)
+// - Session tokens let a reloaded panel resume: buffered replies + subscriptions (sessions.rs)
+// - Optional TLS (settings.ws tls): wss:// with a self-signed certificate, see tls.rs
+// - Optional local endpoint (settings.ws localSocket): a Unix socket / named pipe served next to host:port by the
+//   same accept loop, sharing its connection limit (ws_transport.rs)
+// - settings.ws port 0 binds any free port; the listener is advertised over mDNS (discovery.rs)
+// - Every request is recorded in the WS audit (ws_audit.rs: get_ws_audit, replay_ws_request)
+// - The handshake is refused (403) for an Origin / Host outside settings.ws allowedOrigins / allowedHosts,
+//   or a path other than settings.ws path when one is set
+// - Once an access token exists, clients connect with `?token=`; its role (read-only / control) limits the commands (ws_auth.rs)
+// - Compression (protocol v6, `?compression=deflate`): replies and events above settings.ws compressThresholdBytes
+//   are sent as binary frames of raw-deflated JSON (ws_queue.rs). tungstenite has no permessage-deflate, so the
+//   compression is negotiated in the URL instead of the extension header
+// - MessagePack (protocol v7, `?encoding=msgpack`): requests, replies and events as MessagePack binary frames
+//   instead of JSON text, same fields (protocol.rs); binary attachments stay a JSON-client feature
+// - Frames go out through a bounded queue per connection, written by its own task (ws_queue.rs): a client that
+//   stops reading loses pushed events first, then gets closed, instead of blocking its handler and permit
+// - Every client that connects, disconnects or is rejected is reported as a typed `cep-connection` event
+//   (ConnectionEvent: peer, connection id, session, reason), next to the cep-status line; `list_ws_clients`
+//   lists the open ones for the connection manager of the UI
+// - Busy / queued replies and cep-status events carry an i18n code + params next to the localized message (i18n.rs)
+//
+// - `serve_headless` runs the same server without the Tauri app (commands that need it answer with an error),
+//   for the integration tests in tests/ws_server.rs
 //
 // Usage: call `start_websocket_server(app_handle.clone())` from your lib.rs setup block.
 
-use std::sync::Arc; // Arc = atomically reference-counted pointer for sharing between tasks
-use tauri::{AppHandle, Manager, Emitter}; // handle to the Tauri runtime / app (can be used to emit events later)
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex}; // Arc = atomically reference-counted pointer for sharing between tasks
+use std::time::Duration;
+use once_cell::sync::Lazy;
+use tauri::{AppHandle, Manager}; // handle to the Tauri runtime / app (can be used to emit events later)
 use tokio::net::TcpListener;
-use tokio_tungstenite::{accept_async, tungstenite::Message, WebSocketStream};
+use tokio::time::{Instant, MissedTickBehavior};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::Error as WsError;
+use tokio_rustls::TlsAcceptor;
+use tokio_tungstenite::{accept_hdr_async_with_config, tungstenite::Message, WebSocketStream};
 use futures_util::{StreamExt, SinkExt};
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use serde_json::{json, Value};
-use tokio::sync::{Semaphore, OwnedSemaphorePermit};
+use tokio::sync::{mpsc, Semaphore, OwnedSemaphorePermit};
+use tracing::{debug, error, info, warn};
+
+use crate::crash;
+use crate::discovery;
+use crate::error::AppError;
+use crate::events;
+use crate::features;
+use crate::i18n;
+use crate::protocol::{from_msgpack, Encoding, WsRequest, WsResponse};
+use crate::middleware::RateLimiter;
+use crate::registry::{self, CommandContext};
+use crate::sessions;
+use crate::tls;
+use crate::ws_audit;
+use crate::ws_auth::{self, Role};
+use crate::ws_queue::{self, Outbox};
+use crate::ws_transport::{self, TcpTransport, Transport, WsIo};
+use crate::settings::{self, WsSettings};
 
 ///_______ Listening address/port_______________
+// Defaults only: the live values come from settings.ws
 pub const WS_PORT: u16 = 8080;
 pub const WS_HOST: &str = "127.0.0.1";
 pub const MAX_CONNECTIONS: usize = 1;
-
-pub const DEBUG_WS: bool = true;
+pub const PRO_MAX_CONNECTIONS: usize = 4; // default of settings.ws proMaxConnections (ws_priority feature)
+pub const ADMISSION_WAIT: u64 = 10;       // default of settings.ws admissionWaitSecs (ws_priority feature)
+pub const PING_INTERVAL: u64 = 15; // seconds between server pings
+pub const IDLE_TIMEOUT: u64 = 45;  // seconds without any frame (pong included) before a client is dropped
+pub const MAX_MESSAGE_BYTES: usize = 16 * 1024 * 1024;
+pub const RATE_LIMIT_PER_SEC: u32 = 50;
+pub const RATE_LIMIT_BURST: u32 = 100;
+pub const SESSION_RESUME: u64 = 300; // seconds a closed connection's session stays resumable
+// Default of settings.ws allowedOrigins: CEP panels (file:// pages send "null") and the app's own webview
+pub const ALLOWED_ORIGINS: &[&str] = &["null", "file://", "tauri://localhost", "http://tauri.localhost", "https://tauri.localhost"];
+
+/// Protocol spoken by this build. v1 = original replies, v2 = error codes + streamed "partial" frames,
+/// v3 = binary request frames, v4 = event subscriptions, v5 = resumable sessions, v6 = compressed frames,
+/// v7 = MessagePack encoding.
+pub const PROTOCOL_VERSION: u32 = 7;
+pub const MIN_PROTOCOL_VERSION: u32 = 1; // clients without `?protocol=` are treated as v1
+
+pub const DEBUG_WS: bool = true; // default of settings.debug websocket (see logging.rs)
 
 
 //_____________Struct _________________________
 
-/// Generic request structure from client (CEP).
-#[derive(Debug, Deserialize)]
+type WsStream = WebSocketStream<Box<dyn WsIo>>;
+
+/// One open client connection, as reported by `ws_status`.
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct WsRequest {
-    request_id: Option<u64>,     // optional; if present we echo it back in the reply so the client can match responses.
-    command: String,
-    payload: Value,
+pub struct ConnectionInfo {
+    pub id: u64,
+    pub peer: String,
+    pub protocol: u32,         // negotiated protocol version
+    pub role: Role,
+    pub session: Option<String>, // sessions::public_id, v5 clients only
+    pub connected_at: String,  // RFC 3339
+    pub last_seen_secs: u64,   // seconds since the last frame from the client
+    pub messages_in: u64,
+    pub messages_out: u64,
+    pub events_dropped: u64,   // pushed events dropped because the client didn't keep up (ws_queue.rs)
+    #[serde(skip)]
+    last_seen: Instant,
 }
 
-/// Generic reply structure sent back to clients
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct WsResponse {
-    request_id: Option<u64>,
-    status: String,           // `status` is "ok" or "error".
-    command: String,
-    data: Value,             // holds the command result; 
+pub enum ConnectionState {
+    Connected,
+    Disconnected,
+    Rejected,
+}
+
+/// Payload of the `cep-connection` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionEvent {
+    pub state: ConnectionState,
+    pub id: Option<u64>,         // connection id (ws_status / list_ws_clients); None when rejected before it got one
+    pub peer: String,
+    pub session: Option<String>, // sessions::public_id
+    pub reason: Option<String>,  // rejected: "busy" | "forbidden" | "unsupported_protocol" | "unauthorized";
+                                 // disconnected: "closed" | "idle_timeout" | "message_too_large" | "send_queue_full" | "error"
+    pub at: String,              // RFC 3339
+}
+
+/// Snapshot returned by `ws_status`.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsStatus {
+    pub listening: Option<String>,    // "127.0.0.1:8080" while bound
+    pub tls: bool,                    // wss:// (see tls.rs)
+    pub local: Option<String>,        // "unix:/tmp/faceapp.sock" / "pipe:\\.\pipe\faceapp" while open
+    pub max_connections: usize,       // limit of the current license tier
+    pub connections: Vec<ConnectionInfo>,
+    pub total_accepted: u64,
+    pub total_rejected: u64,          // refused because the server was full
+    pub total_timed_out: u64,         // dropped by the heartbeat
+}
+
+#[derive(Default)]
+struct ServerStats {
+    listening: Option<String>,
+    tls: bool,
+    local: Option<String>,
+    connections: BTreeMap<u64, ConnectionInfo>,
+    accepted: u64,
+    rejected: u64,
+    timed_out: u64,
+}
+
+// Removes the connection from the stats (and detaches its session) when its handler ends, however it ends,
+// and reports it as disconnected with `reason` ("error" unless the handler said otherwise)
+struct ConnectionGuard {
+    id: u64,
+    peer: String,
+    session: Option<String>,
+    app_handle: Option<AppHandle>,
+    reason: &'static str,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        stats().connections.remove(&self.id);
+        let session = self.session.as_deref().map(sessions::public_id);
+        connection_event(self.app_handle.as_ref(), ConnectionState::Disconnected, Some(self.id), &self.peer, session, Some(self.reason));
+        let subscriptions = events::subscriptions(self.id);
+        events::unregister(self.id);
+        if let Some(token) = &self.session {
+            sessions::detach(token, self.id, subscriptions);
+        }
+    }
+}
+
+/// A server started by `serve_headless` (or http_api::serve_headless); dropping it stops accepting (open connections run on).
+pub struct HeadlessServer {
+    pub addr: SocketAddr,
+    pub(crate) task: tauri::async_runtime::JoinHandle<()>,
 }
 
+impl Drop for HeadlessServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+
+
+//_____________Globals __________________
+// Accept loop of the running server, so a settings change can replace it
+static SERVER_TASK: Lazy<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>> = Lazy::new(|| Mutex::new(None));
+
+static STATS: Lazy<Mutex<ServerStats>> = Lazy::new(|| Mutex::new(ServerStats::default()));
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
 
 
 //_____________fn __________________
 
+/// Stop accepting on the current address and start again with the current settings.
+/// Connections that are already open keep running until they close.
+pub fn restart_websocket_server(app_handle: AppHandle) {
+    if let Some(task) = SERVER_TASK.lock().unwrap_or_else(|p| p.into_inner()).take() {
+        task.abort();
+        let mut stats = stats();
+        stats.listening = None;
+        stats.local = None;
+    }
+    info!("🔁 Restarting WS server with new settings");
+    start_websocket_server(app_handle);
+}
+
 /// Start the websocket server and keep it running in the background.
 pub fn start_websocket_server(app_handle: AppHandle) {
     ///
     /// This function spawns a background async task (Tauri runtime) that:
-    ///  - binds to WS_HOST:WS_PORT
-    ///  - accepts incoming TCP connections
+    ///  - binds to settings.ws host:port, and settings.ws localSocket when set
+    ///  - accepts incoming TCP (and Unix socket / named pipe) connections
     ///  - upgrades them to WebSocket
-    ///  - enforces MAX_CONNECTIONS using a Semaphore
+    ///  - enforces settings.ws maxConnections using a Semaphore
     ///  - routes messages to `handle_command` and returns responses
     ///  Usage: Call `start_websocket_server(app.handle().clone())` from `lib.rs`'s setup.
     ///
-    // Create a Semaphore with MAX_CONNECTIONS permits and wrap it in Arc so it can be shared.
-    let sem = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+    let config = settings::get().ws;
+
+    // wss:// needs the certificate before anything is accepted; without it the server stays down
+    let tls_acceptor = if config.tls {
+        match tls::acceptor(&app_handle) {
+            Ok(acceptor) => Some(acceptor),
+            Err(e) => {
+                error!("❌ TLS setup failed, WebSocket server not started: {}", e);
+                emit_cep_status(&app_handle, "cep.start_failed_tls");
+                return;
+            }
+        }
+    } else {
+        None
+    };
 
     // Spawn the server in Tauri's async runtime so it doesn't block the main thread.
-    tauri::async_runtime::spawn(async move {
+    let task = crash::spawn("ws-server", async move {
         // Bind a TCP listener to the configured host/port.
-        let listener = TcpListener::bind((WS_HOST, WS_PORT))
-            .await
-            .expect("Failed to bind WebSocket listener");
-
-        if DEBUG_WS {println!("🚀 WS server listening on ws://{}:{}", WS_HOST, WS_PORT);}
-
-        // Accept loop: wait for incoming TCP connections forever.
-        loop {
-            // listener.accept() yields (TcpStream, SocketAddr)
-            match listener.accept().await {
-                Ok((stream, peer)) => {
-                    // Clone handles to move into the spawned task
-                    let sem = sem.clone();
-                    let app_handle_clone = app_handle.clone();
-                    let peer_str = peer.to_string();
-
-                    // Spawn a task for each accepted TCP stream
-                    tauri::async_runtime::spawn(async move {
-                        // Step 1: perform the WebSocket handshake (upgrade)
-                        match accept_async(stream).await {
-                            Ok(ws_stream) => {
-                                // Step 2: try to get a permit (non-blocking).
-                                // If there's a permit, the client is accepted and handled.
-                                // If no permit available, reply "server busy" and close connection.
-
-                                match sem.try_acquire_owned() {
-                                    Ok(permit) => {
-                                        // We hold an OwnedSemaphorePermit (`permit`) for the
-                                        // lifetime of this connection handler. When `permit` drops,
-                                        // the semaphore count is released automatically.
-                                        if let Err(e) = handle_connection(ws_stream, peer_str, app_handle_clone, permit).await {
-                                            eprintln!("❌ Error handling client: {}", e);
-                                        }
+        let listener = match TcpListener::bind((config.host.as_str(), config.port)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("❌ Failed to bind WebSocket listener on {}:{}: {}", config.host, config.port, e);
+                emit_cep_status(&app_handle, "cep.start_failed_port");
+                discovery::withdraw();
+                return;
+            }
+        };
+        // settings.ws port 0 = any free port: from here on, the one we got
+        let port = listener.local_addr().map(|addr| addr.port()).unwrap_or(config.port);
+        let config = WsSettings { port, ..config };
+        discovery::advertise(&app_handle, &config);
+        let tcp = TcpTransport::new(listener, &config.host, config.port);
+        let local = open_local(&config).unwrap_or_else(|e| {
+            // TCP clients are still served
+            error!("❌ {}", e);
+            None
+        });
+        serve(tcp, local, Some(app_handle), config, tls_acceptor).await;
+    });
+
+    *SERVER_TASK.lock().unwrap_or_else(|p| p.into_inner()) = Some(task);
+}
+
+/// Run the server on `config.host`:`config.port` (port 0 = any free one) without the Tauri app: plain ws://,
+/// cep-status only to WS subscribers, commands that need the app answer with an error.
+/// Used by the integration tests (tests/ws_server.rs).
+pub async fn serve_headless(config: WsSettings) -> Result<HeadlessServer, AppError> {
+    let listener = TcpListener::bind((config.host.as_str(), config.port))
+        .await
+        .map_err(|e| AppError::Ws(format!("Failed to bind {}:{}: {}", config.host, config.port, e)))?;
+    let addr = listener.local_addr()?;
+    let config = WsSettings { port: addr.port(), tls: false, ..config };
+    let tcp = TcpTransport::new(listener, &config.host, config.port);
+    let local = open_local(&config)?;
+    let task = crash::spawn("ws-server", serve(tcp, local, None, config, None));
+    Ok(HeadlessServer { addr, task })
+}
+
+// settings.ws localSocket, when set
+fn open_local(config: &WsSettings) -> Result<Option<ws_transport::LocalTransport>, AppError> {
+    if config.local_socket.is_empty() {
+        return Ok(None);
+    }
+    ws_transport::bind_local(&config.local_socket)
+        .map(Some)
+        .map_err(|e| AppError::Ws(format!("Failed to open the local WS endpoint {}: {}", config.local_socket, e)))
+}
+
+// Accept on every endpoint until the task is aborted
+async fn serve<L: Transport>(tcp: TcpTransport, local: Option<L>, app_handle: Option<AppHandle>, config: WsSettings, tls_acceptor: Option<TlsAcceptor>) {
+    // Create a Semaphore with the highest limit of any tier and wrap it in Arc so it can be shared
+    // (one for all endpoints: the limits count every client); `admit` holds lower tiers to their own limit.
+    let sem = Arc::new(Semaphore::new(config.max_connections.max(config.pro_max_connections)));
+    {
+        let mut stats = stats();
+        stats.listening = Some(tcp.address());
+        stats.tls = tls_acceptor.is_some();
+        stats.local = local.as_ref().map(Transport::address);
+    }
+    match local {
+        Some(local) => {
+            tokio::join!(
+                accept_loop(tcp, app_handle.clone(), config.clone(), tls_acceptor, sem.clone()),
+                accept_loop(local, app_handle, config, None, sem), // TLS is for the network only
+            );
+        }
+        None => accept_loop(tcp, app_handle, config, tls_acceptor, sem).await,
+    }
+}
+
+// Accept connections on `transport` until the task is aborted
+async fn accept_loop<T: Transport>(mut transport: T, app_handle: Option<AppHandle>, config: WsSettings, tls_acceptor: Option<TlsAcceptor>, sem: Arc<Semaphore>) {
+    let scheme = if tls_acceptor.is_some() { "wss" } else { "ws" };
+    info!("🚀 WS server listening on {}://{}", scheme, transport.address());
+    let capacity = config.max_connections.max(config.pro_max_connections);
+
+    // Accept loop: wait for incoming connections forever.
+    loop {
+        // transport.accept() yields (stream, peer description)
+        match transport.accept().await {
+            Ok((stream, peer_str)) => {
+                // Clone handles to move into the spawned task
+                let sem = sem.clone();
+                let app_handle_clone = app_handle.clone();
+                let config = config.clone();
+                let tls_acceptor = tls_acceptor.clone();
+
+                // Spawn a task for each accepted TCP stream
+                crash::spawn("ws-connection", async move {
+                    // Step 0: TLS handshake when wss:// is on
+                    let stream: Box<dyn WsIo> = match tls_acceptor {
+                        Some(acceptor) => match acceptor.accept(stream).await {
+                            Ok(tls_stream) => Box::new(tls_stream),
+                            Err(e) => {
+                                warn!("❌ TLS handshake error from {}: {}", peer_str, e);
+                                return;
+                            }
+                        },
+                        None => stream,
+                    };
+
+                    // Step 1: perform the WebSocket handshake (upgrade), reading `?protocol=N&session=TOKEN`
+                    let mut requested_protocol = None;
+                    let mut requested_session = None;
+                    let mut requested_token = None;
+                    let mut requested_compression = false;
+                    let mut requested_encoding = Encoding::Json;
+                    #[allow(clippy::result_large_err)] // ErrorResponse is tungstenite's type
+                    let callback = |req: &Request, resp: Response| -> Result<Response, ErrorResponse> {
+                        // Browser pages of other sites (and DNS rebinding) are refused before the upgrade
+                        if let Err(reason) = check_handshake(req, &config) {
+                            warn!("⛔ Refusing handshake from {}: {}", peer_str, reason);
+                            connection_event(app_handle_clone.as_ref(), ConnectionState::Rejected, None, &peer_str, None, Some("forbidden"));
+                            let mut refusal = ErrorResponse::new(Some(reason));
+                            *refusal.status_mut() = StatusCode::FORBIDDEN;
+                            return Err(refusal);
+                        }
+                        requested_protocol = requested_protocol_version(req);
+                        requested_session = query_param(req, "session").map(str::to_string);
+                        requested_token = query_param(req, "token").map(str::to_string);
+                        requested_compression = query_param(req, "compression") == Some(ws_queue::DEFLATE);
+                        requested_encoding = Encoding::from_query(query_param(req, "encoding"));
+                        Ok(resp)
+                    };
+                    // Messages above the cap fail in tungstenite before they are buffered whole
+                    let ws_config = WebSocketConfig {
+                        max_message_size: Some(config.max_message_bytes),
+                        max_frame_size: Some(config.max_message_bytes),
+                        ..Default::default()
+                    };
+                    match accept_hdr_async_with_config(stream, callback, Some(ws_config)).await {
+                        Ok(mut ws_stream) => {
+                            // Step 2: try to get a permit within the limit of the license tier.
+                            // If there's a permit, the client is accepted and handled.
+                            // If no permit available, reply "server busy" and close connection.
+
+                            match admit(&mut ws_stream, sem, capacity, &config).await {
+                                Some(permit) => {
+                                    // We hold an OwnedSemaphorePermit (`permit`) for the
+                                    // lifetime of this connection handler. When `permit` drops,
+                                    // the semaphore count is released automatically.
+                                    let protocol = requested_protocol.unwrap_or(MIN_PROTOCOL_VERSION);
+                                    if let Err(e) = handle_connection(ws_stream, peer_str, protocol, requested_session, requested_token, requested_compression, requested_encoding, app_handle_clone, config, permit).await {
+                                        error!("❌ Error handling client: {}", e);
                                     }
-                                    Err(_) => {
-                                        // No permits available -> server is at full capacity.
-                                        // Send a short JSON "server busy" message and close connection.
-                                        if let Err(e) = reject_connection_busy(ws_stream, app_handle_clone).await {
-                                            eprintln!("❌ Error sending busy message: {}", e);
-                                        }
+                                }
+                                None => {
+                                    // No permits available -> server is at full capacity.
+                                    // Send a short JSON "server busy" message and close connection.
+                                    if let Err(e) = reject_connection_busy(ws_stream, &peer_str, app_handle_clone.as_ref()).await {
+                                        error!("❌ Error sending busy message: {}", e);
                                     }
                                 }
                             }
-                            Err(e) => {
-                                eprintln!("❌ WebSocket handshake error from {}: {}", peer_str, e);
-                            }
                         }
-                    });
-                }
-                Err(e) => {
-                    eprintln!("❌ Error accepting TCP connection: {}", e);
-                    // continue accepting next connections
-                }
+                        Err(e) => {
+                            warn!("❌ WebSocket handshake error from {}: {}", peer_str, e);
+                        }
+                    }
+                });
+            }
+            Err(e) => {
+                error!("❌ Error accepting connection on {}: {}", transport.address(), e);
+                // continue accepting next connections
             }
         }
-    });
+    }
 }
 
 
-async fn reject_connection_busy(ws_stream: WebSocketStream<tokio::net::TcpStream>, app_handle: AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+// Limit of the current license tier, and how long a client over it may wait for a slot (None = refused at once)
+fn connection_limits(config: &WsSettings) -> (usize, Option<Duration>) {
+    if features::is_enabled(features::WS_PRIORITY) {
+        (config.max_connections.max(config.pro_max_connections), Some(Duration::from_secs(config.admission_wait_secs)))
+    } else {
+        (config.max_connections, None)
+    }
+}
+
+// A permit for a new connection, or None when the tier's limit is reached (and its wait, if any, ran out)
+async fn admit(ws_stream: &mut WsStream, sem: Arc<Semaphore>, capacity: usize, config: &WsSettings) -> Option<OwnedSemaphorePermit> {
+    let (limit, wait) = connection_limits(config);
+    if let Ok(permit) = sem.clone().try_acquire_owned() {
+        // The semaphore holds the highest tier's limit: below it, count the connections already in
+        if capacity - sem.available_permits() <= limit {
+            return Some(permit);
+        }
+        drop(permit);
+    }
+    let wait = wait.filter(|w| !w.is_zero())?;
+
+    let text = i18n::localized("ws.queued", json!({ "secs": wait.as_secs() }));
+    let queued = json!({ "status": "queued", "code": text.code, "params": text.params, "message": text.message });
+    info!("⏳ Queuing connection: {}", queued);
+    ws_stream.send(Message::Text(queued.to_string())).await.ok()?;
+    // The queued client keeps its socket open; a reply arrives when a slot frees up or the wait ends
+    tokio::time::timeout(wait, sem.acquire_owned()).await.ok()?.ok()
+}
+
+async fn reject_connection_busy(ws_stream: WsStream, peer: &str, app_handle: Option<&AppHandle>) -> Result<(), AppError> {
     /// If the server is at capacity, we send a friendly JSON reply and close the socket.
     /// We accept the WebSocket handshake first (client expects it) then send this message.
     /// 
@@ -135,13 +473,13 @@ async fn reject_connection_busy(ws_stream: WebSocketStream<tokio::net::TcpStream
     // split into writer/reader — we only need the writer to send the busy message
     let (mut write, _read) = ws_stream.split();
 
-    let busy = json!({
-        "status": "error",
-        "message": "Server busy: too many connections"
-    });
+    let text = i18n::localized("ws.server_busy", json!({}));
+    let busy = json!({ "status": "error", "code": text.code, "params": text.params, "message": text.message });
 
-    if DEBUG_WS {println!("⛔ Rejecting connection: {}", busy);}
-    emit_cep_status(&app_handle, "⛔ Connection Rejected: Server Busy.");
+    info!("⛔ Rejecting connection: {}", busy);
+    stats().rejected += 1;
+    cep_status(app_handle, "cep.rejected_busy");
+    connection_event(app_handle, ConnectionState::Rejected, None, peer, None, Some("busy"));
 
 
     // send busy message
@@ -154,83 +492,274 @@ async fn reject_connection_busy(ws_stream: WebSocketStream<tokio::net::TcpStream
 }
 
 /// Handles a single accepted & permitted WebSocket connection.
+#[allow(clippy::too_many_arguments)] // the pieces of one handshake, passed once
 async fn handle_connection(
-    ws_stream: WebSocketStream<tokio::net::TcpStream>,
+    ws_stream: WsStream,
     peer: String,
-    app_handle: AppHandle,
+    requested_protocol: u32,
+    requested_session: Option<String>,
+    requested_token: Option<String>,
+    requested_compression: bool,
+    requested_encoding: Encoding,
+    app_handle: Option<AppHandle>,
+    config: WsSettings,
     _permit: OwnedSemaphorePermit,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), AppError> {
 
-    /// We accept a concrete `WebSocketStream<tokio::net::TcpStream>` (the handshake has already been done).
+    /// We accept a concrete `WsStream` (TCP or TLS, the handshake has already been done).
     /// The argument `_permit: OwnedSemaphorePermit` is intentionally kept in the function signature:
     /// by holding it here (not dropping it), the permit remains active while the handler runs.
     /// When this function returns (or panics), `_permit` is dropped and the semaphore frees a slot.4
     /// 
     ///
 
-    if DEBUG_WS {println!("✅ Client connected: {}", peer);}
-    emit_cep_status(&app_handle, "✅ Connected.");
-
-
+    // split into writer + reader halves (writer: Sink, reader: Stream); the writer is fed through a bounded
+    // queue (ws_queue.rs), so a client that stops reading can't block this loop
+    let (write, mut read) = ws_stream.split();
+    let id = NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed);
+    // Compressed frames (v6, `?compression=deflate`): replies / events above compressThresholdBytes
+    let compress_above = (requested_compression && supports(requested_protocol, CAP_COMPRESSION) && config.compression)
+        .then_some(config.compress_threshold_bytes);
+    // MessagePack frames (v7, `?encoding=msgpack`) both ways
+    let encoding = if supports(requested_protocol, CAP_MSGPACK) { requested_encoding } else { Encoding::Json };
+    let out = ws_queue::outbox(write, id, config.send_queue_frames, config.event_overflow, encoding, compress_above);
+
+    // Too old: say why, then close (newer clients are downgraded to PROTOCOL_VERSION)
+    let Some(protocol) = negotiate_protocol(requested_protocol) else {
+        let err = AppError::UnsupportedProtocol(format!(
+            "Protocol v{} is not supported (server speaks v{} to v{})",
+            requested_protocol, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+        ));
+        warn!("⛔ Rejecting {}: {}", peer, err);
+        connection_event(app_handle.as_ref(), ConnectionState::Rejected, None, &peer, None, Some("unsupported_protocol"));
+        let goodbye = json!({ "status": "error", "code": err.code(), "message": err.to_string(), "hello": hello(app_handle.as_ref(), PROTOCOL_VERSION) });
+        out.send(Message::Text(goodbye.to_string()))?;
+        let _ = out.send(Message::Close(Some(CloseFrame {
+            code: CloseCode::Protocol,
+            reason: "Unsupported protocol version".into(),
+        })));
+        return Ok(());
+    };
+
+    // Access token of the URL -> role (ws_auth.rs); no token needed while none exists
+    let role = match ws_auth::authenticate(requested_token.as_deref()) {
+        Ok(role) => role,
+        Err(err) => {
+            warn!("⛔ Rejecting {}: {}", peer, err);
+            connection_event(app_handle.as_ref(), ConnectionState::Rejected, None, &peer, None, Some("unauthorized"));
+            out.send(Message::Text(error_frame(None, "", &err)))?;
+            let _ = out.send(Message::Close(Some(CloseFrame {
+                code: CloseCode::Policy,
+                reason: "Unauthorized".into(),
+            })));
+            return Ok(());
+        }
+    };
+
+    info!("✅ Client connected: {} (protocol v{}, {:?})", peer, protocol, role);
+    cep_status(app_handle.as_ref(), "cep.connected");
+
+    let idle_timeout = Duration::from_secs(config.idle_timeout_secs);
+    let ping_every = Duration::from_secs(config.ping_interval_secs);
+
+    let mut events_rx = events::register(id);
+    let session = supports(protocol, CAP_SESSIONS).then(|| sessions::open(requested_session.as_deref(), id));
+    let token = session.as_ref().map(|s| s.token.clone());
+    let session_id = token.as_deref().map(sessions::public_id);
+    let mut guard = ConnectionGuard { id, peer: peer.clone(), session: token.clone(), app_handle: app_handle.clone(), reason: "error" };
+    {
+        let mut stats = stats();
+        stats.accepted += 1;
+        stats.connections.insert(id, ConnectionInfo {
+            id,
+            peer: peer.clone(),
+            protocol,
+            role,
+            session: session_id.clone(),
+            connected_at: chrono::Utc::now().to_rfc3339(),
+            last_seen_secs: 0,
+            messages_in: 0,
+            messages_out: 0,
+            events_dropped: 0,
+            last_seen: Instant::now(),
+        });
+    }
+    connection_event(app_handle.as_ref(), ConnectionState::Connected, Some(id), &peer, session_id, None);
 
-    // split into writer + reader halves (writer: Sink, reader: Stream)
-    let (mut write, mut read) = ws_stream.split();
 
     // Send an initial "connected" handshake JSON
-    let hello = json!({
-        "status": "ok",
-        "message": "Connected to Rust WS server"
-    });
-    write.send(Message::Text(hello.to_string())).await?;
-    if DEBUG_WS {println!("Handshake to {}: {}", peer, hello);}
+    let mut hello = hello(app_handle.as_ref(), protocol);
+    hello["status"] = json!("ok");
+    hello["message"] = json!("Connected to Rust WS server");
+    hello["role"] = json!(role);
+    hello["compression"] = json!(compress_above.map(|_| ws_queue::DEFLATE));
+    hello["encoding"] = json!(encoding);
+    if let Some(session) = &session {
+        hello["sessionToken"] = json!(session.token);
+        hello["resumed"] = json!(session.resumed);
+        hello["subscriptions"] = json!(session.subscriptions);
+    }
+    out.send(Message::Text(hello.to_string()))?;
+    debug!("Handshake to {}: {}", peer, hello);
+
+    // Replies of requests that finished while the panel was away
+    for frame in session.map(|s| s.buffered).unwrap_or_default() {
+        out.send(Message::Text(frame))?;
+        touch_connection(id, |c| c.messages_out += 1);
+    }
     
 
+    // Heartbeat: ping every `ping_every`; a client silent for `idle_timeout` is considered gone
+    // (CEP panels that crash or sleep never send a Close frame and would keep the permit forever).
+    let mut heartbeat = tokio::time::interval_at(Instant::now() + ping_every, ping_every);
+    heartbeat.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last_seen = Instant::now();
+    // The connection's request budget, spent by the rate_limit middleware (middleware.rs)
+    let limiter = Arc::new(Mutex::new(RateLimiter::new(config.rate_limit_per_sec, config.rate_limit_burst)));
+
     // Loop reading messages from the client
-    while let Some(msg_res) = read.next().await {
-        let msg = msg_res?; // propagate tungstenite errors via ?
-        match msg {
+    loop {
+        let msg_res = tokio::select! {
+            msg = read.next() => match msg {
+                Some(msg_res) => msg_res,
+                None => {
+                    guard.reason = "closed";
+                    break;
+                }
+            },
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() >= idle_timeout {
+                    warn!("💤 {} silent for {:?}, closing", peer, last_seen.elapsed());
+                    stats().timed_out += 1;
+                    cep_status(app_handle.as_ref(), "cep.disconnected_heartbeat");
+                    guard.reason = "idle_timeout";
+                    let _ = out.send(Message::Close(Some(CloseFrame {
+                        code: CloseCode::Away,
+                        reason: "Heartbeat timeout".into(),
+                    })));
+                    break;
+                }
+                if let Err(e) = out.send(Message::Ping(Vec::new())) {
+                    guard.reason = "send_queue_full";
+                    return Err(e);
+                }
+                continue;
+            }
+            // Subscribed events (events.rs), queued between requests. The same queue hands over replies of a
+            // resumed session (sessions.rs): those must not be dropped like events
+            Some(frame) = events_rx.recv() => {
+                if frame.starts_with(r#"{"status":"event""#) {
+                    let delivered = out.push_event(Message::Text(frame));
+                    touch_connection(id, |c| if delivered { c.messages_out += 1 } else { c.events_dropped += 1 });
+                } else {
+                    out.send(Message::Text(frame))?;
+                    touch_connection(id, |c| c.messages_out += 1);
+                }
+                continue;
+            }
+        };
+
+        let msg = match msg_res {
+            Ok(msg) => msg,
+            // Over maxMessageBytes: tell the client why before closing
+            Err(WsError::Capacity(e)) => {
+                let err = AppError::PayloadTooLarge(format!("Message too large (max {} bytes): {}", config.max_message_bytes, e));
+                warn!("⛔ {}: {}", peer, err);
+                guard.reason = "message_too_large";
+                let _ = out.send(Message::Text(error_frame(None, "", &err)));
+                let _ = out.send(Message::Close(Some(CloseFrame {
+                    code: CloseCode::Size,
+                    reason: "Message too large".into(),
+                })));
+                break;
+            }
+            Err(e) => return Err(e.into()), // propagate tungstenite errors
+        };
+        last_seen = Instant::now();
+        touch_connection(id, |c| {
+            c.last_seen = last_seen;
+            if matches!(msg, Message::Text(_) | Message::Binary(_)) {
+                c.messages_in += 1;
+            }
+        });
+
+        // Text = JSON request; Binary = [u32 header length][JSON header][bytes] (see encode_binary_frame)
+        let (parsed, attachment) = match msg {
             Message::Text(text) => {
                 // Received text frame — expected to be JSON containing { request_id?, command, payload }
-                if DEBUG_WS {println!("Received from {}: {}", peer, text);}
-
-                // Try to parse to our typed request. If parse fails, return an "Invalid JSON" reply.
-                match serde_json::from_str::<WsRequest>(&text) {
-                    Ok(req) => {
-                        // Dispatch the command (async handler so we can await DB/cloud later)
-                        let reply = handle_command(req, &app_handle).await;
-
-                        // Serialize reply and send
-                        let resp_text = serde_json::to_string(&reply)?;
-                        if DEBUG_WS {println!("➡️ Sending to {}: {}", peer, resp_text);}
-                        write.send(Message::Text(resp_text)).await?;
-
-                    }
-                    Err(_) => {
-                        // Invalid JSON — reply with an error
-                        let error = json!({
-                            "status": "error",
-                            "message": "Invalid JSON"
-                        });
-                        if DEBUG_WS {println!("Sending error to {}: {}", peer, error);}
-                        write.send(Message::Text(error.to_string())).await?;
+                debug!("Received from {}: {}", peer, text);
+                let parsed = serde_json::from_str::<WsRequest>(&text)
+                    .map_err(|e| AppError::InvalidInput(format!("Invalid JSON: {}", e)));
+                (parsed, None)
+            }
+            // MessagePack clients send their requests as MessagePack binary frames (no attachments)
+            Message::Binary(data) if encoding == Encoding::MsgPack => {
+                debug!("Received {} MessagePack bytes from {}", data.len(), peer);
+                (from_msgpack::<WsRequest>(&data), None)
+            }
+            Message::Binary(data) => {
+                debug!("Received {} bytes from {}", data.len(), peer);
+                if !supports(protocol, CAP_BINARY) {
+                    let err = AppError::UnsupportedProtocol(format!("Binary frames need protocol v3 (connected with v{})", protocol));
+                    out.send(Message::Text(error_frame(None, "", &err)))?;
+                    continue;
+                }
+                match decode_binary_frame(&data) {
+                    Ok((header, bytes)) => {
+                        let parsed = serde_json::from_value::<WsRequest>(header)
+                            .map_err(|e| AppError::InvalidInput(format!("Invalid binary header: {}", e)));
+                        (parsed, Some(bytes.to_vec()))
                     }
+                    Err(err) => (Err(err), None),
                 }
             }
             Message::Close(_) => {
-                println!("🔌 {} disconnected", peer);
-                emit_cep_status(&app_handle, "🛑 Disconnected...");
-
+                info!("🔌 {} disconnected", peer);
+                cep_status(app_handle.as_ref(), "cep.disconnected");
+                guard.reason = "closed";
                 break;
             }
-            Message::Ping(_) | Message::Pong(_) | Message::Binary(_) => {
-                // ignore or handle if you expect binary frames or pings
+            // pongs only refresh `last_seen`; tungstenite answers pings itself
+            _ => continue,
+        };
+
+        // If parse fails, return an "Invalid JSON" reply.
+        let req = match parsed {
+            Ok(req) => req,
+            Err(err) => {
+                let error = json!({
+                    "status": "error",
+                    "code": err.code(),
+                    "message": err.to_string()
+                });
+                debug!("Sending error to {}: {}", peer, error);
+                out.send(Message::Text(error.to_string()))?;
+                continue;
+            }
+        };
+
+        // Dispatch the command; streamed chunks are sent while it runs
+        let reply = handle_command(req, attachment, app_handle.as_ref(), &out, id, protocol, role, &limiter).await?;
+
+        // Serialize reply and send; a reply the client left before getting is kept for its session
+        let resp_text = serde_json::to_string(&reply)?;
+        debug!("➡️ Sending to {}: {}", peer, resp_text);
+        let kept = token.as_ref().map(|_| resp_text.clone());
+        if let Err(e) = out.send(Message::Text(resp_text)) {
+            if let (Some(token), Some(frame)) = (&token, kept) {
+                sessions::buffer(token, id, frame);
             }
-            _ => {}
+            guard.reason = "send_queue_full";
+            return Err(e);
         }
+        touch_connection(id, |c| c.messages_out += 1);
+
+        // The client was waiting on us, not silent
+        last_seen = Instant::now();
     }
 
     // When function ends, `_permit` gets dropped and the semaphore frees one slot.
-    println!("🛑 Connection handler ended for {}", peer);
+    info!("🛑 Connection handler ended for {}", peer);
     
     Ok(())
 }
@@ -240,72 +769,300 @@ async fn handle_connection(
 //_______________PATHS________________________
 
 /// Central async command dispatcher.
-async fn handle_command(req: WsRequest, app_handle: &AppHandle) -> WsResponse {
-    /// Add new commands here. Returns a typed WsResponse which will be serialized and sent back.
+#[allow(clippy::too_many_arguments)] // the connection's state, passed per request
+async fn handle_command(
+    req: WsRequest,
+    attachment: Option<Vec<u8>>,
+    app_handle: Option<&AppHandle>,
+    out: &Outbox,
+    conn_id: u64,
+    protocol: u32,
+    role: Role,
+    limiter: &Arc<Mutex<RateLimiter>>,
+) -> Result<WsResponse, AppError> {
+    /// Commands live in registry.rs (shared with the webview's `run_command`).
+    /// Chunks a handler streams are written as "partial" frames; the returned WsResponse is the final frame.
     ///
-    /// Note: this function is `async` so you can `await` DB/HTTP/AI calls in handlers.
-    /// 
-    if DEBUG_WS {println!("Dispatching command: {} with payload: {}", req.command, req.payload);}
-
-    match req.command.as_str() {
-        "test_server_connection" => {
-            emit_cep_status(app_handle, "✅ Connected (Server connection tested successfully).");
-            WsResponse {
-                request_id: req.request_id,
-                status: "ok".into(),
-                command: req.command,
-                data: json!("Server is alive!"),
+    debug!("Dispatching command: {} with payload: {}", req.command, req.payload);
+    let WsRequest { request_id, command, payload } = req;
+    let (audited_payload, attachment_bytes) = (payload.clone(), attachment.as_ref().map(Vec::len));
+
+    // v1 clients don't know "partial" frames: they get the chunks collected into the final reply
+    let (chunk_tx, mut chunk_rx) = mpsc::unbounded_channel::<Value>();
+    let ctx = match app_handle {
+        Some(app_handle) => CommandContext::new(app_handle.clone()),
+        None => CommandContext::detached(),
+    }
+    .with_role(role)
+    .with_source("ws")
+    .with_rate_limiter(limiter.clone());
+    let ctx = if supports(protocol, CAP_STREAMING) {
+        ctx.with_sink(Arc::new(move |chunk| {
+            let _ = chunk_tx.send(chunk);
+        }))
+    } else {
+        ctx
+    };
+    let ctx = match attachment {
+        Some(bytes) => ctx.with_attachment(bytes),
+        None => ctx,
+    };
+    // Older clients wouldn't understand "event" frames, so they can't subscribe
+    let ctx = if supports(protocol, CAP_EVENTS) { ctx.with_connection(conn_id) } else { ctx };
+
+    let name = command.clone();
+    let started = Instant::now();
+    let dispatch = registry::dispatch(&name, payload, ctx);
+    tokio::pin!(dispatch);
+
+    // A client gone mid-stream doesn't cancel the command: its final reply can still go to a resumed session
+    let mut seq = 0;
+    let mut connected = true;
+    let result = loop {
+        tokio::select! {
+            result = &mut dispatch => break result,
+            Some(chunk) = chunk_rx.recv() => {
+                if connected && send_partial(out, request_id, &command, &mut seq, chunk, conn_id).is_err() {
+                    connected = false;
+                }
             }
-        },
-
-        "fetch_JSON" => WsResponse {
-            request_id: req.request_id,
-            status: "ok".into(),
-            command: req.command,
-            data: req.payload, // echo back the payload for this example
-        },
-
-        "fetch_deepFaceCameraEmotionList" => WsResponse {
-            request_id: req.request_id,
-            status: "ok".into(),
-            command: req.command,
-            data: json!(["happy", "sad", "angry"]),
-        },
-
-        // Unknown command
-        other => WsResponse {
-            request_id: req.request_id,
-            status: "error".into(),
-            command: other.to_string(),
-            data: json!({ "message": "Unknown command" }),
-        },
+        }
+    };
+    // chunks sent right before the handler returned
+    while let Ok(chunk) = chunk_rx.try_recv() {
+        if connected && send_partial(out, request_id, &command, &mut seq, chunk, conn_id).is_err() {
+            connected = false;
+        }
     }
+
+    ws_audit::record(conn_id, &command, request_id, audited_payload, attachment_bytes, started.elapsed(), &result);
+
+    Ok(match result {
+        Ok(data) => WsResponse::ok(request_id, command, data),
+        Err(err) => WsResponse::error(request_id, command, err),
+    })
 }
 
+fn send_partial(
+    out: &Outbox,
+    request_id: Option<u64>,
+    command: &str,
+    seq: &mut u64,
+    chunk: Value,
+    conn_id: u64,
+) -> Result<(), AppError> {
+    let frame = WsResponse::partial(request_id, command.to_string(), *seq, chunk);
+    out.send(Message::Text(serde_json::to_string(&frame)?))?;
+    touch_connection(conn_id, |c| c.messages_out += 1);
+    *seq += 1;
+    Ok(())
+}
 
 
 
 
 
 
-//______________UI Events____________________
-pub fn emit_status_event(app_handle: &AppHandle, event_name: &str, message: &str) {
-    if let Err(e) = app_handle.emit(event_name, message) {
-        eprintln!("Failed to emit {} event: {}", event_name, e);
+
+// Protocol
+
+const CAP_STREAMING: &str = "streaming";
+const CAP_BINARY: &str = "binary_frames";
+const CAP_EVENTS: &str = "events";
+const CAP_SESSIONS: &str = "sessions";
+const CAP_COMPRESSION: &str = "compression";
+const CAP_MSGPACK: &str = "msgpack";
+
+// Capabilities and the protocol version that introduced them
+const CAPABILITIES: &[(&str, u32)] = &[
+    ("commands", 1),      // registry commands + list_commands
+    ("heartbeat", 1),     // server pings, idle timeout
+    ("error_codes", 2),   // `code` on error replies
+    (CAP_STREAMING, 2),   // "partial" frames before the final reply
+    (CAP_BINARY, 3),      // binary requests: header + raw bytes, no base64
+    (CAP_EVENTS, 4),      // subscribe / unsubscribe + pushed "event" frames
+    (CAP_SESSIONS, 5),    // sessionToken in the hello, `?session=` to resume
+    (CAP_COMPRESSION, 6), // `?compression=deflate`: large frames arrive as binary raw-deflate JSON
+    (CAP_MSGPACK, 7),     // `?encoding=msgpack`: requests and replies as MessagePack binary frames
+];
+
+fn capabilities(protocol: u32) -> Vec<&'static str> {
+    CAPABILITIES.iter().filter(|(_, since)| *since <= protocol).map(|(name, _)| *name).collect()
+}
+
+fn supports(protocol: u32, capability: &str) -> bool {
+    capabilities(protocol).contains(&capability)
+}
+
+// `?protocol=2` in the connection URL
+fn query_param<'a>(req: &'a Request, name: &str) -> Option<&'a str> {
+    req.uri()
+        .query()?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+}
+
+// "127.0.0.1:8080" -> "127.0.0.1", "[::1]:8080" -> "::1"
+fn host_name(host: &str) -> &str {
+    if let Some(rest) = host.strip_prefix('[') {
+        return rest.split(']').next().unwrap_or(rest);
     }
+    host.rsplit_once(':').map(|(name, _)| name).unwrap_or(host)
 }
 
-/// Predefined event emitter for CEP status updates
-pub fn emit_cep_status(app_handle: &AppHandle, status: &str) {
-    emit_status_event(app_handle, "cep-status", status);
+// Origin / Host / path of an upgrade request against settings.ws allowedOrigins, allowedHosts and path.
+fn check_handshake(req: &Request, config: &WsSettings) -> Result<(), String> {
+    let header = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok());
+    check_origin_and_host(header("origin"), header("host"), config)?;
+    if !config.path.is_empty() && req.uri().path().trim_end_matches('/') != config.path.trim_end_matches('/') {
+        return Err(format!("No WebSocket endpoint at {}", req.uri().path()));
+    }
+    Ok(())
+}
+
+/// Origin / Host headers against settings.ws allowedOrigins and allowedHosts (also used by http_api.rs).
+/// No Origin = not a browser (scripts, deepface_cli): allowed. Any IP literal or localhost is a valid Host,
+/// other names only when listed (a rebinding attacker's domain never is).
+pub fn check_origin_and_host(origin: Option<&str>, host: Option<&str>, config: &WsSettings) -> Result<(), String> {
+    if let Some(origin) = origin {
+        let origin = origin.trim_end_matches('/');
+        if !config.allowed_origins.iter().any(|o| o.trim_end_matches('/').eq_ignore_ascii_case(origin)) {
+            return Err(format!("Origin {} is not allowed", origin));
+        }
+    }
+    let host = host.ok_or("Missing Host header")?;
+    let name = host_name(host);
+    let known = name.parse::<IpAddr>().is_ok()
+        || name.eq_ignore_ascii_case("localhost")
+        || config.allowed_hosts.iter().any(|h| h.eq_ignore_ascii_case(name));
+    if !known {
+        return Err(format!("Host {} is not allowed", host));
+    }
+    Ok(())
+}
+
+fn requested_protocol_version(req: &Request) -> Option<u32> {
+    query_param(req, "protocol").and_then(|v| v.trim_start_matches('v').parse().ok())
 }
 
+// None = too old to talk to; newer than us = downgraded
+fn negotiate_protocol(requested: u32) -> Option<u32> {
+    (requested >= MIN_PROTOCOL_VERSION).then(|| requested.min(PROTOCOL_VERSION))
+}
 
+fn hello(app_handle: Option<&AppHandle>, protocol: u32) -> Value {
+    let app_version = app_handle.map(|a| a.package_info().version.to_string()).unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string());
+    json!({
+        "protocolVersion": protocol,
+        "serverProtocolVersion": PROTOCOL_VERSION,
+        "minProtocolVersion": MIN_PROTOCOL_VERSION,
+        "appVersion": app_version,
+        "capabilities": capabilities(protocol),
+    })
+}
 
+/// Binary message layout, shared with deepface_cli:
+///   [u32 big-endian N][N bytes UTF-8 JSON header: { requestId, command, payload }][raw bytes]
+pub fn encode_binary_frame(header: &Value, bytes: &[u8]) -> Vec<u8> {
+    let header = header.to_string();
+    let mut frame = Vec::with_capacity(4 + header.len() + bytes.len());
+    frame.extend_from_slice(&(header.len() as u32).to_be_bytes());
+    frame.extend_from_slice(header.as_bytes());
+    frame.extend_from_slice(bytes);
+    frame
+}
 
+pub fn decode_binary_frame(frame: &[u8]) -> Result<(Value, &[u8]), AppError> {
+    let invalid = |msg: &str| AppError::InvalidInput(format!("Invalid binary frame: {}", msg));
+    let len_bytes: [u8; 4] = frame.get(..4).and_then(|b| b.try_into().ok()).ok_or_else(|| invalid("shorter than 4 bytes"))?;
+    let header_len = u32::from_be_bytes(len_bytes) as usize;
+    let header = frame.get(4..4 + header_len).ok_or_else(|| invalid("header length past the end"))?;
+    let header = serde_json::from_slice(header).map_err(|e| invalid(&format!("header is not JSON ({})", e)))?;
+    Ok((header, &frame[4 + header_len..]))
+}
 
+// Error reply for requests that never reached the dispatcher
+fn error_frame(request_id: Option<u64>, command: &str, err: &AppError) -> String {
+    serde_json::to_string(&WsResponse::error(request_id, command.to_string(), err.clone())).unwrap_or_default()
+}
 
+fn stats() -> std::sync::MutexGuard<'static, ServerStats> {
+    STATS.lock().unwrap_or_else(|p| p.into_inner())
+}
 
+fn touch_connection(id: u64, update: impl FnOnce(&mut ConnectionInfo)) {
+    if let Some(info) = stats().connections.get_mut(&id) {
+        update(info);
+    }
+}
 
+/// Current listener and connections.
+pub fn status() -> WsStatus {
+    let stats = stats();
+    WsStatus {
+        listening: stats.listening.clone(),
+        tls: stats.tls,
+        local: stats.local.clone(),
+        max_connections: connection_limits(&settings::get().ws).0,
+        connections: stats
+            .connections
+            .values()
+            .map(|c| ConnectionInfo { last_seen_secs: c.last_seen.elapsed().as_secs(), ..c.clone() })
+            .collect(),
+        total_accepted: stats.accepted,
+        total_rejected: stats.rejected,
+        total_timed_out: stats.timed_out,
+    }
+}
 
 
+
+//______________UI Events____________________
+pub fn emit_status_event(app_handle: &AppHandle, event_name: &str, message: &i18n::Localized) {
+    events::emit_all_surfaces(app_handle, event_name, message);
+}
+
+/// Predefined event emitter for CEP status updates
+/// `code`: an i18n code of the cep.* messages (locales/*.json)
+pub fn emit_cep_status(app_handle: &AppHandle, code: &'static str) {
+    emit_status_event(app_handle, events::CEP_STATUS, &i18n::localized(code, json!({})));
+}
+
+// cep-connection event; like cep-status, only to WS subscribers without the app
+fn connection_event(app_handle: Option<&AppHandle>, state: ConnectionState, id: Option<u64>, peer: &str, session: Option<String>, reason: Option<&str>) {
+    let event = ConnectionEvent {
+        state,
+        id,
+        peer: peer.to_string(),
+        session,
+        reason: reason.map(str::to_string),
+        at: chrono::Utc::now().to_rfc3339(),
+    };
+    match app_handle {
+        Some(app_handle) => events::emit_all_surfaces(app_handle, events::CEP_CONNECTION, event),
+        None => events::publish(events::CEP_CONNECTION, &event),
+    }
+}
+
+// cep-status of a connection; a server without the app only pushes it to WS subscribers
+fn cep_status(app_handle: Option<&AppHandle>, code: &'static str) {
+    match app_handle {
+        Some(app_handle) => emit_cep_status(app_handle, code),
+        None => events::publish(events::CEP_STATUS, &i18n::localized(code, json!({}))),
+    }
+}
+
+
+
+//_____________Commands ________________________
+
+#[tauri::command]
+pub fn ws_status() -> WsStatus {
+    status()
+}
+
+/// Open WS connections, for the connection manager (updates come as `cep-connection` events).
+#[tauri::command]
+pub fn list_ws_clients() -> Vec<ConnectionInfo> {
+    status().connections
+}
@@ -0,0 +1,244 @@
+// src/whisper.rs
+//
+// Speech-to-text with whisper.cpp, the second sidecar next to deepface_cli (externalBin binaries/whisper_cli,
+// whisper.cpp's whisper-cli program).
+// - `transcribe_clip(path, language)` starts a "transcribe_clip" job (jobs.rs): progress events, cancel_job,
+//   completion notification and history like any other job
+// - a run: ffmpeg writes the clip's audio as a 16 kHz mono WAV (media::extract_wav) into the app cache dir,
+//   whisper_cli transcribes it to JSON (-oj) and its "progress = N%" lines become job progress; cancelling the
+//   job kills the process. One transcription at a time: a model takes 0.5 to 4 GB of RAM
+// - the binary is checked against the checksum of the build before every run, as deepface_cli is (build.rs)
+// - the ggml model (settings.whisper model, DEFAULT_MODEL) is downloaded on first use into <app data>/whisper
+//   through models.rs: resumed downloads, MODEL_DOWNLOAD_EVENT progress, settings.models checksums
+// - segments go to the database next to the clip's markers (transcript_segments, replaced by a new
+//   transcription), read back with `get_transcript(clipId)`; times are seconds, like marker timestamps
+
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::Mutex as AsyncMutex;
+use tracing::{debug, info};
+
+use crate::database::{self, Clip, Job, TranscriptSegment};
+use crate::deepFaceProcess;
+use crate::error::AppError;
+use crate::jobs;
+use crate::media;
+use crate::models;
+use crate::payloads::TranscribeClipArgs;
+use crate::settings;
+
+
+//____________Const___________
+pub const DEFAULT_MODEL: &str = "base"; // default of settings.whisper model (multilingual, ~150 MB)
+pub const MODELS_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main"; // default modelUrl
+pub const AUTO_LANGUAGE: &str = "auto"; // let whisper detect the language
+const SIDECAR: &str = "whisper_cli";    // bundle.externalBin binaries/whisper_cli
+const SIDECAR_SHA256: Option<&str> = option_env!("WHISPER_SIDECAR_SHA256"); // set by build.rs
+const MODEL_DIR: &str = "whisper";      // under the app data dir
+const WORK_DIR: &str = "transcribe";    // under the app cache dir, removed after each run
+const ERROR_LINES: usize = 5;           // last stderr lines kept for the error of a failed run
+
+
+//_____________Struct _________________________
+/// A stored transcription.
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    pub clip: Clip,
+    pub language: Option<String>,
+    pub segments: Vec<TranscriptSegment>,
+}
+
+// whisper-cli -oj output (only what we read)
+#[derive(Debug, Deserialize)]
+struct Output {
+    #[serde(default)]
+    result: OutputResult,
+    transcription: Vec<OutputSegment>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OutputResult {
+    language: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OutputSegment {
+    offsets: Offsets,
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Offsets {
+    from: u64, // ms
+    to: u64,
+}
+
+
+//_____________Globals _______________________
+static RUNNING: Lazy<AsyncMutex<()>> = Lazy::new(|| AsyncMutex::new(())); // one transcription at a time
+
+
+//_____________fn ____________________________
+
+/// Where the bundled sidecar binary is expected (it may be missing from a dev build).
+pub fn sidecar_path() -> Result<PathBuf, AppError> {
+    Ok(PathBuf::from(deepFaceProcess::bundled_sidecar(SIDECAR, AppError::Transcription)?.get_program()))
+}
+
+// "auto", or a language code whisper knows ("en", "fr", "haw"…)
+fn check_language(language: &str) -> Result<(), AppError> {
+    if language == AUTO_LANGUAGE || ((2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_lowercase())) {
+        return Ok(());
+    }
+    Err(AppError::InvalidInput(format!("Unknown language '{}' (a code like en or fr, or auto)", language)))
+}
+
+/// Where the ggml models are (settings.whisper modelDir, default <app data>/whisper).
+pub fn model_dir(app_handle: &AppHandle) -> Result<PathBuf, AppError> {
+    match settings::get().whisper.model_dir {
+        Some(dir) => Ok(dir),
+        None => Ok(app_handle
+            .path()
+            .app_data_dir()
+            .map_err(|e| AppError::Io(format!("No app data dir: {}", e)))?
+            .join(MODEL_DIR)),
+    }
+}
+
+/// The ggml file of the configured model, downloaded when missing.
+pub async fn model_path(app_handle: &AppHandle) -> Result<PathBuf, AppError> {
+    let config = settings::get().whisper;
+    let file = format!("ggml-{}.bin", config.model);
+    models::download_file(&format!("whisper {}", config.model), &file, &config.model_url, &model_dir(app_handle)?).await
+}
+
+// A fresh directory under the app cache dir for one run
+fn new_work_dir(app_handle: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app_handle
+        .path()
+        .app_cache_dir()
+        .map_err(|e| AppError::Io(format!("No app cache dir: {}", e)))?
+        .join(WORK_DIR)
+        .join(hex::encode(rand::random::<[u8; 8]>()));
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Register the clip (when it isn't yet), transcribe its audio and store the segments.
+pub async fn transcribe<P>(app_handle: &AppHandle, args: &TranscribeClipArgs, progress: P) -> Result<Transcript, AppError>
+where
+    P: Fn(f64, &str) -> Result<(), AppError>,
+{
+    let config = settings::get().whisper;
+    let language = args.language.clone().unwrap_or(config.language).trim().to_ascii_lowercase();
+    check_language(&language)?;
+    let fps = match args.fps {
+        Some(fps) => Some(fps),
+        None => media::probe(&args.path).await.ok().and_then(|info| info.fps),
+    };
+    let clip = database::add_clip(&args.path, fps, args.project_id)?;
+
+    progress(0.0, "Loading the whisper model")?;
+    let model = model_path(app_handle).await?;
+    progress(0.0, "Waiting for other transcriptions")?;
+    let _one_at_a_time = RUNNING.lock().await;
+
+    let dir = new_work_dir(app_handle)?;
+    let transcribed = async {
+        progress(0.02, "Extracting audio")?;
+        let wav = dir.join("audio.wav");
+        media::extract_wav(&clip.path, &wav).await?;
+        run(&model, &wav, &language, config.threads, &dir.join("transcript"), &progress).await
+    }
+    .await;
+    let _ = std::fs::remove_dir_all(&dir); // the WAV and the JSON were only needed for the run
+    let output = transcribed?;
+
+    let detected = output.result.language.filter(|l| !l.is_empty()).or_else(|| (language != AUTO_LANGUAGE).then_some(language));
+    let segments: Vec<(f64, f64, String)> = output
+        .transcription
+        .into_iter()
+        .map(|s| (s.offsets.from as f64 / 1000.0, s.offsets.to as f64 / 1000.0, s.text.trim().to_string()))
+        .filter(|(_, _, text)| !text.is_empty())
+        .collect();
+    let segments = database::replace_transcript(clip.id, detected.as_deref(), &segments)?;
+    info!("🗣️ Clip {} transcribed: {} segments ({})", clip.id, segments.len(), detected.as_deref().unwrap_or("unknown language"));
+    Ok(Transcript { clip, language: detected, segments })
+}
+
+// whisper_cli on `wav`, JSON written to `<out>.json`; progress from its stderr, whose error kills it
+async fn run<P>(model: &Path, wav: &Path, language: &str, threads: u32, out: &Path, progress: &P) -> Result<Output, AppError>
+where
+    P: Fn(f64, &str) -> Result<(), AppError>,
+{
+    let sidecar = deepFaceProcess::bundled_sidecar(SIDECAR, AppError::Transcription)?;
+    let exe_path = PathBuf::from(sidecar.get_program());
+    deepFaceProcess::verify_sidecar(&exe_path, SIDECAR_SHA256, AppError::Transcription).await?;
+
+    let mut args = vec!["-m".into(), model.to_string_lossy().into_owned(), "-f".into(), wav.to_string_lossy().into_owned()];
+    args.extend(["-l".into(), language.to_string(), "-oj".into(), "-of".into(), out.to_string_lossy().into_owned(), "-pp".into()]);
+    if threads > 0 {
+        args.extend(["-t".into(), threads.to_string()]);
+    }
+    debug!("Running {:?} {:?}", exe_path, args);
+    let mut child = Command::from(sidecar)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null()) // the segments as text: we read the JSON file
+        .stderr(Stdio::piped())
+        .kill_on_drop(true) // dropped by a failed progress call (job cancelled): the process goes with it
+        .spawn()
+        .map_err(|e| AppError::Transcription(format!("Failed to start whisper_cli: {}", e)))?;
+
+    let stderr = child.stderr.take().ok_or_else(|| AppError::Transcription("whisper_cli has no stderr".into()))?;
+    let mut lines = BufReader::new(stderr).lines();
+    let mut last_lines: Vec<String> = Vec::new();
+    progress(0.05, "Transcribing")?;
+    while let Some(line) = lines.next_line().await? {
+        // "whisper_print_progress_callback: progress =  42%"
+        if let Some(percent) = line.split("progress =").nth(1).and_then(|p| p.trim().trim_end_matches('%').parse::<f64>().ok()) {
+            progress(0.05 + 0.9 * percent / 100.0, &format!("Transcribing ({:.0}%)", percent))?;
+            continue;
+        }
+        debug!("[whisper_cli stderr] {}", line);
+        if last_lines.len() == ERROR_LINES {
+            last_lines.remove(0);
+        }
+        last_lines.push(line);
+    }
+    let status = child.wait().await?;
+    if !status.success() {
+        return Err(AppError::Transcription(format!("whisper_cli failed ({}): {}", status, last_lines.join(" | "))));
+    }
+
+    let json_path = PathBuf::from(format!("{}.json", out.to_string_lossy()));
+    let text = tokio::fs::read(&json_path).await.map_err(|e| AppError::Transcription(format!("No transcript written by whisper_cli: {}", e)))?;
+    serde_json::from_slice(&text).map_err(|e| AppError::Transcription(format!("Unreadable whisper_cli transcript: {}", e)))
+}
+
+/// Start a "transcribe_clip" job.
+pub fn start(app_handle: &AppHandle, path: String, language: Option<String>) -> Result<Job, AppError> {
+    jobs::start(app_handle, "transcribe_clip", json!({ "path": path, "language": language }))
+}
+
+
+//_____________Commands ________________________
+
+/// Example: `invoke("transcribe_clip", { path: "C:/…/clip.mp4", language: "fr" })` -> the queued Job
+/// (language omitted = settings.whisper language, "auto" = detected)
+#[tauri::command]
+pub fn transcribe_clip(app_handle: AppHandle, path: String, language: Option<String>) -> Result<Job, AppError> {
+    start(&app_handle, path, language)
+}
+
+/// Example: `invoke("get_transcript", { clipId: 3 })` -> `[{ id, clipId: 3, start: 0.0, end: 2.36, text: "…", language: "fr" }]`
+#[tauri::command]
+pub async fn get_transcript(clip_id: i64) -> Result<Vec<TranscriptSegment>, AppError> {
+    database::blocking(move || database::list_transcript(clip_id)).await
+}
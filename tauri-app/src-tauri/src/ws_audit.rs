@@ -0,0 +1,219 @@
+// src/ws_audit.rs
+//
+// Audit trail of the WS requests, to debug CEP panels without watching stdout.
+// - every request handled by websocket.rs is recorded: connection, command, requestId, status / error code,
+//   duration, and its payload and reply truncated to AUDIT_PREVIEW_CHARS
+// - the last AUDIT_BUFFER entries stay in memory (`get_ws_audit(n)`), and each entry is appended as a JSON line
+//   to ws_audit.jsonl in the app log dir, rotated at AUDIT_FILE_BYTES with AUDIT_FILES old files kept
+// - `replay_ws_request(id)` runs a recorded request again through the registry, with its full payload (kept in
+//   memory up to REPLAY_MAX_BYTES; binary requests can't be replayed, their bytes aren't kept)
+// Recording follows settings.debug wsAudit.
+
+use chrono::Utc;
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{debug, info, warn};
+
+use crate::error::AppError;
+use crate::registry::{self, CommandContext};
+use crate::settings;
+
+
+//____________Const___________
+pub const WS_AUDIT: bool = true;          // default of settings.debug wsAudit
+pub const AUDIT_BUFFER: usize = 500;      // entries kept in memory
+const AUDIT_PREVIEW_CHARS: usize = 2000;  // payload / reply text kept per entry
+const REPLAY_MAX_BYTES: usize = 256 * 1024; // larger payloads are recorded but not replayable
+const AUDIT_FILE: &str = "ws_audit.jsonl";
+const AUDIT_FILE_BYTES: u64 = 5 * 1024 * 1024;
+const AUDIT_FILES: usize = 3;             // ws_audit.1.jsonl … ws_audit.3.jsonl
+
+
+//_____________Struct _________________________
+/// One recorded request.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: u64,
+    pub at: String,                 // RFC 3339, when the request arrived
+    pub connection: u64,
+    pub command: String,
+    pub request_id: Option<u64>,
+    pub status: &'static str,       // "ok" / "error"
+    pub code: Option<&'static str>, // AppError::code() of an error
+    pub duration_ms: u64,
+    pub payload: Value,             // truncated to a string when too long
+    pub attachment_bytes: Option<usize>,
+    pub reply: Value,               // data of the reply, truncated the same way
+    pub replayable: bool,
+    #[serde(skip)]
+    full_payload: Option<Value>,
+}
+
+/// Result of `replay_ws_request`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayResult {
+    pub original: AuditEntry,
+    pub status: &'static str,
+    pub code: Option<&'static str>,
+    pub duration_ms: u64,
+    pub reply: Value, // not truncated
+}
+
+struct AuditLog {
+    next_id: u64,
+    entries: VecDeque<AuditEntry>,
+}
+
+
+//_____________Globals _______________________
+static LOG: Lazy<Mutex<AuditLog>> = Lazy::new(|| Mutex::new(AuditLog { next_id: 1, entries: VecDeque::new() }));
+static AUDIT_PATH: OnceCell<PathBuf> = OnceCell::new();
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+
+//_____________fn ____________________________
+
+/// Remember where the audit file goes. Call from setup.
+pub fn init(app_handle: &AppHandle) {
+    match app_handle.path().app_log_dir() {
+        Ok(dir) => {
+            let _ = AUDIT_PATH.set(dir.join(AUDIT_FILE));
+        }
+        Err(e) => warn!("❌ No app log dir, the WS audit stays in memory: {}", e),
+    }
+}
+
+// The value, or its JSON text cut to AUDIT_PREVIEW_CHARS when longer
+fn preview(value: &Value) -> Value {
+    let text = value.to_string();
+    if text.chars().count() <= AUDIT_PREVIEW_CHARS {
+        return value.clone();
+    }
+    let cut: String = text.chars().take(AUDIT_PREVIEW_CHARS).collect();
+    Value::String(format!("{}… ({} bytes)", cut, text.len()))
+}
+
+fn error_reply(err: &AppError) -> Value {
+    serde_json::to_value(err).unwrap_or_else(|_| json!({ "message": err.to_string() }))
+}
+
+/// Record a handled request (websocket.rs, after the dispatch).
+pub fn record(
+    connection: u64,
+    command: &str,
+    request_id: Option<u64>,
+    payload: Value,
+    attachment_bytes: Option<usize>,
+    elapsed: Duration,
+    result: &Result<Value, AppError>,
+) {
+    if !settings::get().debug.ws_audit {
+        return;
+    }
+    let (status, code, reply) = match result {
+        Ok(data) => ("ok", None, preview(data)),
+        Err(err) => ("error", Some(err.code()), preview(&error_reply(err))),
+    };
+    let replayable = attachment_bytes.is_none() && payload.to_string().len() <= REPLAY_MAX_BYTES;
+    let mut entry = AuditEntry {
+        id: 0,
+        at: (Utc::now() - chrono::Duration::from_std(elapsed).unwrap_or_default()).to_rfc3339(),
+        connection,
+        command: command.to_string(),
+        request_id,
+        status,
+        code,
+        duration_ms: elapsed.as_millis() as u64,
+        payload: preview(&payload),
+        attachment_bytes,
+        reply,
+        replayable,
+        full_payload: replayable.then_some(payload),
+    };
+    {
+        let mut log = LOG.lock().unwrap_or_else(|p| p.into_inner());
+        entry.id = log.next_id;
+        log.next_id += 1;
+        if log.entries.len() >= AUDIT_BUFFER {
+            log.entries.pop_front();
+        }
+        log.entries.push_back(entry.clone());
+    }
+    append(&entry);
+}
+
+// ws_audit.jsonl -> ws_audit.1.jsonl -> … once it is over AUDIT_FILE_BYTES
+fn rotate(path: &PathBuf) {
+    let numbered = |n: usize| path.with_extension(format!("{}.jsonl", n));
+    let _ = std::fs::remove_file(numbered(AUDIT_FILES));
+    for n in (1..AUDIT_FILES).rev() {
+        let _ = std::fs::rename(numbered(n), numbered(n + 1));
+    }
+    let _ = std::fs::rename(path, numbered(1));
+}
+
+fn append(entry: &AuditEntry) {
+    let Some(path) = AUDIT_PATH.get() else { return };
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|p| p.into_inner());
+    if std::fs::metadata(path).map(|m| m.len() >= AUDIT_FILE_BYTES).unwrap_or(false) {
+        rotate(path);
+    }
+    let Ok(line) = serde_json::to_string(entry) else { return };
+    let written = OpenOptions::new().create(true).append(true).open(path).and_then(|mut file| writeln!(file, "{}", line));
+    if let Err(e) = written {
+        debug!("Failed to write {:?}: {}", path, e);
+    }
+}
+
+/// Last `n` entries, newest first.
+pub fn recent(n: usize) -> Vec<AuditEntry> {
+    let log = LOG.lock().unwrap_or_else(|p| p.into_inner());
+    log.entries.iter().rev().take(n).cloned().collect()
+}
+
+fn find(id: u64) -> Option<AuditEntry> {
+    let log = LOG.lock().unwrap_or_else(|p| p.into_inner());
+    log.entries.iter().find(|e| e.id == id).cloned()
+}
+
+/// Run entry `id` again (not streamed, no WS connection: subscribe & co. answer as from the webview).
+pub async fn replay(app_handle: AppHandle, id: u64) -> Result<ReplayResult, AppError> {
+    let original = find(id).ok_or_else(|| AppError::InvalidInput(format!("No WS audit entry {} (only the last {} are kept)", id, AUDIT_BUFFER)))?;
+    let Some(payload) = original.full_payload.clone() else {
+        return Err(AppError::InvalidInput(format!("WS audit entry {} can't be replayed (binary or too large)", id)));
+    };
+    info!("🔁 Replaying WS request {} ({})", id, original.command);
+    let started = std::time::Instant::now();
+    let result = registry::dispatch(&original.command, payload, CommandContext::new(app_handle)).await;
+    let (status, code, reply) = match result {
+        Ok(data) => ("ok", None, data),
+        Err(err) => ("error", Some(err.code()), error_reply(&err)),
+    };
+    Ok(ReplayResult { original, status, code, duration_ms: started.elapsed().as_millis() as u64, reply })
+}
+
+
+//_____________Commands ________________________
+
+/// Example: `invoke("get_ws_audit", { n: 50 })` -> `[{ id, at, connection, command, requestId, status, code, durationMs, payload, reply, replayable }]`
+#[tauri::command]
+pub fn get_ws_audit(n: Option<usize>) -> Vec<AuditEntry> {
+    recent(n.unwrap_or(AUDIT_BUFFER))
+}
+
+/// Debug: run a recorded request again and return its new reply.
+/// Example: `invoke("replay_ws_request", { id: 42 })`
+#[tauri::command]
+pub async fn replay_ws_request(app_handle: AppHandle, id: u64) -> Result<ReplayResult, AppError> {
+    replay(app_handle, id).await
+}
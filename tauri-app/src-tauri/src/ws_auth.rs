@@ -0,0 +1,224 @@
+// src/ws_auth.rs
+//
+// Who may do what over the WebSocket: access tokens bound to a role, checked by the registry dispatcher.
+// - `control` runs every command; `readOnly` only the READ_ONLY_COMMANDS and the commands starting with
+//   READ_ONLY_PREFIXES (plugin commands excluded), anything else answers `permission_denied` (monitoring
+//   dashboards that can't mutate state)
+// - while no token exists every client is `control`, as before; once one does, clients connect with
+//   `ws://host:port/?protocol=5&token=<token>` and a missing / unknown token gets `unauthorized` and is closed
+// - tokens are created / revoked from the webview only (`create_ws_token`, `revoke_ws_token`), never over WS;
+//   only their SHA-256 is kept, in the OS keychain (secrets.rs), so `create_ws_token` is the one time it's shown
+// - a connection keeps the role it opened with; revoking a token closes nothing, it only refuses new connections
+// - tokens that can't be read (keychain error, corrupt entry) never mean "no token": every client is refused
+//   `unauthorized` until they can, and creating / revoking one retries the read first
+// The webview, deep links and audit replays run as `control`.
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use tracing::{error, info};
+
+use crate::error::AppError;
+use crate::secrets;
+
+
+//____________Const___________
+const TOKEN_BYTES: usize = 32;
+const READ_ONLY_PREFIXES: &[&str] = &["list_", "get_", "search_"];
+const READ_ONLY_COMMANDS: &[&str] = &[
+    "test_server_connection",
+    "subscribe",
+    "unsubscribe",
+    "protocol_schema",
+    "deepface_status",
+    "ws_status",
+    "probe_media",
+    "detect_scenes",
+    "greet",
+];
+
+
+//_____________Struct _________________________
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Role {
+    ReadOnly,
+    #[default]
+    Control,
+}
+
+/// A token as listed by `list_ws_tokens` (the token itself is never kept).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenInfo {
+    pub name: String,
+    pub role: Role,
+    pub created_at: String,
+}
+
+/// `create_ws_token` result: the only time the token is shown.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewToken {
+    #[serde(flatten)]
+    pub info: TokenInfo,
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredToken {
+    #[serde(flatten)]
+    info: TokenInfo,
+    sha256: String, // hex
+}
+
+
+//_____________Globals _______________________
+static TOKENS: Lazy<RwLock<Vec<StoredToken>>> = Lazy::new(|| RwLock::new(Vec::new()));
+static TOKENS_UNAVAILABLE: AtomicBool = AtomicBool::new(false); // the stored tokens couldn't be read: refuse everyone
+
+
+//_____________fn ____________________________
+
+/// Load the tokens from the keychain. Call from setup, after secrets::init and before the WS server.
+pub fn init() {
+    let unavailable = match secrets::get_secret(secrets::WS_ACCESS_TOKENS) {
+        Ok(Some(json)) => match serde_json::from_str::<Vec<StoredToken>>(&json) {
+            Ok(tokens) => {
+                info!("🔑 {} WS access token(s), clients must authenticate", tokens.len());
+                *TOKENS.write().unwrap_or_else(|p| p.into_inner()) = tokens;
+                false
+            }
+            Err(e) => {
+                error!("❌ Unreadable WS access tokens, every WS client is refused: {}", e);
+                true
+            }
+        },
+        Ok(None) => false,
+        Err(e) => {
+            error!("❌ Failed to read the WS access tokens, every WS client is refused: {}", e);
+            true
+        }
+    };
+    TOKENS_UNAVAILABLE.store(unavailable, Ordering::Relaxed);
+}
+
+// Tokens are only changed once the stored ones are known, so an unreadable keychain can't lose them
+fn ensure_loaded() -> Result<(), AppError> {
+    if TOKENS_UNAVAILABLE.load(Ordering::Relaxed) {
+        init();
+    }
+    if TOKENS_UNAVAILABLE.load(Ordering::Relaxed) {
+        return Err(AppError::Io("The WS access tokens can't be read from the keychain (see the log)".into()));
+    }
+    Ok(())
+}
+
+fn save(tokens: &[StoredToken]) -> Result<(), AppError> {
+    if tokens.is_empty() {
+        return secrets::delete_secret(secrets::WS_ACCESS_TOKENS);
+    }
+    let json = serde_json::to_string(tokens).map_err(|e| AppError::Io(format!("Failed to serialize WS tokens: {}", e)))?;
+    secrets::set_secret(secrets::WS_ACCESS_TOKENS, &json)
+}
+
+fn digest(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+// Equal without returning early, so timing doesn't tell how much of a guess was right
+fn same(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Role of a client connecting with `token` (the `?token=` of its URL).
+pub fn authenticate(token: Option<&str>) -> Result<Role, AppError> {
+    if TOKENS_UNAVAILABLE.load(Ordering::Relaxed) {
+        return Err(AppError::Unauthorized("The access tokens of this server can't be read: access is closed".into()));
+    }
+    let tokens = TOKENS.read().unwrap_or_else(|p| p.into_inner());
+    if tokens.is_empty() {
+        return Ok(Role::Control);
+    }
+    let token = token.filter(|t| !t.is_empty()).ok_or_else(|| AppError::Unauthorized("This server needs an access token (?token=…)".into()))?;
+    let sha256 = digest(token);
+    tokens
+        .iter()
+        .find(|t| same(&t.sha256, &sha256))
+        .map(|t| t.info.role)
+        .ok_or_else(|| AppError::Unauthorized("Unknown or revoked access token".into()))
+}
+
+/// Commands a `readOnly` client may run. Plugin commands (`<plugin>.<command>`, plugins.rs) never are,
+/// whatever their plugin is called.
+pub fn is_read_only(command: &str) -> bool {
+    if command.contains('.') {
+        return false;
+    }
+    READ_ONLY_COMMANDS.contains(&command) || READ_ONLY_PREFIXES.iter().any(|p| command.starts_with(p))
+}
+
+/// Refuse `command` to a role that can't run it (the auth middleware, middleware.rs).
+pub fn check(role: Role, command: &str) -> Result<(), AppError> {
+    if role == Role::ReadOnly && !is_read_only(command) {
+        return Err(AppError::PermissionDenied(format!("{} needs a control token (this client is read-only)", command)));
+    }
+    Ok(())
+}
+
+pub fn list() -> Vec<TokenInfo> {
+    TOKENS.read().unwrap_or_else(|p| p.into_inner()).iter().map(|t| t.info.clone()).collect()
+}
+
+
+//_____________Commands ________________________
+
+/// New WS access token for `name` (replacing a token of that name); `role` defaults to readOnly.
+/// Example: `invoke("create_ws_token", { name: "dashboard", role: "readOnly" })` -> `{ name, role, createdAt, token }`
+#[tauri::command]
+pub fn create_ws_token(name: String, role: Option<Role>) -> Result<NewToken, AppError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::InvalidInput("A WS token needs a name".into()));
+    }
+    let mut bytes = [0u8; TOKEN_BYTES];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let token = hex::encode(bytes);
+    let info = TokenInfo { name, role: role.unwrap_or(Role::ReadOnly), created_at: Utc::now().to_rfc3339() };
+
+    ensure_loaded()?;
+    let mut tokens = TOKENS.write().unwrap_or_else(|p| p.into_inner());
+    let mut updated = tokens.clone();
+    updated.retain(|t| t.info.name != info.name);
+    updated.push(StoredToken { info: info.clone(), sha256: digest(&token) });
+    save(&updated)?;
+    *tokens = updated;
+    info!("🔑 WS token '{}' created ({:?})", info.name, info.role);
+    Ok(NewToken { info, token })
+}
+
+/// Returns false when no token had this name.
+#[tauri::command]
+pub fn revoke_ws_token(name: String) -> Result<bool, AppError> {
+    ensure_loaded()?;
+    let mut tokens = TOKENS.write().unwrap_or_else(|p| p.into_inner());
+    let mut updated = tokens.clone();
+    updated.retain(|t| t.info.name != name);
+    if updated.len() == tokens.len() {
+        return Ok(false);
+    }
+    save(&updated)?;
+    *tokens = updated;
+    info!("🔑 WS token '{}' revoked", name);
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn list_ws_tokens() -> Vec<TokenInfo> {
+    list()
+}
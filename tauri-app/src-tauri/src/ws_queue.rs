@@ -0,0 +1,236 @@
+// src/ws_queue.rs
+//
+// Outbound queue of a WS connection, so a client that stops reading can't stall its handler (and its permit).
+// - websocket.rs never writes to the socket itself: it queues frames in an `Outbox`, and a writer task per
+//   connection sends them in order, each write bounded by WRITE_TIMEOUT
+// - the queue holds at most settings.ws sendQueueFrames frames:
+//   - replies, partial chunks, pings, close frames: `send` fails with an explicit error once it's full
+//     (the handler then closes the connection: "send_queue_full")
+//   - pushed events: `push_event` drops one per settings.ws eventOverflow, `dropOldest` (the oldest queued event,
+//     so the client catches up on recent state) or `dropNewest` (the one being pushed); replies are never dropped
+// - compression (websocket.rs, v6 clients that asked for it): a text frame of at least the threshold is written
+//   as a binary frame holding its raw-deflate (RFC 1951) bytes, `new DecompressionStream("deflate-raw")` in the
+//   panel; counters ws.compression.bytes_in / bytes_out / frames show what it saves in get_metrics
+// - MessagePack (v7 clients connected with `?encoding=msgpack`): every text frame is re-encoded to a binary
+//   MessagePack frame here, off the handler (not compressed: MessagePack frames aren't text)
+// - dropping the `Outbox` (handler ended) lets the writer flush what's queued, then it closes the socket
+
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use futures_util::{Sink, SinkExt};
+use serde_json::Value;
+use std::io::Write;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, warn};
+
+use crate::crash;
+use crate::error::AppError;
+use crate::metrics;
+use crate::protocol::{self, Encoding};
+
+
+//____________Const___________
+pub const SEND_QUEUE_FRAMES: usize = 256; // default of settings.ws sendQueueFrames
+pub const COMPRESS_THRESHOLD: usize = 16 * 1024; // default of settings.ws compressThresholdBytes: smaller frames aren't worth it
+pub const DEFLATE: &str = "deflate"; // `?compression=` value, and `compression` of the hello when on
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10); // one frame to the socket, then the client is considered gone
+
+
+//_____________Struct _________________________
+/// What `push_event` drops when the queue is full (settings.ws eventOverflow).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EventOverflow {
+    #[default]
+    DropOldest,
+    DropNewest,
+}
+
+struct Queued {
+    message: Message,
+    event: bool, // a pushed event: may be dropped
+}
+
+#[derive(Default)]
+struct State {
+    frames: VecDeque<Queued>,
+    closed: bool, // no more frames: the Outbox is gone, or the writer failed
+}
+
+struct Shared {
+    state: Mutex<State>,
+    ready: Notify,
+    limit: usize,
+    overflow: EventOverflow,
+}
+
+/// Sending side of a connection's queue, held by its handler.
+pub struct Outbox {
+    shared: Arc<Shared>,
+    connection: u64,
+}
+
+
+//_____________fn ____________________________
+
+impl Shared {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|p| p.into_inner())
+    }
+
+    fn close(&self) {
+        self.state().closed = true;
+        self.ready.notify_one();
+    }
+
+    // Next frame to write; None once closed and flushed
+    async fn next(&self) -> Option<Message> {
+        loop {
+            let ready = self.ready.notified();
+            {
+                let mut state = self.state();
+                if let Some(queued) = state.frames.pop_front() {
+                    return Some(queued.message);
+                }
+                if state.closed {
+                    return None;
+                }
+            }
+            ready.await;
+        }
+    }
+}
+
+/// Queue for `connection`, written to `write` by its own task in `encoding`; JSON text frames of `compress_above`
+/// bytes or more are deflated (None = never).
+pub fn outbox<S>(write: S, connection: u64, limit: usize, overflow: EventOverflow, encoding: Encoding, compress_above: Option<usize>) -> Outbox
+where
+    S: Sink<Message> + Unpin + Send + 'static,
+    S::Error: std::fmt::Display,
+{
+    let shared = Arc::new(Shared { state: Mutex::new(State::default()), ready: Notify::new(), limit: limit.max(1), overflow });
+    crash::spawn("ws-writer", write_loop(write, shared.clone(), connection, encoding, compress_above));
+    Outbox { shared, connection }
+}
+
+// The JSON text frames queued by websocket.rs, in the connection's encoding
+fn encode(message: Message, encoding: Encoding, compress_above: Option<usize>) -> Message {
+    match (message, encoding) {
+        (Message::Text(text), Encoding::MsgPack) => {
+            match serde_json::from_str::<Value>(&text).map_err(AppError::from).and_then(|frame| protocol::to_msgpack(&frame)) {
+                Ok(bytes) => Message::Binary(bytes),
+                Err(e) => {
+                    debug!("MessagePack encoding failed, sending JSON: {}", e);
+                    Message::Text(text)
+                }
+            }
+        }
+        (message, _) => compress(message, compress_above),
+    }
+}
+
+// A large text frame as a binary frame of its raw-deflate bytes; anything else as is
+fn compress(message: Message, compress_above: Option<usize>) -> Message {
+    let Message::Text(text) = message else { return message };
+    if !compress_above.is_some_and(|threshold| text.len() >= threshold) {
+        return Message::Text(text);
+    }
+    let mut encoder = DeflateEncoder::new(Vec::with_capacity(text.len() / 4), Compression::fast());
+    match encoder.write_all(text.as_bytes()).and_then(|_| encoder.finish()) {
+        Ok(bytes) => {
+            metrics::incr("ws.compression.frames");
+            metrics::add("ws.compression.bytes_in", text.len() as u64);
+            metrics::add("ws.compression.bytes_out", bytes.len() as u64);
+            Message::Binary(bytes)
+        }
+        Err(e) => {
+            debug!("Deflate failed, sending uncompressed: {}", e);
+            Message::Text(text)
+        }
+    }
+}
+
+async fn write_loop<S>(mut write: S, shared: Arc<Shared>, connection: u64, encoding: Encoding, compress_above: Option<usize>)
+where
+    S: Sink<Message> + Unpin,
+    S::Error: std::fmt::Display,
+{
+    while let Some(message) = shared.next().await {
+        let closing = matches!(message, Message::Close(_));
+        let message = encode(message, encoding, compress_above);
+        match tokio::time::timeout(WRITE_TIMEOUT, write.send(message)).await {
+            Ok(Ok(())) if !closing => {}
+            Ok(Ok(())) => break,
+            Ok(Err(e)) => {
+                debug!("Connection {}: write failed: {}", connection, e);
+                break;
+            }
+            Err(_) => {
+                warn!("⏳ Connection {}: client not reading for {:?}, dropping its queue", connection, WRITE_TIMEOUT);
+                break;
+            }
+        }
+    }
+    // Later sends fail instead of queuing for nobody
+    shared.close();
+    let _ = write.close().await;
+}
+
+impl Outbox {
+    /// Queue a reply (or ping / close frame). Fails when the queue is full or the socket is gone.
+    pub fn send(&self, message: Message) -> Result<(), AppError> {
+        let mut state = self.shared.state();
+        if state.closed {
+            return Err(AppError::Ws("Connection closed".into()));
+        }
+        if state.frames.len() >= self.shared.limit {
+            metrics::incr("ws.send_queue_full");
+            return Err(AppError::Ws(format!("Send queue full ({} frames): the client isn't reading", self.shared.limit)));
+        }
+        state.frames.push_back(Queued { message, event: false });
+        drop(state);
+        self.shared.ready.notify_one();
+        Ok(())
+    }
+
+    /// Queue a pushed event, dropping one per the overflow policy when full. Returns false when one was dropped.
+    pub fn push_event(&self, message: Message) -> bool {
+        let mut state = self.shared.state();
+        if state.closed {
+            return false;
+        }
+        let mut dropped = false;
+        if state.frames.len() >= self.shared.limit {
+            dropped = true;
+            let oldest = state.frames.iter().position(|q| q.event);
+            match (self.shared.overflow, oldest) {
+                (EventOverflow::DropOldest, Some(i)) => {
+                    state.frames.remove(i);
+                }
+                // Nothing droppable but replies, or dropNewest: this one goes
+                _ => {
+                    metrics::incr("ws.events_dropped");
+                    debug!("Connection {}: send queue full, event dropped", self.connection);
+                    return false;
+                }
+            }
+            metrics::incr("ws.events_dropped");
+            debug!("Connection {}: send queue full, oldest event dropped", self.connection);
+        }
+        state.frames.push_back(Queued { message, event: true });
+        drop(state);
+        self.shared.ready.notify_one();
+        !dropped
+    }
+}
+
+impl Drop for Outbox {
+    fn drop(&mut self) {
+        self.shared.close();
+    }
+}
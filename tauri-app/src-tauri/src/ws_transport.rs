@@ -0,0 +1,123 @@
+// src/ws_transport.rs
+//
+// Where WS connections come from, so one accept loop (websocket.rs) serves all of them.
+// - `Transport`: accepts the next connection as a byte stream, with a peer description for logs / ws_status
+// - `TcpTransport`: settings.ws host:port, the endpoint CEP panels use (TLS applies to it only)
+// - `LocalTransport`: settings.ws localSocket, a Unix domain socket or a Windows named pipe for local tools
+//   that shouldn't open a TCP port; same protocol, same connection limits and dispatcher
+// The Unix socket file is created owner-only (0600) and replaces a stale socket left by a previous run or
+// listener (a restart doesn't wait for the old one to close); a path that exists and isn't a socket is never removed.
+
+use std::future::Future;
+use std::io;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+
+#[cfg(unix)]
+use tokio::net::UnixListener;
+#[cfg(windows)]
+use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+
+
+//_____________Struct _________________________
+
+/// A connection of any transport: TCP, TLS, Unix socket or named pipe.
+pub trait WsIo: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> WsIo for T {}
+
+pub trait Transport: Send + 'static {
+    /// Next connection, and its peer ("127.0.0.1:52814", "unix:/tmp/faceapp.sock", …).
+    fn accept(&mut self) -> impl Future<Output = io::Result<(Box<dyn WsIo>, String)>> + Send;
+    /// What it listens on, for logs and ws_status.
+    fn address(&self) -> String;
+}
+
+pub struct TcpTransport {
+    listener: TcpListener,
+    address: String,
+}
+
+#[cfg(unix)]
+pub struct UnixTransport {
+    listener: UnixListener,
+    path: String,
+}
+
+#[cfg(windows)]
+pub struct PipeTransport {
+    name: String,
+    next: NamedPipeServer, // instance the next client connects to
+}
+
+#[cfg(unix)]
+pub type LocalTransport = UnixTransport;
+#[cfg(windows)]
+pub type LocalTransport = PipeTransport;
+
+
+//_____________fn ____________________________
+
+impl TcpTransport {
+    pub fn new(listener: TcpListener, host: &str, port: u16) -> Self {
+        TcpTransport { listener, address: format!("{}:{}", host, port) }
+    }
+}
+
+impl Transport for TcpTransport {
+    async fn accept(&mut self) -> io::Result<(Box<dyn WsIo>, String)> {
+        let (stream, peer) = self.listener.accept().await?;
+        Ok((Box::new(stream), peer.to_string()))
+    }
+
+    fn address(&self) -> String {
+        self.address.clone()
+    }
+}
+
+/// Open settings.ws localSocket: a Unix socket at `path`.
+#[cfg(unix)]
+pub fn bind_local(path: &str) -> io::Result<LocalTransport> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    // A socket file outlives the process that bound it; anything else at that path is left alone
+    if std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_socket()) {
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(UnixTransport { listener, path: path.to_string() })
+}
+
+#[cfg(unix)]
+impl Transport for UnixTransport {
+    async fn accept(&mut self) -> io::Result<(Box<dyn WsIo>, String)> {
+        let (stream, _) = self.listener.accept().await?; // clients of a Unix socket are unnamed
+        Ok((Box::new(stream), format!("unix:{}", self.path)))
+    }
+
+    fn address(&self) -> String {
+        format!("unix:{}", self.path)
+    }
+}
+
+/// Open settings.ws localSocket: the named pipe `name` (`\\.\pipe\…`).
+#[cfg(windows)]
+pub fn bind_local(name: &str) -> io::Result<LocalTransport> {
+    // first_pipe_instance: fail instead of sharing the name with another process
+    let next = ServerOptions::new().first_pipe_instance(true).create(name)?;
+    Ok(PipeTransport { name: name.to_string(), next })
+}
+
+#[cfg(windows)]
+impl Transport for PipeTransport {
+    async fn accept(&mut self) -> io::Result<(Box<dyn WsIo>, String)> {
+        self.next.connect().await?;
+        // A pipe instance serves one client: open the next one before handing this one over
+        let connected = std::mem::replace(&mut self.next, ServerOptions::new().create(&self.name)?);
+        Ok((Box::new(connected), format!("pipe:{}", self.name)))
+    }
+
+    fn address(&self) -> String {
+        format!("pipe:{}", self.name)
+    }
+}
@@ -0,0 +1,77 @@
+// tests/http_api.rs
+//
+// The HTTP API (POST /command) end to end, without Tauri: `serve_http_headless` on a free port, a reqwest client.
+// - the WS reply envelope, HTTP status per error code, Origin checks
+// Run with `cargo test --test http_api`.
+
+use _tauri_local::testing::{self, HeadlessServer, WsSettings, COMMAND_PATH};
+use serde_json::{json, Value};
+
+
+//_____________fn ____________________________
+
+async fn server() -> HeadlessServer {
+    let config = WsSettings { host: "127.0.0.1".into(), http_port: 0, ..WsSettings::default() };
+    testing::serve_http_headless(config).await.expect("headless HTTP API")
+}
+
+// POST `body` to /command: (HTTP status, JSON reply)
+async fn post(server: &HeadlessServer, body: String, origin: Option<&str>) -> (u16, Value) {
+    let mut request = reqwest::Client::new()
+        .post(format!("http://{}{}", server.addr, COMMAND_PATH))
+        .header("Content-Type", "application/json")
+        .body(body);
+    if let Some(origin) = origin {
+        request = request.header("Origin", origin);
+    }
+    let response = request.send().await.expect("HTTP request");
+    let status = response.status().as_u16();
+    (status, response.json().await.expect("reply is not JSON"))
+}
+
+
+//_____________Tests ________________________
+
+#[tokio::test]
+async fn commands_reply_with_the_ws_envelope() {
+    let server = server().await;
+
+    let (status, alive) = post(&server, json!({ "requestId": 1, "command": "test_server_connection" }).to_string(), None).await;
+    assert_eq!(status, 200);
+    assert_eq!(alive, json!({ "requestId": 1, "status": "ok", "command": "test_server_connection", "data": "Server is alive!" }));
+
+    let (status, greeting) = post(&server, json!({ "command": "greet", "payload": { "name": "Ada" } }).to_string(), None).await;
+    assert_eq!(status, 200);
+    assert!(greeting["data"].as_str().is_some_and(|s| s.contains("Ada")));
+}
+
+#[tokio::test]
+async fn errors_map_to_http_statuses() {
+    let server = server().await;
+
+    let (status, unknown) = post(&server, json!({ "command": "no_such_command" }).to_string(), None).await;
+    assert_eq!(status, 404);
+    assert_eq!(unknown["status"], "error");
+    assert_eq!(unknown["code"], "unknown_command");
+
+    let (status, invalid) = post(&server, json!({ "command": "greet", "payload": { "name": 42 } }).to_string(), None).await;
+    assert_eq!(status, 400);
+    assert_eq!(invalid["code"], "invalid_payload");
+
+    let (status, not_json) = post(&server, "{ not json".into(), None).await;
+    assert_eq!(status, 400);
+    assert_eq!(not_json["code"], "invalid_input");
+}
+
+#[tokio::test]
+async fn foreign_origins_are_refused() {
+    let server = server().await;
+
+    let body = json!({ "command": "test_server_connection" }).to_string();
+    let (status, refused) = post(&server, body.clone(), Some("https://evil.example")).await;
+    assert_eq!(status, 403);
+    assert_eq!(refused["code"], "permission_denied");
+
+    let (status, _) = post(&server, body, Some("file://")).await;
+    assert_eq!(status, 200);
+}
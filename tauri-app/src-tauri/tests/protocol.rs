@@ -0,0 +1,103 @@
+// tests/protocol.rs
+//
+// The WS frames (protocol.rs) on the wire: field names, JSON and MessagePack round trips, and protocol.schema.json.
+// After changing a frame, regenerate the schema with `UPDATE_PROTOCOL_SCHEMA=1 cargo test --test protocol`.
+
+use _tauri_local::protocol::{self, Encoding, EventFrame, ResponseStatus, WsRequest, WsResponse, SCHEMA_FILE};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fmt::Debug;
+use std::path::PathBuf;
+
+
+//_____________fn ____________________________
+
+// `value` -> JSON -> back, equal to itself; returns the JSON
+fn round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: &T) -> Value {
+    let wire = serde_json::to_value(value).expect("serialize");
+    let back: T = serde_json::from_value(wire.clone()).expect("deserialize");
+    assert_eq!(&back, value);
+    wire
+}
+
+fn schema_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(SCHEMA_FILE)
+}
+
+
+//_____________Tests ________________________
+
+#[test]
+fn request_round_trips_in_camel_case() {
+    let request = WsRequest { request_id: Some(7), command: "greet".into(), payload: json!({ "name": "Ada" }) };
+    assert_eq!(round_trip(&request), json!({ "requestId": 7, "command": "greet", "payload": { "name": "Ada" } }));
+}
+
+#[test]
+fn request_fields_are_optional_as_for_v1_clients() {
+    let request: WsRequest = serde_json::from_str(r#"{ "command": "list_commands" }"#).expect("minimal request");
+    assert_eq!(request, WsRequest { request_id: None, command: "list_commands".into(), payload: Value::Null });
+    assert!(serde_json::from_str::<WsRequest>(r#"{ "request_id": 1 }"#).is_err());
+}
+
+#[test]
+fn responses_round_trip() {
+    let ok = WsResponse::ok(Some(1), "greet".into(), json!("Hello"));
+    assert_eq!(round_trip(&ok), json!({ "requestId": 1, "status": "ok", "command": "greet", "data": "Hello" }));
+
+    let partial = WsResponse::partial(Some(2), "analyze_clip".into(), 0, json!({ "progress": 0.5 }));
+    let wire = round_trip(&partial);
+    assert_eq!(wire["status"], "partial");
+    assert_eq!(wire["seq"], 0);
+    assert!(wire.get("code").is_none());
+
+    let error = WsResponse {
+        request_id: None,
+        status: ResponseStatus::Error,
+        seq: None,
+        code: Some("unknown_command".into()),
+        command: "nope".into(),
+        data: json!({ "message": "Unknown command: nope" }),
+    };
+    let wire = round_trip(&error);
+    assert_eq!(wire["requestId"], Value::Null);
+    assert_eq!(wire["code"], "unknown_command");
+    assert!(wire.get("seq").is_none());
+}
+
+#[test]
+fn event_frames_round_trip() {
+    let frame = EventFrame::new("marker-added", json!({ "id": 3 }));
+    assert_eq!(round_trip(&frame), json!({ "status": "event", "event": "marker-added", "data": { "id": 3 } }));
+    assert!(serde_json::from_value::<EventFrame>(json!({ "status": "ok", "event": "x", "data": null })).is_err());
+}
+
+#[test]
+fn frames_round_trip_as_msgpack() {
+    let request = WsRequest { request_id: Some(3), command: "analyze_clip".into(), payload: json!({ "scores": [0.25, 0.5, 1.0] }) };
+    let bytes = protocol::to_msgpack(&request).expect("encode");
+    assert_eq!(protocol::from_msgpack::<WsRequest>(&bytes).expect("decode"), request);
+
+    let reply = WsResponse::partial(Some(3), "analyze_clip".into(), 1, json!({ "frame": 12, "emotion": "happy" }));
+    let bytes = protocol::to_msgpack(&reply).expect("encode");
+    assert_eq!(protocol::from_msgpack::<WsResponse>(&bytes).expect("decode"), reply);
+
+    assert!(protocol::from_msgpack::<WsRequest>(b"not msgpack").is_err());
+    assert_eq!(Encoding::from_query(Some("msgpack")), Encoding::MsgPack);
+    assert_eq!(Encoding::from_query(Some("cbor")), Encoding::Json);
+}
+
+#[test]
+fn schema_file_is_up_to_date() {
+    let schema = protocol::schema();
+    let path = schema_path();
+    if std::env::var_os("UPDATE_PROTOCOL_SCHEMA").is_some() {
+        let text = serde_json::to_string_pretty(&schema).expect("schema to JSON");
+        std::fs::write(&path, text + "\n").expect("write the schema");
+        return;
+    }
+    let text = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{:?}: {}", path, e));
+    let committed: Value = serde_json::from_str(&text).expect("protocol.schema.json is not JSON");
+    assert_eq!(committed, schema, "{} is out of date: run UPDATE_PROTOCOL_SCHEMA=1 cargo test --test protocol", SCHEMA_FILE);
+}
@@ -0,0 +1,380 @@
+// tests/ws_server.rs
+//
+// The WS server end to end, without Tauri: `serve_headless` on a free port, a tokio-tungstenite client.
+// - handshake (hello frame, protocol negotiation, Origin / path checks), request / reply, error codes, compression, MessagePack
+// - connection limit (busy reply), rate limiting, macros, pushed events after `subscribe`, cep-connection lifecycle events
+// - the local endpoint (settings.ws localSocket) next to TCP, on Unix
+// Run with `cargo test --test ws_server`.
+
+use _tauri_local::protocol::{self, WsRequest};
+use _tauri_local::testing::{self, HeadlessServer, WsSettings, PROTOCOL_VERSION};
+use flate2::read::DeflateDecoder;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::io::Read;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+
+//____________Const___________
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+
+//_____________Struct _________________________
+type Client = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+
+//_____________fn ____________________________
+
+async fn server(config: WsSettings) -> HeadlessServer {
+    let config = WsSettings { host: "127.0.0.1".into(), port: 0, ..config };
+    testing::serve_headless(config).await.expect("headless WS server")
+}
+
+// Connect with `?protocol=N` (None = no query, a v1 client)
+async fn connect(server: &HeadlessServer, protocol: Option<u32>) -> Client {
+    let query = protocol.map(|p| format!("?protocol={}", p)).unwrap_or_default();
+    let (client, _) = connect_async(format!("ws://{}/{}", server.addr, query)).await.expect("WS connect");
+    client
+}
+
+// Next text frame as JSON (pings and pongs skipped)
+async fn next_json(client: &mut Client) -> Value {
+    loop {
+        let msg = tokio::time::timeout(REPLY_TIMEOUT, client.next())
+            .await
+            .expect("no frame before the timeout")
+            .expect("connection closed")
+            .expect("WS error");
+        if let Message::Text(text) = msg {
+            return serde_json::from_str(&text).expect("frame is not JSON");
+        }
+    }
+}
+
+async fn send(client: &mut Client, request: Value) {
+    client.send(Message::Text(request.to_string())).await.expect("WS send");
+}
+
+// Send a request and wait for its final reply ("partial" and "event" frames skipped)
+async fn request(client: &mut Client, request_id: u64, command: &str, payload: Value) -> Value {
+    send(client, json!({ "requestId": request_id, "command": command, "payload": payload })).await;
+    loop {
+        let frame = next_json(client).await;
+        if frame["requestId"] == json!(request_id) && frame["status"] != "partial" {
+            return frame;
+        }
+    }
+}
+
+// Connected client, hello frame already read
+async fn hello_client(server: &HeadlessServer) -> Client {
+    let mut client = connect(server, Some(PROTOCOL_VERSION)).await;
+    assert_eq!(next_json(&mut client).await["status"], "ok");
+    client
+}
+
+
+//_____________Tests ________________________
+
+#[tokio::test]
+async fn hello_frame_negotiates_the_protocol() {
+    // Three clients at once: above the default maxConnections
+    let server = server(WsSettings { max_connections: 3, pro_max_connections: 3, ..WsSettings::default() }).await;
+
+    let mut client = connect(&server, Some(PROTOCOL_VERSION)).await;
+    let hello = next_json(&mut client).await;
+    assert_eq!(hello["status"], "ok");
+    assert_eq!(hello["protocolVersion"], PROTOCOL_VERSION);
+    assert_eq!(hello["serverProtocolVersion"], PROTOCOL_VERSION);
+    assert!(hello["sessionToken"].is_string());
+    assert_eq!(hello["role"], "control"); // no access token exists
+    assert!(hello["capabilities"].as_array().is_some_and(|c| !c.is_empty()));
+
+    // Newer clients are downgraded, clients without `?protocol=` get v1
+    let mut newer = connect(&server, Some(PROTOCOL_VERSION + 1)).await;
+    assert_eq!(next_json(&mut newer).await["protocolVersion"], PROTOCOL_VERSION);
+    let mut legacy = connect(&server, None).await;
+    assert_eq!(next_json(&mut legacy).await["protocolVersion"], testing::MIN_PROTOCOL_VERSION);
+}
+
+#[tokio::test]
+async fn foreign_origins_and_paths_are_refused() {
+    let server = server(WsSettings { path: "/cep".into(), ..WsSettings::default() }).await;
+
+    let mut request = format!("ws://{}/cep", server.addr).into_client_request().expect("WS request");
+    request.headers_mut().insert("Origin", HeaderValue::from_static("https://evil.example"));
+    match connect_async(request).await {
+        Err(WsError::Http(response)) => assert_eq!(response.status(), 403),
+        other => panic!("cross-origin handshake not refused: {:?}", other.map(|_| ())),
+    }
+    assert!(connect_async(format!("ws://{}/other", server.addr)).await.is_err());
+
+    // A CEP panel (file:// page) on the right path gets in
+    let mut request = format!("ws://{}/cep?protocol={}", server.addr, PROTOCOL_VERSION).into_client_request().expect("WS request");
+    request.headers_mut().insert("Origin", HeaderValue::from_static("file://"));
+    let (mut client, _) = connect_async(request).await.expect("WS connect");
+    assert_eq!(next_json(&mut client).await["status"], "ok");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn local_socket_serves_the_same_commands() {
+    let path = std::env::temp_dir().join(format!("ws_server_test_{}.sock", std::process::id()));
+    let local_socket = path.to_string_lossy().into_owned();
+    // Two clients at once, one per endpoint
+    let server = server(WsSettings { local_socket, max_connections: 2, pro_max_connections: 2, ..WsSettings::default() }).await;
+
+    let stream = tokio::net::UnixStream::connect(&path).await.expect("connect to the local socket");
+    let (mut client, _) = tokio_tungstenite::client_async(format!("ws://localhost/?protocol={}", PROTOCOL_VERSION), stream)
+        .await
+        .expect("WS handshake over the local socket");
+    let hello = loop {
+        if let Message::Text(text) = client.next().await.expect("connection closed").expect("WS error") {
+            break serde_json::from_str::<Value>(&text).expect("frame is not JSON");
+        }
+    };
+    assert_eq!(hello["status"], "ok");
+
+    client
+        .send(Message::Text(json!({ "requestId": 1, "command": "test_server_connection", "payload": {} }).to_string()))
+        .await
+        .expect("WS send");
+    let reply = loop {
+        if let Message::Text(text) = client.next().await.expect("connection closed").expect("WS error") {
+            break serde_json::from_str::<Value>(&text).expect("frame is not JSON");
+        }
+    };
+    assert_eq!(reply["requestId"], 1);
+    assert_eq!(reply["data"], "Server is alive!");
+
+    // TCP is served alongside
+    hello_client(&server).await;
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn large_frames_are_deflated_when_asked() {
+    let server = server(WsSettings { compress_threshold_bytes: 512, max_connections: 2, pro_max_connections: 2, ..WsSettings::default() }).await;
+    let url = format!("ws://{}/?protocol={}&compression=deflate", server.addr, PROTOCOL_VERSION);
+    let (mut client, _) = connect_async(url).await.expect("WS connect");
+    let hello = next_json(&mut client).await; // small: still text
+    assert_eq!(hello["compression"], "deflate");
+
+    send(&mut client, json!({ "requestId": 1, "command": "list_commands", "payload": {} })).await;
+    let compressed = loop {
+        let msg = tokio::time::timeout(REPLY_TIMEOUT, client.next()).await.expect("no reply").expect("connection closed").expect("WS error");
+        if let Message::Binary(bytes) = msg {
+            break bytes;
+        }
+    };
+    let mut text = String::new();
+    DeflateDecoder::new(compressed.as_slice()).read_to_string(&mut text).expect("raw deflate");
+    let reply: Value = serde_json::from_str(&text).expect("inflated frame is not JSON");
+    assert_eq!(reply["requestId"], 1);
+    assert!(text.len() > compressed.len());
+
+    // Without `?compression=` nothing changes
+    let mut plain = hello_client(&server).await;
+    assert_eq!(request(&mut plain, 2, "list_commands", json!({})).await["status"], "ok");
+}
+
+#[tokio::test]
+async fn msgpack_clients_get_msgpack_frames() {
+    let server = server(WsSettings::default()).await;
+    let url = format!("ws://{}/?protocol={}&encoding=msgpack", server.addr, PROTOCOL_VERSION);
+    let (mut client, _) = connect_async(url).await.expect("WS connect");
+
+    // Next binary frame, decoded
+    async fn next_msgpack(client: &mut Client) -> Value {
+        loop {
+            let msg = tokio::time::timeout(REPLY_TIMEOUT, client.next()).await.expect("no frame").expect("connection closed").expect("WS error");
+            match msg {
+                Message::Binary(bytes) => return protocol::from_msgpack(&bytes).expect("frame is not MessagePack"),
+                Message::Text(text) => panic!("text frame to a MessagePack client: {}", text),
+                _ => {}
+            }
+        }
+    }
+    let hello = next_msgpack(&mut client).await;
+    assert_eq!(hello["status"], "ok");
+    assert_eq!(hello["encoding"], "msgpack");
+
+    let request = WsRequest { request_id: Some(1), command: "greet".into(), payload: json!({ "name": "Ada" }) };
+    client.send(Message::Binary(protocol::to_msgpack(&request).expect("encode"))).await.expect("WS send");
+    let reply = next_msgpack(&mut client).await;
+    assert_eq!(reply["requestId"], 1);
+    assert_eq!(reply["status"], "ok");
+    assert!(reply["data"].as_str().is_some_and(|s| s.contains("Ada")));
+}
+
+#[tokio::test]
+async fn unsupported_protocol_is_refused() {
+    let server = server(WsSettings::default()).await;
+
+    let mut client = connect(&server, Some(0)).await;
+    let goodbye = next_json(&mut client).await;
+    assert_eq!(goodbye["status"], "error");
+    assert_eq!(goodbye["code"], "unsupported_protocol");
+    assert_eq!(goodbye["hello"]["serverProtocolVersion"], PROTOCOL_VERSION);
+}
+
+#[tokio::test]
+async fn commands_reply_with_their_request_id() {
+    let server = server(WsSettings::default()).await;
+    let mut client = hello_client(&server).await;
+
+    let alive = request(&mut client, 1, "test_server_connection", json!({})).await;
+    assert_eq!(alive["status"], "ok");
+    assert_eq!(alive["command"], "test_server_connection");
+    assert_eq!(alive["data"], "Server is alive!");
+
+    let greeting = request(&mut client, 2, "greet", json!({ "name": "Ada" })).await;
+    assert_eq!(greeting["status"], "ok");
+    assert!(greeting["data"].as_str().is_some_and(|s| s.contains("Ada")));
+
+    let commands = request(&mut client, 3, "list_commands", json!({})).await;
+    let names: Vec<&str> = commands["data"].as_array().expect("command list").iter().filter_map(|c| c["name"].as_str()).collect();
+    assert!(names.contains(&"greet"));
+    assert!(names.contains(&"subscribe"));
+    let read_only = |name: &str| commands["data"].as_array().unwrap().iter().find(|c| c["name"] == name).map(|c| c["readOnly"].clone());
+    assert_eq!(read_only("list_markers"), Some(json!(true)));
+    assert_eq!(read_only("delete_marker"), Some(json!(false)));
+}
+
+#[tokio::test]
+async fn errors_carry_their_code() {
+    let server = server(WsSettings::default()).await;
+    let mut client = hello_client(&server).await;
+
+    let unknown = request(&mut client, 1, "no_such_command", json!({})).await;
+    assert_eq!(unknown["status"], "error");
+    assert_eq!(unknown["code"], "unknown_command");
+
+    let invalid = request(&mut client, 2, "greet", json!({ "name": 42 })).await;
+    assert_eq!(invalid["status"], "error");
+    assert_eq!(invalid["code"], "invalid_payload");
+
+    // No app behind a headless server
+    let needs_app = request(&mut client, 3, "get_tls_info", json!({})).await;
+    assert_eq!(needs_app["status"], "error");
+    assert_eq!(needs_app["code"], "ws");
+
+    client.send(Message::Text("{ not json".into())).await.expect("WS send");
+    let not_json = next_json(&mut client).await;
+    assert_eq!(not_json["status"], "error");
+    assert_eq!(not_json["code"], "invalid_input");
+
+    // The connection is still usable after errors
+    assert_eq!(request(&mut client, 4, "test_server_connection", json!({})).await["status"], "ok");
+}
+
+#[tokio::test]
+async fn connections_over_the_limit_are_busy() {
+    let server = server(WsSettings { max_connections: 1, pro_max_connections: 1, ..WsSettings::default() }).await;
+    let mut first = hello_client(&server).await;
+
+    let mut second = connect(&server, Some(PROTOCOL_VERSION)).await;
+    let busy = next_json(&mut second).await;
+    assert_eq!(busy["status"], "error");
+    assert_eq!(busy["code"], "ws.server_busy");
+    assert!(busy["message"].is_string());
+
+    // The first client isn't affected
+    assert_eq!(request(&mut first, 1, "test_server_connection", json!({})).await["status"], "ok");
+}
+
+#[tokio::test]
+async fn requests_over_the_rate_limit_are_refused() {
+    let server = server(WsSettings { rate_limit_per_sec: 1, rate_limit_burst: 1, ..WsSettings::default() }).await;
+    let mut client = hello_client(&server).await;
+
+    assert_eq!(request(&mut client, 1, "test_server_connection", json!({})).await["status"], "ok");
+    let limited = request(&mut client, 2, "test_server_connection", json!({})).await;
+    assert_eq!(limited["status"], "error");
+    assert_eq!(limited["code"], "rate_limited");
+}
+
+#[tokio::test]
+async fn macros_pass_results_between_steps() {
+    let server = server(WsSettings::default()).await;
+    let mut client = hello_client(&server).await;
+
+    let steps = json!([
+        { "command": "greet", "payload": { "name": "${who}" }, "as": "greeting" },
+        { "command": "fetch_JSON", "payload": { "said": "${greeting}", "line": "${prev} again" } },
+    ]);
+    let run = request(&mut client, 1, "run_macro", json!({ "steps": steps, "vars": { "who": "Ada" } })).await;
+    assert_eq!(run["status"], "ok");
+    assert_eq!(run["data"]["completed"], true);
+    let greeting = run["data"]["steps"][0]["data"].clone();
+    assert!(greeting.as_str().is_some_and(|s| s.contains("Ada")));
+    assert_eq!(run["data"]["result"]["said"], greeting);
+    assert_eq!(run["data"]["result"]["line"], format!("{} again", greeting.as_str().unwrap_or_default()));
+
+    // A failing step stops the macro unless it may fail
+    let steps = json!([
+        { "command": "greet", "payload": { "name": 42 }, "onError": "continue" },
+        { "command": "greet", "payload": { "name": "${missing}" } },
+        { "command": "test_server_connection" },
+    ]);
+    let run = request(&mut client, 2, "run_macro", json!({ "steps": steps })).await;
+    assert_eq!(run["data"]["completed"], false);
+    assert_eq!(run["data"]["stoppedAt"], 1);
+    assert_eq!(run["data"]["steps"][0]["code"], "invalid_payload");
+    assert_eq!(run["data"]["steps"].as_array().map(Vec::len), Some(2));
+
+    let nested = request(&mut client, 3, "run_macro", json!({ "steps": [{ "command": "run_macro" }] })).await;
+    assert_eq!(nested["code"], "invalid_input");
+}
+
+#[tokio::test]
+async fn subscribed_events_are_pushed() {
+    let server = server(WsSettings::default()).await;
+    let mut client = hello_client(&server).await;
+
+    let subscribed = request(&mut client, 1, "subscribe", json!({ "events": ["job-progress"] })).await;
+    assert_eq!(subscribed["status"], "ok");
+    assert_eq!(subscribed["data"], json!(["job-progress"]));
+
+    let job = json!({ "id": 7, "kind": "ws_server_test", "status": "running", "progress": 0.5 });
+    testing::publish_event("job-progress", &job);
+    let event = loop {
+        let frame = next_json(&mut client).await;
+        if frame["status"] == "event" && frame["data"]["kind"] == "ws_server_test" {
+            break frame;
+        }
+    };
+    assert_eq!(event["event"], "job-progress");
+    assert_eq!(event["data"], job);
+
+    let unknown = request(&mut client, 2, "subscribe", json!({ "events": ["not-an-event"] })).await;
+    assert_eq!(unknown["code"], "invalid_input");
+}
+
+#[tokio::test]
+async fn connection_lifecycle_is_reported() {
+    let server = server(WsSettings::default()).await;
+    let mut watcher = hello_client(&server).await;
+    request(&mut watcher, 1, "subscribe", json!({ "events": ["cep-connection"] })).await;
+
+    // maxConnections is 1: the watcher holds the slot, the next client is turned away
+    let mut second = connect(&server, Some(PROTOCOL_VERSION)).await;
+    assert_eq!(next_json(&mut second).await["code"], "ws.server_busy");
+    let rejected = loop {
+        let frame = next_json(&mut watcher).await;
+        if frame["status"] == "event" && frame["event"] == "cep-connection" && frame["data"]["state"] == "rejected" {
+            break frame["data"].clone();
+        }
+    };
+    assert_eq!(rejected["reason"], "busy");
+    assert!(rejected["peer"].as_str().is_some_and(|p| p.starts_with("127.0.0.1:")));
+
+    let clients = request(&mut watcher, 2, "list_ws_clients", json!({})).await;
+    let clients = clients["data"].as_array().expect("client list");
+    assert!(clients.iter().any(|c| c["role"] == "control" && c["session"].is_string()));
+}
@@ -1,3 +1,5 @@
+// Typed wrappers of the commands.rs / deepFaceProcess.rs commands, generated by src-tauri/build.rs
+import { commands } from "./bindings.js";
 const { invoke } = window.__TAURI__.core;
 const { listen } = window.__TAURI__.core;
 
@@ -12,11 +14,19 @@ let greetInputEl;
 let greetMsgEl;
 async function greet() {
   // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-  greetMsgEl.textContent = await invoke("greet", { name: greetInputEl.value });
+  greetMsgEl.textContent = await commands.greet(greetInputEl.value);
 }
 // Example project triggers
-async function addMarker(timestamp) {
-  await invoke("add_marker", { timestamp });
+async function addMarker(clipId, timestamp, fields = {}) {
+  return await commands.addMarker({ clipId, timestamp, ...fields });
+}
+async function exportMarkers(clipId, format, path) {
+  return await invoke("export_markers", { clipId, format, path });
+}
+async function getClipThumbnail(path, timestamp, size = 320) {
+  // base64 data URL: the webview can't load files from the app cache dir directly
+  const thumbnail = await invoke("get_clip_thumbnail", { path, timestamp, size, base64: true });
+  return thumbnail.dataUrl;
 }
 
 
@@ -34,6 +44,7 @@ function setupUI_dev(){
   const btn_analyze = document.getElementById("deepFaceTestButton_analyze");
   const btn_verify = document.getElementById("deepFaceTestButton_verify");
   const btn_detect = document.getElementById("deepFaceTestButton_detect");
+  const btn_monitor = document.getElementById("openMonitorButton");
 
 
   btn_start.addEventListener("click", async () => {
@@ -68,35 +79,33 @@ function setupUI_dev(){
     }
   });
 
+  // Logs / WS / deepface / jobs in a second window
+  btn_monitor.addEventListener("click", async () => {
+    try {
+      await invoke("open_monitor_window")
+    } catch (err) {
+      console.error("Monitor window failed:", err);
+    }
+  });
+
 }
 
 async function startDeepfaceServer(){
-  await invoke("start_deepface_server", {port: 8765})
+  await commands.startDeepfaceServer(8765)
 }
 async function PerformDeepfaceTest(action){
   let res;
   switch (action){
     case "analyze":
-      res = await invoke("analyze_deepface", {
-        frame: testImgPath1,
-        actions: "emotion",
-        detector: "opencv"
-        })
+      res = await commands.analyzeDeepface(testImgPath1, "emotion", "opencv")
         break;
     case "verify":
         // verify two frames
-      res = await invoke("verify_deepface", {
-        img1: testImgPath1,
-        img2: testImgPath2,
-        detector: "opencv"
-      })
+      res = await commands.verifyDeepface(testImgPath1, testImgPath2, "opencv")
       break;
     
     case "detect":
-      res = await invoke("detect_deepface", {
-        frame: testImgPath1,
-        detector: "opencv"
-      })
+      res = await commands.detectDeepface(testImgPath1, "opencv")
 
   }
 
@@ -146,7 +155,8 @@ function setupPipelinesStatus(){
       return;
     }
     
-    el.textContent = `🔌 CEP: ${event.payload}`;
+    // payload: { code, params, message } (message in the backend locale)
+    el.textContent = `🔌 CEP: ${event.payload.message}`;
     el.className = "status-indicator";
   });
   
@@ -159,11 +169,37 @@ function setupPipelinesStatus(){
       return;
     }
     
-    el.textContent = `🌐 Cloud: ${event.payload}`;
+    // payload: { state, valid, tier, expiresAt, daysLeft, message, error: { kind, message } | null, graceRemainingSecs }
+    const { message, tier, daysLeft } = event.payload;
+    const expiry = daysLeft != null ? ` (${daysLeft} days left)` : "";
+    el.textContent = `🌐 Cloud: ${message}${tier ? ` [${tier}]` : ""}${expiry}`;
     el.className = "status-indicator";
   });
 }
 
+// Open WS clients (list_ws_clients), refreshed on every cep-connection event
+async function renderCepClients() {
+  const el = document.getElementById("cep-clients");
+  if (!el) return;
+  const clients = await invoke("list_ws_clients");
+  // payload: [{ id, peer, protocol, role, session, connectedAt, lastSeenSecs, messagesIn, messagesOut }]
+  el.replaceChildren(...clients.map(c => {
+    const li = document.createElement("li");
+    li.textContent = `#${c.id} ${c.peer} · v${c.protocol} · ${c.role}${c.session ? ` · session ${c.session}` : ""} · ${c.messagesIn}↓ ${c.messagesOut}↑`;
+    return li;
+  }));
+}
+
+function setupCepClients() {
+  renderCepClients();
+  // payload: { state: "connected" | "disconnected" | "rejected", id, peer, session, reason, at }
+  window.__TAURI__.event.listen("cep-connection", event => {
+    const { state, peer, reason } = event.payload;
+    console.log(`cep-connection: ${state} ${peer}${reason ? ` (${reason})` : ""}`);
+    renderCepClients();
+  });
+}
+
 //*_____________________________________________________
 
 window.addEventListener("DOMContentLoaded", () => {
@@ -177,6 +213,7 @@ window.addEventListener("DOMContentLoaded", () => {
 
 
   setupPipelinesStatus();
+  setupCepClients();
 
   setupUI_dev();
 
@@ -0,0 +1,91 @@
+const { invoke } = window.__TAURI__.core;
+const { listen } = window.__TAURI__.event;
+
+const MAX_LOG_LINES = 1000; // lines kept in the panel
+const INITIAL_LOGS = 200;   // history shown when the window opens
+
+// Jobs by id, from the snapshots and the job-progress events in between
+const jobs = new Map();
+
+
+//*______________Status______________
+function showWs(ws) {
+  const el = document.getElementById("monitor-ws");
+  el.textContent = ws.listening
+    ? `🔌 WS: ${ws.tls ? "wss" : "ws"}://${ws.listening}, ${ws.connections.length}/${ws.maxConnections} connections ` +
+      `(accepted ${ws.totalAccepted}, rejected ${ws.totalRejected}, timed out ${ws.totalTimedOut})`
+    : "🔌 WS: not listening";
+  el.className = ws.listening ? "status-indicator ok" : "status-indicator error";
+
+  const body = document.querySelector("#monitor-connections tbody");
+  body.replaceChildren(...ws.connections.map((c) => {
+    const row = document.createElement("tr");
+    for (const value of [c.id, c.peer, c.protocol, c.messagesIn, c.messagesOut, `${c.lastSeenSecs}s`]) {
+      const cell = document.createElement("td");
+      cell.textContent = value;
+      row.appendChild(cell);
+    }
+    return row;
+  }));
+}
+
+function showDeepface(deepface) {
+  const el = document.getElementById("monitor-deepface");
+  el.textContent = `🧠 DeepFace: ${deepface.running ? "running" : "stopped"}, ${deepface.queueDepth} request(s) queued`;
+  el.className = deepface.running ? (deepface.queueDepth > 1 ? "status-indicator warn" : "status-indicator ok") : "status-indicator error";
+}
+
+function showJobs() {
+  const list = document.getElementById("monitor-jobs");
+  const active = [...jobs.values()].filter((j) => j.status === "queued" || j.status === "running");
+  list.replaceChildren(...active.map((job) => {
+    const item = document.createElement("li");
+    item.textContent = `#${job.id} ${job.kind} ${job.status} ${Math.round(job.progress * 100)}%${job.message ? ` – ${job.message}` : ""}`;
+    return item;
+  }));
+}
+
+
+//*______________Logs______________
+function appendLogs(records) {
+  const panel = document.getElementById("monitor-logs");
+  const atBottom = panel.scrollTop + panel.clientHeight >= panel.scrollHeight - 4;
+  for (const record of records) {
+    const line = document.createElement("div");
+    line.className = `log-line log-${record.level.toLowerCase()}`;
+    line.textContent = `${record.timestamp} ${record.level} ${record.target}: ${record.message}`;
+    panel.appendChild(line);
+  }
+  while (panel.childElementCount > MAX_LOG_LINES) {
+    panel.firstElementChild.remove();
+  }
+  if (atBottom) panel.scrollTop = panel.scrollHeight;
+}
+
+
+//*_____________________________________________________
+
+window.addEventListener("DOMContentLoaded", async () => {
+  document.getElementById("monitor-clear-logs").addEventListener("click", () => {
+    document.getElementById("monitor-logs").replaceChildren();
+  });
+
+  appendLogs(await invoke("get_recent_logs", { n: INITIAL_LOGS }));
+
+  // Sent to this window only, once a second (monitor.rs)
+  await listen("monitor-snapshot", (event) => {
+    const snapshot = event.payload;
+    showWs(snapshot.ws);
+    showDeepface(snapshot.deepface);
+    jobs.clear();
+    for (const job of snapshot.jobs) jobs.set(job.id, job);
+    showJobs();
+    appendLogs(snapshot.logs);
+  });
+
+  // Progress between two snapshots
+  await listen("job-progress", (event) => {
+    jobs.set(event.payload.id, event.payload);
+    showJobs();
+  });
+});